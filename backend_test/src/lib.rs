@@ -2,82 +2,164 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, spanned::Spanned, FnArg, GenericArgument, Ident, ItemFn, Pat, PathArguments,
-    Signature, Type,
+    parse_macro_input, AttributeArgs, FnArg, GenericArgument, Ident, ItemFn, Lit, Meta, NestedMeta,
+    Pat, Path, PathArguments, ReturnType, Signature, Type,
 };
 
+/// Longest test name used in an isolated database's name, which leaves room for
+/// the prefix and nonce within MongoDB's limit of 63 bytes.
+const MAX_DB_TEST_NAME_LEN: usize = 40;
+
 /// Transform an asynchronous test into a synchronous one, inject dependencies,
 /// and ensure that the database is cleared regardless of how the test terminates.
 ///
 /// Injectable dependencies are [`rocket::local::asynchronous::Client`],
 /// [`mongodb::Database`], and [`crate::model::mongodb::Coll<T>`].
+///
+/// The test may return either `()` or `anyhow::Result<()>`; an `Err` fails the test
+/// with the error and its backtrace.
+///
+/// The attribute accepts these comma-separated options:
+/// - `admin` or `voter`: log the client in as the example admin or voter.
+/// - `db = "shared"` (the default): use whatever database the server picks.
+/// - `db = "isolated"`: use a database named after the test plus a random nonce,
+///   e.g. `test_cast_ballots_1a2b3c4d`, so that tests run in parallel against one
+///   MongoDB instance can never share collections, and any database left behind
+///   by `SKIP_TEST_CLEANUP` can be traced to its test.
+/// - `config = "path"`: pass the server's config through the named function, a
+///   `fn(Figment) -> Figment`, before it launches, for tests needing non-default
+///   config.
+///
+/// Whichever database is used, cleanup drops exactly that one.
+///
+/// ```ignore
+/// #[backend_test(voter, db = "isolated")]
+/// async fn cast_ballots(client: Client, db: Database) {}
+///
+/// fn uk_only(figment: Figment) -> Figment {
+///     figment.merge(("allowed_sms_prefixes", ["+44"]))
+/// }
+///
+/// #[backend_test(config = "uk_only")]
+/// async fn restricted_sms_prefixes(client: Client) {}
+/// ```
 #[proc_macro_attribute]
 pub fn backend_test(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
     let mut item_fn = parse_macro_input!(input as ItemFn);
 
-    // Extract type information and reject invalid function signatures.
-    let (test_args, collection_idents, collection_types) = match check_sig(item_fn.sig.clone()) {
+    let TestArgs {
+        login,
+        isolated_db,
+        config,
+    } = match parse_args(args) {
         Ok(args) => args,
         Err(err) => {
             return err.into_compile_error().into();
         }
     };
 
+    // Extract type information and reject invalid function signatures.
+    let TestSig {
+        args: test_args,
+        collection_idents,
+        collection_types,
+        returns_result,
+    } = match check_sig(item_fn.sig.clone()) {
+        Ok(sig) => sig,
+        Err(err) => {
+            return err.into_compile_error().into();
+        }
+    };
+
+    // Fail the test if it returned an error.
+    let check_outcome = if returns_result {
+        quote! {
+            if let Err(err) = outcome {
+                panic!("Test returned an error: {}\n\nBacktrace:\n{}", err, err.backtrace());
+            }
+        }
+    } else {
+        quote! {
+            let () = outcome;
+        }
+    };
+
     // Rename the future so the test can have its original name.
     let name = item_fn.sig.ident.clone();
     let new_name = format_ident!("{}_fut", name);
     item_fn.sig.ident = new_name.clone();
 
     // Log in the client as admin/voter if needed.
-    let maybe_login = parse_macro_input!(args as Option<Ident>)
-        .and_then(|arg| {
-            if arg == "admin" {
-                Some(quote! {
-                    log::trace!("Inserting admin into database");
-                    crate::model::mongodb::Coll::<crate::model::db::admin::NewAdmin>::from_db(&db)
-                        .insert_one(crate::model::db::admin::NewAdmin::example(), None)
-                        .await
-                        .unwrap();
-
-                    log::trace!("Pre-authenticating as admin");
-                    rocket_client
-                        .post(uri!(crate::api::auth::authenticate))
-                        .header(rocket::http::ContentType::JSON)
-                        .body(rocket::serde::json::json!(crate::model::api::admin::AdminCredentials::example1()).to_string())
-                        .dispatch()
-                        .await;
-                })
-            } else if arg == "voter" {
-                Some(quote! {
-                    use crate::model::api::sms::Sms;
-
-                    log::trace!("Pre-authenticating as voter (stage 1)");
-                    rocket_client
-                        .post(uri!(crate::api::auth::challenge))
-                        .header(rocket::http::ContentType::JSON)
-                        .body(rocket::serde::json::json!(crate::model::api::auth::VoterChallengeRequest::example()).to_string())
-                        .dispatch()
-                        .await;
-
-                    let cookies = rocket_client.cookies();
-                    let cookie = cookies.get_private(crate::model::api::otp::CHALLENGE_COOKIE).unwrap();
-                    let config = rocket_client.rocket().state::<crate::config::Config>().unwrap();
-                    let challenge = crate::model::api::otp::Challenge::from_cookie(&cookie, config).unwrap();
-                    let challenge_response = crate::model::api::auth::VoterVerifyRequest::example(challenge.code);
-
-                    log::trace!("Pre-authenticating as voter (stage 2)");
-                    rocket_client
-                        .post(uri!(crate::api::auth::verify))
-                        .header(rocket::http::ContentType::JSON)
-                        .body(rocket::serde::json::json!(challenge_response).to_string())
-                        .dispatch()
-                        .await;
-                })
-            } else {
-                None
-            }
-        })
-        .unwrap_or_default();
+    let maybe_login = match login {
+        Some(Login::Admin) => quote! {
+            log::trace!("Inserting admin into database");
+            crate::model::mongodb::Coll::<crate::model::db::admin::NewAdmin>::from_db(&db)
+                .insert_one(crate::model::db::admin::NewAdmin::example(), None)
+                .await
+                .unwrap();
+
+            log::trace!("Pre-authenticating as admin");
+            rocket_client
+                .post(uri!(crate::api::auth::authenticate))
+                .header(rocket::http::ContentType::JSON)
+                .body(rocket::serde::json::json!(crate::model::api::admin::AdminCredentials::example1()).to_string())
+                .dispatch()
+                .await;
+        },
+        Some(Login::Voter) => quote! {
+            use crate::model::api::sms::Sms;
+
+            log::trace!("Pre-authenticating as voter (stage 1)");
+            rocket_client
+                .post(uri!(crate::api::auth::challenge))
+                .header(rocket::http::ContentType::JSON)
+                .body(rocket::serde::json::json!(crate::model::api::auth::VoterChallengeRequest::example()).to_string())
+                .dispatch()
+                .await;
+
+            let cookies = rocket_client.cookies();
+            let cookie = cookies.get_private(crate::model::api::otp::CHALLENGE_COOKIE).unwrap();
+            let config = rocket_client.rocket().state::<crate::config::Config>().unwrap();
+            let challenge = crate::model::api::otp::Challenge::from_cookie(&cookie, config).unwrap();
+            let challenge_response = crate::model::api::auth::VoterVerifyRequest::example(challenge.code);
+
+            log::trace!("Pre-authenticating as voter (stage 2)");
+            rocket_client
+                .post(uri!(crate::api::auth::verify))
+                .header(rocket::http::ContentType::JSON)
+                .body(rocket::serde::json::json!(challenge_response).to_string())
+                .dispatch()
+                .await;
+        },
+        None => quote! {},
+    };
+
+    // Override the server's config if asked to.
+    let maybe_configure = match config {
+        Some(config) => quote! {
+            let figment = #config(server.figment().clone());
+            let server = server.configure(figment);
+        },
+        None => quote! {},
+    };
+
+    // Point the server at a database of the test's own if asked to.
+    let maybe_isolate = if isolated_db {
+        let test_name = name
+            .to_string()
+            .chars()
+            .take(MAX_DB_TEST_NAME_LEN)
+            .collect::<String>();
+        quote! {
+            let db_name = format!("test_{}_{:08x}", #test_name, rand::random::<u32>());
+            log::trace!("Using isolated database {}", db_name);
+            let figment = server.figment().clone().merge(("test_db_name", db_name));
+            let server = server.configure(figment);
+        }
+    } else {
+        quote! {}
+    };
 
     // Rewrite the test function.
     quote! {
@@ -86,7 +168,10 @@ pub fn backend_test(args: TokenStream, input: TokenStream) -> TokenStream {
             /// Test setup.
             async fn setup() -> (rocket::local::asynchronous::Client, mongodb::Database) {
                 log::debug!("Performing test setup...");
-                let rocket_client = rocket::local::asynchronous::Client::tracked(crate::build())
+                let server = crate::build();
+                #maybe_configure
+                #maybe_isolate
+                let rocket_client = rocket::local::asynchronous::Client::tracked(server)
                     .await
                     .unwrap();
                 let db = rocket_client.rocket().state::<mongodb::Database>().unwrap().clone();
@@ -141,7 +226,7 @@ pub fn backend_test(args: TokenStream, input: TokenStream) -> TokenStream {
                     let #collection_idents = crate::model::mongodb::Coll::<#collection_types>::from_db(&db);
                 )*
 
-                runtime.block_on(#new_name(#(#test_args),* #(,#collection_idents)*));
+                runtime.block_on(#new_name(#(#test_args),* #(,#collection_idents)*))
             });
 
             // Run the cleanup (unless suppressed).
@@ -151,21 +236,153 @@ pub fn backend_test(args: TokenStream, input: TokenStream) -> TokenStream {
             }
 
             // If the test panicked, re-raise the panic.
-            if let Err(cause) = result {
-                std::panic::panic_any(cause);
-            }
+            let outcome = match result {
+                Ok(outcome) => outcome,
+                Err(cause) => std::panic::panic_any(cause),
+            };
+            #check_outcome
         }
     }
     .into()
 }
 
+/// Which user, if any, to log the client in as.
+enum Login {
+    Admin,
+    Voter,
+}
+
+/// The options given to the attribute.
+struct TestArgs {
+    /// Who to log in as.
+    login: Option<Login>,
+    /// Whether to use a database of the test's own.
+    isolated_db: bool,
+    /// The function to pass the server's config through, if any.
+    config: Option<Path>,
+}
+
+/// Parse the attribute's options, rejecting unknown or repeated ones.
+fn parse_args(args: AttributeArgs) -> Result<TestArgs, syn::Error> {
+    let mut login = None;
+    let mut db = None;
+    let mut config = None;
+    for arg in args {
+        match &arg {
+            NestedMeta::Meta(Meta::Path(path))
+                if path.is_ident("admin") || path.is_ident("voter") =>
+            {
+                if login.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &arg,
+                        "Test cannot log in as more than one user",
+                    ));
+                }
+                login = Some(if path.is_ident("admin") {
+                    Login::Admin
+                } else {
+                    Login::Voter
+                });
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("db") => {
+                if db.is_some() {
+                    return Err(syn::Error::new_spanned(&arg, "Duplicate `db` option"));
+                }
+                match &name_value.lit {
+                    Lit::Str(value) if value.value() == "shared" => db = Some(false),
+                    Lit::Str(value) if value.value() == "isolated" => db = Some(true),
+                    lit => {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "Expected `db = \"shared\"` or `db = \"isolated\"`",
+                        ));
+                    }
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("config") => {
+                if config.is_some() {
+                    return Err(syn::Error::new_spanned(&arg, "Duplicate `config` option"));
+                }
+                match &name_value.lit {
+                    Lit::Str(value) => config = Some(value.parse::<Path>()?),
+                    lit => {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "Expected `config = \"path::to::function\"`",
+                        ));
+                    }
+                }
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &arg,
+                    "Expected one of `admin`, `voter`, `db = \"...\"` or `config = \"...\"`",
+                ));
+            }
+        }
+    }
+
+    Ok(TestArgs {
+        login,
+        isolated_db: db.unwrap_or(false),
+        config,
+    })
+}
+
+/// The parts of a test signature needed to generate the harness.
+struct TestSig {
+    /// Arguments to pass for the injected client and database.
+    args: Vec<TokenStream2>,
+    /// Names of the injected collections.
+    collection_idents: Vec<Ident>,
+    /// Types stored in the injected collections.
+    collection_types: Vec<Ident>,
+    /// Whether the test returns `anyhow::Result<()>` rather than `()`.
+    returns_result: bool,
+}
+
+/// Whether the path is exactly `anyhow::Result<()>`, the only error type whose
+/// backtrace the harness knows how to print.
+fn is_anyhow_unit_result(path: &Path) -> bool {
+    let segments = path.segments.iter().collect::<Vec<_>>();
+    let [anyhow, result] = segments[..] else {
+        return false;
+    };
+    if anyhow.ident != "anyhow" || !anyhow.arguments.is_empty() || result.ident != "Result" {
+        return false;
+    }
+    let PathArguments::AngleBracketed(generics) = &result.arguments else {
+        return false;
+    };
+    matches!(
+        generics.args.iter().collect::<Vec<_>>()[..],
+        [GenericArgument::Type(Type::Tuple(tuple))] if tuple.elems.is_empty()
+    )
+}
+
 /// Ensure the wrapped test is async, extract parameters to inject, and reject unknown parameters.
-#[allow(clippy::type_complexity)]
-fn check_sig(sig: Signature) -> Result<(Vec<TokenStream2>, Vec<Ident>, Vec<Ident>), syn::Error> {
+fn check_sig(sig: Signature) -> Result<TestSig, syn::Error> {
     if sig.asyncness.is_none() {
-        return Err(syn::Error::new(sig.span(), "Test must be marked `async`"));
+        return Err(syn::Error::new_spanned(
+            sig.fn_token,
+            "Test must be marked `async`",
+        ));
     }
 
+    let returns_result = match &sig.output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(type_path) if is_anyhow_unit_result(&type_path.path) => true,
+            Type::Tuple(tuple) if tuple.elems.is_empty() => false,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "Test must return `()` or `anyhow::Result<()>`",
+                ));
+            }
+        },
+    };
+
     let mut has_client = false;
     let mut has_db = false;
     let mut args = vec![];
@@ -179,15 +396,15 @@ fn check_sig(sig: Signature) -> Result<(Vec<TokenStream2>, Vec<Ident>, Vec<Ident
                     if let Some(type_ident) = type_path.path.get_ident() {
                         if type_ident == "Client" {
                             if has_client {
-                                return Err(syn::Error::new(input.span(), "Test cannot accept more than one `rocket::local::asynchronous::Client`"));
+                                return Err(syn::Error::new_spanned(input, "Test cannot accept more than one `rocket::local::asynchronous::Client`"));
                             }
                             has_client = true;
                             args.push(quote! { rocket_client });
                             continue;
                         } else if type_ident == "Database" {
                             if has_db {
-                                return Err(syn::Error::new(
-                                    input.span(),
+                                return Err(syn::Error::new_spanned(
+                                    input,
                                     "Test cannot accept more than one `mongodb::Database`",
                                 ));
                             }
@@ -218,11 +435,16 @@ fn check_sig(sig: Signature) -> Result<(Vec<TokenStream2>, Vec<Ident>, Vec<Ident
             }
         }
 
-        return Err(syn::Error::new(
-            input.span(),
+        return Err(syn::Error::new_spanned(
+            input,
             "Expected one of `client_ident: Client`, `db_ident: Database` or `collection_ident: Coll<T>`",
         ));
     }
 
-    Ok((args, collection_idents, collection_types))
+    Ok(TestSig {
+        args,
+        collection_idents,
+        collection_types,
+        returns_result,
+    })
 }
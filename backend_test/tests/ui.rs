@@ -0,0 +1,7 @@
+//! Check that the macro rejects invalid tests with helpful errors.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
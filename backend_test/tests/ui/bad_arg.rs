@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test(superuser)]
+async fn bad_arg() {}
+
+fn main() {}
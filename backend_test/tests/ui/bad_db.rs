@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test(voter, db = "private")]
+async fn bad_db() {}
+
+fn main() {}
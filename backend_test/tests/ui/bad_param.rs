@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test]
+async fn bad_param(client: Client, other: Unsupported) {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use backend_test::backend_test;
+
+#[backend_test]
+async fn bad_result() -> std::io::Result<()> {
+    Ok(())
+}
+
+fn main() {}
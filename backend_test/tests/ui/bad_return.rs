@@ -0,0 +1,8 @@
+use backend_test::backend_test;
+
+#[backend_test]
+async fn bad_return() -> u32 {
+    0
+}
+
+fn main() {}
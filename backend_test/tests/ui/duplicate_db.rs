@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test]
+async fn duplicate_db(db: Database, other_db: Database) {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test]
+fn not_async() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use backend_test::backend_test;
+
+#[backend_test(admin, voter)]
+async fn two_logins() {}
+
+fn main() {}
@@ -2,18 +2,24 @@ use anyhow::anyhow;
 use chrono::{Duration, Utc};
 use clap::{Parser, ValueEnum};
 use const_format::concatcp;
+use dreip_client::{
+    model::{
+        api::{
+            ballot::{BallotRecall, BallotSpec, OwnershipProof},
+            election::{ElectionSpec, QuestionSpec},
+        },
+        common::election::ResultVisibility,
+    },
+    ApiClient,
+};
 use rand::seq::SliceRandom;
 use regex::bytes::Regex;
-use reqwest::blocking::{Client, Response};
-use serde::{de::IgnoredAny, Deserialize};
-use serde_json::json;
 use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::io::{self, ErrorKind, Read, Write};
 use std::ops::{AddAssign, Div};
 use std::process::{self, Child, Command, Stdio};
-use std::thread;
 use std::time::{Duration as StdDuration, Instant};
 use tempfile::NamedTempFile;
 
@@ -30,10 +36,16 @@ const ROCKET_ENV: &[(&str, &str)] = &[
     ("ROCKET_AWS_REGION", "dummy"),
     ("ROCKET_AWS_ACCESS_KEY_ID", "dummy"),
     ("ROCKET_AWS_SECRET_ACCESS_KEY", "dummy"),
+    ("ROCKET_SECURE_COOKIES", "false"),
 ];
 
 const CAPTCHA_RESPONSE: &str = "this response will succeed in test mode";
 
+const DEFAULT_ADMIN_USERNAME: &str = "replace-this-admin-asap";
+const DEFAULT_ADMIN_PASSWORD: &str = "insecure";
+/// What the default admin's password is changed to, as it must be before use.
+const ADMIN_PASSWORD: &str = "benchmark-admin-password";
+
 #[rustfmt::skip]
 const CANDIDATES: &[&str] = &[
     "Alice",
@@ -66,7 +78,7 @@ struct Args {
     #[arg(long)]
     remote: Option<String>,
 
-    /// How many threads to use. Defaults to the number of logical CPUs.
+    /// How many voters to run concurrently. Defaults to the number of logical CPUs.
     #[arg(long, default_value_t = num_cpus::get())]
     threads: usize,
 
@@ -110,19 +122,18 @@ impl Default for ConfirmMode {
     }
 }
 
-/// Construct a URL from segments.
-macro_rules! url {
-    ($($segment:expr),+) => {{
-        std::path::PathBuf::from_iter([$($segment),+]).to_str().unwrap()
-    }}
-}
-
 /// Set up everything we need before starting the server.
 fn setup_deps(always_reuse: bool) -> anyhow::Result<()> {
-    // Ensure the optimised build is up-to-date. Turn off default features to
+    // Ensure the optimised build is up-to-date. Turn off the `otp` feature to
     // bypass OTP/CAPTCHA authentication.
     Command::new("cargo")
-        .args(["build", "--release", "--no-default-features"])
+        .args([
+            "build",
+            "--release",
+            "--no-default-features",
+            "--features",
+            "server",
+        ])
         .status()?
         .success()
         .then_some(())
@@ -231,27 +242,21 @@ fn terminate_child(child: &mut Child) -> anyhow::Result<()> {
 }
 
 /// Set up everything we need before starting the server.
-fn launch_server(logfile: Stdio) -> anyhow::Result<Child> {
+async fn launch_server(logfile: Stdio) -> anyhow::Result<Child> {
     let mut proc = Command::new("./target/release/dreip-backend")
         .stdout(logfile)
         .spawn()?;
 
     // Wait for the server to be reachable.
-    let client = Client::new();
+    let client = ApiClient::new(LOCAL_URL)?;
     loop {
-        let resp = client
-            .get(url!(LOCAL_URL, "auth/check"))
-            .send()
-            .and_then(Response::error_for_status);
-
-        if let Ok(resp) = resp {
-            let text = resp.text();
-            if let Ok("Unauthenticated") = text.as_deref() {
+        if let Ok(auth) = client.check_auth().await {
+            if auth == "Unauthenticated" {
                 break;
             } else {
                 terminate_child(&mut proc)?;
                 proc.wait()?;
-                return Err(anyhow!("Bad response: {:?}", text));
+                return Err(anyhow!("Bad response: {:?}", auth));
             }
         }
 
@@ -265,82 +270,64 @@ fn launch_server(logfile: Stdio) -> anyhow::Result<Child> {
 }
 
 /// Create an election to benchmark against and return its ID.
-fn setup_election(url: &str) -> anyhow::Result<String> {
-    let client = Client::builder().cookie_store(true).build()?;
-
-    // Authenticate as admin.
-    let creds = json!({
-        "username": "replace-this-admin-asap",
-        "password": "insecure",
-    });
-    client
-        .post(url!(url, "auth/admin"))
-        .json(&creds)
-        .send()
-        .and_then(Response::error_for_status)?;
+async fn setup_election(url: &str) -> anyhow::Result<u32> {
+    let client = ApiClient::new(url)?;
+
+    // Authenticate as admin, changing the default password on a fresh DB.
+    if client
+        .admin_login(DEFAULT_ADMIN_USERNAME, DEFAULT_ADMIN_PASSWORD)
+        .await
+        .is_ok()
+    {
+        client
+            .change_admin_password(DEFAULT_ADMIN_PASSWORD, ADMIN_PASSWORD)
+            .await?;
+    } else {
+        client
+            .admin_login(DEFAULT_ADMIN_USERNAME, ADMIN_PASSWORD)
+            .await?;
+    }
 
     // Create election.
     let start = Utc::now() - Duration::hours(1);
     let end = start + Duration::hours(2);
-    let election = json!({
-        "name": "Benchmark Election",
-        "start_time": start,
-        "end_time": end,
-        "electorates": [],
-        "questions": [{
-            "description": "Benchmark Question",
-            "constraints": {},
-            "candidates": CANDIDATES,
+    let spec = ElectionSpec {
+        name: "Benchmark Election".to_string(),
+        start_time: start,
+        end_time: end,
+        requires_step_up: false,
+        electorates: Vec::new(),
+        questions: vec![QuestionSpec {
+            description: "Benchmark Question".to_string(),
+            constraints: HashMap::new(),
+            candidates: CANDIDATES.iter().map(|c| c.to_string()).collect(),
+            end_time: None,
+            allow_write_in: false,
+            result_visibility: ResultVisibility::Full,
+            quorum: None,
         }],
-    });
-    let resp = client
-        .post(url!(url, "elections"))
-        .json(&election)
-        .send()
-        .and_then(Response::error_for_status)?;
-
-    #[derive(Deserialize)]
-    struct Eid {
-        id: u32,
-    }
-    let Eid { id } = resp.json()?;
-    let id_str = id.to_string();
+    };
+    let election = client.create_election(&spec).await?;
 
     // Publish the election.
-    client
-        .post(url!(url, "elections", &id_str, "publish"))
-        .send()
-        .and_then(Response::error_for_status)?;
+    client.publish_election(election.id).await?;
 
-    Ok(id_str)
+    Ok(election.id)
 }
 
-/// Authenticate as a voter and return the client with embedded auth cookies.
-fn voter_auth(url: &str, voter_id: u32) -> anyhow::Result<(Client, StdDuration)> {
-    let client = Client::builder().cookie_store(true).build()?;
+/// Authenticate as a voter and return the authenticated client.
+async fn voter_auth(url: &str, voter_id: u32) -> anyhow::Result<(ApiClient, StdDuration)> {
+    let client = ApiClient::new(url)?;
     let start = Instant::now();
 
     // Challenge phase.
-    let data = json!({
-        "sms": format!("+1{:010}", voter_id),
-        "g_recaptcha_response": CAPTCHA_RESPONSE,
-    });
-    client
-        .post(url!(url, "auth/voter/challenge"))
-        .json(&data)
-        .send()
-        .and_then(Response::error_for_status)?;
+    let sms = format!("+1{:010}", voter_id).parse()?;
+    client.voter_challenge(sms, CAPTCHA_RESPONSE).await?;
 
     // Verification phase.
-    let data = json!({
-        "code": "123456",
-        "g_recaptcha_response": CAPTCHA_RESPONSE,
-    });
     client
-        .post(url!(url, "auth/voter/verify"))
-        .json(&data)
-        .send()
-        .and_then(Response::error_for_status)?;
+        .voter_verify("123456".parse()?, CAPTCHA_RESPONSE)
+        .await?;
 
     Ok((client, start.elapsed()))
 }
@@ -375,63 +362,42 @@ impl Div<u32> for VoteTimings {
 
 /// Cast a vote and either confirm or audit as per the `confirm_mode`. The `client` must be
 /// pre-authenticated.
-fn cast_vote(
-    url: &str,
-    eid: &str,
-    client: &Client,
+async fn cast_vote(
+    eid: u32,
+    client: &ApiClient,
     confirm_mode: ConfirmMode,
 ) -> anyhow::Result<VoteTimings> {
     // Join the election.
     let pre_join = Instant::now();
-    client
-        .post(url!(url, "elections", eid, "join"))
-        .json(&json!({}))
-        .send()
-        .and_then(Response::error_for_status)?;
+    client.join(eid, &HashMap::new()).await?;
     let post_join = Instant::now();
 
     // Cast provisional vote.
     let pre_cast = Instant::now();
-    let vote = json!([{
-        "question": 1,
-        "candidate": CANDIDATES.choose(&mut rand::thread_rng()),
-    }]);
-    let resp = client
-        .post(url!(url, "elections", eid, "votes/cast"))
-        .json(&vote)
-        .send()
-        .and_then(Response::error_for_status)?;
+    let candidate = CANDIDATES.choose(&mut rand::thread_rng()).unwrap();
+    let vote = [BallotSpec {
+        question: 1,
+        candidate: candidate.to_string(),
+        write_in_name: None,
+    }];
+    let receipts = client.cast(eid, &vote).await?;
     let post_cast = Instant::now();
 
-    #[derive(Deserialize)]
-    struct Receipt {
-        ballot_id: u32,
-        question_id: u32,
-        signature: String,
-    }
-    let [Receipt {
-        question_id,
-        ballot_id,
-        signature,
-    }] = resp.json()?;
+    let [receipt] = <[_; 1]>::try_from(receipts)
+        .map_err(|receipts| anyhow!("expected 1 receipt, got {}", receipts.len()))?;
 
     // Confirm (or audit) vote.
     let pre_confirm = Instant::now();
-    let confirmation = json!([{
-        "ballot_id": ballot_id,
-        "question_id": question_id,
-        "signature": signature,
-    }]);
-    let endpoint = if confirm_mode.should_confirm() {
-        "votes/confirm"
+    let recall = [BallotRecall {
+        ballot_id: receipt.ballot_id,
+        question_id: receipt.question_id,
+        proof: OwnershipProof::Signature(receipt.signature),
+    }];
+    if confirm_mode.should_confirm() {
+        client.confirm(eid, &recall).await?;
     } else {
-        "votes/audit"
-    };
-    client
-        .post(url!(url, "elections", eid, endpoint))
-        .json(&confirmation)
-        .send()
-        .and_then(Response::error_for_status)?;
+        client.audit(eid, &recall).await?;
+    }
     let post_confirm = Instant::now();
 
     Ok(VoteTimings {
@@ -442,73 +408,72 @@ fn cast_vote(
 }
 
 /// Run the benchmark.
-fn benchmark(
+async fn benchmark(
     url: &str,
-    eid: &str,
-    num_threads: usize,
+    eid: u32,
+    num_tasks: usize,
     confirm_mode: ConfirmMode,
 ) -> anyhow::Result<()> {
-    const ITERATIONS_PER_THREAD: usize = 100;
-    let end_val: usize = num_threads * ITERATIONS_PER_THREAD;
+    const ITERATIONS_PER_TASK: usize = 100;
+    let end_val: usize = num_tasks * ITERATIONS_PER_TASK;
 
     let start = Instant::now();
-    thread::scope(|s| {
-        let mut threads = Vec::with_capacity(num_threads);
+    let mut tasks = Vec::with_capacity(num_tasks);
 
-        for start in (0..end_val).step_by(ITERATIONS_PER_THREAD) {
-            let t = s.spawn(move || {
-                let mut auth_duration = StdDuration::ZERO;
-                let mut vote_duration = VoteTimings::default();
+    for start in (0..end_val).step_by(ITERATIONS_PER_TASK) {
+        let url = url.to_string();
+        let t = tokio::spawn(async move {
+            let mut auth_duration = StdDuration::ZERO;
+            let mut vote_duration = VoteTimings::default();
 
-                for voter_id in start..(start + ITERATIONS_PER_THREAD) {
-                    let (client, auth_dur) = voter_auth(url, voter_id as u32)?;
-                    let vote_dur = cast_vote(url, eid, &client, confirm_mode)?;
+            for voter_id in start..(start + ITERATIONS_PER_TASK) {
+                let (client, auth_dur) = voter_auth(&url, voter_id as u32).await?;
+                let vote_dur = cast_vote(eid, &client, confirm_mode).await?;
 
-                    auth_duration += auth_dur;
-                    vote_duration += vote_dur;
-                }
+                auth_duration += auth_dur;
+                vote_duration += vote_dur;
+            }
 
-                let avg_auth_dur = auth_duration / ITERATIONS_PER_THREAD as u32;
-                let avg_vote_dur = vote_duration / ITERATIONS_PER_THREAD as u32;
-                Ok::<_, anyhow::Error>((avg_auth_dur, avg_vote_dur))
-            });
-            threads.push(t);
-        }
+            let avg_auth_dur = auth_duration / ITERATIONS_PER_TASK as u32;
+            let avg_vote_dur = vote_duration / ITERATIONS_PER_TASK as u32;
+            Ok::<_, anyhow::Error>((avg_auth_dur, avg_vote_dur))
+        });
+        tasks.push(t);
+    }
 
-        let mut auth_duration = StdDuration::ZERO;
-        let mut vote_duration = VoteTimings::default();
-        for t in threads {
-            let (auth_dur, vote_dur) = t.join().expect("thread panicked")?;
-            auth_duration += auth_dur;
-            vote_duration += vote_dur;
-        }
-        let total_duration = start.elapsed();
-
-        let avg_auth_duration = auth_duration / num_threads as u32;
-        let avg_vote_duration = vote_duration / num_threads as u32;
-        let avg_total_duration = avg_auth_duration
-            + avg_vote_duration.join
-            + avg_vote_duration.cast
-            + avg_vote_duration.confirm;
-
-        // Theoretical votes per sec is 1/avg_duration * num_threads.
-        let votes_per_sec = num_threads as f64 / avg_total_duration.as_secs_f64();
-        // Actual votes per sec is total_votes / total_time.
-        let actual_votes_per_sec = end_val as f64 / total_duration.as_secs_f64();
-
-        println!("auth: {:?}", avg_auth_duration);
-        println!("join: {:?}", avg_vote_duration.join);
-        println!("cast: {:?}", avg_vote_duration.cast);
-        println!("conf: {:?}", avg_vote_duration.confirm);
-
-        println!("\ntotal: {:?} ({:.2}/s)", avg_total_duration, votes_per_sec);
-        println!(
-            "actual duration: {} votes in {:?} ({:.2}/s)",
-            end_val, total_duration, actual_votes_per_sec
-        );
+    let mut auth_duration = StdDuration::ZERO;
+    let mut vote_duration = VoteTimings::default();
+    for t in tasks {
+        let (auth_dur, vote_dur) = t.await.expect("task panicked")?;
+        auth_duration += auth_dur;
+        vote_duration += vote_dur;
+    }
+    let total_duration = start.elapsed();
+
+    let avg_auth_duration = auth_duration / num_tasks as u32;
+    let avg_vote_duration = vote_duration / num_tasks as u32;
+    let avg_total_duration = avg_auth_duration
+        + avg_vote_duration.join
+        + avg_vote_duration.cast
+        + avg_vote_duration.confirm;
+
+    // Theoretical votes per sec is 1/avg_duration * num_tasks.
+    let votes_per_sec = num_tasks as f64 / avg_total_duration.as_secs_f64();
+    // Actual votes per sec is total_votes / total_time.
+    let actual_votes_per_sec = end_val as f64 / total_duration.as_secs_f64();
+
+    println!("auth: {:?}", avg_auth_duration);
+    println!("join: {:?}", avg_vote_duration.join);
+    println!("cast: {:?}", avg_vote_duration.cast);
+    println!("conf: {:?}", avg_vote_duration.confirm);
+
+    println!("\ntotal: {:?} ({:.2}/s)", avg_total_duration, votes_per_sec);
+    println!(
+        "actual duration: {} votes in {:?} ({:.2}/s)",
+        end_val, total_duration, actual_votes_per_sec
+    );
 
-        Ok(())
-    })
+    Ok(())
 }
 
 /// Ensure that all dependencies for the verifier are ready.
@@ -532,7 +497,7 @@ fn setup_verifier() -> anyhow::Result<()> {
 }
 
 /// Return `Ok(())` if the election integrity can be successfully verified.
-fn verify(url: &str, eid: &str) -> anyhow::Result<()> {
+async fn verify(url: &str, eid: u32) -> anyhow::Result<()> {
     // Hack the DB to mark the election as finished, so we can get the totals out.
     let end_time = Utc::now() - Duration::minutes(1);
     let mongosh_cmd = format!(
@@ -558,22 +523,17 @@ fn verify(url: &str, eid: &str) -> anyhow::Result<()> {
         .ok_or_else(|| anyhow!("failed to modify DB"))?;
 
     // Dump the election results.
-    let client = Client::new();
-    let dump = client
-        .get(url!(url, "elections", eid, "1/dump"))
-        .send()
-        .and_then(Response::error_for_status)?
-        .bytes()?;
+    let client = ApiClient::new(url)?;
+    let dump = client.question_dump(eid, 1).await?;
 
     // Sanity-check that we actually got the totals.
-    let obj: HashMap<String, IgnoredAny> = serde_json::from_slice(&dump)?;
-    if !obj.contains_key("totals") {
+    if dump.totals.is_none() {
         return Err(anyhow!("election dump missing totals"));
     }
 
     // Dump the dump to a file and run the verifier on it.
     let mut f = NamedTempFile::new()?;
-    f.write_all(&dump)?;
+    f.write_all(&serde_json::to_vec(&dump)?)?;
     f.flush()?;
 
     Command::new("./target/release/verification-cli")
@@ -586,7 +546,7 @@ fn verify(url: &str, eid: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn run() -> anyhow::Result<()> {
+async fn run() -> anyhow::Result<()> {
     let args = Args::parse();
     let url = args.remote.as_deref().unwrap_or(LOCAL_URL);
 
@@ -609,22 +569,23 @@ fn run() -> anyhow::Result<()> {
                 }
             }
         };
-        proc = Some(launch_server(logfile)?);
+        proc = Some(launch_server(logfile).await?);
     }
 
-    // Use a closure to ensure the cleanup below runs.
-    let result = (|| {
+    // Use an async block to ensure the cleanup below runs.
+    let result = async {
         // Run the benchmark.
-        let eid = setup_election(url)?;
-        benchmark(url, &eid, args.threads, args.confirm_mode)?;
+        let eid = setup_election(url).await?;
+        benchmark(url, eid, args.threads, args.confirm_mode).await?;
 
         // Verify if requested.
         if args.verify {
-            verify(url, &eid)?;
+            verify(url, eid).await?;
         }
 
         Ok(())
-    })();
+    }
+    .await;
 
     // Kill the server.
     if let Some(p) = proc.as_mut() {
@@ -635,8 +596,9 @@ fn run() -> anyhow::Result<()> {
     result
 }
 
-fn main() {
-    if let Err(e) = run() {
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
         eprintln!("FATAL: {}", e);
         process::exit(1);
     }
@@ -0,0 +1,58 @@
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors from calling the API.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The request could not be sent, or the response could not be read.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// Voting in the election is temporarily suspended.
+    #[error("Voting in the election is suspended")]
+    Suspended,
+    /// The election requires step-up verification, which has not been done.
+    #[error("The election requires step-up verification")]
+    StepUpRequired,
+    /// The server rejected the request, with a machine-readable code if it gave one.
+    #[error("Request failed with status {status}")]
+    Status {
+        status: StatusCode,
+        code: Option<String>,
+    },
+}
+
+impl Error {
+    /// Get the HTTP response status associated with this error, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Http(err) => err.status(),
+            Error::Suspended => Some(StatusCode::SERVICE_UNAVAILABLE),
+            Error::StepUpRequired => Some(StatusCode::FORBIDDEN),
+            Error::Status { status, .. } => Some(*status),
+        }
+    }
+
+    /// Interpret an unsuccessful response.
+    /// The server only sends a JSON body for errors with a machine-readable code.
+    pub(crate) async fn from_response(response: Response) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            code: String,
+        }
+
+        let status = response.status();
+        let code = response
+            .json::<ErrorBody>()
+            .await
+            .ok()
+            .map(|body| body.code);
+        match code.as_deref() {
+            Some("election_suspended") => Error::Suspended,
+            Some("step_up_required") => Error::StepUpRequired,
+            _ => Error::Status { status, code },
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! A typed async client for the DRE-ip backend API.
+//!
+//! Requests and responses use the backend's own API types, so they cannot
+//! drift from what the server actually accepts and returns.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+use dreip_backend::model::{
+    api::{
+        admin::{AdminCredentials, AdminPasswordChange},
+        auth::{VoterChallengeRequest, VoterVerifyRequest, CSRF_COOKIE, CSRF_HEADER},
+        ballot::{BallotRecall, BallotSpec},
+        candidate_totals::CandidateTotalsDesc,
+        election::{ElectionDescription, ElectionResults, ElectionSpec, ElectionSummary},
+        otp::Code,
+        receipt::{PublicReceipt, Receipt},
+        sms::Sms,
+    },
+    common::{
+        allowed_questions::{AllowedQuestions, Joins},
+        ballot::{Audited, BallotId, Confirmed, Unconfirmed},
+        election::{CandidateId, ElectionId, QuestionId},
+    },
+};
+
+mod error;
+
+pub use dreip_backend::model;
+pub use error::{Error, Result};
+
+/// A client for a single server, holding the cookies and CSRF token of
+/// whichever user last logged in.
+pub struct ApiClient {
+    base_url: String,
+    http: Client,
+    csrf_token: RwLock<Option<String>>,
+}
+
+impl ApiClient {
+    /// Create a client for the server at the given base URL, e.g. `http://localhost:8000`.
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        let http = Client::builder().cookie_store(true).build()?;
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+            csrf_token: RwLock::new(None),
+        })
+    }
+
+    /// Which kind of user the client is authenticated as: `Admin`, `Voter`, or
+    /// `Unauthenticated`.
+    pub async fn check_auth(&self) -> Result<String> {
+        let response = self.send(self.request(Method::GET, "auth/check")).await?;
+        Ok(response.text().await?)
+    }
+
+    /// Log in as an admin.
+    pub async fn admin_login(&self, username: &str, password: &str) -> Result<()> {
+        let credentials = AdminCredentials {
+            username: username.to_string(),
+            password: password.into(),
+        };
+        let request = self.request(Method::POST, "auth/admin").json(&credentials);
+        self.login(request).await
+    }
+
+    /// Change the logged-in admin's password. Admin only.
+    pub async fn change_admin_password(
+        &self,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        let change = AdminPasswordChange {
+            current_password: current_password.into(),
+            new_password: new_password.into(),
+        };
+        let request = self
+            .request(Method::PUT, "admins/me/password")
+            .json(&change);
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Request an OTP challenge for a voter.
+    pub async fn voter_challenge(&self, sms: Sms, g_recaptcha_response: &str) -> Result<()> {
+        let challenge = VoterChallengeRequest::new(sms, g_recaptcha_response.to_string(), None);
+        let request = self
+            .request(Method::POST, "auth/voter/challenge")
+            .json(&challenge);
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Log in as a voter by answering the OTP challenge.
+    pub async fn voter_verify(&self, code: Code, g_recaptcha_response: &str) -> Result<()> {
+        let verify = VoterVerifyRequest::new(code, g_recaptcha_response.to_string());
+        let request = self
+            .request(Method::POST, "auth/voter/verify")
+            .json(&verify);
+        self.login(request).await
+    }
+
+    /// Log out, whoever is logged in.
+    pub async fn logout(&self) -> Result<()> {
+        self.send(self.request(Method::DELETE, "auth")).await?;
+        *self.csrf_token.write().unwrap() = None;
+        Ok(())
+    }
+
+    /// Get a summary of every election, optionally including archived ones.
+    pub async fn elections(&self, archived: bool) -> Result<Vec<ElectionSummary>> {
+        let request = self
+            .request(Method::GET, "elections")
+            .query(&[("archived", archived)]);
+        self.json(request).await
+    }
+
+    /// Get the full description of an election.
+    pub async fn election(&self, election_id: ElectionId) -> Result<ElectionDescription> {
+        let path = format!("elections/{election_id}");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Export an election's configuration, in the form accepted by
+    /// [`ApiClient::create_election`]. Admin only.
+    pub async fn election_spec(&self, election_id: ElectionId) -> Result<ElectionSpec> {
+        let path = format!("elections/{election_id}/spec");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Create a draft election. Admin only.
+    pub async fn create_election(&self, spec: &ElectionSpec) -> Result<ElectionDescription> {
+        let request = self.request(Method::POST, "elections").json(spec);
+        self.json(request).await
+    }
+
+    /// Replace an election's branding, or remove it with `None`. This is
+    /// allowed in any state. Admin only.
+    pub async fn set_branding(
+        &self,
+        election_id: ElectionId,
+        branding: Option<&serde_json::Value>,
+    ) -> Result<ElectionDescription> {
+        let path = format!("elections/{election_id}/branding");
+        let request = self.request(Method::PATCH, &path).json(&branding);
+        self.json(request).await
+    }
+
+    /// Publish a draft election. Admin only.
+    pub async fn publish_election(&self, election_id: ElectionId) -> Result<()> {
+        let path = format!("elections/{election_id}/publish");
+        self.send(self.request(Method::POST, &path)).await?;
+        Ok(())
+    }
+
+    /// Archive an election, finalizing it if it is in progress. Admin only.
+    pub async fn archive_election(&self, election_id: ElectionId) -> Result<()> {
+        let path = format!("elections/{election_id}/archive");
+        self.send(self.request(Method::POST, &path)).await?;
+        Ok(())
+    }
+
+    /// Delete an election and all of its data. Archived elections are refused
+    /// until every question has been exported with a signed dump, unless
+    /// `acknowledge_no_export` is set. Admin only.
+    pub async fn delete_election(
+        &self,
+        election_id: ElectionId,
+        acknowledge_no_export: bool,
+    ) -> Result<()> {
+        let path = format!("elections/{election_id}?acknowledge_no_export={acknowledge_no_export}");
+        self.send(self.request(Method::DELETE, &path)).await?;
+        Ok(())
+    }
+
+    /// Has the voter joined the election?
+    pub async fn has_joined(&self, election_id: ElectionId) -> Result<bool> {
+        let path = format!("elections/{election_id}/join");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Join an election, claiming membership of the given groups.
+    pub async fn join(&self, election_id: ElectionId, joins: &Joins) -> Result<()> {
+        let path = format!("elections/{election_id}/join");
+        self.send(self.request(Method::POST, &path).json(joins))
+            .await?;
+        Ok(())
+    }
+
+    /// Get the questions the voter may answer, and whether each is already confirmed.
+    pub async fn allowed_questions(&self, election_id: ElectionId) -> Result<AllowedQuestions> {
+        let path = format!("elections/{election_id}/questions/allowed");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Cast provisional ballots.
+    pub async fn cast(
+        &self,
+        election_id: ElectionId,
+        ballots: &[BallotSpec],
+    ) -> Result<Vec<Receipt<Unconfirmed>>> {
+        let path = format!("elections/{election_id}/votes/cast");
+        self.json(self.request(Method::POST, &path).json(ballots))
+            .await
+    }
+
+    /// Audit provisional ballots, revealing their votes.
+    pub async fn audit(
+        &self,
+        election_id: ElectionId,
+        ballots: &[BallotRecall],
+    ) -> Result<Vec<Receipt<Audited>>> {
+        let path = format!("elections/{election_id}/votes/audit");
+        self.json(self.request(Method::POST, &path).json(ballots))
+            .await
+    }
+
+    /// Confirm provisional ballots, counting their votes.
+    pub async fn confirm(
+        &self,
+        election_id: ElectionId,
+        ballots: &[BallotRecall],
+    ) -> Result<Vec<Receipt<Confirmed>>> {
+        let path = format!("elections/{election_id}/votes/confirm");
+        self.json(self.request(Method::POST, &path).json(ballots))
+            .await
+    }
+
+    /// Get the public receipt of a single ballot.
+    pub async fn ballot(
+        &self,
+        election_id: ElectionId,
+        question_id: QuestionId,
+        ballot_id: BallotId,
+    ) -> Result<PublicReceipt> {
+        let path = format!("elections/{election_id}/{question_id}/ballots/{ballot_id}");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Get the candidate totals of a question, once the election has ended.
+    pub async fn candidate_totals(
+        &self,
+        election_id: ElectionId,
+        question_id: QuestionId,
+    ) -> Result<HashMap<CandidateId, CandidateTotalsDesc>> {
+        let path = format!("elections/{election_id}/{question_id}/totals");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Get everything needed to verify a question.
+    pub async fn question_dump(
+        &self,
+        election_id: ElectionId,
+        question_id: QuestionId,
+    ) -> Result<ElectionResults> {
+        let path = format!("elections/{election_id}/{question_id}/dump");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Get everything needed to verify a question, signed by the election key.
+    pub async fn signed_question_dump(
+        &self,
+        election_id: ElectionId,
+        question_id: QuestionId,
+    ) -> Result<ElectionResults> {
+        let path = format!("elections/{election_id}/{question_id}/dump?signed=true");
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Start building a request to the given path, echoing the CSRF token if
+    /// we have one.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path);
+        let request = self.http.request(method, url);
+        match self.csrf_token.read().unwrap().as_deref() {
+            Some(token) => request.header(CSRF_HEADER, token),
+            None => request,
+        }
+    }
+
+    /// Send a request, failing on an unsuccessful status.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    /// Send a request and deserialize the response.
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    /// Send a login request and remember the CSRF token it issues.
+    async fn login(&self, request: RequestBuilder) -> Result<()> {
+        let response = self.send(request).await?;
+        let token = response
+            .cookies()
+            .find(|cookie| cookie.name() == CSRF_COOKIE)
+            .map(|cookie| cookie.value().to_string());
+        *self.csrf_token.write().unwrap() = token;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use wiremock::{
+        matchers::{body_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use dreip_backend::model::common::election::ElectionState;
+
+    use super::*;
+
+    async fn mock(server: &MockServer, verb: &str, route: &str, response: ResponseTemplate) {
+        Mock::given(method(verb))
+            .and(path(route))
+            .respond_with(response)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn decodes_responses() {
+        let server = MockServer::start().await;
+        let client = ApiClient::new(server.uri()).unwrap();
+
+        let now = Utc::now();
+        let summary = serde_json::json!([{
+            "id": 3,
+            "name": "Test Election",
+            "state": "Published",
+            "start_time": now,
+            "end_time": now,
+            "suspended": false,
+            "requires_step_up": false,
+        }]);
+        mock(
+            &server,
+            "GET",
+            "/elections",
+            ResponseTemplate::new(200).set_body_json(summary),
+        )
+        .await;
+        mock(
+            &server,
+            "GET",
+            "/elections/3/join",
+            ResponseTemplate::new(200).set_body_json(true),
+        )
+        .await;
+
+        let elections = client.elections(false).await.unwrap();
+        assert_eq!(elections.len(), 1);
+        assert_eq!(elections[0].id, 3);
+        assert_eq!(elections[0].state, ElectionState::Published);
+        assert!(elections[0].authorship.is_none());
+        assert!(client.has_joined(3).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn maps_errors() {
+        let server = MockServer::start().await;
+        let client = ApiClient::new(server.uri()).unwrap();
+
+        // Errors with a machine-readable code get their own variants.
+        mock(
+            &server,
+            "POST",
+            "/elections/1/votes/cast",
+            ResponseTemplate::new(503).set_body_json(serde_json::json!({
+                "code": "election_suspended",
+            })),
+        )
+        .await;
+        mock(
+            &server,
+            "POST",
+            "/elections/2/votes/cast",
+            ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "code": "step_up_required",
+            })),
+        )
+        .await;
+        let ballots = [BallotSpec {
+            question: 1,
+            candidate: "Alice".to_string(),
+            write_in_name: None,
+        }];
+        let err = client.cast(1, &ballots).await.unwrap_err();
+        assert!(matches!(err, Error::Suspended), "{err:?}");
+        assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+        let err = client.cast(2, &ballots).await.unwrap_err();
+        assert!(matches!(err, Error::StepUpRequired), "{err:?}");
+
+        // Other errors keep their status, and any unrecognised code.
+        mock(
+            &server,
+            "GET",
+            "/elections/3",
+            ResponseTemplate::new(404).set_body_string("<html>Not Found</html>"),
+        )
+        .await;
+        mock(
+            &server,
+            "GET",
+            "/elections/4",
+            ResponseTemplate::new(409).set_body_json(serde_json::json!({
+                "code": "something_new",
+            })),
+        )
+        .await;
+        let err = client.election(3).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Status {
+                    status: reqwest::StatusCode::NOT_FOUND,
+                    code: None,
+                }
+            ),
+            "{err:?}"
+        );
+        let err = client.election(4).await.unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::Status {
+                    status: reqwest::StatusCode::CONFLICT,
+                    code: Some(code),
+                } if code == "something_new"
+            ),
+            "{err:?}"
+        );
+
+        // A response that does not match the API types is a decoding error.
+        mock(
+            &server,
+            "GET",
+            "/elections/5/join",
+            ResponseTemplate::new(200).set_body_json("yes"),
+        )
+        .await;
+        let err = client.has_joined(5).await.unwrap_err();
+        assert!(matches!(&err, Error::Http(e) if e.is_decode()), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn echoes_csrf_token() {
+        let server = MockServer::start().await;
+        let client = ApiClient::new(server.uri()).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/auth/admin"))
+            .and(body_json(serde_json::json!({
+                "username": "admin",
+                "password": "password",
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .append_header("Set-Cookie", "auth_token=secret; Path=/; HttpOnly")
+                    .append_header("Set-Cookie", format!("{CSRF_COOKIE}=token123; Path=/")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/elections/1/publish"))
+            .and(header(CSRF_HEADER, "token123"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        client.admin_login("admin", "password").await.unwrap();
+        client.publish_election(1).await.unwrap();
+    }
+}
@@ -1,75 +1,517 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
 use chrono::Utc;
+use dre_ip::Serializable;
 use mongodb::{
-    bson::doc,
-    options::{FindOneAndUpdateOptions, ReturnDocument},
-    Client,
+    bson::{doc, oid::ObjectId, DateTime, Document},
+    options::{
+        AggregateOptions, CountOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions,
+        ReturnDocument, UpdateOptions,
+    },
+    Client, Database,
 };
 use rocket::{
+    data::{Data, ToByteUnit},
     futures::{FutureExt, TryStreamExt},
-    http::Status,
+    http::{Header, Status},
     serde::json::Json,
     tokio::sync::Mutex,
     Route, State,
 };
+use serde::Deserialize;
+use serde_json::Value;
 
 use crate::{
+    config::{Config, SmsRouter},
     error::{Error, Result},
+    events::{DomainEvent, EventBus},
     logging::RequestId,
     model::{
         api::{
-            admin::AdminCredentials,
-            auth::AuthToken,
-            election::{ElectionDescription, ElectionSpec},
+            activity::{ActivityBucket, ActivityReport},
+            admin::{
+                hash_password, AdminCredentials, AdminPasswordChange, AdminTokenDesc,
+                AdminTokenSpec, NewAdminTokenDesc, MIN_PASSWORD_LENGTH,
+            },
+            auth::{AuthToken, CsrfChecked},
+            ballot::WriteInCount,
+            bulk_archive::{BulkArchiveOutcome, BulkArchiveRequest, BulkArchiveResult},
+            clock_skew::ClockSkewReport,
+            consent::ConsentSummary,
+            counter::CounterStatus,
+            db_stats::DbStats,
+            election::{
+                branding_to_bson, check_branding, DroppedQuestion, ElectionCrypto,
+                ElectionDescription, ElectionModification, ElectionPublication, ElectionSpec,
+                ElectionSpecInput, ElectorateSpec, FinalizationSummary,
+            },
+            finalize_preview::FinalizePreview,
+            health::{ElectionHealth, QuestionHealth},
+            maintenance::MaintenanceReportDesc,
+            pagination::{MaybePaginated, OptionalPagination},
+            rate_limit::{KeyRejections, LimiterSummary, RateLimitSummary},
+            retention::RetentionReport,
+            sms::{Sms, SmsDeliverySummary, SmsQueueStatus, SmsRoute},
+            task::TaskDesc,
+            voter::{
+                HmacRotationReport, ImportRowError, VoterFreezeRequest, VoterImportReport,
+                VoterLookup, MAX_REPORTED_IMPORT_ERRORS,
+            },
         },
         common::{
-            ballot::{Audited, Unconfirmed},
-            election::{ElectionId, ElectionState},
+            allowed_questions::{AllowedQuestions, Joins},
+            ballot::{Audited, BallotId, Confirmed},
+            election::{ElectionId, ElectionState, QuestionId},
         },
         db::{
             admin::{Admin, NewAdmin},
-            ballot::{AnyBallot, Ballot},
-            candidate_totals::CandidateTotals,
-            election::{Election, ElectionFinalizers},
-            voter::Voter,
+            admin_token::{AdminToken, NewAdminToken},
+            audit::AuditLogEntry,
+            ballot::AnyBallot,
+            board::{BoardEntry, BoardHead},
+            candidate_totals::{
+                create_zero_totals, delete_stale_totals, CandidateTotals, TotalsDelta,
+            },
+            challenge_delivery::{ChallengeDelivery, DeliveryStatus},
+            election::{preview_finalization, ArchiveExport, Election, ElectionFinalizers},
+            hmac_check::HmacCheck,
+            maintenance::{Maintenance, MaintenanceReport},
+            rate_limit::{RateLimitEvent, RATE_LIMIT_EVENT_LIFETIME},
+            voter::{NewVoter, Voter, VoterFreeze},
+            voter_election::VoterElection,
         },
         mongodb::{
-            ballot_counter_id, is_duplicate_key_error, u32_id_filter, Coll, Counter,
-            ELECTION_ID_COUNTER_ID,
+            ballot_counter_id, is_duplicate_key_error, u32_id_filter, ClockSkewMonitor, Coll,
+            CommandMonitor, Counter, Id, RequestComment, ELECTION_ID_COUNTER_ID,
         },
     },
 };
 
+use crate::scheduled_task::TaskRegistry;
+
+use super::{archive_export::ArchiveExports, public::election_ballot_stats, sms_queue::SmsQueue};
+
 pub fn routes() -> Vec<Route> {
     routes![
         get_admins,
+        get_sms_routes,
+        run_maintenance,
+        last_maintenance_run,
+        list_tasks,
+        trigger_task,
+        rate_limit_summary,
+        db_stats,
+        clock_skew,
+        hmac_rotation,
+        sms_delivery_summary,
+        sms_queue_status,
         create_admin,
         delete_admin,
+        change_admin_password,
+        create_admin_token,
+        get_admin_tokens,
+        revoke_admin_token,
         create_election,
         modify_election,
+        set_branding,
+        set_board_embargo,
+        election_consent,
+        set_consent,
+        put_electorate,
+        delete_electorate,
         publish_election,
+        rotate_election_keys,
+        suspend_election,
+        resume_election,
         archive_election,
+        finalize_preview,
+        bulk_archive_elections,
+        election_finalization,
+        election_spec,
+        election_retention,
         delete_election,
+        lookup_voter,
+        freeze_voter,
+        unfreeze_voter,
+        import_voters,
+        election_counters,
+        election_activity,
+        repair_counters,
+        election_health,
+        write_ins,
     ]
 }
 
-#[get("/admins")]
+/// Fields that the admin listing can be sorted by.
+const ADMIN_SORT_FIELDS: &[&str] = &["username"];
+
+/// List admin usernames. Every admin is listed unless pagination or sorting
+/// is asked for; see [`OptionalPagination`].
+#[get("/admins?<pagination..>")]
 async fn get_admins(
     token: AuthToken<Admin>,
+    pagination: OptionalPagination,
     admins: Coll<Admin>,
     request_id: RequestId,
-) -> Result<Json<Vec<String>>> {
+) -> Result<Json<MaybePaginated<String>>> {
     info!("  req{} Admin {} acting", request_id, token.id);
-    let admin_list: Vec<Admin> = admins.find(None, None).await?.try_collect().await?;
-    let admin_names = admin_list
-        .into_iter()
-        .map(|admin| admin.admin.username)
-        .collect();
-    Ok(Json(admin_names))
+    let Some(pagination) = pagination.0 else {
+        let admin_list: Vec<Admin> = admins.find(None, None).await?.try_collect().await?;
+        let admin_names = admin_list
+            .into_iter()
+            .map(|admin| admin.admin.username)
+            .collect();
+        return Ok(Json(MaybePaginated::All(admin_names)));
+    };
+
+    let sort = pagination
+        .sort(ADMIN_SORT_FIELDS)
+        .map_err(|e| Error::Status(Status::UnprocessableEntity, e))?
+        .unwrap_or_else(|| doc! { "_id": 1 });
+    let options = FindOptions::builder()
+        .sort(sort)
+        .skip(u64::from(pagination.skip()))
+        .limit(i64::from(pagination.page_size()))
+        .build()
+        .with_request_id(request_id);
+    let admin_names = admins
+        .find(None, options)
+        .await?
+        .map_ok(|admin| admin.admin.username)
+        .try_collect()
+        .await?;
+    let total = admins
+        .count_documents(None, CountOptions::for_request(request_id))
+        .await?;
+    Ok(Json(MaybePaginated::Paginated(
+        pagination.to_paginated(total, admin_names),
+    )))
+}
+
+#[get("/admin/sms-routes")]
+async fn get_sms_routes(
+    token: AuthToken<Admin>,
+    sms_router: &State<SmsRouter>,
+    request_id: RequestId,
+) -> Json<Vec<SmsRoute>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    Json(sms_router.routes())
+}
+
+#[post("/admin/maintenance/run")]
+async fn run_maintenance(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    maintenance: &State<Maintenance>,
+    request_id: RequestId,
+) -> Result<Json<MaintenanceReportDesc>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let report = maintenance.run().await?;
+    warn!(
+        "  req{} Ran maintenance, purged {} voters and {} counters, redacted {} ballots",
+        request_id, report.voters_purged, report.counters_purged, report.ballots_redacted
+    );
+    Ok(Json(report.into()))
+}
+
+#[get("/admin/maintenance/last-run")]
+async fn last_maintenance_run(
+    token: AuthToken<Admin>,
+    reports: Coll<MaintenanceReport>,
+    request_id: RequestId,
+) -> Result<Json<MaintenanceReportDesc>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let report = MaintenanceReport::latest(&reports, FindOneOptions::for_request(request_id))
+        .await?
+        .ok_or_else(|| Error::not_found("Maintenance run".to_string()))?;
+    Ok(Json(report.into()))
+}
+
+/// List every scheduled task, soonest first.
+#[get("/admin/tasks")]
+async fn list_tasks(
+    token: AuthToken<Admin>,
+    registry: &State<TaskRegistry>,
+    request_id: RequestId,
+) -> Json<Vec<TaskDesc>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    Json(registry.list())
+}
+
+/// Run a pending scheduled task now, if it may be run early.
+#[post("/admin/tasks/<name>/trigger")]
+async fn trigger_task(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    name: &str,
+    registry: &State<TaskRegistry>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+
+    // Record the trigger before attempting it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(username, "task_trigger", format!("Task {}", name))
+        .record(&audit_log, request_id)
+        .await?;
+
+    registry.trigger(name)?;
+    warn!(
+        "  req{} Triggered scheduled task {} early",
+        request_id, name
+    );
+    Ok(())
+}
+
+/// How many of each limiter's most rejected keys are summarised.
+const TOP_RATE_LIMIT_KEYS: usize = 5;
+
+/// Summarise the requests rejected by rate limiters within the given window
+/// (e.g. `30m`, `1h`, `7d`), which defaults to the last hour.
+#[get("/admin/rate-limits/summary?<window>")]
+async fn rate_limit_summary(
+    token: AuthToken<Admin>,
+    window: Option<&str>,
+    rate_limit_events: Coll<RateLimitEvent>,
+    request_id: RequestId,
+) -> Result<Json<RateLimitSummary>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let window = parse_window(window.unwrap_or("1h"))?;
+    let since = DateTime::from_system_time(SystemTime::now() - window);
+
+    let pipeline = [
+        doc! {
+            "$match": {
+                "created_at": {"$gt": since},
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": {"limiter": "$limiter", "key_hash": "$key_hash"},
+                "count": {"$sum": 1},
+            }
+        },
+        doc! {
+            "$sort": {"count": -1, "_id.key_hash": 1}
+        },
+        doc! {
+            "$group": {
+                "_id": "$_id.limiter",
+                "rejections": {"$sum": "$count"},
+                "keys": {"$push": {"key_hash": "$_id.key_hash", "rejections": "$count"}},
+            }
+        },
+        doc! {
+            "$sort": {"_id": 1}
+        },
+    ];
+    let limiters = rate_limit_events
+        .aggregate(pipeline, AggregateOptions::for_request(request_id))
+        .await?
+        .with_type::<LimiterStats>()
+        .map_ok(|stats| LimiterSummary {
+            limiter: stats.limiter,
+            rejections: stats.rejections,
+            top_keys: stats.keys.into_iter().take(TOP_RATE_LIMIT_KEYS).collect(),
+        })
+        .try_collect()
+        .await?;
+
+    Ok(Json(RateLimitSummary {
+        window_secs: window.as_secs(),
+        limiters,
+    }))
+}
+
+/// Rejections by a single limiter, as produced by the aggregation in
+/// [`rate_limit_summary`]. Keys are sorted with the most rejected first.
+#[derive(Deserialize)]
+struct LimiterStats {
+    #[serde(rename = "_id")]
+    limiter: String,
+    rejections: u64,
+    keys: Vec<KeyRejections>,
+}
+
+/// Parse a rate limit summary window such as `90s`, `30m`, `1h` or `7d`.
+/// Fails with 400 if the window is malformed, empty, or longer than rate limit
+/// rejections are kept for.
+fn parse_window(window: &str) -> Result<Duration> {
+    let window = parse_span("window", window)?;
+    if window > RATE_LIMIT_EVENT_LIFETIME {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!(
+                "Window too long: rate limit rejections are only kept for {} days",
+                RATE_LIMIT_EVENT_LIFETIME.as_secs() / (24 * 60 * 60)
+            ),
+        ));
+    }
+    Ok(window)
+}
+
+/// Parse a length of time such as `90s`, `30m`, `1h` or `7d`, given as the
+/// named query parameter. Fails with 400 if it is malformed or empty.
+fn parse_span(name: &str, span: &str) -> Result<Duration> {
+    let invalid = || {
+        Error::Status(
+            Status::BadRequest,
+            format!("Invalid {name} '{span}': expected e.g. '30m', '1h' or '7d'"),
+        )
+    };
+    let unit_secs = match span.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let count: u64 = span[..span.len() - 1].parse().map_err(|_| invalid())?;
+    let span = count
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)?;
+    if span.is_zero() {
+        return Err(invalid());
+    }
+    Ok(span)
+}
+
+/// Timings of the database commands issued since the server started, by
+/// collection and command, along with the most recent slow ones.
+#[get("/admin/db-stats")]
+async fn db_stats(
+    token: AuthToken<Admin>,
+    monitor: &State<CommandMonitor>,
+    request_id: RequestId,
+) -> Json<DbStats> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    Json(monitor.stats())
+}
+
+/// The most recent measurement of how far the database server's clock is
+/// ahead of ours, taken at launch and then periodically.
+#[get("/admin/clock-skew")]
+async fn clock_skew(
+    token: AuthToken<Admin>,
+    monitor: &State<ClockSkewMonitor>,
+    request_id: RequestId,
+) -> Json<ClockSkewReport> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    Json(monitor.report())
+}
+
+/// Count the voters registered before and after `hmac_secret` was last
+/// changed, to gauge how many were orphaned by the change. Voters are dated by
+/// their ID, to the second, so those registered in the same second as the
+/// change count as after it.
+#[get("/admin/hmac-rotation")]
+async fn hmac_rotation(
+    token: AuthToken<Admin>,
+    hmac_checks: Coll<HmacCheck>,
+    voters: Coll<Voter>,
+    request_id: RequestId,
+) -> Result<Json<HmacRotationReport>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let rotated_at = HmacCheck::get(&hmac_checks, FindOneOptions::for_request(request_id))
+        .await?
+        .and_then(|check| check.rotated_at);
+    let voters_before = match rotated_at {
+        Some(rotated_at) => {
+            // The lowest ID generated in the second of the change.
+            let secs = (rotated_at.timestamp_millis() / 1000) as u32;
+            let mut first_id = [0; 12];
+            first_id[..4].copy_from_slice(&secs.to_be_bytes());
+            voters
+                .count_documents(
+                    doc! { "_id": { "$lt": ObjectId::from_bytes(first_id) } },
+                    CountOptions::for_request(request_id),
+                )
+                .await?
+        }
+        None => 0,
+    };
+    let total = voters
+        .count_documents(None, CountOptions::for_request(request_id))
+        .await?;
+
+    Ok(Json(HmacRotationReport {
+        rotated_at: rotated_at.map(DateTime::to_chrono),
+        voters_before,
+        // Voters may have been purged between the counts.
+        voters_after: total.saturating_sub(voters_before),
+    }))
+}
+
+/// Summarise what became of the OTP challenges sent to voters' numbers, to
+/// spot numbers, or whole routes, that SMS is not reaching.
+#[get("/admin/sms-deliveries")]
+async fn sms_delivery_summary(
+    token: AuthToken<Admin>,
+    deliveries: Coll<ChallengeDelivery>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<SmsDeliverySummary>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let pipeline = [doc! {
+        "$group": {
+            "_id": "$status",
+            "count": {"$sum": 1},
+        }
+    }];
+    let mut counts = deliveries
+        .aggregate(pipeline, AggregateOptions::for_request(request_id))
+        .await?
+        .with_type::<StatusCount>();
+    let mut summary = SmsDeliverySummary::default();
+    while let Some(StatusCount { status, count }) = counts.try_next().await? {
+        let total = match status {
+            DeliveryStatus::Sent => &mut summary.sent,
+            DeliveryStatus::Delivered => &mut summary.delivered,
+            DeliveryStatus::OptedOut => &mut summary.opted_out,
+            DeliveryStatus::InvalidNumber => &mut summary.invalid_number,
+            DeliveryStatus::Failed => &mut summary.failed,
+        };
+        *total = count;
+    }
+    summary.undeliverable = deliveries
+        .count_documents(
+            doc! { "failures": { "$gte": config.max_sms_delivery_failures() } },
+            CountOptions::for_request(request_id),
+        )
+        .await?;
+
+    Ok(Json(summary))
+}
+
+/// Number of challenge delivery records with a single status, as produced by
+/// the aggregation in [`sms_delivery_summary`].
+#[derive(Deserialize)]
+struct StatusCount {
+    #[serde(rename = "_id")]
+    status: DeliveryStatus,
+    count: u64,
+}
+
+/// Report how many OTP challenges are waiting to be sent, and how many could
+/// not be, to spot SNS falling behind during a rush of voters.
+#[get("/admin/sms-queue")]
+async fn sms_queue_status(
+    token: AuthToken<Admin>,
+    sms_queue: &State<SmsQueue>,
+    request_id: RequestId,
+) -> Json<SmsQueueStatus> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    Json(sms_queue.status())
 }
 
 #[post("/admins", data = "<new_admin>", format = "json")]
 async fn create_admin(
     token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
     new_admin: Json<AdminCredentials>,
     admins: Coll<NewAdmin>,
     request_id: RequestId,
@@ -102,8 +544,10 @@ async fn create_admin(
 #[delete("/admins/<username>")]
 async fn delete_admin(
     token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
     username: String,
     admins: Coll<Admin>,
+    admin_tokens: Coll<AdminToken>,
     request_id: RequestId,
 ) -> Result<()> {
     info!("  req{} Admin {} acting", request_id, token.id);
@@ -125,39 +569,223 @@ async fn delete_admin(
     let filter = doc! {
         "username": &username,
     };
-    let result = admins.delete_one(filter, None).await?;
+    let admin = admins
+        .find_one_and_delete(filter, None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Admin {}", username)))?;
+    // Their access tokens would no longer work anyway, so tidy them up.
+    admin_tokens
+        .delete_many(doc! { "admin_id": admin.id }, None)
+        .await?;
+    warn!("  req{request_id} Deleted admin user: {username}");
+    Ok(())
+}
+
+#[put("/admins/me/password", data = "<change>", format = "json")]
+async fn change_admin_password(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    change: Json<AdminPasswordChange>,
+    admins: Coll<Admin>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let change = change.0;
+    let admin = admins
+        .find_one(token.id.as_doc(), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Admin {}", token.id)))?;
+    if !admin.verify_password(change.current_password.expose()) {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Current password is incorrect".to_string(),
+        ));
+    }
+    let new_password = change.new_password.expose();
+    if new_password.len() < MIN_PASSWORD_LENGTH {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("New password must be at least {MIN_PASSWORD_LENGTH} characters"),
+        ));
+    }
+    if admin.verify_password(new_password) {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "New password must differ from the current one".to_string(),
+        ));
+    }
+
+    let update = doc! {
+        "$set": {
+            "password_hash": hash_password(new_password),
+            "must_change_password": false,
+        }
+    };
+    admins.update_one(token.id.as_doc(), update, None).await?;
+    warn!(
+        "  req{} Admin {} changed their password",
+        request_id, admin.username
+    );
+    Ok(())
+}
+
+#[post("/admins/me/tokens", data = "<spec>", format = "json")]
+async fn create_admin_token(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    spec: Json<AdminTokenSpec>,
+    admin_tokens: Coll<NewAdminToken>,
+    request_id: RequestId,
+) -> Result<Json<NewAdminTokenDesc>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let spec = spec.0;
+    if spec.name.is_empty() {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Token name must not be empty".to_string(),
+        ));
+    }
+    if spec.expires_at.is_some_and(|expiry| expiry <= Utc::now()) {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Token expiry must be in the future".to_string(),
+        ));
+    }
+
+    let (new_token, secret) = NewAdminToken::generate(token.id, spec.name, spec.expires_at);
+    let id = admin_tokens
+        .insert_one(&new_token, None)
+        .await?
+        .inserted_id
+        .as_object_id()
+        .unwrap() // Safe because the ID comes directly from the database.
+        .into();
+    let new_token = AdminToken {
+        id,
+        token: new_token,
+    };
+    warn!(
+        "  req{} Admin {} created access token {} ({})",
+        request_id, token.id, new_token.id, new_token.name
+    );
+    Ok(Json(NewAdminTokenDesc {
+        token: new_token.into(),
+        secret,
+    }))
+}
+
+#[get("/admins/me/tokens")]
+async fn get_admin_tokens(
+    token: AuthToken<Admin>,
+    admin_tokens: Coll<AdminToken>,
+    request_id: RequestId,
+) -> Result<Json<Vec<AdminTokenDesc>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let options = FindOptions::builder()
+        .sort(doc! { "created_at": 1 })
+        .build()
+        .with_request_id(request_id);
+    let tokens = admin_tokens
+        .find(doc! { "admin_id": token.id }, options)
+        .await?
+        .map_ok(AdminTokenDesc::from)
+        .try_collect()
+        .await?;
+    Ok(Json(tokens))
+}
+
+#[delete("/admins/me/tokens/<token_id>")]
+async fn revoke_admin_token(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    token_id: &str,
+    admin_tokens: Coll<AdminToken>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let not_found = || Error::not_found(format!("Access token {}", token_id));
+    let id: Id = token_id.parse().map_err(|_| not_found())?;
+
+    // Admins can only revoke their own tokens.
+    let filter = doc! {
+        "_id": id,
+        "admin_id": token.id,
+    };
+    let result = admin_tokens.delete_one(filter, None).await?;
     if result.deleted_count == 0 {
-        Err(Error::not_found(format!("Admin {}", username)))
-    } else {
-        warn!("  req{request_id} Deleted admin user: {username}");
-        Ok(())
+        return Err(not_found());
     }
+    warn!(
+        "  req{} Admin {} revoked access token {}",
+        request_id, token.id, id
+    );
+    Ok(())
 }
 
 #[post("/elections", data = "<spec>", format = "json")]
+#[allow(clippy::too_many_arguments)]
 async fn create_election(
     token: AuthToken<Admin>,
-    spec: Json<ElectionSpec>,
+    _csrf: CsrfChecked,
+    spec: Json<ElectionSpecInput>,
     elections: Coll<Election>,
     counters: Coll<Counter>,
+    candidate_totals: Coll<CandidateTotals>,
+    admins: Coll<Admin>,
     db_client: &State<Client>,
     request_id: RequestId,
 ) -> Result<Json<ElectionDescription>> {
     info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let spec = ElectionSpec::try_from(spec.0).map_err(Error::InvalidElectionSpec)?;
+    spec.check_branding()?;
+    spec.check_question_descriptions()?;
+    spec.check_question_end_times()?;
+    spec.check_candidates()?;
+    spec.check_quorums()?;
+    spec.check_translations()?;
 
     // Obtain a unique election ID.
     let election_id = Counter::next(&counters, ELECTION_ID_COUNTER_ID).await?;
     trace!("  req{request_id} Obtained election id {election_id}");
 
     // Create the election.
-    let election = spec.0.into_election(election_id, rand::thread_rng());
+    let mut election = spec.into_election(election_id, rand::thread_rng());
+    election.metadata.created_by = username;
+
+    insert_election(
+        &election,
+        &elections,
+        &counters,
+        &candidate_totals,
+        db_client,
+        request_id,
+    )
+    .await?;
+
+    warn!(
+        "  req{} Created {:?} election {} - {}",
+        request_id, election.metadata.state, election.id, election.metadata.name
+    );
+
+    Ok(Json(ElectionDescription::for_admin(election)))
+}
 
-    // Insert the election.
+/// Insert a new election, along with a ballot counter for each question, and
+/// zero candidate totals if it is inserted already published.
+pub(super) async fn insert_election(
+    election: &Election,
+    elections: &Coll<Election>,
+    counters: &Coll<Counter>,
+    candidate_totals: &Coll<CandidateTotals>,
+    db_client: &Client,
+    request_id: RequestId,
+) -> Result<()> {
     let mut session = db_client.start_session(None).await?;
     session
         .with_transaction(
-            (request_id, &elections, &election, &counters),
-            |session, (request_id, elections, election, counters)| {
+            (request_id, elections, election, counters, candidate_totals),
+            |session, (request_id, elections, election, counters, candidate_totals)| {
                 async move {
                     elections
                         .insert_one_with_session(*election, None, session)
@@ -176,7 +804,7 @@ async fn create_election(
                         .questions
                         .keys()
                         .map(|question_id| Counter {
-                            id: ballot_counter_id(election_id, *question_id),
+                            id: ballot_counter_id(election.id, *question_id),
                             next: 1,
                         })
                         .collect::<Vec<_>>();
@@ -190,6 +818,12 @@ async fn create_election(
                         new_counters.len()
                     );
 
+                    if election.metadata.state == ElectionState::Published {
+                        let created =
+                            create_zero_totals(election, candidate_totals, session).await?;
+                        trace!("  req{} Inserted {} candidate totals", request_id, created);
+                    }
+
                     Ok(())
                 }
                 .boxed()
@@ -197,24 +831,32 @@ async fn create_election(
             None,
         )
         .await?;
-
-    warn!(
-        "  req{} Created {:?} election {} - {}",
-        request_id, election.metadata.state, election.id, election.metadata.name
-    );
-
-    Ok(Json(election.into()))
+    Ok(())
 }
 
 #[put("/elections/<election_id>", data = "<spec>", format = "json")]
+#[allow(clippy::too_many_arguments)]
 async fn modify_election(
     token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
     election_id: ElectionId,
-    spec: Json<ElectionSpec>,
+    spec: Json<ElectionSpecInput>,
     elections: Coll<Election>,
+    admins: Coll<Admin>,
+    voter_elections: Coll<VoterElection>,
+    election_finalizers: &State<ElectionFinalizers>,
+    db_client: &State<Client>,
     request_id: RequestId,
-) -> Result<Json<ElectionDescription>> {
+) -> Result<Json<ElectionModification>> {
     info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let spec = ElectionSpec::try_from(spec.0).map_err(Error::InvalidElectionSpec)?;
+    spec.check_branding()?;
+    spec.check_question_descriptions()?;
+    spec.check_question_end_times()?;
+    spec.check_candidates()?;
+    spec.check_quorums()?;
+    spec.check_translations()?;
 
     // Get the existing election.
     let election = elections
@@ -223,175 +865,1304 @@ async fn modify_election(
         .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
 
     // Check we are allowed to modify it.
-    let now = Utc::now();
-    if !(election.metadata.state == ElectionState::Draft
-        || election.metadata.state == ElectionState::Published
-            && election.metadata.start_time > now)
-    {
-        return Err(Error::Status(
-            Status::BadRequest,
-            format!("Cannot modify election {}", election_id),
-        ));
-    }
-
-    // Replace with the new spec.
-    let new_election = spec.0.into_election(election_id, rand::thread_rng());
-    let result = elections
-        .replace_one(u32_id_filter(election_id), &new_election, None)
-        .await?;
-    assert_eq!(result.modified_count, 1);
-    warn!("  req{request_id} Modified election {election_id}");
+    check_modifiable(&election)?;
 
-    Ok(Json(new_election.into()))
-}
+    // Replace with the new spec, keeping the original creator and joined voters.
+    let mut new_election = spec.into_election(election_id, rand::thread_rng());
+    new_election.metadata.created_by = election.metadata.created_by;
+    new_election.metadata.joined_count = election.metadata.joined_count;
+    new_election.metadata.last_modified_by = Some(username);
+    new_election.keep_question_slugs(&election);
 
-#[post("/elections/<election_id>/publish")]
-async fn publish_election(
-    token: AuthToken<Admin>,
-    election_id: ElectionId,
-    elections: Coll<Election>,
-    unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
-    audited_ballots: Coll<Ballot<Audited>>,
-    election_finalizers: &State<ElectionFinalizers>,
-    request_id: RequestId,
-) -> Result<()> {
-    info!("  req{} Admin {} acting", request_id, token.id);
+    // Voters who have already joined have allowed questions keyed by the old
+    // question IDs, so carry them over by matching question descriptions.
+    let mapping = election.question_mapping(&new_election);
+    let constraints_changed = election.constraints_changed(&new_election, &mapping);
+    let mut dropped_questions = election
+        .questions
+        .values()
+        .filter(|question| !mapping.contains_key(&question.id))
+        .map(DroppedQuestion::from)
+        .collect::<Vec<_>>();
+    dropped_questions.sort_unstable_by_key(|question| question.id);
 
-    // Update the state.
-    let filter = doc! {
-        "_id": election_id,
-        "state": ElectionState::Draft,
-    };
-    let update = doc! {
-        "$set": {
-            "state": ElectionState::Published,
+    // Replace the election and update the voters atomically.
+    let mut session = db_client.start_session(None).await?;
+    let voters_affected = session
+        .with_transaction(
+            (
+                request_id,
+                election_id,
+                &new_election,
+                &mapping,
+                constraints_changed,
+                &elections,
+                &voter_elections,
+            ),
+            |session,
+             (
+                request_id,
+                election_id,
+                new_election,
+                mapping,
+                constraints_changed,
+                elections,
+                voter_elections,
+            )| {
+                async move {
+                    let result = elections
+                        .replace_one_with_session(
+                            u32_id_filter(*election_id),
+                            *new_election,
+                            None,
+                            session,
+                        )
+                        .await?;
+                    assert_eq!(result.modified_count, 1);
+
+                    let joined = voter_elections
+                        .find_with_session(doc! { "election_id": *election_id }, None, session)
+                        .await?
+                        .stream(session)
+                        .try_collect::<Vec<_>>()
+                        .await?;
+                    let mut voters_affected: u64 = 0;
+                    let mut voters_unjoined: i64 = 0;
+                    for voter_election in joined {
+                        // Their groups may no longer allow the same questions,
+                        // so work them out again. The groups of voters who
+                        // joined before they were recorded are unknown, so
+                        // those voters must join again if any constraint changed.
+                        let allowed = &voter_election.allowed_questions;
+                        let updated = match &voter_election.joins {
+                            Some(joins) => allowed.recompute(new_election, joins, mapping).ok(),
+                            None if *constraints_changed => None,
+                            None => Some(allowed.remap(mapping)),
+                        };
+                        if updated.as_ref() == Some(allowed) {
+                            continue;
+                        }
+                        let filter = VoterElection::filter(voter_election.voter_id, *election_id);
+                        let updated = updated.filter(|updated| !updated.is_empty());
+                        if let Some(updated) = updated {
+                            let updated = mongodb::bson::to_bson(&updated).unwrap(); // Cannot fail.
+                            let update = doc! { "$set": { "allowed_questions": updated } };
+                            voter_elections
+                                .update_one_with_session(filter, update, None, session)
+                                .await?;
+                        } else {
+                            // Nothing left to vote on, or we can't tell what
+                            // is, so make them join again.
+                            voters_unjoined += 1;
+                            voter_elections
+                                .delete_one_with_session(filter, None, session)
+                                .await?;
+                        }
+                        voters_affected += 1;
+                    }
+                    // Voters who must join again no longer count towards the cap.
+                    if voters_unjoined > 0 {
+                        elections
+                            .update_one_with_session(
+                                u32_id_filter(*election_id),
+                                // Never below zero, as voters of older elections were not counted.
+                                vec![doc! {
+                                    "$set": {
+                                        "joined_count": {
+                                            "$max": [
+                                                0,
+                                                { "$subtract": ["$joined_count", voters_unjoined] },
+                                            ],
+                                        }
+                                    }
+                                }],
+                                None,
+                                session,
+                            )
+                            .await?;
+                    }
+                    trace!(
+                        "  req{} Remapped allowed questions for {} voters",
+                        request_id,
+                        voters_affected
+                    );
+
+                    Ok(voters_affected)
+                }
+                .boxed()
+            },
+            None,
+        )
+        .await?;
+    // The election is a draft again, so must not be finalized until republished.
+    election_finalizers.cancel_election(election_id).await;
+    warn!("  req{request_id} Modified election {election_id}");
+    if voters_affected > 0 {
+        warn!(
+            "  req{} Modifying election {} affected {} joined voters, dropping questions {:?}",
+            request_id,
+            election_id,
+            voters_affected,
+            dropped_questions
+                .iter()
+                .map(|question| question.id)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    Ok(Json(ElectionModification {
+        election: ElectionDescription::for_admin(new_election),
+        voters_affected,
+        dropped_questions,
+    }))
+}
+
+/// Replace an election's branding, or remove it with `null`. Unlike the rest of
+/// the spec, this can be changed at any time, as it has no bearing on the vote.
+#[patch(
+    "/elections/<election_id>/branding",
+    data = "<branding>",
+    format = "json"
+)]
+async fn set_branding(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    branding: Json<Option<Value>>,
+    elections: Coll<Election>,
+    admins: Coll<Admin>,
+    request_id: RequestId,
+) -> Result<Json<ElectionDescription>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let update = match &branding.0 {
+        Some(branding) => {
+            check_branding(branding)?;
+            let branding = branding_to_bson(branding)?;
+            doc! { "$set": { "branding": branding, "last_modified_by": username } }
         }
+        None => doc! {
+            "$set": { "last_modified_by": username },
+            "$unset": { "branding": "" },
+        },
     };
     let options = FindOneAndUpdateOptions::builder()
         .return_document(ReturnDocument::After)
         .build();
-    let result = elections
-        .find_one_and_update(filter, update, options)
-        .await?;
-    let election = match result {
-        Some(e) => e,
-        None => {
+    let election = elections
+        .find_one_and_update(u32_id_filter(election_id), update, options)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    warn!("  req{request_id} Updated branding of election {election_id}");
+
+    Ok(Json(ElectionDescription::for_admin(election)))
+}
+
+/// Hide an election's ballots and dumps from everyone but admins until the
+/// given time, or lift the embargo with `null`. Like branding, this can be
+/// changed at any time.
+#[patch("/elections/<election_id>/embargo", data = "<until>", format = "json")]
+async fn set_board_embargo(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    until: Json<Option<chrono::DateTime<Utc>>>,
+    elections: Coll<Election>,
+    admins: Coll<Admin>,
+    request_id: RequestId,
+) -> Result<Json<ElectionDescription>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let update = match until.0 {
+        Some(until) => doc! {
+            "$set": {
+                "embargo_public_board_until": until,
+                "last_modified_by": username,
+            },
+        },
+        None => doc! {
+            "$set": { "last_modified_by": username },
+            "$unset": { "embargo_public_board_until": "" },
+        },
+    };
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    let election = elections
+        .find_one_and_update(u32_id_filter(election_id), update, options)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    warn!("  req{request_id} Updated public board embargo of election {election_id}");
+
+    Ok(Json(ElectionDescription::for_admin(election)))
+}
+
+/// Summarise how many of an election's voters have accepted each version of
+/// its consent text.
+#[get("/elections/<election_id>/consent")]
+async fn election_consent(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    voter_elections: Coll<VoterElection>,
+    request_id: RequestId,
+) -> Result<Json<ConsentSummary>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    Ok(Json(
+        summarise_consent(&election, &voter_elections, request_id).await?,
+    ))
+}
+
+/// Replace the text voters must accept to join an election, bumping its
+/// version, or stop requiring consent with `null`. Like branding, this can be
+/// changed at any time. Voters who accepted an earlier version stay joined,
+/// and are counted as stale in the returned summary.
+#[patch("/elections/<election_id>/consent", data = "<text>", format = "json")]
+#[allow(clippy::too_many_arguments)]
+async fn set_consent(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    text: Json<Option<String>>,
+    elections: Coll<Election>,
+    voter_elections: Coll<VoterElection>,
+    admins: Coll<Admin>,
+    request_id: RequestId,
+) -> Result<Json<ConsentSummary>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let update = match &text.0 {
+        Some(text) if text.is_empty() => {
             return Err(Error::Status(
-                Status::BadRequest,
-                format!(
-                    "Election {} doesn't exist or isn't a draft; cannot publish.",
-                    election_id
-                ),
+                Status::UnprocessableEntity,
+                "Consent text must not be empty".to_string(),
             ));
         }
+        Some(text) => doc! {
+            "$set": { "consent_text": text, "last_modified_by": username },
+            "$inc": { "consent_version": 1 },
+        },
+        None => doc! {
+            "$set": { "last_modified_by": username },
+            "$unset": { "consent_text": "" },
+        },
     };
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    let election = elections
+        .find_one_and_update(u32_id_filter(election_id), update, options)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
 
-    // Schedule the election finalizer.
-    election_finalizers
-        .schedule_election(unconfirmed_ballots, audited_ballots, &election)
-        .await;
-    warn!("  req{request_id} Published election {election_id}");
+    let summary = summarise_consent(&election, &voter_elections, request_id).await?;
+    warn!(
+        "  req{request_id} Updated consent text of election {election_id} to version {}; \
+        {} joined voters accepted another version",
+        summary.consent_version, summary.stale
+    );
+    Ok(Json(summary))
+}
 
-    Ok(())
+/// Count the election's voters by the version of its consent text they accepted.
+async fn summarise_consent(
+    election: &Election,
+    voter_elections: &Coll<VoterElection>,
+    request_id: RequestId,
+) -> Result<ConsentSummary> {
+    let pipeline = [
+        doc! { "$match": { "election_id": election.id } },
+        doc! {
+            "$group": {
+                "_id": "$consent.version",
+                "count": { "$sum": 1 },
+            }
+        },
+    ];
+    let mut counts = voter_elections
+        .aggregate(pipeline, AggregateOptions::for_request(request_id))
+        .await?
+        .with_type::<VersionCount>();
+    let mut summary = ConsentSummary {
+        election_id: election.id,
+        consent_text: election.metadata.consent_text.clone(),
+        consent_version: election.metadata.consent_version,
+        accepted: HashMap::new(),
+        stale: 0,
+        not_accepted: 0,
+    };
+    while let Some(VersionCount { version, count }) = counts.try_next().await? {
+        match version {
+            Some(version) => {
+                if version != summary.consent_version {
+                    summary.stale += count;
+                }
+                summary.accepted.insert(version, count);
+            }
+            None => summary.not_accepted = count,
+        }
+    }
+    Ok(summary)
 }
 
-#[post("/elections/<election_id>/archive")]
-async fn archive_election(
+/// Number of an election's voters who accepted a single version of its
+/// consent text, or none, as produced by the aggregation in [`summarise_consent`].
+#[derive(Deserialize)]
+struct VersionCount {
+    #[serde(rename = "_id")]
+    version: Option<u32>,
+    count: u64,
+}
+
+/// Create or replace a single electorate, without resubmitting the whole spec.
+/// Like [`modify_election`], this is only allowed before the election starts.
+/// Fails with 409 if any question's constraints name a group being removed.
+#[put(
+    "/elections/<election_id>/electorates/<name>",
+    data = "<spec>",
+    format = "json"
+)]
+#[allow(clippy::too_many_arguments)]
+async fn put_electorate(
     token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
     election_id: ElectionId,
+    name: &str,
+    spec: Json<ElectorateSpec>,
     elections: Coll<Election>,
-    election_finalizers: &State<ElectionFinalizers>,
+    admins: Coll<Admin>,
     request_id: RequestId,
-) -> Result<()> {
+) -> Result<Json<ElectionDescription>> {
     info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let election = modifiable_election_for_electorate(election_id, name, &elections).await?;
+    check_not_orphaned(
+        &election,
+        name,
+        election.questions_orphaned_by(name, Some(&spec.groups)),
+    )?;
 
-    // Update the state.
-    let filter = doc! {
-        "_id": election_id,
-        "$or": [{"state": ElectionState::Draft}, {"state": ElectionState::Published}],
-    };
+    let electorate = mongodb::bson::to_bson(&spec.0.into_electorate(name.to_string())).unwrap(); // Cannot fail.
     let update = doc! {
         "$set": {
-            "state": ElectionState::Archived,
+            format!("electorates.{name}"): electorate,
+            "last_modified_by": username,
         }
     };
-    let result = elections.update_one(filter, update, None).await?;
-    if result.modified_count != 1 {
+    let election = update_modifiable_election(election_id, update, &elections).await?;
+    warn!("  req{request_id} Set electorate '{name}' of election {election_id}");
+
+    Ok(Json(ElectionDescription::for_admin(election)))
+}
+
+/// Delete a single electorate, without resubmitting the whole spec.
+/// Like [`modify_election`], this is only allowed before the election starts.
+/// Fails with 409 if any question's constraints name the electorate.
+#[delete("/elections/<election_id>/electorates/<name>")]
+async fn delete_electorate(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    name: &str,
+    elections: Coll<Election>,
+    admins: Coll<Admin>,
+    request_id: RequestId,
+) -> Result<Json<ElectionDescription>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let election = modifiable_election_for_electorate(election_id, name, &elections).await?;
+    if !election.electorates.contains_key(name) {
+        return Err(Error::not_found(format!(
+            "Electorate '{name}' of election {election_id}"
+        )));
+    }
+    check_not_orphaned(&election, name, election.questions_orphaned_by(name, None))?;
+
+    let update = doc! {
+        "$set": { "last_modified_by": username },
+        "$unset": { format!("electorates.{name}"): "" },
+    };
+    let election = update_modifiable_election(election_id, update, &elections).await?;
+    warn!("  req{request_id} Deleted electorate '{name}' of election {election_id}");
+
+    Ok(Json(ElectionDescription::for_admin(election)))
+}
+
+/// Get an election whose electorates are about to be changed, checking that
+/// it may still be modified and that the electorate name is usable as a key.
+async fn modifiable_election_for_electorate(
+    election_id: ElectionId,
+    name: &str,
+    elections: &Coll<Election>,
+) -> Result<Election> {
+    // Electorates are keyed by name, which must therefore be a valid field name.
+    if name.is_empty() || name.contains('.') || name.starts_with('$') {
         return Err(Error::Status(
             Status::BadRequest,
-            format!(
-                "Election {} doesn't exist or is already archived.",
-                election_id
-            ),
+            format!("Invalid electorate name '{name}'"),
         ));
     }
+    let election = elections
+        .find_one(u32_id_filter(election_id), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    check_modifiable(&election)?;
+    Ok(election)
+}
 
-    // Run the election finalizer.
-    election_finalizers.finalize_election(election_id).await?;
-    warn!("  req{request_id} Archived election {election_id}");
+/// Fail with 409 if changing an electorate would orphan the given questions.
+fn check_not_orphaned(election: &Election, name: &str, orphaned: Vec<QuestionId>) -> Result<()> {
+    if orphaned.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Status(
+        Status::Conflict,
+        format!(
+            "Questions {:?} of election {} are constrained by groups of electorate '{}' that would no longer exist",
+            orphaned, election.id, name
+        ),
+    ))
+}
+
+/// Apply a targeted update to an election, provided it may still be
+/// modified, returning the updated election.
+async fn update_modifiable_election(
+    election_id: ElectionId,
+    update: Document,
+    elections: &Coll<Election>,
+) -> Result<Election> {
+    // Repeat the checks of `check_modifiable`, in case the election was
+    // published or started in the meantime.
+    let now = DateTime::from_chrono(Utc::now());
+    let filter = doc! {
+        "_id": election_id,
+        "$or": [
+            { "state": ElectionState::Draft },
+            { "state": ElectionState::Published, "start_time": { "$gt": now } },
+        ],
+    };
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    elections
+        .find_one_and_update(filter, update, options)
+        .await?
+        .ok_or_else(|| {
+            Error::Status(
+                Status::BadRequest,
+                format!("Cannot modify election {}", election_id),
+            )
+        })
+}
 
+/// Check that an election may still be modified: it must be a draft, or
+/// published but not yet started.
+fn check_modifiable(election: &Election) -> Result<()> {
+    let now = Utc::now();
+    if !(election.metadata.state == ElectionState::Draft
+        || election.metadata.state == ElectionState::Published
+            && election.metadata.start_time > now)
+    {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("Cannot modify election {}", election.id),
+        ));
+    }
     Ok(())
 }
 
-#[delete("/elections/<election_id>")]
+/// Replace a draft election's keypair with a freshly generated one, in case
+/// the old private key may have been exposed. Nothing else about the election
+/// changes, so its question IDs and voters are kept.
+#[post("/elections/<election_id>/rotate-keys")]
 #[allow(clippy::too_many_arguments)]
-async fn delete_election(
+async fn rotate_election_keys(
     token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
     election_id: ElectionId,
     elections: Coll<Election>,
-    ballots: Coll<AnyBallot>,
-    totals: Coll<CandidateTotals>,
-    voters: Coll<Voter>,
-    counters: Coll<Counter>,
-    db_client: &State<Client>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
     request_id: RequestId,
-) -> Result<()> {
+) -> Result<Json<ElectionCrypto>> {
     info!("  req{} Admin {} acting", request_id, token.id);
-    // Get the election.
-    let election = elections
+    let mut election = elections
         .find_one(u32_id_filter(election_id), None)
         .await?
         .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    if election.metadata.state != ElectionState::Draft {
+        return Err(key_rotation_conflict(election_id));
+    }
 
-    // Check that the election is in a deletable state.
-    if !(election.metadata.state == ElectionState::Draft
-        || election.metadata.state == ElectionState::Archived)
-    {
-        return Err(Error::Status(
-            Status::BadRequest,
-            format!("Cannot delete election {}", election_id),
-        ));
+    // Record the rotation before performing it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username.clone(),
+        "key_rotation",
+        format!("Election {}", election_id),
+    )
+    .record(&audit_log, request_id)
+    .await?;
+
+    election.rotate_keys(rand::thread_rng());
+    let crypto = mongodb::bson::to_bson(&election.crypto).unwrap(); // Cannot fail.
+    let filter = doc! {
+        "_id": election_id,
+        "state": ElectionState::Draft,
+    };
+    let update = doc! {
+        "$set": {
+            "crypto": crypto,
+            "last_modified_by": username,
+        }
+    };
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    // The election may have been published since it was read.
+    let election = elections
+        .find_one_and_update(filter, update, options)
+        .await?
+        .ok_or_else(|| key_rotation_conflict(election_id))?;
+
+    warn!("  req{request_id} Rotated the keys of election {election_id}");
+    Ok(Json(ElectionCrypto::from(&election)))
+}
+
+/// The error for rotating the keys of an election that is no longer a draft,
+/// since ballots may already have been cast against its keys.
+fn key_rotation_conflict(election_id: ElectionId) -> Error {
+    Error::Status(
+        Status::Conflict,
+        format!(
+            "Election {} isn't a draft; cannot rotate its keys.",
+            election_id
+        ),
+    )
+}
+
+/// Publish a draft election.
+///
+/// An election that has already ended would be finalized as soon as it was
+/// published, so that is refused unless `allow_ended` is set.
+#[post("/elections/<election_id>/publish?<allow_ended>")]
+#[allow(clippy::too_many_arguments)]
+async fn publish_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    allow_ended: Option<bool>,
+    elections: Coll<Election>,
+    candidate_totals: Coll<CandidateTotals>,
+    election_finalizers: &State<ElectionFinalizers>,
+    events: &State<EventBus>,
+    db_client: &State<Client>,
+    db: &State<Database>,
+    request_id: RequestId,
+) -> Result<Json<ElectionPublication>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let allow_ended = allow_ended.unwrap_or(false);
+    let now = Utc::now();
+
+    // Refuse to publish an election with duplicate candidates, since
+    // every ballot cast against it would fail.
+    let mut filter = doc! {
+        "_id": election_id,
+        "state": ElectionState::Draft,
+    };
+    if let Some(draft) = elections.find_one(filter.clone(), None).await? {
+        if !allow_ended && draft.metadata.end_time <= now {
+            return Err(ended_election_error(election_id));
+        }
+
+        let mut bad_questions = draft
+            .questions
+            .values()
+            .filter(|question| {
+                let unique = question.candidates.iter().collect::<HashSet<_>>();
+                unique.len() != question.candidates.len()
+            })
+            .map(|question| question.id)
+            .collect::<Vec<_>>();
+        if !bad_questions.is_empty() {
+            bad_questions.sort_unstable();
+            return Err(Error::Status(
+                Status::UnprocessableEntity,
+                format!(
+                    "Election {} has duplicate candidates in questions {:?}; cannot publish.",
+                    election_id, bad_questions
+                ),
+            ));
+        }
+    }
+
+    // The draft may be modified between the check above and the update, so
+    // only publish it if it has still not ended.
+    if !allow_ended {
+        filter.insert("end_time", doc! { "$gt": now });
     }
 
-    // Atomically delete the election and all associated data.
+    // Update the state, and create zero totals for every candidate in the same
+    // transaction, so that a published election always has them.
     let mut session = db_client.start_session(None).await?;
-    session
+    let result = session
+        .with_transaction(
+            (&filter, &elections, &candidate_totals, request_id),
+            |session, (filter, elections, candidate_totals, request_id)| {
+                async move {
+                    // Clear any record of a previous finalizer or end warning,
+                    // since the new ones start afresh.
+                    let update = doc! {
+                        "$set": {
+                            "state": ElectionState::Published,
+                        },
+                        "$unset": {
+                            "finalization": "",
+                            "end_warning": "",
+                        },
+                    };
+                    let options = FindOneAndUpdateOptions::builder()
+                        .return_document(ReturnDocument::After)
+                        .build();
+                    let Some(mut election) = elections
+                        .find_one_and_update_with_session(filter.clone(), update, options, session)
+                        .await?
+                    else {
+                        return Ok(None);
+                    };
+
+                    // Questions that allow write-ins get their write-in
+                    // candidate now, so that it has totals like the others.
+                    let write_in_questions = election.add_write_in_candidates();
+                    if !write_in_questions.is_empty() {
+                        let mut candidates = Document::new();
+                        for question_id in &write_in_questions {
+                            candidates.insert(
+                                format!("questions.{question_id}.candidates"),
+                                election.questions[question_id].candidates.clone(),
+                            );
+                        }
+                        elections
+                            .update_one_with_session(
+                                u32_id_filter(election.id),
+                                doc! { "$set": candidates },
+                                None,
+                                session,
+                            )
+                            .await?;
+                        trace!(
+                            "  req{} Added write-in candidates to questions {:?}",
+                            request_id,
+                            write_in_questions
+                        );
+                    }
+
+                    // A draft has never been open for voting, so totals left
+                    // over from a previous publication are all zero, but may be
+                    // for candidates that have since been removed.
+                    let deleted = delete_stale_totals(&election, candidate_totals, session).await?;
+                    let created = create_zero_totals(&election, candidate_totals, session).await?;
+                    trace!(
+                        "  req{} Created {} and deleted {} candidate totals",
+                        request_id,
+                        created,
+                        deleted
+                    );
+
+                    Ok(Some(election))
+                }
+                .boxed()
+            },
+            None,
+        )
+        .await?;
+    let election = match result {
+        Some(e) => e,
+        None => {
+            // Distinguish a draft that was modified to end in the past.
+            let draft = doc! { "_id": election_id, "state": ElectionState::Draft };
+            if !allow_ended && elections.count_documents(draft, None).await? > 0 {
+                return Err(ended_election_error(election_id));
+            }
+            return Err(Error::Status(
+                Status::BadRequest,
+                format!(
+                    "Election {} doesn't exist or isn't a draft; cannot publish.",
+                    election_id
+                ),
+            ));
+        }
+    };
+
+    // Schedule the election finalizer.
+    election_finalizers
+        .schedule_election(db_client, db, &election)
+        .await;
+    events.emit(
+        DomainEvent::ElectionPublished { election_id },
+        Some(request_id),
+    );
+
+    let warning =
+        (election.metadata.start_time <= now && now < election.metadata.end_time).then(|| {
+            format!(
+                "Voting in election {} started at {} and is open immediately.",
+                election_id, election.metadata.start_time
+            )
+        });
+    Ok(Json(ElectionPublication { warning }))
+}
+
+/// The error for publishing an election that has already ended.
+fn ended_election_error(election_id: ElectionId) -> Error {
+    Error::Status(
+        Status::UnprocessableEntity,
+        format!(
+            "Election {} has already ended; pass allow_ended to publish it anyway.",
+            election_id
+        ),
+    )
+}
+
+#[post("/elections/<election_id>/suspend")]
+async fn suspend_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+
+    // Record the suspend before performing it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username,
+        "election_suspend",
+        format!("Election {}", election_id),
+    )
+    .record(&audit_log, request_id)
+    .await?;
+
+    set_suspended(election_id, true, &elections).await?;
+    warn!("  req{request_id} Suspended voting in election {election_id}");
+    Ok(())
+}
+
+#[post("/elections/<election_id>/resume")]
+async fn resume_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+
+    // Record the resume before performing it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username,
+        "election_resume",
+        format!("Election {}", election_id),
+    )
+    .record(&audit_log, request_id)
+    .await?;
+
+    set_suspended(election_id, false, &elections).await?;
+    warn!("  req{request_id} Resumed voting in election {election_id}");
+    Ok(())
+}
+
+/// Archive an election, returning the unconfirmed ballots its finalizer
+/// audited as a result.
+#[post("/elections/<election_id>/archive")]
+#[allow(clippy::too_many_arguments)]
+async fn archive_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    election_finalizers: &State<ElectionFinalizers>,
+    archive_exports: &State<ArchiveExports>,
+    events: &State<EventBus>,
+    request_id: RequestId,
+) -> Result<Json<FinalizePreview>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let preview = archive_and_finalize(
+        election_id,
+        &elections,
+        &ballots,
+        election_finalizers,
+        archive_exports,
+        events,
+        request_id,
+    )
+    .await?;
+    Ok(Json(preview))
+}
+
+/// Count the unconfirmed ballots the election's finalizer would audit if it
+/// ran now, by question and by how recently they were cast, without changing
+/// anything.
+#[get("/elections/<election_id>/finalize/preview")]
+async fn finalize_preview(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    request_id: RequestId,
+) -> Result<Json<FinalizePreview>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    let preview = preview_finalization(
+        election_id,
+        &ballots,
+        Utc::now(),
+        AggregateOptions::for_request(request_id),
+    )
+    .await?;
+    Ok(Json(preview))
+}
+
+/// Archive many finished elections at once, e.g. at the end of a term.
+///
+/// Only published elections that have ended are archived, one after another so
+/// that their finalizers do not all run at once. Each is archived just as by
+/// [`archive_election`], and audited once archived. An election whose
+/// finalizer fails is still archived, and its finalizer retried later. The
+/// result for every election asked for, or matching the filter, is returned in
+/// ID order.
+#[post("/elections/bulk-archive", data = "<request>", format = "json")]
+#[allow(clippy::too_many_arguments)]
+async fn bulk_archive_elections(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    request: Json<BulkArchiveRequest>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    election_finalizers: &State<ElectionFinalizers>,
+    archive_exports: &State<ArchiveExports>,
+    events: &State<EventBus>,
+    request_id: RequestId,
+) -> Result<Json<Vec<BulkArchiveResult>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let request = request.into_inner();
+
+    // Find the elections to consider, noting any that were asked for but don't exist.
+    let candidates: Vec<(ElectionId, Option<Election>)> =
+        match (request.election_ids, request.ended_before) {
+            (Some(mut election_ids), None) => {
+                election_ids.sort_unstable();
+                election_ids.dedup();
+                let filter = doc! { "_id": { "$in": election_ids.clone() } };
+                let mut found = elections
+                    .find(filter, FindOptions::for_request(request_id))
+                    .await?
+                    .map_ok(|election| (election.id, election))
+                    .try_collect::<HashMap<_, _>>()
+                    .await?;
+                election_ids
+                    .into_iter()
+                    .map(|election_id| (election_id, found.remove(&election_id)))
+                    .collect()
+            }
+            (None, Some(ended_before)) => {
+                let filter = doc! {
+                    "end_time": { "$lt": ended_before },
+                    "state": { "$ne": ElectionState::Archived },
+                };
+                let options = FindOptions::builder()
+                    .sort(doc! { "_id": 1 })
+                    .build()
+                    .with_request_id(request_id);
+                elections
+                    .find(filter, options)
+                    .await?
+                    .map_ok(|election| (election.id, Some(election)))
+                    .try_collect()
+                    .await?
+            }
+            _ => {
+                return Err(Error::Status(
+                    Status::BadRequest,
+                    "Give exactly one of `election_ids` and `ended_before`".to_string(),
+                ));
+            }
+        };
+
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    let now = Utc::now();
+    let mut results = Vec::with_capacity(candidates.len());
+    for (election_id, election) in candidates {
+        let skipped = |reason: &str| BulkArchiveOutcome::Skipped {
+            reason: reason.to_string(),
+        };
+        let outcome = match election {
+            None => skipped("Election does not exist"),
+            Some(election) if election.metadata.state == ElectionState::Draft => {
+                skipped("Election is a draft")
+            }
+            Some(election) if election.metadata.state == ElectionState::Archived => {
+                skipped("Election is already archived")
+            }
+            Some(election) if election.metadata.rehearsal => {
+                skipped("Election is a rehearsal, which is deleted automatically")
+            }
+            Some(election) if election.metadata.end_time > now => {
+                skipped("Election has not ended yet")
+            }
+            Some(_) if request.dry_run => BulkArchiveOutcome::WouldArchive,
+            Some(_) => {
+                bulk_archive_one(
+                    election_id,
+                    &username,
+                    &elections,
+                    &ballots,
+                    &audit_log,
+                    election_finalizers,
+                    archive_exports,
+                    events,
+                    request_id,
+                )
+                .await
+            }
+        };
+        results.push(BulkArchiveResult {
+            election_id,
+            outcome,
+        });
+    }
+
+    let archived = results
+        .iter()
+        .filter(|result| {
+            matches!(
+                result.outcome,
+                BulkArchiveOutcome::Archived | BulkArchiveOutcome::FinalizationPending { .. }
+            )
+        })
+        .count();
+    info!(
+        "  req{} Bulk archived {} of {} elections{}",
+        request_id,
+        archived,
+        results.len(),
+        if request.dry_run { " (dry run)" } else { "" }
+    );
+    Ok(Json(results))
+}
+
+/// Archive one election of a bulk archive, auditing it once it is archived so
+/// that the log does not claim elections that failed to be.
+#[allow(clippy::too_many_arguments)]
+async fn bulk_archive_one(
+    election_id: ElectionId,
+    username: &str,
+    elections: &Coll<Election>,
+    ballots: &Coll<AnyBallot>,
+    audit_log: &Coll<AuditLogEntry>,
+    election_finalizers: &ElectionFinalizers,
+    archive_exports: &ArchiveExports,
+    events: &EventBus,
+    request_id: RequestId,
+) -> BulkArchiveOutcome {
+    let exported = match archive(election_id, elections, archive_exports, events, request_id).await
+    {
+        Ok(exported) => exported,
+        Err(err) => {
+            error!("  req{request_id} Failed to archive election {election_id}: {err}");
+            return BulkArchiveOutcome::Failed {
+                error: err.to_string(),
+            };
+        }
+    };
+
+    let audited = AuditLogEntry::new(
+        username.to_string(),
+        "bulk_archive",
+        format!("Election {}", election_id),
+    )
+    .record(audit_log, request_id)
+    .await;
+    if let Err(err) = audited {
+        error!("  req{request_id} Failed to audit archiving election {election_id}: {err}");
+    }
+
+    let finalized = finalize_archived(
+        election_id,
+        exported,
+        ballots,
+        election_finalizers,
+        archive_exports,
+        request_id,
+    )
+    .await;
+    match finalized {
+        Ok(_) => BulkArchiveOutcome::Archived,
+        Err(err) => {
+            warn!("  req{request_id} Archived election {election_id}, but failed to finalize it: {err}");
+            BulkArchiveOutcome::FinalizationPending {
+                error: err.to_string(),
+            }
+        }
+    }
+}
+
+#[get("/elections/<election_id>/finalization")]
+async fn election_finalization(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    request_id: RequestId,
+) -> Result<Json<FinalizationSummary>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(u32_id_filter(election_id), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    let finalization = election
+        .finalization
+        .ok_or_else(|| Error::not_found(format!("Finalization of election {}", election_id)))?;
+    Ok(Json(finalization.into()))
+}
+
+/// An election spec, served as a file to download.
+#[derive(Responder)]
+struct SpecDownload {
+    spec: Json<ElectionSpec>,
+    disposition: Header<'static>,
+}
+
+/// Export an election's configuration in the form accepted by `create_election`,
+/// so that it can be kept under version control and imported elsewhere.
+#[get("/elections/<election_id>/spec")]
+async fn election_spec(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    request_id: RequestId,
+) -> Result<SpecDownload> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    let disposition = Header::new(
+        "Content-Disposition",
+        format!(
+            "attachment; filename=\"election-{}-spec.json\"",
+            election_id
+        ),
+    );
+    Ok(SpecDownload {
+        spec: Json(election.into()),
+        disposition,
+    })
+}
+
+/// Report when each of an election's questions was last exported with a
+/// signed dump, and whether the election may be deleted.
+#[get("/elections/<election_id>/retention")]
+async fn election_retention(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    request_id: RequestId,
+) -> Result<Json<RetentionReport>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(u32_id_filter(election_id), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    Ok(Json(RetentionReport::from(&election)))
+}
+
+/// Permanently delete a draft or archived election.
+///
+/// An archived election's data must first be exported by fetching a signed
+/// dump of every question, unless `acknowledge_no_export` is set, in which
+/// case the acknowledgement is audited.
+#[delete("/elections/<election_id>?<acknowledge_no_export>")]
+#[allow(clippy::too_many_arguments)]
+async fn delete_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    acknowledge_no_export: Option<bool>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    totals: Coll<CandidateTotals>,
+    totals_deltas: Coll<TotalsDelta>,
+    voter_elections: Coll<VoterElection>,
+    counters: Coll<Counter>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    election_finalizers: &State<ElectionFinalizers>,
+    db_client: &State<Client>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    // Get the election.
+    let election = elections
+        .find_one(u32_id_filter(election_id), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    // Check that the election is in a deletable state.
+    if !(election.metadata.state == ElectionState::Draft
+        || election.metadata.state == ElectionState::Archived)
+    {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("Cannot delete election {}", election_id),
+        ));
+    }
+
+    // Check that an archived election's data has been exported, or that the
+    // admin has accepted losing it.
+    if election.metadata.state == ElectionState::Archived && !election.is_exported() {
+        if !acknowledge_no_export.unwrap_or(false) {
+            let report = RetentionReport::from(&election);
+            let unexported = report
+                .questions
+                .iter()
+                .filter(|question| question.last_dump_at.is_none())
+                .map(|question| question.question_id)
+                .collect::<Vec<_>>();
+            return Err(Error::Status(
+                Status::Conflict,
+                format!(
+                    "Election {} has no signed dump of questions {:?}; \
+                    export them or set `acknowledge_no_export`",
+                    election_id, unexported
+                ),
+            ));
+        }
+        let username = acting_admin_username(&token, &admins, request_id).await?;
+        AuditLogEntry::new(
+            username,
+            "delete_without_export",
+            format!("Election {} - {}", election_id, election.metadata.name),
+        )
+        .record(&audit_log, request_id)
+        .await?;
+    }
+
+    // Concurrency: only delete if still in correct state.
+    let filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Draft}, {"state": ElectionState::Archived}],
+    };
+    delete_election_data(
+        &election,
+        filter,
+        &elections,
+        &ballots,
+        &totals,
+        &totals_deltas,
+        &voter_elections,
+        &counters,
+        &board_heads,
+        &board_entries,
+        db_client,
+        request_id,
+    )
+    .await?;
+    // Nothing is left to finalize; this only matters if an archived election's
+    // finalizer had failed and was waiting to retry.
+    election_finalizers.cancel_election(election_id).await;
+    warn!(
+        "  req{} Permanently deleted election {} - {}",
+        request_id, election.id, election.metadata.name
+    );
+
+    Ok(())
+}
+
+/// Atomically delete an election matching the given filter, and all its data.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn delete_election_data(
+    election: &Election,
+    election_filter: Document,
+    elections: &Coll<Election>,
+    ballots: &Coll<AnyBallot>,
+    totals: &Coll<CandidateTotals>,
+    totals_deltas: &Coll<TotalsDelta>,
+    voter_elections: &Coll<VoterElection>,
+    counters: &Coll<Counter>,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    db_client: &Client,
+    request_id: RequestId,
+) -> Result<()> {
+    let election_id = election.id;
+    let mut session = db_client.start_session(None).await?;
+    session
         .with_transaction(
             (
                 election_id,
-                &election,
-                &elections,
-                &ballots,
-                &totals,
-                &voters,
-                &counters,
+                election,
+                &election_filter,
+                elections,
+                ballots,
+                totals,
+                totals_deltas,
+                voter_elections,
+                counters,
+                board_heads,
+                board_entries,
             ),
-            |session, (election_id, election, elections, ballots, totals, voters, counters)| {
+            |session,
+             (
+                election_id,
+                election,
+                election_filter,
+                elections,
+                ballots,
+                totals,
+                totals_deltas,
+                voter_elections,
+                counters,
+                board_heads,
+                board_entries,
+            )| {
                 async move {
                     // Delete the election itself.
-                    // Concurrency: only delete if still in correct state.
-                    let filter = doc! {
-                        "_id": *election_id,
-                        "$or": [{"state": ElectionState::Draft}, {"state": ElectionState::Archived}],
-                    };
                     let result = elections
-                        .delete_one_with_session(filter, None, session)
+                        .delete_one_with_session(election_filter.clone(), None, session)
                         .await?;
                     match result.deleted_count {
                         0 => {
                             // Concurrency error.
                         }
-                        1 => {},
+                        1 => {}
                         _ => unreachable!(),
                     }
                     trace!("  req{request_id} Deleted election {election_id}");
@@ -410,7 +2181,7 @@ async fn delete_election(
                         election_id
                     );
                     let result = totals
-                        .delete_many_with_session(filter, None, session)
+                        .delete_many_with_session(filter.clone(), None, session)
                         .await?;
                     trace!(
                         "  req{} Deleted {} totals for election {}",
@@ -418,22 +2189,33 @@ async fn delete_election(
                         result.deleted_count,
                         election_id
                     );
+                    totals_deltas
+                        .delete_many_with_session(filter.clone(), None, session)
+                        .await?;
+
+                    // Delete the bulletin board.
+                    board_heads
+                        .delete_many_with_session(filter.clone(), None, session)
+                        .await?;
+                    let result = board_entries
+                        .delete_many_with_session(filter.clone(), None, session)
+                        .await?;
+                    trace!(
+                        "  req{} Deleted {} bulletin board events for election {}",
+                        request_id,
+                        result.deleted_count,
+                        election_id
+                    );
 
                     // Remove the election from all voters' allowed questions.
-                    let field_to_remove = format!("allowed_questions.{}", election_id);
-                    let update = doc! {
-                        "$unset": {
-                            &field_to_remove: "",
-                        }
-                    };
-                    let result = voters
-                        .update_many_with_session(doc! {}, update, None, session)
+                    let result = voter_elections
+                        .delete_many_with_session(filter, None, session)
                         .await?;
                     trace!(
                         "  req{} Removed election {} from {} voters",
                         request_id,
                         election_id,
-                        result.modified_count
+                        result.deleted_count
                     );
 
                     // Delete the counters.
@@ -456,360 +2238,4550 @@ async fn delete_election(
                     );
 
                     Ok(())
-                }.boxed()
-            },
+                }
+                .boxed()
+            },
             None,
         )
         .await?;
-    warn!(
-        "  req{} Permanently deleted election {} - {}",
-        request_id, election.id, election.metadata.name
-    );
-
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+/// Let support staff check whether a voter has joined an election, and which
+/// questions they have confirmed ballots for, without acting as that voter.
+/// Voters who do not exist are reported as not having joined, so lookups do
+/// not reveal which numbers belong to voters.
+/// Every lookup is recorded in the audit log, whatever its outcome.
+#[get("/elections/<election_id>/voters/lookup?<sms>")]
+#[allow(clippy::too_many_arguments)]
+async fn lookup_voter(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    sms: &str,
+    elections: Coll<Election>,
+    voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<VoterLookup>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
 
-    use chrono::Duration;
-    use mongodb::{bson::Document, Database};
-    use rand::Rng;
-    use rocket::{
-        http::{ContentType, Status},
-        local::asynchronous::{Client, LocalResponse},
-        serde::json::serde_json,
-        tokio,
+    // Record the lookup before anything else, so that it is audited whatever the outcome.
+    let sms = Sms::parse_in(sms, config.default_sms_region());
+    let number = match &sms {
+        Ok(sms) => format!("SMS {}", sms.redacted()),
+        Err(_) => "invalid SMS".to_string(),
     };
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username,
+        "voter_lookup",
+        format!("Election {}, {}", election_id, number),
+    )
+    .record(&audit_log, request_id)
+    .await?;
 
-    use crate::{
-        config::Config,
-        model::{
-            api::{
-                election::{ElectionSpec, QuestionSpec},
-                sms::Sms,
-            },
-            common::{
-                allowed_questions::AllowedQuestions,
-                ballot::{Audited, Confirmed, Unconfirmed},
-            },
-            db::{
-                admin::DEFAULT_ADMIN_USERNAME,
-                ballot::{Ballot, BallotCore},
-                candidate_totals::NewCandidateTotals,
-                election::ElectionMetadata,
-                voter::NewVoter,
-            },
-            mongodb::{Id, MongoCollection},
+    let sms = sms
+        .map_err(|_| Error::Status(Status::BadRequest, "Invalid SMS number format".to_string()))?;
+
+    // Check the election exists.
+    elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    // An unknown voter is reported as not having joined, like a known one, and
+    // is still looked for in the election, so that neither the response nor
+    // its timing reveals whether the number belongs to any voter.
+    let sms_hmac = sms.into_hmac(config);
+    let voter_id = voters
+        .find_one(
+            doc! { "sms_hmac": sms_hmac.to_bytestring() },
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .map_or_else(Id::new, |voter| voter.id);
+
+    // Only reveal the voter's participation in this election.
+    let allowed_questions = VoterElection::allowed_questions(
+        &voter_elections,
+        voter_id,
+        election_id,
+        FindOneOptions::for_request(request_id),
+    )
+    .await?;
+    Ok(Json(VoterLookup {
+        joined: allowed_questions.is_some(),
+        allowed_questions: allowed_questions.unwrap_or_default(),
+    }))
+}
+
+/// Freeze a voter, barring them from joining elections and casting, confirming
+/// or auditing ballots until they are unfrozen. Ballots they have already
+/// confirmed are left alone.
+/// The voter is identified by their SMS number, which is only stored hashed.
+#[post("/voters/<sms>/freeze", data = "<request>", format = "json")]
+#[allow(clippy::too_many_arguments)]
+async fn freeze_voter(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    sms: &str,
+    request: Json<VoterFreezeRequest>,
+    voters: Coll<Voter>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let freeze = VoterFreeze {
+        time: Utc::now(),
+        reason: request.0.reason,
+    };
+    set_frozen(
+        sms,
+        Some(freeze),
+        &token,
+        &voters,
+        &admins,
+        &audit_log,
+        config,
+        request_id,
+    )
+    .await
+}
+
+/// Unfreeze a voter, letting them vote again.
+#[post("/voters/<sms>/unfreeze")]
+#[allow(clippy::too_many_arguments)]
+async fn unfreeze_voter(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    sms: &str,
+    voters: Coll<Voter>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<()> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    set_frozen(
+        sms, None, &token, &voters, &admins, &audit_log, config, request_id,
+    )
+    .await
+}
+
+/// Freeze the voter with the given SMS number if given a freeze, or unfreeze
+/// them if not, recording the change in the audit log.
+#[allow(clippy::too_many_arguments)]
+async fn set_frozen(
+    sms: &str,
+    freeze: Option<VoterFreeze>,
+    token: &AuthToken<Admin>,
+    voters: &Coll<Voter>,
+    admins: &Coll<Admin>,
+    audit_log: &Coll<AuditLogEntry>,
+    config: &Config,
+    request_id: RequestId,
+) -> Result<()> {
+    let sms = Sms::parse_in(&sms, config.default_sms_region())
+        .map_err(|_| Error::Status(Status::BadRequest, "Invalid SMS number format".to_string()))?;
+
+    // Record the change before making it, so that it is audited whatever the outcome.
+    // The reason is free text, so is kept off the audit log in case it names the voter.
+    let action = if freeze.is_some() {
+        "voter_freeze"
+    } else {
+        "voter_unfreeze"
+    };
+    let username = acting_admin_username(token, admins, request_id).await?;
+    AuditLogEntry::new(username, action, format!("SMS {}", sms.redacted()))
+        .record(audit_log, request_id)
+        .await?;
+
+    let update = match &freeze {
+        Some(freeze) => {
+            let freeze = mongodb::bson::to_bson(freeze).unwrap(); // Cannot fail.
+            doc! { "$set": { "frozen": true, "freeze": freeze } }
+        }
+        None => doc! {
+            "$set": { "frozen": false },
+            "$unset": { "freeze": "" },
         },
     };
+    let sms_hmac = sms.into_hmac(config);
+    let result = voters
+        .update_one(
+            doc! { "sms_hmac": sms_hmac.to_bytestring() },
+            update,
+            UpdateOptions::for_request(request_id),
+        )
+        .await?;
+    if result.matched_count == 0 {
+        return Err(Error::not_found("Voter".to_string()));
+    }
+    match freeze {
+        Some(_) => warn!("  req{request_id} Froze voter"),
+        None => warn!("  req{request_id} Unfroze voter"),
+    }
+    Ok(())
+}
 
-    use super::*;
+/// Import voters from a CSV electoral roll, joining each to the election with
+/// the groups given for them.
+/// The roll must have a header row naming the `sms`, `electorate` and `group`
+/// columns. Each row gives one group of a voter, so a voter in several groups
+/// has several rows, anywhere in the roll; a voter in no groups has one row
+/// with the electorate and group left blank.
+/// The roll is read whole, up to `max_voter_import_bytes`, so one too large
+/// is refused before any voter is imported.
+/// Voters who have already joined the election are left alone, as are voters
+/// with any rejected rows, since they would otherwise get the wrong questions.
+/// Imported voters' groups are kept, so their questions are worked out again
+/// if the election is later edited, as for voters who joined themselves.
+/// Elections requiring consent are refused, since imported voters would never
+/// accept it.
+#[post(
+    "/elections/<election_id>/voters/import",
+    data = "<roll>",
+    format = "text/csv"
+)]
+#[allow(clippy::too_many_arguments)]
+async fn import_voters(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    roll: Data<'_>,
+    elections: Coll<Election>,
+    voters: Coll<Voter>,
+    new_voters: Coll<NewVoter>,
+    voter_elections: Coll<VoterElection>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<VoterImportReport>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    if election.metadata.state == ElectionState::Archived {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("Election {} is archived; cannot import voters", election_id),
+        ));
+    }
+    // Voters must accept the consent text themselves, which they do on joining.
+    if election.metadata.consent_text.is_some() {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!(
+                "Election {} requires voters' consent; cannot import voters",
+                election_id
+            ),
+        ));
+    }
 
-    #[backend_test(admin)]
-    async fn create_delete_admin(client: Client, db: Database) {
-        // Create admin
-        create_admin(&client, &AdminCredentials::example2()).await;
+    // Read the roll, refusing any that are too big.
+    let limit = config.max_voter_import_bytes();
+    let roll = roll.open(limit.bytes()).into_bytes().await.map_err(|e| {
+        Error::Status(
+            Status::BadRequest,
+            format!("Failed to read electoral roll: {}", e),
+        )
+    })?;
+    if !roll.is_complete() {
+        return Err(Error::Status(
+            Status::PayloadTooLarge,
+            format!(
+                "Electoral roll is too large: the limit is {limit} bytes (`max_voter_import_bytes`)"
+            ),
+        ));
+    }
 
-        // Ensure the admin has been inserted
-        let admins = Coll::<Admin>::from_db(&db);
-        let with_username = doc! { "username": &NewAdmin::example2().username };
-        let inserted_admin = admins
-            .find_one(with_username.clone(), None)
-            .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(NewAdmin::example2().username, inserted_admin.username);
+    // Find the columns.
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(roll.as_slice());
+    let headers = reader.headers().map_err(|e| {
+        Error::Status(
+            Status::UnprocessableEntity,
+            format!("Invalid electoral roll header: {}", e),
+        )
+    })?;
+    let column = |name: &str| {
+        headers.iter().position(|h| h == name).ok_or_else(|| {
+            Error::Status(
+                Status::UnprocessableEntity,
+                format!("Electoral roll has no `{}` column", name),
+            )
+        })
+    };
+    let columns = [column("sms")?, column("electorate")?, column("group")?];
 
-        // Delete the admin.
-        let count = admins.count_documents(None, None).await.unwrap();
-        assert_eq!(count, 3); // Default admin, test admin, new admin.
+    // Record the import before performing it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username,
+        "voter_import",
+        format!("Election {}, {} bytes", election_id, roll.len()),
+    )
+    .record(&audit_log, request_id)
+    .await?;
+
+    // Gather each voter's rows, which need not be consecutive, then import
+    // the voters. The roll is already held whole, within the size limit, so
+    // this holds little more.
+    let mut importer = VoterImporter::new(
+        &election,
+        &voters,
+        &new_voters,
+        &voter_elections,
+        config,
+        request_id,
+    );
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line());
+                importer.reject(line, e.to_string());
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let [sms, electorate, group] = columns.map(|i| record.get(i).unwrap_or_default());
+        importer.row(line, sms, electorate, group);
+    }
+    let report = importer.finish().await?;
+
+    // Imported voters count as having joined.
+    let joined = report.created + report.updated;
+    if joined > 0 {
+        elections
+            .update_one(
+                u32_id_filter(election_id),
+                doc! { "$inc": { "joined_count": joined as i64 } },
+                UpdateOptions::for_request(request_id),
+            )
+            .await?;
+    }
+    warn!(
+        "  req{} Imported voters into election {}: {} created, {} updated, {} skipped, {} rows rejected",
+        request_id, election_id, report.created, report.updated, report.skipped, report.rejected
+    );
+
+    Ok(Json(report))
+}
+
+/// Imports voters from the rows of an electoral roll, gathering each voter's
+/// rows wherever they appear before importing any of them.
+struct VoterImporter<'a> {
+    election: &'a Election,
+    voters: &'a Coll<Voter>,
+    new_voters: &'a Coll<NewVoter>,
+    voter_elections: &'a Coll<VoterElection>,
+    config: &'a Config,
+    request_id: RequestId,
+    /// The voters on the roll, in the order they first appear.
+    roll: Vec<RollVoter>,
+    /// The position in `roll` of each voter, by the E.164 form of their number.
+    positions: HashMap<String, usize>,
+    report: VoterImportReport,
+}
+
+/// A voter on an electoral roll, the groups they have been given so far, and
+/// whether any of their rows were rejected.
+struct RollVoter {
+    sms: Sms,
+    joins: Joins,
+    rejected: bool,
+}
+
+impl<'a> VoterImporter<'a> {
+    fn new(
+        election: &'a Election,
+        voters: &'a Coll<Voter>,
+        new_voters: &'a Coll<NewVoter>,
+        voter_elections: &'a Coll<VoterElection>,
+        config: &'a Config,
+        request_id: RequestId,
+    ) -> Self {
+        Self {
+            election,
+            voters,
+            new_voters,
+            voter_elections,
+            config,
+            request_id,
+            roll: Vec::new(),
+            positions: HashMap::new(),
+            report: VoterImportReport::default(),
+        }
+    }
+
+    /// Add a row to its voter, who may already have had rows anywhere earlier
+    /// in the roll.
+    fn row(&mut self, line: u64, sms: &str, electorate: &str, group: &str) {
+        let sms = match Sms::parse_in(sms, self.config.default_sms_region()) {
+            Ok(sms) if sms.can_receive_sms() => sms,
+            Ok(_) => {
+                self.reject(line, "SMS number cannot receive text messages".to_string());
+                return;
+            }
+            Err(e) => {
+                self.reject(line, format!("Invalid SMS number: {}", e));
+                return;
+            }
+        };
+        let roll = &mut self.roll;
+        let position = *self.positions.entry(sms.e164()).or_insert_with(|| {
+            roll.push(RollVoter {
+                sms,
+                joins: Joins::new(),
+                rejected: false,
+            });
+            roll.len() - 1
+        });
+
+        if let Err(e) = self.check_group(&self.roll[position], electorate, group) {
+            self.reject(line, e);
+            self.roll[position].rejected = true;
+            return;
+        }
+        if !electorate.is_empty() {
+            self.roll[position]
+                .joins
+                .entry(electorate.to_string())
+                .or_default()
+                .insert(group.to_string());
+        }
+    }
+
+    /// Check that the voter may be given the group, explaining why not.
+    fn check_group(
+        &self,
+        voter: &RollVoter,
+        electorate_name: &str,
+        group: &str,
+    ) -> std::result::Result<(), String> {
+        match (electorate_name.is_empty(), group.is_empty()) {
+            (true, true) => return Ok(()),
+            (false, false) => {}
+            _ => return Err("Electorate and group must be given together".to_string()),
+        }
+        let electorate = self
+            .election
+            .electorates
+            .get(electorate_name)
+            .ok_or_else(|| format!("Unknown electorate '{}'", electorate_name))?;
+        if !electorate.groups.contains(group) {
+            return Err(format!(
+                "Unknown group '{}' in electorate '{}'",
+                group, electorate_name
+            ));
+        }
+        let joined = voter.joins.get(electorate_name);
+        if electorate.is_mutex && joined.map_or(false, |groups| !groups.contains(group)) {
+            return Err(format!(
+                "Cannot join more than one group in mutex electorate {}",
+                electorate_name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record a rejected row.
+    fn reject(&mut self, line: u64, error: String) {
+        debug!(
+            "  req{} Rejected electoral roll line {}: {}",
+            self.request_id, line, error
+        );
+        self.report.rejected += 1;
+        if self.report.errors.len() < MAX_REPORTED_IMPORT_ERRORS {
+            self.report.errors.push(ImportRowError { line, error });
+        }
+    }
+
+    /// Import a voter.
+    async fn import(&mut self, sms: Sms, joins: Joins) -> Result<()> {
+        let allowed_questions = AllowedQuestions::for_joins(self.election, &joins)?;
+        let voter = NewVoter::new(sms, self.config);
+
+        // Find the voter, or create them if they are new.
+        let filter = doc! { "sms_hmac": voter.sms_hmac.to_bytestring() };
+        let existing = self
+            .voters
+            .find_one(filter.clone(), FindOneOptions::for_request(self.request_id))
+            .await?;
+        let (voter_id, created) = match existing {
+            Some(existing) => (existing.id, false),
+            None => {
+                let result = self.new_voters.insert_one(&voter, None).await;
+                if is_duplicate_key_error(result.as_ref()) {
+                    // They were created concurrently, so use them.
+                    let existing = self
+                        .voters
+                        .find_one(filter, FindOneOptions::for_request(self.request_id))
+                        .await?
+                        .ok_or_else(|| Error::not_found("Voter".to_string()))?;
+                    (existing.id, false)
+                } else {
+                    let id = result?
+                        .inserted_id
+                        .as_object_id()
+                        .unwrap() // Safe because the ID comes directly from the database.
+                        .into();
+                    (id, true)
+                }
+            }
+        };
+
+        // Join them to the election, unless they already have.
+        let voter_election = VoterElection {
+            voter_id,
+            election_id: self.election.id,
+            allowed_questions,
+            joins: Some(joins),
+            consent: None,
+        };
+        let result = self.voter_elections.insert_one(&voter_election, None).await;
+        if is_duplicate_key_error(result.as_ref()) {
+            self.report.skipped += 1;
+        } else {
+            result?;
+            if created {
+                self.report.created += 1;
+            } else {
+                self.report.updated += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Import every voter none of whose rows were rejected, and report on the
+    /// whole import.
+    async fn finish(mut self) -> Result<VoterImportReport> {
+        for voter in std::mem::take(&mut self.roll) {
+            if !voter.rejected {
+                self.import(voter.sms, voter.joins).await?;
+            }
+        }
+        Ok(self.report)
+    }
+}
+
+/// Compare each question's ballot counter with the ballots actually stored, to
+/// diagnose casting failures caused by stale counters, e.g. after a restore.
+#[get("/elections/<election_id>/counters")]
+async fn election_counters(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    counters: Coll<Counter>,
+    request_id: RequestId,
+) -> Result<Json<Vec<CounterStatus>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    let statuses = counter_statuses(&election, &ballots, &counters, request_id).await?;
+    Ok(Json(statuses))
+}
+
+/// Longest window an activity report may cover.
+const MAX_ACTIVITY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Most buckets an activity report may be split into.
+const MAX_ACTIVITY_BUCKETS: u64 = 720;
+/// The event counted when a ballot is cast, alongside its state changes.
+const CAST_EVENT: &str = "cast";
+
+/// Count the ballots cast, audited and confirmed in each bucket (default
+/// `30s`) of a recent window (default `5m`), along with each question's
+/// ballot counter, for live monitoring of an election while voting.
+///
+/// Buckets are aligned to multiples of their length since the epoch, and the
+/// window ends with the bucket containing the time of the request.
+#[get("/elections/<election_id>/activity?<window>&<bucket>")]
+#[allow(clippy::too_many_arguments)]
+async fn election_activity(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    window: Option<&str>,
+    bucket: Option<&str>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    counters: Coll<Counter>,
+    request_id: RequestId,
+) -> Result<Json<ActivityReport>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let window = parse_span("window", window.unwrap_or("5m"))?;
+    let bucket = parse_span("bucket", bucket.unwrap_or("30s"))?;
+    if window > MAX_ACTIVITY_WINDOW {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!(
+                "Window too long: at most {}h can be reported",
+                MAX_ACTIVITY_WINDOW.as_secs() / (60 * 60)
+            ),
+        ));
+    }
+    if window.as_secs() % bucket.as_secs() != 0 {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Window must be a whole number of buckets".to_string(),
+        ));
+    }
+    let bucket_count = window.as_secs() / bucket.as_secs();
+    if bucket_count > MAX_ACTIVITY_BUCKETS {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("Too many buckets: at most {MAX_ACTIVITY_BUCKETS} can be reported"),
+        ));
+    }
+
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    let bucket_ms = bucket.as_millis() as i64;
+    let end_ms = (Utc::now().timestamp_millis() / bucket_ms + 1) * bucket_ms;
+    let start_ms = end_ms - window.as_millis() as i64;
+    let mut buckets = (0..bucket_count as i64)
+        .map(|i| ActivityBucket {
+            start: DateTime::from_millis(start_ms + i * bucket_ms).to_chrono(),
+            cast: 0,
+            audited: 0,
+            confirmed: 0,
+        })
+        .collect::<Vec<_>>();
+    let pipeline = activity_pipeline(
+        election_id,
+        DateTime::from_millis(start_ms),
+        DateTime::from_millis(end_ms),
+        bucket_ms,
+    );
+    let mut cursor = ballots
+        .aggregate(pipeline, AggregateOptions::for_request(request_id))
+        .await?
+        .with_type::<BucketEvents>();
+    while let Some(group) = cursor.try_next().await? {
+        let Some(bucket) = buckets.get_mut(group.key.bucket as usize) else {
+            continue;
+        };
+        let count = match group.key.event.as_bytes() {
+            event if event == CAST_EVENT.as_bytes() => &mut bucket.cast,
+            event if event == Audited.as_ref() => &mut bucket.audited,
+            event if event == Confirmed.as_ref() => &mut bucket.confirmed,
+            _ => {
+                warn!(
+                    "  req{} Not counting {} ballots changed to unrecognised state '{}'",
+                    request_id, group.count, group.key.event
+                );
+                continue;
+            }
+        };
+        *count = group.count;
+    }
+
+    let counters = counter_statuses(&election, &ballots, &counters, request_id).await?;
+    Ok(Json(ActivityReport {
+        window_start: DateTime::from_millis(start_ms).to_chrono(),
+        window_end: DateTime::from_millis(end_ms).to_chrono(),
+        bucket_secs: bucket.as_secs(),
+        buckets,
+        counters,
+    }))
+}
+
+/// Compare each question's ballot counter with the ballots actually stored,
+/// by ascending question ID.
+async fn counter_statuses(
+    election: &Election,
+    ballots: &Coll<AnyBallot>,
+    counters: &Coll<Counter>,
+    request_id: RequestId,
+) -> Result<Vec<CounterStatus>> {
+    let max_ballot_ids = ballots
+        .aggregate(
+            max_ballot_id_pipeline(election.id),
+            AggregateOptions::for_request(request_id),
+        )
+        .await?
+        .with_type::<MaxBallotId>()
+        .map_ok(|max| (max.question_id, max.ballot_id))
+        .try_collect::<HashMap<_, _>>()
+        .await?;
+    let mut question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+    question_ids.sort_unstable();
+    let mut statuses = Vec::with_capacity(question_ids.len());
+    for question_id in question_ids {
+        let counter = counters
+            .find_one(
+                doc! { "_id": ballot_counter_id(election.id, question_id) },
+                FindOneOptions::for_request(request_id),
+            )
+            .await?;
+        statuses.push(CounterStatus::new(
+            question_id,
+            counter.map(|counter| counter.next),
+            max_ballot_ids.get(&question_id).copied(),
+        ));
+    }
+    Ok(statuses)
+}
+
+/// Check whether an election is healthy in one call: its state, finalizer,
+/// ballot counters, ballots and candidate totals.
+#[get("/elections/<election_id>/health")]
+#[allow(clippy::too_many_arguments)]
+async fn election_health(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    counters: Coll<Counter>,
+    candidate_totals: Coll<CandidateTotals>,
+    election_finalizers: &State<ElectionFinalizers>,
+    request_id: RequestId,
+) -> Result<Json<ElectionHealth>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    let statuses = counter_statuses(&election, &ballots, &counters, request_id).await?;
+    let mut ballot_stats = election_ballot_stats(&ballots, election_id, None, request_id).await?;
+    let existing_totals = candidate_totals
+        .find(
+            doc! { "election_id": election_id },
+            FindOptions::for_request(request_id),
+        )
+        .await?
+        .map_ok(|total| (total.question_id, total.candidate_name.clone()))
+        .try_collect::<HashSet<_>>()
+        .await?;
+    let questions = statuses
+        .into_iter()
+        .map(|counter| {
+            let question = &election.questions[&counter.question_id];
+            let missing_totals = question
+                .candidates
+                .iter()
+                .filter(|candidate| {
+                    !existing_totals.contains(&(question.id, candidate.to_string()))
+                })
+                .cloned()
+                .collect();
+            QuestionHealth {
+                ballots: ballot_stats.remove(&question.id).unwrap_or_default(),
+                counter,
+                missing_totals,
+            }
+        })
+        .collect();
+
+    let finalizer_scheduled = election_finalizers.has_finalizer(election_id).await;
+    let health = ElectionHealth::new(election, finalizer_scheduled, questions);
+    if !health.healthy {
+        debug!(
+            "  req{} Election {} is unhealthy: {}",
+            request_id,
+            election_id,
+            health.problems.join("; ")
+        );
+    }
+    Ok(Json(health))
+}
+
+/// Count the names written in on a question's confirmed ballots for its
+/// write-in candidate, most common first. Names are counted exactly as they
+/// were written, apart from surrounding whitespace.
+#[get("/elections/<election_id>/<question_id>/write-ins")]
+async fn write_ins(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    question_id: QuestionId,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    request_id: RequestId,
+) -> Result<Json<Vec<WriteInCount>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+    let question = election.questions.get(&question_id).ok_or_else(|| {
+        Error::not_found(format!(
+            "Question {} in election {}",
+            question_id, election_id
+        ))
+    })?;
+    if !question.allow_write_in {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!(
+                "Question {} in election {} does not allow write-ins",
+                question_id, election_id
+            ),
+        ));
+    }
+
+    let pipeline = [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+                "question_id": question_id,
+                "state": Confirmed,
+                "write_in_name": {"$exists": true},
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": "$write_in_name",
+                "count": {"$sum": 1},
+            }
+        },
+        doc! { "$project": { "_id": 0, "name": "$_id", "count": 1 } },
+        doc! { "$sort": { "count": -1, "name": 1 } },
+    ];
+    let counts = ballots
+        .aggregate(pipeline, AggregateOptions::for_request(request_id))
+        .await?
+        .with_type::<WriteInCount>()
+        .try_collect::<Vec<_>>()
+        .await?;
+    debug!(
+        "  req{} Found {} distinct write-ins for question {} of election {}",
+        request_id,
+        counts.len(),
+        question_id,
+        election_id
+    );
+    Ok(Json(counts))
+}
+
+/// Reset each question's ballot counter to just past the highest ballot ID
+/// stored for it, creating any missing counters.
+///
+/// Refused while the election is receiving votes unless `force` is set, since
+/// ballots cast during the repair could otherwise be given duplicate IDs.
+#[post("/elections/<election_id>/counters/repair?<force>")]
+#[allow(clippy::too_many_arguments)]
+async fn repair_counters(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    force: Option<bool>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    counters: Coll<Counter>,
+    admins: Coll<Admin>,
+    audit_log: Coll<AuditLogEntry>,
+    db_client: &State<Client>,
+    request_id: RequestId,
+) -> Result<Json<Vec<CounterStatus>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let force = force.unwrap_or(false);
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election {}", election_id)))?;
+
+    // Check the election is not receiving votes.
+    let now = Utc::now();
+    let receiving_votes = election.metadata.state == ElectionState::Published
+        && !election.metadata.suspended
+        && election.metadata.start_time <= now
+        && now < election.metadata.end_time;
+    if receiving_votes && !force {
+        return Err(Error::Status(
+            Status::Conflict,
+            format!(
+                "Election {} is receiving votes; suspend it or use force",
+                election_id
+            ),
+        ));
+    }
+
+    // Record the repair before performing it, so that it is audited whatever the outcome.
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+    AuditLogEntry::new(
+        username,
+        "counter_repair",
+        format!("Election {}, forced: {}", election_id, force),
+    )
+    .record(&audit_log, request_id)
+    .await?;
+
+    let mut question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+    question_ids.sort_unstable();
+    let mut session = db_client.start_session(None).await?;
+    let statuses = session
+        .with_transaction(
+            (election_id, &question_ids, &ballots, &counters),
+            |session, (election_id, question_ids, ballots, counters)| {
+                async move {
+                    let max_ballot_ids = ballots
+                        .aggregate_with_session(max_ballot_id_pipeline(*election_id), None, session)
+                        .await?
+                        .with_type::<MaxBallotId>()
+                        .stream(session)
+                        .map_ok(|max| (max.question_id, max.ballot_id))
+                        .try_collect::<HashMap<_, _>>()
+                        .await?;
+
+                    let mut statuses = Vec::with_capacity(question_ids.len());
+                    for question_id in question_ids.iter() {
+                        let max_ballot_id = max_ballot_ids.get(question_id).copied();
+                        let next = max_ballot_id.map_or(1, |max| max + 1);
+                        let update = doc! {
+                            "$set": {
+                                "next": next,
+                            }
+                        };
+                        let options = UpdateOptions::builder().upsert(true).build();
+                        counters
+                            .update_one_with_session(
+                                doc! { "_id": ballot_counter_id(*election_id, *question_id) },
+                                update,
+                                options,
+                                session,
+                            )
+                            .await?;
+                        statuses.push(CounterStatus::new(*question_id, Some(next), max_ballot_id));
+                    }
+                    Ok(statuses)
+                }
+                .boxed()
+            },
+            None,
+        )
+        .await?;
+
+    warn!("  req{request_id} Repaired ballot counters of election {election_id}");
+    Ok(Json(statuses))
+}
+
+/// The highest ballot ID stored for a single question.
+#[derive(Deserialize)]
+struct MaxBallotId {
+    #[serde(rename = "_id")]
+    question_id: QuestionId,
+    ballot_id: BallotId,
+}
+
+/// Find the highest ballot ID stored for each question of the given election.
+fn max_ballot_id_pipeline(election_id: ElectionId) -> [Document; 2] {
+    [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": "$question_id",
+                "ballot_id": {"$max": "$ballot_id"},
+            }
+        },
+    ]
+}
+
+/// Events of a single kind within a single bucket, as produced by
+/// [`activity_pipeline`].
+#[derive(Deserialize)]
+struct BucketEvents {
+    #[serde(rename = "_id")]
+    key: BucketEventKey,
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct BucketEventKey {
+    bucket: u64,
+    event: String,
+}
+
+/// Count the ballots of the given election cast, and changed to each state,
+/// in each bucket of `bucket_ms` milliseconds from `start` until `end`.
+/// Ballots are cast at their creation time, and change state at their
+/// `state_changed_at`, which is absent for unconfirmed ballots and ballots
+/// which changed state before it was recorded.
+fn activity_pipeline(
+    election_id: ElectionId,
+    start: DateTime,
+    end: DateTime,
+    bucket_ms: i64,
+) -> [Document; 5] {
+    [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+                "$or": [
+                    {"creation_time": {"$gte": start}},
+                    {"state_changed_at": {"$gte": start}},
+                ],
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "events": [
+                    {"event": CAST_EVENT, "time": "$creation_time"},
+                    {"event": "$state", "time": "$state_changed_at"},
+                ],
+            }
+        },
+        doc! {
+            "$unwind": "$events"
+        },
+        doc! {
+            "$match": {
+                "events.time": {"$gte": start, "$lt": end},
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": {
+                    "bucket": {
+                        "$toLong": {
+                            "$floor": {
+                                "$divide": [{"$subtract": ["$events.time", start]}, bucket_ms],
+                            }
+                        }
+                    },
+                    "event": "$events.event",
+                },
+                "count": {"$sum": 1},
+            }
+        },
+    ]
+}
+
+/// Look up the username of the admin making the request.
+pub(super) async fn acting_admin_username(
+    token: &AuthToken<Admin>,
+    admins: &Coll<Admin>,
+    request_id: RequestId,
+) -> Result<String> {
+    let admin = admins
+        .find_one(token.id.as_doc(), FindOneOptions::for_request(request_id))
+        .await?
+        .ok_or_else(|| {
+            Error::Status(
+                Status::Unauthorized,
+                format!("Admin {} no longer exists", token.id),
+            )
+        })?;
+    Ok(admin.admin.username)
+}
+
+/// Archive a draft or published election, run its finalizer, and start
+/// uploading its signed dumps if it ran and archive exports are enabled.
+/// Returns the unconfirmed ballots the finalizer was to audit.
+async fn archive_and_finalize(
+    election_id: ElectionId,
+    elections: &Coll<Election>,
+    ballots: &Coll<AnyBallot>,
+    election_finalizers: &ElectionFinalizers,
+    archive_exports: &ArchiveExports,
+    events: &EventBus,
+    request_id: RequestId,
+) -> Result<FinalizePreview> {
+    let exported = archive(election_id, elections, archive_exports, events, request_id).await?;
+    finalize_archived(
+        election_id,
+        exported,
+        ballots,
+        election_finalizers,
+        archive_exports,
+        request_id,
+    )
+    .await
+}
+
+/// Archive a draft or published election, without running its finalizer.
+/// Returns whether its signed dumps are to be uploaded once it is finalized.
+async fn archive(
+    election_id: ElectionId,
+    elections: &Coll<Election>,
+    archive_exports: &ArchiveExports,
+    events: &EventBus,
+    request_id: RequestId,
+) -> Result<bool> {
+    // Update the state.
+    let filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Draft}, {"state": ElectionState::Published}],
+    };
+    let update = doc! {
+        "$set": {
+            "state": ElectionState::Archived,
+            "archived_at": DateTime::now(),
+        }
+    };
+    let previous = elections
+        .find_one_and_update(filter, update, None)
+        .await?
+        .ok_or_else(|| {
+            Error::Status(
+                Status::BadRequest,
+                format!(
+                    "Election {} doesn't exist or is already archived.",
+                    election_id
+                ),
+            )
+        })?;
+
+    // Only elections that ran have results worth keeping. Marking the upload
+    // as pending now means it is retried even if finalizing fails below.
+    let exported = archive_exports.is_enabled()
+        && previous.metadata.state == ElectionState::Published
+        && !previous.metadata.rehearsal;
+    if exported {
+        let update = doc! {
+            "$set": { "archive_export": ArchiveExport::default() },
+        };
+        elections
+            .update_one(u32_id_filter(election_id), update, None)
+            .await?;
+    }
+
+    events.emit(
+        DomainEvent::ElectionArchived { election_id },
+        Some(request_id),
+    );
+    Ok(exported)
+}
+
+/// Run the finalizer of a just-archived election, and start uploading its
+/// signed dumps if `exported` and it ran. If it fails, it is retried later.
+/// Returns the unconfirmed ballots the finalizer was to audit.
+async fn finalize_archived(
+    election_id: ElectionId,
+    exported: bool,
+    ballots: &Coll<AnyBallot>,
+    election_finalizers: &ElectionFinalizers,
+    archive_exports: &ArchiveExports,
+    request_id: RequestId,
+) -> Result<FinalizePreview> {
+    // Note what the finalizer is about to audit, now that no more ballots
+    // can be cast, then run it.
+    let preview = preview_finalization(
+        election_id,
+        ballots,
+        Utc::now(),
+        AggregateOptions::for_request(request_id),
+    )
+    .await?;
+    election_finalizers.finalize_election(election_id).await?;
+
+    // Upload the signed dumps without holding up the response; any failure
+    // is recorded on the election and retried.
+    if exported {
+        archive_exports.export_in_background(election_id, request_id);
+    }
+
+    Ok(preview)
+}
+
+/// Suspend or resume voting in a published election.
+/// This does not affect the election's timing or finalizer.
+async fn set_suspended(
+    election_id: ElectionId,
+    suspended: bool,
+    elections: &Coll<Election>,
+) -> Result<()> {
+    let filter = doc! {
+        "_id": election_id,
+        "state": ElectionState::Published,
+        // Concurrency: only match if not already in the target state.
+        "suspended": { "$ne": suspended },
+    };
+    let update = doc! {
+        "$set": {
+            "suspended": suspended,
+        }
+    };
+    let result = elections.update_one(filter, update, None).await?;
+    if result.modified_count != 1 {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!(
+                "Election {} doesn't exist, isn't published, or is already {}.",
+                election_id,
+                if suspended { "suspended" } else { "running" }
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
+    use dre_ip::DreipScalar;
+    use mongodb::{bson::Document, Database};
+    use rand::Rng;
+    use rocket::{
+        http::{ContentType, Status},
+        local::asynchronous::{Client, LocalResponse},
+        serde::json::serde_json,
+        tokio,
+    };
+
+    use crate::{
+        config::Config,
+        model::{
+            api::{
+                auth::{VoterChallengeRequest, VoterVerifyRequest, WithCsrf, AUTH_TOKEN_COOKIE},
+                ballot::BallotSpec,
+                election::{
+                    ElectionResults, ElectionSpec, ElectionSummary, ElectionTiming, QuestionSpec,
+                    MAX_BRANDING_SIZE,
+                },
+                finalize_preview::UnconfirmedByAge,
+                otp::{Challenge, CHALLENGE_COOKIE},
+                pagination::{Paginated, PaginationRequest, SortOrder},
+                receipt::Receipt,
+                sms::Sms,
+                task::TaskState,
+            },
+            common::{
+                allowed_questions::{AllowedQuestions, Joins},
+                ballot::{Audited, Confirmed, Unconfirmed},
+                election::{Translations, WRITE_IN_CANDIDATE},
+            },
+            db::{
+                admin::{DEFAULT_ADMIN_PASSWORD, DEFAULT_ADMIN_USERNAME},
+                ballot::{Ballot, BallotCore},
+                candidate_totals::NewCandidateTotals,
+                election::{ElectionMetadata, EndWarningSettings},
+                rate_limit::{hash_key, RateLimitObserver},
+                voter::NewVoter,
+                voter_election::ConsentAcceptance,
+            },
+            mongodb::{Id, MongoCollection},
+        },
+    };
+
+    use super::*;
+
+    #[backend_test(admin)]
+    async fn create_delete_admin(client: Client, db: Database) {
+        // Create admin
+        create_admin(&client, &AdminCredentials::example2()).await;
+
+        // Ensure the admin has been inserted
+        let admins = Coll::<Admin>::from_db(&db);
+        let with_username = doc! { "username": &NewAdmin::example2().username };
+        let inserted_admin = admins
+            .find_one(with_username.clone(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(NewAdmin::example2().username, inserted_admin.username);
+
+        // Delete the admin.
+        let count = admins.count_documents(None, None).await.unwrap();
+        assert_eq!(count, 3); // Default admin, test admin, new admin.
+        let response = client
+            .delete(uri!(delete_admin(AdminCredentials::example2().username)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+
+        // Ensure the admin has been deleted.
+        let count = admins.count_documents(None, None).await.unwrap();
+        assert_eq!(count, 2);
+        let expected = vec![
+            DEFAULT_ADMIN_USERNAME.to_string(),
+            AdminCredentials::example1().username,
+        ];
+        let remaining_admins: Vec<String> = admins
+            .find(None, None)
+            .await
+            .unwrap()
+            .map_ok(|a| a.admin.username)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(expected, remaining_admins);
+    }
+
+    #[backend_test(admin)]
+    async fn bad_create_admin(client: Client, db: Database) {
+        // Try empty username.
+        let credentials = AdminCredentials {
+            username: "".to_string(),
+            password: "foo".into(),
+        };
+        create_admin_expect_status(&client, &credentials, Status::BadRequest).await;
+
+        // Try empty password.
+        let credentials = AdminCredentials {
+            username: "foo".to_string(),
+            password: "".into(),
+        };
+        create_admin_expect_status(&client, &credentials, Status::BadRequest).await;
+
+        // Try empty both.
+        create_admin_expect_status(&client, &AdminCredentials::empty(), Status::BadRequest).await;
+
+        // Try duplicate username.
+        create_admin_expect_status(&client, &AdminCredentials::example1(), Status::BadRequest)
+            .await;
+
+        // Ensure no admins were created.
+        let num_admins = count_matches::<Admin>(&db, doc! {}).await;
+        assert_eq!(num_admins, 2); // Default admin and test admin.
+    }
+
+    #[backend_test(admin)]
+    async fn get_admins(client: Client) {
+        // Create some admins.
+        create_admin(&client, &AdminCredentials::example2()).await;
+        create_admin(&client, &AdminCredentials::example3()).await;
+
+        // Check that all admins are listed.
+        let response = client.get(uri!(get_admins(_))).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        assert!(response.body().is_some());
+
+        let admins: Vec<String> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let expected = vec![
+            DEFAULT_ADMIN_USERNAME.to_string(),
+            AdminCredentials::example1().username,
+            AdminCredentials::example2().username,
+            AdminCredentials::example3().username,
+        ];
+        assert_eq!(admins, expected);
+    }
+
+    #[backend_test(admin)]
+    async fn get_admins_sorted(client: Client) {
+        create_admin(&client, &AdminCredentials::example2()).await;
+        create_admin(&client, &AdminCredentials::example3()).await;
+
+        // Page through the admins in descending order of username.
+        let mut usernames = Vec::new();
+        for page_num in 1..=2 {
+            let pagination = PaginationRequest {
+                page_num,
+                page_size: 2,
+                sort_by: Some("username".to_string()),
+                order: Some(SortOrder::Desc),
+            };
+            let response = client
+                .get(uri!(get_admins(OptionalPagination(Some(pagination)))))
+                .dispatch()
+                .await;
+            assert_eq!(Status::Ok, response.status());
+            let page: Paginated<String> =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            assert_eq!(page.pagination.total, 4);
+            assert_eq!(page.items.len(), 2);
+            usernames.extend(page.items);
+        }
+        let mut expected = vec![
+            DEFAULT_ADMIN_USERNAME.to_string(),
+            AdminCredentials::example1().username,
+            AdminCredentials::example2().username,
+            AdminCredentials::example3().username,
+        ];
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(usernames, expected);
+
+        // Other fields cannot be sorted by.
+        let pagination = PaginationRequest {
+            sort_by: Some("password_hash".to_string()),
+            ..Default::default()
+        };
+        let response = client
+            .get(uri!(get_admins(OptionalPagination(Some(pagination)))))
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+    }
+
+    #[backend_test(admin)]
+    async fn get_sms_routes(client: Client) {
+        let response = client.get(uri!(get_sms_routes)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+
+        // There is always a default route, which matches everything and so comes last.
+        let routes: Vec<SmsRoute> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let default = routes.last().unwrap();
+        assert_eq!(default.prefix, "");
+        for pair in routes.windows(2) {
+            assert!(pair[0].prefix.len() >= pair[1].prefix.len());
+        }
+    }
+
+    #[backend_test]
+    async fn get_sms_routes_unauthenticated(client: Client) {
+        let response = client.get(uri!(get_sms_routes)).dispatch().await;
+        assert_eq!(Status::Unauthorized, response.status());
+    }
+
+    #[backend_test(admin)]
+    async fn access_tokens(client: Client, db: Database) {
+        // Create a token while logged in as usual.
+        let spec = AdminTokenSpec {
+            name: "CI".to_string(),
+            expires_at: None,
+        };
+        let response = client
+            .post(uri!(create_admin_token))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&spec).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let new_token: NewAdminTokenDesc =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(new_token.token.name, "CI");
+
+        // Only a hash of the secret is stored, and it is never listed.
+        let stored = Coll::<AdminToken>::from_db(&db)
+            .find_one(None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(stored.secret_hash, new_token.secret);
+        let response = client.get(uri!(get_admin_tokens)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        assert!(!raw_response.contains(&new_token.secret));
+        let tokens: Vec<AdminTokenDesc> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(tokens, vec![new_token.token.clone()]);
+
+        // Log out, so only the token can authenticate.
+        let response = client.delete("/auth").csrf().dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        assert!(client.cookies().get(AUTH_TOKEN_COOKIE).is_none());
+        let bearer = |secret: &str| Header::new("Authorization", format!("Bearer {secret}"));
+        let create = |header: Option<Header<'static>>| {
+            let mut request = client
+                .post(uri!(create_election))
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ElectionSpec::current_example()).unwrap());
+            if let Some(header) = header {
+                request = request.header(header);
+            }
+            request.dispatch()
+        };
+
+        // The token works without cookies or a CSRF header, as its admin.
+        let response = create(Some(bearer(&new_token.secret))).await;
+        assert_eq!(Status::Ok, response.status());
+        let election: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let metadata = Coll::<ElectionMetadata>::from_db(&db)
+            .find_one(u32_id_filter(election.id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.created_by, NewAdmin::example().username);
+
+        // Missing or unknown tokens are rejected.
+        assert_eq!(Status::Unauthorized, create(None).await.status());
+        assert_eq!(
+            Status::Unauthorized,
+            create(Some(bearer("not-a-token"))).await.status()
+        );
+
+        // Expired tokens are rejected.
+        let (expired, expired_secret) = NewAdminToken::generate(
+            stored.admin_id,
+            "Old".to_string(),
+            Some(Utc::now() - Duration::try_minutes(1).unwrap()),
+        );
+        Coll::<NewAdminToken>::from_db(&db)
+            .insert_one(expired, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            Status::Unauthorized,
+            create(Some(bearer(&expired_secret))).await.status()
+        );
+
+        // Revoke the token using itself; it then stops working.
+        let response = client
+            .delete(uri!(revoke_admin_token(new_token.token.id.as_str())))
+            .header(bearer(&new_token.secret))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(
+            Status::Unauthorized,
+            create(Some(bearer(&new_token.secret))).await.status()
+        );
+    }
+
+    #[backend_test]
+    async fn default_admin_password_change(client: Client) {
+        // Log in as the default admin.
+        let credentials = AdminCredentials {
+            username: DEFAULT_ADMIN_USERNAME.to_string(),
+            password: DEFAULT_ADMIN_PASSWORD.into(),
+        };
+        let response = client
+            .post(uri!(crate::api::auth::authenticate))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&credentials).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let create = || {
+            client
+                .post(uri!(create_election))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ElectionSpec::current_example()).unwrap())
+                .dispatch()
+        };
+        let change_password = |change: AdminPasswordChange| {
+            client
+                .put(uri!(change_admin_password))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&change).unwrap())
+                .dispatch()
+        };
+
+        // Nothing else can be done until the password is changed.
+        let response = create().await;
+        assert_eq!(Status::Forbidden, response.status());
+        let body: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "password_change_required");
+
+        // The current password must be right, and the new one acceptable.
+        let response = change_password(AdminPasswordChange {
+            current_password: "wrong-password".into(),
+            new_password: "a-much-better-password".into(),
+        })
+        .await;
+        assert_eq!(Status::BadRequest, response.status());
+        let response = change_password(AdminPasswordChange {
+            current_password: DEFAULT_ADMIN_PASSWORD.into(),
+            new_password: DEFAULT_ADMIN_PASSWORD.into(),
+        })
+        .await;
+        assert_eq!(Status::BadRequest, response.status());
+        assert_eq!(Status::Forbidden, create().await.status());
+
+        // Once changed, the admin can carry on as normal.
+        let response = change_password(AdminPasswordChange {
+            current_password: DEFAULT_ADMIN_PASSWORD.into(),
+            new_password: "a-much-better-password".into(),
+        })
+        .await;
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Status::Ok, create().await.status());
+    }
+
+    #[backend_test(admin)]
+    async fn create_election(client: Client, db: Database) {
+        // Create an election.
+        let response = client
+            .post(uri!(create_election))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ElectionSpec::current_example()).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let response_election: ElectionDescription = serde_json::from_str(&raw_response).unwrap();
+
+        // Ensure it is present in the DB.
+        let elections = Coll::<ElectionMetadata>::from_db(&db);
+        let with_name = doc! { "name": &ElectionSpec::current_example().name };
+        let inserted_election = elections
+            .find_one(with_name.clone(), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut expected_election = ElectionMetadata::from(ElectionSpec::current_example());
+        expected_election.created_by = NewAdmin::example().username;
+        assert_eq!(expected_election, inserted_election);
+
+        // Ensure the counters were created.
+        for question_id in response_election.questions.keys() {
+            let counter = Coll::<Counter>::from_db(&db)
+                .find_one(
+                    doc! {"_id": ballot_counter_id(response_election.id, *question_id)},
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(counter.next, 1);
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn create_election_local_times(client: Client) {
+        let create = |start_time: &str| {
+            let mut spec = serde_json::to_value(ElectionSpec::current_example()).unwrap();
+            spec["start_time"] = serde_json::json!(start_time);
+            spec["end_time"] = serde_json::json!("2024-06-01T17:00:00");
+            spec["timezone"] = serde_json::json!("Europe/London");
+            client
+                .post(uri!(create_election))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(spec.to_string())
+                .dispatch()
+        };
+
+        // Local times are stored in UTC, and the timezone is described.
+        let response = create("2024-03-01T09:00:00").await;
+        assert_eq!(Status::Ok, response.status());
+        let election: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let utc = |time: &str| time.parse::<chrono::DateTime<Utc>>().unwrap();
+        assert_eq!(election.start_time, utc("2024-03-01T09:00:00Z"));
+        assert_eq!(election.end_time, utc("2024-06-01T16:00:00Z"));
+        assert_eq!(election.timezone.as_deref(), Some("Europe/London"));
+
+        // Times skipped by the clocks going forward are refused, explaining
+        // which instants they could have meant.
+        let response = create("2024-03-31T01:30:00").await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "invalid_election_spec");
+        let detail = body["detail"].as_str().unwrap();
+        assert!(detail.starts_with("start_time 2024-03-31 01:30:00 does not exist"));
+        assert!(detail.contains("2024-03-31T00:30:00Z or 2024-03-31T01:30:00Z"));
+
+        // So are unknown timezones.
+        let mut spec = serde_json::to_value(ElectionSpec::current_example()).unwrap();
+        spec["timezone"] = serde_json::json!("Europe/Atlantis");
+        let response = client
+            .post(uri!(create_election))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(spec.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "invalid_election_spec");
+        assert_eq!(body["detail"], "Unknown timezone 'Europe/Atlantis'");
+    }
+
+    #[backend_test(admin)]
+    async fn create_election_without_room(client: Client) {
+        // A cap of zero would make an election nobody could join.
+        let mut spec = serde_json::to_value(ElectionSpec::current_example()).unwrap();
+        spec["max_voters"] = serde_json::json!(0);
+        let response = client
+            .post(uri!(create_election))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(spec.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "invalid_election_spec");
+        assert_eq!(
+            body["detail"],
+            "max_voters must be at least 1, or omitted for no cap"
+        );
+    }
+
+    #[backend_test(admin)]
+    async fn election_authorship(client: Client) {
+        let username = NewAdmin::example().username;
+
+        // The creator is recorded.
+        let mut spec = ElectionSpec::current_example();
+        spec.start_time = Utc::now() + Duration::try_days(1).unwrap();
+        let election = create_election_for_spec(&client, &spec).await;
+        let authorship = election.authorship.unwrap();
+        assert_eq!(authorship.created_by, username);
+        assert_eq!(authorship.last_modified_by, None);
+
+        // The modifier is recorded, and the creator kept.
+        spec.name = "New Name".to_string();
+        let modified = modify_election_with_spec(&client, election.id, &spec).await;
+        let authorship = modified.authorship.unwrap();
+        assert_eq!(authorship.created_by, username);
+        assert_eq!(authorship.last_modified_by, Some(username.clone()));
+
+        // Admins see authorship when fetching elections.
+        publish(&client, election.id).await;
+        let path = format!("/elections/{}", election.id);
+        let response = client.get(&path).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let description: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(description.authorship, Some(authorship.clone()));
+
+        let response = client.get("/elections").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let summaries: Vec<ElectionSummary> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let summary = summaries.iter().find(|s| s.id == election.id).unwrap();
+        assert_eq!(summary.authorship, Some(authorship));
+
+        // The public do not.
+        let response = client.delete("/auth").csrf().dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        for path in [path.as_str(), "/elections"] {
+            let response = client.get(path).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            let raw_response = response.into_string().await.unwrap();
+            assert!(raw_response.contains("New Name"));
+            assert!(!raw_response.contains("created_by"));
+            assert!(!raw_response.contains("last_modified_by"));
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn publish_archive(client: Client, db: Database) {
+        // Try to publish/archive an election that doesn't exist.
+        publish_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
+        archive_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
+
+        // Create an election.
+        let spec = ElectionSpec::current_example();
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Archive it.
+        archive(&client, election.id).await;
+        let archived = get_election_by_id(&db, election.id).await;
+        assert_eq!(archived.metadata.state, ElectionState::Archived);
+
+        // Check we can't publish it or archive it again.
+        publish_expect_status(&client, election.id, Status::BadRequest).await;
+        archive_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Create a new election.
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Publish it.
+        publish(&client, election.id).await;
+        let published = get_election_by_id(&db, election.id).await;
+        assert_eq!(published.metadata.state, ElectionState::Published);
+
+        // Check we can't publish it again.
+        publish_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Archive it.
+        archive(&client, election.id).await;
+        let archived = get_election_by_id(&db, election.id).await;
+        assert_eq!(archived.metadata.state, ElectionState::Archived);
+    }
+
+    #[backend_test(admin)]
+    async fn bulk_archive(client: Client, db: Database) {
+        // One published election that has ended, one still running, and one
+        // draft that would have ended.
+        let ended = create_election_for_spec(&client, &ElectionSpec::past_example()).await;
+        publish_ended(&client, ended.id).await;
+        let ongoing = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        publish(&client, ongoing.id).await;
+        let draft = create_election_for_spec(&client, &ElectionSpec::past_example()).await;
+
+        let bulk_archive = |request: BulkArchiveRequest| {
+            let client = &client;
+            async move {
+                let response = client
+                    .post(uri!(bulk_archive_elections))
+                    .csrf()
+                    .header(ContentType::JSON)
+                    .body(serde_json::to_string(&request).unwrap())
+                    .dispatch()
+                    .await;
+                assert_eq!(response.status(), Status::Ok);
+                let results: Vec<BulkArchiveResult> =
+                    serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+                results
+                    .into_iter()
+                    .map(|result| (result.election_id, result.outcome))
+                    .collect::<HashMap<_, _>>()
+            }
+        };
+        let skipped = |reason: &str| BulkArchiveOutcome::Skipped {
+            reason: reason.to_string(),
+        };
+
+        // A dry run reports what would happen, without doing it.
+        let request = BulkArchiveRequest {
+            ended_before: Some(Utc::now()),
+            dry_run: true,
+            ..Default::default()
+        };
+        let results = bulk_archive(request.clone()).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&ended.id], BulkArchiveOutcome::WouldArchive);
+        assert_eq!(results[&draft.id], skipped("Election is a draft"));
+        let election = get_election_by_id(&db, ended.id).await;
+        assert_eq!(election.metadata.state, ElectionState::Published);
+
+        // Only the published election that has ended is archived, and audited.
+        let results = bulk_archive(BulkArchiveRequest {
+            dry_run: false,
+            ..request
+        })
+        .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&ended.id], BulkArchiveOutcome::Archived);
+        assert_eq!(results[&draft.id], skipped("Election is a draft"));
+        for (id, state) in [
+            (ended.id, ElectionState::Archived),
+            (ongoing.id, ElectionState::Published),
+            (draft.id, ElectionState::Draft),
+        ] {
+            assert_eq!(get_election_by_id(&db, id).await.metadata.state, state);
+        }
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "bulk_archive" }, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].detail, format!("Election {}", ended.id));
+
+        // Explicitly listed elections are reported whatever their state.
+        let missing = loop {
+            let id = rand::thread_rng().gen();
+            if ![ended.id, ongoing.id, draft.id].contains(&id) {
+                break id;
+            }
+        };
+        let results = bulk_archive(BulkArchiveRequest {
+            election_ids: Some(vec![ended.id, ongoing.id, missing]),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(results[&ended.id], skipped("Election is already archived"));
+        assert_eq!(results[&ongoing.id], skipped("Election has not ended yet"));
+        assert_eq!(results[&missing], skipped("Election does not exist"));
+
+        // Exactly one of the IDs and the filter must be given.
+        for request in [
+            BulkArchiveRequest::default(),
+            BulkArchiveRequest {
+                election_ids: Some(vec![ended.id]),
+                ended_before: Some(Utc::now()),
+                dry_run: false,
+            },
+        ] {
+            let response = client
+                .post(uri!(bulk_archive_elections))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&request).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn scheduled_tasks(client: Client, db: Database) {
+        // Publishing an election schedules its finalizer, and one for its
+        // early-closing question.
+        let mut spec = ElectionSpec::future_example();
+        spec.questions[0].end_time = Some(spec.start_time + Duration::try_days(1).unwrap());
+        let election = create_election_for_spec(&client, &spec).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == spec.questions[0].description)
+            .unwrap();
+        publish(&client, election.id).await;
+
+        let finalizer_name = format!("election-{}-finalizer", election.id);
+        let tasks = list_tasks(&client).await;
+        let finalizer = tasks.iter().find(|t| t.name == finalizer_name).unwrap();
+        assert_eq!(finalizer.scheduled_for, election.end_time);
+        assert_eq!(finalizer.state, TaskState::Pending);
+        assert!(!finalizer.allow_early);
+        let question_finalizer_name =
+            format!("election-{}-question-{}-finalizer", election.id, q1.id);
+        let question_finalizer = tasks
+            .iter()
+            .find(|t| t.name == question_finalizer_name)
+            .unwrap();
+        assert!(!question_finalizer.allow_early);
+
+        // Neither finalizer may be triggered while voting is open.
+        for name in [&question_finalizer_name, &finalizer_name] {
+            let response = client
+                .post(uri!(trigger_task(name.as_str())))
+                .csrf()
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+        }
+        let tasks = list_tasks(&client).await;
+        let finalizer = tasks.iter().find(|t| t.name == finalizer_name).unwrap();
+        assert_eq!(finalizer.state, TaskState::Pending);
+        assert!(get_finalization(&client, election.id)
+            .await
+            .completed_at
+            .is_none());
+
+        // The end warning may be triggered early, after which it completes.
+        let warning_name = format!("election-{}-end-warning", election.id);
+        let response = client
+            .post(uri!(trigger_task(warning_name.as_str())))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        // (hopefully not flaky) sleep to make sure the warning has gone through.
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let tasks = list_tasks(&client).await;
+        let warning = tasks.iter().find(|t| t.name == warning_name).unwrap();
+        assert_eq!(warning.state, TaskState::Completed);
+
+        // It cannot be triggered again, and unknown tasks cannot be triggered at all.
+        let response = client
+            .post(uri!(trigger_task(warning_name.as_str())))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Conflict);
+        let response = client
+            .post(uri!(trigger_task("no-such-task")))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Every attempt is audited, whether or not it succeeded.
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "task_trigger" }, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 5);
+        assert!(entries
+            .iter()
+            .any(|entry| entry.detail == "Task no-such-task"));
+
+        // Archiving the election cancels its remaining tasks.
+        archive(&client, election.id).await;
+        let tasks = list_tasks(&client).await;
+        let question_finalizer = tasks
+            .iter()
+            .find(|t| t.name == question_finalizer_name)
+            .unwrap();
+        assert_eq!(question_finalizer.state, TaskState::Cancelled);
+    }
+
+    async fn list_tasks(client: &Client) -> Vec<TaskDesc> {
+        let response = client.get(uri!(list_tasks)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    #[backend_test(admin)]
+    async fn publish_ended_or_started(client: Client, db: Database) {
+        // Elections that have already ended are refused...
+        let election = create_election_for_spec(&client, &ElectionSpec::past_example()).await;
+        publish_expect_status(&client, election.id, Status::UnprocessableEntity).await;
+        let draft = get_election_by_id(&db, election.id).await;
+        assert_eq!(draft.metadata.state, ElectionState::Draft);
+
+        // ...unless explicitly allowed.
+        let response = client
+            .post(uri!(publish_election(election.id, Some(true))))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let published = get_election_by_id(&db, election.id).await;
+        assert_eq!(published.metadata.state, ElectionState::Published);
+
+        // Elections that have started are published, with a warning.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        let response = client
+            .post(uri!(publish_election(election.id, _)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let publication: ElectionPublication =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(publication.warning.unwrap().contains("started"));
+
+        // Elections that have yet to start are published without one.
+        let election = create_election_for_spec(&client, &ElectionSpec::future_example()).await;
+        let response = client
+            .post(uri!(publish_election(election.id, _)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let publication: ElectionPublication =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(publication, ElectionPublication::default());
+    }
+
+    #[backend_test(admin)]
+    async fn rotate_keys(client: Client, db: Database) {
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        let before = get_election_by_id(&db, election.id).await;
+        let rotate = |id: ElectionId| {
+            client
+                .post(uri!(rotate_election_keys(id)))
+                .csrf()
+                .dispatch()
+        };
+
+        // Rotating a draft's keys replaces only its keys.
+        let response = rotate(election.id).await;
+        assert_eq!(Status::Ok, response.status());
+        let crypto: ElectionCrypto =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let after = get_election_by_id(&db, election.id).await;
+        assert_ne!(crypto.public_key, before.crypto.public_key);
+        assert_eq!(crypto, ElectionCrypto::from(&after));
+        assert_eq!(after.questions, before.questions);
+        assert_eq!(after.metadata.name, before.metadata.name);
+        assert_eq!(
+            after.metadata.last_modified_by,
+            Some(NewAdmin::example().username)
+        );
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "key_rotation" }, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        // Unknown elections can't be rotated.
+        assert_eq!(Status::NotFound, rotate(election.id + 100).await.status());
+
+        // Once published, the keys are fixed.
+        publish(&client, election.id).await;
+        assert_eq!(Status::Conflict, rotate(election.id).await.status());
+        assert_eq!(
+            get_election_by_id(&db, election.id).await.crypto,
+            after.crypto
+        );
+
+        // Ballots are signed with the new keys.
+        let question = after.questions.values().next().unwrap();
+        join_as_example_voter(&client, &db, election.id, question.id).await;
+        let ballot_specs = vec![BallotSpec {
+            question: question.id,
+            candidate: question.candidates[0].clone(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(crate::api::voting::cast_ballots(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let receipts: Vec<Receipt<Unconfirmed>> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(receipts.len(), 1);
+        assert!(crypto
+            .public_key
+            .verify(&receipts[0].message(), &receipts[0].signature));
+        assert!(!before
+            .crypto
+            .public_key
+            .verify(&receipts[0].message(), &receipts[0].signature));
+    }
+
+    #[backend_test(admin)]
+    async fn suspend_resume(client: Client, db: Database) {
+        // Try to suspend/resume an election that doesn't exist.
+        suspend_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
+        resume_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
+
+        // Drafts can't be suspended.
+        let spec = ElectionSpec::current_example();
+        let election = create_election_for_spec(&client, &spec).await;
+        suspend_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Publish it, then suspend it.
+        publish(&client, election.id).await;
+        resume_expect_status(&client, election.id, Status::BadRequest).await;
+        suspend_expect_status(&client, election.id, Status::Ok).await;
+        let suspended = get_election_by_id(&db, election.id).await;
+        assert!(suspended.metadata.suspended);
+        assert_eq!(suspended.metadata.state, ElectionState::Published);
+        suspend_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Resume it.
+        resume_expect_status(&client, election.id, Status::Ok).await;
+        let resumed = get_election_by_id(&db, election.id).await;
+        assert!(!resumed.metadata.suspended);
+        resume_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Archived elections can't be suspended.
+        archive(&client, election.id).await;
+        suspend_expect_status(&client, election.id, Status::BadRequest).await;
+
+        // Every attempt is audited, whether or not it succeeded.
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(
+                doc! { "action": { "$in": ["election_suspend", "election_resume"] } },
+                None,
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let count = |action: &str| entries.iter().filter(|e| e.action == action).count();
+        assert_eq!(count("election_suspend"), 5);
+        assert_eq!(count("election_resume"), 4);
+        assert!(entries
+            .iter()
+            .any(|entry| entry.detail == format!("Election {}", election.id)));
+    }
+
+    #[backend_test(admin)]
+    async fn publish_duplicate_candidates(client: Client, db: Database) {
+        // Hand-insert a draft with a duplicate candidate.
+        let mut election = Election::draft_example();
+        let question = election.questions.values_mut().next().unwrap();
+        let duplicate = question.candidates[0].clone();
+        question.candidates.push(duplicate);
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        // Check we can't publish it.
+        publish_expect_status(&client, election.id, Status::UnprocessableEntity).await;
+        let draft = get_election_by_id(&db, election.id).await;
+        assert_eq!(draft.metadata.state, ElectionState::Draft);
+    }
+
+    #[backend_test(admin)]
+    async fn publish_creates_zero_totals(client: Client, db: Database) {
+        // Create a future election, so it can be modified after publishing.
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Drafts have no totals.
+        let filter = doc! {"election_id": election.id};
+        assert_no_matches::<CandidateTotals>(&db, filter.clone()).await;
+
+        // Publishing creates zero totals for every candidate.
+        publish(&client, election.id).await;
+        let candidates = |spec: &ElectionSpec| {
+            spec.questions
+                .iter()
+                .enumerate()
+                .flat_map(|(index, question)| {
+                    let question_id = index as u32 + 1;
+                    question
+                        .candidates
+                        .iter()
+                        .map(move |candidate| (question_id, candidate.clone()))
+                })
+                .collect::<Vec<_>>()
+        };
+        let totals = |db: Database| async move {
+            Coll::<CandidateTotals>::from_db(&db)
+                .find(doc! {"election_id": election.id}, None)
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+        };
+        let published = totals(db.clone()).await;
+        assert_eq!(published.len(), candidates(&spec).len());
+        for (question_id, candidate) in candidates(&spec) {
+            let total = published
+                .iter()
+                .find(|t| t.question_id == question_id && t.candidate_name == candidate)
+                .unwrap();
+            assert_eq!(total.crypto.tally, DreipScalar::zero());
+            assert_eq!(total.crypto.r_sum, DreipScalar::zero());
+        }
+
+        // Modifying and republishing drops removed candidates and adds new
+        // ones, without duplicating the rest.
+        let removed = spec.questions[0].candidates.pop().unwrap();
+        spec.questions[0]
+            .candidates
+            .push("New Candidate".to_string());
+        modify_election_with_spec(&client, election.id, &spec).await;
+        publish(&client, election.id).await;
+        let republished = totals(db.clone()).await;
+        assert_eq!(republished.len(), candidates(&spec).len());
+        assert!(republished
+            .iter()
+            .all(|t| !(t.question_id == 1 && t.candidate_name == removed)));
+        assert!(republished
+            .iter()
+            .any(|t| t.question_id == 1 && t.candidate_name == "New Candidate"));
+    }
+
+    #[backend_test(admin)]
+    async fn modify_election(client: Client) {
+        // Try to modify an election that doesn't exist.
+        modify_expect_status(
+            &client,
+            rand::thread_rng().gen(),
+            &ElectionSpec::current_example(),
+            Status::NotFound,
+        )
+        .await;
+
+        // Create an election.
+        let mut spec = ElectionSpec::future_example();
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Modify it.
+        spec.name = "New Name".to_string();
+        let modified = modify_election_with_spec(&client, election.id, &spec).await;
+        assert_eq!(modified.name, spec.name);
+        assert_eq!(modified.state, election.state);
+        assert_eq!(modified.start_time, election.start_time);
+        assert_eq!(modified.end_time, election.end_time);
+        assert_eq!(modified.electorates, election.electorates);
+
+        // Publish it.
+        publish(&client, election.id).await;
+
+        // Modify it again.
+        spec.start_time = Utc::now();
+        let modified = modify_election_with_spec(&client, election.id, &spec).await;
+        assert_eq!(modified.name, spec.name);
+        assert_eq!(modified.state, ElectionState::Draft);
+        assert!(modified.start_time - spec.start_time < Duration::try_seconds(1).unwrap());
+        assert_eq!(modified.end_time, election.end_time);
+        assert_eq!(modified.electorates, election.electorates);
+
+        // Re-publish.
+        publish(&client, election.id).await;
+
+        // Ensure we can't modify due to the new start date.
+        modify_expect_status(
+            &client,
+            election.id,
+            &ElectionSpec::current_example(),
+            Status::BadRequest,
+        )
+        .await;
+
+        // Archive it.
+        archive(&client, election.id).await;
+
+        // Ensure we can't modify an archived election.
+        modify_expect_status(
+            &client,
+            election.id,
+            &ElectionSpec::current_example(),
+            Status::BadRequest,
+        )
+        .await;
+
+        // Ensure we can't modify an election that went straight from draft to archived
+        // while still being before the start time.
+        let election = create_election_for_spec(&client, &ElectionSpec::future_example()).await;
+        archive(&client, election.id).await;
+        modify_expect_status(
+            &client,
+            election.id,
+            &ElectionSpec::current_example(),
+            Status::BadRequest,
+        )
+        .await;
+    }
+
+    #[backend_test(admin)]
+    async fn modify_election_remaps_voters(client: Client, db: Database) {
+        // Create and publish a future election with four questions.
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+
+        // Some voters join it. Question IDs are assigned in order, starting at 1.
+        let voter_ids = [Id::new(), Id::new(), Id::new()];
+        let joined = |voter_id: Id, questions: &[u32]| VoterElection {
+            voter_id,
+            election_id: election.id,
+            allowed_questions: AllowedQuestions {
+                confirmed: questions.iter().map(|id| (*id, false)).collect(),
+            },
+            joins: None,
+            consent: None,
+        };
+        Coll::<VoterElection>::from_db(&db)
+            .insert_many(
+                [
+                    joined(voter_ids[0], &[1, 2, 3, 4]),
+                    joined(voter_ids[1], &[2]),
+                    joined(voter_ids[2], &[]),
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Reword the first question and remove the second.
+        let reworded = QuestionSpec {
+            description: "Who should be captain of the Quidditch team next year?".to_string(),
+            ..QuestionSpec::example1()
+        };
+        spec.questions = vec![reworded, QuestionSpec::example3(), QuestionSpec::example4()];
+        let response = modify_expect_status(&client, election.id, &spec, Status::Ok).await;
+        let raw_response = response.into_string().await.unwrap();
+        let modification: serde_json::Value = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(modification["questions"].as_object().unwrap().len(), 3);
+        assert_eq!(modification["voters_affected"], 2);
+        let dropped: Vec<DroppedQuestion> =
+            serde_json::from_value(modification["dropped_questions"].clone()).unwrap();
+        assert_eq!(
+            dropped,
+            vec![
+                DroppedQuestion {
+                    id: 1,
+                    description: QuestionSpec::example1().description,
+                },
+                DroppedQuestion {
+                    id: 2,
+                    description: QuestionSpec::example2().description,
+                },
+            ]
+        );
+
+        // The third and fourth questions are now the second and third.
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        let allowed = |voter_id| {
+            VoterElection::allowed_questions(&voter_elections, voter_id, election.id, None)
+        };
+        assert_eq!(
+            allowed(voter_ids[0]).await.unwrap().unwrap().confirmed,
+            HashMap::from([(2, false), (3, false)])
+        );
+
+        // A voter left with nothing to vote on must join again.
+        assert_eq!(allowed(voter_ids[1]).await.unwrap(), None);
+
+        // A voter who was never allowed any questions is untouched.
+        assert!(allowed(voter_ids[2]).await.unwrap().unwrap().is_empty());
+    }
+
+    #[backend_test(admin)]
+    async fn modify_election_recomputes_voters(client: Client, db: Database) {
+        // Create and publish a future election with four questions.
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+
+        // Two voters join Quidditch and one joins Moongolf, giving them the
+        // first or second question and the fourth, which is open to all. The
+        // groups of the last Quidditch voter were not recorded.
+        let voter_ids = [Id::new(), Id::new(), Id::new()];
+        let joined = |voter_id: Id, group: Option<&str>, questions: &[u32]| VoterElection {
+            voter_id,
+            election_id: election.id,
+            allowed_questions: AllowedQuestions {
+                confirmed: questions.iter().map(|id| (*id, false)).collect(),
+            },
+            joins: group.map(|group| {
+                Joins::from([("Societies".to_string(), HashSet::from([group.to_string()]))])
+            }),
+            consent: None,
+        };
+        Coll::<VoterElection>::from_db(&db)
+            .insert_many(
+                [
+                    joined(voter_ids[0], Some("Quidditch"), &[1, 4]),
+                    joined(voter_ids[1], Some("Moongolf"), &[2, 4]),
+                    joined(voter_ids[2], None, &[1, 4]),
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Move the first question from Quidditch to Moongolf.
+        spec.questions[0].constraints = QuestionSpec::example2().constraints;
+        let response = modify_expect_status(&client, election.id, &spec, Status::Ok).await;
+        let modification: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(modification["voters_affected"], 3);
+
+        // The Quidditch voter loses the question, and the Moongolf voter gains it.
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        let allowed = |voter_id| {
+            VoterElection::allowed_questions(&voter_elections, voter_id, election.id, None)
+        };
+        assert_eq!(
+            allowed(voter_ids[0]).await.unwrap().unwrap().confirmed,
+            HashMap::from([(4, false)])
+        );
+        assert_eq!(
+            allowed(voter_ids[1]).await.unwrap().unwrap().confirmed,
+            HashMap::from([(1, false), (2, false), (4, false)])
+        );
+
+        // A voter whose groups are unknown must join again.
+        assert_eq!(allowed(voter_ids[2]).await.unwrap(), None);
+    }
+
+    #[backend_test(admin)]
+    async fn modify_election_recomputes_pre_joined_voters(client: Client, db: Database) {
+        // Create and publish a future election which can already be joined.
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        spec.pre_join_minutes = Some(60 * 24 * 60);
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election.id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(election.is_joinable_at(Utc::now()));
+
+        // A voter joins Quidditch early, as joining would record them.
+        let voter_id = Id::new();
+        let joins = Joins::from([(
+            "Societies".to_string(),
+            HashSet::from(["Quidditch".to_string()]),
+        )]);
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        voter_elections
+            .insert_one(
+                VoterElection {
+                    voter_id,
+                    election_id: election.id,
+                    allowed_questions: AllowedQuestions::for_joins(&election, &joins).unwrap(),
+                    joins: Some(joins),
+                    consent: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Moving the first question from Quidditch to Moongolf takes it away
+        // from them before the election even starts.
+        spec.questions[0].constraints = QuestionSpec::example2().constraints;
+        let response = modify_expect_status(&client, election.id, &spec, Status::Ok).await;
+        let modification: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(modification["voters_affected"], 1);
+        let allowed =
+            VoterElection::allowed_questions(&voter_elections, voter_id, election.id, None)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(allowed.confirmed, HashMap::from([(4, false)]));
+    }
+
+    #[backend_test(admin)]
+    async fn question_slugs(client: Client) {
+        // Questions cannot share a description, on creation or modification.
+        let mut duplicated = ElectionSpec::future_example();
+        duplicated.questions.push(QuestionSpec::example1());
+        let response = client
+            .post(uri!(create_election))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&duplicated).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        let election = create_election_for_spec(&client, &spec).await;
+        duplicated.start_time = future.start_time;
+        duplicated.end_time = future.end_time;
+        modify_expect_status(
+            &client,
+            election.id,
+            &duplicated,
+            Status::UnprocessableEntity,
+        )
+        .await;
+
+        // Every question has a slug, unique within the election.
+        let slugs = |election: &ElectionDescription| {
+            election
+                .questions
+                .values()
+                .map(|question| (question.description.clone(), question.slug.clone()))
+                .collect::<HashMap<_, _>>()
+        };
+        let original = slugs(&election);
+        assert_eq!(
+            original[&QuestionSpec::example1().description],
+            "who-should-be-captain-of-the-quidditch-team"
+        );
+        let unique = original.values().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), original.len());
+
+        // Reordering the questions changes their IDs, but not their slugs,
+        // while a reworded question gets a new slug.
+        spec.questions.reverse();
+        spec.questions[0].description = "Who should be the new captain?".to_string();
+        let modified = modify_election_with_spec(&client, election.id, &spec).await;
+        let modified_slugs = slugs(&modified);
+        for question in &spec.questions[1..] {
+            assert_eq!(
+                modified_slugs[&question.description],
+                original[&question.description]
+            );
+            let id = |election: &ElectionDescription| {
+                election
+                    .questions
+                    .values()
+                    .find(|q| q.description == question.description)
+                    .unwrap()
+                    .id
+            };
+            assert_ne!(id(&modified), id(&election));
+        }
+        assert_eq!(
+            modified_slugs["Who should be the new captain?"],
+            "who-should-be-the-new-captain"
+        );
+    }
+
+    #[backend_test(admin)]
+    async fn electorate_management(client: Client, db: Database) {
+        // Create a future election.
+        let mut spec = ElectionSpec::current_example();
+        let future = ElectionSpec::future_example();
+        spec.start_time = future.start_time;
+        spec.end_time = future.end_time;
+        let election = create_election_for_spec(&client, &spec).await;
+        let original = get_election_by_id(&db, election.id).await;
+
+        let put = |name: &'static str, groups: &[&str], is_mutex: bool| {
+            let client = &client;
+            let spec = ElectorateSpec {
+                groups: groups.iter().map(|group| group.to_string()).collect(),
+                is_mutex,
+            };
+            async move {
+                client
+                    .put(uri!(put_electorate(election.id, name)))
+                    .csrf()
+                    .header(ContentType::JSON)
+                    .body(serde_json::to_string(&spec).unwrap())
+                    .dispatch()
+                    .await
+            }
+        };
+        let delete = |name: &'static str| {
+            let client = &client;
+            async move {
+                client
+                    .delete(uri!(delete_electorate(election.id, name)))
+                    .csrf()
+                    .dispatch()
+                    .await
+            }
+        };
+
+        // Add a group to an electorate.
+        let groups = ["Quidditch", "Moongolf", "CompSoc", "Dance"];
+        let response = put("Societies", &groups, false).await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let modified: ElectionDescription = serde_json::from_str(&raw_response).unwrap();
+        assert!(modified.electorates["Societies"].groups.contains("Dance"));
+        assert_eq!(
+            modified.electorates["Courses"],
+            original.electorates["Courses"]
+        );
+
+        // Nothing else was touched.
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(stored.crypto, original.crypto);
+        assert_eq!(stored.questions, original.questions);
+        assert_eq!(
+            stored.metadata.last_modified_by,
+            Some(NewAdmin::example().username)
+        );
+
+        // Removing groups or electorates that questions are constrained by is
+        // refused, listing the questions.
+        let response = put("Societies", &["Quidditch", "Dance"], false).await;
+        assert_eq!(Status::Conflict, response.status());
+        assert!(response.into_string().await.unwrap().contains("[2, 3]"));
+        let response = delete("Courses").await;
+        assert_eq!(Status::Conflict, response.status());
+        assert!(response.into_string().await.unwrap().contains("[3]"));
+        assert_eq!(
+            get_election_by_id(&db, election.id).await.electorates,
+            stored.electorates
+        );
+
+        // Unconstrained electorates can be added and deleted freely.
+        let response = put("Years", &["First", "Second"], true).await;
+        assert_eq!(Status::Ok, response.status());
+        assert!(get_election_by_id(&db, election.id)
+            .await
+            .electorates
+            .contains_key("Years"));
+        let response = delete("Years").await;
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(
+            get_election_by_id(&db, election.id).await.electorates,
+            stored.electorates
+        );
+        assert_eq!(Status::NotFound, delete("Years").await.status());
+
+        // Names that cannot be used as keys are rejected.
+        assert_eq!(Status::BadRequest, put("$Years", &[], false).await.status());
+        assert_eq!(Status::BadRequest, put("Ye.ars", &[], false).await.status());
+
+        // Once the election has started, electorates can no longer be changed.
+        let started = DateTime::from_chrono(Utc::now() - Duration::try_minutes(1).unwrap());
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { "start_time": started } },
+                None,
+            )
+            .await
+            .unwrap();
+        publish(&client, election.id).await;
+        assert_eq!(Status::BadRequest, put("Years", &[], false).await.status());
+        assert_eq!(Status::BadRequest, delete("Courses").await.status());
+
+        // Voters joining afterwards see the new group.
+        client
+            .post(uri!(crate::api::auth::challenge))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&VoterChallengeRequest::example()).unwrap())
+            .dispatch()
+            .await;
+        let cookie = client.cookies().get_private(CHALLENGE_COOKIE).unwrap();
+        let config = client.rocket().state::<Config>().unwrap();
+        let challenge = Challenge::from_cookie(&cookie, config).unwrap();
+        let response = client
+            .post(uri!(crate::api::auth::verify))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&VoterVerifyRequest::example(challenge.code)).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let joins = HashMap::from([(
+            "Societies".to_string(),
+            HashSet::from(["Dance".to_string()]),
+        )]);
+        let response = client
+            .post(uri!(crate::api::voting::join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let voter = Coll::<Voter>::from_db(&db)
+            .find_one(
+                doc! { "sms_hmac": Sms::example_hmac(&client).to_bytestring() },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let allowed =
+            VoterElection::allowed_questions(&Coll::from_db(&db), voter.id, election.id, None)
+                .await
+                .unwrap()
+                .unwrap();
+        // Only the unconstrained question is open to dancers.
+        assert_eq!(allowed.confirmed, HashMap::from([(4, false)]));
+    }
+
+    #[backend_test(admin)]
+    async fn delete_election(client: Client, db: Database) {
+        // Try to delete an election that doesn't exist.
+        delete_expect_status(&client, rand::thread_rng().gen(), Status::NotFound).await;
+
+        // Create an election.
+        let spec = ElectionSpec::current_example();
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Delete it.
+        delete(&client, election.id).await;
+        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+
+        // Create a new election.
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // Publish it.
+        publish(&client, election.id).await;
+
+        // Check it can't be deleted.
+        delete_expect_status(&client, election.id, Status::BadRequest).await;
+        get_election_by_id(&db, election.id).await;
+
+        // Archive it.
+        archive(&client, election.id).await;
+
+        // Delete it, though it was never exported.
+        delete_unexported(&client, election.id).await;
+        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+
+        // Create an active election.
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+
+        // Cast, audit, and confirm various votes.
+        insert_ballots(&db, election.id).await;
+        let voters = insert_allowed_questions(&client, &db, election.id).await;
+
+        // Delete it.
+        archive(&client, election.id).await;
+        delete_unexported(&client, election.id).await;
+        let filter = doc! {
+            "election_id": election.id,
+        };
+        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+        assert_no_matches::<Counter>(&db, u32_id_filter(election.id)).await;
+        assert_no_matches::<AnyBallot>(&db, filter.clone()).await;
+        assert_no_matches::<CandidateTotals>(&db, filter.clone()).await;
+        assert_no_matches::<TotalsDelta>(&db, filter.clone()).await;
+        assert_no_matches::<VoterElection>(&db, filter).await;
+        // Check we didn't accidentally remove allowed questions from a different election.
+        let other_voter = doc! { "voter_id": voters[3] };
+        assert_eq!(count_matches::<VoterElection>(&db, other_voter).await, 1);
+    }
+
+    #[backend_test(admin)]
+    async fn delete_requires_export(client: Client, db: Database) {
+        // Drafts can always be deleted.
+        let spec = ElectionSpec::current_example();
+        let election = create_election_for_spec(&client, &spec).await;
+        assert!(get_retention(&client, election.id).await.deletable);
+
+        // Archived elections can't be deleted until every question is exported.
+        publish(&client, election.id).await;
+        assert!(!get_retention(&client, election.id).await.deletable);
+        archive(&client, election.id).await;
+        let report = get_retention(&client, election.id).await;
+        assert!(!report.deletable);
+        assert_eq!(report.questions.len(), election.questions.len());
+        assert!(report
+            .questions
+            .iter()
+            .all(|question| question.last_dump_at.is_none()));
+        delete_expect_status(&client, election.id, Status::Conflict).await;
+
+        // Unsigned dumps don't count, but signed ones do.
+        let mut question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+        question_ids.sort_unstable();
+        let dump = |question_id: QuestionId, signed: bool| {
+            client
+                .get(uri!(crate::api::public::question_dump(
+                    election.id,
+                    question_id,
+                    Some(signed)
+                )))
+                .dispatch()
+        };
+        for question_id in &question_ids {
+            assert_eq!(dump(*question_id, false).await.status(), Status::Ok);
+        }
+        assert!(!get_retention(&client, election.id).await.deletable);
+        let (last, rest) = question_ids.split_last().unwrap();
+        for question_id in rest {
+            assert_eq!(dump(*question_id, true).await.status(), Status::Ok);
+        }
+        let report = get_retention(&client, election.id).await;
+        assert!(!report.deletable);
+        for question in &report.questions {
+            assert_eq!(
+                question.last_dump_at.is_some(),
+                question.question_id != *last
+            );
+        }
+        delete_expect_status(&client, election.id, Status::Conflict).await;
+        let response = dump(*last, true).await;
+        assert_eq!(response.status(), Status::Ok);
+        let signed: ElectionResults =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(get_retention(&client, election.id).await.deletable);
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(
+            stored.questions[last].last_dump_signature,
+            signed.dump_signature
+        );
+
+        // Exported elections need no acknowledgement to delete.
+        delete(&client, election.id).await;
+        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+        assert_no_matches::<AuditLogEntry>(&db, doc! { "action": "delete_without_export" }).await;
+
+        // Unexported ones can be deleted if the admin acknowledges it, which is audited.
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+        archive(&client, election.id).await;
+        delete_unexported(&client, election.id).await;
+        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "delete_without_export" }, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].admin, NewAdmin::example().username);
+
+        // Unknown elections have no report.
+        let response = client
+            .get(uri!(election_retention(election.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(admin)]
+    async fn maintenance(client: Client, db: Database) {
+        // Nothing to report before the first run.
+        let response = client.get(uri!(last_maintenance_run)).dispatch().await;
+        assert_eq!(Status::NotFound, response.status());
+
+        // Create an active election.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        publish(&client, election.id).await;
+        let missing_election_id: ElectionId = rand::thread_rng().gen();
+
+        // Seed voters registered long ago and just now, in various elections.
+        let config = client.rocket().state::<Config>().unwrap();
+        let long_ago = Utc::now() - config.maintenance_retention() - Duration::try_days(1).unwrap();
+        let old_id = || {
+            let mut bytes = [0; 12];
+            bytes[..4].copy_from_slice(&(long_ago.timestamp() as u32).to_be_bytes());
+            rand::thread_rng().fill(&mut bytes[4..]);
+            Id::from_bytes(bytes).unwrap()
+        };
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        let voter = |id: Id, sms: &str, election_ids: &[ElectionId]| {
+            let voter = Voter {
+                id,
+                voter: NewVoter::new(sms.parse::<Sms>().unwrap(), config),
+            };
+            let joined = election_ids
+                .iter()
+                .map(|election_id| VoterElection {
+                    voter_id: id,
+                    election_id: *election_id,
+                    allowed_questions: AllowedQuestions::default(),
+                    joins: None,
+                    consent: None,
+                })
+                .collect::<Vec<_>>();
+            (voter, joined)
+        };
+        let (never_joined, _) = voter(old_id(), "+441234567890", &[]);
+        let (deleted_only, deleted_only_joined) =
+            voter(old_id(), "+441234567891", &[missing_election_id]);
+        let (active, active_joined) = voter(
+            old_id(),
+            "+441234567892",
+            &[missing_election_id, election.id],
+        );
+        let (new, _) = voter(Id::new(), "+441234567893", &[]);
+        let voters = Coll::<Voter>::from_db(&db);
+        voters
+            .insert_many([&never_joined, &deleted_only, &active, &new], None)
+            .await
+            .unwrap();
+        voter_elections
+            .insert_many(deleted_only_joined.iter().chain(&active_joined), None)
+            .await
+            .unwrap();
+
+        // Seed a counter for an election that no longer exists.
+        let orphan_counter = ballot_counter_id(missing_election_id, 1);
+        Coll::<Counter>::from_db(&db)
+            .insert_one(
+                Counter {
+                    id: orphan_counter.clone(),
+                    next: 1,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Run maintenance.
+        let response = client.post(uri!(run_maintenance)).csrf().dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let report: MaintenanceReportDesc = response.into_json().await.unwrap();
+        assert_eq!(report.voters_purged, 2);
+        assert_eq!(report.counters_purged, 1);
+
+        // Only the orphans are gone.
+        assert_no_matches::<Voter>(&db, never_joined.id.as_doc()).await;
+        assert_no_matches::<Voter>(&db, deleted_only.id.as_doc()).await;
+        assert_no_matches::<VoterElection>(&db, doc! { "voter_id": deleted_only.id }).await;
+        assert_no_matches::<Counter>(&db, doc! { "_id": orphan_counter }).await;
+        for survivor in [active.id, new.id] {
+            assert!(voters
+                .find_one(survivor.as_doc(), None)
+                .await
+                .unwrap()
+                .is_some());
+        }
+        for question_id in election.questions.keys() {
+            let filter = doc! { "_id": ballot_counter_id(election.id, *question_id) };
+            assert_eq!(count_matches::<Counter>(&db, filter).await, 1);
+        }
+
+        // The report is kept.
+        let response = client.get(uri!(last_maintenance_run)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let last_run: MaintenanceReportDesc = response.into_json().await.unwrap();
+        assert_eq!(last_run.voters_purged, report.voters_purged);
+        assert_eq!(last_run.counters_purged, report.counters_purged);
+    }
+
+    #[backend_test(admin)]
+    async fn rate_limit_summaries(client: Client, db: Database) {
+        // Trip some limiters, once two hours ago and the rest just now.
+        let events = Coll::<RateLimitEvent>::from_db(&db);
+        let mut old = RateLimitEvent::new("code_recalls", "voter-b", RequestId::next());
+        old.created_at = DateTime::from_system_time(
+            SystemTime::now() - std::time::Duration::from_secs(2 * 60 * 60),
+        );
+        events.insert_one(old, None).await.unwrap();
+        for key in ["voter-a", "voter-b", "voter-a", "voter-a"] {
+            events.record("code_recalls", key, RequestId::next()).await;
+        }
+        for _ in 0..2 {
+            events.record("otp", "voter-c", RequestId::next()).await;
+        }
+        for i in 0..=TOP_RATE_LIMIT_KEYS {
+            events
+                .record("sms", &format!("voter-{i}"), RequestId::next())
+                .await;
+        }
+
+        let summary = |window: Option<&'static str>| {
+            let client = &client;
+            async move {
+                let response = client
+                    .get(uri!(rate_limit_summary(window)))
+                    .dispatch()
+                    .await;
+                assert_eq!(Status::Ok, response.status());
+                let raw_response = response.into_string().await.unwrap();
+                serde_json::from_str::<RateLimitSummary>(&raw_response).unwrap()
+            }
+        };
+
+        // By default, only the last hour is summarised.
+        let last_hour = summary(None).await;
+        assert_eq!(last_hour.window_secs, 60 * 60);
+        let limiters: Vec<_> = last_hour.limiters.iter().map(|l| &l.limiter).collect();
+        assert_eq!(limiters, ["code_recalls", "otp", "sms"]);
+        let code_recalls = &last_hour.limiters[0];
+        assert_eq!(code_recalls.rejections, 4);
+        assert_eq!(
+            code_recalls.top_keys,
+            [
+                KeyRejections {
+                    key_hash: hash_key("voter-a"),
+                    rejections: 3,
+                },
+                KeyRejections {
+                    key_hash: hash_key("voter-b"),
+                    rejections: 1,
+                },
+            ]
+        );
+        assert_eq!(last_hour.limiters[1].rejections, 2);
+
+        // Only the most rejected keys are listed.
+        let sms = &last_hour.limiters[2];
+        assert_eq!(sms.rejections, TOP_RATE_LIMIT_KEYS as u64 + 1);
+        assert_eq!(sms.top_keys.len(), TOP_RATE_LIMIT_KEYS);
+
+        // A longer window includes the older rejection.
+        let last_day = summary(Some("1d")).await;
+        assert_eq!(last_day.window_secs, 24 * 60 * 60);
+        assert_eq!(last_day.limiters[0].rejections, 5);
+        assert_eq!(last_day.limiters[0].top_keys[1].rejections, 2);
+
+        // Malformed, empty and overly long windows are rejected.
+        for window in ["1", "h", "1w", "-1h", "0m", "8d"] {
+            let response = client
+                .get(uri!(rate_limit_summary(Some(window))))
+                .dispatch()
+                .await;
+            assert_eq!(Status::BadRequest, response.status(), "{window}");
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn sms_delivery_summary(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        let delivery = |number: &str, status, failures| ChallengeDelivery {
+            sms_hmac: number
+                .parse::<Sms>()
+                .unwrap()
+                .into_hmac(client.rocket().state().unwrap()),
+            region: None,
+            message_id: None,
+            status,
+            failures,
+            updated_at: DateTime::now(),
+        };
+        deliveries
+            .insert_many(
+                [
+                    delivery("+447400000001", DeliveryStatus::Sent, 0),
+                    delivery("+447400000002", DeliveryStatus::Sent, 0),
+                    delivery("+447400000003", DeliveryStatus::Delivered, 0),
+                    delivery("+447400000004", DeliveryStatus::OptedOut, 1),
+                    delivery("+447400000005", DeliveryStatus::Failed, 3),
+                    delivery("+447400000006", DeliveryStatus::OptedOut, 4),
+                    delivery("+447400000007", DeliveryStatus::InvalidNumber, 1),
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let response = client.get(uri!(sms_delivery_summary)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let summary: SmsDeliverySummary =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(
+            summary,
+            SmsDeliverySummary {
+                sent: 2,
+                delivered: 1,
+                opted_out: 2,
+                invalid_number: 1,
+                failed: 1,
+                undeliverable: 2,
+            }
+        );
+    }
+
+    #[backend_test(admin)]
+    async fn sms_queue_status(client: Client) {
+        let status = || async {
+            let response = client.get(uri!(sms_queue_status)).dispatch().await;
+            assert_eq!(Status::Ok, response.status());
+            serde_json::from_str::<SmsQueueStatus>(&response.into_string().await.unwrap()).unwrap()
+        };
+        let initial = status().await;
+        assert!(initial.enabled);
+        assert_eq!(10_000, initial.capacity);
+        assert_eq!(0, initial.depth);
+
+        // Challenges are accepted straight away, and sent soon after.
+        let response = client
+            .post(uri!(crate::api::auth::challenge))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&VoterChallengeRequest::example()).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Accepted, response.status());
+        let mut sent = status().await;
+        for _ in 0..100 {
+            if sent.sent > initial.sent {
+                break;
+            }
+            rocket::tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            sent = status().await;
+        }
+        assert_eq!(initial.sent + 1, sent.sent);
+        assert_eq!(initial.failed, sent.failed);
+        assert_eq!(0, sent.depth);
+    }
+
+    #[backend_test(admin)]
+    async fn db_stats(client: Client) {
+        // Listing admins reads the admins collection.
+        let response = client.get(uri!(get_admins(_))).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+
+        let response = client.get(uri!(db_stats)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let stats: DbStats = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(stats.slow_threshold_ms, 250);
+        let admin_finds = stats
+            .commands
+            .iter()
+            .find(|command| command.collection == "admins" && command.command == "find")
+            .unwrap();
+        assert!(admin_finds.count >= 1);
+        let all = admin_finds.buckets.last().unwrap();
+        assert_eq!(all.le_ms, None);
+        assert_eq!(all.count, admin_finds.count);
+    }
+
+    #[backend_test(admin)]
+    async fn clock_skew(client: Client) {
+        let response = client.get(uri!(clock_skew)).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let report: ClockSkewReport =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(report.warn_threshold_ms, 2000);
+        assert_eq!(report.max_threshold_ms, 60_000);
+        assert_eq!(report.check_interval_secs, 600);
+        // The skew was measured at launch, against a database on this machine.
+        let skew = report.last.unwrap();
+        assert!(skew.skew_ms.abs() < 2000, "{skew:?}");
+
+        // It is measured again periodically.
+        let registry = client.rocket().state::<TaskRegistry>().unwrap();
+        let task = registry
+            .list()
+            .into_iter()
+            .find(|task| task.name == "clock_skew_check")
+            .unwrap();
+        assert_eq!(task.state, TaskState::Pending);
+    }
+
+    #[backend_test(admin)]
+    async fn hmac_rotation(client: Client, db: Database) {
+        let report = || {
+            let client = &client;
+            async move {
+                let response = client.get(uri!(hmac_rotation)).dispatch().await;
+                assert_eq!(Status::Ok, response.status());
+                let raw_response = response.into_string().await.unwrap();
+                serde_json::from_str::<HmacRotationReport>(&raw_response).unwrap()
+            }
+        };
+
+        // Seed voters registered a day ago and just now.
+        let config = client.rocket().state::<Config>().unwrap();
+        let day_ago = Utc::now() - Duration::try_days(1).unwrap();
+        let mut old_id = [0; 12];
+        old_id[..4].copy_from_slice(&(day_ago.timestamp() as u32).to_be_bytes());
+        let voters = [
+            (Id::from_bytes(old_id).unwrap(), "+441234567890"),
+            (Id::new(), "+440987654321"),
+        ]
+        .map(|(id, sms)| Voter {
+            id,
+            voter: NewVoter::new(sms.parse::<Sms>().unwrap(), config),
+        });
+        Coll::<Voter>::from_db(&db)
+            .insert_many(voters, None)
+            .await
+            .unwrap();
+
+        // Without a change of secret, no voters are orphaned.
+        let unchanged = report().await;
+        assert_eq!(unchanged.rotated_at, None);
+        assert_eq!(unchanged.voters_before, 0);
+        assert_eq!(unchanged.voters_after, 2);
+
+        // After a change, voters registered before it are.
+        let hour_ago = Utc::now() - Duration::try_hours(1).unwrap();
+        Coll::<HmacCheck>::from_db(&db)
+            .update_one(
+                doc! {},
+                doc! { "$set": { "rotated_at": DateTime::from_chrono(hour_ago) } },
+                None,
+            )
+            .await
+            .unwrap();
+        let rotated = report().await;
+        assert_eq!(
+            rotated.rotated_at,
+            Some(DateTime::from_chrono(hour_ago).to_chrono())
+        );
+        assert_eq!(rotated.voters_before, 1);
+        assert_eq!(rotated.voters_after, 1);
+    }
+
+    #[backend_test(admin)]
+    async fn voter_lookup(client: Client, db: Database) {
+        // Create an election and a voter who has joined it.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        let config = client.rocket().state::<Config>().unwrap();
+        let sms = "+441234567890";
+        let question_id = *election.questions.keys().next().unwrap();
+        let allowed = AllowedQuestions {
+            confirmed: HashMap::from([(question_id, true)]),
+        };
+        let voter_id = Coll::<NewVoter>::from_db(&db)
+            .insert_one(NewVoter::new(sms.parse().unwrap(), config), None)
+            .await
+            .unwrap()
+            .inserted_id
+            .as_object_id()
+            .unwrap()
+            .into();
+        Coll::<VoterElection>::from_db(&db)
+            .insert_one(
+                VoterElection {
+                    voter_id,
+                    election_id: election.id,
+                    allowed_questions: allowed.clone(),
+                    joins: None,
+                    consent: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The voter is found with their questions for the election.
+        let response = client
+            .get(uri!(lookup_voter(election.id, sms)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let lookup: VoterLookup = serde_json::from_str(&raw_response).unwrap();
+        assert!(lookup.joined);
+        assert_eq!(lookup.allowed_questions, allowed);
+
+        // An unknown voter looks like one who has not joined.
+        let response = client
+            .get(uri!(lookup_voter(election.id, "+440987654321")))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let lookup: VoterLookup = serde_json::from_str(&raw_response).unwrap();
+        assert!(!lookup.joined);
+        assert_eq!(lookup.allowed_questions, AllowedQuestions::default());
+
+        // An invalid number is rejected.
+        let response = client
+            .get(uri!(lookup_voter(election.id, "not a number")))
+            .dispatch()
+            .await;
+        assert_eq!(Status::BadRequest, response.status());
+
+        // So is an unknown election.
+        let response = client
+            .get(uri!(lookup_voter(election.id + 1, sms)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
+
+        // Every lookup was audited, without the full number.
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "voter_lookup" }, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 4);
+        for entry in &entries {
+            assert_eq!(entry.admin, NewAdmin::example().username);
+            assert!(!entry.detail.contains("1234567890"));
+            assert!(!entry.detail.contains("0987654321"));
+        }
+        assert!(entries.iter().any(|entry| entry.detail.contains("***321")));
+        assert!(entries
+            .iter()
+            .any(|entry| entry.detail.contains("invalid SMS")));
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|entry| entry.detail.contains("***890"))
+                .count(),
+            2
+        );
+    }
+
+    #[backend_test(admin)]
+    async fn freeze_voters(client: Client, db: Database) {
+        let config = client.rocket().state::<Config>().unwrap();
+        let sms = "+441234567890";
+        let voters = Coll::<Voter>::from_db(&db);
+        Coll::<NewVoter>::from_db(&db)
+            .insert_one(NewVoter::new(sms.parse().unwrap(), config), None)
+            .await
+            .unwrap();
+        let get_voter = || async { voters.find_one(None, None).await.unwrap().unwrap() };
+        let freeze = |sms: &'static str, reason: Option<&str>| {
+            let request = VoterFreezeRequest {
+                reason: reason.map(str::to_string),
+            };
+            client
+                .post(uri!(freeze_voter(sms)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&request).unwrap())
+                .dispatch()
+        };
+
+        // Freezing records when and why.
+        let response = freeze(sms, Some("OTP fraud")).await;
+        assert_eq!(Status::Ok, response.status());
+        let voter = get_voter().await;
+        assert!(voter.frozen);
+        let voter_freeze = voter.freeze.clone().unwrap();
+        assert_eq!(voter_freeze.reason.as_deref(), Some("OTP fraud"));
+        assert!(voter_freeze.time <= Utc::now());
+
+        // Unfreezing clears both.
+        let response = client
+            .post(uri!(unfreeze_voter(sms)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let voter = get_voter().await;
+        assert!(!voter.frozen);
+        assert_eq!(voter.freeze, None);
+
+        // Unknown voters are not found, and invalid numbers are rejected.
+        let response = freeze("+440987654321", None).await;
+        assert_eq!(Status::NotFound, response.status());
+        let response = freeze("not a number", None).await;
+        assert_eq!(Status::BadRequest, response.status());
+
+        // The valid requests were audited, without the full number or reason.
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(
+                doc! { "action": { "$in": ["voter_freeze", "voter_unfreeze"] } },
+                None,
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let actions = entries
+            .iter()
+            .map(|entry| entry.action.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(actions, ["voter_freeze", "voter_unfreeze", "voter_freeze"]);
+        for entry in &entries {
+            assert!(!entry.detail.contains("1234567890"));
+            assert!(!entry.detail.contains("OTP fraud"));
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn voter_import(client: Client, db: Database) {
+        // Create an election, and a voter who exists but hasn't joined it.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        let config = client.rocket().state::<Config>().unwrap();
+        let hmac = |sms: &str| sms.parse::<Sms>().unwrap().into_hmac(config);
+        Coll::<NewVoter>::from_db(&db)
+            .insert_one(
+                NewVoter::new("+447700900003".parse().unwrap(), config),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Import good rows, a bad number, and an unknown group.
+        let roll = "sms,electorate,group
++447700900001,Societies,Quidditch
++447700900001,Societies,CompSoc
++447700900001,Courses,Maths
++447700900002,,
++447700900003,Courses,Physics
+not a number,Societies,Quidditch
++447700900004,Societies,Moongolf
++447700900004,Societies,Underwater Hockey
++447700900005,Courses,Maths
++447700900005,Courses,Physics
+";
+        let import = || {
+            client
+                .post(uri!(import_voters(election.id)))
+                .csrf()
+                .header(ContentType::CSV)
+                .body(roll)
+                .dispatch()
+        };
+        let response = import().await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let report: VoterImportReport = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(report.created, 2);
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.skipped, 0);
+        assert_eq!(report.rejected, 3);
+        let lines = report
+            .errors
+            .iter()
+            .map(|error| error.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![7, 9, 11]);
+        assert!(report.errors[0].error.contains("Invalid SMS number"));
+        assert!(report.errors[1].error.contains("Underwater Hockey"));
+        assert!(report.errors[2].error.contains("mutex"));
+
+        // The imported voters have joined with the questions for their groups.
+        let voters = Coll::<Voter>::from_db(&db);
+        let election = get_election_by_id(&db, election.id).await;
+        for (sms, joins) in [
+            (
+                "+447700900001",
+                Joins::from([
+                    (
+                        "Societies".to_string(),
+                        HashSet::from(["Quidditch".to_string(), "CompSoc".to_string()]),
+                    ),
+                    ("Courses".to_string(), HashSet::from(["Maths".to_string()])),
+                ]),
+            ),
+            ("+447700900002", Joins::new()),
+            (
+                "+447700900003",
+                Joins::from([(
+                    "Courses".to_string(),
+                    HashSet::from(["Physics".to_string()]),
+                )]),
+            ),
+        ] {
+            let voter = voters
+                .find_one(doc! { "sms_hmac": hmac(sms).to_bytestring() }, None)
+                .await
+                .unwrap()
+                .unwrap();
+            let allowed =
+                VoterElection::allowed_questions(&Coll::from_db(&db), voter.id, election.id, None)
+                    .await
+                    .unwrap();
+            assert_eq!(
+                allowed,
+                Some(AllowedQuestions::for_joins(&election, &joins).unwrap()),
+                "{sms}"
+            );
+        }
+        assert_eq!(election.metadata.joined_count, 3);
+
+        // Voters with rejected rows were left out entirely.
+        for sms in ["+447700900004", "+447700900005"] {
+            let voter = voters
+                .find_one(doc! { "sms_hmac": hmac(sms).to_bytestring() }, None)
+                .await
+                .unwrap();
+            assert!(voter.is_none(), "{sms}");
+        }
+
+        // Importing again leaves the voters who have joined alone.
+        let response = import().await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let report: VoterImportReport = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!((report.created, report.updated, report.skipped), (0, 0, 3));
+        assert_eq!(report.rejected, 3);
+
+        // A roll without the right columns is rejected outright.
+        let response = client
+            .post(uri!(import_voters(election.id)))
+            .csrf()
+            .header(ContentType::CSV)
+            .body("phone,electorate,group\n")
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+    }
+
+    #[backend_test(admin)]
+    async fn voter_import_gathers_rows(client: Client, db: Database) {
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        let config = client.rocket().state::<Config>().unwrap();
+        let hmac = |sms: &str| sms.parse::<Sms>().unwrap().into_hmac(config);
+
+        // A voter's rows may be split up, and a rejected row leaves its voter
+        // out even after their earlier rows were accepted.
+        let roll = "sms,electorate,group
++447700900001,Societies,Quidditch
++447700900002,Courses,Maths
++447700900001,Courses,Physics
++447700900002,Courses,Physics
++447700900001,Societies,CompSoc
+";
+        let response = client
+            .post(uri!(import_voters(election.id)))
+            .csrf()
+            .header(ContentType::CSV)
+            .body(roll)
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let report: VoterImportReport =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!((report.created, report.updated, report.skipped), (1, 0, 0));
+        assert_eq!(report.rejected, 1);
+        assert_eq!(report.errors[0].line, 5);
+        assert!(report.errors[0].error.contains("mutex"));
+
+        // The first voter joined with all their groups.
+        let voters = Coll::<Voter>::from_db(&db);
+        let election = get_election_by_id(&db, election.id).await;
+        let voter = voters
+            .find_one(
+                doc! { "sms_hmac": hmac("+447700900001").to_bytestring() },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let joins = Joins::from([
+            (
+                "Societies".to_string(),
+                HashSet::from(["Quidditch".to_string(), "CompSoc".to_string()]),
+            ),
+            (
+                "Courses".to_string(),
+                HashSet::from(["Physics".to_string()]),
+            ),
+        ]);
+        let allowed =
+            VoterElection::allowed_questions(&Coll::from_db(&db), voter.id, election.id, None)
+                .await
+                .unwrap();
+        assert_eq!(
+            allowed,
+            Some(AllowedQuestions::for_joins(&election, &joins).unwrap())
+        );
+        assert_eq!(election.metadata.joined_count, 1);
+
+        // The second was left out.
+        let voter = voters
+            .find_one(
+                doc! { "sms_hmac": hmac("+447700900002").to_bytestring() },
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(voter.is_none());
+    }
+
+    #[backend_test(admin)]
+    async fn voter_import_requires_no_consent(client: Client, db: Database) {
+        // Voters cannot be imported into an election they must consent to, as
+        // they would never accept it.
+        let mut spec = ElectionSpec::current_example();
+        spec.consent_text = Some("I agree to the terms of the election.".to_string());
+        let election = create_election_for_spec(&client, &spec).await;
+        let import = || {
+            client
+                .post(uri!(import_voters(election.id)))
+                .csrf()
+                .header(ContentType::CSV)
+                .body("sms,electorate,group\n+447700900001,Societies,Quidditch\n")
+                .dispatch()
+        };
+        let response = import().await;
+        assert_eq!(Status::BadRequest, response.status());
+        assert_no_matches::<VoterElection>(&db, doc! { "election_id": election.id }).await;
+        assert_eq!(
+            get_election_by_id(&db, election.id)
+                .await
+                .metadata
+                .joined_count,
+            0
+        );
+
+        // Once consent is no longer required, they can be.
+        let response = client
+            .patch(uri!(set_consent(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body("null")
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let response = import().await;
+        assert_eq!(Status::Ok, response.status());
+        let report: VoterImportReport =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(report.created, 1);
+        assert_eq!(
+            get_election_by_id(&db, election.id)
+                .await
+                .metadata
+                .joined_count,
+            1
+        );
+    }
+
+    #[backend_test(admin)]
+    async fn export_spec(client: Client) {
+        let spec = ElectionSpec::current_example();
+        let election = create_election_for_spec(&client, &spec).await;
+
+        // The exported spec is the one the election was created from, up to ordering.
+        let response = client
+            .get(uri!(election_spec(election.id)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some(
+                format!(
+                    "attachment; filename=\"election-{}-spec.json\"",
+                    election.id
+                )
+                .as_str()
+            )
+        );
+        let raw_response = response.into_string().await.unwrap();
+        let exported: ElectionSpec = serde_json::from_str(&raw_response).unwrap();
+        let mut expected = spec.clone();
+        expected
+            .electorates
+            .sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(exported, expected);
+
+        // Importing it gives an equivalent election, modulo its ID and keys.
+        let imported = create_election_for_spec(&client, &exported).await;
+        assert_ne!(imported.id, election.id);
+        assert_ne!(imported.crypto, election.crypto);
+        assert_eq!(
+            ElectionDescription {
+                id: election.id,
+                crypto: election.crypto.clone(),
+                ..imported
+            },
+            election
+        );
+
+        // Unknown elections have no spec.
+        let response = client
+            .get(uri!(election_spec(election.id + 100)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
+    }
+
+    #[backend_test(admin)]
+    async fn branding(client: Client, db: Database) {
+        // Branding must be a JSON object of limited size.
+        let mut spec = ElectionSpec::current_example();
+        for (branding, status) in [
+            (serde_json::json!("logo.png"), Status::UnprocessableEntity),
+            (
+                serde_json::json!({ "instructions": "x".repeat(MAX_BRANDING_SIZE) }),
+                Status::PayloadTooLarge,
+            ),
+            (
+                serde_json::json!({ "version": u64::MAX }),
+                Status::UnprocessableEntity,
+            ),
+        ] {
+            spec.branding = Some(branding);
+            let response = client
+                .post(uri!(create_election))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&spec).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), status);
+        }
+
+        // Create an election with valid branding, which voters can't see until it is published.
+        let branding = serde_json::json!({
+            "logo_url": "https://example.com/logo.png",
+            "theme_colour": "#7f2b3c",
+            "contact": { "email": "elections@example.com" },
+        });
+        spec.branding = Some(branding.clone());
+        let election = create_election_for_spec(&client, &spec).await;
+        assert_eq!(election.branding, Some(branding.clone()));
+        let draft = get_election_by_id(&db, election.id).await;
+        assert_eq!(ElectionDescription::from(draft.clone()).branding, None);
+        assert_eq!(ElectionSummary::from(draft).branding, None);
+        publish(&client, election.id).await;
+        let published = get_election_by_id(&db, election.id).await;
+        assert_eq!(
+            ElectionDescription::from(published).branding,
+            Some(branding.clone())
+        );
+
+        // Take a dump before rebranding.
+        let question_id = *election.questions.keys().min().unwrap();
+        let dump = || async {
+            let response = client
+                .get(uri!(crate::api::public::question_dump(
+                    election.id,
+                    question_id
+                )))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            response.into_string().await.unwrap()
+        };
+        let dump_before = dump().await;
+        assert!(!dump_before.contains("logo_url"));
+
+        // Rebrand the published election.
+        let set = |branding: Option<Value>| {
+            client
+                .patch(uri!(set_branding(election.id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&branding).unwrap())
+                .dispatch()
+        };
+        let rebranding = serde_json::json!({ "theme_colour": "#000000" });
+        let response = set(Some(rebranding.clone())).await;
+        assert_eq!(response.status(), Status::Ok);
+        let rebranded: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(rebranded.branding, Some(rebranding.clone()));
+        assert_eq!(rebranded.crypto, election.crypto);
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(stored.metadata.branding, Some(rebranding));
+        assert_eq!(stored.metadata.state, ElectionState::Published);
+
+        // The dump is unaffected.
+        assert_eq!(dump().await, dump_before);
+
+        // Oversized or unstorable rebranding is refused, and branding can be removed.
+        let response = set(Some(
+            serde_json::json!({ "instructions": "x".repeat(MAX_BRANDING_SIZE) }),
+        ))
+        .await;
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        let response = set(Some(serde_json::json!({ "version": u64::MAX }))).await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let response = set(None).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            get_election_by_id(&db, election.id).await.metadata.branding,
+            None
+        );
+
+        // Unknown elections can't be rebranded.
+        let response = client
+            .patch(uri!(set_branding(election.id + 100)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body("null")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(admin)]
+    async fn translations(client: Client) {
+        let welsh = |text: &str| Translations::from([("cy".to_string(), text.to_string())]);
+        let mut spec = ElectionSpec::current_example();
+        spec.name_translations = welsh("Etholiad Prawf");
+        spec.questions[0].description_translations = welsh("Pwy ddylai fod yn gapten?");
+        spec.questions[0].candidate_translations =
+            HashMap::from([("Chris Riches".to_string(), welsh("Chris Rhys"))]);
+
+        // Translations are kept alongside the canonical strings.
+        let election = create_election_for_spec(&client, &spec).await;
+        assert_eq!(election.name, spec.name);
+        assert_eq!(election.name_translations, spec.name_translations);
+        let question = election
+            .questions
+            .values()
+            .find(|question| question.description == spec.questions[0].description)
+            .unwrap();
+        assert_eq!(question.candidates, spec.questions[0].candidates);
+        assert_eq!(
+            question.description_translations,
+            spec.questions[0].description_translations
+        );
+        assert_eq!(
+            question.candidate_translations,
+            spec.questions[0].candidate_translations
+        );
+
+        // Language codes must be well-formed, translations must not be empty,
+        // and only candidates of the question can be translated.
+        let mut bad_code = spec.clone();
+        bad_code.name_translations =
+            Translations::from([("Welsh".to_string(), "Etholiad".to_string())]);
+        let mut empty = spec.clone();
+        empty.questions[0].description_translations = welsh("");
+        let mut not_candidate = spec.clone();
+        not_candidate.questions[0].candidate_translations =
+            HashMap::from([("Harry Potter".to_string(), welsh("Harri Potter"))]);
+        for bad_spec in [bad_code, empty, not_candidate] {
+            let response = client
+                .post(uri!(create_election))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&bad_spec).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn consent(client: Client, db: Database) {
+        // Consent text can be set in the spec, and is described to everyone.
+        let spec = ElectionSpec {
+            consent_text: Some("We will process your data.".to_string()),
+            consent_version: 1,
+            ..ElectionSpec::current_example()
+        };
+        let election = create_election_for_spec(&client, &spec).await;
+        assert_eq!(election.consent_text, spec.consent_text);
+        assert_eq!(election.consent_version, 1);
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(ElectionDescription::from(stored).consent_version, 1);
+
+        // Two voters accept it, and one joined before it was required.
+        let joined = |consent: Option<u32>| VoterElection {
+            voter_id: Id::new(),
+            election_id: election.id,
+            allowed_questions: AllowedQuestions::default(),
+            joins: None,
+            consent: consent.map(|version| ConsentAcceptance {
+                version,
+                accepted_at: DateTime::now(),
+            }),
+        };
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        voter_elections
+            .insert_many([joined(Some(1)), joined(Some(1)), joined(None)], None)
+            .await
+            .unwrap();
+        let get_summary = || async {
+            let response = client
+                .get(uri!(election_consent(election.id)))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<ConsentSummary>(&response.into_string().await.unwrap()).unwrap()
+        };
+        let mut expected = ConsentSummary {
+            election_id: election.id,
+            consent_text: spec.consent_text.clone(),
+            consent_version: 1,
+            accepted: HashMap::from_iter([(1, 2)]),
+            stale: 0,
+            not_accepted: 1,
+        };
+        assert_eq!(get_summary().await, expected);
+
+        // Changing the text bumps its version, reporting but keeping the voters
+        // who accepted the old one.
+        let set = |text: Option<&str>| {
+            client
+                .patch(uri!(set_consent(election.id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&text).unwrap())
+                .dispatch()
+        };
+        let response = set(Some("We will process your data for 30 days.")).await;
+        assert_eq!(response.status(), Status::Ok);
+        let summary: ConsentSummary =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        expected.consent_text = Some("We will process your data for 30 days.".to_string());
+        expected.consent_version = 2;
+        expected.stale = 2;
+        assert_eq!(summary, expected);
+        assert_eq!(get_summary().await, expected);
+        assert_eq!(
+            voter_elections
+                .count_documents(doc! { "election_id": election.id }, None)
+                .await
+                .unwrap(),
+            3
+        );
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(stored.metadata.consent_version, 2);
+        assert_eq!(
+            stored.metadata.last_modified_by.as_deref(),
+            Some(DEFAULT_ADMIN_USERNAME)
+        );
+
+        // Consent can stop being required, and empty text is refused.
+        assert_eq!(set(None).await.status(), Status::Ok);
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(stored.metadata.consent_text, None);
+        assert_eq!(stored.metadata.consent_version, 2);
+        assert_eq!(set(Some("")).await.status(), Status::UnprocessableEntity);
+
+        // Unknown elections have no consent text.
+        let response = client
+            .get(uri!(election_consent(election.id + 100)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client
+            .patch(uri!(set_consent(election.id + 100)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body("null")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(admin)]
+    async fn board_embargo(client: Client, db: Database) {
+        // Embargoes can be set in the spec, and are only described to admins.
+        let until = Utc::now() + Duration::try_days(1).unwrap();
+        let spec = ElectionSpec {
+            embargo_public_board_until: Some(until),
+            ..ElectionSpec::current_example()
+        };
+        let election = create_election_for_spec(&client, &spec).await;
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(
+            election.embargo_public_board_until,
+            Some(DateTime::from_chrono(until).to_chrono())
+        );
+        assert!(stored.is_board_embargoed_at(Utc::now()));
+        assert!(ElectionDescription::from(stored)
+            .embargo_public_board_until
+            .is_none());
+
+        // They can be moved at any time, and lifted.
+        publish(&client, election.id).await;
+        let set = |until: Option<chrono::DateTime<Utc>>| {
+            client
+                .patch(uri!(set_board_embargo(election.id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&until).unwrap())
+                .dispatch()
+        };
+        let later = until + Duration::try_days(1).unwrap();
+        let response = set(Some(later)).await;
+        assert_eq!(response.status(), Status::Ok);
+        let moved: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(
+            moved.embargo_public_board_until,
+            Some(DateTime::from_chrono(later).to_chrono())
+        );
+        let response = set(None).await;
+        assert_eq!(response.status(), Status::Ok);
+        let stored = get_election_by_id(&db, election.id).await;
+        assert_eq!(stored.metadata.embargo_public_board_until, None);
+        assert_eq!(stored.metadata.state, ElectionState::Published);
+
+        // Unknown elections can't be embargoed.
+        let response = client
+            .patch(uri!(set_board_embargo(election.id + 100)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body("null")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(admin)]
+    async fn repair_stale_counters(client: Client, db: Database) {
+        // Create an active election whose ballots were inserted behind the counters' backs,
+        // as happens when restoring ballots from a backup.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        publish(&client, election.id).await;
+        insert_ballots(&db, election.id).await;
+        let election = get_election_by_id(&db, election.id).await;
+        let question_id = |spec: QuestionSpec| {
+            election
+                .questions
+                .values()
+                .find(|q| q.description == spec.description)
+                .unwrap()
+                .id
+        };
+        let q1 = question_id(QuestionSpec::example1());
+        let q3 = question_id(QuestionSpec::example3());
+
+        // The counters with ballots are flagged; the one without is fine.
+        let response = client
+            .get(uri!(election_counters(election.id)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let statuses: Vec<CounterStatus> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(statuses.len(), election.questions.len());
+        for status in &statuses {
+            assert_eq!(status.next, Some(1));
+            assert_eq!(status.mismatch, status.question_id != q3);
+            assert_eq!(status.max_ballot_id.is_some(), status.question_id != q3);
+        }
+
+        // Repairing is refused while the election is receiving votes, unless forced.
+        let response = client
+            .post(uri!(repair_counters(election.id, _)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Conflict, response.status());
+        let response = client
+            .post(uri!(repair_counters(election.id, Some(true))))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let repaired: Vec<CounterStatus> = serde_json::from_str(&raw_response).unwrap();
+        for status in &repaired {
+            assert!(!status.mismatch);
+            assert_eq!(
+                status.next,
+                Some(status.max_ballot_id.map_or(1, |max| max + 1))
+            );
+        }
+
+        // The counters now agree with the ballots, and only the forced repair was audited.
         let response = client
-            .delete(uri!(delete_admin(AdminCredentials::example2().username)))
+            .get(uri!(election_counters(election.id)))
             .dispatch()
             .await;
-        assert_eq!(Status::Ok, response.status());
-
-        // Ensure the admin has been deleted.
-        let count = admins.count_documents(None, None).await.unwrap();
-        assert_eq!(count, 2);
-        let expected = vec![
-            DEFAULT_ADMIN_USERNAME.to_string(),
-            AdminCredentials::example1().username,
-        ];
-        let remaining_admins: Vec<String> = admins
-            .find(None, None)
+        let raw_response = response.into_string().await.unwrap();
+        let statuses: Vec<CounterStatus> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(statuses, repaired);
+        let entries: Vec<AuditLogEntry> = Coll::<AuditLogEntry>::from_db(&db)
+            .find(doc! { "action": "counter_repair" }, None)
             .await
             .unwrap()
-            .map_ok(|a| a.admin.username)
             .try_collect()
             .await
             .unwrap();
-        assert_eq!(expected, remaining_admins);
-    }
-
-    #[backend_test(admin)]
-    async fn bad_create_admin(client: Client, db: Database) {
-        // Try empty username.
-        let credentials = AdminCredentials {
-            username: "".to_string(),
-            password: "foo".to_string(),
-        };
-        create_admin_expect_status(&client, &credentials, Status::BadRequest).await;
-
-        // Try empty password.
-        let credentials = AdminCredentials {
-            username: "foo".to_string(),
-            password: "".to_string(),
-        };
-        create_admin_expect_status(&client, &credentials, Status::BadRequest).await;
-
-        // Try empty both.
-        create_admin_expect_status(&client, &AdminCredentials::empty(), Status::BadRequest).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].admin, NewAdmin::example().username);
 
-        // Try duplicate username.
-        create_admin_expect_status(&client, &AdminCredentials::example1(), Status::BadRequest)
+        // Log in as a voter who may answer the first question, and cast successfully.
+        join_as_example_voter(&client, &db, election.id, q1).await;
+        let ballot_specs = vec![BallotSpec {
+            question: q1,
+            candidate: election.questions[&q1].candidates[0].clone(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(crate::api::voting::cast_ballots(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
             .await;
-
-        // Ensure no admins were created.
-        let num_admins = count_matches::<Admin>(&db, doc! {}).await;
-        assert_eq!(num_admins, 2); // Default admin and test admin.
-    }
-
-    #[backend_test(admin)]
-    async fn get_admins(client: Client) {
-        // Create some admins.
-        create_admin(&client, &AdminCredentials::example2()).await;
-        create_admin(&client, &AdminCredentials::example3()).await;
-
-        // Check that all admins are listed.
-        let response = client.get(uri!(get_admins)).dispatch().await;
         assert_eq!(Status::Ok, response.status());
-        assert!(response.body().is_some());
-
-        let admins: Vec<String> =
-            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
-        let expected = vec![
-            DEFAULT_ADMIN_USERNAME.to_string(),
-            AdminCredentials::example1().username,
-            AdminCredentials::example2().username,
-            AdminCredentials::example3().username,
-        ];
-        assert_eq!(admins, expected);
     }
 
     #[backend_test(admin)]
-    async fn create_election(client: Client, db: Database) {
-        // Create an election.
+    async fn write_ins(client: Client, db: Database) {
+        // Nobody may name a candidate after the write-in candidate.
+        let mut spec = ElectionSpec::current_example();
+        spec.questions[0]
+            .candidates
+            .push(WRITE_IN_CANDIDATE.to_string());
         let response = client
             .post(uri!(create_election))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&ElectionSpec::current_example()).unwrap())
+            .body(serde_json::to_string(&spec).unwrap())
             .dispatch()
             .await;
-        assert_eq!(Status::Ok, response.status());
-        let raw_response = response.into_string().await.unwrap();
-        let response_election: ElectionDescription = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
 
-        // Ensure it is present in the DB.
-        let elections = Coll::<ElectionMetadata>::from_db(&db);
-        let with_name = doc! { "name": &ElectionSpec::current_example().name };
-        let inserted_election = elections
-            .find_one(with_name.clone(), None)
+        // Publishing gives questions that allow write-ins the write-in
+        // candidate, with zero totals, and leaves the others alone.
+        let mut spec = ElectionSpec::current_example();
+        spec.questions[0].allow_write_in = true;
+        let election = create_election_for_spec(&client, &spec).await;
+        publish(&client, election.id).await;
+        let election = get_election_by_id(&db, election.id).await;
+        let (q1, q2) = (1, 2);
+        assert_eq!(
+            election.questions[&q1].candidates.last().unwrap(),
+            WRITE_IN_CANDIDATE
+        );
+        assert!(!election.questions[&q2]
+            .candidates
+            .contains(&WRITE_IN_CANDIDATE.to_string()));
+        let write_in_totals = doc! {
+            "election_id": election.id,
+            "question_id": q1,
+            "candidate_name": WRITE_IN_CANDIDATE,
+        };
+        assert_eq!(
+            count_matches::<CandidateTotals>(&db, write_in_totals).await,
+            1
+        );
+        // The spec leaves it out again, since publishing adds it.
+        assert_eq!(
+            ElectionSpec::from(election.clone()).questions,
+            spec.questions
+        );
+
+        // Confirm some write-ins, and a vote for a listed candidate.
+        let question = &election.questions[&q1];
+        let mut rng = rand::thread_rng();
+        let names = [
+            Some("Minerva McGonagall"),
+            Some("Rubeus Hagrid"),
+            Some("Minerva McGonagall"),
+            None,
+        ];
+        let confirmed = names
+            .into_iter()
+            .zip(1..)
+            .map(|(name, ballot_id)| {
+                let yes = match name {
+                    Some(_) => WRITE_IN_CANDIDATE.to_string(),
+                    None => question.candidates[0].clone(),
+                };
+                let no = question
+                    .candidates
+                    .iter()
+                    .filter(|candidate| **candidate != yes)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let mut ballot =
+                    BallotCore::new(ballot_id, q1, yes, no, &election, &mut rng).unwrap();
+                ballot.write_in_name = name.map(str::to_string);
+                ballot.confirm(&mut HashMap::new())
+            })
+            .collect::<Vec<_>>();
+        Coll::<BallotCore<Confirmed>>::from_db(&db)
+            .insert_many(confirmed, None)
             .await
-            .unwrap()
             .unwrap();
+
+        // The names are counted, most common first.
+        let response = client
+            .get(uri!(write_ins(election.id, q1)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let counts: Vec<WriteInCount> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
         assert_eq!(
-            ElectionMetadata::from(ElectionSpec::current_example()),
-            inserted_election
+            counts,
+            vec![
+                WriteInCount {
+                    name: "Minerva McGonagall".to_string(),
+                    count: 2,
+                },
+                WriteInCount {
+                    name: "Rubeus Hagrid".to_string(),
+                    count: 1,
+                },
+            ]
         );
 
-        // Ensure the counters were created.
-        for question_id in response_election.questions.keys() {
-            let counter = Coll::<Counter>::from_db(&db)
-                .find_one(
-                    doc! {"_id": ballot_counter_id(response_election.id, *question_id)},
-                    None,
-                )
-                .await
-                .unwrap()
-                .unwrap();
-            assert_eq!(counter.next, 1);
-        }
+        // Questions without write-ins have none to count.
+        let response = client
+            .get(uri!(write_ins(election.id, q2)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
     }
 
     #[backend_test(admin)]
-    async fn publish_archive(client: Client, db: Database) {
-        // Try to publish/archive an election that doesn't exist.
-        publish_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
-        archive_expect_status(&client, rand::thread_rng().gen(), Status::BadRequest).await;
-
-        // Create an election.
-        let spec = ElectionSpec::current_example();
-        let election = create_election_for_spec(&client, &spec).await;
+    async fn finalize_preview(client: Client, db: Database) {
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        publish(&client, election.id).await;
+        let election = get_election_by_id(&db, election.id).await;
+        let mut question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+        question_ids.sort_unstable();
+        let (q1, q2) = (question_ids[0], question_ids[1]);
 
-        // Archive it.
-        archive(&client, election.id).await;
-        let archived = get_election_by_id(&db, election.id).await;
-        assert_eq!(archived.metadata.state, ElectionState::Archived);
+        // Seed unconfirmed ballots cast at controlled times.
+        let now = Utc::now();
+        let ago = |secs| now - Duration::try_seconds(secs).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut next_ballot_id = 0;
+        let mut ballot = |question_id: QuestionId, created| {
+            let question = &election.questions[&question_id];
+            next_ballot_id += 1;
+            let mut ballot = BallotCore::new(
+                next_ballot_id,
+                question.id,
+                question.candidates[0].clone(),
+                question.candidates[1..].iter().cloned(),
+                &election,
+                &mut rng,
+            )
+            .unwrap();
+            ballot.creation_time = ago(created);
+            ballot
+        };
+        let unconfirmed = vec![
+            ballot(q1, 10),
+            ballot(q1, 4 * 60),
+            ballot(q1, 30 * 60),
+            ballot(q1, 3 * 60 * 60),
+            ballot(q2, 6 * 60),
+            ballot(q2, 2 * 60 * 60),
+            ballot(q2, 24 * 60 * 60),
+        ];
+        // Ballots already audited or confirmed are left alone by the finalizer.
+        let confirmed = ballot(q1, 10).confirm(None);
+        let audited = ballot(q2, 10).audit();
+        Coll::<BallotCore<Unconfirmed>>::from_db(&db)
+            .insert_many(unconfirmed, None)
+            .await
+            .unwrap();
+        Coll::<BallotCore<Confirmed>>::from_db(&db)
+            .insert_one(confirmed, None)
+            .await
+            .unwrap();
+        Coll::<BallotCore<Audited>>::from_db(&db)
+            .insert_one(audited, None)
+            .await
+            .unwrap();
 
-        // Check we can't publish it or archive it again.
-        publish_expect_status(&client, election.id, Status::BadRequest).await;
-        archive_expect_status(&client, election.id, Status::BadRequest).await;
+        // The ballots are counted by question and age.
+        let expected_q1 = UnconfirmedByAge {
+            under_5_minutes: 2,
+            under_1_hour: 1,
+            older: 1,
+        };
+        let expected_q2 = UnconfirmedByAge {
+            under_5_minutes: 0,
+            under_1_hour: 1,
+            older: 2,
+        };
+        let expected_total = UnconfirmedByAge {
+            under_5_minutes: 2,
+            under_1_hour: 2,
+            older: 3,
+        };
+        let response = client
+            .get(uri!(finalize_preview(election.id)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let preview: FinalizePreview =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(preview.as_of >= now);
+        assert_eq!(preview.total, expected_total);
+        assert_eq!(preview.total.total(), 7);
+        assert_eq!(
+            preview.questions,
+            HashMap::from([(q1, expected_q1), (q2, expected_q2)])
+        );
 
-        // Create a new election.
-        let election = create_election_for_spec(&client, &spec).await;
+        // Nothing was audited by previewing.
+        let unconfirmed_filter = doc! {"election_id": election.id, "state": Unconfirmed};
+        assert_eq!(
+            count_matches::<AnyBallot>(&db, unconfirmed_filter.clone()).await,
+            7
+        );
 
-        // Publish it.
-        publish(&client, election.id).await;
-        let published = get_election_by_id(&db, election.id).await;
-        assert_eq!(published.metadata.state, ElectionState::Published);
+        // Archiving reports the same ballots, and audits them.
+        let response = client
+            .post(uri!(archive_election(election.id)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let archived: FinalizePreview =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(archived.total.total(), 7);
+        assert_eq!(archived.questions, preview.questions);
+        assert_no_matches::<AnyBallot>(&db, unconfirmed_filter).await;
 
-        // Check we can't publish it again.
-        publish_expect_status(&client, election.id, Status::BadRequest).await;
+        // Now there is nothing left to finalize.
+        let response = client
+            .get(uri!(finalize_preview(election.id)))
+            .dispatch()
+            .await;
+        let preview: FinalizePreview =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(preview.total, UnconfirmedByAge::default());
+        assert!(preview.questions.is_empty());
 
-        // Archive it.
-        archive(&client, election.id).await;
-        let archived = get_election_by_id(&db, election.id).await;
-        assert_eq!(archived.metadata.state, ElectionState::Archived);
+        // Previews of elections that don't exist are not found.
+        let response = client
+            .get(uri!(finalize_preview(election.id + 1)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
     }
 
     #[backend_test(admin)]
-    async fn modify_election(client: Client) {
-        // Try to modify an election that doesn't exist.
-        modify_expect_status(
-            &client,
-            rand::thread_rng().gen(),
-            &ElectionSpec::current_example(),
-            Status::NotFound,
-        )
-        .await;
-
-        // Create an election.
-        let mut spec = ElectionSpec::future_example();
-        let election = create_election_for_spec(&client, &spec).await;
-
-        // Modify it.
-        spec.name = "New Name".to_string();
-        let modified = modify_election_with_spec(&client, election.id, &spec).await;
-        assert_eq!(modified.name, spec.name);
-        assert_eq!(modified.state, election.state);
-        assert_eq!(modified.start_time, election.start_time);
-        assert_eq!(modified.end_time, election.end_time);
-        assert_eq!(modified.electorates, election.electorates);
-
-        // Publish it.
-        publish(&client, election.id).await;
-
-        // Modify it again.
-        spec.start_time = Utc::now();
-        let modified = modify_election_with_spec(&client, election.id, &spec).await;
-        assert_eq!(modified.name, spec.name);
-        assert_eq!(modified.state, ElectionState::Draft);
-        assert!(modified.start_time - spec.start_time < Duration::try_seconds(1).unwrap());
-        assert_eq!(modified.end_time, election.end_time);
-        assert_eq!(modified.electorates, election.electorates);
-
-        // Re-publish.
+    async fn election_activity(client: Client, db: Database) {
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
         publish(&client, election.id).await;
+        let other = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
+        publish(&client, other.id).await;
+        let election = get_election_by_id(&db, election.id).await;
+        let other = get_election_by_id(&db, other.id).await;
 
-        // Ensure we can't modify due to the new start date.
-        modify_expect_status(
-            &client,
-            election.id,
-            &ElectionSpec::current_example(),
-            Status::BadRequest,
-        )
-        .await;
-
-        // Archive it.
-        archive(&client, election.id).await;
-
-        // Ensure we can't modify an archived election.
-        modify_expect_status(
-            &client,
-            election.id,
-            &ElectionSpec::current_example(),
-            Status::BadRequest,
-        )
-        .await;
-
-        // Ensure we can't modify an election that went straight from draft to archived
-        // while still being before the start time.
-        let election = create_election_for_spec(&client, &ElectionSpec::future_example()).await;
-        archive(&client, election.id).await;
-        modify_expect_status(
-            &client,
-            election.id,
-            &ElectionSpec::current_example(),
-            Status::BadRequest,
-        )
-        .await;
-    }
+        // Seed ballots at controlled times.
+        let now = Utc::now();
+        let ago = |secs| now - Duration::try_seconds(secs).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut next_ballot_id = 0;
+        let mut ballot = |election: &Election, created| {
+            let question = election.questions.values().next().unwrap();
+            next_ballot_id += 1;
+            let mut ballot = BallotCore::new(
+                next_ballot_id,
+                question.id,
+                question.candidates[0].clone(),
+                question.candidates[1..].iter().cloned(),
+                election,
+                &mut rng,
+            )
+            .unwrap();
+            ballot.creation_time = ago(created);
+            ballot
+        };
+        let changed_at = |secs| Some(DateTime::from_chrono(ago(secs)));
+        let unconfirmed = vec![
+            ballot(&election, 10),
+            ballot(&election, 10),
+            // Other elections are not counted.
+            ballot(&other, 10),
+        ];
+        let mut audited = ballot(&election, 100).audit();
+        audited.state_changed_at = changed_at(95);
+        // Ballots cast before the window still count when they change state
+        // within it.
+        let mut confirmed_late = ballot(&election, 60 * 60).confirm(None);
+        confirmed_late.state_changed_at = changed_at(200);
+        // Ballots cast and confirmed before the window are not counted.
+        let mut confirmed_early = ballot(&election, 2 * 60 * 60).confirm(None);
+        confirmed_early.state_changed_at = changed_at(2 * 60 * 60);
+        Coll::<BallotCore<Unconfirmed>>::from_db(&db)
+            .insert_many(unconfirmed, None)
+            .await
+            .unwrap();
+        Coll::<BallotCore<Audited>>::from_db(&db)
+            .insert_one(audited, None)
+            .await
+            .unwrap();
+        Coll::<BallotCore<Confirmed>>::from_db(&db)
+            .insert_many([confirmed_late, confirmed_early], None)
+            .await
+            .unwrap();
 
-    #[backend_test(admin)]
-    async fn delete_election(client: Client, db: Database) {
-        // Try to delete an election that doesn't exist.
-        delete_expect_status(&client, rand::thread_rng().gen(), Status::NotFound).await;
+        let response = client
+            .get(uri!(election_activity(
+                election.id,
+                Some("5m"),
+                Some("30s")
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let report: ActivityReport =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(report.bucket_secs, 30);
+        assert_eq!(report.buckets.len(), 10);
+        assert_eq!(
+            report.window_end - report.window_start,
+            Duration::try_minutes(5).unwrap()
+        );
+        assert!(report.window_end > now);
+        assert_eq!(report.window_start.timestamp() % 30, 0);
+        assert_eq!(report.buckets[0].start, report.window_start);
 
-        // Create an election.
-        let spec = ElectionSpec::current_example();
-        let election = create_election_for_spec(&client, &spec).await;
+        // Each event is counted in the bucket containing it.
+        let bucket_of = |secs| ((ago(secs) - report.window_start).num_seconds() / 30) as usize;
+        let mut expected: Vec<(u64, u64, u64)> = vec![(0, 0, 0); 10];
+        expected[bucket_of(10)].0 += 2;
+        expected[bucket_of(100)].0 += 1;
+        expected[bucket_of(95)].1 += 1;
+        expected[bucket_of(200)].2 += 1;
+        let counts = report
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.cast, bucket.audited, bucket.confirmed))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, expected);
 
-        // Delete it.
-        delete(&client, election.id).await;
-        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+        // The counters are reported for every question.
+        assert_eq!(report.counters.len(), election.questions.len());
+        assert!(report
+            .counters
+            .windows(2)
+            .all(|pair| pair[0].question_id < pair[1].question_id));
 
-        // Create a new election.
-        let election = create_election_for_spec(&client, &spec).await;
+        // Windows and buckets default to 5m and 30s.
+        let response = client
+            .get(uri!(election_activity(
+                election.id,
+                None::<&str>,
+                None::<&str>
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let report: ActivityReport =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(report.bucket_secs, 30);
+        assert_eq!(report.buckets.len(), 10);
 
-        // Publish it.
-        publish(&client, election.id).await;
+        // Malformed, indivisible, overly long and overly fine windows are rejected.
+        for (window, bucket) in [
+            ("5", "30s"),
+            ("5m", "0s"),
+            ("5m", "-30s"),
+            ("5m", "7s"),
+            ("2m", "5m"),
+            ("25h", "1h"),
+            ("24h", "1m"),
+        ] {
+            let response = client
+                .get(uri!(election_activity(
+                    election.id,
+                    Some(window),
+                    Some(bucket)
+                )))
+                .dispatch()
+                .await;
+            assert_eq!(Status::BadRequest, response.status(), "{window} {bucket}");
+        }
 
-        // Check it can't be deleted.
-        delete_expect_status(&client, election.id, Status::BadRequest).await;
-        get_election_by_id(&db, election.id).await;
+        // Unknown elections have no activity.
+        let response = client
+            .get(uri!(election_activity(
+                rand::thread_rng().gen::<u32>(),
+                None::<&str>,
+                None::<&str>
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
+    }
 
-        // Archive it.
-        archive(&client, election.id).await;
+    #[backend_test(admin)]
+    async fn election_health(client: Client, db: Database) {
+        let health = |election_id| {
+            let client = &client;
+            async move {
+                let response = client
+                    .get(uri!(election_health(election_id)))
+                    .dispatch()
+                    .await;
+                assert_eq!(Status::Ok, response.status());
+                let raw_response = response.into_string().await.unwrap();
+                serde_json::from_str::<ElectionHealth>(&raw_response).unwrap()
+            }
+        };
 
-        // Delete it.
-        delete(&client, election.id).await;
-        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
+        // Unknown elections have no health.
+        let response = client
+            .get(uri!(election_health(rand::thread_rng().gen::<u32>())))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
 
-        // Create an active election.
-        let election = create_election_for_spec(&client, &spec).await;
+        // A freshly published election is healthy.
+        let election = create_election_for_spec(&client, &ElectionSpec::current_example()).await;
         publish(&client, election.id).await;
+        let healthy = health(election.id).await;
+        assert!(healthy.healthy, "{:?}", healthy.problems);
+        assert_eq!(healthy.state, ElectionState::Published);
+        assert_eq!(healthy.timing, ElectionTiming::Current);
+        assert!(healthy.finalizer_scheduled);
+        assert_eq!(healthy.questions.len(), election.questions.len());
+        for question in &healthy.questions {
+            assert!(!question.counter.mismatch);
+            assert!(question.missing_totals.is_empty());
+        }
 
-        // Cast, audit, and confirm various votes.
+        // Ballots inserted behind the counters' backs leave them stale, and
+        // a lost totals document is noticed.
         insert_ballots(&db, election.id).await;
-        let voters = insert_allowed_questions(&client, &db, election.id).await;
-
-        // Delete it.
-        archive(&client, election.id).await;
-        delete(&client, election.id).await;
-        let filter = doc! {
-            "election_id": election.id,
-        };
-        assert_no_matches::<Election>(&db, u32_id_filter(election.id)).await;
-        assert_no_matches::<Counter>(&db, u32_id_filter(election.id)).await;
-        assert_no_matches::<AnyBallot>(&db, filter.clone()).await;
-        assert_no_matches::<CandidateTotals>(&db, filter).await;
-        let field_name = format!("allowed_questions.{}", election.id);
-        let filter = doc! {
-            &field_name: {
-                "$exists": true,
-            }
-        };
-        assert_no_matches::<Voter>(&db, filter).await;
-        // Check we didn't accidentally remove allowed questions from a different election.
-        let other_voter = Coll::<Voter>::from_db(&db)
-            .find_one(voters[3].as_doc(), None)
+        let q1 = get_election_by_id(&db, election.id)
             .await
-            .unwrap()
+            .questions
+            .into_values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap();
+        Coll::<CandidateTotals>::from_db(&db)
+            .delete_one(
+                doc! {
+                    "election_id": election.id,
+                    "question_id": q1.id,
+                    "candidate_name": &q1.candidates[0],
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let unhealthy = health(election.id).await;
+        assert!(!unhealthy.healthy);
+        let q1_health = unhealthy
+            .questions
+            .iter()
+            .find(|question| question.counter.question_id == q1.id)
             .unwrap();
-        assert!(!other_voter.allowed_questions.is_empty());
+        assert!(q1_health.counter.mismatch);
+        assert_eq!(q1_health.missing_totals, [q1.candidates[0].clone()]);
+        assert!(q1_health.ballots.unconfirmed + q1_health.ballots.confirmed > 0);
+        assert!(unhealthy
+            .problems
+            .iter()
+            .any(|problem| problem.contains("candidate totals")));
     }
 
     #[backend_test(admin)]
@@ -844,6 +6816,11 @@ mod tests {
         assert_no_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter.clone()).await;
         let final_audited = count_matches::<Ballot<Audited>>(&db, audited_filter).await;
         assert_eq!(final_audited, audited + unconfirmed);
+
+        // Check the finalizer recorded what it did before archiving returned.
+        let finalization = get_finalization(&client, election.id).await;
+        assert_eq!(u64::from(finalization.ballots_audited), unconfirmed);
+        assert_eq!(finalization.attempts, 1);
     }
 
     #[backend_test(admin)]
@@ -867,8 +6844,15 @@ mod tests {
         };
         let audited = count_matches::<Ballot<Audited>>(&db, audited_filter.clone()).await;
 
+        // Nothing has been finalized yet.
+        let response = client
+            .get(uri!(election_finalization(election.id)))
+            .dispatch()
+            .await;
+        assert_eq!(Status::NotFound, response.status());
+
         // Publish it, causing a finalizer to be scheduled that should immediately trigger.
-        publish(&client, election.id).await;
+        publish_ended(&client, election.id).await;
         // (hopefully not flaky) sleep to make sure the finalizers have gone through.
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
@@ -876,6 +6860,290 @@ mod tests {
         assert_no_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter.clone()).await;
         let final_audited = count_matches::<Ballot<Audited>>(&db, audited_filter).await;
         assert_eq!(final_audited, audited + unconfirmed);
+
+        // Check the finalizer recorded what it did, and that admins see the same.
+        let finalization = get_finalization(&client, election.id).await;
+        assert_eq!(u64::from(finalization.ballots_audited), unconfirmed);
+        assert_eq!(finalization.attempts, 1);
+        assert!(finalization.completed_at.is_some());
+        assert_eq!(finalization.last_error, None);
+        let description =
+            ElectionDescription::for_admin(get_election_by_id(&db, election.id).await);
+        assert_eq!(description.finalization, Some(finalization));
+        let description = ElectionDescription::from(get_election_by_id(&db, election.id).await);
+        assert_eq!(description.finalization, None);
+    }
+
+    #[backend_test(admin)]
+    async fn finalize_question_early(client: Client, db: Database) {
+        // Questions may not close outside the election.
+        let mut spec = ElectionSpec::current_example();
+        for end_time in [
+            spec.start_time,
+            spec.end_time + Duration::try_seconds(1).unwrap(),
+        ] {
+            spec.questions[0].end_time = Some(end_time);
+            let response = client
+                .post(uri!(create_election))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&spec).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+        }
+
+        // Create an election whose first question closes shortly, and add votes.
+        let close_time = Utc::now() + Duration::try_seconds(2).unwrap();
+        spec.questions[0].end_time = Some(close_time);
+        let election = create_election_for_spec(&client, &spec).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == spec.questions[0].description)
+            .unwrap();
+        let q2 = election
+            .questions
+            .values()
+            .find(|q| q.description == spec.questions[1].description)
+            .unwrap();
+        assert_eq!(
+            q1.end_time.unwrap().timestamp_millis(),
+            close_time.timestamp_millis()
+        );
+        assert_eq!(q2.end_time, None);
+        publish(&client, election.id).await;
+        insert_ballots(&db, election.id).await;
+
+        // Only the early-closing question has its own finalizer.
+        let finalizers = client.rocket().state::<ElectionFinalizers>().unwrap();
+        assert!(finalizers.has_question_finalizer(election.id, q1.id).await);
+        assert!(!finalizers.has_question_finalizer(election.id, q2.id).await);
+        let unconfirmed_filter = |question_id: QuestionId| {
+            doc! {
+                "election_id": election.id,
+                "question_id": question_id,
+                "state": Unconfirmed,
+            }
+        };
+        assert_ne!(
+            count_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter(q1.id)).await,
+            0
+        );
+        let q2_unconfirmed =
+            count_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter(q2.id)).await;
+        assert_ne!(q2_unconfirmed, 0);
+
+        // (hopefully not flaky) sleep until shortly after the question closes.
+        tokio::time::sleep(tokio::time::Duration::from_millis(2500)).await;
+
+        // The question's unconfirmed ballots were audited, but not the other's.
+        assert!(!finalizers.has_question_finalizer(election.id, q1.id).await);
+        assert_no_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter(q1.id)).await;
+        assert_eq!(
+            count_matches::<Ballot<Unconfirmed>>(&db, unconfirmed_filter(q2.id)).await,
+            q2_unconfirmed
+        );
+        assert!(finalizers.has_finalizer(election.id).await);
+    }
+
+    #[backend_test(admin)]
+    async fn end_warning(client: Client, db: Database) {
+        // Create an election ending shortly and add some votes.
+        let mut spec = ElectionSpec::current_example();
+        spec.end_time = Utc::now() + Duration::try_seconds(3).unwrap();
+        let election = create_election_for_spec(&client, &spec).await;
+        insert_ballots(&db, election.id).await;
+
+        // Publish it behind the managed finalizers' backs, so only ours know about it.
+        let update = doc! {
+            "$set": {
+                "state": ElectionState::Published,
+            }
+        };
+        Coll::<Election>::from_db(&db)
+            .update_one(u32_id_filter(election.id), update, None)
+            .await
+            .unwrap();
+        let election = get_election_by_id(&db, election.id).await;
+        let question_id = |spec: QuestionSpec| {
+            election
+                .questions
+                .values()
+                .find(|q| q.description == spec.description)
+                .unwrap()
+                .id
+        };
+        let expected_unconfirmed = HashMap::from([
+            (question_id(QuestionSpec::example1()), 2),
+            (question_id(QuestionSpec::example2()), 1),
+        ]);
+
+        // Schedule with a short lead time, so the warning is due in a second.
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        let finalizers = ElectionFinalizers::new(
+            EndWarningSettings {
+                lead: Duration::try_seconds(2).unwrap(),
+                threshold: 2,
+                webhook_url: None,
+            },
+            EventBus::new(),
+            TaskRegistry::new(),
+        );
+        finalizers
+            .schedule_election(db_client, &db, &election)
+            .await;
+        assert!(finalizers.has_end_warning(election.id).await);
+        assert!(get_election_by_id(&db, election.id)
+            .await
+            .end_warning
+            .is_none());
+
+        // (hopefully not flaky) sleep to make sure the warning has gone through.
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        assert!(!finalizers.has_end_warning(election.id).await);
+        let election = get_election_by_id(&db, election.id).await;
+        let warning = election.end_warning.clone().unwrap();
+        assert_eq!(warning.unconfirmed, expected_unconfirmed);
+
+        // Rescheduling does not warn again.
+        finalizers
+            .schedule_election(db_client, &db, &election)
+            .await;
+        assert!(!finalizers.has_end_warning(election.id).await);
+        finalizers.cancel_election(election.id).await;
+        assert!(!finalizers.has_finalizer(election.id).await);
+        let election = get_election_by_id(&db, election.id).await;
+        assert_eq!(election.end_warning, Some(warning));
+    }
+
+    #[backend_test(admin)]
+    async fn end_warning_webhook(client: Client, db: Database) {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        // Create and publish an election ending shortly, with some votes.
+        let mut spec = ElectionSpec::current_example();
+        spec.end_time = Utc::now() + Duration::try_seconds(3).unwrap();
+        let election = create_election_for_spec(&client, &spec).await;
+        insert_ballots(&db, election.id).await;
+        let update = doc! {
+            "$set": {
+                "state": ElectionState::Published,
+            }
+        };
+        Coll::<Election>::from_db(&db)
+            .update_one(u32_id_filter(election.id), update, None)
+            .await
+            .unwrap();
+        let election = get_election_by_id(&db, election.id).await;
+
+        // Warn a webhook served locally, which fails every request.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/hooks/end-warning",
+            listener.local_addr().unwrap()
+        );
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        let finalizers = ElectionFinalizers::new(
+            EndWarningSettings {
+                lead: Duration::try_seconds(2).unwrap(),
+                threshold: 2,
+                webhook_url: Some(url),
+            },
+            EventBus::new(),
+            TaskRegistry::new(),
+        );
+        finalizers
+            .schedule_election(db_client, &db, &election)
+            .await;
+
+        // Read one request, however it is split up, and answer it with a 500.
+        let serve = async {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            let mut body_start = None;
+            loop {
+                let read = stream.read(&mut buf).await.unwrap();
+                assert_ne!(read, 0, "webhook request ended early");
+                request.extend_from_slice(&buf[..read]);
+                body_start = body_start.or_else(|| {
+                    request
+                        .windows(4)
+                        .position(|window| window == b"\r\n\r\n")
+                        .map(|end| end + 4)
+                });
+                if let Some(body_start) = body_start {
+                    let head = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .map_or(0, |length| length.trim().parse::<usize>().unwrap());
+                    if request.len() >= body_start + length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            let body_start = body_start.unwrap();
+            let head = String::from_utf8(request[..body_start].to_vec()).unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&request[body_start..]).unwrap();
+            (head, body)
+        };
+        let (head, body) = tokio::time::timeout(tokio::time::Duration::from_secs(5), serve)
+            .await
+            .expect("webhook was never called");
+
+        // The warning was POSTed as JSON, counting each question's unconfirmed ballots.
+        assert!(head.starts_with("POST /hooks/end-warning HTTP/1.1\r\n"));
+        assert!(head
+            .to_lowercase()
+            .contains("content-type: application/json\r\n"));
+        let question_id = |spec: QuestionSpec| {
+            election
+                .questions
+                .values()
+                .find(|q| q.description == spec.description)
+                .unwrap()
+                .id
+        };
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "election_id": election.id,
+                "name": election.metadata.name,
+                "end_time": election.metadata.end_time,
+                "total_unconfirmed": 3,
+                "unconfirmed": {
+                    question_id(QuestionSpec::example1()).to_string(): 2,
+                    question_id(QuestionSpec::example2()).to_string(): 1,
+                },
+            })
+        );
+
+        // The failure is only logged: the warning is still recorded as sent,
+        // so it is not sent again.
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        let election = get_election_by_id(&db, election.id).await;
+        assert_eq!(election.end_warning.unwrap().unconfirmed.len(), 2);
+        finalizers
+            .schedule_election(db_client, &db, &election)
+            .await;
+        assert!(!finalizers.has_end_warning(election.id).await);
+        finalizers.cancel_election(election.id).await;
+    }
+
+    async fn get_finalization(client: &Client, id: ElectionId) -> FinalizationSummary {
+        let response = client.get(uri!(election_finalization(id))).dispatch().await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        serde_json::from_str(&raw_response).unwrap()
     }
 
     async fn get_election_by_id(db: &Database, id: ElectionId) -> Election {
@@ -886,6 +7154,55 @@ mod tests {
             .unwrap()
     }
 
+    /// Log in as the example voter, allowed to answer only the given question.
+    async fn join_as_example_voter(
+        client: &Client,
+        db: &Database,
+        election_id: ElectionId,
+        question_id: QuestionId,
+    ) {
+        client
+            .post(uri!(crate::api::auth::challenge))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&VoterChallengeRequest::example()).unwrap())
+            .dispatch()
+            .await;
+        let cookie = client.cookies().get_private(CHALLENGE_COOKIE).unwrap();
+        let config = client.rocket().state::<Config>().unwrap();
+        let challenge = Challenge::from_cookie(&cookie, config).unwrap();
+        let response = client
+            .post(uri!(crate::api::auth::verify))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&VoterVerifyRequest::example(challenge.code)).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let allowed = AllowedQuestions {
+            confirmed: HashMap::from([(question_id, false)]),
+        };
+        let voter = Coll::<Voter>::from_db(db)
+            .find_one(
+                doc! { "sms_hmac": Sms::example_hmac(client).to_bytestring() },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        Coll::<VoterElection>::from_db(db)
+            .insert_one(
+                VoterElection {
+                    voter_id: voter.id,
+                    election_id,
+                    allowed_questions: allowed,
+                    joins: None,
+                    consent: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
     async fn count_matches<T: MongoCollection>(db: &Database, filter: Document) -> u64 {
         Coll::<T>::from_db(db)
             .count_documents(filter, None)
@@ -901,6 +7218,7 @@ mod tests {
     async fn create_election_for_spec(client: &Client, spec: &ElectionSpec) -> ElectionDescription {
         let response = client
             .post(uri!(create_election))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(spec).unwrap())
             .dispatch()
@@ -917,6 +7235,7 @@ mod tests {
     async fn create_admin_expect_status(client: &Client, spec: &AdminCredentials, status: Status) {
         let response = client
             .post(uri!(create_admin))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(spec).unwrap())
             .dispatch()
@@ -941,6 +7260,7 @@ mod tests {
     ) -> LocalResponse<'c> {
         let response = client
             .put(uri!(modify_election(id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(spec).unwrap())
             .dispatch()
@@ -954,7 +7274,38 @@ mod tests {
     }
 
     async fn publish_expect_status(client: &Client, id: ElectionId, status: Status) {
-        let response = client.post(uri!(publish_election(id))).dispatch().await;
+        let response = client
+            .post(uri!(publish_election(id, _)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), status);
+    }
+
+    async fn publish_ended(client: &Client, id: ElectionId) {
+        let response = client
+            .post(uri!(publish_election(id, Some(true))))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    async fn suspend_expect_status(client: &Client, id: ElectionId, status: Status) {
+        let response = client
+            .post(uri!(suspend_election(id)))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), status);
+    }
+
+    async fn resume_expect_status(client: &Client, id: ElectionId, status: Status) {
+        let response = client
+            .post(uri!(resume_election(id)))
+            .csrf()
+            .dispatch()
+            .await;
         assert_eq!(response.status(), status);
     }
 
@@ -963,7 +7314,11 @@ mod tests {
     }
 
     async fn archive_expect_status(client: &Client, id: ElectionId, status: Status) {
-        let response = client.post(uri!(archive_election(id))).dispatch().await;
+        let response = client
+            .post(uri!(archive_election(id)))
+            .csrf()
+            .dispatch()
+            .await;
         assert_eq!(response.status(), status);
     }
 
@@ -972,10 +7327,29 @@ mod tests {
     }
 
     async fn delete_expect_status(client: &Client, id: ElectionId, status: Status) {
-        let response = client.delete(uri!(delete_election(id))).dispatch().await;
+        let response = client
+            .delete(uri!(delete_election(id, _)))
+            .csrf()
+            .dispatch()
+            .await;
         assert_eq!(response.status(), status);
     }
 
+    async fn delete_unexported(client: &Client, id: ElectionId) {
+        let response = client
+            .delete(uri!(delete_election(id, Some(true))))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    async fn get_retention(client: &Client, id: ElectionId) -> RetentionReport {
+        let response = client.get(uri!(election_retention(id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
     async fn insert_ballots(db: &Database, election_id: ElectionId) {
         let election = get_election_by_id(db, election_id).await;
         let q1 = election
@@ -1079,9 +7453,8 @@ mod tests {
             .insert_many(unconfirmed, None)
             .await
             .unwrap();
-        // Insert candidate totals.
-        Coll::<NewCandidateTotals>::from_db(db)
-            .insert_many(candidate_totals, None)
+        // Store candidate totals, replacing the zero totals from publishing.
+        crate::testdata::store_totals(db, candidate_totals)
             .await
             .unwrap();
     }
@@ -1112,65 +7485,49 @@ mod tests {
             .unwrap()
             .id;
 
-        // First voter has voted on everything.
-        let allowed_questions = HashMap::from_iter(vec![(
-            election_id,
-            AllowedQuestions {
-                confirmed: HashMap::from_iter(vec![(q1, true), (q2, true), (q3, true)]),
-            },
-        )]);
-        let voter1 = NewVoter {
-            sms_hmac: "+441234567890".parse::<Sms>().unwrap().into_hmac(config),
-            allowed_questions,
-        };
-
-        // Second voter has voted on some.
-        let allowed_questions = HashMap::from_iter(vec![(
-            election_id,
-            AllowedQuestions {
-                confirmed: HashMap::from_iter(vec![(q1, true), (q3, false)]),
-            },
-        )]);
-        let voter2 = NewVoter {
-            sms_hmac: "+440987654321".parse::<Sms>().unwrap().into_hmac(config),
-            allowed_questions,
-        };
-
-        // Third voter is not allowed to vote on any.
-        let allowed_questions = HashMap::from_iter(vec![(
-            election_id,
-            AllowedQuestions {
-                confirmed: HashMap::new(),
-            },
-        )]);
-        let voter3 = NewVoter {
-            sms_hmac: "+440123443210".parse::<Sms>().unwrap().into_hmac(config),
-            allowed_questions,
-        };
-
-        // Fourth voter never even joined.
-        let allowed_questions = HashMap::from_iter(vec![(
-            rand::thread_rng().gen(),
-            AllowedQuestions {
-                confirmed: HashMap::new(),
-            },
-        )]);
-        let voter4 = NewVoter {
-            sms_hmac: "+444321001234".parse::<Sms>().unwrap().into_hmac(config),
-            allowed_questions,
-        };
-
+        let voters = [
+            "+441234567890",
+            "+440987654321",
+            "+440123443210",
+            "+444321001234",
+        ]
+        .map(|sms| NewVoter::new(sms.parse::<Sms>().unwrap(), config));
         let result = Coll::<NewVoter>::from_db(db)
-            .insert_many(vec![voter1, voter2, voter3, voter4], None)
+            .insert_many(voters, None)
             .await
             .unwrap();
-        let mut resulting_ids = Vec::with_capacity(4);
-        for i in 0..4 {
-            resulting_ids.push(result.inserted_ids.get(&i).unwrap());
-        }
-        resulting_ids
-            .into_iter()
-            .map(|v| v.as_object_id().unwrap().into())
-            .collect()
+        let voter_ids = (0..4)
+            .map(|i| Id::from(result.inserted_ids[&i].as_object_id().unwrap()))
+            .collect::<Vec<_>>();
+        let joined = |voter_id: Id, election_id: ElectionId, confirmed: Vec<(QuestionId, bool)>| {
+            VoterElection {
+                voter_id,
+                election_id,
+                allowed_questions: AllowedQuestions {
+                    confirmed: HashMap::from_iter(confirmed),
+                },
+                joins: None,
+                consent: None,
+            }
+        };
+        let voter_elections = vec![
+            // First voter has voted on everything.
+            joined(
+                voter_ids[0],
+                election_id,
+                vec![(q1, true), (q2, true), (q3, true)],
+            ),
+            // Second voter has voted on some.
+            joined(voter_ids[1], election_id, vec![(q1, true), (q3, false)]),
+            // Third voter is not allowed to vote on any.
+            joined(voter_ids[2], election_id, vec![]),
+            // Fourth voter never even joined.
+            joined(voter_ids[3], rand::thread_rng().gen(), vec![]),
+        ];
+        Coll::<VoterElection>::from_db(db)
+            .insert_many(voter_elections, None)
+            .await
+            .unwrap();
+        voter_ids
     }
 }
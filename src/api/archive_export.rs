@@ -0,0 +1,546 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use mongodb::{
+    bson::{doc, DateTime},
+    options::{FindOneOptions, FindOptions, UpdateOptions},
+    Client, Database,
+};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    futures::{
+        future::{BoxFuture, FutureExt},
+        TryStreamExt,
+    },
+    serde::json::serde_json,
+    tokio::{self, sync::Mutex},
+    Build, Rocket,
+};
+use serde::Serialize;
+
+use crate::{
+    config::{ArchiveUploader, Config, S3Archive},
+    error::Result,
+    logging::RequestId,
+    model::{
+        api::election::ElectionDescription,
+        common::election::{ElectionId, ElectionState},
+        db::election::{ArchiveExport, Election},
+        mongodb::{Coll, RequestComment},
+    },
+    scheduled_task::{ScheduledTask, TaskRegistry},
+};
+
+use super::public::{dump_question, record_export, withhold_dump_totals};
+
+/// How often uploads that have not yet succeeded are retried.
+const RETRY_INTERVAL_MINUTES: i64 = 15;
+
+/// The name of the scheduled retry task.
+const RETRY_TASK_NAME: &str = "archive-export-retry";
+
+/// Uploads the signed dumps of archived elections to the archive bucket, so
+/// that they are kept independently of the database.
+///
+/// Each question's dump is uploaded as `<prefix>/<election_id>/<question_id>.json`,
+/// alongside the election's description as `<prefix>/<election_id>/election.json`.
+/// An election is uploaded in the background once it has been archived and
+/// finalized, and any that fail are retried periodically.
+pub struct ArchiveExports {
+    exporter: Option<Exporter>,
+    task: Arc<Mutex<Option<ScheduledTask<()>>>>,
+    registry: TaskRegistry,
+}
+
+/// Everything needed to upload an election, shared with background tasks.
+#[derive(Clone)]
+struct Exporter {
+    uploader: Arc<dyn ArchiveUploader>,
+    prefix: String,
+    db_client: Client,
+    db: Database,
+}
+
+impl ArchiveExports {
+    /// Exports that are not used, since no archive bucket is configured.
+    pub fn disabled() -> Self {
+        Self {
+            exporter: None,
+            task: Default::default(),
+            registry: TaskRegistry::new(),
+        }
+    }
+
+    /// Create exports to the given uploader under the given key prefix, with
+    /// nothing scheduled, which register their retry task with `registry`.
+    pub fn new(
+        uploader: Arc<dyn ArchiveUploader>,
+        prefix: &str,
+        db_client: Client,
+        db: Database,
+        registry: TaskRegistry,
+    ) -> Self {
+        Self {
+            exporter: Some(Exporter {
+                uploader,
+                prefix: prefix.to_string(),
+                db_client,
+                db,
+            }),
+            task: Default::default(),
+            registry,
+        }
+    }
+
+    /// Is there an archive bucket to upload to?
+    pub fn is_enabled(&self) -> bool {
+        self.exporter.is_some()
+    }
+
+    /// Upload the given election in the background, recording the outcome on
+    /// it. This has no effect if exports are disabled.
+    pub fn export_in_background(&self, election_id: ElectionId, request_id: RequestId) {
+        if let Some(exporter) = self.exporter.clone() {
+            tokio::spawn(async move {
+                if let Err(e) = exporter.export(election_id, request_id).await {
+                    error!("  req{request_id} Failed to record archive export of election {election_id}: {e}");
+                }
+            });
+        }
+    }
+
+    /// Schedule pending uploads to be retried one interval from now, and
+    /// every interval thereafter. This has no effect if exports are disabled.
+    pub async fn schedule(&self) {
+        let Some(exporter) = self.exporter.clone() else {
+            return;
+        };
+        let task = Self::scheduled(exporter, self.task.clone(), self.registry.clone());
+        let task = ScheduledTask::new(RETRY_TASK_NAME, task, Utc::now() + retry_interval());
+        self.registry.register(&task, true);
+        *self.task.lock().await = Some(task);
+    }
+
+    /// Retry pending uploads, then reschedule for the next interval.
+    /// Since this is a recursive async function, we must use `BoxFuture` to
+    /// avoid an infinitely-recursive state machine.
+    fn scheduled(
+        exporter: Exporter,
+        task: Arc<Mutex<Option<ScheduledTask<()>>>>,
+        registry: TaskRegistry,
+    ) -> BoxFuture<'static, ()> {
+        async move {
+            if let Err(e) = exporter.export_pending(RequestId::next()).await {
+                error!("Retrying archive exports failed: {e}");
+            }
+            let next = Self::scheduled(exporter, task.clone(), registry.clone());
+            let next = ScheduledTask::new(RETRY_TASK_NAME, next, Utc::now() + retry_interval());
+            registry.register(&next, true);
+            *task.lock().await = Some(next);
+        }
+        .boxed()
+    }
+}
+
+impl Exporter {
+    /// Upload every archived election that is waiting to be, returning how
+    /// many were attempted.
+    async fn export_pending(&self, request_id: RequestId) -> Result<usize> {
+        let filter = doc! {
+            "state": ElectionState::Archived,
+            "archive_export": { "$exists": true },
+            "archive_export.uploaded_at": null,
+        };
+        let pending: Vec<ElectionId> = Coll::<Election>::from_db(&self.db)
+            .find(filter, FindOptions::for_request(request_id))
+            .await?
+            .map_ok(|election| election.id)
+            .try_collect()
+            .await?;
+        for &election_id in &pending {
+            self.export(election_id, request_id).await?;
+        }
+        if !pending.is_empty() {
+            info!(
+                "  req{request_id} Retried archive export of {} elections",
+                pending.len()
+            );
+        }
+        Ok(pending.len())
+    }
+
+    /// Upload the given election, if it is archived and finalized, and record
+    /// the outcome on it. Only failing to read or record the outcome is an error.
+    async fn export(&self, election_id: ElectionId, request_id: RequestId) -> Result<()> {
+        let elections = Coll::<Election>::from_db(&self.db);
+        let filter = doc! {
+            "_id": election_id,
+            "state": ElectionState::Archived,
+            "finalization.completed_at": { "$ne": null },
+        };
+        let Some(election) = elections
+            .find_one(filter, FindOneOptions::for_request(request_id))
+            .await?
+        else {
+            debug!("  req{request_id} Election {election_id} is not ready to be exported");
+            return Ok(());
+        };
+
+        let mut export = election.archive_export.clone().unwrap_or_default();
+        export.attempts += 1;
+        match self.upload(&election, request_id).await {
+            Ok(object_keys) => {
+                info!(
+                    "  req{request_id} Uploaded {} objects for election {election_id} to the archive bucket",
+                    object_keys.len()
+                );
+                export.uploaded_at = Some(DateTime::now());
+                export.object_keys = object_keys;
+                export.last_error = None;
+            }
+            Err(e) => {
+                error!("  req{request_id} Failed to upload election {election_id} to the archive bucket: {e}");
+                export.last_error = Some(e);
+            }
+        }
+
+        let filter = doc! {
+            "_id": election_id,
+            "state": ElectionState::Archived,
+        };
+        let update = doc! {
+            "$set": { "archive_export": export },
+        };
+        elections
+            .update_one(filter, update, UpdateOptions::for_request(request_id))
+            .await?;
+        Ok(())
+    }
+
+    /// Upload the election's description and the signed dump of each of its
+    /// questions, returning the keys of the objects uploaded.
+    async fn upload(
+        &self,
+        election: &Election,
+        request_id: RequestId,
+    ) -> std::result::Result<Vec<String>, String> {
+        let elections = Coll::from_db(&self.db);
+        let totals = Coll::from_db(&self.db);
+        let totals_deltas = Coll::from_db(&self.db);
+        let ballots = Coll::from_db(&self.db);
+        let board_heads = Coll::from_db(&self.db);
+        let board_entries = Coll::from_db(&self.db);
+
+        let description = ElectionDescription::from(election.clone());
+        let mut object_keys = vec![self.put(election.id, "election.json", &description).await?];
+
+        let mut question_ids: Vec<_> = election.questions.keys().copied().collect();
+        question_ids.sort_unstable();
+        for question_id in question_ids {
+            // The same signed dump the public endpoint serves to non-admins.
+            let (election, mut dump) = dump_question(
+                election.id,
+                question_id,
+                false,
+                None,
+                &elections,
+                &totals,
+                &totals_deltas,
+                &ballots,
+                &board_heads,
+                &board_entries,
+                &self.db_client,
+                request_id,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            withhold_dump_totals(&election, question_id, &mut dump, request_id);
+            dump.sign(&election);
+            let name = format!("{question_id}.json");
+            object_keys.push(self.put(election.id, &name, &dump).await?);
+            record_export(&election, question_id, &dump, &elections, request_id)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(object_keys)
+    }
+
+    /// Upload a value as JSON under the given name for the given election,
+    /// returning its key.
+    async fn put(
+        &self,
+        election_id: ElectionId,
+        name: &str,
+        value: &impl Serialize,
+    ) -> std::result::Result<String, String> {
+        let key = object_key(&self.prefix, election_id, name);
+        let json = serde_json::to_vec(value).unwrap(); // Cannot fail.
+        self.uploader.upload(&key, json).await?;
+        Ok(key)
+    }
+}
+
+/// The key of an election's object, under the prefix if there is one.
+fn object_key(prefix: &str, election_id: ElectionId, name: &str) -> String {
+    if prefix.is_empty() {
+        format!("{election_id}/{name}")
+    } else {
+        format!("{prefix}/{election_id}/{name}")
+    }
+}
+
+fn retry_interval() -> Duration {
+    Duration::try_minutes(RETRY_INTERVAL_MINUTES).unwrap()
+}
+
+/// A fairing that places [`ArchiveExports`] into managed state, uploading to
+/// the [`S3Archive`] if an archive bucket is configured, and schedules their
+/// retries.
+pub struct ArchiveExportFairing;
+
+#[rocket::async_trait]
+impl Fairing for ArchiveExportFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Archive Export",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let Some(config) = rocket.state::<Config>() else {
+            error!("Config was not available when setting up archive exports");
+            return Err(rocket);
+        };
+        if config.archive_bucket().is_none() {
+            info!("No archive bucket configured; archived elections will not be uploaded");
+            return Ok(rocket.manage(ArchiveExports::disabled()));
+        }
+        let Some(archive) = rocket.state::<S3Archive>() else {
+            error!("S3 archive was not available when setting up archive exports");
+            return Err(rocket);
+        };
+        let Some(db_client) = rocket.state::<Client>() else {
+            error!("Database client was not available when setting up archive exports");
+            return Err(rocket);
+        };
+        let Some(db) = rocket.state::<Database>() else {
+            error!("Database was not available when setting up archive exports");
+            return Err(rocket);
+        };
+        let Some(registry) = rocket.state::<TaskRegistry>() else {
+            error!("Task registry was not available when setting up archive exports");
+            return Err(rocket);
+        };
+
+        let exports = ArchiveExports::new(
+            Arc::new(archive.clone()),
+            config.archive_prefix(),
+            db_client.clone(),
+            db.clone(),
+            registry.clone(),
+        );
+        exports.schedule().await;
+        Ok(rocket.manage(exports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex as StdMutex;
+
+    use rocket::local::asynchronous::Client;
+
+    use crate::model::{
+        api::election::{ElectionResults, ElectionSpec},
+        common::election::ResultVisibility,
+        mongodb::u32_id_filter,
+    };
+    use crate::testdata::{insert_ballots, insert_elections};
+
+    use super::*;
+
+    /// Keeps uploaded objects in memory, or fails every upload if `failing`.
+    #[derive(Default)]
+    struct MockUploader {
+        objects: StdMutex<HashMap<String, Vec<u8>>>,
+        failing: StdMutex<bool>,
+    }
+
+    #[rocket::async_trait]
+    impl ArchiveUploader for MockUploader {
+        async fn upload(&self, key: &str, json: Vec<u8>) -> std::result::Result<(), String> {
+            if *self.failing.lock().unwrap() {
+                return Err(format!("Access denied to '{key}'"));
+            }
+            self.objects.lock().unwrap().insert(key.to_string(), json);
+            Ok(())
+        }
+    }
+
+    #[backend_test]
+    async fn export_archived_election(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let elections = Coll::<Election>::from_db(&db);
+        let election = elections
+            .find_one(doc! { "name": ElectionSpec::current_example().name }, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let uploader = Arc::new(MockUploader::default());
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap().clone();
+        let exporter = Exporter {
+            uploader: uploader.clone(),
+            prefix: "elections".to_string(),
+            db_client,
+            db: db.clone(),
+        };
+
+        // Nothing is uploaded until the election is archived and finalized.
+        exporter
+            .export(election.id, RequestId::next())
+            .await
+            .unwrap();
+        assert!(uploader.objects.lock().unwrap().is_empty());
+        let update = doc! {
+            "$set": {
+                "state": ElectionState::Archived,
+                "archive_export": ArchiveExport::default(),
+                "finalization.completed_at": DateTime::now(),
+            }
+        };
+        elections
+            .update_one(u32_id_filter(election.id), update, None)
+            .await
+            .unwrap();
+
+        // A failed upload is recorded, not returned.
+        *uploader.failing.lock().unwrap() = true;
+        exporter
+            .export(election.id, RequestId::next())
+            .await
+            .unwrap();
+        let failed = get_export(&elections, election.id).await;
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.uploaded_at.is_none());
+        assert!(failed.last_error.unwrap().contains("Access denied"));
+
+        // Retrying uploads it.
+        *uploader.failing.lock().unwrap() = false;
+        let attempted = exporter.export_pending(RequestId::next()).await.unwrap();
+        assert_eq!(attempted, 1);
+        let uploaded = get_export(&elections, election.id).await;
+        assert_eq!(uploaded.attempts, 2);
+        assert!(uploaded.uploaded_at.is_some());
+        assert!(uploaded.last_error.is_none());
+
+        // The description and every question's signed dump are uploaded.
+        let mut expected_keys = vec![format!("elections/{}/election.json", election.id)];
+        let mut question_ids: Vec<_> = election.questions.keys().copied().collect();
+        question_ids.sort_unstable();
+        expected_keys.extend(
+            question_ids
+                .iter()
+                .map(|question_id| format!("elections/{}/{question_id}.json", election.id)),
+        );
+        assert_eq!(uploaded.object_keys, expected_keys);
+        let objects = uploader.objects.lock().unwrap().clone();
+        assert_eq!(objects.len(), expected_keys.len());
+        let description: ElectionDescription =
+            serde_json::from_slice(&objects[&expected_keys[0]]).unwrap();
+        assert_eq!(description.id, election.id);
+        for key in &expected_keys[1..] {
+            let dump: ElectionResults = serde_json::from_slice(&objects[key]).unwrap();
+            assert!(dump.verify().is_ok());
+            assert!(dump.verify_signature().is_ok());
+        }
+
+        // The uploads count as exports for deletion.
+        let election = elections
+            .find_one(u32_id_filter(election.id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(election
+            .questions
+            .values()
+            .all(|question| question.last_dump_at.is_some()));
+
+        // Nothing is left to retry.
+        let attempted = exporter.export_pending(RequestId::next()).await.unwrap();
+        assert_eq!(attempted, 0);
+    }
+
+    #[backend_test]
+    async fn export_withholds_totals(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let elections = Coll::<Election>::from_db(&db);
+        let election = elections
+            .find_one(doc! { "name": ElectionSpec::current_example().name }, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Archive the election, keeping one question's results for admins.
+        let mut question_ids: Vec<_> = election.questions.keys().copied().collect();
+        question_ids.sort_unstable();
+        let withheld = question_ids[0];
+        let visibility = mongodb::bson::to_bson(&ResultVisibility::AdminOnly).unwrap();
+        let update = doc! {
+            "$set": {
+                "state": ElectionState::Archived,
+                "archive_export": ArchiveExport::default(),
+                "finalization.completed_at": DateTime::now(),
+                format!("questions.{withheld}.result_visibility"): visibility,
+            }
+        };
+        elections
+            .update_one(u32_id_filter(election.id), update, None)
+            .await
+            .unwrap();
+
+        let uploader = Arc::new(MockUploader::default());
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap().clone();
+        let exporter = Exporter {
+            uploader: uploader.clone(),
+            prefix: String::new(),
+            db_client,
+            db: db.clone(),
+        };
+        exporter
+            .export(election.id, RequestId::next())
+            .await
+            .unwrap();
+
+        // Only that question's dump is uploaded without its totals, and every
+        // dump still verifies.
+        let objects = uploader.objects.lock().unwrap().clone();
+        for question_id in question_ids {
+            let key = object_key("", election.id, &format!("{question_id}.json"));
+            let dump: ElectionResults = serde_json::from_slice(&objects[&key]).unwrap();
+            assert_eq!(dump.totals.is_none(), question_id == withheld);
+            assert!(dump.verify().is_ok());
+            assert!(dump.verify_signature().is_ok());
+        }
+    }
+
+    #[test]
+    fn object_keys() {
+        assert_eq!(object_key("", 3, "election.json"), "3/election.json");
+        assert_eq!(object_key("dre-ip", 3, "1.json"), "dre-ip/3/1.json");
+    }
+
+    async fn get_export(elections: &Coll<Election>, election_id: ElectionId) -> ArchiveExport {
+        elections
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap()
+            .archive_export
+            .unwrap()
+    }
+}
@@ -1,30 +1,38 @@
-use aws_sdk_sns::Client as SnsClient;
 use dre_ip::Serializable;
-use mongodb::bson::doc;
-use rocket::{
-    http::{Cookie, CookieJar, Status},
-    serde::json::Json,
-    Route, State,
+use mongodb::{
+    bson::{doc, DateTime},
+    options::{FindOneOptions, ReplaceOptions, UpdateOptions},
 };
+use rocket::{http::Status, serde::json::Json, Route, State};
+use zeroize::Zeroizing;
 
 use crate::{
-    config::Config,
+    config::{Config, SmsNotifier, SmsRouter},
     error::{Error, Result},
     logging::RequestId,
     model::{
         api::{
             admin::AdminCredentials,
-            auth::{AuthToken, VoterChallengeRequest, VoterVerifyRequest, AUTH_TOKEN_COOKIE},
+            auth::{
+                csrf_cookie, AuthToken, CsrfChecked, ScopedCookies, StepUpToken,
+                VoterChallengeRequest, VoterVerifyRequest, AUTH_TOKEN_COOKIE, CSRF_COOKIE,
+                STEP_UP_COOKIE,
+            },
             otp::{Challenge, CHALLENGE_COOKIE},
+            sms::Sms,
         },
+        common::election::ElectionId,
         db::{
             admin::Admin,
+            challenge_delivery::{ChallengeDelivery, DeliveryStatus},
             voter::{NewVoter, Voter},
         },
-        mongodb::{Coll, Id},
+        mongodb::{Coll, Id, RequestComment},
     },
 };
 
+use super::{client_ip::ClientIp, sms_queue::SmsQueue};
+
 pub fn routes() -> Vec<Route> {
     routes![
         check_auth_admin,
@@ -33,6 +41,8 @@ pub fn routes() -> Vec<Route> {
         authenticate,
         challenge,
         verify,
+        step_up_challenge,
+        step_up_verify,
         logout_admin,
         logout_voter,
         logout_none,
@@ -56,7 +66,7 @@ async fn check_auth_none() -> &'static str {
 
 #[post("/auth/admin", data = "<credentials>", format = "json")]
 async fn authenticate(
-    cookies: &CookieJar<'_>,
+    cookies: ScopedCookies<'_>,
     credentials: Json<AdminCredentials>,
     admins: Coll<Admin>,
     config: &State<Config>,
@@ -69,7 +79,7 @@ async fn authenticate(
     let admin = admins
         .find_one(with_username, None)
         .await?
-        .filter(|admin| admin.verify_password(&credentials.password))
+        .filter(|admin| admin.verify_password(credentials.password.expose().as_bytes()))
         .ok_or_else(|| {
             warn!(
                 "  req{} Failed login attempt for admin {}",
@@ -83,6 +93,7 @@ async fn authenticate(
 
     let token = AuthToken::new(&admin);
     cookies.add(token.into_cookie(config));
+    cookies.add(csrf_cookie(config));
     info!(
         "  req{} Admin {} ({}) successfully authenticated",
         request_id, credentials.username, admin.id
@@ -91,71 +102,256 @@ async fn authenticate(
     Ok(())
 }
 
-#[cfg_attr(any(not(feature = "otp"), test), allow(unused_variables))]
 #[post("/auth/voter/challenge", data = "<auth_request>", format = "json")]
+#[allow(clippy::too_many_arguments)]
 async fn challenge(
     auth_request: Json<VoterChallengeRequest>,
-    cookies: &CookieJar<'_>,
+    client_ip: ClientIp,
+    cookies: ScopedCookies<'_>,
+    deliveries: Coll<ChallengeDelivery>,
     config: &State<Config>,
-    sender: &State<SnsClient>,
-) -> Result<()> {
+    sms_queue: &State<SmsQueue>,
+    sms_router: &State<SmsRouter>,
+    request_id: RequestId,
+) -> Result<Status> {
     // Verify the reCAPTCHA.
+    let lang = auth_request.lang.clone();
     let sms = auth_request
         .0
-        .verify(config.recaptcha_secret(), config.hostname())
+        .verify(
+            config.recaptcha_secret(),
+            config.hostname(),
+            client_ip.0,
+            config.default_sms_region(),
+        )
         .await?;
 
+    send_challenge(
+        sms,
+        lang.as_deref(),
+        cookies,
+        &deliveries,
+        config,
+        sms_queue,
+        sms_router.inner(),
+        request_id,
+    )
+    .await
+}
+
+/// Send an OTP to the given number, and set the challenge cookie to verify it against.
+///
+/// If the SMS queue is enabled, the OTP is only queued to be sent, and this
+/// gives [`Status::Accepted`] rather than [`Status::Ok`].
+#[allow(clippy::too_many_arguments)]
+async fn send_challenge(
+    sms: Sms,
+    lang: Option<&str>,
+    cookies: ScopedCookies<'_>,
+    deliveries: &Coll<ChallengeDelivery>,
+    config: &Config,
+    sms_queue: &SmsQueue,
+    notifier: &impl SmsNotifier,
+    request_id: RequestId,
+) -> Result<Status> {
+    // Only send SMS to numbers this deployment allows.
+    if !sms.matches_prefixes(config.allowed_sms_prefixes()) {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!(
+                "SMS number must start with one of the following: {}",
+                config.allowed_sms_prefixes().join(", ")
+            ),
+        ));
+    }
+
+    // SNS accepts messages to landlines and the like, but they never arrive.
+    if !sms.can_receive_sms() {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            "SMS number cannot receive text messages; please use a mobile number".to_string(),
+        ));
+    }
+
     // Choose the OTP.
     let challenge = Challenge::new(sms);
+    let message = config.sms_templates().render(
+        lang,
+        config.sms_default_language(),
+        &Zeroizing::new(challenge.code.to_string()),
+    );
 
-    // Send the OTP.
-    #[cfg(all(feature = "otp", not(test)))]
-    sender
-        .publish()
-        .phone_number(challenge.sms.to_string())
-        .message(format!("Voter registration code: {}", challenge.code))
-        .send()
-        .await
-        .map_err(|_| {
-            Error::Status(
-                Status::InternalServerError,
-                "Failed to send message".to_string(),
-            )
-        })?;
+    // Send the OTP, unless the number is known not to receive them. Queued
+    // OTPs may still turn out to be undeliverable, but the voter can only
+    // find that out by never receiving them.
+    let sms_hmac = challenge.sms.clone().into_hmac(config);
+    let limit = config.max_sms_delivery_failures();
+    let status = if sms_queue.is_enabled() {
+        previous_failures(&challenge.sms, &sms_hmac, limit, deliveries, request_id).await?;
+        sms_queue.enqueue(challenge.sms.clone(), sms_hmac, message, request_id)?;
+        Status::Accepted
+    } else {
+        deliver_sms(
+            &challenge.sms,
+            sms_hmac,
+            message,
+            limit,
+            deliveries,
+            notifier,
+            request_id,
+        )
+        .await?;
+        Status::Ok
+    };
 
     // Set the cookie.
     cookies.add_private(challenge.into_cookie(config));
 
-    Ok(())
+    Ok(status)
+}
+
+/// Count the messages to the number with the given HMAC that could not be
+/// delivered in a row.
+///
+/// Fails with [`Error::SmsUndeliverable`] if there have been `limit` of them,
+/// so no more should be sent.
+async fn previous_failures(
+    sms: &Sms,
+    sms_hmac: &[u8],
+    limit: u32,
+    deliveries: &Coll<ChallengeDelivery>,
+    request_id: RequestId,
+) -> Result<u32> {
+    let previous_failures = deliveries
+        .find_one(
+            doc! { "sms_hmac": sms_hmac.to_bytestring() },
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .map_or(0, |delivery| delivery.failures);
+    if previous_failures >= limit {
+        warn!(
+            "  req{} Not sending SMS to {}: the last {} were undeliverable",
+            request_id,
+            sms.redacted(),
+            previous_failures
+        );
+        return Err(Error::SmsUndeliverable);
+    }
+    Ok(previous_failures)
+}
+
+/// Send a message to the given number, whose HMAC is given, recording what
+/// became of it.
+///
+/// Fails with [`Error::SmsUndeliverable`], without trying to send anything, if
+/// the last `limit` messages to the number could not be delivered because of
+/// the number itself, or if this one makes it so. A failure of SNS neither
+/// counts towards this nor resets it.
+pub(super) async fn deliver_sms(
+    sms: &Sms,
+    sms_hmac: Vec<u8>,
+    message: String,
+    limit: u32,
+    deliveries: &Coll<ChallengeDelivery>,
+    notifier: &impl SmsNotifier,
+    request_id: RequestId,
+) -> Result<()> {
+    let previous_failures =
+        previous_failures(sms, &sms_hmac, limit, deliveries, request_id).await?;
+    let with_sms_hmac = doc! { "sms_hmac": sms_hmac.to_bytestring() };
+
+    let outcome = notifier.send_sms(sms, message).await;
+    let failures = match outcome.status {
+        status if status.is_number_failure() => previous_failures + 1,
+        DeliveryStatus::Failed => previous_failures,
+        _ => 0,
+    };
+    let delivery = ChallengeDelivery {
+        sms_hmac,
+        region: sms.region().map(|region| region.as_ref().to_string()),
+        message_id: outcome.message_id,
+        status: outcome.status,
+        failures,
+        updated_at: DateTime::now(),
+    };
+    let upsert = ReplaceOptions::builder()
+        .upsert(true)
+        .build()
+        .with_request_id(request_id);
+    deliveries
+        .replace_one(with_sms_hmac, &delivery, upsert)
+        .await?;
+
+    match outcome.status {
+        DeliveryStatus::Sent | DeliveryStatus::Delivered => Ok(()),
+        _ if failures >= limit => {
+            warn!(
+                "  req{} SMS to {} undeliverable {} times in a row",
+                request_id,
+                sms.redacted(),
+                failures
+            );
+            Err(Error::SmsUndeliverable)
+        }
+        DeliveryStatus::OptedOut => Err(Error::Status(
+            Status::UnprocessableEntity,
+            "SMS number has opted out of text messages".to_string(),
+        )),
+        DeliveryStatus::InvalidNumber => Err(Error::Status(
+            Status::UnprocessableEntity,
+            "SMS number is not accepted by the SMS provider".to_string(),
+        )),
+        DeliveryStatus::Failed => Err(Error::Status(
+            Status::InternalServerError,
+            "Failed to send message".to_string(),
+        )),
+    }
+}
+
+/// Record that a challenge reached the number with the given HMAC, as its code
+/// was verified. Failing to do so is logged but does not fail verification.
+async fn record_delivered(
+    sms_hmac: &[u8],
+    deliveries: &Coll<ChallengeDelivery>,
+    request_id: RequestId,
+) {
+    let update = doc! {
+        "$set": {
+            "status": DeliveryStatus::Delivered,
+            "failures": 0,
+            "updated_at": DateTime::now(),
+        }
+    };
+    let result = deliveries
+        .update_one(
+            doc! { "sms_hmac": sms_hmac.to_bytestring() },
+            update,
+            UpdateOptions::for_request(request_id),
+        )
+        .await;
+    if let Err(e) = result {
+        error!("  req{request_id} Failed to record SMS delivery: {e}");
+    }
 }
 
-#[cfg_attr(not(feature = "otp"), allow(unused_variables))]
 #[post("/auth/voter/verify", data = "<auth_request>", format = "json")]
+#[allow(clippy::too_many_arguments)]
 async fn verify(
     auth_request: Json<VoterVerifyRequest>,
+    client_ip: ClientIp,
     challenge: Challenge,
-    cookies: &CookieJar<'_>,
+    cookies: ScopedCookies<'_>,
     voters: Coll<Voter>,
     new_voters: Coll<NewVoter>,
+    deliveries: Coll<ChallengeDelivery>,
     config: &State<Config>,
     request_id: RequestId,
 ) -> Result<()> {
-    #[cfg(feature = "otp")]
-    {
-        let code = auth_request
-            .0
-            .verify(config.recaptcha_secret(), config.hostname())
-            .await?;
-        if challenge.code != code {
-            // Submitted code is invalid and so the verification fails
-            return Err(Error::Status(
-                Status::Unauthorized,
-                format!("Incorrect OTP code {:?}", code),
-            ));
-        }
-    }
+    check_code(auth_request.0, client_ip, &challenge, config).await?;
 
     let voter = NewVoter::new(challenge.sms, config);
+    record_delivered(&voter.sms_hmac, &deliveries, request_id).await;
 
     let with_sms_hmac = doc! {
         "sms_hmac": voter.sms_hmac.to_bytestring(),
@@ -188,9 +384,10 @@ async fn verify(
     // Create the auth token cookie.
     let claims = AuthToken::new(&db_voter);
     cookies.add(claims.into_cookie(config));
+    cookies.add(csrf_cookie(config));
 
     // We no longer need the OTP challenge.
-    cookies.remove(Cookie::from(CHALLENGE_COOKIE));
+    cookies.remove(CHALLENGE_COOKIE);
 
     info!(
         "  req{} Voter {} successfully authenticated",
@@ -199,37 +396,197 @@ async fn verify(
     Ok(())
 }
 
+/// Check the submitted OTP code against the challenge.
+#[cfg_attr(not(feature = "otp"), allow(unused_variables))]
+async fn check_code(
+    auth_request: VoterVerifyRequest,
+    client_ip: ClientIp,
+    challenge: &Challenge,
+    config: &Config,
+) -> Result<()> {
+    #[cfg(feature = "otp")]
+    {
+        let code = auth_request
+            .verify(config.recaptcha_secret(), config.hostname(), client_ip.0)
+            .await?;
+        if challenge.code != code {
+            // Submitted code is invalid and so the verification fails
+            return Err(Error::Status(
+                Status::Unauthorized,
+                "Incorrect OTP code".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that the given number belongs to the given voter.
+fn check_voter_sms(sms: Sms, voter: &Voter, config: &Config) -> Result<()> {
+    if sms.into_hmac(config) != voter.sms_hmac {
+        return Err(Error::Status(
+            Status::Forbidden,
+            "SMS number does not belong to the signed-in voter".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Begin stepping up for an election: the voter re-enters their SMS number
+/// and is sent a fresh OTP.
+#[post(
+    "/elections/<election_id>/step-up/challenge",
+    data = "<auth_request>",
+    format = "json"
+)]
+#[allow(clippy::too_many_arguments)]
+async fn step_up_challenge(
+    token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    auth_request: Json<VoterChallengeRequest>,
+    client_ip: ClientIp,
+    voters: Coll<Voter>,
+    deliveries: Coll<ChallengeDelivery>,
+    cookies: ScopedCookies<'_>,
+    config: &State<Config>,
+    sms_queue: &State<SmsQueue>,
+    sms_router: &State<SmsRouter>,
+    request_id: RequestId,
+) -> Result<Status> {
+    info!(
+        "  req{} Voter {} stepping up for election {}",
+        request_id, token.id, election_id
+    );
+    // Verify the reCAPTCHA.
+    let lang = auth_request.lang.clone();
+    let sms = auth_request
+        .0
+        .verify(
+            config.recaptcha_secret(),
+            config.hostname(),
+            client_ip.0,
+            config.default_sms_region(),
+        )
+        .await?;
+
+    // We only store the HMAC of the number, so the voter must supply it again.
+    let voter = voters
+        .find_one(token.id.as_doc(), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Voter with ID {}", token.id)))?;
+    check_voter_sms(sms.clone(), &voter, config)?;
+
+    send_challenge(
+        sms,
+        lang.as_deref(),
+        cookies,
+        &deliveries,
+        config,
+        sms_queue,
+        sms_router.inner(),
+        request_id,
+    )
+    .await
+}
+
+/// Complete stepping up for an election, issuing a short-lived token scoped to it.
+#[post(
+    "/elections/<election_id>/step-up/verify",
+    data = "<auth_request>",
+    format = "json"
+)]
+#[allow(clippy::too_many_arguments)]
+async fn step_up_verify(
+    token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    auth_request: Json<VoterVerifyRequest>,
+    client_ip: ClientIp,
+    challenge: Challenge,
+    voters: Coll<Voter>,
+    deliveries: Coll<ChallengeDelivery>,
+    cookies: ScopedCookies<'_>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<()> {
+    check_code(auth_request.0, client_ip, &challenge, config).await?;
+
+    // The challenge must have been sent to this voter's number.
+    let voter = voters
+        .find_one(token.id.as_doc(), None)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Voter with ID {}", token.id)))?;
+    check_voter_sms(challenge.sms, &voter, config)?;
+    record_delivered(&voter.sms_hmac, &deliveries, request_id).await;
+
+    cookies.add(StepUpToken::new(voter.id, election_id).into_cookie(config));
+    cookies.remove(CHALLENGE_COOKIE);
+
+    info!(
+        "  req{} Voter {} stepped up for election {}",
+        request_id, voter.id, election_id
+    );
+    Ok(())
+}
+
 #[delete("/auth", rank = 1)]
-fn logout_admin(token: AuthToken<Admin>, cookies: &CookieJar, request_id: RequestId) -> Status {
+fn logout_admin(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    cookies: ScopedCookies<'_>,
+    request_id: RequestId,
+) -> Status {
     info!("  req{} Admin {} logging out", request_id, token.id);
-    cookies.remove(Cookie::from(AUTH_TOKEN_COOKIE));
+    remove_auth_cookies(cookies);
     Status::Ok
 }
 
 #[delete("/auth", rank = 2)]
-fn logout_voter(token: AuthToken<Voter>, cookies: &CookieJar, request_id: RequestId) -> Status {
+fn logout_voter(
+    token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    cookies: ScopedCookies<'_>,
+    request_id: RequestId,
+) -> Status {
     info!("  req{} Voter {} logging out", request_id, token.id);
-    cookies.remove(Cookie::from(AUTH_TOKEN_COOKIE));
+    remove_auth_cookies(cookies);
     Status::Ok
 }
 
 #[delete("/auth", rank = 3)]
-fn logout_none(cookies: &CookieJar) -> Status {
-    cookies.remove(Cookie::from(AUTH_TOKEN_COOKIE));
+fn logout_none(cookies: ScopedCookies<'_>) -> Status {
+    remove_auth_cookies(cookies);
     Status::Ok
 }
 
+/// Remove the cookies set on login.
+fn remove_auth_cookies(cookies: ScopedCookies<'_>) {
+    cookies.remove(AUTH_TOKEN_COOKIE);
+    cookies.remove(CSRF_COOKIE);
+    cookies.remove(STEP_UP_COOKIE);
+}
+
 #[cfg(test)]
 mod tests {
-    use rocket::{http::ContentType, local::asynchronous::Client, serde::json::serde_json::json};
-    use std::str::FromStr;
+    use mongodb::Database;
+    use rocket::{
+        figment::Figment,
+        http::{ContentType, Header, SameSite},
+        local::asynchronous::Client,
+        serde::json::serde_json::{self, json, Value},
+    };
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
     use crate::model::{
         api::{
+            auth::{WithCsrf, CSRF_HEADER},
             otp::{Challenge, Code, CODE_LENGTH},
             sms::Sms,
         },
-        db::admin::NewAdmin,
+        db::{admin::NewAdmin, challenge_delivery::SmsOutcome},
     };
 
     use super::*;
@@ -275,6 +632,37 @@ mod tests {
 
         assert_eq!(Status::Ok, response.status());
         assert!(client.cookies().get(AUTH_TOKEN_COOKIE).is_some());
+
+        // The auth token must be hidden from scripts, but the CSRF token must not be
+        let auth_cookie = response.cookies().get(AUTH_TOKEN_COOKIE).unwrap();
+        assert_eq!(Some(true), auth_cookie.http_only());
+        assert_eq!(Some(SameSite::Strict), auth_cookie.same_site());
+        let csrf_cookie = response.cookies().get(CSRF_COOKIE).unwrap();
+        assert_eq!(Some(false), csrf_cookie.http_only());
+        assert_eq!(Some(SameSite::Strict), csrf_cookie.same_site());
+
+        // Without a configured domain, they are only sent back to this host.
+        for cookie in [auth_cookie, csrf_cookie] {
+            assert_eq!(None, cookie.domain());
+            assert_eq!(Some("/"), cookie.path());
+        }
+    }
+
+    #[backend_test(admin)]
+    async fn csrf_header_required(client: Client) {
+        // No header
+        let response = client.delete(uri!(logout_admin)).dispatch().await;
+        assert_eq!(Status::Forbidden, response.status());
+        assert!(client.cookies().get(AUTH_TOKEN_COOKIE).is_some());
+
+        // Mismatched header
+        let response = client
+            .delete(uri!(logout_admin))
+            .header(Header::new(CSRF_HEADER, "not-the-token"))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Forbidden, response.status());
+        assert!(client.cookies().get(AUTH_TOKEN_COOKIE).is_some());
     }
 
     #[backend_test]
@@ -321,7 +709,7 @@ mod tests {
             .dispatch()
             .await;
 
-        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Status::Accepted, response.status());
 
         // Submit verification
         let cookie = client.cookies().get_private(CHALLENGE_COOKIE).unwrap();
@@ -394,6 +782,338 @@ mod tests {
         assert_eq!(Status::UnprocessableEntity, response.status());
     }
 
+    /// Only allow UK mobiles. Challenges are sent before responding, as small
+    /// deployments may choose.
+    fn uk_mobiles_only(figment: Figment) -> Figment {
+        figment
+            .merge(("allowed_sms_prefixes", ["+447"]))
+            .merge(("sms_queue_enabled", false))
+    }
+
+    #[backend_test(config = "uk_mobiles_only")]
+    async fn restricted_sms_prefixes(client: Client) {
+        // A US number is blocked before any SMS is sent.
+        let mut body = json!(VoterChallengeRequest::example());
+        body["sms"] = json!("+15555550100");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        assert!(client.cookies().get_private(CHALLENGE_COOKIE).is_none());
+
+        // A UK mobile is allowed.
+        let mut body = json!(VoterChallengeRequest::example());
+        body["sms"] = json!("+44 7700 900123");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        assert!(client.cookies().get_private(CHALLENGE_COOKIE).is_some());
+    }
+
+    /// Read national numbers as British. Challenges are sent before
+    /// responding, as small deployments may choose.
+    fn uk_default_region(figment: Figment) -> Figment {
+        figment
+            .merge(("default_sms_region", "GB"))
+            .merge(("sms_queue_enabled", false))
+    }
+
+    #[backend_test(config = "uk_default_region")]
+    async fn national_sms_format(client: Client, db: Database) {
+        // A nationally formatted number is read as being from the default
+        // region, and becomes the same challenge as its international form.
+        let mut body = json!(VoterChallengeRequest::example());
+        body["sms"] = json!("07700 900123");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let config = client.rocket().state::<Config>().unwrap();
+        let cookie = client.cookies().get_private(CHALLENGE_COOKIE).unwrap();
+        let challenge = Challenge::from_cookie(&cookie, config).unwrap();
+        assert_eq!(challenge.sms, "+447700900123".parse::<Sms>().unwrap());
+
+        // Its region is recorded with its delivery.
+        let delivery = Coll::<ChallengeDelivery>::from_db(&db)
+            .find_one(
+                doc! { "sms_hmac": challenge.sms.into_hmac(config).to_bytestring() },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("GB".to_string()), delivery.region);
+
+        // Anything else is still rejected.
+        body["sms"] = json!("not a number");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+    }
+
+    #[backend_test]
+    async fn national_sms_format_needs_region(client: Client) {
+        // Without a default region, only international formats are accepted.
+        let mut body = json!(VoterChallengeRequest::example());
+        body["sms"] = json!("07700 900123");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        assert!(client.cookies().get_private(CHALLENGE_COOKIE).is_none());
+    }
+
+    /// Share cookies across a domain, under a path.
+    fn scoped_cookie_config(figment: Figment) -> Figment {
+        figment
+            .merge(("cookie_domain", ".vote.example.ac.uk"))
+            .merge(("cookie_path", "/api"))
+    }
+
+    #[backend_test(config = "scoped_cookie_config")]
+    async fn scoped_cookies(client: Client, db: Database) {
+        Coll::<NewAdmin>::from_db(&db)
+            .insert_one(NewAdmin::example(), None)
+            .await
+            .unwrap();
+        let login = |host: &'static str| {
+            client
+                .post(uri!(authenticate))
+                .header(ContentType::JSON)
+                .header(Header::new("Host", host))
+                .body(json!(AdminCredentials::example1()).to_string())
+                .dispatch()
+        };
+
+        // A request to a host outside the domain gets host-only cookies, which
+        // its browser would accept, but still on the configured path.
+        let response = login("localhost").await;
+        assert_eq!(Status::Ok, response.status());
+        let auth_cookie = response.cookies().get(AUTH_TOKEN_COOKIE).unwrap();
+        assert_eq!(None, auth_cookie.domain());
+        assert_eq!(Some("/api"), auth_cookie.path());
+
+        // A request to a subdomain gets cookies shared with the whole domain.
+        let response = login("api.vote.example.ac.uk").await;
+        assert_eq!(Status::Ok, response.status());
+        for name in [AUTH_TOKEN_COOKIE, CSRF_COOKIE] {
+            let cookie = response.cookies().get(name).unwrap();
+            assert_eq!(Some("vote.example.ac.uk"), cookie.domain());
+            assert_eq!(Some("/api"), cookie.path());
+        }
+
+        // Logging out removes them with the same scope, or browsers would keep them.
+        let response = client
+            .delete(uri!(logout_admin))
+            .header(Header::new("Host", "api.vote.example.ac.uk"))
+            .csrf()
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        for name in [AUTH_TOKEN_COOKIE, CSRF_COOKIE] {
+            let cookie = response.cookies().get(name).unwrap();
+            assert_eq!("", cookie.value());
+            assert_eq!(Some("vote.example.ac.uk"), cookie.domain());
+            assert_eq!(Some("/api"), cookie.path());
+        }
+        assert_eq!(None, client.cookies().get(AUTH_TOKEN_COOKIE));
+    }
+
+    #[backend_test]
+    async fn landline_voter_sms(client: Client) {
+        let mut body = json!(VoterChallengeRequest::example());
+        body["sms"] = json!("+442079460000");
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(body.to_string())
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        assert!(client.cookies().get_private(CHALLENGE_COOKIE).is_none());
+    }
+
+    /// Sends nothing, but reports every message as having the given status.
+    struct MockNotifier {
+        status: DeliveryStatus,
+        sent: AtomicUsize,
+    }
+
+    impl MockNotifier {
+        fn new(status: DeliveryStatus) -> Self {
+            Self {
+                status,
+                sent: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[rocket::async_trait]
+    impl SmsNotifier for MockNotifier {
+        async fn send_sms(&self, _sms: &Sms, _message: String) -> SmsOutcome {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            match self.status {
+                DeliveryStatus::Sent => SmsOutcome::sent(Some("mock-id".to_string())),
+                status => SmsOutcome::not_sent(status),
+            }
+        }
+    }
+
+    const REQUEST_ID: RequestId = RequestId {
+        id: 42,
+        client_op_id: None,
+    };
+
+    async fn deliver(
+        client: &Client,
+        deliveries: &Coll<ChallengeDelivery>,
+        notifier: &MockNotifier,
+    ) -> Result<()> {
+        let config = client.rocket().state::<Config>().unwrap();
+        deliver_sms(
+            &Sms::example(),
+            Sms::example_hmac(client),
+            "Your code".to_string(),
+            config.max_sms_delivery_failures(),
+            deliveries,
+            notifier,
+            REQUEST_ID,
+        )
+        .await
+    }
+
+    #[backend_test]
+    async fn undeliverable_sms(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        let delivery = || async {
+            deliveries
+                .find_one(
+                    doc! { "sms_hmac": Sms::example_hmac(&client).to_bytestring() },
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap()
+        };
+
+        // Failures are reported as such until there have been too many in a row.
+        let opted_out = MockNotifier::new(DeliveryStatus::OptedOut);
+        let error = deliver(&client, &deliveries, &opted_out).await.unwrap_err();
+        assert_eq!(Status::UnprocessableEntity, error.status());
+        assert_eq!(None, error.code());
+        let invalid = MockNotifier::new(DeliveryStatus::InvalidNumber);
+        let error = deliver(&client, &deliveries, &invalid).await.unwrap_err();
+        assert_eq!(Status::UnprocessableEntity, error.status());
+        assert_eq!(2, delivery().await.failures);
+        let error = deliver(&client, &deliveries, &invalid).await.unwrap_err();
+        assert_eq!(Some("sms_undeliverable"), error.code());
+        assert_eq!(DeliveryStatus::InvalidNumber, delivery().await.status);
+
+        // After that, nothing more is sent.
+        let working = MockNotifier::new(DeliveryStatus::Sent);
+        let error = deliver(&client, &deliveries, &working).await.unwrap_err();
+        assert_eq!(Some("sms_undeliverable"), error.code());
+        assert_eq!(0, working.sent.load(Ordering::SeqCst));
+
+        // Until a code is verified, proving that challenges do arrive.
+        record_delivered(&Sms::example_hmac(&client), &deliveries, REQUEST_ID).await;
+        assert_eq!(DeliveryStatus::Delivered, delivery().await.status);
+        deliver(&client, &deliveries, &working).await.unwrap();
+        assert_eq!(1, working.sent.load(Ordering::SeqCst));
+        let delivery = delivery().await;
+        assert_eq!(DeliveryStatus::Sent, delivery.status);
+        assert_eq!(Some("mock-id".to_string()), delivery.message_id);
+        assert_eq!(0, delivery.failures);
+        assert_eq!(Some("GB".to_string()), delivery.region);
+    }
+
+    #[backend_test]
+    async fn sms_provider_failures(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        let failures = || async {
+            deliveries
+                .find_one(
+                    doc! { "sms_hmac": Sms::example_hmac(&client).to_bytestring() },
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .failures
+        };
+
+        // A number one strike from being given up on...
+        let invalid = MockNotifier::new(DeliveryStatus::InvalidNumber);
+        for _ in 1..client
+            .rocket()
+            .state::<Config>()
+            .unwrap()
+            .max_sms_delivery_failures()
+        {
+            deliver(&client, &deliveries, &invalid).await.unwrap_err();
+        }
+        let strikes = failures().await;
+
+        // ...is not held responsible however often SNS itself fails.
+        let failed = MockNotifier::new(DeliveryStatus::Failed);
+        for _ in 0..5 {
+            let error = deliver(&client, &deliveries, &failed).await.unwrap_err();
+            assert_eq!(Status::InternalServerError, error.status());
+            assert_eq!(strikes, failures().await);
+        }
+        assert_eq!(5, failed.sent.load(Ordering::SeqCst));
+
+        // Nor are its strikes forgotten.
+        let error = deliver(&client, &deliveries, &invalid).await.unwrap_err();
+        assert_eq!(Some("sms_undeliverable"), error.code());
+    }
+
+    #[backend_test]
+    async fn undeliverable_sms_challenge(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        deliveries
+            .insert_one(
+                ChallengeDelivery {
+                    sms_hmac: Sms::example_hmac(&client),
+                    region: None,
+                    message_id: None,
+                    status: DeliveryStatus::Failed,
+                    failures: 3,
+                    updated_at: DateTime::now(),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let response = client
+            .post(uri!(challenge))
+            .header(ContentType::JSON)
+            .body(json!(VoterChallengeRequest::example()).to_string())
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::UnprocessableEntity, response.status());
+        let body: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "sms_undeliverable");
+        assert!(client.cookies().get_private(CHALLENGE_COOKIE).is_none());
+    }
+
     #[backend_test]
     async fn invalid_recaptcha_challenge(client: Client) {
         let response = client
@@ -439,10 +1159,11 @@ mod tests {
 
     #[backend_test(admin)]
     async fn logout_admin(client: Client) {
-        let response = client.delete(uri!(logout_admin)).dispatch().await;
+        let response = client.delete(uri!(logout_admin)).csrf().dispatch().await;
 
         assert_eq!(Status::Ok, response.status());
         assert_eq!(None, client.cookies().get(AUTH_TOKEN_COOKIE));
+        assert_eq!(None, client.cookies().get(CSRF_COOKIE));
     }
 
     #[backend_test]
@@ -469,7 +1190,7 @@ mod tests {
 
         assert!(client.cookies().get(AUTH_TOKEN_COOKIE).is_some());
 
-        let response = client.delete(uri!(logout_voter)).dispatch().await;
+        let response = client.delete(uri!(logout_voter)).csrf().dispatch().await;
 
         assert_eq!(Status::Ok, response.status());
         assert_eq!(None, client.cookies().get(AUTH_TOKEN_COOKIE));
@@ -0,0 +1,221 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+
+use ipnet::IpNet;
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+
+use crate::config::Config;
+
+/// Name of the header to which each proxy appends the address it received a
+/// request from.
+pub const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// The IP address of the client that made a request, as far as we can tell.
+///
+/// Behind a load balancer, the socket address is the load balancer's, so the
+/// client's is taken from the [`FORWARDED_FOR_HEADER`] instead, but only as
+/// far back as it was added by proxies in [`Config::trusted_proxies`]. Anyone
+/// can send the header, so it is ignored entirely unless the request came
+/// straight from a trusted proxy.
+///
+/// `None` if the socket address is unknown, which only happens in tests.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl ClientIp {
+    /// Work out the client IP of the given request.
+    pub fn for_request(req: &Request<'_>) -> Self {
+        *req.local_cache(|| {
+            let trusted_proxies = req
+                .rocket()
+                .state::<Config>()
+                .map(Config::trusted_proxies)
+                .unwrap_or_default();
+            let forwarded_for = req
+                .headers()
+                .get(FORWARDED_FOR_HEADER)
+                .collect::<Vec<_>>()
+                .join(",");
+            let peer = req.remote().map(|remote| remote.ip());
+            ClientIp(resolve(peer, &forwarded_for, trusted_proxies))
+        })
+    }
+}
+
+impl Display for ClientIp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(ip) => write!(f, "{ip}"),
+            None => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Allow the client IP to be accessed via request guard.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = (); // No errors possible, use the `!` type once stabilised.
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientIp::for_request(req))
+    }
+}
+
+/// Find the client IP given the socket peer address and the comma-separated
+/// hops of the forwarded-for header.
+///
+/// Hops are walked from the right, i.e. starting with the one added by the
+/// peer, for as long as the address they were received from is a trusted
+/// proxy. The first untrusted address is the client. A malformed hop stops the
+/// walk at the proxy that added it.
+fn resolve(peer: Option<IpAddr>, forwarded_for: &str, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+    let mut client = peer?.to_canonical();
+    for hop in forwarded_for.rsplit(',').map(str::trim) {
+        if !is_trusted(&client) {
+            break;
+        }
+        if hop.is_empty() {
+            continue;
+        }
+        match parse_hop(hop) {
+            Some(ip) => client = ip.to_canonical(),
+            None => break,
+        }
+    }
+    Some(client)
+}
+
+/// Parse a single forwarded-for hop, which some proxies write with a port.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .or_else(|_| hop.trim_matches(['[', ']']).parse::<IpAddr>())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{figment::Figment, local::asynchronous::Client};
+
+    use super::*;
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    fn nets(nets: &[&str]) -> Vec<IpNet> {
+        nets.iter().map(|net| net.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn untrusted_peer() {
+        let trusted = nets(&["10.0.0.0/8"]);
+        // No proxies at all.
+        assert_eq!(resolve(ip("203.0.113.7"), "", &[]), ip("203.0.113.7"));
+        // A client claiming to be someone else is ignored.
+        assert_eq!(
+            resolve(ip("203.0.113.7"), "198.51.100.1", &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            resolve(ip("203.0.113.7"), "198.51.100.1", &[]),
+            ip("203.0.113.7")
+        );
+        // Unknown peers stay unknown.
+        assert_eq!(resolve(None, "198.51.100.1", &trusted), None);
+    }
+
+    #[test]
+    fn trusted_hops() {
+        let trusted = nets(&["10.0.0.0/8", "192.168.1.1/32"]);
+        // A single load balancer.
+        assert_eq!(
+            resolve(ip("10.0.0.5"), "203.0.113.7", &trusted),
+            ip("203.0.113.7")
+        );
+        // Multiple trusted hops, with a spoofed entry from the client on the
+        // left that is never reached.
+        assert_eq!(
+            resolve(
+                ip("10.0.0.5"),
+                "198.51.100.1, 203.0.113.7, 192.168.1.1",
+                &trusted
+            ),
+            ip("203.0.113.7")
+        );
+        // Every hop trusted: the leftmost is as far back as we can go.
+        assert_eq!(
+            resolve(ip("10.0.0.5"), "10.1.1.1,10.2.2.2", &trusted),
+            ip("10.1.1.1")
+        );
+        // A trusted proxy without the header is itself the client.
+        assert_eq!(resolve(ip("10.0.0.5"), "", &trusted), ip("10.0.0.5"));
+        // A malformed hop stops at the proxy that added it.
+        assert_eq!(
+            resolve(ip("10.0.0.5"), "203.0.113.7, garbage", &trusted),
+            ip("10.0.0.5")
+        );
+        // Hops with ports.
+        assert_eq!(
+            resolve(ip("10.0.0.5"), "203.0.113.7:51234", &trusted),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn ipv6() {
+        let trusted = nets(&["fd00::/8", "10.0.0.0/8"]);
+        assert_eq!(
+            resolve(ip("fd00::1"), "2001:db8::7", &trusted),
+            ip("2001:db8::7")
+        );
+        assert_eq!(
+            resolve(ip("fd00::1"), "[2001:db8::7]:443", &trusted),
+            ip("2001:db8::7")
+        );
+        assert_eq!(
+            resolve(ip("fd00::1"), "[2001:db8::7]", &trusted),
+            ip("2001:db8::7")
+        );
+        // IPv4 peers on a dual-stack socket are still matched against IPv4 ranges.
+        assert_eq!(
+            resolve(ip("::ffff:10.0.0.5"), "203.0.113.7", &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            resolve(ip("2001:db8::1"), "203.0.113.7", &trusted),
+            ip("2001:db8::1")
+        );
+    }
+
+    fn behind_load_balancer(figment: Figment) -> Figment {
+        figment.merge(("trusted_proxies", ["10.0.0.0/8"]))
+    }
+
+    #[backend_test(config = "behind_load_balancer")]
+    async fn logged_client_ip(client: Client) {
+        // Through the load balancer.
+        let request = client
+            .get("/elections")
+            .remote("10.0.0.5:443".parse().unwrap())
+            .header(rocket::http::Header::new(
+                FORWARDED_FOR_HEADER,
+                "198.51.100.1, 203.0.113.7",
+            ));
+        assert!(crate::logging::request_log_line(&request).ends_with(" from 203.0.113.7"));
+
+        // Straight from a client spoofing the header.
+        let request = client
+            .get("/elections")
+            .remote("198.51.100.1:51234".parse().unwrap())
+            .header(rocket::http::Header::new(
+                FORWARDED_FOR_HEADER,
+                "203.0.113.7",
+            ));
+        assert!(crate::logging::request_log_line(&request).ends_with(" from 198.51.100.1"));
+    }
+}
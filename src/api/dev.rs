@@ -0,0 +1,236 @@
+//! Endpoints for local development only, compiled in only with the
+//! `dev-tools` feature, and never in release builds.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use mongodb::{bson::doc, options::FindOptions, Client, Database};
+use rocket::{futures::TryStreamExt, http::Status, serde::json::Json, Route, State};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    logging::RequestId,
+    model::{
+        api::{
+            auth::{AuthToken, CsrfChecked},
+            election::{ElectionResults, ElectionSpec, QuestionSpec},
+        },
+        common::election::{ElectionState, QuestionId},
+        db::{
+            admin::Admin,
+            ballot::AnyBallot,
+            board::{BoardEntry, BoardHead},
+            candidate_totals::{CandidateTotals, TotalsDelta},
+            election::Election,
+        },
+        mongodb::{Coll, Counter, RequestComment, ELECTION_ID_COUNTER_ID},
+    },
+    testdata::{BallotCounts, SeedBallots},
+};
+
+use super::public::dump_question;
+
+/// The most questions a generated election may have.
+const MAX_GENERATED_QUESTIONS: usize = 20;
+
+/// The most ballots in each state that may be generated for each question.
+const MAX_GENERATED_BALLOTS: usize = 1000;
+
+pub fn routes() -> Vec<Route> {
+    routes![generate_dump]
+}
+
+/// What [`generate_dump`] should generate.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct GenerateDumpSpec {
+    /// Number of questions, cycling through the example questions.
+    questions: usize,
+    /// Number of ballots in each state for each question.
+    #[serde(flatten)]
+    ballots: BallotCounts,
+}
+
+/// Everything needed to test a client or verifier against a generated election.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeneratedDump {
+    /// The election document, as stored.
+    election: Election,
+    /// Every ballot, in every state, as stored.
+    ballots: Vec<AnyBallot>,
+    /// The candidate totals, as stored.
+    totals: Vec<CandidateTotals>,
+    /// The dump of each question, with totals, as from the dump endpoint.
+    dumps: HashMap<QuestionId, ElectionResults>,
+}
+
+/// Generate a finished election with random ballots, store it, and return
+/// everything about it, for end-to-end tests of clients and verifiers.
+#[post("/dev/generate-dump", data = "<spec>", format = "json")]
+#[allow(clippy::too_many_arguments)]
+async fn generate_dump(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    spec: Json<GenerateDumpSpec>,
+    elections: Coll<Election>,
+    counters: Coll<Counter>,
+    totals: Coll<CandidateTotals>,
+    totals_deltas: Coll<TotalsDelta>,
+    ballots: Coll<AnyBallot>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
+    db_client: &State<Client>,
+    db: &State<Database>,
+    request_id: RequestId,
+) -> Result<Json<GeneratedDump>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let counts = spec.ballots;
+    if spec.questions == 0 || spec.questions > MAX_GENERATED_QUESTIONS {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!("Between 1 and {MAX_GENERATED_QUESTIONS} questions may be generated"),
+        ));
+    }
+    if [counts.unconfirmed, counts.audited, counts.confirmed]
+        .into_iter()
+        .any(|count| count > MAX_GENERATED_BALLOTS)
+    {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!("At most {MAX_GENERATED_BALLOTS} ballots of each state may be generated"),
+        ));
+    }
+
+    // Create an election that has just finished, so its totals are public.
+    let election_id = Counter::next(&counters, ELECTION_ID_COUNTER_ID).await?;
+    let mut election_spec = ElectionSpec::current_example();
+    election_spec.name = format!("Generated Election {election_id}");
+    election_spec.end_time = Utc::now();
+    election_spec.start_time = election_spec.end_time - Duration::try_days(1).unwrap();
+    election_spec.questions = (0..spec.questions).map(example_question).collect();
+    let mut election = election_spec.into_election(election_id, rand::thread_rng());
+    election.metadata.state = ElectionState::Published;
+    elections.insert_one(&election, None).await?;
+    SeedBallots::new(&election, counts, rand::thread_rng())
+        .insert(db)
+        .await?;
+    debug!(
+        "  req{} Generated election {} with {} questions",
+        request_id, election_id, spec.questions
+    );
+
+    // Dump it exactly as the public endpoints would.
+    let mut dumps = HashMap::new();
+    for question_id in election.questions.keys() {
+        let (_, dump) = dump_question(
+            election_id,
+            *question_id,
+            true,
+            None,
+            &elections,
+            &totals,
+            &totals_deltas,
+            &ballots,
+            &board_heads,
+            &board_entries,
+            db_client,
+            request_id,
+        )
+        .await?;
+        dumps.insert(*question_id, dump);
+    }
+    let filter = doc! { "election_id": election_id };
+    let all_ballots = ballots
+        .find(filter.clone(), FindOptions::for_request(request_id))
+        .await?
+        .try_collect()
+        .await?;
+    let all_totals = totals
+        .find(filter, FindOptions::for_request(request_id))
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(GeneratedDump {
+        election,
+        ballots: all_ballots,
+        totals: all_totals,
+        dumps,
+    }))
+}
+
+/// The `index`th generated question, cycling through the example questions.
+/// Descriptions are made unique after the first cycle.
+fn example_question(index: usize) -> QuestionSpec {
+    let mut question = match index % 4 {
+        0 => QuestionSpec::example1(),
+        1 => QuestionSpec::example2(),
+        2 => QuestionSpec::example3(),
+        _ => QuestionSpec::example4(),
+    };
+    if index >= 4 {
+        question.description = format!("{} ({})", question.description, index / 4 + 1);
+    }
+    question
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::ContentType,
+        local::asynchronous::Client,
+        serde::json::serde_json::{self, json},
+    };
+
+    use crate::model::api::auth::WithCsrf;
+
+    use super::*;
+
+    #[backend_test(admin)]
+    async fn generate_dump(client: Client) {
+        let response = client
+            .post(uri!(generate_dump))
+            .header(ContentType::JSON)
+            .csrf()
+            .body(
+                json!({
+                    "questions": 5,
+                    "unconfirmed": 1,
+                    "audited": 2,
+                    "confirmed": 3,
+                })
+                .to_string(),
+            )
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let dump: GeneratedDump =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(dump.election.questions.len(), 5);
+        assert_eq!(dump.ballots.len(), 5 * 6);
+        assert_eq!(dump.totals.len(), 5 * 2);
+        assert_eq!(dump.dumps.len(), 5);
+        for results in dump.dumps.values() {
+            assert_eq!(results.audited.len(), 2);
+            assert_eq!(results.confirmed.len(), 3);
+            assert!(results.totals.is_some());
+            results.verify().unwrap();
+        }
+
+        // Silly requests are refused.
+        for spec in [
+            json!({"questions": 0, "unconfirmed": 0, "audited": 0, "confirmed": 1}),
+            json!({"questions": 1, "unconfirmed": 0, "audited": 1001, "confirmed": 1}),
+        ] {
+            let response = client
+                .post(uri!(generate_dump))
+                .header(ContentType::JSON)
+                .csrf()
+                .body(spec.to_string())
+                .dispatch()
+                .await;
+            assert_eq!(Status::UnprocessableEntity, response.status());
+        }
+    }
+}
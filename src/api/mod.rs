@@ -1,8 +1,15 @@
 use rocket::Route;
 
 mod admin;
+pub mod archive_export;
 mod auth;
+pub mod client_ip;
+#[cfg(all(feature = "dev-tools", debug_assertions))]
+mod dev;
 mod public;
+pub mod recent_casts;
+mod rehearsal;
+pub mod sms_queue;
 mod voting;
 
 pub fn routes() -> Vec<Route> {
@@ -11,5 +18,8 @@ pub fn routes() -> Vec<Route> {
     routes.extend(public::routes());
     routes.extend(auth::routes());
     routes.extend(voting::routes());
+    routes.extend(rehearsal::routes());
+    #[cfg(all(feature = "dev-tools", debug_assertions))]
+    routes.extend(dev::routes());
     routes
 }
@@ -1,36 +1,53 @@
 use std::collections::HashMap;
+use std::time::Duration as StdDuration;
 
 use chrono::Utc;
 use mongodb::{
-    bson::doc,
-    options::{FindOptions, SessionOptions},
-    Client,
-};
-use rocket::{
-    futures::{StreamExt, TryStreamExt},
-    serde::json::Json,
-    Route, State,
+    bson::{doc, DateTime, Document},
+    options::{AggregateOptions, CountOptions, FindOneOptions, FindOptions, SessionOptions},
+    Client, ClientSession,
 };
+use rocket::{futures::TryStreamExt, http::Status, serde::json::Json, Route, State};
+use serde::Deserialize;
 
 use crate::{
-    error::{Error, Result},
+    config::Config,
+    error::{with_retries, Error, Result, READ_RETRIES},
     logging::RequestId,
     model::{
         api::{
             auth::AuthToken,
-            candidate_totals::CandidateTotalsDesc,
-            election::{ElectionDescription, ElectionResults, ElectionSummary, ElectionTiming},
-            pagination::{Paginated, PaginationRequest},
-            receipt::{PublicReceipt, Receipt},
+            ballot::BallotStats,
+            board::BoardHeadDesc,
+            candidate_totals::{tally_to_u64, CandidateTotalsDesc},
+            cbor::Negotiated,
+            election::{
+                ElectionDescription, ElectionResults, ElectionResultsChanges, ElectionSummary,
+                ElectionTiming, QuestionCrypto,
+            },
+            full_results::{FullResults, QuestionResults},
+            pagination::{MaybePaginated, OptionalPagination, Paginated, PaginationRequest},
+            receipt::{
+                normalize_confirmation_phrase, PublicReceipt, Receipt, ReceiptState,
+                CONFIRMATION_PHRASE_LENGTH, RECEIPT_SCHEMA_VERSION,
+            },
         },
         common::{
-            ballot::{Audited, BallotId, Confirmed},
-            election::{CandidateId, ElectionId, ElectionState, QuestionId},
+            ballot::{Audited, AuditedRedacted, BallotId, Confirmed, Unconfirmed},
+            board::BoardChain,
+            election::{
+                check_lang_code, CandidateId, ElectionId, ElectionState, QuestionId, QuorumReport,
+            },
         },
         db::{
-            admin::Admin, ballot::AnyBallot, candidate_totals::CandidateTotals, election::Election,
+            admin::Admin,
+            ballot::{AnyBallot, UnknownBallot},
+            board::{board_head_id, BoardEntry, BoardHead},
+            candidate_totals::{election_totals, question_totals, CandidateTotals, TotalsDelta},
+            election::Election,
+            voter_election::VoterElection,
         },
-        mongodb::{u32_id_filter, Coll},
+        mongodb::{u32_id_filter, Coll, RequestComment, SecondaryColl},
     },
 };
 
@@ -43,126 +60,348 @@ pub fn routes() -> Vec<Route> {
         election_question_ballots,
         election_question_ballot,
         candidate_totals,
+        ballot_stats,
+        full_results,
+        board_head,
+        question_crypto,
         question_dump,
+        question_dump_changes,
     ]
 }
 
-#[get("/elections?<archived>&<timing>", rank = 1)]
+/// Fields that election listings can be sorted by.
+const ELECTION_SORT_FIELDS: &[&str] = &["start_time", "end_time", "name"];
+
+/// Fields that ballot listings can be sorted by.
+const BALLOT_SORT_FIELDS: &[&str] = &["ballot_id"];
+
+/// List elections, including drafts. Every election is listed unless
+/// pagination or sorting is asked for; see [`OptionalPagination`].
+/// Names are translated into `lang`, if given, where there is a translation.
+/// Unlike the public listing, this reads from the primary, so that admins see
+/// elections they have just created or edited.
+#[get("/elections?<archived>&<timing>&<lang>&<pagination..>", rank = 1)]
 async fn elections_admin(
     token: AuthToken<Admin>,
     archived: Option<bool>,
     timing: Option<ElectionTiming>,
+    lang: Option<String>,
+    pagination: OptionalPagination,
     elections: Coll<Election>,
     request_id: RequestId,
-) -> Result<Json<Vec<ElectionSummary>>> {
+) -> Result<Json<MaybePaginated<ElectionSummary>>> {
     info!("  req{} Admin {} acting", request_id, token.id);
+    check_lang(lang.as_deref())?;
     let archived = archived.unwrap_or(false);
-    metadata_for_elections(request_id, elections, true, archived, timing).await
+    with_retries(READ_RETRIES, || {
+        metadata_for_elections(
+            request_id,
+            &elections,
+            true,
+            archived,
+            timing,
+            lang.as_deref(),
+            pagination.0.as_ref(),
+        )
+    })
+    .await
 }
 
-#[get("/elections?<archived>&<timing>", rank = 2)]
+/// List published elections. Every election is listed unless pagination or
+/// sorting is asked for; see [`OptionalPagination`].
+/// Names are translated into `lang`, if given, where there is a translation.
+#[get("/elections?<archived>&<timing>&<lang>&<pagination..>", rank = 2)]
 async fn elections_non_admin(
     archived: Option<bool>,
     timing: Option<ElectionTiming>,
-    elections: Coll<Election>,
+    lang: Option<String>,
+    pagination: OptionalPagination,
+    elections: SecondaryColl<Election>,
     request_id: RequestId,
-) -> Result<Json<Vec<ElectionSummary>>> {
+) -> Result<Json<MaybePaginated<ElectionSummary>>> {
+    check_lang(lang.as_deref())?;
     let archived = archived.unwrap_or(false);
-    metadata_for_elections(request_id, elections, false, archived, timing).await
+    with_retries(READ_RETRIES, || {
+        metadata_for_elections(
+            request_id,
+            &elections,
+            false,
+            archived,
+            timing,
+            lang.as_deref(),
+            pagination.0.as_ref(),
+        )
+    })
+    .await
 }
 
-#[get("/elections/<election_id>", rank = 1)]
+/// Describe an election. Display strings are translated into `lang`, if
+/// given, where there is a translation.
+#[get("/elections/<election_id>?<lang>", rank = 1)]
 async fn election_admin(
     token: AuthToken<Admin>,
     election_id: ElectionId,
+    lang: Option<String>,
     elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    voter_elections: Coll<VoterElection>,
     request_id: RequestId,
 ) -> Result<Json<ElectionDescription>> {
     info!("  req{} Admin {} acting", request_id, token.id);
+    check_lang(lang.as_deref())?;
     let election = elections
         .find_one(u32_id_filter(election_id), None)
         .await?
         .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
-    Ok(Json(election.into()))
+    let finished = election_finished(&election);
+    let mut description = ElectionDescription::for_admin(election);
+    if finished {
+        add_quorum_reports(
+            &mut description,
+            &ballots,
+            &voter_elections,
+            None,
+            request_id,
+        )
+        .await?;
+    }
+    if let Some(lang) = lang {
+        description.localise(&lang);
+    }
+    Ok(Json(description))
 }
 
-#[get("/elections/<election_id>", rank = 2)]
+/// Describe a published election. Display strings are translated into `lang`,
+/// if given, where there is a translation.
+#[get("/elections/<election_id>?<lang>", rank = 2)]
 async fn election_non_admin(
     election_id: ElectionId,
+    lang: Option<String>,
     elections: Coll<Election>,
+    ballots: SecondaryColl<AnyBallot>,
+    voter_elections: SecondaryColl<VoterElection>,
+    config: &State<Config>,
+    request_id: RequestId,
 ) -> Result<Json<ElectionDescription>> {
+    check_lang(lang.as_deref())?;
     let filter = doc! {
         "_id": election_id,
         "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+        "rehearsal": {"$ne": true},
     };
 
-    let election = elections
-        .find_one(filter, None)
-        .await?
-        .ok_or_else(|| Error::not_found(format!("Non-admin election with ID '{}'", election_id)))?;
+    let election = with_retries(READ_RETRIES, || async {
+        Ok(elections.find_one(filter.clone(), None).await?)
+    })
+    .await?
+    .ok_or_else(|| Error::not_found(format!("Non-admin election with ID '{}'", election_id)))?;
+
+    let finished = election_finished(&election);
+    let mut description = ElectionDescription::from(election);
+    if finished {
+        add_quorum_reports(
+            &mut description,
+            &ballots,
+            &voter_elections,
+            Some(config.public_query_timeout()),
+            request_id,
+        )
+        .await?;
+    }
+    if let Some(lang) = lang {
+        description.localise(&lang);
+    }
+    Ok(Json(description))
+}
+
+/// Check that the requested language, if any, is a well-formed language code.
+fn check_lang(lang: Option<&str>) -> Result<()> {
+    match lang {
+        Some(lang) => check_lang_code(lang).map_err(|e| Error::Status(Status::BadRequest, e)),
+        None => Ok(()),
+    }
+}
+
+/// Has the given election ended, so that its results are available?
+fn election_finished(election: &Election) -> bool {
+    match election.metadata.state {
+        ElectionState::Draft => false,
+        ElectionState::Archived => true,
+        _ => Utc::now() > election.metadata.end_time,
+    }
+}
 
-    Ok(Json(election.into()))
+/// Report on the quorum of every question in a finished election's description,
+/// giving up on the counts after `max_time`, if given.
+///
+/// Counting is skipped if no question has a quorum, as there is then nothing
+/// to report that the full results do not already show.
+async fn add_quorum_reports(
+    description: &mut ElectionDescription,
+    ballots: &Coll<AnyBallot>,
+    voter_elections: &Coll<VoterElection>,
+    max_time: Option<StdDuration>,
+    request_id: RequestId,
+) -> Result<()> {
+    if description
+        .questions
+        .values()
+        .all(|question| question.quorum.is_none())
+    {
+        return Ok(());
+    }
+    let mut stats_by_question =
+        election_ballot_stats(ballots, description.id, max_time, request_id).await?;
+    let mut eligible_by_question = VoterElection::eligible_voters_by_question(
+        voter_elections,
+        description.id,
+        AggregateOptions::builder()
+            .max_time(max_time)
+            .build()
+            .with_request_id(request_id),
+    )
+    .await?;
+    for question in description.questions.values_mut() {
+        let confirmed = stats_by_question
+            .remove(&question.id)
+            .unwrap_or_default()
+            .confirmed;
+        let eligible = eligible_by_question
+            .remove(&question.id)
+            .unwrap_or_default();
+        question.quorum_report = Some(QuorumReport::new(question.quorum, eligible, confirmed));
+    }
+    Ok(())
 }
 
-#[get("/elections/<election_id>/<question_id>/ballots?<filter_pattern>&<pagination..>")]
+/// List a question's ballots, optionally only those in the given `state`.
+///
+/// Audited ballots can further be filtered by the `candidate` they reveal, and
+/// any ballot by a pattern its ID must match; see [`check_filter_pattern`].
+/// Ballots can also be found by their confirmation `phrase`, which may match
+/// more than one; all of them are listed. Phrases are stored with the ballots,
+/// so this is an indexed query like any other filter.
+#[get(
+    "/elections/<election_id>/<question_id>/ballots?<filter_pattern>&<state>&<candidate>&<phrase>&<pagination..>"
+)]
+#[allow(clippy::too_many_arguments)]
 async fn election_question_ballots(
     election_id: ElectionId,
     question_id: QuestionId,
     filter_pattern: Option<String>,
+    state: Option<ReceiptState>,
+    candidate: Option<CandidateId>,
+    phrase: Option<String>,
     pagination: PaginationRequest,
-    elections: Coll<Election>,
-    ballots: Coll<AnyBallot>,
+    admin: Option<AuthToken<Admin>>,
+    elections: SecondaryColl<Election>,
+    ballots: SecondaryColl<AnyBallot>,
+    config: &State<Config>,
     request_id: RequestId,
 ) -> Result<Json<Paginated<PublicReceipt>>> {
-    // No need to filter our drafts if non-admin, since draft elections cannot have ballots.
-    let election = elections
-        .find_one(u32_id_filter(election_id), None)
-        .await?
-        .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
-
-    let mut filter = doc! {
-        "election_id": election_id,
-        "question_id": question_id,
-    };
-    if let Some(pattern) = filter_pattern {
-        filter.insert(
-            "$expr",
-            doc! {
-                "$regexMatch": {
-                    "input": {"$toString": "$ballot_id"},
-                    "regex": pattern,
-                }
-            },
-        );
+    // Only audited ballots reveal their candidate.
+    if candidate.is_some() && state != Some(ReceiptState::Audited) {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Ballots can only be filtered by candidate when filtering audited ballots".to_string(),
+        ));
     }
+    if let Some(pattern) = &filter_pattern {
+        check_filter_pattern(pattern)?;
+    }
+    let sort = pagination
+        .sort(BALLOT_SORT_FIELDS)
+        .map_err(|e| Error::Status(Status::UnprocessableEntity, e))?;
+    let phrase = phrase
+        .map(|phrase| {
+            normalize_confirmation_phrase(&phrase).ok_or_else(|| {
+                Error::Status(
+                    Status::BadRequest,
+                    format!(
+                        "A confirmation phrase is {CONFIRMATION_PHRASE_LENGTH} words from the word list"
+                    ),
+                )
+            })
+        })
+        .transpose()?;
+
+    with_retries(READ_RETRIES, || async {
+        // No need to filter our drafts if non-admin, since draft elections cannot have ballots.
+        let election = elections
+            .find_one(
+                u32_id_filter(election_id),
+                FindOneOptions::for_request(request_id),
+            )
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+        check_board_embargo(&election, admin.as_ref(), request_id)?;
 
-    let pagination_options = FindOptions::builder()
-        .skip(u64::from(pagination.skip()))
-        .limit(i64::from(pagination.page_size()))
-        .build();
-    trace!(
-        "  req{} Using page size {}",
-        request_id,
-        pagination.page_size()
-    );
+        let mut filter = doc! {
+            "election_id": election_id,
+            "question_id": question_id,
+        };
+        if let Some(pattern) = &filter_pattern {
+            filter.insert(
+                "$expr",
+                doc! {
+                    "$regexMatch": {
+                        "input": {"$toString": "$ballot_id"},
+                        "regex": pattern.as_str(),
+                    }
+                },
+            );
+        }
+        if let Some(state) = state {
+            filter.insert("state", state);
+        }
+        if let Some(candidate) = &candidate {
+            filter.insert("state_data.candidate", candidate);
+        }
+        if let Some(phrase) = &phrase {
+            filter.insert("confirmation_phrase", phrase.as_str());
+        }
 
-    let ballots_page = ballots
-        .find(filter.clone(), pagination_options)
-        .await?
-        .map(|ballot| ballot.map(|ballot| PublicReceipt::from_ballot(ballot, &election)))
-        .try_collect::<Vec<_>>()
-        .await?;
+        let pagination_options = FindOptions::builder()
+            .sort(sort.clone())
+            .skip(u64::from(pagination.skip()))
+            .limit(i64::from(pagination.page_size()))
+            .max_time(config.public_query_timeout())
+            .build()
+            .with_request_id(request_id);
+        trace!(
+            "  req{} Using page size {}",
+            request_id,
+            pagination.page_size()
+        );
 
-    let total_ballots = ballots.count_documents(filter, None).await?;
+        let mut ballots_page = Vec::new();
+        let mut skipped = 0;
+        let mut ballots_cursor = ballots.find(filter.clone(), pagination_options).await?;
+        while let Some(ballot) = ballots_cursor.try_next().await? {
+            match public_receipt(request_id, ballot, &election) {
+                Some(receipt) => ballots_page.push(receipt),
+                None => skipped += 1,
+            }
+        }
 
-    let paginated = pagination.to_paginated(total_ballots, ballots_page);
-    debug!(
-        "  req{} Returning {} ballots of {} total",
-        request_id,
-        paginated.items.len(),
-        paginated.pagination.total
-    );
-    Ok(Json(paginated))
+        let count_options = CountOptions::builder()
+            .max_time(config.public_query_timeout())
+            .build()
+            .with_request_id(request_id);
+        let total_ballots = ballots.count_documents(filter, count_options).await?;
+
+        let mut paginated = pagination.to_paginated(total_ballots, ballots_page);
+        paginated.pagination.skipped = skipped;
+        debug!(
+            "  req{} Returning {} ballots of {} total ({} skipped)",
+            request_id,
+            paginated.items.len(),
+            paginated.pagination.total,
+            skipped
+        );
+        Ok(Json(paginated))
+    })
+    .await
 }
 
 #[get("/elections/<election_id>/<question_id>/ballots/<ballot_id>")]
@@ -170,86 +409,559 @@ async fn election_question_ballot(
     election_id: ElectionId,
     question_id: QuestionId,
     ballot_id: BallotId,
+    admin: Option<AuthToken<Admin>>,
+    elections: SecondaryColl<Election>,
+    ballots: SecondaryColl<AnyBallot>,
+    request_id: RequestId,
+) -> Result<Negotiated<PublicReceipt>> {
+    with_retries(READ_RETRIES, || async {
+        // No need to filter our drafts if non-admin, since draft elections cannot have ballots.
+        let election = elections
+            .find_one(
+                u32_id_filter(election_id),
+                FindOneOptions::for_request(request_id),
+            )
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+        check_board_embargo(&election, admin.as_ref(), request_id)?;
+
+        let election_question_ballot = doc! {
+            "ballot_id": ballot_id,
+            "election_id": election_id,
+            "question_id": question_id,
+        };
+
+        let ballot = ballots
+            .find_one(
+                election_question_ballot,
+                FindOneOptions::for_request(request_id),
+            )
+            .await?
+            .and_then(|ballot| public_receipt(request_id, ballot, &election))
+            .ok_or_else(|| {
+                Error::not_found(format!(
+                    "Ballot with ID '{}' for election '{}', question '{}'",
+                    ballot_id, election_id, question_id
+                ))
+            })?;
+
+        Ok(Negotiated(ballot))
+    })
+    .await
+}
+
+/// The totals of a question in a finished election.
+///
+/// Questions whose results are not fully public have their totals withheld
+/// from everyone but admins, as though the election were still in progress.
+#[get("/elections/<election_id>/<question_id>/totals")]
+#[allow(clippy::too_many_arguments)]
+async fn candidate_totals(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    admin: Option<AuthToken<Admin>>,
+    elections: SecondaryColl<Election>,
+    totals: SecondaryColl<CandidateTotals>,
+    totals_deltas: SecondaryColl<TotalsDelta>,
+    ballots: SecondaryColl<AnyBallot>,
+    voter_elections: SecondaryColl<VoterElection>,
+    db_client: &State<Client>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<HashMap<CandidateId, CandidateTotalsDesc>>> {
+    let max_time = Some(config.public_query_timeout());
+    with_retries(READ_RETRIES, || async {
+        let election = elections
+            .find_one(u32_id_filter(election_id), None)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+
+        let withheld = admin.is_none()
+            && election
+                .questions
+                .get(&question_id)
+                .is_some_and(|question| !question.result_visibility.shows_totals());
+        if withheld
+            || election.metadata.state != ElectionState::Archived
+                && Utc::now() <= election.metadata.end_time
+        {
+            return Err(Error::not_found(format!(
+                "Election with ID '{}'",
+                election_id
+            )));
+        }
+
+        // Read the totals and any deltas not yet folded into them from the same
+        // snapshot, so that a concurrent fold cannot skew them.
+        let session_options = SessionOptions::builder().snapshot(true).build();
+        let mut session = db_client.start_session(Some(session_options)).await?;
+        let mut question_totals = question_totals(
+            election_id,
+            question_id,
+            &totals,
+            &totals_deltas,
+            &mut session,
+            max_time,
+            request_id,
+        )
+        .await?
+        .into_iter()
+        .map(|(candidate, total)| (candidate, CandidateTotalsDesc::from(total)))
+        .collect::<HashMap<_, _>>();
+
+        let stats =
+            question_ballot_stats(&ballots, election_id, question_id, max_time, request_id).await?;
+        let eligible = VoterElection::eligible_voters(
+            &voter_elections,
+            election_id,
+            question_id,
+            CountOptions::builder()
+                .max_time(max_time)
+                .build()
+                .with_request_id(request_id),
+        )
+        .await?;
+        let quorum = election
+            .questions
+            .get(&question_id)
+            .and_then(|question| question.quorum);
+        let quorum = QuorumReport::new(quorum, eligible, stats.confirmed);
+        for totals in question_totals.values_mut() {
+            totals.ballot_stats = Some(stats.clone());
+            totals.quorum = Some(quorum.clone());
+        }
+
+        Ok(Json(question_totals))
+    })
+    .await
+}
+
+#[get("/elections/<election_id>/<question_id>/stats")]
+async fn ballot_stats(
+    election_id: ElectionId,
+    question_id: QuestionId,
     elections: Coll<Election>,
     ballots: Coll<AnyBallot>,
-) -> Result<Json<PublicReceipt>> {
-    // No need to filter our drafts if non-admin, since draft elections cannot have ballots.
+    request_id: RequestId,
+) -> Result<Json<BallotStats>> {
+    let election_filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+    };
     let election = elections
-        .find_one(u32_id_filter(election_id), None)
+        .find_one(election_filter, FindOneOptions::for_request(request_id))
         .await?
         .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+    if !election.questions.contains_key(&question_id) {
+        return Err(Error::not_found(format!(
+            "Question with ID '{}' in election '{}'",
+            question_id, election_id
+        )));
+    }
 
-    let election_question_ballot = doc! {
-        "ballot_id": ballot_id,
-        "election_id": election_id,
-        "question_id": question_id,
-    };
+    let stats = question_ballot_stats(&ballots, election_id, question_id, None, request_id).await?;
+    Ok(Json(stats))
+}
 
-    let ballot = ballots
-        .find_one(election_question_ballot, None)
-        .await?
-        .map(|ballot| PublicReceipt::from_ballot(ballot, &election))
-        .ok_or_else(|| {
-            Error::not_found(format!(
-                "Ballot with ID '{}' for election '{}', question '{}'",
-                ballot_id, election_id, question_id
-            ))
-        })?;
-
-    Ok(Json(ballot))
+/// The results of every question in a finished election, for a results page.
+///
+/// Like the totals of a single question, these are only available once the
+/// election has ended. Except for admins, questions whose results are only for
+/// admins are left out, and those that only show their winners have their
+/// tallies withheld.
+#[get("/elections/<election_id>/results")]
+#[allow(clippy::too_many_arguments)]
+async fn full_results(
+    election_id: ElectionId,
+    admin: Option<AuthToken<Admin>>,
+    elections: SecondaryColl<Election>,
+    totals: SecondaryColl<CandidateTotals>,
+    totals_deltas: SecondaryColl<TotalsDelta>,
+    ballots: SecondaryColl<AnyBallot>,
+    voter_elections: SecondaryColl<VoterElection>,
+    db_client: &State<Client>,
+    request_id: RequestId,
+) -> Result<Json<FullResults>> {
+    with_retries(READ_RETRIES, || async {
+        let election = elections
+            .find_one(u32_id_filter(election_id), None)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+
+        if election.metadata.state != ElectionState::Archived
+            && Utc::now() <= election.metadata.end_time
+        {
+            return Err(Error::not_found(format!(
+                "Election with ID '{}'",
+                election_id
+            )));
+        }
+
+        // As for a single question, read the totals and unfolded deltas from
+        // the same snapshot.
+        let session_options = SessionOptions::builder().snapshot(true).build();
+        let mut session = db_client.start_session(Some(session_options)).await?;
+        let mut totals_by_question = election_totals(
+            election_id,
+            &totals,
+            &totals_deltas,
+            &mut session,
+            request_id,
+        )
+        .await?;
+        let mut stats_by_question =
+            election_ballot_stats(&ballots, election_id, None, request_id).await?;
+        let mut eligible_by_question = VoterElection::eligible_voters_by_question(
+            &voter_elections,
+            election_id,
+            AggregateOptions::for_request(request_id),
+        )
+        .await?;
+
+        let mut questions = election
+            .questions
+            .into_values()
+            .filter(|question| admin.is_some() || question.result_visibility.shows_winners())
+            .collect::<Vec<_>>();
+        questions.sort_by_key(|question| question.id);
+        let questions = questions
+            .into_iter()
+            .map(|question| {
+                let tallies = totals_by_question
+                    .remove(&question.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(candidate, total)| {
+                        let tally = tally_to_u64(total.crypto.tally).ok_or_else(|| {
+                            Error::Status(
+                                Status::InternalServerError,
+                                format!(
+                                    "Tally for '{}' in question {} does not fit in 64 bits",
+                                    candidate, question.id
+                                ),
+                            )
+                        })?;
+                        Ok((candidate, tally))
+                    })
+                    .collect::<Result<HashMap<_, _>>>()?;
+                let stats = stats_by_question.remove(&question.id).unwrap_or_default();
+                let withheld = admin.is_none() && !question.result_visibility.shows_totals();
+                let eligible = eligible_by_question
+                    .remove(&question.id)
+                    .unwrap_or_default();
+                let mut results = QuestionResults::new(question.into(), tallies, &stats, eligible);
+                if withheld {
+                    results.withhold_tallies();
+                }
+                Ok(results)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Json(FullResults {
+            election_id,
+            election_name: election.metadata.name,
+            questions,
+        }))
+    })
+    .await
 }
 
-#[get("/elections/<election_id>/<question_id>/totals")]
-async fn candidate_totals(
+#[get("/elections/<election_id>/<question_id>/board-head")]
+async fn board_head(
     election_id: ElectionId,
     question_id: QuestionId,
     elections: Coll<Election>,
-    totals: Coll<CandidateTotals>,
-) -> Result<Json<HashMap<CandidateId, CandidateTotalsDesc>>> {
+    board_heads: Coll<BoardHead>,
+    request_id: RequestId,
+) -> Result<Json<BoardHeadDesc>> {
+    let election_filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+    };
     let election = elections
-        .find_one(u32_id_filter(election_id), None)
+        .find_one(election_filter, FindOneOptions::for_request(request_id))
         .await?
         .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
-
-    if election.metadata.state != ElectionState::Archived
-        && Utc::now() <= election.metadata.end_time
-    {
+    if !election.questions.contains_key(&question_id) {
         return Err(Error::not_found(format!(
-            "Election with ID '{}'",
-            election_id
+            "Question with ID '{}' in election '{}'",
+            question_id, election_id
         )));
     }
 
-    let question_totals_filter = doc! {
-        "election_id": election_id,
-        "question_id": question_id,
-    };
-    let question_totals = totals
-        .find(question_totals_filter, None)
+    // A question nobody has voted on yet has no head document.
+    let head = board_heads
+        .find_one(
+            doc! { "_id": board_head_id(election_id, question_id) },
+            FindOneOptions::for_request(request_id),
+        )
         .await?
-        .map_ok(|tot| (tot.candidate_name.clone(), tot.into()))
-        .try_collect::<HashMap<_, _>>()
-        .await?;
+        .unwrap_or_else(|| BoardHead::genesis(election_id, question_id));
+
+    Ok(Json(head.into()))
+}
 
-    Ok(Json(question_totals))
+/// The crypto needed to verify receipts for a question, without the rest of
+/// the election description.
+#[get("/elections/<election_id>/<question_id>/crypto")]
+async fn question_crypto(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    elections: Coll<Election>,
+    request_id: RequestId,
+) -> Result<Json<QuestionCrypto>> {
+    let election_filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+    };
+    let election = elections
+        .find_one(election_filter, FindOneOptions::for_request(request_id))
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+    let crypto = QuestionCrypto::for_question(&election, question_id).ok_or_else(|| {
+        Error::not_found(format!(
+            "Question with ID '{}' in election '{}'",
+            question_id, election_id
+        ))
+    })?;
+
+    Ok(Json(crypto))
 }
 
-#[get("/elections/<election_id>/<question_id>/dump")]
+/// Everything needed to verify a question.
+///
+/// If `signed` is set, the dump is signed by the election key, so that copies
+/// of it can be shown to be authentic.
+#[get("/elections/<election_id>/<question_id>/dump?<signed>")]
+#[allow(clippy::too_many_arguments)]
 async fn question_dump(
     election_id: ElectionId,
     question_id: QuestionId,
+    signed: Option<bool>,
+    admin: Option<AuthToken<Admin>>,
     elections: Coll<Election>,
     totals: Coll<CandidateTotals>,
+    totals_deltas: Coll<TotalsDelta>,
     ballots: Coll<AnyBallot>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
     db_client: &State<Client>,
+    config: &State<Config>,
     request_id: RequestId,
 ) -> Result<Json<ElectionResults>> {
+    let (election, mut dump) = dump_question(
+        election_id,
+        question_id,
+        false,
+        Some(config.public_query_timeout()),
+        &elections,
+        &totals,
+        &totals_deltas,
+        &ballots,
+        &board_heads,
+        &board_entries,
+        db_client,
+        request_id,
+    )
+    .await?;
+    check_board_embargo(&election, admin.as_ref(), request_id)?;
+    if admin.is_none() {
+        withhold_dump_totals(&election, question_id, &mut dump, request_id);
+    }
+    if signed.unwrap_or(false) {
+        dump.sign(&election);
+        if election.metadata.state == ElectionState::Archived {
+            record_export(&election, question_id, &dump, &elections, request_id).await?;
+        }
+    }
+    debug!(
+        "  req{} Created dump of election {} with {} audited, {} redacted, {} confirmed, {} skipped",
+        request_id,
+        election_id,
+        dump.audited.len(),
+        dump.redacted.len(),
+        dump.confirmed.len(),
+        dump.skipped
+    );
+
+    Ok(Json(dump))
+}
+
+/// Withhold the totals from a dump of a question whose results are not fully
+/// public, as for anyone but admins. The ballots stay public so that they can
+/// still be verified.
+pub(super) fn withhold_dump_totals(
+    election: &Election,
+    question_id: QuestionId,
+    dump: &mut ElectionResults,
+    request_id: RequestId,
+) {
+    let withheld = election
+        .questions
+        .get(&question_id)
+        .is_some_and(|question| !question.result_visibility.shows_totals());
+    if withheld {
+        info!("  req{request_id} Withholding totals of question {question_id}");
+        dump.totals = None;
+    }
+}
+
+/// Record that a signed dump of an archived election's question has been
+/// served, so that the election may be deleted once every question has been.
+pub(super) async fn record_export(
+    election: &Election,
+    question_id: QuestionId,
+    dump: &ElectionResults,
+    elections: &Coll<Election>,
+    request_id: RequestId,
+) -> Result<()> {
+    let signature = mongodb::bson::to_bson(&dump.dump_signature).unwrap(); // Cannot fail.
+    let filter = doc! {
+        "_id": election.id,
+        "state": ElectionState::Archived,
+    };
+    let question = format!("questions.{question_id}");
+    let update = doc! {
+        "$set": {
+            format!("{question}.last_dump_at"): DateTime::now(),
+            format!("{question}.last_dump_signature"): signature,
+        }
+    };
+    elections.update_one(filter, update, None).await?;
+    trace!(
+        "  req{} Recorded export of question {} of election {}",
+        request_id,
+        question_id,
+        election.id
+    );
+    Ok(())
+}
+
+/// Hide the ballots of an election whose public board is embargoed from
+/// non-admins, as though the election did not exist. This is checked on every
+/// request, so the embargo lifts by itself once its time passes.
+fn check_board_embargo(
+    election: &Election,
+    admin: Option<&AuthToken<Admin>>,
+    request_id: RequestId,
+) -> Result<()> {
+    if admin.is_none() && election.is_board_embargoed_at(Utc::now()) {
+        debug!(
+            "  req{} Public board of election {} is embargoed",
+            request_id, election.id
+        );
+        return Err(Error::not_found(format!(
+            "Election with ID '{}'",
+            election.id
+        )));
+    }
+    Ok(())
+}
+
+/// The longest ballot ID filter pattern accepted. Ballot IDs are short numbers,
+/// so longer patterns are of no use.
+const MAX_FILTER_PATTERN_LENGTH: usize = 32;
+
+/// Reject ballot ID filter patterns that are too long, or that could make the
+/// database's regex engine backtrack catastrophically: backreferences, and
+/// repetition of a group that itself contains repetition or alternatives, like
+/// `(a+)+` or `(a|aa)*`. Otherwise invalid patterns are left to the database.
+fn check_filter_pattern(pattern: &str) -> Result<()> {
+    let reject = |reason: &str| {
+        Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!("Ballot filter pattern '{pattern}' {reason}"),
+        ))
+    };
+    if pattern.chars().count() > MAX_FILTER_PATTERN_LENGTH {
+        return reject(&format!(
+            "is longer than {MAX_FILTER_PATTERN_LENGTH} characters"
+        ));
+    }
+
+    // Whether each open group, outermost first, contains repetition or alternatives.
+    let mut groups = vec![false];
+    // Whether the last thing seen was such a group closing.
+    let mut closed_risky_group = false;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let after_risky_group = std::mem::take(&mut closed_risky_group);
+        match c {
+            '\\' => {
+                if let Some('1'..='9') = chars.next() {
+                    return reject("contains a backreference");
+                }
+            }
+            '[' => {
+                // Nothing in a character class repeats. A leading ']' is literal.
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']');
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' => {
+                // The '?' of a group modifier like `(?:` is not repetition.
+                chars.next_if_eq(&'?');
+                groups.push(false);
+            }
+            ')' => {
+                let risky = groups.pop().unwrap();
+                let Some(outer) = groups.last_mut() else {
+                    return reject("has unbalanced parentheses");
+                };
+                *outer |= risky;
+                closed_risky_group = risky;
+            }
+            '*' | '+' | '?' | '{' => {
+                if after_risky_group {
+                    return reject("repeats a group that contains repetition or alternatives");
+                }
+                *groups.last_mut().unwrap() = true;
+            }
+            '|' => *groups.last_mut().unwrap() = true,
+            _ => {}
+        }
+    }
+    if groups.len() > 1 {
+        return reject("has unbalanced parentheses");
+    }
+    Ok(())
+}
+
+/// Dump a question of a published or archived election, returning the election too.
+/// Candidate totals are only included once the election has finished, unless
+/// `with_totals` is set. The heavier queries are given up on after `max_time`,
+/// if any.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn dump_question(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    with_totals: bool,
+    max_time: Option<StdDuration>,
+    elections: &Coll<Election>,
+    totals: &Coll<CandidateTotals>,
+    totals_deltas: &Coll<TotalsDelta>,
+    ballots: &Coll<AnyBallot>,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    db_client: &Client,
+    request_id: RequestId,
+) -> Result<(Election, ElectionResults)> {
     let election;
     let mut candidate_totals = None;
-    let mut audited_receipts = HashMap::new();
-    let mut confirmed_receipts = HashMap::new();
+    let receipts;
+    let board;
+    let last_event_seq;
     {
-        // Ensure we read a consistent snapshot of the election data.
+        // Ensure we read a consistent snapshot of the election data. This stays
+        // on the primary, unlike the lighter public endpoints.
         let session_options = SessionOptions::builder().snapshot(true).build();
         let mut session = db_client.start_session(Some(session_options)).await?;
 
@@ -258,30 +970,36 @@ async fn question_dump(
             "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
         };
         election = elections
-            .find_one_with_session(election_filter, None, &mut session)
+            .find_one_with_session(
+                election_filter,
+                FindOneOptions::for_request(request_id),
+                &mut session,
+            )
             .await?
             .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
 
-        // Only retrieve totals if the election has finished.
-        if election.metadata.state == ElectionState::Archived
+        // Only retrieve totals if the election has finished, unless asked to.
+        if with_totals
+            || election.metadata.state == ElectionState::Archived
             || Utc::now() > election.metadata.end_time
         {
-            info!("  req{request_id} Election finished, including totals");
-            let totals_filter = doc! {
-                "election_id": election_id,
-                "question_id": question_id,
-            };
-            let mut totals_cursor = totals
-                .find_with_session(totals_filter, None, &mut session)
-                .await?;
-            candidate_totals = Some({
-                let mut candidate_totals = HashMap::new();
-                while let Some(total) = totals_cursor.next(&mut session).await {
-                    let total = total?;
-                    candidate_totals.insert(total.candidate_name.clone(), total.into());
-                }
-                candidate_totals
-            });
+            info!("  req{request_id} Including totals");
+            let totals = question_totals(
+                election_id,
+                question_id,
+                totals,
+                totals_deltas,
+                &mut session,
+                max_time,
+                request_id,
+            )
+            .await?;
+            candidate_totals = Some(
+                totals
+                    .into_iter()
+                    .map(|(candidate, total)| (candidate, CandidateTotalsDesc::from(total)))
+                    .collect(),
+            );
         } else {
             info!("  req{request_id} Election ongoing, excluding totals");
         }
@@ -289,53 +1007,384 @@ async fn question_dump(
         let ballots_filter = doc! {
             "election_id": election_id,
             "question_id": question_id,
-            "$or": [{"state": Audited}, {"state": Confirmed}],
         };
-        let mut election_ballots = ballots
-            .find_with_session(ballots_filter, None, &mut session)
+        receipts = published_receipts(
+            ballots,
+            ballots_filter,
+            &election,
+            &mut session,
+            max_time,
+            request_id,
+        )
+        .await?;
+
+        // Retrieve the bulletin board hash chain, if anything has been appended to it.
+        // Questions last voted on before the chain was introduced have no head.
+        let head = board_heads
+            .find_one_with_session(
+                doc! { "_id": board_head_id(election_id, question_id) },
+                FindOneOptions::for_request(request_id),
+                &mut session,
+            )
             .await?;
-        while let Some(ballot) = election_ballots.next(&mut session).await {
-            match ballot? {
-                AnyBallot::Unconfirmed(_) => {} // Ignore unconfirmed ballots.
-                AnyBallot::Audited(b) => {
-                    audited_receipts.insert(b.ballot_id, Receipt::from_ballot(b.ballot, &election));
-                }
-                AnyBallot::Confirmed(b) => {
-                    confirmed_receipts
-                        .insert(b.ballot_id, Receipt::from_ballot(b.ballot, &election));
-                }
+        last_event_seq = head.as_ref().map_or(0, |head| head.length);
+        if let Some(head) = head {
+            let entries_filter = doc! {
+                "election_id": election_id,
+                "question_id": question_id,
+            };
+            let entries_options = FindOptions::builder()
+                .sort(doc! { "position": 1 })
+                .max_time(max_time)
+                .build()
+                .with_request_id(request_id);
+            let mut entries = board_entries
+                .find_with_session(entries_filter, entries_options, &mut session)
+                .await?;
+            let mut events = Vec::new();
+            while let Some(entry) = entries.next(&mut session).await {
+                events.push(entry?.event);
             }
+            board = Some(BoardChain {
+                length: head.length,
+                head: head.head,
+                events,
+            });
+        } else {
+            board = None;
         }
     }
 
     let dump = ElectionResults {
-        election: ElectionDescription::from(election).crypto,
-        audited: audited_receipts,
-        confirmed: confirmed_receipts,
+        schema_version: RECEIPT_SCHEMA_VERSION,
+        election: ElectionDescription::from(election.clone()).crypto,
+        audited: receipts.audited,
+        redacted: receipts.redacted,
+        confirmed: receipts.confirmed,
         totals: candidate_totals,
+        skipped: receipts.skipped,
+        board,
+        last_event_seq,
+        dump_signature: None,
     };
-    debug!(
-        "  req{} Created dump of election {} with {} audited, {} confirmed",
-        request_id,
-        election_id,
-        dump.audited.len(),
-        dump.confirmed.len()
+
+    Ok((election, dump))
+}
+
+/// Convert a ballot into a public receipt.
+/// Ballots in an unrecognised state are logged and produce `None`.
+fn public_receipt(
+    request_id: RequestId,
+    ballot: AnyBallot,
+    election: &Election,
+) -> Option<PublicReceipt> {
+    if let AnyBallot::Unknown(ref unknown) = ballot {
+        warn_unknown_ballot(request_id, unknown);
+    }
+    PublicReceipt::from_ballot(ballot, election)
+}
+
+#[get("/elections/<election_id>/<question_id>/dump/changes?<since>")]
+#[allow(clippy::too_many_arguments)]
+async fn question_dump_changes(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    since: u64,
+    admin: Option<AuthToken<Admin>>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    board_heads: Coll<BoardHead>,
+    db_client: &State<Client>,
+    request_id: RequestId,
+) -> Result<Json<ElectionResultsChanges>> {
+    // Ensure the changes and the high-water mark are consistent.
+    let session_options = SessionOptions::builder().snapshot(true).build();
+    let mut session = db_client.start_session(Some(session_options)).await?;
+
+    let election_filter = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+    };
+    let election = elections
+        .find_one_with_session(
+            election_filter,
+            FindOneOptions::for_request(request_id),
+            &mut session,
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+    check_board_embargo(&election, admin.as_ref(), request_id)?;
+    if !election.questions.contains_key(&question_id) {
+        return Err(Error::not_found(format!(
+            "Question with ID '{}' in election '{}'",
+            question_id, election_id
+        )));
+    }
+
+    let ballots_filter = doc! {
+        "election_id": election_id,
+        "question_id": question_id,
+        "last_event_seq": { "$gt": since as i64 },
+    };
+    let receipts = published_receipts(
+        &ballots,
+        ballots_filter,
+        &election,
+        &mut session,
+        None,
+        request_id,
+    )
+    .await?;
+
+    // The sequence number is the length of the bulletin board.
+    let last_event_seq = board_heads
+        .find_one_with_session(
+            doc! { "_id": board_head_id(election_id, question_id) },
+            FindOneOptions::for_request(request_id),
+            &mut session,
+        )
+        .await?
+        .map_or(0, |head| head.length);
+
+    let changes = ElectionResultsChanges {
+        schema_version: RECEIPT_SCHEMA_VERSION,
+        audited: receipts.audited,
+        redacted: receipts.redacted,
+        confirmed: receipts.confirmed,
+        skipped: receipts.skipped,
+        last_event_seq,
+    };
+    debug!(
+        "  req{} Found {} audited and {} confirmed changes to question {} of election {} since {}",
+        request_id,
+        changes.audited.len(),
+        changes.confirmed.len(),
+        question_id,
+        election_id,
+        since
     );
 
-    Ok(Json(dump))
+    Ok(Json(changes))
+}
+
+/// Receipts of audited and confirmed ballots, by ballot ID.
+#[derive(Default)]
+struct PublishedReceipts {
+    audited: HashMap<BallotId, Receipt<Audited>>,
+    /// Audited ballots whose secrets have been redacted.
+    redacted: HashMap<BallotId, Receipt<AuditedRedacted>>,
+    confirmed: HashMap<BallotId, Receipt<Confirmed>>,
+    /// Number of ballots in an unrecognised state.
+    skipped: u64,
+}
+
+/// Get the receipts of all audited and confirmed ballots matching the filter,
+/// along with the number skipped for being in an unrecognised state.
+async fn published_receipts(
+    ballots: &Coll<AnyBallot>,
+    mut filter: Document,
+    election: &Election,
+    session: &mut ClientSession,
+    max_time: Option<StdDuration>,
+    request_id: RequestId,
+) -> Result<PublishedReceipts> {
+    let mut receipts = PublishedReceipts::default();
+
+    // Match everything except unconfirmed ballots, so that ballots in
+    // unrecognised states are counted rather than silently left out.
+    filter.insert("state", doc! { "$ne": Unconfirmed });
+    let options = FindOptions::builder()
+        .max_time(max_time)
+        .build()
+        .with_request_id(request_id);
+    let mut cursor = ballots.find_with_session(filter, options, session).await?;
+    while let Some(ballot) = cursor.next(session).await {
+        match ballot? {
+            AnyBallot::Unconfirmed(_) => {} // Ignore unconfirmed ballots.
+            AnyBallot::Audited(b) => {
+                let receipt = Receipt::from_ballot(b.ballot, election);
+                receipts.audited.insert(receipt.ballot_id, receipt);
+            }
+            AnyBallot::AuditedRedacted(b) => {
+                let receipt = Receipt::from_ballot(b.ballot, election);
+                receipts.redacted.insert(receipt.ballot_id, receipt);
+            }
+            AnyBallot::Confirmed(b) => {
+                let receipt = Receipt::from_ballot(b.ballot, election);
+                receipts.confirmed.insert(receipt.ballot_id, receipt);
+            }
+            AnyBallot::Unknown(b) => {
+                warn_unknown_ballot(request_id, &b);
+                receipts.skipped += 1;
+            }
+        }
+    }
+
+    Ok(receipts)
+}
+
+/// Log that we are skipping a ballot in an unrecognised state.
+fn warn_unknown_ballot(request_id: RequestId, ballot: &UnknownBallot) {
+    warn!(
+        "  req{} Skipping ballot document {} with unrecognised state '{}'",
+        request_id, ballot.internal_id, ballot.state
+    );
+}
+
+/// Ballot count and latest creation time for a single state, as produced by
+/// the aggregation in [`question_ballot_stats`].
+#[derive(Deserialize)]
+struct StateStats {
+    #[serde(rename = "_id")]
+    state: String,
+    count: u64,
+    /// Absent if none of the ballots have a creation time.
+    last_activity: Option<DateTime>,
+}
+
+/// Count a question's ballots in each state, in a single aggregation.
+/// Ballots in an unrecognised state are logged and not counted.
+async fn question_ballot_stats(
+    ballots: &Coll<AnyBallot>,
+    election_id: ElectionId,
+    question_id: QuestionId,
+    max_time: Option<StdDuration>,
+    request_id: RequestId,
+) -> Result<BallotStats> {
+    let pipeline = [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+                "question_id": question_id,
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": "$state",
+                "count": {"$sum": 1},
+                "last_activity": {"$max": "$creation_time"},
+            }
+        },
+    ];
+    let options = AggregateOptions::builder()
+        .max_time(max_time)
+        .build()
+        .with_request_id(request_id);
+    let mut cursor = ballots
+        .aggregate(pipeline, options)
+        .await?
+        .with_type::<StateStats>();
+
+    let mut stats = BallotStats::default();
+    while let Some(group) = cursor.try_next().await? {
+        add_state_stats(&mut stats, group, request_id);
+    }
+    trace!("  req{} Ballot stats: {:?}", request_id, stats);
+
+    Ok(stats)
+}
+
+/// Ballot stats for a single question and state, as produced by the
+/// aggregation in [`election_ballot_stats`].
+#[derive(Deserialize)]
+struct QuestionStateStats {
+    #[serde(rename = "_id")]
+    key: QuestionStateKey,
+    count: u64,
+    last_activity: Option<DateTime>,
+}
+
+#[derive(Deserialize)]
+struct QuestionStateKey {
+    question_id: QuestionId,
+    state: String,
+}
+
+/// Count the ballots of every question in an election in each state, in a
+/// single aggregation, giving up after `max_time`, if given. Questions without
+/// any ballots are omitted.
+pub(super) async fn election_ballot_stats(
+    ballots: &Coll<AnyBallot>,
+    election_id: ElectionId,
+    max_time: Option<StdDuration>,
+    request_id: RequestId,
+) -> Result<HashMap<QuestionId, BallotStats>> {
+    let pipeline = [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": {"question_id": "$question_id", "state": "$state"},
+                "count": {"$sum": 1},
+                "last_activity": {"$max": "$creation_time"},
+            }
+        },
+    ];
+    let options = AggregateOptions::builder()
+        .max_time(max_time)
+        .build()
+        .with_request_id(request_id);
+    let mut cursor = ballots
+        .aggregate(pipeline, options)
+        .await?
+        .with_type::<QuestionStateStats>();
+
+    let mut stats = HashMap::<_, BallotStats>::new();
+    while let Some(group) = cursor.try_next().await? {
+        let state_stats = StateStats {
+            state: group.key.state,
+            count: group.count,
+            last_activity: group.last_activity,
+        };
+        add_state_stats(
+            stats.entry(group.key.question_id).or_default(),
+            state_stats,
+            request_id,
+        );
+    }
+    trace!("  req{} Election ballot stats: {:?}", request_id, stats);
+
+    Ok(stats)
+}
+
+/// Add the count of ballots in a single state to a question's stats.
+/// Ballots in an unrecognised state are logged and not counted.
+fn add_state_stats(stats: &mut BallotStats, group: StateStats, request_id: RequestId) {
+    let count = match group.state.as_bytes() {
+        state if state == Unconfirmed.as_ref() => &mut stats.unconfirmed,
+        state if state == Audited.as_ref() => &mut stats.audited,
+        state if state == Confirmed.as_ref() => &mut stats.confirmed,
+        _ => {
+            warn!(
+                "  req{} Not counting {} ballots with unrecognised state '{}'",
+                request_id, group.count, group.state
+            );
+            return;
+        }
+    };
+    *count = group.count;
+    stats.last_activity = stats
+        .last_activity
+        .max(group.last_activity.map(DateTime::to_chrono));
 }
 
 /// Retrieve the metadata for elections.
-/// If `admin` is false, admin-only elections will be hidden.
+/// If `admin` is false, admin-only elections and rehearsals will be hidden.
 /// If `archived` is true, archived elections will be returned instead of non-archived ones.
 /// If `timing` is provided, only elections with that status will be returned.
+/// If `lang` is provided, names are translated into it where possible.
 async fn metadata_for_elections(
     request_id: RequestId,
-    elections: Coll<Election>,
+    elections: &Coll<Election>,
     admin: bool,
     archived: bool,
     timing: Option<ElectionTiming>,
-) -> Result<Json<Vec<ElectionSummary>>> {
+    lang: Option<&str>,
+    pagination: Option<&PaginationRequest>,
+) -> Result<Json<MaybePaginated<ElectionSummary>>> {
     let mut filter = if archived {
         doc! {
             "state": ElectionState::Archived,
@@ -349,26 +1398,73 @@ async fn metadata_for_elections(
             "state": ElectionState::Published,
         }
     };
+    if !admin {
+        filter.insert("rehearsal", doc! {"$ne": true});
+    }
     if let Some(timing) = timing {
         filter.extend(timing.filter());
     }
 
-    let elections = elections
-        .find(filter, None)
+    let summarise = |election| {
+        let mut summary = if admin {
+            ElectionSummary::for_admin(election)
+        } else {
+            ElectionSummary::from(election)
+        };
+        if let Some(lang) = lang {
+            summary.localise(lang);
+        }
+        summary
+    };
+
+    let Some(pagination) = pagination else {
+        let metadata = elections
+            .find(filter, FindOptions::for_request(request_id))
+            .await?
+            .map_ok(summarise)
+            .try_collect::<Vec<_>>()
+            .await?;
+        debug!("  req{} Found {} elections", request_id, metadata.len());
+        return Ok(Json(MaybePaginated::All(metadata)));
+    };
+
+    let sort = pagination
+        .sort(ELECTION_SORT_FIELDS)
+        .map_err(|e| Error::Status(Status::UnprocessableEntity, e))?
+        .unwrap_or_else(|| doc! { "_id": 1 });
+    let options = FindOptions::builder()
+        .sort(sort)
+        .skip(u64::from(pagination.skip()))
+        .limit(i64::from(pagination.page_size()))
+        .build()
+        .with_request_id(request_id);
+    let page = elections
+        .find(filter.clone(), options)
         .await?
+        .map_ok(summarise)
         .try_collect::<Vec<_>>()
         .await?;
+    let total = elections
+        .count_documents(filter, CountOptions::for_request(request_id))
+        .await?;
+    debug!(
+        "  req{} Returning {} elections of {} total",
+        request_id,
+        page.len(),
+        total
+    );
 
-    let metadata = elections.into_iter().map(Into::into).collect::<Vec<_>>();
-    debug!("  req{} Found {} elections", request_id, metadata.len());
-
-    Ok(Json(metadata))
+    Ok(Json(MaybePaginated::Paginated(
+        pagination.to_paginated(total, page),
+    )))
 }
 
 #[cfg(test)]
 mod tests {
-    use mongodb::Database;
+    use mongodb::{bson::Document, Database};
     use rocket::{
+        figment::Figment,
+        futures::StreamExt,
         http::Status,
         local::asynchronous::{Client, LocalResponse},
         serde::json::serde_json,
@@ -376,14 +1472,27 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::model::{
-        api::election::{ElectionSpec, QuestionSpec},
-        common::ballot::Unconfirmed,
+        api::{
+            election::{
+                verify_public_receipt_for_question, ElectionSpec, QuestionSpec, ReceiptError,
+                VerificationError,
+            },
+            pagination::SortOrder,
+        },
+        common::{
+            allowed_questions::AllowedQuestions,
+            board::GENESIS_HASH,
+            election::{QuorumSpec, ResultVisibility, Translations},
+        },
         db::{
             ballot::{Ballot, BallotCore},
-            candidate_totals::NewCandidateTotals,
-            election::{ElectionMetadata, Question},
+            election::ElectionMetadata,
+            maintenance::Maintenance,
         },
+        mongodb::{Id, MongoCollection},
     };
+    use crate::scheduled_task::TaskRegistry;
+    use crate::testdata::{insert_ballots, insert_elections};
 
     use super::*;
 
@@ -406,12 +1515,14 @@ mod tests {
 
     #[backend_test(admin)]
     async fn get_all_elections_as_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let response = client
             .get(uri!(elections_admin(
                 Some(false),
-                Option::<ElectionTiming>::None
+                Option::<ElectionTiming>::None,
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -428,12 +1539,14 @@ mod tests {
 
     #[backend_test]
     async fn only_get_published_elections_as_non_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let response = client
             .get(uri!(elections_non_admin(
                 Some(false),
-                Option::<ElectionTiming>::None
+                Option::<ElectionTiming>::None,
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -443,13 +1556,15 @@ mod tests {
 
     #[backend_test]
     async fn get_archived(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         // Try getting all archived.
         let response = client
             .get(uri!(elections_non_admin(
                 Some(true),
-                Option::<ElectionTiming>::None
+                Option::<ElectionTiming>::None,
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -459,13 +1574,15 @@ mod tests {
 
     #[backend_test(admin)]
     async fn get_specific_timings(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         // Get future (expect draft example).
         let response = client
             .get(uri!(elections_admin(
                 Some(false),
-                Some(ElectionTiming::Future)
+                Some(ElectionTiming::Future),
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -475,7 +1592,9 @@ mod tests {
         let response = client
             .get(uri!(elections_admin(
                 Some(false),
-                Some(ElectionTiming::Current)
+                Some(ElectionTiming::Current),
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -485,7 +1604,9 @@ mod tests {
         let response = client
             .get(uri!(elections_admin(
                 Some(true),
-                Some(ElectionTiming::Current)
+                Some(ElectionTiming::Current),
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -495,7 +1616,9 @@ mod tests {
         let response = client
             .get(uri!(elections_admin(
                 Some(false),
-                Some(ElectionTiming::Past)
+                Some(ElectionTiming::Past),
+                _,
+                _
             )))
             .dispatch()
             .await;
@@ -505,21 +1628,77 @@ mod tests {
         let response = client
             .get(uri!(elections_admin(
                 Some(true),
-                Some(ElectionTiming::Past)
+                Some(ElectionTiming::Past),
+                _,
+                _
             )))
             .dispatch()
             .await;
         check_response_has_elections(response, vec![Election::archived_example().metadata]).await;
     }
 
+    #[backend_test(admin)]
+    async fn get_elections_sorted(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+
+        // Page through the elections, latest start first.
+        let mut names = Vec::new();
+        for page_num in 1..=2 {
+            let pagination = PaginationRequest {
+                page_num,
+                page_size: 1,
+                sort_by: Some("start_time".to_string()),
+                order: Some(SortOrder::Desc),
+            };
+            let response = client
+                .get(uri!(elections_admin(
+                    Some(false),
+                    Option::<ElectionTiming>::None,
+                    _,
+                    OptionalPagination(Some(pagination))
+                )))
+                .dispatch()
+                .await;
+            assert_eq!(Status::Ok, response.status());
+            let page: Paginated<ElectionSummary> =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            assert_eq!(page.pagination.total, 2);
+            assert_eq!(page.items.len(), 1);
+            names.extend(page.items.into_iter().map(|election| election.name));
+        }
+        assert_eq!(
+            names,
+            vec![
+                Election::draft_example().metadata.name,
+                Election::published_example().metadata.name,
+            ]
+        );
+
+        // Other fields cannot be sorted by.
+        let pagination = PaginationRequest {
+            sort_by: Some("state".to_string()),
+            ..Default::default()
+        };
+        let response = client
+            .get(uri!(elections_admin(
+                Some(false),
+                Option::<ElectionTiming>::None,
+                _,
+                OptionalPagination(Some(pagination))
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(Status::UnprocessableEntity, response.status());
+    }
+
     #[backend_test(admin)]
     async fn get_published_election_as_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
 
         let response = client
-            .get(uri!(election_admin(election.id)))
+            .get(uri!(election_admin(election.id, _)))
             .dispatch()
             .await;
 
@@ -554,12 +1733,12 @@ mod tests {
 
     #[backend_test(admin)]
     async fn get_draft_election_as_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::future_example()).await;
 
         let response = client
-            .get(uri!(election_admin(election.id)))
+            .get(uri!(election_admin(election.id, _)))
             .dispatch()
             .await;
 
@@ -594,12 +1773,12 @@ mod tests {
 
     #[backend_test]
     async fn get_published_election_as_non_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
 
         let response = client
-            .get(uri!(election_non_admin(election.id)))
+            .get(uri!(election_non_admin(election.id, _)))
             .dispatch()
             .await;
 
@@ -632,15 +1811,124 @@ mod tests {
         }
     }
 
+    #[backend_test]
+    async fn get_translated_election(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let question = election.questions.values().next().unwrap();
+        let candidate = question.candidates[0].clone();
+
+        // Make the election bilingual, with only one candidate translated.
+        let welsh = |text: String| Translations::from([("cy".to_string(), text)]);
+        let name_cy = format!("{} (cy)", election.metadata.name);
+        let description_cy = format!("{} (cy)", question.description);
+        let candidate_cy = format!("{candidate} (cy)");
+        let description_key = format!("questions.{}.description_translations", question.id);
+        let candidates_key = format!("questions.{}.candidate_translations", question.id);
+        let mut candidate_translations = Document::new();
+        candidate_translations.insert(&candidate, doc! {"cy": &candidate_cy});
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! {
+                    "$set": {
+                        "name_translations": {"cy": &name_cy},
+                        description_key: {"cy": &description_cy},
+                        candidates_key: candidate_translations,
+                    }
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let election_id = election.id;
+        let describe = |lang: Option<&'static str>| {
+            let client = &client;
+            async move {
+                let response = client
+                    .get(uri!(election_non_admin(election_id, lang)))
+                    .dispatch()
+                    .await;
+                assert_eq!(Status::Ok, response.status());
+                let raw_response = response.into_string().await.unwrap();
+                serde_json::from_str::<ElectionDescription>(&raw_response).unwrap()
+            }
+        };
+
+        // By default, the canonical strings are shown, with every translation.
+        let described = describe(None).await;
+        assert_eq!(described.name, election.metadata.name);
+        assert_eq!(described.canonical_name, None);
+        assert_eq!(described.name_translations, welsh(name_cy.clone()));
+        let described_question = &described.questions[&question.id];
+        assert_eq!(described_question.description, question.description);
+        assert_eq!(described_question.canonical_description, None);
+        assert_eq!(
+            described_question.candidate_translations,
+            HashMap::from([(candidate.clone(), welsh(candidate_cy.clone()))])
+        );
+        assert_eq!(described_question.candidate_display_names, None);
+
+        // In Welsh, the display strings are swapped, but candidates stay canonical.
+        let described = describe(Some("cy")).await;
+        assert_eq!(described.name, name_cy);
+        assert_eq!(
+            described.canonical_name,
+            Some(election.metadata.name.clone())
+        );
+        let described_question = &described.questions[&question.id];
+        assert_eq!(described_question.description, description_cy);
+        assert_eq!(
+            described_question.canonical_description,
+            Some(question.description.clone())
+        );
+        assert_eq!(described_question.candidates, question.candidates);
+        let display_names = described_question.candidate_display_names.as_ref().unwrap();
+        assert_eq!(display_names[&candidate], candidate_cy);
+        for other in &question.candidates[1..] {
+            assert_eq!(&display_names[other], other);
+        }
+
+        // Languages without a translation fall back to the canonical strings.
+        let described = describe(Some("fr")).await;
+        assert_eq!(described.name, election.metadata.name);
+        assert_eq!(described.canonical_name, None);
+
+        // Listings translate names too.
+        let response = client
+            .get(uri!(elections_non_admin(
+                Some(false),
+                Option::<ElectionTiming>::None,
+                Some("cy"),
+                _
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, response.status());
+        let raw_response = response.into_string().await.unwrap();
+        let summaries = serde_json::from_str::<Vec<ElectionSummary>>(&raw_response).unwrap();
+        let summary = summaries.iter().find(|s| s.id == election.id).unwrap();
+        assert_eq!(summary.name, name_cy);
+        assert_eq!(summary.canonical_name, Some(election.metadata.name.clone()));
+
+        // Malformed languages are refused.
+        let response = client
+            .get(uri!(election_non_admin(election.id, Some("Welsh"))))
+            .dispatch()
+            .await;
+        assert_eq!(Status::BadRequest, response.status());
+    }
+
     #[backend_test]
     async fn get_archived_election_as_non_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::past_example()).await;
 
         // Try getting a specific archived election.
         let response = client
-            .get(uri!(election_non_admin(election.id)))
+            .get(uri!(election_non_admin(election.id, _)))
             .dispatch()
             .await;
         assert_eq!(Status::Ok, response.status());
@@ -674,12 +1962,12 @@ mod tests {
 
     #[backend_test]
     async fn fail_to_get_draft_election_as_non_admin(client: Client, db: Database) {
-        insert_elections(&db).await;
+        insert_elections(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::future_example()).await;
 
         let response = client
-            .get(uri!(election_non_admin(election.id)))
+            .get(uri!(election_non_admin(election.id, _)))
             .dispatch()
             .await;
 
@@ -688,8 +1976,8 @@ mod tests {
 
     #[backend_test]
     async fn get_election_question_ballots(client: Client, db: Database) {
-        insert_elections(&db).await;
-        insert_ballots(&db).await;
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
         let question_id = *election
@@ -708,12 +1996,16 @@ mod tests {
         let pagination = PaginationRequest {
             page_num: 1,
             page_size: 2,
+            ..Default::default()
         };
         let response = client
             .get(uri!(election_question_ballots(
                 election.id,
                 question_id,
                 Option::<String>::None,
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
                 pagination
             )))
             .dispatch()
@@ -732,12 +2024,16 @@ mod tests {
         let pagination = PaginationRequest {
             page_num: 1,
             page_size: 50,
+            ..Default::default()
         };
         let response = client
             .get(uri!(election_question_ballots(
                 election.id,
                 question_id,
                 Option::<String>::None,
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
                 pagination
             )))
             .dispatch()
@@ -761,10 +2057,81 @@ mod tests {
         );
     }
 
+    #[backend_test]
+    async fn get_election_question_ballots_sorted(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let question_id = *election
+            .questions
+            .iter()
+            .find_map(|(id, q)| {
+                if q.description == QuestionSpec::example1().description {
+                    Some(id)
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+
+        // Page through the ballots in descending order of ID.
+        let mut ballot_ids = Vec::new();
+        for page_num in 1..=3 {
+            let pagination = PaginationRequest {
+                page_num,
+                page_size: 4,
+                sort_by: Some("ballot_id".to_string()),
+                order: Some(SortOrder::Desc),
+            };
+            let response = client
+                .get(uri!(election_question_ballots(
+                    election.id,
+                    question_id,
+                    Option::<String>::None,
+                    Option::<ReceiptState>::None,
+                    Option::<CandidateId>::None,
+                    Option::<String>::None,
+                    pagination
+                )))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            let receipts: Paginated<PublicReceipt> =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            assert_eq!(receipts.pagination.total, 9);
+            ballot_ids.extend(receipts.items.iter().map(PublicReceipt::ballot_id));
+        }
+        assert_eq!(ballot_ids.len(), 9);
+        let mut expected = ballot_ids.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        expected.dedup();
+        assert_eq!(ballot_ids, expected);
+
+        // Other fields cannot be sorted by.
+        let pagination = PaginationRequest {
+            sort_by: Some("state".to_string()),
+            ..Default::default()
+        };
+        let response = client
+            .get(uri!(election_question_ballots(
+                election.id,
+                question_id,
+                Option::<String>::None,
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
+                pagination
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
     #[backend_test]
     async fn get_election_question_ballots_filter(client: Client, db: Database) {
-        insert_elections(&db).await;
-        insert_ballots(&db).await;
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
 
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
         let question_id = *election
@@ -784,12 +2151,16 @@ mod tests {
         let pagination = PaginationRequest {
             page_num: 1,
             page_size: 50,
+            ..Default::default()
         };
         let response = client
             .get(uri!(election_question_ballots(
                 election.id,
                 question_id,
                 Some("3".to_string()),
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
                 pagination
             )))
             .dispatch()
@@ -809,52 +2180,325 @@ mod tests {
     }
 
     #[backend_test]
-    async fn get_election_question_ballot(client: Client, db: Database) {
-        insert_elections(&db).await;
-        insert_ballots(&db).await;
+    async fn get_election_question_ballots_hostile_filter(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let question_id = *election.questions.keys().next().unwrap();
+
+        let long_pattern = "1".repeat(MAX_FILTER_PATTERN_LENGTH + 1);
+        // Patterns that could take the regex engine forever are refused
+        // before reaching the database, as are overly long ones.
+        for pattern in ["(a+)+$", "(\\d|\\d\\d)*x", long_pattern.as_str()] {
+            let response = client
+                .get(uri!(election_question_ballots(
+                    election.id,
+                    question_id,
+                    Some(pattern.to_string()),
+                    Option::<ReceiptState>::None,
+                    Option::<CandidateId>::None,
+                    Option::<String>::None,
+                    PaginationRequest {
+                        page_num: 1,
+                        page_size: 50,
+                        ..Default::default()
+                    }
+                )))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+        }
+    }
+
+    #[test]
+    fn filter_patterns() {
+        for pattern in [
+            "3",
+            "^1[0-9]+$",
+            "(12)+",
+            "(?:1|2)3",
+            "[(+]+",
+            "\\(+",
+            "a{2}",
+        ] {
+            assert!(check_filter_pattern(pattern).is_ok(), "{pattern}");
+        }
+        for pattern in [
+            "(a+)+", "(a|aa)*", "((a*))+", "(?:a+)?", "(a)\\1", "(a", "a)",
+        ] {
+            assert!(check_filter_pattern(pattern).is_err(), "{pattern}");
+        }
+    }
 
+    fn impatient_public_queries(figment: Figment) -> Figment {
+        figment.merge(("public_query_timeout_ms", 1))
+    }
+
+    #[backend_test(config = "impatient_public_queries")]
+    async fn public_query_timeout(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let question_id = *election.questions.keys().next().unwrap();
 
-        let ballot = Coll::<Ballot<Audited>>::from_db(&db)
-            .find_one(doc! {"state": Audited}, None)
+        // Enough ballots that scanning them all takes well over a millisecond.
+        let ballots = (0..50_000).map(|ballot_id| {
+            doc! {
+                "ballot_id": ballot_id,
+                "election_id": election.id,
+                "question_id": question_id,
+                "state": Confirmed,
+            }
+        });
+        db.collection::<Document>(AnyBallot::NAME)
+            .insert_many(ballots, None)
             .await
-            .unwrap()
             .unwrap();
 
+        // A pattern no ballot matches makes the database check every one.
         let response = client
-            .get(uri!(election_question_ballot(
-                ballot.election_id,
-                ballot.question_id,
-                ballot.ballot_id
+            .get(uri!(election_question_ballots(
+                election.id,
+                question_id,
+                Some("99999999".to_string()),
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
+                PaginationRequest {
+                    page_num: 1,
+                    page_size: 50,
+                    ..Default::default()
+                }
             )))
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::Ok);
-        assert!(response.body().is_some());
-
-        let raw_response = response.into_string().await.unwrap();
-        let receipt: PublicReceipt = serde_json::from_str(&raw_response).unwrap();
-        assert_eq!(
-            receipt,
-            PublicReceipt::from_ballot(AnyBallot::Audited(ballot), &election)
-        );
+        assert_eq!(response.status(), Status::GatewayTimeout);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "query_timeout");
     }
 
     #[backend_test]
-    async fn candidate_totals(client: Client, db: Database) {
-        insert_elections(&db).await;
-        insert_ballots(&db).await;
-
-        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+    async fn get_election_question_ballots_by_state(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
 
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
         let q1 = election
             .questions
             .values()
             .find(|q| q.description == QuestionSpec::example1().description)
             .unwrap();
-
-        // Ensure we cannot get the totals on an in-progress election.
-        let response = client
+        let q2 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example2().description)
+            .unwrap();
+
+        let ballot_ids =
+            |question_id: QuestionId, state: ReceiptState, candidate: Option<&CandidateId>| {
+                let client = &client;
+                let candidate = candidate.cloned();
+                async move {
+                    let pagination = PaginationRequest {
+                        page_num: 1,
+                        page_size: 50,
+                        ..Default::default()
+                    };
+                    let response = client
+                        .get(uri!(election_question_ballots(
+                            election.id,
+                            question_id,
+                            Option::<String>::None,
+                            Some(state),
+                            candidate,
+                            Option::<String>::None,
+                            pagination
+                        )))
+                        .dispatch()
+                        .await;
+                    assert_eq!(response.status(), Status::Ok);
+                    let raw_response = response.into_string().await.unwrap();
+                    let receipts: Paginated<PublicReceipt> =
+                        serde_json::from_str(&raw_response).unwrap();
+                    assert_eq!(receipts.pagination.total, receipts.items.len() as u64);
+                    let mut ids = receipts
+                        .items
+                        .iter()
+                        .map(|receipt| match (receipt, state) {
+                            (PublicReceipt::Unconfirmed(stub), ReceiptState::Unconfirmed) => {
+                                stub.ballot_id
+                            }
+                            (PublicReceipt::Audited(receipt), ReceiptState::Audited) => {
+                                receipt.ballot_id
+                            }
+                            (PublicReceipt::Confirmed(receipt), ReceiptState::Confirmed) => {
+                                receipt.ballot_id
+                            }
+                            _ => panic!("Receipt in the wrong state: {receipt:?}"),
+                        })
+                        .collect::<Vec<_>>();
+                    ids.sort_unstable();
+                    ids
+                }
+            };
+
+        // Filter by state.
+        assert_eq!(
+            ballot_ids(q1.id, ReceiptState::Confirmed, None).await,
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            ballot_ids(q1.id, ReceiptState::Audited, None).await,
+            vec![6, 7]
+        );
+        assert_eq!(
+            ballot_ids(q1.id, ReceiptState::Unconfirmed, None).await,
+            vec![8, 9]
+        );
+        assert_eq!(
+            ballot_ids(q2.id, ReceiptState::Audited, None).await,
+            vec![4, 5, 6, 7]
+        );
+
+        // Filter audited ballots by the candidate they reveal.
+        let (q1c1, q1c2) = (&q1.candidates[0], &q1.candidates[1]);
+        let (q2c1, q2c2) = (&q2.candidates[0], &q2.candidates[1]);
+        assert_eq!(
+            ballot_ids(q1.id, ReceiptState::Audited, Some(q1c1)).await,
+            vec![6]
+        );
+        assert_eq!(
+            ballot_ids(q1.id, ReceiptState::Audited, Some(q1c2)).await,
+            vec![7]
+        );
+        assert_eq!(
+            ballot_ids(q2.id, ReceiptState::Audited, Some(q2c1)).await,
+            vec![7]
+        );
+        assert_eq!(
+            ballot_ids(q2.id, ReceiptState::Audited, Some(q2c2)).await,
+            vec![4, 5, 6]
+        );
+        assert!(ballot_ids(q2.id, ReceiptState::Audited, Some(q1c1))
+            .await
+            .is_empty());
+
+        // Other states do not reveal their candidate.
+        let pagination = PaginationRequest {
+            page_num: 1,
+            page_size: 50,
+            ..Default::default()
+        };
+        let response = client
+            .get(uri!(election_question_ballots(
+                election.id,
+                q1.id,
+                Option::<String>::None,
+                Some(ReceiptState::Confirmed),
+                Some(q1c1.clone()),
+                Option::<String>::None,
+                pagination
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[backend_test]
+    async fn get_election_question_ballots_unknown_state(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap();
+        insert_unknown_ballot(&db, election.id, q1.id).await;
+
+        // The listing should still succeed, with the bad ballot skipped.
+        let pagination = PaginationRequest {
+            page_num: 1,
+            page_size: 50,
+            ..Default::default()
+        };
+        let response = client
+            .get(uri!(election_question_ballots(
+                election.id,
+                q1.id,
+                Option::<String>::None,
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
+                pagination
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        let raw_response = response.into_string().await.unwrap();
+        let receipts: Paginated<PublicReceipt> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(receipts.pagination.total, 10);
+        assert_eq!(receipts.pagination.skipped, 1);
+        assert_eq!(receipts.items.len(), 9);
+
+        // Fetching the bad ballot directly should act as if it does not exist.
+        let response = client
+            .get(uri!(election_question_ballot(election.id, q1.id, 1000)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test]
+    async fn get_election_question_ballot(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+
+        let ballot = Coll::<Ballot<Audited>>::from_db(&db)
+            .find_one(doc! {"state": Audited}, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let response = client
+            .get(uri!(election_question_ballot(
+                ballot.election_id,
+                ballot.question_id,
+                ballot.ballot_id
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: PublicReceipt = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(
+            Some(receipt),
+            PublicReceipt::from_ballot(AnyBallot::Audited(ballot), &election)
+        );
+    }
+
+    #[backend_test]
+    async fn candidate_totals(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap();
+
+        // Ensure we cannot get the totals on an in-progress election.
+        let response = client
             .get(uri!(candidate_totals(election.id, q1.id,)))
             .dispatch()
             .await;
@@ -873,19 +2517,320 @@ mod tests {
             .get(uri!(candidate_totals(election.id, q1.id,)))
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::Ok);
-        assert!(response.body().is_some());
-
-        let raw_response = response.into_string().await.unwrap();
-        let totals: HashMap<CandidateId, CandidateTotalsDesc> =
-            serde_json::from_str(&raw_response).unwrap();
-        assert_eq!(totals.len(), QuestionSpec::example1().candidates.len());
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        let raw_response = response.into_string().await.unwrap();
+        let totals: HashMap<CandidateId, CandidateTotalsDesc> =
+            serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(totals.len(), QuestionSpec::example1().candidates.len());
+
+        // Each candidate's totals carry the question's ballot stats.
+        for candidate_totals in totals.values() {
+            let stats = candidate_totals.ballot_stats.as_ref().unwrap();
+            assert_eq!(stats.confirmed, 5);
+            assert_eq!(stats.audited, 2);
+            assert_eq!(stats.unconfirmed, 2);
+        }
+
+        // The second question's three confirmed ballots are counted by each
+        // candidate's totals, and agree with the tallies.
+        let q2 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example2().description)
+            .unwrap();
+        let response = client
+            .get(uri!(candidate_totals(election.id, q2.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let totals: HashMap<CandidateId, CandidateTotalsDesc> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(totals
+            .values()
+            .all(|totals| totals.confirmed_ballot_count == 3));
+        let tally_sum = totals
+            .values()
+            .map(|totals| tally_to_u64(totals.tally).unwrap())
+            .sum::<u64>();
+        assert_eq!(tally_sum, 3);
+    }
+
+    #[backend_test]
+    async fn full_results(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+
+        // Ensure we cannot get the results of an in-progress election.
+        let response = client.get(uri!(full_results(election.id))).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Tie the third question with one confirmed vote for each candidate,
+        // recorded as deltas that have not been folded yet.
+        let q3 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example3().description)
+            .unwrap()
+            .clone();
+        let mut rng = rand::thread_rng();
+        for (ballot_id, yes, no) in [(1, 0, 1), (2, 1, 0)] {
+            let ballot = Ballot {
+                internal_id: Id::new(),
+                ballot: BallotCore::new(
+                    ballot_id,
+                    q3.id,
+                    q3.candidates[yes].clone(),
+                    vec![q3.candidates[no].clone()],
+                    &election,
+                    &mut rng,
+                )
+                .unwrap(),
+            };
+            let (confirmed, delta) = TotalsDelta::confirm(ballot, &q3.candidates);
+            Coll::<Ballot<Confirmed>>::from_db(&db)
+                .insert_one(confirmed, None)
+                .await
+                .unwrap();
+            Coll::<TotalsDelta>::from_db(&db)
+                .insert_one(delta, None)
+                .await
+                .unwrap();
+        }
+
+        // Set the end time in the past.
+        election.metadata.end_time = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
+        Coll::<Election>::from_db(&db)
+            .replace_one(u32_id_filter(election.id), &election, None)
+            .await
+            .unwrap();
+
+        let response = client.get(uri!(full_results(election.id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let results: FullResults =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(results.election_id, election.id);
+        assert_eq!(results.election_name, election.metadata.name);
+        assert_eq!(results.questions.len(), election.questions.len());
+        assert!(results
+            .questions
+            .windows(2)
+            .all(|pair| pair[0].question.id < pair[1].question.id));
+        let question = |spec: QuestionSpec| {
+            results
+                .questions
+                .iter()
+                .find(|q| q.question.description == spec.description)
+                .unwrap()
+        };
+
+        // The first question has a clear winner.
+        let q1 = question(QuestionSpec::example1());
+        assert_eq!(q1.tallies["Chris Riches"], 3);
+        assert_eq!(q1.tallies["Parry Hotter"], 2);
+        assert_eq!(q1.winners, vec!["Chris Riches".to_string()]);
+        assert!(!q1.tied);
+        assert_eq!((q1.confirmed, q1.audited), (5, 2));
+
+        let q2 = question(QuestionSpec::example2());
+        assert_eq!(q2.tallies["John Smith"], 0);
+        assert_eq!(q2.winners, vec!["Jane Doe".to_string()]);
+        assert_eq!((q2.confirmed, q2.audited), (3, 4));
+
+        // The third question is tied, counting the unfolded deltas.
+        let q3 = question(QuestionSpec::example3());
+        assert_eq!(q3.tallies["Yes"], 1);
+        assert_eq!(q3.tallies["No"], 1);
+        assert_eq!(q3.winners, vec!["Yes".to_string(), "No".to_string()]);
+        assert!(q3.tied);
+        assert_eq!((q3.confirmed, q3.audited), (2, 0));
+
+        // Nobody voted on the fourth question.
+        let q4 = question(QuestionSpec::example4());
+        assert!(q4.tallies.values().all(|tally| *tally == 0));
+        assert!(q4.winners.is_empty());
+        assert!(!q4.tied);
+        assert_eq!((q4.confirmed, q4.audited), (0, 0));
+    }
+
+    #[backend_test]
+    async fn ballot_stats(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap();
+
+        // No ballots yet.
+        let response = client
+            .get(uri!(ballot_stats(election.id, q1.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let stats: BallotStats = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(stats, BallotStats::default());
+
+        // Available while the election is in progress.
+        let before = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
+        insert_ballots(&db).await.unwrap();
+        insert_unknown_ballot(&db, election.id, q1.id).await;
+        let response = client
+            .get(uri!(ballot_stats(election.id, q1.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let stats: BallotStats = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(stats.confirmed, 5);
+        assert_eq!(stats.audited, 2);
+        assert_eq!(stats.unconfirmed, 2);
+        assert!(stats.last_activity.unwrap() >= before);
+
+        // Unknown questions and draft elections are not found.
+        let missing_question = election.questions.keys().max().unwrap() + 1;
+        let response = client
+            .get(uri!(ballot_stats(election.id, missing_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let draft = get_election_for_spec(&db, ElectionSpec::future_example()).await;
+        let draft_question = *draft.questions.keys().next().unwrap();
+        let response = client
+            .get(uri!(ballot_stats(draft.id, draft_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test]
+    async fn board_head(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap();
+
+        // Ballots written outside of the voting endpoints have no chain.
+        let response = client
+            .get(uri!(board_head(election.id, q1.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let head: BoardHeadDesc = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(head.length, 0);
+        assert_eq!(head.head, GENESIS_HASH);
+
+        let response = client
+            .get(uri!(question_dump(election.id, q1.id, _)))
+            .dispatch()
+            .await;
+        let raw_response = response.into_string().await.unwrap();
+        let results: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert!(results.board.is_none());
+
+        // Unknown questions and draft elections are not found.
+        let missing_question = election.questions.keys().max().unwrap() + 1;
+        let response = client
+            .get(uri!(board_head(election.id, missing_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let draft = get_election_for_spec(&db, ElectionSpec::future_example()).await;
+        let draft_question = *draft.questions.keys().next().unwrap();
+        let response = client
+            .get(uri!(board_head(draft.id, draft_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test]
+    async fn question_crypto(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let ballot = Coll::<Ballot<Confirmed>>::from_db(&db)
+            .find_one(doc! {"election_id": election.id, "state": Confirmed}, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let response = client
+            .get(uri!(question_crypto(election.id, ballot.question_id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let crypto: QuestionCrypto = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(crypto.election_id, election.id);
+        assert_eq!(crypto.question_id, ballot.question_id);
+        assert_eq!(
+            crypto.candidates,
+            election.questions[&ballot.question_id].candidates
+        );
+
+        // The receipt verifies with nothing but the question crypto.
+        let response = client
+            .get(uri!(election_question_ballot(
+                ballot.election_id,
+                ballot.question_id,
+                ballot.ballot_id
+            )))
+            .dispatch()
+            .await;
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: PublicReceipt = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(
+            verify_public_receipt_for_question(&receipt, &crypto),
+            Ok(())
+        );
+
+        // A receipt for another question does not.
+        let mut other_question = crypto.clone();
+        other_question.question_id += 1;
+        assert_eq!(
+            verify_public_receipt_for_question(&receipt, &other_question),
+            Err(VerificationError::Receipt(ReceiptError::WrongQuestion {
+                ballot_id: ballot.ballot_id
+            }))
+        );
+
+        // Unknown questions and draft elections are not found.
+        let missing_question = election.questions.keys().max().unwrap() + 1;
+        let response = client
+            .get(uri!(question_crypto(election.id, missing_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let draft = get_election_for_spec(&db, ElectionSpec::future_example()).await;
+        let draft_question = *draft.questions.keys().next().unwrap();
+        let response = client
+            .get(uri!(question_crypto(draft.id, draft_question)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
     }
 
     #[backend_test]
     async fn question_dump(client: Client, db: Database) {
-        insert_elections(&db).await;
-        insert_ballots(&db).await;
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
 
         let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
 
@@ -897,7 +2842,7 @@ mod tests {
 
         // Try with an in-progress election.
         let response = client
-            .get(uri!(question_dump(election.id, q1.id)))
+            .get(uri!(question_dump(election.id, q1.id, _)))
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
@@ -921,7 +2866,7 @@ mod tests {
         assert_eq!(result.modified_count, 1);
 
         let response = client
-            .get(uri!(question_dump(election.id, q1.id)))
+            .get(uri!(question_dump(election.id, q1.id, _)))
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
@@ -929,192 +2874,550 @@ mod tests {
 
         let raw_response = response.into_string().await.unwrap();
         let results: ElectionResults = serde_json::from_str(&raw_response).unwrap();
-        assert_eq!(results.election, ElectionDescription::from(election).crypto);
+        assert_eq!(
+            results.election,
+            ElectionDescription::from(election.clone()).crypto
+        );
         assert!(results.totals.is_some());
         assert!(results.verify().is_ok());
+        assert!(!raw_response.contains("dump_signature"));
+        assert_eq!(
+            results.verify_signature(),
+            Err(VerificationError::MissingDumpSignature)
+        );
+
+        // Signed dumps are otherwise identical.
+        let response = client
+            .get(uri!(question_dump(election.id, q1.id, Some(true))))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let mut signed: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert!(signed.verify().is_ok());
+        assert!(signed.verify_signature().is_ok());
+        let signature = signed.dump_signature.take();
+        assert!(signature.is_some());
+        assert_eq!(signed, results);
+
+        // Any change to the dump invalidates the signature.
+        signed.dump_signature = signature;
+        signed.skipped += 1;
+        assert_eq!(
+            signed.verify_signature(),
+            Err(VerificationError::DumpSignature)
+        );
     }
 
-    /// This isn't really a test, but a way of generating test data for end-to-end tests.
-    #[backend_test(admin)]
-    async fn generate_test_data(client: Client, db: Database) {
-        insert_elections(&db).await;
+    #[backend_test]
+    async fn question_dump_unknown_state(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
 
-        // Put the election in the past, and set ID to 1.
-        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
-        election.id = 1;
-        election.metadata.end_time = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
-        let result = Coll::<Election>::from_db(&db)
-            .delete_many(doc! {}, None)
-            .await
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let q1 = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
             .unwrap();
-        assert_eq!(result.deleted_count, 3);
+
+        // With no bad ballots, the skipped count should not even be present.
+        let response = client
+            .get(uri!(question_dump(election.id, q1.id, _)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        assert!(!raw_response.contains("skipped"));
+
+        // Add a bad ballot and check the dump still succeeds.
+        insert_unknown_ballot(&db, election.id, q1.id).await;
+        let response = client
+            .get(uri!(question_dump(election.id, q1.id, _)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        let raw_response = response.into_string().await.unwrap();
+        let results: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(results.skipped, 1);
+        assert_eq!(results.audited.len() + results.confirmed.len(), 7);
+        assert!(results.verify().is_ok());
+    }
+
+    #[backend_test]
+    async fn question_dump_redacted(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+
+        // Archive the election just now, long after it ended.
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let end_time = Utc::now() - chrono::Duration::try_days(365).unwrap();
+        let archived_at = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
         Coll::<Election>::from_db(&db)
-            .insert_one(&election, None)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! {
+                    "$set": {
+                        "state": ElectionState::Archived,
+                        "end_time": DateTime::from_chrono(end_time),
+                        "archived_at": DateTime::from_chrono(archived_at),
+                    }
+                },
+                None,
+            )
             .await
             .unwrap();
+        let dump = |question_id: QuestionId| {
+            let client = &client;
+            async move {
+                let response = client
+                    .get(uri!(question_dump(election.id, question_id, _)))
+                    .dispatch()
+                    .await;
+                assert_eq!(response.status(), Status::Ok);
+                let raw_response = response.into_string().await.unwrap();
+                serde_json::from_str::<ElectionResults>(&raw_response).unwrap()
+            }
+        };
+        let question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+        let mut before = Vec::new();
+        for question_id in &question_ids {
+            before.push(dump(*question_id).await);
+        }
 
-        insert_ballots(&db).await;
+        // Secrets are kept for the retention period from archiving, however
+        // long ago the election ended.
+        let config = client.rocket().state::<Config>().unwrap();
+        let maintenance = Maintenance::new(
+            db.clone(),
+            config.maintenance_retention(),
+            Some(chrono::Duration::try_days(30).unwrap()),
+            TaskRegistry::new(),
+        );
+        assert_eq!(maintenance.run().await.unwrap().ballots_redacted, 0);
+
+        // Redact everything archived, however recently.
+        let maintenance = Maintenance::new(
+            db.clone(),
+            config.maintenance_retention(),
+            Some(chrono::Duration::zero()),
+            TaskRegistry::new(),
+        );
+        assert_eq!(maintenance.run().await.unwrap().ballots_redacted, 6);
+        let redacted = doc! { "election_id": election.id, "state": Audited, "redacted": true };
+        assert_eq!(
+            db.collection::<Document>(AnyBallot::NAME)
+                .count_documents(redacted, None)
+                .await
+                .unwrap(),
+            6
+        );
+        // Nothing is left to redact the second time.
+        assert_eq!(maintenance.run().await.unwrap().ballots_redacted, 0);
+
+        // The dumps still verify, with the audited ballots redacted but
+        // otherwise unchanged.
+        for (question_id, before) in question_ids.into_iter().zip(before) {
+            let after = dump(question_id).await;
+            assert!(after.audited.is_empty());
+            assert_eq!(after.redacted.len(), before.audited.len());
+            assert_eq!(after.verify_all(), vec![]);
+            assert_eq!(ElectionResults::diff(&before, &after), vec![]);
+        }
+    }
 
+    #[backend_test]
+    async fn embargoed_board(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
         let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
 
-        let election_json = serde_json::to_string(&election).unwrap();
-        println!("{election_json}\n");
+        // Embargoed ballots and dumps are hidden from non-admins.
+        embargo_board(
+            &db,
+            &election,
+            Utc::now() + chrono::Duration::try_hours(1).unwrap(),
+        )
+        .await;
+        assert_eq!(
+            board_statuses(&client, &db, &election).await,
+            [Status::NotFound; 3]
+        );
 
-        for question in election.questions.values() {
-            let ballots = Coll::<AnyBallot>::from_db(&db)
-                .find(doc! {"question_id": question.id}, None)
-                .await
-                .unwrap()
-                .try_collect::<Vec<_>>()
-                .await
-                .unwrap();
-            for ballot in ballots {
-                let ballot_json = serde_json::to_string(&ballot).unwrap();
-                println!("{ballot_json},");
-            }
-            println!();
-            let totals = Coll::<CandidateTotals>::from_db(&db)
-                .find(doc! {"question_id": question.id}, None)
-                .await
-                .unwrap()
-                .try_collect::<Vec<_>>()
-                .await
-                .unwrap();
-            for total in totals {
-                let totals_json = serde_json::to_string(&total).unwrap();
-                println!("{totals_json},");
-            }
-            println!("\nFull dump:");
-            let response = client
-                .get(uri!(question_dump(election.id, question.id)))
-                .dispatch()
-                .await;
-            assert_eq!(response.status(), Status::Ok);
-            let dump = response.into_string().await.unwrap();
-            println!("{}", dump);
-            println!("\n\n\n");
-        }
+        // The embargo lifts by itself once its time passes.
+        embargo_board(
+            &db,
+            &election,
+            Utc::now() - chrono::Duration::try_seconds(1).unwrap(),
+        )
+        .await;
+        assert_eq!(
+            board_statuses(&client, &db, &election).await,
+            [Status::Ok; 3]
+        );
     }
 
-    async fn insert_elections(db: &Database) {
-        Coll::<Election>::from_db(db)
-            .insert_many(
-                [
-                    Election::published_example(),
-                    Election::draft_example(),
-                    Election::archived_example(),
-                ],
+    #[backend_test(admin)]
+    async fn embargoed_board_as_admin(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+
+        embargo_board(
+            &db,
+            &election,
+            Utc::now() + chrono::Duration::try_hours(1).unwrap(),
+        )
+        .await;
+        assert_eq!(
+            board_statuses(&client, &db, &election).await,
+            [Status::Ok; 3]
+        );
+    }
+
+    #[backend_test]
+    async fn result_visibility(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let [q1, q2, q3] = hide_results(&db, &election).await;
+
+        // While in progress, nothing differs from fully public questions.
+        let observed = observe_results(&client, &election, [q1, q2, q3]).await;
+        assert_eq!(observed.totals, [Status::NotFound; 3]);
+        assert_eq!(observed.dump_totals, [false; 3]);
+        assert!(observed.results.is_none());
+
+        // Once ended, only fully public questions have public totals.
+        election.metadata.end_time = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { "end_time": election.metadata.end_time } },
                 None,
             )
             .await
             .unwrap();
+        let observed = observe_results(&client, &election, [q1, q2, q3]).await;
+        assert_eq!(
+            observed.totals,
+            [Status::NotFound, Status::NotFound, Status::Ok]
+        );
+        assert_eq!(observed.dump_totals, [false, false, true]);
+        // Only the winner of the first question is shown, and nothing of the second.
+        let results = observed.results.unwrap();
+        assert_eq!(results.questions.len(), election.questions.len() - 1);
+        let first = results
+            .questions
+            .iter()
+            .find(|q| q.question.id == q1)
+            .unwrap();
+        assert!(first.tallies.is_empty());
+        assert_eq!(first.winners, vec!["Chris Riches".to_string()]);
+        assert!(results.questions.iter().all(|q| q.question.id != q2));
+        let third = results
+            .questions
+            .iter()
+            .find(|q| q.question.id == q3)
+            .unwrap();
+        assert!(!third.tallies.is_empty());
+
+        // The ballots of questions with withheld totals can still be verified.
+        let response = client
+            .get(uri!(question_dump(election.id, q1, _)))
+            .dispatch()
+            .await;
+        let dump: ElectionResults =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(!dump.confirmed.is_empty());
+        assert!(dump.verify().is_ok());
     }
 
-    async fn insert_ballots(db: &Database) {
-        let election = get_election_for_spec(db, ElectionSpec::current_example()).await;
-        let q1 = election
-            .questions
-            .values()
-            .find(|q| q.description == QuestionSpec::example1().description)
+    #[backend_test(admin)]
+    async fn result_visibility_as_admin(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
+        let [q1, q2, q3] = hide_results(&db, &election).await;
+
+        // Admins see no more than anyone else while the election is in progress.
+        let observed = observe_results(&client, &election, [q1, q2, q3]).await;
+        assert_eq!(observed.totals, [Status::NotFound; 3]);
+        assert_eq!(observed.dump_totals, [false; 3]);
+        assert!(observed.results.is_none());
+
+        // Once it is over, they see everything.
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { "state": ElectionState::Archived } },
+                None,
+            )
+            .await
             .unwrap();
-        let q1c1 = q1.candidates.first().unwrap();
-        let q1c2 = q1.candidates.get(1).unwrap();
+        let observed = observe_results(&client, &election, [q1, q2, q3]).await;
+        assert_eq!(observed.totals, [Status::Ok; 3]);
+        assert_eq!(observed.dump_totals, [true; 3]);
+        let results = observed.results.unwrap();
+        assert_eq!(results.questions.len(), election.questions.len());
+        assert!(results.questions.iter().all(|q| !q.tallies.is_empty()));
+    }
+
+    #[backend_test]
+    async fn quorum(client: Client, db: Database) {
+        insert_elections(&db).await.unwrap();
+        insert_ballots(&db).await.unwrap();
+        let mut election = get_election_for_spec(&db, ElectionSpec::current_example()).await;
         let q2 = election
             .questions
             .values()
             .find(|q| q.description == QuestionSpec::example2().description)
+            .unwrap()
+            .id;
+
+        // Once the election ends, there is nothing to report while no question
+        // has a quorum.
+        election.metadata.end_time = Utc::now() - chrono::Duration::try_seconds(1).unwrap();
+        let elections = Coll::<Election>::from_db(&db);
+        elections
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { "end_time": election.metadata.end_time } },
+                None,
+            )
+            .await
+            .unwrap();
+        let response = client
+            .get(uri!(election_non_admin(election.id, _)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let description: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(description
+            .questions
+            .values()
+            .all(|question| question.quorum_report.is_none()));
+
+        // Require all three voters allowed to answer the second question to
+        // confirm a ballot.
+        let quorum = mongodb::bson::to_bson(&QuorumSpec::Count(3)).unwrap();
+        elections
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { format!("questions.{q2}.quorum"): quorum } },
+                None,
+            )
+            .await
+            .unwrap();
+        let voter_elections = (0..3).map(|_| VoterElection {
+            voter_id: Id::new(),
+            election_id: election.id,
+            allowed_questions: AllowedQuestions {
+                confirmed: HashMap::from([(q2, true)]),
+            },
+            joins: None,
+            consent: None,
+        });
+        Coll::<VoterElection>::from_db(&db)
+            .insert_many(voter_elections, None)
+            .await
             .unwrap();
-        let q2c1 = q2.candidates.first().unwrap();
-        let q2c2 = q2.candidates.get(1).unwrap();
-        let mut rng = rand::thread_rng();
 
-        let mut candidate_totals = Vec::new();
-        for candidate in q1.candidates.iter() {
-            candidate_totals.push(NewCandidateTotals::new(
-                election.id,
-                q1.id,
-                candidate.clone(),
-            ));
-        }
-        for candidate in q2.candidates.iter() {
-            candidate_totals.push(NewCandidateTotals::new(
-                election.id,
-                q2.id,
-                candidate.clone(),
-            ));
+        // With only two of their ballots confirmed, the quorum is not met.
+        let ballots = Coll::<AnyBallot>::from_db(&db);
+        ballots
+            .update_one(
+                doc! {
+                    "election_id": election.id,
+                    "question_id": q2,
+                    "state": Confirmed,
+                },
+                doc! { "$set": { "state": Unconfirmed } },
+                None,
+            )
+            .await
+            .unwrap();
+        let reports = observe_quorum(&client, &election, q2).await;
+        for report in reports {
+            assert_eq!(report.quorum_met, Some(false));
+            assert_eq!(report.required, Some(3));
+            assert_eq!(report.eligible_voters, 3);
+            assert_eq!(report.confirmed_ballots, 2);
         }
-        // This relies on no duplicate candidate names between questions, which is true for the examples.
-        let mut totals_map = candidate_totals
-            .iter_mut()
-            .map(|t| (t.candidate_name.clone(), &mut t.crypto))
-            .collect::<HashMap<_, _>>();
-
-        let mut ballot =
-            |question: &Question, yes: CandidateId, no: CandidateId, ballot_id: &mut BallotId| {
-                *ballot_id += 1;
-                BallotCore::new(*ballot_id, question.id, yes, vec![no], &election, &mut rng)
-                    .unwrap()
-            };
 
-        macro_rules! ballot {
-            ($q:ident, $yes:ident, $no:ident, $id:ident) => {
-                ballot(&$q, $yes.clone(), $no.clone(), &mut $id)
-            };
+        // Once the third is confirmed, it is.
+        ballots
+            .update_one(
+                doc! {
+                    "election_id": election.id,
+                    "question_id": q2,
+                    "state": Unconfirmed,
+                },
+                doc! { "$set": { "state": Confirmed } },
+                None,
+            )
+            .await
+            .unwrap();
+        let reports = observe_quorum(&client, &election, q2).await;
+        for report in reports {
+            assert_eq!(report.quorum_met, Some(true));
+            assert_eq!(report.confirmed_ballots, 3);
         }
 
-        let mut q1_id = 0;
-        let mut q2_id = 0;
-        // Create confirmed ballots.
-        let confirmed = vec![
-            // q1: 3 votes for candidate 1, 2 votes for candidate 2
-            ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
-            ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
-            ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
-            ballot!(q1, q1c2, q1c1, q1_id).confirm(&mut totals_map),
-            ballot!(q1, q1c2, q1c1, q1_id).confirm(&mut totals_map),
-            // q2: 3 votes for candidate 2
-            ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
-            ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
-            ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
-        ];
+        // Questions without a quorum still report their turnout.
+        let response = client.get(uri!(full_results(election.id))).dispatch().await;
+        let results: FullResults =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(results
+            .questions
+            .iter()
+            .filter(|q| q.question.id != q2)
+            .all(|q| q.quorum.quorum_met.is_none() && q.quorum.required.is_none()));
+    }
 
-        // Create audited ballots.
-        let audited = vec![
-            // q1: 1 vote for each
-            ballot!(q1, q1c1, q1c2, q1_id).audit(),
-            ballot!(q1, q1c2, q1c1, q1_id).audit(),
-            // q2: 3 votes for candidate 2, 1 vote for candidate 1
-            ballot!(q2, q2c2, q2c1, q2_id).audit(),
-            ballot!(q2, q2c2, q2c1, q2_id).audit(),
-            ballot!(q2, q2c2, q2c1, q2_id).audit(),
-            ballot!(q2, q2c1, q2c2, q2_id).audit(),
-        ];
+    /// The quorum reports of a question from its totals, the election's
+    /// results, and the election's description, in that order.
+    async fn observe_quorum(
+        client: &Client,
+        election: &Election,
+        question_id: QuestionId,
+    ) -> [QuorumReport; 3] {
+        let response = client
+            .get(uri!(candidate_totals(election.id, question_id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let totals: HashMap<CandidateId, CandidateTotalsDesc> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let from_totals = totals.into_values().next().unwrap().quorum.unwrap();
 
-        // Create unconfirmed ballots.
-        let unconfirmed = vec![
-            // q1: 1 vote for each
-            ballot!(q1, q1c1, q1c2, q1_id),
-            ballot!(q1, q1c2, q1c1, q1_id),
-            // q2: 1 vote for candidate 1
-            ballot!(q2, q2c1, q2c2, q2_id),
-        ];
+        let response = client.get(uri!(full_results(election.id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let results: FullResults =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let from_results = results
+            .questions
+            .into_iter()
+            .find(|q| q.question.id == question_id)
+            .unwrap()
+            .quorum;
 
-        // Insert ballots.
-        Coll::<BallotCore<Confirmed>>::from_db(db)
-            .insert_many(confirmed, None)
-            .await
-            .unwrap();
-        Coll::<BallotCore<Audited>>::from_db(db)
-            .insert_many(audited, None)
-            .await
-            .unwrap();
-        Coll::<BallotCore<Unconfirmed>>::from_db(db)
-            .insert_many(unconfirmed, None)
-            .await
+        let response = client
+            .get(uri!(election_non_admin(election.id, _)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let mut description: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let from_description = description
+            .questions
+            .remove(&question_id)
+            .unwrap()
+            .quorum_report
             .unwrap();
-        // Insert candidate totals.
-        Coll::<NewCandidateTotals>::from_db(db)
-            .insert_many(candidate_totals, None)
+
+        [from_totals, from_results, from_description]
+    }
+
+    /// Show only the winner of the first example question, and nothing of the
+    /// second, returning their IDs along with that of the fully public third.
+    async fn hide_results(db: &Database, election: &Election) -> [QuestionId; 3] {
+        let question_id = |spec: QuestionSpec| {
+            election
+                .questions
+                .values()
+                .find(|q| q.description == spec.description)
+                .unwrap()
+                .id
+        };
+        let ids = [
+            question_id(QuestionSpec::example1()),
+            question_id(QuestionSpec::example2()),
+            question_id(QuestionSpec::example3()),
+        ];
+        for (question_id, visibility) in [
+            (ids[0], ResultVisibility::WinnerOnly),
+            (ids[1], ResultVisibility::AdminOnly),
+        ] {
+            let visibility = mongodb::bson::to_bson(&visibility).unwrap();
+            Coll::<Election>::from_db(db)
+                .update_one(
+                    u32_id_filter(election.id),
+                    doc! { "$set": { format!("questions.{question_id}.result_visibility"): visibility } },
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        ids
+    }
+
+    /// What the client can see of the results of some of an election's questions.
+    struct ObservedResults {
+        /// Status of getting each question's totals.
+        totals: [Status; 3],
+        /// Does each question's dump include its totals?
+        dump_totals: [bool; 3],
+        /// The election's results, if they can be got.
+        results: Option<FullResults>,
+    }
+
+    async fn observe_results(
+        client: &Client,
+        election: &Election,
+        question_ids: [QuestionId; 3],
+    ) -> ObservedResults {
+        let mut totals = [Status::Ok; 3];
+        let mut dump_totals = [false; 3];
+        for (i, question_id) in question_ids.into_iter().enumerate() {
+            let response = client
+                .get(uri!(candidate_totals(election.id, question_id)))
+                .dispatch()
+                .await;
+            totals[i] = response.status();
+            let response = client
+                .get(uri!(question_dump(election.id, question_id, _)))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            let dump: ElectionResults =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            dump_totals[i] = dump.totals.is_some();
+        }
+        let response = client.get(uri!(full_results(election.id))).dispatch().await;
+        let results = match response.status() {
+            Status::Ok => {
+                Some(serde_json::from_str(&response.into_string().await.unwrap()).unwrap())
+            }
+            status => {
+                assert_eq!(status, Status::NotFound);
+                None
+            }
+        };
+        ObservedResults {
+            totals,
+            dump_totals,
+            results,
+        }
+    }
+
+    /// Insert a ballot document with a state that this server does not understand.
+    async fn insert_unknown_ballot(
+        db: &Database,
+        election_id: ElectionId,
+        question_id: QuestionId,
+    ) {
+        let ballot = doc! {
+            "ballot_id": 1000,
+            "election_id": election_id,
+            "question_id": question_id,
+            "state": "Discarded",
+        };
+        db.collection::<Document>(AnyBallot::NAME)
+            .insert_one(ballot, None)
             .await
             .unwrap();
     }
@@ -1127,6 +3430,59 @@ mod tests {
             .unwrap()
     }
 
+    async fn embargo_board(db: &Database, election: &Election, until: chrono::DateTime<Utc>) {
+        let result = Coll::<Election>::from_db(db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! { "$set": { "embargo_public_board_until": until } },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.modified_count, 1);
+    }
+
+    /// Get the statuses of listing, getting one of, and dumping the ballots
+    /// of one of the election's questions.
+    async fn board_statuses(client: &Client, db: &Database, election: &Election) -> [Status; 3] {
+        let ballot = Coll::<Ballot<Audited>>::from_db(db)
+            .find_one(doc! { "election_id": election.id, "state": Audited }, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let (question_id, ballot_id) = (ballot.question_id, ballot.ballot_id);
+        let pagination = PaginationRequest {
+            page_num: 1,
+            page_size: 10,
+            ..Default::default()
+        };
+        let ballots = client
+            .get(uri!(election_question_ballots(
+                election.id,
+                question_id,
+                Option::<String>::None,
+                Option::<ReceiptState>::None,
+                Option::<CandidateId>::None,
+                Option::<String>::None,
+                pagination
+            )))
+            .dispatch()
+            .await;
+        let ballot = client
+            .get(uri!(election_question_ballot(
+                election.id,
+                question_id,
+                ballot_id
+            )))
+            .dispatch()
+            .await;
+        let dump = client
+            .get(uri!(question_dump(election.id, question_id, _)))
+            .dispatch()
+            .await;
+        [ballots.status(), ballot.status(), dump.status()]
+    }
+
     /// Dump the current state of the database to stdout; useful for debugging.
     #[allow(dead_code)]
     async fn dump_db_state(db: &Database) {
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    tokio::sync::Mutex,
+    Build, Rocket,
+};
+
+use crate::{
+    config::Config,
+    model::{
+        api::{ballot::BallotSpec, receipt::Receipt},
+        common::{ballot::Unconfirmed, election::ElectionId},
+        mongodb::Id,
+    },
+};
+
+/// A ballot a voter cast recently, with the spec it was cast from.
+struct RecentCast {
+    cast_at: Instant,
+    election_id: ElectionId,
+    spec: BallotSpec,
+    receipt: Receipt<Unconfirmed>,
+}
+
+/// The ballots each voter cast within the last few seconds, so that an
+/// accidental double-submission can be answered with the ballots it already
+/// cast rather than minting more.
+///
+/// This is only held in memory, so a double-submission that reaches another
+/// server instance, or races the original, is cast as normal; clients wanting
+/// a guarantee should send an idempotency key instead, in which case the key
+/// alone decides what counts as a retry.
+pub struct RecentCasts {
+    window: Duration,
+    casts: Mutex<HashMap<Id, Vec<RecentCast>>>,
+}
+
+impl RecentCasts {
+    /// Remember casts for the given window. A zero window remembers nothing.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            casts: Default::default(),
+        }
+    }
+
+    /// Find the receipts of ballots the voter recently cast in the election
+    /// for exactly these specs, in the same order, if every one of them was.
+    pub async fn find(
+        &self,
+        voter_id: Id,
+        election_id: ElectionId,
+        specs: &[BallotSpec],
+    ) -> Option<Vec<Receipt<Unconfirmed>>> {
+        let mut casts_locked = self.casts.lock().await;
+        self.forget_expired(&mut casts_locked);
+        let casts = casts_locked.get(&voter_id)?;
+
+        // Each spec must match a different cast.
+        let mut used = vec![false; casts.len()];
+        let mut receipts = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let index = casts.iter().enumerate().position(|(i, cast)| {
+                !used[i] && cast.election_id == election_id && cast.spec == *spec
+            })?;
+            used[index] = true;
+            receipts.push(casts[index].receipt.clone());
+        }
+        Some(receipts)
+    }
+
+    /// Remember that the voter cast the given ballots, with the receipts in
+    /// the same order as the specs.
+    pub async fn record(
+        &self,
+        voter_id: Id,
+        election_id: ElectionId,
+        specs: Vec<BallotSpec>,
+        receipts: &[Receipt<Unconfirmed>],
+    ) {
+        if self.window.is_zero() {
+            return;
+        }
+        let mut casts_locked = self.casts.lock().await;
+        self.forget_expired(&mut casts_locked);
+        let now = Instant::now();
+        casts_locked
+            .entry(voter_id)
+            .or_default()
+            .extend(
+                specs
+                    .into_iter()
+                    .zip(receipts)
+                    .map(|(spec, receipt)| RecentCast {
+                        cast_at: now,
+                        election_id,
+                        spec,
+                        receipt: receipt.clone(),
+                    }),
+            );
+    }
+
+    /// Forget casts older than the window, and voters left with none.
+    fn forget_expired(&self, casts: &mut HashMap<Id, Vec<RecentCast>>) {
+        casts.retain(|_, voter_casts| {
+            voter_casts.retain(|cast| cast.cast_at.elapsed() < self.window);
+            !voter_casts.is_empty()
+        });
+    }
+}
+
+/// A fairing that places a [`RecentCasts`] into managed state.
+/// This fairing depends on the config being available in managed state, and
+/// so must be attached after the fairing responsible for that.
+pub struct RecentCastsFairing;
+
+#[rocket::async_trait]
+impl Fairing for RecentCastsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Recent Casts",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let window = match rocket.state::<Config>() {
+            Some(config) => config.duplicate_cast_window(),
+            None => {
+                error!("Config was not available when setting up duplicate cast detection");
+                return Err(rocket);
+            }
+        };
+        Ok(rocket.manage(RecentCasts::new(window)))
+    }
+}
@@ -0,0 +1,576 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, DateTime},
+    options::{FindOneOptions, FindOptions},
+    Client, Database,
+};
+use rand::seq::{IteratorRandom, SliceRandom};
+use rocket::{futures::TryStreamExt, http::Status, serde::json::Json, tokio, Route, State};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    logging::RequestId,
+    model::{
+        api::{
+            auth::{AuthToken, CsrfChecked},
+            ballot::{BallotRecall, BallotSpec, OwnershipProof},
+            election::ElectionSpec,
+            rehearsal::{QuestionVerification, RehearsalReportDesc, RehearsalSpec},
+        },
+        common::{
+            allowed_questions::{AllowedQuestions, Joins},
+            election::{ElectionId, ElectionState, WRITE_IN_CANDIDATE},
+        },
+        db::{
+            admin::Admin, candidate_totals::TotalsFolder, election::Election,
+            rehearsal::RehearsalReport, voter::NewVoter, voter_election::VoterElection,
+        },
+        mongodb::{
+            u32_id_filter, Coll, Counter, Deadline, Id, RequestComment, ELECTION_ID_COUNTER_ID,
+        },
+    },
+};
+
+use super::{
+    admin::{acting_admin_username, delete_election_data, insert_election},
+    public::dump_question,
+    voting::{audit, cast, confirm, join},
+};
+
+/// The most synthetic voters a single rehearsal may have.
+const MAX_REHEARSAL_VOTERS: u32 = 10_000;
+
+pub fn routes() -> Vec<Route> {
+    routes![rehearse_election, election_rehearsals]
+}
+
+/// Rehearse an election: copy it into a throwaway published election, hidden
+/// from everyone but admins, and have synthetic voters vote in it in the
+/// background. The report is stored once the copy's results have been verified,
+/// and the copy is deleted after the configured TTL.
+#[post("/elections/<election_id>/rehearse", data = "<spec>", format = "json")]
+#[allow(clippy::too_many_arguments)]
+async fn rehearse_election(
+    token: AuthToken<Admin>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    spec: Json<RehearsalSpec>,
+    elections: Coll<Election>,
+    counters: Coll<Counter>,
+    admins: Coll<Admin>,
+    reports: Coll<RehearsalReport>,
+    db_client: &State<Client>,
+    db: &State<Database>,
+    totals_folder: &State<TotalsFolder>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<RehearsalReportDesc>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    if spec.voters == 0 || spec.voters > MAX_REHEARSAL_VOTERS {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!("A rehearsal must have between 1 and {MAX_REHEARSAL_VOTERS} voters"),
+        ));
+    }
+    if !(0.0..=1.0).contains(&spec.confirm_probability) {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            "Confirm probability must be between 0 and 1".to_string(),
+        ));
+    }
+    let username = acting_admin_username(&token, &admins, request_id).await?;
+
+    // Clean up any rehearsals whose deletion was missed, e.g. due to a restart.
+    delete_expired_rehearsals(db_client, db, request_id).await?;
+
+    let election = elections
+        .find_one(
+            u32_id_filter(election_id),
+            FindOneOptions::for_request(request_id),
+        )
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Election with ID '{}'", election_id)))?;
+    if election.metadata.rehearsal {
+        return Err(Error::Status(
+            Status::BadRequest,
+            format!("Election {} is itself a rehearsal", election_id),
+        ));
+    }
+
+    // Copy the election, with fresh keys, open for voting until it expires.
+    let rehearsal_id = Counter::next(&counters, ELECTION_ID_COUNTER_ID).await?;
+    let started_at = Utc::now();
+    let expires_at = started_at + config.rehearsal_ttl();
+    let mut rehearsal =
+        ElectionSpec::from(election).into_election(rehearsal_id, rand::thread_rng());
+    rehearsal.metadata.name = format!("{} (rehearsal)", rehearsal.metadata.name);
+    rehearsal.metadata.state = ElectionState::Published;
+    // The spec leaves out the write-in candidates, which publishing adds.
+    rehearsal.add_write_in_candidates();
+    rehearsal.metadata.start_time = started_at;
+    rehearsal.metadata.end_time = expires_at;
+    // Every synthetic voter must be able to join.
+    rehearsal.metadata.max_voters = None;
+    // Early question closes belong to the real schedule, not the rehearsal's.
+    for question in rehearsal.questions.values_mut() {
+        question.end_time = None;
+    }
+    rehearsal.metadata.created_by = username;
+    rehearsal.metadata.rehearsal = true;
+    insert_election(
+        &rehearsal,
+        &elections,
+        &counters,
+        &Coll::from_db(db),
+        db_client,
+        request_id,
+    )
+    .await?;
+
+    let report = RehearsalReport {
+        rehearsal_election_id: rehearsal_id,
+        election_id,
+        voters: spec.voters,
+        confirm_probability: spec.confirm_probability,
+        started_at,
+        finished_at: None,
+        expires_at,
+        ballots_confirmed: 0,
+        ballots_audited: 0,
+        questions: Vec::new(),
+        verified: false,
+        error: None,
+    };
+    reports.insert_one(&report, None).await?;
+    warn!(
+        "  req{} Rehearsing election {} as {} with {} voters",
+        request_id, election_id, rehearsal_id, spec.voters
+    );
+
+    // The rehearsal outlives the request, so needs its own handles.
+    let db_client = db_client.inner().clone();
+    let db = db.inner().clone();
+    let totals_folder = totals_folder.inner().clone();
+    let vote_timeout = config.vote_request_timeout();
+    let hmac_secret = config.hmac_secret().to_vec();
+    let mut background_report = report.clone();
+    tokio::spawn(async move {
+        if let Err(err) = rehearse(
+            &rehearsal,
+            &mut background_report,
+            &db_client,
+            &db,
+            &totals_folder,
+            &hmac_secret,
+            vote_timeout,
+            request_id,
+        )
+        .await
+        {
+            error!("  req{request_id} Rehearsal {rehearsal_id} failed: {err}");
+            background_report.error = Some(err.to_string());
+        }
+        background_report.finished_at = Some(DateTime::now());
+        background_report.verified = background_report.error.is_none()
+            && background_report
+                .questions
+                .iter()
+                .all(|question| question.verified);
+        let reports = Coll::<RehearsalReport>::from_db(&db);
+        if let Err(err) = reports
+            .replace_one(u32_id_filter(rehearsal_id), &background_report, None)
+            .await
+        {
+            error!("  req{request_id} Failed to store rehearsal {rehearsal_id} report: {err}");
+        }
+        info!(
+            "  req{} Rehearsal {} finished, verified: {}",
+            request_id, rehearsal_id, background_report.verified
+        );
+
+        let until_expiry = (expires_at - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(until_expiry).await;
+        if let Err(err) = delete_rehearsal(&rehearsal, &db_client, &db, request_id).await {
+            error!("  req{request_id} Failed to delete rehearsal {rehearsal_id}: {err}");
+        }
+    });
+
+    Ok(Json(report.into()))
+}
+
+/// List the reports of an election's rehearsals, most recent first.
+#[get("/elections/<election_id>/rehearsals")]
+async fn election_rehearsals(
+    token: AuthToken<Admin>,
+    election_id: ElectionId,
+    reports: Coll<RehearsalReport>,
+    request_id: RequestId,
+) -> Result<Json<Vec<RehearsalReportDesc>>> {
+    info!("  req{} Admin {} acting", request_id, token.id);
+    let mut options = FindOptions::for_request(request_id);
+    options.sort = Some(doc! {"started_at": -1});
+    let reports = reports
+        .find(doc! {"election_id": election_id}, options)
+        .await?
+        .map_ok(RehearsalReportDesc::from)
+        .try_collect()
+        .await?;
+    Ok(Json(reports))
+}
+
+/// Have the report's synthetic voters join the rehearsal election and vote for
+/// random candidates, through the same code paths as the voting endpoints.
+/// Each ballot is confirmed with the report's probability, and audited
+/// otherwise. Then verify each question's results, recording the outcome.
+/// Each cast, audit and confirmation is given `vote_timeout` to finish, just
+/// like a voter's request.
+async fn rehearse(
+    election: &Election,
+    report: &mut RehearsalReport,
+    db_client: &Client,
+    db: &Database,
+    totals_folder: &TotalsFolder,
+    hmac_secret: &[u8],
+    vote_timeout: Duration,
+    request_id: RequestId,
+) -> Result<()> {
+    let elections = Coll::from_db(db);
+    let new_voters = Coll::<NewVoter>::from_db(db);
+    let voter_elections = Coll::from_db(db);
+    let new_ballots = Coll::from_db(db);
+    let unconfirmed_ballots = Coll::from_db(db);
+    let audited_ballots = Coll::from_db(db);
+    let confirmed_ballots = Coll::from_db(db);
+    let ballots = Coll::from_db(db);
+    let candidate_totals = Coll::from_db(db);
+    let totals_deltas = Coll::from_db(db);
+    let counters = Coll::from_db(db);
+    let board_heads = Coll::from_db(db);
+    let board_entries = Coll::from_db(db);
+
+    for _ in 0..report.voters {
+        // A random SMS HMAC, which no real voter can log in with.
+        let voter = NewVoter {
+            sms_hmac: rand::random::<[u8; 32]>().to_vec(),
+            frozen: false,
+            freeze: None,
+        };
+        let voter_id: Id = new_voters
+            .insert_one(&voter, None)
+            .await?
+            .inserted_id
+            .as_object_id()
+            .unwrap() // Safe because the ID comes directly from the database.
+            .into();
+
+        // Join one group of each mutex electorate, and every group of the rest.
+        let joins = election
+            .electorates
+            .iter()
+            .map(|(name, electorate)| {
+                let groups = if electorate.is_mutex {
+                    electorate
+                        .groups
+                        .iter()
+                        .choose(&mut rand::thread_rng())
+                        .cloned()
+                        .into_iter()
+                        .collect()
+                } else {
+                    electorate.groups.clone()
+                };
+                (name.clone(), groups)
+            })
+            .collect::<Joins>();
+        let voter_election = VoterElection {
+            voter_id,
+            election_id: election.id,
+            allowed_questions: AllowedQuestions::for_joins(election, &joins)?,
+            joins: Some(joins),
+            consent: None,
+        };
+        join(
+            &voter_election,
+            election,
+            &elections,
+            &voter_elections,
+            db_client,
+            request_id,
+        )
+        .await?;
+
+        let ballot_specs = voter_election
+            .allowed_questions
+            .keys()
+            .filter_map(|question_id| {
+                let candidates = &election.questions[question_id].candidates;
+                candidates
+                    .choose(&mut rand::thread_rng())
+                    .map(|candidate| BallotSpec {
+                        question: *question_id,
+                        candidate: candidate.clone(),
+                        write_in_name: (candidate == WRITE_IN_CANDIDATE)
+                            .then(|| "Rehearsal Write-In".to_string()),
+                    })
+            })
+            .collect::<Vec<_>>();
+        if ballot_specs.is_empty() {
+            continue;
+        }
+        let receipts = cast(
+            election,
+            ballot_specs,
+            &new_ballots,
+            &counters,
+            &board_heads,
+            &board_entries,
+            db_client,
+            Deadline::after(vote_timeout),
+            request_id,
+        )
+        .await?;
+
+        let (to_confirm, to_audit): (Vec<_>, Vec<_>) = receipts
+            .into_iter()
+            .map(|receipt| BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id: receipt.question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            })
+            .partition(|_| rand::random::<f32>() < report.confirm_probability);
+        if !to_audit.is_empty() {
+            let audited = audit(
+                voter_id,
+                election,
+                &to_audit,
+                &unconfirmed_ballots,
+                &audited_ballots,
+                &board_heads,
+                &board_entries,
+                db_client,
+                hmac_secret,
+                Deadline::after(vote_timeout),
+                request_id,
+            )
+            .await?;
+            report.ballots_audited += audited.len() as u32;
+        }
+        if !to_confirm.is_empty() {
+            let confirmed = confirm(
+                voter_id,
+                election,
+                &to_confirm,
+                &voter_elections,
+                &unconfirmed_ballots,
+                &confirmed_ballots,
+                &totals_deltas,
+                totals_folder,
+                &board_heads,
+                &board_entries,
+                db_client,
+                hmac_secret,
+                Deadline::after(vote_timeout),
+                request_id,
+            )
+            .await?;
+            report.ballots_confirmed += confirmed.len() as u32;
+        }
+    }
+    debug!(
+        "  req{} Rehearsal {} confirmed {} and audited {} ballots",
+        request_id, election.id, report.ballots_confirmed, report.ballots_audited
+    );
+
+    let mut question_ids = election.questions.keys().copied().collect::<Vec<_>>();
+    question_ids.sort_unstable();
+    for question_id in question_ids {
+        let (_, results) = dump_question(
+            election.id,
+            question_id,
+            true,
+            None,
+            &elections,
+            &candidate_totals,
+            &totals_deltas,
+            &ballots,
+            &board_heads,
+            &board_entries,
+            db_client,
+            request_id,
+        )
+        .await?;
+        let error = results.verify().err().map(|err| format!("{err:?}"));
+        report.questions.push(QuestionVerification {
+            question_id,
+            verified: error.is_none(),
+            error,
+        });
+    }
+
+    Ok(())
+}
+
+/// Delete every rehearsal election that has expired.
+async fn delete_expired_rehearsals(
+    db_client: &Client,
+    db: &Database,
+    request_id: RequestId,
+) -> Result<()> {
+    let filter = doc! {
+        "rehearsal": true,
+        "end_time": { "$lte": Utc::now() },
+    };
+    let expired = Coll::<Election>::from_db(db)
+        .find(filter, FindOptions::for_request(request_id))
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    for election in expired {
+        delete_rehearsal(&election, db_client, db, request_id).await?;
+    }
+    Ok(())
+}
+
+/// Delete a rehearsal election and all its data.
+/// Its synthetic voters are left for maintenance to purge.
+async fn delete_rehearsal(
+    election: &Election,
+    db_client: &Client,
+    db: &Database,
+    request_id: RequestId,
+) -> Result<()> {
+    // Only ever delete rehearsals, whatever state they are in.
+    let filter = doc! {
+        "_id": election.id,
+        "rehearsal": true,
+    };
+    delete_election_data(
+        election,
+        filter,
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        &Coll::from_db(db),
+        db_client,
+        request_id,
+    )
+    .await?;
+    warn!(
+        "  req{} Deleted rehearsal election {} - {}",
+        request_id, election.id, election.metadata.name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rocket::{
+        http::ContentType,
+        local::asynchronous::{Client, LocalResponse},
+        serde::json::serde_json,
+    };
+
+    use crate::model::api::{auth::WithCsrf, election::ElectionSummary};
+
+    use super::*;
+
+    #[backend_test(admin)]
+    async fn rehearsal_verifies_and_is_hidden(client: Client, db: Database) {
+        let election = Election::published_example();
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        // Bad specs are rejected.
+        for (voters, confirm_probability) in [(0, 0.5), (10, 1.5), (10, f32::NAN)] {
+            let spec = RehearsalSpec {
+                voters,
+                confirm_probability,
+            };
+            let response = start_rehearsal(&client, election.id, &spec).await;
+            assert_eq!(Status::UnprocessableEntity, response.status());
+        }
+
+        // Start a rehearsal.
+        let spec = RehearsalSpec {
+            voters: 10,
+            confirm_probability: 0.5,
+        };
+        let response = start_rehearsal(&client, election.id, &spec).await;
+        assert_eq!(Status::Ok, response.status());
+        let started: RehearsalReportDesc =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(started.election_id, election.id);
+        assert_ne!(started.rehearsal_election_id, election.id);
+        assert_eq!(started.finished_at, None);
+
+        // Wait for it to finish.
+        let mut report = None;
+        for _ in 0..100 {
+            let response = client
+                .get(uri!(election_rehearsals(election.id)))
+                .dispatch()
+                .await;
+            assert_eq!(Status::Ok, response.status());
+            let mut reports: Vec<RehearsalReportDesc> =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            assert_eq!(reports.len(), 1);
+            if reports[0].finished_at.is_some() {
+                report = reports.pop();
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let report = report.expect("Rehearsal did not finish");
+        assert!(report.verified, "{report:?}");
+        assert_eq!(report.error, None);
+        assert_eq!(report.questions.len(), election.questions.len());
+        assert!(report.ballots_confirmed + report.ballots_audited > 0);
+
+        // The rehearsal is listed for admins.
+        let response = client.get("/elections").dispatch().await;
+        let summaries: Vec<ElectionSummary> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(summaries
+            .iter()
+            .any(|summary| summary.id == report.rehearsal_election_id));
+
+        // But invisible to everyone else.
+        let response = client.delete("/auth").csrf().dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let path = format!("/elections/{}", report.rehearsal_election_id);
+        let response = client.get(path.as_str()).dispatch().await;
+        assert_eq!(Status::NotFound, response.status());
+        let response = client.get("/elections").dispatch().await;
+        let summaries: Vec<ElectionSummary> =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(summaries.iter().any(|summary| summary.id == election.id));
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.id != report.rehearsal_election_id));
+    }
+
+    async fn start_rehearsal<'c>(
+        client: &'c Client,
+        election_id: ElectionId,
+        spec: &RehearsalSpec,
+    ) -> LocalResponse<'c> {
+        client
+            .post(uri!(rehearse_election(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(spec).unwrap())
+            .dispatch()
+            .await
+    }
+}
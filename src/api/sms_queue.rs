@@ -0,0 +1,473 @@
+use std::mem;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use mongodb::Database;
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    futures::future::join_all,
+    tokio::{
+        self,
+        sync::{mpsc, Mutex as AsyncMutex},
+        task::JoinHandle,
+        time::{self, Duration},
+    },
+    Build, Orbit, Rocket,
+};
+
+use crate::{
+    config::{Config, SmsNotifier, SmsRouter},
+    error::{Error, Result},
+    logging::RequestId,
+    model::{
+        api::sms::{Sms, SmsQueueStatus},
+        db::challenge_delivery::{ChallengeDelivery, SmsOutcome},
+        mongodb::Coll,
+    },
+};
+
+use super::auth::deliver_sms;
+
+/// How many times a message that SNS throttled is retried before giving up.
+const MAX_THROTTLE_RETRIES: u32 = 5;
+
+/// How long to wait before retrying a throttled message for the first time.
+/// Each further retry waits twice as long as the last.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// How long shutdown waits for queued messages to be sent.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A message waiting to be sent, along with the request that asked for it.
+struct QueuedSms {
+    sms: Sms,
+    sms_hmac: Vec<u8>,
+    message: String,
+    request_id: RequestId,
+}
+
+/// What the queue has done since it started, shared with its senders.
+#[derive(Default)]
+struct QueueStats {
+    depth: AtomicUsize,
+    sent: AtomicU64,
+    failed: AtomicU64,
+    throttle_retries: AtomicU64,
+    throttled_out: AtomicU64,
+}
+
+/// A queue of OTP challenges waiting to be sent by a pool of sender tasks, so
+/// that a burst of voters asking for codes at once need not wait for SNS, and
+/// SNS throttling us only delays their codes.
+///
+/// The queue is bounded; once it is full, challenges are refused with
+/// [`Error::SmsQueueFull`] until it has room again.
+pub struct SmsQueue {
+    capacity: usize,
+    sender: Mutex<Option<mpsc::Sender<QueuedSms>>>,
+    senders: Mutex<Vec<JoinHandle<()>>>,
+    stats: Arc<QueueStats>,
+}
+
+impl SmsQueue {
+    /// A queue that is not used, so challenges are sent before responding.
+    pub fn disabled() -> Self {
+        Self {
+            capacity: 0,
+            sender: Mutex::new(None),
+            senders: Mutex::new(Vec::new()),
+            stats: Default::default(),
+        }
+    }
+
+    /// Start a queue holding up to `capacity` messages, with `senders` tasks
+    /// sending them through the notifier in parallel and recording what became
+    /// of them as [`deliver_sms`] does.
+    ///
+    /// Messages that SNS throttles are retried after `backoff`, then twice
+    /// that, and so on, up to [`MAX_THROTTLE_RETRIES`] times.
+    pub fn start(
+        capacity: usize,
+        senders: usize,
+        notifier: Arc<dyn SmsNotifier>,
+        deliveries: Coll<ChallengeDelivery>,
+        max_failures: u32,
+        backoff: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let receiver = Arc::new(AsyncMutex::new(receiver));
+        let stats = Arc::new(QueueStats::default());
+        let notifier = Arc::new(Backoff {
+            notifier,
+            initial: backoff,
+            stats: stats.clone(),
+        });
+        let handles = (0..senders)
+            .map(|_| {
+                tokio::spawn(send_queued(
+                    receiver.clone(),
+                    notifier.clone(),
+                    deliveries.clone(),
+                    max_failures,
+                    stats.clone(),
+                ))
+            })
+            .collect();
+
+        Self {
+            capacity,
+            sender: Mutex::new(Some(sender)),
+            senders: Mutex::new(handles),
+            stats,
+        }
+    }
+
+    /// Are messages queued, rather than sent before responding?
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Queue a message to the number with the given HMAC, without waiting.
+    ///
+    /// Fails with [`Error::SmsQueueFull`] if the queue has no room, or has
+    /// been drained.
+    pub fn enqueue(
+        &self,
+        sms: Sms,
+        sms_hmac: Vec<u8>,
+        message: String,
+        request_id: RequestId,
+    ) -> Result<()> {
+        let queued = QueuedSms {
+            sms,
+            sms_hmac,
+            message,
+            request_id,
+        };
+        // Count the message first, so that a sender taking it straight away
+        // cannot make the depth underflow.
+        self.stats.depth.fetch_add(1, Ordering::SeqCst);
+        let sent = match &*self.sender.lock().unwrap() {
+            Some(sender) => sender.try_send(queued).is_ok(),
+            None => false,
+        };
+        if !sent {
+            self.stats.depth.fetch_sub(1, Ordering::SeqCst);
+            return Err(Error::SmsQueueFull);
+        }
+        Ok(())
+    }
+
+    /// Report how full the queue is, and what it has done so far.
+    pub fn status(&self) -> SmsQueueStatus {
+        SmsQueueStatus {
+            enabled: self.is_enabled(),
+            depth: self.stats.depth.load(Ordering::SeqCst) as u64,
+            capacity: self.capacity as u64,
+            sent: self.stats.sent.load(Ordering::SeqCst),
+            failed: self.stats.failed.load(Ordering::SeqCst),
+            throttle_retries: self.stats.throttle_retries.load(Ordering::SeqCst),
+            throttled_out: self.stats.throttled_out.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Stop accepting messages, and wait up to `timeout` for the senders to
+    /// send those already queued.
+    pub async fn drain(&self, timeout: Duration) {
+        self.sender.lock().unwrap().take();
+        let senders = mem::take(&mut *self.senders.lock().unwrap());
+        if senders.is_empty() {
+            return;
+        }
+        let depth = self.stats.depth.load(Ordering::SeqCst);
+        info!("Draining SMS queue of {depth} messages...");
+        match time::timeout(timeout, join_all(senders)).await {
+            Ok(_) => info!("...SMS queue drained"),
+            Err(_) => warn!(
+                "...gave up draining SMS queue with {} messages unsent",
+                self.stats.depth.load(Ordering::SeqCst)
+            ),
+        }
+    }
+}
+
+/// Send queued messages until the queue is drained and empty.
+async fn send_queued(
+    receiver: Arc<AsyncMutex<mpsc::Receiver<QueuedSms>>>,
+    notifier: Arc<Backoff>,
+    deliveries: Coll<ChallengeDelivery>,
+    max_failures: u32,
+    stats: Arc<QueueStats>,
+) {
+    loop {
+        let Some(queued) = receiver.lock().await.recv().await else {
+            break;
+        };
+        stats.depth.fetch_sub(1, Ordering::SeqCst);
+        let result = deliver_sms(
+            &queued.sms,
+            queued.sms_hmac,
+            queued.message,
+            max_failures,
+            &deliveries,
+            notifier.as_ref(),
+            queued.request_id,
+        )
+        .await;
+        match result {
+            Ok(()) => {
+                stats.sent.fetch_add(1, Ordering::SeqCst);
+            }
+            Err(e) => {
+                stats.failed.fetch_add(1, Ordering::SeqCst);
+                warn!(
+                    "  req{} Queued SMS to {} was not sent: {e}",
+                    queued.request_id,
+                    queued.sms.redacted()
+                );
+            }
+        }
+    }
+}
+
+/// Sends messages through another notifier, retrying those that SNS throttled
+/// with exponential backoff. Giving up on a message is counted here, as it is
+/// no fault of the number, so [`deliver_sms`] holds no failure against it.
+struct Backoff {
+    notifier: Arc<dyn SmsNotifier>,
+    initial: Duration,
+    stats: Arc<QueueStats>,
+}
+
+#[rocket::async_trait]
+impl SmsNotifier for Backoff {
+    async fn send_sms(&self, sms: &Sms, message: String) -> SmsOutcome {
+        let mut delay = self.initial;
+        let mut retries = 0;
+        loop {
+            let outcome = self.notifier.send_sms(sms, message.clone()).await;
+            if !outcome.throttled {
+                return outcome;
+            }
+            if retries == MAX_THROTTLE_RETRIES {
+                self.stats.throttled_out.fetch_add(1, Ordering::SeqCst);
+                return outcome;
+            }
+            retries += 1;
+            self.stats.throttle_retries.fetch_add(1, Ordering::SeqCst);
+            time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// A fairing that places an [`SmsQueue`] into managed state, starting it if
+/// the config enables it, and drains it on shutdown.
+/// This fairing depends on the config, database, and SMS router being
+/// available in managed state, and so must be attached after the fairings
+/// responsible for them.
+pub struct SmsQueueFairing;
+
+#[rocket::async_trait]
+impl Fairing for SmsQueueFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "SMS Queue",
+            kind: Kind::Ignite | Kind::Shutdown,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let Some(config) = rocket.state::<Config>() else {
+            error!("Config was not available when setting up the SMS queue");
+            return Err(rocket);
+        };
+        if !config.sms_queue_enabled() {
+            info!("SMS queue disabled; challenges will be sent before responding");
+            return Ok(rocket.manage(SmsQueue::disabled()));
+        }
+        let Some(db) = rocket.state::<Database>() else {
+            error!("Database was not available when setting up the SMS queue");
+            return Err(rocket);
+        };
+        let Some(sms_router) = rocket.state::<SmsRouter>() else {
+            error!("SMS router was not available when setting up the SMS queue");
+            return Err(rocket);
+        };
+
+        let capacity = config.sms_queue_capacity();
+        let senders = config.sms_queue_senders();
+        let queue = SmsQueue::start(
+            capacity,
+            senders,
+            Arc::new(sms_router.clone()),
+            Coll::from_db(db),
+            config.max_sms_delivery_failures(),
+            INITIAL_BACKOFF,
+        );
+        info!("Started SMS queue of {capacity} messages with {senders} senders");
+        Ok(rocket.manage(queue))
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        if let Some(queue) = rocket.state::<SmsQueue>() {
+            queue.drain(DRAIN_TIMEOUT).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::asynchronous::Client, tokio::sync::Semaphore};
+
+    use crate::model::db::challenge_delivery::DeliveryStatus;
+
+    use super::*;
+
+    /// Sends nothing, but reports the first `throttled` messages as throttled,
+    /// and waits for a permit from `gate` before each message.
+    struct MockNotifier {
+        throttled: usize,
+        calls: AtomicUsize,
+        gate: Semaphore,
+    }
+
+    impl MockNotifier {
+        fn new(throttled: usize) -> Self {
+            Self {
+                throttled,
+                calls: AtomicUsize::new(0),
+                gate: Semaphore::new(Semaphore::MAX_PERMITS),
+            }
+        }
+
+        /// A notifier that sends nothing until given permits.
+        fn gated() -> Self {
+            Self {
+                gate: Semaphore::new(0),
+                ..Self::new(0)
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[rocket::async_trait]
+    impl SmsNotifier for MockNotifier {
+        async fn send_sms(&self, _sms: &Sms, _message: String) -> SmsOutcome {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            self.gate.acquire().await.unwrap().forget();
+            if call < self.throttled {
+                SmsOutcome::throttled()
+            } else {
+                SmsOutcome::sent(Some("mock-id".to_string()))
+            }
+        }
+    }
+
+    const REQUEST_ID: RequestId = RequestId {
+        id: 42,
+        client_op_id: None,
+    };
+
+    fn start(
+        capacity: usize,
+        notifier: &Arc<MockNotifier>,
+        deliveries: &Coll<ChallengeDelivery>,
+    ) -> SmsQueue {
+        SmsQueue::start(
+            capacity,
+            1,
+            notifier.clone(),
+            deliveries.clone(),
+            3,
+            Duration::from_millis(1),
+        )
+    }
+
+    fn enqueue(client: &Client, queue: &SmsQueue) -> Result<()> {
+        queue.enqueue(
+            Sms::example(),
+            Sms::example_hmac(client),
+            "Your code".to_string(),
+            REQUEST_ID,
+        )
+    }
+
+    #[backend_test]
+    async fn throttled_messages_retried(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        // Messages throttled a few times are sent in the end.
+        let notifier = Arc::new(MockNotifier::new(2));
+        let queue = start(10, &notifier, &deliveries);
+        enqueue(&client, &queue).unwrap();
+        queue.drain(DRAIN_TIMEOUT).await;
+        assert_eq!(3, notifier.calls());
+        let status = queue.status();
+        assert_eq!(
+            (1, 0, 2),
+            (status.sent, status.failed, status.throttle_retries)
+        );
+        let delivery = deliveries.find_one(None, None).await.unwrap().unwrap();
+        assert_eq!(DeliveryStatus::Sent, delivery.status);
+        assert_eq!(Some("mock-id".to_string()), delivery.message_id);
+
+        assert_eq!(0, status.throttled_out);
+
+        // Messages throttled every time are given up on, without holding it
+        // against the number, however often it happens.
+        let notifier = Arc::new(MockNotifier::new(usize::MAX));
+        let queue = start(10, &notifier, &deliveries);
+        for _ in 0..4 {
+            enqueue(&client, &queue).unwrap();
+        }
+        queue.drain(DRAIN_TIMEOUT).await;
+        assert_eq!(4 * (MAX_THROTTLE_RETRIES as usize + 1), notifier.calls());
+        let status = queue.status();
+        assert_eq!(
+            (0, 4, 4 * u64::from(MAX_THROTTLE_RETRIES), 4),
+            (
+                status.sent,
+                status.failed,
+                status.throttle_retries,
+                status.throttled_out
+            )
+        );
+        let delivery = deliveries.find_one(None, None).await.unwrap().unwrap();
+        assert_eq!(DeliveryStatus::Failed, delivery.status);
+        assert_eq!(0, delivery.failures);
+    }
+
+    #[backend_test]
+    async fn queue_saturation(client: Client, deliveries: Coll<ChallengeDelivery>) {
+        let notifier = Arc::new(MockNotifier::gated());
+        let queue = start(2, &notifier, &deliveries);
+        assert!(queue.is_enabled());
+
+        // Wait for the sender to take the first message, leaving room for two more.
+        enqueue(&client, &queue).unwrap();
+        while notifier.calls() == 0 {
+            time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(0, queue.status().depth);
+        enqueue(&client, &queue).unwrap();
+        enqueue(&client, &queue).unwrap();
+        assert_eq!(2, queue.status().depth);
+
+        // Once full, messages are refused until there is room.
+        let error = enqueue(&client, &queue).unwrap_err();
+        assert_eq!(Status::ServiceUnavailable, error.status());
+        assert_eq!(Some("sms_queue_full"), error.code());
+        assert!(error.retry_after().is_some());
+
+        // Draining sends everything already queued, then refuses more.
+        notifier.gate.add_permits(3);
+        queue.drain(DRAIN_TIMEOUT).await;
+        let status = queue.status();
+        assert_eq!((0, 3, 0), (status.depth, status.sent, status.failed));
+        assert!(enqueue(&client, &queue).is_err());
+    }
+}
@@ -1,46 +1,76 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::time::SystemTime;
 
 use chrono::Utc;
-use mongodb::{bson::doc, error::Error as DbError, options::ReplaceOptions, Client};
+use dre_ip::Serializable;
+use mongodb::{
+    bson::{doc, DateTime},
+    error::Error as DbError,
+    options::{CountOptions, FindOneOptions, ReplaceOptions, UpdateOptions},
+    Client,
+};
 use rocket::{
     futures::{FutureExt, TryStreamExt},
     http::Status,
+    response::{self, Responder},
     serde::json::Json,
-    Route, State,
+    Request, Route, State,
 };
+use serde::Serialize;
 
 use crate::{
+    config::Config,
     error::{Error, Result},
-    logging::RequestId,
+    events::{DomainEvent, EventBus},
+    logging::{ClientOpIdChecked, RequestId},
     model::{
         api::{
-            auth::AuthToken,
-            ballot::{BallotRecall, BallotSpec},
-            receipt::Receipt,
+            auth::{constant_time_eq, AuthToken, CsrfChecked, StepUp},
+            ballot::{
+                BallotRecall, BallotReference, BallotSpec, OwnershipProof, DUPLICATE_CAST_HEADER,
+            },
+            bundle::ReceiptBundle,
+            idempotency::{IdempotencyKey, IdempotencyScope, Idempotent},
+            receipt::{calc_recall_code, PublicReceipt, Receipt},
         },
         common::{
-            allowed_questions::AllowedQuestions,
-            ballot::{Audited, Confirmed, Unconfirmed},
-            election::{ElectionId, ElectionState},
+            allowed_questions::{AllowedQuestions, JoinRequest, Joins},
+            ballot::{Audited, BallotId, Confirmed, Unconfirmed},
+            election::{
+                self, CandidateId, ElectionId, ElectionState, QuestionId, WRITE_IN_CANDIDATE,
+            },
         },
         db::{
-            ballot::{Ballot, NewBallot},
-            candidate_totals::{CandidateTotals, NewCandidateTotals},
-            election::Election,
+            ballot::{AnyBallot, Ballot, NewBallot},
+            board::{append_to_board, BoardEntry, BoardHead},
+            candidate_totals::{TotalsDelta, TotalsFolder},
+            election::{Election, Question},
+            idempotency::IdempotentResponse,
+            rate_limit::{RateLimitEvent, RateLimitObserver},
+            recall_attempt::{RecallAttempt, CODE_RECALL_LIMITER, RECALL_ATTEMPT_WINDOW},
             voter::Voter,
+            voter_election::{ConsentAcceptance, VoterElection},
+        },
+        mongodb::{
+            ballot_counter_id, is_duplicate_key_error, run_transaction, Coll, Counter, Deadline,
+            Id, RequestComment,
         },
-        mongodb::{ballot_counter_id, Coll, Counter, Id},
     },
 };
 
+use super::recent_casts::RecentCasts;
+
 pub fn routes() -> Vec<Route> {
     routes![
         has_joined,
         join_election,
+        eligibility_preview,
         get_allowed,
+        ballot_order,
         cast_ballots,
         audit_ballots,
-        confirm_ballots
+        confirm_ballots,
+        receipt_bundle
     ]
 }
 
@@ -48,28 +78,50 @@ pub fn routes() -> Vec<Route> {
 async fn has_joined(
     token: AuthToken<Voter>,
     election_id: ElectionId,
-    voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
+    request_id: RequestId,
 ) -> Result<Json<bool>> {
-    let voter = voter_by_id(token.id, &voters).await?;
-    Ok(Json(voter.allowed_questions.contains_key(&election_id)))
+    let joined = voter_elections
+        .count_documents(
+            VoterElection::filter(token.id, election_id),
+            CountOptions::for_request(request_id),
+        )
+        .await?;
+    Ok(Json(joined > 0))
 }
 
-#[post("/elections/<election_id>/join", data = "<joins>", format = "json")]
+#[post("/elections/<election_id>/join", data = "<request>", format = "json")]
+#[allow(clippy::too_many_arguments)]
 async fn join_election(
     token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    _client_op_id: ClientOpIdChecked,
     election_id: ElectionId,
-    joins: Json<HashMap<String, HashSet<String>>>,
+    request: Json<JoinRequest>,
     elections: Coll<Election>,
     voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
+    db_client: &State<Client>,
+    config: &State<Config>,
     request_id: RequestId,
 ) -> Result<()> {
+    check_join_limits(&request.joins, config)?;
     let voter = voter_by_id(token.id, &voters).await?;
     info!(
         "  req{} Voter {} joining election {}",
         request_id, token.id, election_id
     );
+    if voter.frozen {
+        return Err(Error::VoterFrozen);
+    }
     // Reject if voter has already joined the election
-    if voter.allowed_questions.contains_key(&election_id) {
+    let joined = voter_elections
+        .count_documents(
+            VoterElection::filter(voter.id, election_id),
+            CountOptions::for_request(request_id),
+        )
+        .await?;
+    if joined > 0 {
         return Err(Error::Status(
             Status::Forbidden,
             format!(
@@ -79,124 +131,115 @@ async fn join_election(
         ));
     }
 
-    let election = active_election_by_id(election_id, &elections).await?;
-
-    // Check that electorates and groups exist and meet mutex requirements
-    for (electorate_name, groups) in &joins.0 {
-        let electorate = election.electorates.get(electorate_name).ok_or_else(|| {
-            Error::not_found(format!("Electorate with name '{}'", electorate_name))
-        })?;
-
-        if electorate.is_mutex && groups.len() > 1 {
-            return Err(Error::Status(
-                Status::UnprocessableEntity,
-                format!(
-                    "Cannot join more than one group in mutex electorate {}",
-                    electorate_name
-                ),
-            ));
-        }
-
-        let invalid_groups: Vec<_> = groups.difference(&electorate.groups).collect();
-        if !invalid_groups.is_empty() {
-            return Err(Error::not_found(format!(
-                "Groups for electorate '{}' with the following names '{:?}'",
-                electorate_name, invalid_groups
-            )));
-        }
-    }
+    let election = joinable_election_by_id(election_id, &elections, request_id).await?;
+    let consent = check_consent(&election, request.consent_version)?;
 
-    // Find questions restricted to those groups
-    let allowed_questions = election
-        .questions
-        .iter()
-        .filter_map(|(question_id, question)| {
-            // The question is allowed if it has no constraints, or if at least one of its constraints is satisfied.
-            let question_allowed = question.constraints.is_empty()
-                || question
-                    .constraints
-                    .iter()
-                    .any(|(electorate_name, groups)| {
-                        let electorate_joined = joins.get(electorate_name);
-                        if let Some(joined) = electorate_joined {
-                            !groups.is_disjoint(joined)
-                        } else {
-                            false
-                        }
-                    });
-            if question_allowed {
-                Some((*question_id, false))
-            } else {
-                None
-            }
-        })
-        .collect::<HashMap<_, _>>();
-    let allowed_questions = AllowedQuestions {
-        confirmed: allowed_questions,
-    };
+    let allowed_questions = AllowedQuestions::for_joins(&election, &request.joins)?;
     let num_allowed = allowed_questions.confirmed.len();
     if num_allowed > 0 {
         debug!("  req{request_id} Voter has {num_allowed} allowed questions");
     } else {
         warn!("  req{request_id} Voter has no allowed questions");
     }
-    let allowed_questions = mongodb::bson::to_bson(&allowed_questions).unwrap(); // Cannot fail.
+    let voter_election = VoterElection {
+        voter_id: voter.id,
+        election_id,
+        allowed_questions,
+        joins: Some(request.0.joins),
+        consent,
+    };
+    join(
+        &voter_election,
+        &election,
+        &elections,
+        &voter_elections,
+        db_client,
+        request_id,
+    )
+    .await
+}
 
-    // Join the election by adding the voter's unanswered questions
-    let allowed_questions_election_id = format!("allowed_questions.{}", election.id);
-    let result = voters
-        .update_one(
-            doc! {
-                "_id": voter.id,
-                // Concurrency: don't match if it was already set.
-                &allowed_questions_election_id: { "$exists": false },
-            },
-            doc! {
-                "$set": {
-                    &allowed_questions_election_id: allowed_questions,
-                }
-            },
-            None,
-        )
-        .await?;
+#[post(
+    "/elections/<election_id>/eligibility-preview",
+    data = "<joins>",
+    format = "json"
+)]
+async fn eligibility_preview(
+    token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    joins: Json<Joins>,
+    elections: Coll<Election>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<AllowedQuestions>> {
+    check_join_limits(&joins, config)?;
+    info!(
+        "  req{} Voter {} previewing eligibility for election {}",
+        request_id, token.id, election_id
+    );
+    let election = joinable_election_by_id(election_id, &elections, request_id).await?;
 
-    match result.matched_count {
-        0 => {
-            // Concurrency error: someone else set the list before us.
-            warn!(
-                "  req{} Rejecting racy update to voter's allowed questions",
-                request_id
-            );
-            Err(Error::Status(
-                Status::Forbidden,
-                format!(
-                    "Voter has already joined election with ID '{}'",
-                    election_id
-                ),
-            ))
-        }
-        1 => Ok(()),
-        _ => unreachable!(),
-    }
+    // Run exactly the same checks as joining, but write nothing.
+    let allowed_questions = AllowedQuestions::for_joins(&election, &joins)?;
+    debug!(
+        "  req{} Voter would have {} allowed questions",
+        request_id,
+        allowed_questions.confirmed.len()
+    );
+
+    Ok(Json(allowed_questions))
 }
 
 #[get("/elections/<election_id>/questions/allowed")]
 async fn get_allowed(
     token: AuthToken<Voter>,
     election_id: ElectionId,
-    voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
+    request_id: RequestId,
 ) -> Result<Json<AllowedQuestions>> {
-    let voter = voter_by_id(token.id, &voters).await?;
     // Find what questions they can still vote for.
-    let allowed = voter
-        .allowed_questions
-        .get(&election_id)
-        .cloned()
-        .unwrap_or_default();
+    let allowed = VoterElection::allowed_questions(
+        &voter_elections,
+        token.id,
+        election_id,
+        FindOneOptions::for_request(request_id),
+    )
+    .await?
+    .unwrap_or_default();
 
     Ok(Json(allowed))
 }
 
+#[get("/elections/<election_id>/questions/<question_id>/ballot-order")]
+async fn ballot_order(
+    token: AuthToken<Voter>,
+    election_id: ElectionId,
+    question_id: QuestionId,
+    elections: Coll<Election>,
+    config: &State<Config>,
+    request_id: RequestId,
+) -> Result<Json<Vec<CandidateId>>> {
+    let election = active_election_by_id(election_id, &elections, request_id).await?;
+    let question = election.questions.get(&question_id).ok_or_else(|| {
+        Error::not_found(format!(
+            "Question with ID '{}' in election '{}'",
+            question_id, election_id
+        ))
+    })?;
+    if !election.metadata.randomise_candidate_order {
+        return Ok(Json(question.candidates.clone()));
+    }
+    let order = election::ballot_order(
+        &question.candidates,
+        config.hmac_secret(),
+        &token.id.to_bytes(),
+        election_id,
+        question_id,
+    );
+    Ok(Json(order))
+}
+
 #[post(
     "/elections/<election_id>/votes/cast",
     data = "<ballot_specs>",
@@ -205,21 +248,39 @@ async fn get_allowed(
 #[allow(clippy::too_many_arguments)]
 async fn cast_ballots(
     token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    _client_op_id: ClientOpIdChecked,
+    step_up: StepUp,
     election_id: ElectionId,
     ballot_specs: Json<Vec<BallotSpec>>,
+    voters: Coll<Voter>,
     elections: Coll<Election>,
     ballots: Coll<NewBallot>,
     counters: Coll<Counter>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
+    idempotency_key: IdempotencyKey,
+    idempotent_responses: Coll<IdempotentResponse>,
     db_client: &State<Client>,
+    config: &State<Config>,
+    events: &State<EventBus>,
+    recent_casts: &State<RecentCasts>,
     request_id: RequestId,
-) -> Result<Json<Vec<Receipt<Unconfirmed>>>> {
-    // Check we actually have ballots to cast.
+) -> Result<CastResponse> {
+    let deadline = Deadline::after(config.vote_request_timeout());
+    // Check we actually have ballots to cast, but not too many.
     if ballot_specs.is_empty() {
         return Err(Error::Status(
             Status::BadRequest,
             "Cannot cast an empty list of ballots".to_string(),
         ));
     }
+    check_request_limit(
+        ballot_specs.len(),
+        config.max_cast_ballots(),
+        "ballots",
+        "max_cast_ballots",
+    )?;
     info!(
         "  req{} Voter {} casting {} ballots for election {}",
         request_id,
@@ -227,96 +288,96 @@ async fn cast_ballots(
         ballot_specs.len(),
         election_id
     );
+    check_not_frozen(token.id, &voters, request_id).await?;
 
-    // Get the election.
-    let election = active_election_by_id(election_id, &elections).await?;
-
-    // Ensure that the questions and candidates exist.
-    for ballot_spec in &*ballot_specs {
-        if let Some(question) = election.questions.get(&ballot_spec.question) {
-            if !question.candidates.contains(&ballot_spec.candidate) {
-                return Err(Error::not_found(format!(
-                    "Candidate '{}' for question '{}'",
-                    ballot_spec.candidate, ballot_spec.question
-                )));
-            }
-        } else {
-            return Err(Error::not_found(format!(
-                "Question '{}'",
-                ballot_spec.question
-            )));
+    // Replay the response to an earlier attempt at this request, if there was one.
+    let idempotency = idempotency_key.scope(token.id, "cast", election_id, &ballot_specs.0);
+    if let Some(scope) = &idempotency {
+        if let Some(response) = scope.replay(&idempotent_responses, request_id).await? {
+            return Ok(CastResponse::new(response));
         }
     }
 
-    // Generate cryptographic ballots.
-    // The scoped block is needed to force `rng` to be dropped before the next `await`.
-    let mut new_ballots = Vec::new();
-    {
-        for ballot_spec in ballot_specs.0 {
-            // Get the yes and no candidates for this ballot.
-            let question = election.questions.get(&ballot_spec.question).unwrap(); // Already checked.
-            let yes_candidate = ballot_spec.candidate; // Already checked that it exists.
-            let no_candidates = question
-                .candidates
-                .iter()
-                .filter(|name| name != &&yes_candidate)
-                .cloned()
-                .collect::<Vec<_>>();
-            // Sanity check.
-            assert_eq!(question.candidates.len() - 1, no_candidates.len());
-
-            // Obtain the next ballot ID.
-            let counter_id = ballot_counter_id(election_id, question.id);
-            let ballot_id = Counter::next(&counters, &counter_id).await?;
+    // Get the election.
+    let election = active_election_by_id(election_id, &elections, request_id).await?;
+    step_up.require(&election, token.id)?;
 
-            // Create the ballot.
-            let ballot = NewBallot::new(
-                ballot_id,
-                question.id,
-                yes_candidate,
-                no_candidates,
-                &election,
-                rand::thread_rng(),
-            )
-            .ok_or_else(|| {
-                Error::Status(
-                    Status::InternalServerError,
-                    format!("Duplicate candidates for question {}", question.id),
-                )
-            })?;
-            debug!(
-                "  req{} Created ballot {} for question {}",
-                request_id, ballot.ballot_id, ballot.question_id
+    // Answer an accidental double-submission with the ballots it already cast,
+    // as long as none of them have since been confirmed or audited. Requests
+    // with an idempotency key are left to the key to decide what is a retry.
+    let recent = if idempotency.is_none() {
+        recent_casts
+            .find(token.id, election_id, &ballot_specs)
+            .await
+    } else {
+        None
+    };
+    if let Some(receipts) = recent {
+        let filter = doc! {
+            "election_id": election_id,
+            "state": Unconfirmed,
+            "$or": receipts
+                .iter()
+                .map(|receipt| doc! {
+                    "question_id": receipt.question_id,
+                    "ballot_id": receipt.ballot_id,
+                })
+                .collect::<Vec<_>>(),
+        };
+        let unconfirmed = ballots
+            .count_documents(filter, CountOptions::for_request(request_id))
+            .await?;
+        if unconfirmed == receipts.len() as u64 {
+            info!(
+                "  req{} Duplicate cast; returning the original ballots",
+                request_id
             );
-            new_ballots.push(ballot);
+            let response = respond(receipts, idempotency, &idempotent_responses, request_id).await;
+            return Ok(CastResponse {
+                receipts: response,
+                duplicate: true,
+            });
         }
     }
 
-    // Insert ballots into DB within a transaction, so this entire endpoint is atomic.
-    let mut session = db_client.start_session(None).await?;
-    session
-        .with_transaction(
-            (&ballots, &new_ballots),
-            |session, (ballots, new_ballots)| {
-                async {
-                    ballots
-                        .insert_many_with_session(new_ballots.iter(), None, session)
-                        .await
-                }
-                .boxed()
+    let mut receipts = cast(
+        &election,
+        ballot_specs.0.clone(),
+        &ballots,
+        &counters,
+        &board_heads,
+        &board_entries,
+        db_client,
+        deadline,
+        request_id,
+    )
+    .await?;
+    // Only the voter sees these, so they're neither signed nor kept with the ballots.
+    for receipt in &mut receipts {
+        receipt.recall_code = Some(calc_recall_code(
+            config.hmac_secret(),
+            token.id,
+            receipt.election_id,
+            receipt.question_id,
+            receipt.ballot_id,
+        ));
+    }
+    for receipt in &receipts {
+        events.emit(
+            DomainEvent::BallotCast {
+                election_id: receipt.election_id,
+                question_id: receipt.question_id,
+                ballot_id: receipt.ballot_id,
             },
-            None,
-        )
-        .await?;
-    trace!("  req{request_id} Committed ballots to database");
-
-    // Return receipts.
-    let receipts = new_ballots
-        .into_iter()
-        .map(|ballot| Receipt::from_ballot(ballot, &election))
-        .collect();
-
-    Ok(Json(receipts))
+            Some(request_id),
+        );
+    }
+    recent_casts
+        .record(token.id, election_id, ballot_specs.0, &receipts)
+        .await;
+    Ok(CastResponse::new(
+        respond(receipts, idempotency, &idempotent_responses, request_id).await,
+    ))
 }
 
 #[post(
@@ -327,17 +388,37 @@ async fn cast_ballots(
 #[allow(clippy::too_many_arguments)]
 async fn audit_ballots(
     token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    _client_op_id: ClientOpIdChecked,
+    step_up: StepUp,
     election_id: ElectionId,
     ballot_recalls: Json<Vec<BallotRecall>>,
+    voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
     elections: Coll<Election>,
     unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
     audited_ballots: Coll<Ballot<Audited>>,
+    recall_attempts: Coll<RecallAttempt>,
+    rate_limit_events: Coll<RateLimitEvent>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
+    idempotency_key: IdempotencyKey,
+    idempotent_responses: Coll<IdempotentResponse>,
     db_client: &State<Client>,
+    config: &State<Config>,
+    events: &State<EventBus>,
     request_id: RequestId,
-) -> Result<Json<Vec<Receipt<Audited>>>> {
+) -> Result<Idempotent<Vec<Receipt<Audited>>>> {
+    let deadline = Deadline::after(config.vote_request_timeout());
+    check_request_limit(
+        ballot_recalls.len(),
+        config.max_recall_ballots(),
+        "ballots",
+        "max_recall_ballots",
+    )?;
     if ballot_recalls.is_empty() {
         info!("  req{} Voter {} auditing no ballots", request_id, token.id);
-        return Ok(Json(Vec::new()));
+        return Ok(Idempotent::new(&Vec::new()));
     }
     info!(
         "  req{} Voter {} auditing {} ballots",
@@ -345,67 +426,55 @@ async fn audit_ballots(
         token.id,
         ballot_recalls.len()
     );
+    check_not_frozen(token.id, &voters, request_id).await?;
 
-    // Get the election.
-    let election = active_election_by_id(election_id, &elections).await?;
-    let ballots = recall_ballots(&ballot_recalls.0, &unconfirmed_ballots, &election)
-        .await?
-        .into_iter()
-        .map(Ballot::audit)
-        .collect::<Vec<_>>();
+    // Replay the response to an earlier attempt at this request, if there was one.
+    let idempotency = idempotency_key.scope(token.id, "audit", election_id, &ballot_recalls.0);
+    if let Some(scope) = &idempotency {
+        if let Some(response) = scope.replay(&idempotent_responses, request_id).await? {
+            return Ok(response);
+        }
+    }
 
-    // Update ballots in DB using a transaction so the whole endpoint is atomic.
-    let mut session = db_client.start_session(None).await?;
-    session
-        .with_transaction(
-            (request_id, &ballots, &audited_ballots),
-            |session, (request_id, ballots, audited_ballots)| {
-                async move {
-                    for ballot in ballots.iter() {
-                        let filter = doc! {
-                            "_id": ballot.internal_id,
-                            // Concurrency: only match if this ballot is still unconfirmed.
-                            "state": Unconfirmed,
-                        };
-                        let result = audited_ballots
-                            .replace_one_with_session(filter, ballot, None, session)
-                            .await?;
-                        match result.matched_count {
-                            0 => {
-                                // Concurrency error: ballot was not unconfirmed.
-                                warn!(
-                                    "  req{} Rejecting racy audit to ballot {}",
-                                    request_id, ballot.ballot_id
-                                );
-                                return Err(DbError::custom(Error::not_found(format!(
-                                    "Ballot with ID '{}'",
-                                    ballot.ballot_id
-                                ))));
-                            }
-                            1 => {}
-                            _ => unreachable!(),
-                        }
-                        debug!(
-                            "  req{} Audited ballot {} for question {}",
-                            request_id, ballot.ballot_id, ballot.question_id
-                        );
-                    }
-                    Ok(())
-                }
-                .boxed()
+    // Get the election.
+    let election = active_election_by_id(election_id, &elections, request_id).await?;
+    step_up.require(&election, token.id)?;
+    check_code_recalls(
+        &ballot_recalls,
+        token.id,
+        election_id,
+        &voter_elections,
+        &recall_attempts,
+        &rate_limit_events,
+        config,
+        request_id,
+    )
+    .await?;
+    let receipts = audit(
+        token.id,
+        &election,
+        &ballot_recalls,
+        &unconfirmed_ballots,
+        &audited_ballots,
+        &board_heads,
+        &board_entries,
+        db_client,
+        config.hmac_secret(),
+        deadline,
+        request_id,
+    )
+    .await?;
+    for receipt in &receipts {
+        events.emit(
+            DomainEvent::BallotAudited {
+                election_id: receipt.election_id,
+                question_id: receipt.question_id,
+                ballot_id: receipt.ballot_id,
             },
-            None,
-        )
-        .await?;
-    trace!("  req{request_id} Committed changes to database");
-
-    // Return receipts.
-    let receipts = ballots
-        .into_iter()
-        .map(|ballot| Receipt::from_ballot(ballot.ballot, &election))
-        .collect();
-
-    Ok(Json(receipts))
+            Some(request_id),
+        );
+    }
+    Ok(respond(receipts, idempotency, &idempotent_responses, request_id).await)
 }
 
 #[post(
@@ -416,22 +485,42 @@ async fn audit_ballots(
 #[allow(clippy::too_many_arguments)]
 async fn confirm_ballots(
     token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    _client_op_id: ClientOpIdChecked,
+    step_up: StepUp,
     election_id: ElectionId,
     ballot_recalls: Json<Vec<BallotRecall>>,
     voters: Coll<Voter>,
+    voter_elections: Coll<VoterElection>,
     elections: Coll<Election>,
     unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
     confirmed_ballots: Coll<Ballot<Confirmed>>,
-    candidate_totals: Coll<CandidateTotals>,
+    totals_deltas: Coll<TotalsDelta>,
+    totals_folder: &State<TotalsFolder>,
+    board_heads: Coll<BoardHead>,
+    board_entries: Coll<BoardEntry>,
+    recall_attempts: Coll<RecallAttempt>,
+    rate_limit_events: Coll<RateLimitEvent>,
+    idempotency_key: IdempotencyKey,
+    idempotent_responses: Coll<IdempotentResponse>,
     db_client: &State<Client>,
+    config: &State<Config>,
+    events: &State<EventBus>,
     request_id: RequestId,
-) -> Result<Json<Vec<Receipt<Confirmed>>>> {
+) -> Result<Idempotent<Vec<Receipt<Confirmed>>>> {
+    let deadline = Deadline::after(config.vote_request_timeout());
+    check_request_limit(
+        ballot_recalls.len(),
+        config.max_recall_ballots(),
+        "ballots",
+        "max_recall_ballots",
+    )?;
     if ballot_recalls.is_empty() {
         info!(
             "  req{} Voter {} confirming no ballots",
             request_id, token.id
         );
-        return Ok(Json(Vec::new()));
+        return Ok(Idempotent::new(&Vec::new()));
     }
     info!(
         "  req{} Voter {} confirming {} ballots",
@@ -439,213 +528,666 @@ async fn confirm_ballots(
         token.id,
         ballot_recalls.len()
     );
+    check_not_frozen(token.id, &voters, request_id).await?;
 
-    let mut voter = voter_by_id(token.id, &voters).await?;
-    // Get the election.
-    let election = active_election_by_id(election_id, &elections).await?;
+    // Replay the response to an earlier attempt at this request, if there was one.
+    let idempotency = idempotency_key.scope(token.id, "confirm", election_id, &ballot_recalls.0);
+    if let Some(scope) = &idempotency {
+        if let Some(response) = scope.replay(&idempotent_responses, request_id).await? {
+            return Ok(response);
+        }
+    }
 
-    // Update DB in a transaction so the whole endpoint is atomic.
-    let mut new_ballots = Vec::with_capacity(ballot_recalls.len());
-    let mut session = db_client.start_session(None).await?;
-    session
-        .with_transaction(
-            (
-                request_id,
-                election_id,
-                &ballot_recalls,
-                &election,
-                &mut voter,
-                &mut new_ballots,
-                &unconfirmed_ballots,
-                &confirmed_ballots,
-                &voters,
-                &candidate_totals,
-            ),
-            |session,
-             (
-                request_id,
-                election_id,
-                ballot_recalls,
-                election,
-                voter,
-                new_ballots,
-                unconfirmed_ballots,
-                confirmed_ballots,
-                voters,
-                candidate_totals,
-            )| {
-                async move {
-                    // The transaction might get retried, but we must consume the ballots each time to
-                    // update the totals. Therefore fetch them each time.
-                    let recalled_ballots =
-                        recall_ballots(&ballot_recalls.0, unconfirmed_ballots, election)
-                            .await
-                            .map_err(DbError::custom)?;
-                    new_ballots.clear();
-
-                    for ballot in recalled_ballots {
-                        // Check that the user is eligible to vote on this question.
-                        let allowed_questions = match voter.allowed_questions.get_mut(election_id) {
-                            Some(allowed) => allowed,
-                            None => {
-                                return Err(DbError::custom(Error::Status(
-                                    Status::BadRequest,
-                                    format!(
-                                        "Voter {} has not yet joined election {}",
-                                        voter.id, election_id
-                                    ),
-                                )));
-                            }
-                        };
-                        if let Some(confirmed) =
-                            allowed_questions.confirmed.get_mut(&ballot.question_id)
-                        {
-                            if *confirmed {
-                                return Err(DbError::custom(Error::Status(
-                                    Status::BadRequest,
-                                    format!(
-                                        "Voter {} has already voted on question {}",
-                                        voter.id, ballot.question_id
-                                    ),
-                                )));
-                            }
+    let voter = voter_by_id(token.id, &voters).await?;
+    // Get the election.
+    let election = active_election_by_id(election_id, &elections, request_id).await?;
+    step_up.require(&election, token.id)?;
+    check_code_recalls(
+        &ballot_recalls,
+        token.id,
+        election_id,
+        &voter_elections,
+        &recall_attempts,
+        &rate_limit_events,
+        config,
+        request_id,
+    )
+    .await?;
 
-                            // All tests passed, the voter can confirm this ballot.
-                            let question_confirmed =
-                                format!("allowed_questions.{}.{}", election_id, ballot.question_id);
-                            let filter = doc! {
-                                "_id": voter.id,
-                                // Concurrency: only match if still false.
-                                &question_confirmed: false,
-                            };
-                            let update = doc! {
-                                "$set": {
-                                    &question_confirmed: true,
-                                }
-                            };
-                            let result = voters
-                                .update_one_with_session(filter, update, None, session)
-                                .await?;
-                            match result.matched_count {
-                                0 => {
-                                    // Concurrency error: question was already confirmed.
-                                    warn!(
-                                        "  req{} Rejecting racy answer to question {}",
-                                        request_id, ballot.question_id
-                                    );
-                                    return Err(DbError::custom(Error::Status(
-                                        Status::BadRequest,
-                                        format!(
-                                            "Voter {} has already voted on question {}",
-                                            voter.id, ballot.question_id
-                                        ),
-                                    )));
-                                }
-                                1 => {}
-                                _ => unreachable!(),
-                            }
-                        } else {
-                            return Err(DbError::custom(Error::Status(
-                                Status::BadRequest,
-                                format!(
-                                    "Voter {} is not allowed to vote on question {}",
-                                    voter.id, ballot.question_id
-                                ),
-                            )));
-                        }
-                        trace!(
-                            "  req{} Marked question {} as confirmed",
-                            request_id,
-                            ballot.question_id
-                        );
+    let receipts = confirm(
+        voter.id,
+        &election,
+        &ballot_recalls,
+        &voter_elections,
+        &unconfirmed_ballots,
+        &confirmed_ballots,
+        &totals_deltas,
+        totals_folder,
+        &board_heads,
+        &board_entries,
+        db_client,
+        config.hmac_secret(),
+        deadline,
+        request_id,
+    )
+    .await?;
+    for receipt in &receipts {
+        events.emit(
+            DomainEvent::BallotConfirmed {
+                election_id: receipt.election_id,
+                question_id: receipt.question_id,
+                ballot_id: receipt.ballot_id,
+            },
+            Some(request_id),
+        );
+    }
+    Ok(respond(receipts, idempotency, &idempotent_responses, request_id).await)
+}
 
-                        // Get candidate totals.
-                        let filter = doc! {
-                            "election_id": *election_id,
-                            "question_id": ballot.question_id,
-                        };
-                        let mut totals = candidate_totals
-                            .find_with_session(filter, None, session)
-                            .await?
-                            .stream(session)
-                            .try_collect::<Vec<_>>()
-                            .await?;
-                        // If the totals don't exist yet, we need to create them.
-                        if totals.len() != ballot.crypto.votes.len() {
-                            assert_eq!(totals.len(), 0);
-                            debug!(
-                                "  req{} Creating candidate totals for question {}",
-                                request_id, ballot.question_id
-                            );
-                            let question = election.questions.get(&ballot.question_id).unwrap();
-                            for candidate in &question.candidates {
-                                totals.push(CandidateTotals {
-                                    id: Id::new(),
-                                    totals: NewCandidateTotals::new(
-                                        *election_id,
-                                        ballot.question_id,
-                                        candidate.clone(),
-                                    ),
-                                });
-                            }
+#[post(
+    "/elections/<election_id>/votes/mine/bundle",
+    data = "<ballot_refs>",
+    format = "json"
+)]
+async fn receipt_bundle(
+    token: AuthToken<Voter>,
+    _csrf: CsrfChecked,
+    election_id: ElectionId,
+    ballot_refs: Json<Vec<BallotReference>>,
+    elections: Coll<Election>,
+    ballots: Coll<AnyBallot>,
+    request_id: RequestId,
+) -> Result<Json<ReceiptBundle>> {
+    // Check we actually have ballots to bundle.
+    if ballot_refs.is_empty() {
+        return Err(Error::Status(
+            Status::BadRequest,
+            "Cannot bundle an empty list of ballots".to_string(),
+        ));
+    }
+    info!(
+        "  req{} Voter {} bundling {} receipts",
+        request_id,
+        token.id,
+        ballot_refs.len()
+    );
+
+    // Get the election.
+    let election = finished_election_by_id(election_id, &elections, request_id).await?;
+
+    // Get the current receipt for each ballot.
+    let mut receipts = Vec::with_capacity(ballot_refs.len());
+    for reference in ballot_refs.iter() {
+        let filter = doc! {
+            "ballot_id": reference.ballot_id,
+            "election_id": election.id,
+            "question_id": reference.question_id,
+        };
+        let receipt = ballots
+            .find_one(filter, FindOneOptions::for_request(request_id))
+            .await?
+            .and_then(|ballot| PublicReceipt::from_ballot(ballot, &election))
+            .ok_or_else(|| Error::not_found(format!("Ballot with ID '{}'", reference.ballot_id)))?;
+        receipts.push(receipt);
+    }
+
+    Ok(Json(ReceiptBundle::new(&election, receipts)))
+}
+
+/// The longest name that may be written in, in characters.
+const MAX_WRITE_IN_NAME_LENGTH: usize = 100;
+
+/// Cast ballots in the given active election, returning their receipts.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn cast(
+    election: &Election,
+    ballot_specs: Vec<BallotSpec>,
+    ballots: &Coll<NewBallot>,
+    counters: &Coll<Counter>,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    db_client: &Client,
+    deadline: Deadline,
+    request_id: RequestId,
+) -> Result<Vec<Receipt<Unconfirmed>>> {
+    // Ensure that the questions and candidates exist.
+    for ballot_spec in &ballot_specs {
+        if let Some(question) = election.questions.get(&ballot_spec.question) {
+            if question.is_closed() {
+                return Err(closed_question(question.id));
+            }
+            if !question.candidates.contains(&ballot_spec.candidate) {
+                return Err(Error::not_found(format!(
+                    "Candidate '{}' for question '{}'",
+                    ballot_spec.candidate, ballot_spec.question
+                )));
+            }
+            check_write_in_name(ballot_spec)?;
+            // Bad election data; we can't construct a valid ballot.
+            let unique = question.candidates.iter().collect::<HashSet<_>>();
+            if unique.len() != question.candidates.len() {
+                return Err(Error::Status(
+                    Status::UnprocessableEntity,
+                    format!("Duplicate candidates for question {}", question.id),
+                ));
+            }
+        } else {
+            return Err(Error::not_found(format!(
+                "Question '{}'",
+                ballot_spec.question
+            )));
+        }
+    }
+
+    // Generate cryptographic ballots.
+    // The scoped block is needed to force `rng` to be dropped before the next `await`.
+    let mut new_ballots = Vec::new();
+    {
+        for ballot_spec in ballot_specs {
+            // Get the yes and no candidates for this ballot.
+            let question = election.questions.get(&ballot_spec.question).unwrap(); // Already checked.
+            let yes_candidate = ballot_spec.candidate; // Already checked that it exists.
+            let no_candidates = question
+                .candidates
+                .iter()
+                .filter(|name| name != &&yes_candidate)
+                .cloned()
+                .collect::<Vec<_>>();
+            // Sanity check.
+            assert_eq!(question.candidates.len() - 1, no_candidates.len());
+
+            // Obtain the next ballot ID.
+            let counter_id = ballot_counter_id(election.id, question.id);
+            let ballot_id = Counter::next(counters, &counter_id).await?;
+
+            // Create the ballot.
+            let mut ballot = NewBallot::new(
+                ballot_id,
+                question.id,
+                yes_candidate,
+                no_candidates,
+                election,
+                rand::thread_rng(),
+            )
+            .ok_or_else(|| {
+                Error::Status(
+                    Status::UnprocessableEntity,
+                    format!("Duplicate candidates for question {}", question.id),
+                )
+            })?;
+            ballot.write_in_name = ballot_spec
+                .write_in_name
+                .map(|name| name.trim().to_string());
+            debug!(
+                "  req{} Created ballot {} for question {}",
+                request_id, ballot.ballot_id, ballot.question_id
+            );
+            new_ballots.push(ballot);
+        }
+    }
+
+    // Insert ballots into DB within a transaction, so this entire endpoint is atomic.
+    let mut session = db_client.start_session(None).await?;
+    run_transaction(
+        &mut session,
+        (
+            request_id,
+            ballots,
+            &mut new_ballots,
+            board_heads,
+            board_entries,
+        ),
+        |session, (request_id, ballots, new_ballots, board_heads, board_entries)| {
+            async move {
+                append_to_board(
+                    new_ballots.iter_mut(),
+                    board_heads,
+                    board_entries,
+                    session,
+                    Some(*request_id),
+                )
+                .await?;
+                ballots
+                    .insert_many_with_session(new_ballots.iter(), None, session)
+                    .await?;
+                Ok(())
+            }
+            .boxed()
+        },
+        deadline,
+        request_id,
+    )
+    .await?;
+    trace!("  req{request_id} Committed ballots to database");
+
+    // Return receipts.
+    let receipts = new_ballots
+        .into_iter()
+        .map(|ballot| Receipt::from_ballot(ballot, election))
+        .collect::<Vec<_>>();
+
+    Ok(receipts)
+}
+
+/// Check that a ballot has a name written in if and only if it is for the
+/// write-in candidate, and that the name is neither blank nor too long.
+fn check_write_in_name(ballot_spec: &BallotSpec) -> Result<()> {
+    let is_write_in = ballot_spec.candidate == WRITE_IN_CANDIDATE;
+    let problem = match &ballot_spec.write_in_name {
+        None if is_write_in => "needs a name written in",
+        None => return Ok(()),
+        Some(_) if !is_write_in => "cannot have a name written in, as it is not a write-in",
+        Some(name) if name.trim().is_empty() => "has a blank name written in",
+        Some(name) if name.trim().chars().count() > MAX_WRITE_IN_NAME_LENGTH => {
+            "has a name written in that is too long"
+        }
+        Some(name) if name.chars().any(char::is_control) => {
+            "has a name written in with control characters"
+        }
+        Some(_) => return Ok(()),
+    };
+    Err(Error::Status(
+        Status::UnprocessableEntity,
+        format!("Ballot for question {} {}", ballot_spec.question, problem),
+    ))
+}
+
+/// Audit the given ballots for a voter in an active election, returning their
+/// receipts.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn audit(
+    voter_id: Id,
+    election: &Election,
+    ballot_recalls: &[BallotRecall],
+    unconfirmed_ballots: &Coll<Ballot<Unconfirmed>>,
+    audited_ballots: &Coll<Ballot<Audited>>,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    db_client: &Client,
+    hmac_secret: &[u8],
+    deadline: Deadline,
+    request_id: RequestId,
+) -> Result<Vec<Receipt<Audited>>> {
+    let mut ballots = recall_ballots(
+        voter_id,
+        ballot_recalls,
+        unconfirmed_ballots,
+        election,
+        hmac_secret,
+        request_id,
+    )
+    .await?
+    .into_iter()
+    .map(Ballot::audit)
+    .collect::<Vec<_>>();
+
+    // Update ballots in DB using a transaction so the whole endpoint is atomic.
+    let mut session = db_client.start_session(None).await?;
+    run_transaction(
+        &mut session,
+        (
+            request_id,
+            &mut ballots,
+            audited_ballots,
+            board_heads,
+            board_entries,
+        ),
+        |session, (request_id, ballots, audited_ballots, board_heads, board_entries)| {
+            async move {
+                append_to_board(
+                    ballots.iter_mut().map(|ballot| &mut ballot.ballot),
+                    board_heads,
+                    board_entries,
+                    session,
+                    Some(*request_id),
+                )
+                .await?;
+                for ballot in ballots.iter() {
+                    let filter = doc! {
+                        "_id": ballot.internal_id,
+                        // Concurrency: only match if this ballot is still unconfirmed.
+                        "state": Unconfirmed,
+                    };
+                    let options = ReplaceOptions::for_request(*request_id);
+                    let result = audited_ballots
+                        .replace_one_with_session(filter, ballot, options, session)
+                        .await?;
+                    match result.matched_count {
+                        0 => {
+                            // Concurrency error: ballot was not unconfirmed.
+                            warn!(
+                                "  req{} Rejecting racy audit to ballot {}",
+                                request_id, ballot.ballot_id
+                            );
+                            return Err(DbError::custom(Error::not_found(format!(
+                                "Ballot with ID '{}'",
+                                ballot.ballot_id
+                            ))));
+                        }
+                        1 => {}
+                        _ => unreachable!(),
+                    }
+                    debug!(
+                        "  req{} Audited ballot {} for question {}",
+                        request_id, ballot.ballot_id, ballot.question_id
+                    );
+                }
+                Ok(())
+            }
+            .boxed()
+        },
+        deadline,
+        request_id,
+    )
+    .await?;
+    trace!("  req{request_id} Committed changes to database");
+
+    // Return receipts.
+    let receipts = ballots
+        .into_iter()
+        .map(|ballot| Receipt::from_ballot(ballot.ballot, election))
+        .collect::<Vec<_>>();
+
+    Ok(receipts)
+}
+
+/// Confirm the given ballots for a voter in an active election, counting their
+/// votes and returning their receipts.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn confirm(
+    voter_id: Id,
+    election: &Election,
+    ballot_recalls: &[BallotRecall],
+    voter_elections: &Coll<VoterElection>,
+    unconfirmed_ballots: &Coll<Ballot<Unconfirmed>>,
+    confirmed_ballots: &Coll<Ballot<Confirmed>>,
+    totals_deltas: &Coll<TotalsDelta>,
+    totals_folder: &TotalsFolder,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    db_client: &Client,
+    hmac_secret: &[u8],
+    deadline: Deadline,
+    request_id: RequestId,
+) -> Result<Vec<Receipt<Confirmed>>> {
+    let election_id = election.id;
+    let allowed_questions = VoterElection::allowed_questions(
+        voter_elections,
+        voter_id,
+        election_id,
+        FindOneOptions::for_request(request_id),
+    )
+    .await?;
+
+    // Update DB in a transaction so the whole endpoint is atomic.
+    let mut new_ballots = Vec::with_capacity(ballot_recalls.len());
+    let mut session = db_client.start_session(None).await?;
+    run_transaction(
+        &mut session,
+        (
+            request_id,
+            election_id,
+            ballot_recalls,
+            election,
+            hmac_secret,
+            &allowed_questions,
+            &mut new_ballots,
+            unconfirmed_ballots,
+            confirmed_ballots,
+            voter_elections,
+            totals_deltas,
+            board_heads,
+            board_entries,
+        ),
+        |session,
+         (
+            request_id,
+            election_id,
+            ballot_recalls,
+            election,
+            hmac_secret,
+            allowed_questions,
+            new_ballots,
+            unconfirmed_ballots,
+            confirmed_ballots,
+            voter_elections,
+            totals_deltas,
+            board_heads,
+            board_entries,
+        )| {
+            async move {
+                // The transaction might get retried, but we must consume the ballots each time to
+                // update the totals. Therefore fetch them each time.
+                let recalled_ballots = recall_ballots(
+                    voter_id,
+                    ballot_recalls,
+                    unconfirmed_ballots,
+                    election,
+                    hmac_secret,
+                    *request_id,
+                )
+                .await
+                .map_err(DbError::custom)?;
+                new_ballots.clear();
+
+                for ballot in recalled_ballots {
+                    // Check that the user is eligible to vote on this question.
+                    let allowed_questions = match allowed_questions {
+                        Some(allowed) => allowed,
+                        None => {
+                            return Err(DbError::custom(hidden_ballot(
+                                ballot.ballot_id,
+                                &format!(
+                                    "voter {} has not joined election {}",
+                                    voter_id, election_id
+                                ),
+                                *request_id,
+                            )));
+                        }
+                    };
+                    if let Some(confirmed) = allowed_questions.confirmed.get(&ballot.question_id) {
+                        if *confirmed {
+                            return Err(DbError::custom(Error::Status(
+                                Status::BadRequest,
+                                format!(
+                                    "Voter {} has already voted on question {}",
+                                    voter_id, ballot.question_id
+                                ),
+                            )));
                         }
-                        assert_eq!(totals.len(), ballot.crypto.votes.len());
-                        // Convert to hashmap.
-                        let mut totals_map = totals
-                            .iter_mut()
-                            .map(|t| (t.candidate_name.clone(), &mut t.crypto))
-                            .collect::<HashMap<_, _>>();
-
-                        // Confirm ballot.
-                        let confirmed = ballot.confirm(&mut totals_map);
-                        let filter = doc! {
-                            "_id": confirmed.internal_id,
-                            // Concurrency: only match if this ballot is still unconfirmed.
-                            "state": Unconfirmed,
+
+                        // All tests passed, the voter can confirm this ballot.
+                        let question_confirmed = VoterElection::confirmed_field(ballot.question_id);
+                        let mut filter = VoterElection::filter(voter_id, *election_id);
+                        // Concurrency: only match if still false.
+                        filter.insert(&question_confirmed, false);
+                        let update = doc! {
+                            "$set": {
+                                &question_confirmed: true,
+                            }
                         };
-                        let result = confirmed_ballots
-                            .replace_one_with_session(filter, &confirmed, None, session)
+                        let options = UpdateOptions::for_request(*request_id);
+                        let result = voter_elections
+                            .update_one_with_session(filter, update, options, session)
                             .await?;
                         match result.matched_count {
                             0 => {
-                                // Concurrency error: ballot was not unconfirmed.
+                                // Concurrency error: question was already confirmed.
                                 warn!(
-                                    "  req{} Rejecting racy confirm to ballot {}",
-                                    request_id, confirmed.ballot_id
+                                    "  req{} Rejecting racy answer to question {}",
+                                    request_id, ballot.question_id
                                 );
-                                return Err(DbError::custom(Error::not_found(format!(
-                                    "Ballot with ID '{}'",
-                                    confirmed.ballot_id
-                                ))));
+                                return Err(DbError::custom(Error::Status(
+                                    Status::BadRequest,
+                                    format!(
+                                        "Voter {} has already voted on question {}",
+                                        voter_id, ballot.question_id
+                                    ),
+                                )));
                             }
                             1 => {}
                             _ => unreachable!(),
                         }
-                        debug!(
-                            "  req{} Confirmed ballot {} for question {}",
-                            request_id, confirmed.ballot_id, confirmed.question_id
-                        );
+                    } else {
+                        return Err(DbError::custom(hidden_ballot(
+                            ballot.ballot_id,
+                            &format!(
+                                "voter {} may not answer question {}",
+                                voter_id, ballot.question_id
+                            ),
+                            *request_id,
+                        )));
+                    }
+                    trace!(
+                        "  req{} Marked question {} as confirmed",
+                        request_id,
+                        ballot.question_id
+                    );
+
+                    // Confirm ballot; it is written once stamped by the bulletin board.
+                    // Its votes are recorded as a delta, which is folded into the candidate
+                    // totals in the background so that it does not conflict with other
+                    // confirmations of the same question.
+                    let question = election.questions.get(&ballot.question_id).unwrap();
+                    let (confirmed, delta) = TotalsDelta::confirm(ballot, &question.candidates);
+                    totals_deltas
+                        .insert_one_with_session(delta, None, session)
+                        .await?;
+                    trace!("  req{request_id} Recorded totals delta");
+
+                    new_ballots.push(confirmed);
+                }
+                append_to_board(
+                    new_ballots.iter_mut().map(|ballot| &mut ballot.ballot),
+                    board_heads,
+                    board_entries,
+                    session,
+                    Some(*request_id),
+                )
+                .await?;
 
-                        // Write updated candidate totals.
-                        for t in totals {
-                            let filter = doc! {
-                                // Concurrency: we rely on the unique index created across the following three
-                                // attributes to ensure we don't accidentally upsert multiple fresh copies in
-                                // parallel.
-                                "election_id": *election_id,
-                                "question_id": confirmed.question_id,
-                                "candidate_name": &t.candidate_name,
-                            };
-                            let options = ReplaceOptions::builder().upsert(true).build();
-                            let result = candidate_totals
-                                .replace_one_with_session(filter, t, options, session)
-                                .await?;
-                            assert!(result.modified_count == 1 || result.upserted_id.is_some());
+                // Write confirmed ballots.
+                for confirmed in new_ballots.iter() {
+                    let filter = doc! {
+                        "_id": confirmed.internal_id,
+                        // Concurrency: only match if this ballot is still unconfirmed.
+                        "state": Unconfirmed,
+                    };
+                    let options = ReplaceOptions::for_request(*request_id);
+                    let result = confirmed_ballots
+                        .replace_one_with_session(filter, confirmed, options, session)
+                        .await?;
+                    match result.matched_count {
+                        0 => {
+                            // Concurrency error: ballot was not unconfirmed.
+                            warn!(
+                                "  req{} Rejecting racy confirm to ballot {}",
+                                request_id, confirmed.ballot_id
+                            );
+                            return Err(DbError::custom(Error::not_found(format!(
+                                "Ballot with ID '{}'",
+                                confirmed.ballot_id
+                            ))));
                         }
-                        trace!("  req{request_id} Wrote new candidate totals");
+                        1 => {}
+                        _ => unreachable!(),
+                    }
+                    debug!(
+                        "  req{} Confirmed ballot {} for question {}",
+                        request_id, confirmed.ballot_id, confirmed.question_id
+                    );
+                }
+                Ok(())
+            }
+            .boxed()
+        },
+        deadline,
+        request_id,
+    )
+    .await?;
+    trace!("  req{request_id} Committed changes to database");
+
+    // Have the new deltas folded into the candidate totals in the background.
+    // Until they are, readers of the totals include them.
+    totals_folder.request(
+        election_id,
+        new_ballots.iter().map(|ballot| ballot.question_id),
+    );
+
+    // Return receipts.
+    let receipts = new_ballots
+        .into_iter()
+        .map(|ballot| Receipt::from_ballot(ballot.ballot, election))
+        .collect::<Vec<_>>();
+
+    Ok(receipts)
+}
+
+/// Join a voter to an election by recording their membership of it.
+/// This counts towards the election's cap, failing with 409 if it is full.
+pub(super) async fn join(
+    voter_election: &VoterElection,
+    election: &Election,
+    elections: &Coll<Election>,
+    voter_elections: &Coll<VoterElection>,
+    db_client: &Client,
+    request_id: RequestId,
+) -> Result<()> {
+    // Join the election by recording the voter's unanswered questions, counting
+    // them towards the election's cap in the same transaction.
+    let mut session = db_client.start_session(None).await?;
+    session
+        .with_transaction(
+            (
+                request_id,
+                election,
+                voter_election,
+                elections,
+                voter_elections,
+            ),
+            |session, (request_id, election, voter_election, elections, voter_elections)| {
+                async move {
+                    let result = elections
+                        .update_one_with_session(
+                            doc! {
+                                "_id": election.id,
+                                // Concurrency: don't match if the last place was just taken.
+                                "$or": [
+                                    { "max_voters": null },
+                                    { "$expr": { "$lt": ["$joined_count", "$max_voters"] } },
+                                ],
+                            },
+                            doc! { "$inc": { "joined_count": 1 } },
+                            None,
+                            session,
+                        )
+                        .await?;
+                    if result.matched_count == 0 {
+                        warn!("  req{} Election {} is full", request_id, election.id);
+                        return Err(DbError::custom(Error::Status(
+                            Status::Conflict,
+                            format!("Election with ID '{}' is full", election.id),
+                        )));
+                    }
 
-                        new_ballots.push(confirmed);
+                    let result = voter_elections
+                        .insert_one_with_session(*voter_election, None, session)
+                        .await;
+                    if is_duplicate_key_error(result.as_ref()) {
+                        // Concurrency error: someone else joined before us.
+                        warn!(
+                            "  req{} Rejecting racy update to voter's allowed questions",
+                            request_id
+                        );
+                        return Err(DbError::custom(Error::Status(
+                            Status::Forbidden,
+                            format!(
+                                "Voter has already joined election with ID '{}'",
+                                election.id
+                            ),
+                        )));
                     }
+                    result?;
                     Ok(())
                 }
                 .boxed()
@@ -653,15 +1195,104 @@ async fn confirm_ballots(
             None,
         )
         .await?;
-    trace!("  req{request_id} Committed changes to database");
 
-    // Return receipts.
-    let receipts = new_ballots
-        .into_iter()
-        .map(|ballot| Receipt::from_ballot(ballot.ballot, &election))
-        .collect();
+    Ok(())
+}
+
+/// The response to casting ballots, marked with the [`DUPLICATE_CAST_HEADER`]
+/// if it repeats the receipts of ballots cast moments before.
+struct CastResponse {
+    receipts: Idempotent<Vec<Receipt<Unconfirmed>>>,
+    duplicate: bool,
+}
+
+impl CastResponse {
+    fn new(receipts: Idempotent<Vec<Receipt<Unconfirmed>>>) -> Self {
+        Self {
+            receipts,
+            duplicate: false,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for CastResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = self.receipts.respond_to(req)?;
+        if self.duplicate {
+            response.set_raw_header(DUPLICATE_CAST_HEADER, "true");
+        }
+        Ok(response)
+    }
+}
+
+/// Respond to a request that has succeeded, storing the response for replay
+/// if the request had an idempotency key.
+/// This must only be called once the request's changes have been committed.
+async fn respond<T: Serialize>(
+    response: T,
+    idempotency: Option<IdempotencyScope>,
+    idempotent_responses: &Coll<IdempotentResponse>,
+    request_id: RequestId,
+) -> Idempotent<T> {
+    let response = Idempotent::new(&response);
+    if let Some(scope) = idempotency {
+        scope
+            .store(&response, idempotent_responses, request_id)
+            .await;
+    }
+    response
+}
+
+/// Reject a request carrying more than `limit` of something, naming the config
+/// option that sets the limit.
+fn check_request_limit(count: usize, limit: usize, what: &str, option: &str) -> Result<()> {
+    if count > limit {
+        return Err(Error::Status(
+            Status::PayloadTooLarge,
+            format!("Too many {what} in one request: the limit is {limit} (`{option}`)"),
+        ));
+    }
+    Ok(())
+}
 
-    Ok(Json(receipts))
+/// Reject joining with more electorates or groups than configured.
+fn check_join_limits(joins: &Joins, config: &Config) -> Result<()> {
+    check_request_limit(
+        joins.len(),
+        config.max_join_electorates(),
+        "electorates",
+        "max_join_electorates",
+    )?;
+    let groups = joins.values().map(HashSet::len).sum();
+    check_request_limit(
+        groups,
+        config.max_join_groups(),
+        "groups",
+        "max_join_groups",
+    )
+}
+
+/// Check that a joining voter has accepted the current version of the
+/// election's consent text, if it has one, returning the acceptance to record.
+/// Fails with [`Error::ConsentRequired`] if they have not.
+fn check_consent(
+    election: &Election,
+    accepted_version: Option<u32>,
+) -> Result<Option<ConsentAcceptance>> {
+    let Some(text) = &election.metadata.consent_text else {
+        return Ok(None);
+    };
+    let version = election.metadata.consent_version;
+    if accepted_version != Some(version) {
+        return Err(Error::ConsentRequired {
+            text: text.clone(),
+            version,
+        });
+    }
+    Ok(Some(ConsentAcceptance {
+        version,
+        accepted_at: DateTime::now(),
+    }))
 }
 
 async fn voter_by_id(voter_id: Id, voters: &Coll<Voter>) -> Result<Voter> {
@@ -671,35 +1302,229 @@ async fn voter_by_id(voter_id: Id, voters: &Coll<Voter>) -> Result<Voter> {
         .ok_or_else(|| Error::not_found(format!("Voter with ID {}", voter_id)))
 }
 
+/// Fail with [`Error::VoterFrozen`] if an admin has frozen the voter.
+async fn check_not_frozen(voter_id: Id, voters: &Coll<Voter>, request_id: RequestId) -> Result<()> {
+    let frozen = voters
+        .count_documents(
+            doc! { "_id": *voter_id, "frozen": true },
+            CountOptions::for_request(request_id),
+        )
+        .await?;
+    if frozen > 0 {
+        return Err(Error::VoterFrozen);
+    }
+    Ok(())
+}
+
 /// Return an active Election from the database via ID lookup.
-/// An active election is finalised and within its start and end times.
+/// See [`Election::is_votable_at`] for what makes an election active.
+/// Fails with [`Error::Suspended`] if voting in the election is suspended.
 async fn active_election_by_id(
     election_id: ElectionId,
     elections: &Coll<Election>,
+    request_id: RequestId,
+) -> Result<Election> {
+    open_election_by_id(
+        election_id,
+        elections,
+        Election::is_votable_at,
+        "Active",
+        request_id,
+    )
+    .await
+}
+
+/// Return an Election that voters can join from the database via ID lookup.
+/// This is an active election, or one that has yet to start but allows voters
+/// to join early; see [`Election::is_joinable_at`].
+/// Fails with [`Error::Suspended`] if voting in the election is suspended.
+async fn joinable_election_by_id(
+    election_id: ElectionId,
+    elections: &Coll<Election>,
+    request_id: RequestId,
+) -> Result<Election> {
+    open_election_by_id(
+        election_id,
+        elections,
+        Election::is_joinable_at,
+        "Joinable",
+        request_id,
+    )
+    .await
+}
+
+/// Return an Election from the database via ID lookup, if it is open to voters
+/// now by the given predicate, described by `kind` in the error if not.
+async fn open_election_by_id(
+    election_id: ElectionId,
+    elections: &Coll<Election>,
+    is_open: impl Fn(&Election, chrono::DateTime<Utc>) -> bool,
+    kind: &str,
+    request_id: RequestId,
 ) -> Result<Election> {
     let now = Utc::now();
 
-    let is_active = doc! {
+    // Narrow the search as far as both predicates allow; the given one has the
+    // final say.
+    let filter = doc! {
         "_id": election_id,
         "state": ElectionState::Published,
-        "start_time": { "$lte": now },
         "end_time": { "$gt": now },
+        "rehearsal": {"$ne": true},
     };
 
-    elections
-        .find_one(is_active, None)
+    let election = elections
+        .find_one(filter, FindOneOptions::for_request(request_id))
         .await?
-        .ok_or_else(|| Error::not_found(format!("Active election with ID '{}'", election_id)))
+        .filter(|election| is_open(election, now))
+        .ok_or_else(|| Error::not_found(format!("{} election with ID '{}'", kind, election_id)))?;
+
+    if election.metadata.suspended {
+        return Err(Error::Suspended(election_id));
+    }
+    Ok(election)
 }
 
-/// Get the given unconfirmed ballots, verifying their signatures.
-async fn recall_ballots(
-    ballot_recalls: &[BallotRecall],
-    unconfirmed_ballots: &Coll<Ballot<Unconfirmed>>,
-    election: &Election,
+/// Return a finished Election from the database via ID lookup.
+/// A finished election is published or archived and past its end time.
+async fn finished_election_by_id(
+    election_id: ElectionId,
+    elections: &Coll<Election>,
+    request_id: RequestId,
+) -> Result<Election> {
+    let is_finished = doc! {
+        "_id": election_id,
+        "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+        "end_time": { "$lte": Utc::now() },
+    };
+
+    elections
+        .find_one(is_finished, FindOneOptions::for_request(request_id))
+        .await?
+        .ok_or_else(|| Error::not_found(format!("Finished election with ID '{}'", election_id)))
+}
+
+/// Check that the voter may recall the given ballots by recall code.
+/// Codes are much shorter than signatures, so the voter must also be able to
+/// answer each such ballot's question, and may only recall
+/// [`Config::max_code_recalls`] ballots by code per hour. Every code recall
+/// counts towards this, whether or not the code turns out to be right.
+///
+/// Fails with 404 if the voter cannot answer a question, indistinguishably
+/// from the ballot not existing, or 429 if they have made too many code
+/// recalls, which is reported to the given observer. Recalls by signature are
+/// not checked.
+#[allow(clippy::too_many_arguments)]
+async fn check_code_recalls(
+    ballot_recalls: &[BallotRecall],
+    voter_id: Id,
+    election_id: ElectionId,
+    voter_elections: &Coll<VoterElection>,
+    recall_attempts: &Coll<RecallAttempt>,
+    observer: &impl RateLimitObserver,
+    config: &Config,
+    request_id: RequestId,
+) -> Result<()> {
+    let code_recalls = ballot_recalls
+        .iter()
+        .filter(|recall| matches!(recall.proof, OwnershipProof::RecallCode(_)))
+        .collect::<Vec<_>>();
+    if code_recalls.is_empty() {
+        return Ok(());
+    }
+
+    let allowed_questions = VoterElection::allowed_questions(
+        voter_elections,
+        voter_id,
+        election_id,
+        FindOneOptions::for_request(request_id),
+    )
+    .await?;
+    for recall in &code_recalls {
+        let allowed = allowed_questions
+            .as_ref()
+            .is_some_and(|allowed| allowed.confirmed.contains_key(&recall.question_id));
+        if !allowed {
+            return Err(hidden_ballot(
+                recall.ballot_id,
+                &format!(
+                    "voter {} may not answer question {}",
+                    voter_id, recall.question_id
+                ),
+                request_id,
+            ));
+        }
+    }
+
+    let since = DateTime::from_system_time(SystemTime::now() - RECALL_ATTEMPT_WINDOW);
+    let recent = recall_attempts
+        .count_documents(
+            doc! { "voter_id": voter_id, "created_at": { "$gt": since } },
+            CountOptions::for_request(request_id),
+        )
+        .await?;
+    let limit = config.max_code_recalls();
+    if recent as usize + code_recalls.len() > limit {
+        warn!(
+            "  req{} Voter {} exceeded the recall code limit",
+            request_id, voter_id
+        );
+        observer
+            .record(CODE_RECALL_LIMITER, &voter_id.to_string(), request_id)
+            .await;
+        return Err(Error::Status(
+            Status::TooManyRequests,
+            format!(
+                "Too many ballots recalled by recall code: the limit is {limit} per hour (`max_code_recalls`)"
+            ),
+        ));
+    }
+
+    let now = DateTime::now();
+    let attempts = code_recalls.iter().map(|_| RecallAttempt {
+        voter_id,
+        election_id,
+        created_at: now,
+    });
+    recall_attempts.insert_many(attempts, None).await?;
+    Ok(())
+}
+
+/// The error for voting on a question that has closed before the end of its
+/// election, matching that for an election that has ended.
+fn closed_question(question_id: QuestionId) -> Error {
+    Error::not_found(format!("Open question with ID '{}'", question_id))
+}
+
+/// The error for recalling a ballot that the voter cannot act on, for whatever
+/// reason. It is the same whether the ballot is missing, its ownership proof is
+/// wrong, or the voter may not answer its question, so that it reveals nothing
+/// about which ballots exist or which elections the voter has joined. The real
+/// reason is only logged.
+fn hidden_ballot(ballot_id: BallotId, reason: &str, request_id: RequestId) -> Error {
+    warn!("  req{request_id} Refusing to recall ballot {ballot_id}: {reason}");
+    Error::not_found(format!("Ballot with ID '{}'", ballot_id))
+}
+
+/// Get the given unconfirmed ballots for a voter, verifying their ownership
+/// proofs.
+async fn recall_ballots(
+    voter_id: Id,
+    ballot_recalls: &[BallotRecall],
+    unconfirmed_ballots: &Coll<Ballot<Unconfirmed>>,
+    election: &Election,
+    hmac_secret: &[u8],
+    request_id: RequestId,
 ) -> Result<Vec<Ballot<Unconfirmed>>> {
     let mut ballots = Vec::with_capacity(ballot_recalls.len());
     for recall in ballot_recalls {
+        let closed = election
+            .questions
+            .get(&recall.question_id)
+            .is_some_and(Question::is_closed);
+        if closed {
+            return Err(closed_question(recall.question_id));
+        }
         let filter = doc! {
             "ballot_id": recall.ballot_id,
             "election_id": election.id,
@@ -707,17 +1532,39 @@ async fn recall_ballots(
             "state": Unconfirmed,
         };
         let ballot = unconfirmed_ballots
-            .find_one(filter, None)
+            .find_one(filter, FindOneOptions::for_request(request_id))
             .await?
-            .filter(|ballot| {
-                // Verify ownership of the ballot. If this fails, we return
-                // an error indistinguishable from the ballot ID not existing,
-                // so an attacker cannot learn anything about valid ballot IDs.
+            .ok_or_else(|| {
+                hidden_ballot(recall.ballot_id, "no such unconfirmed ballot", request_id)
+            })?;
+        // Verify ownership of the ballot. If this fails, we return an error
+        // indistinguishable from the ballot ID not existing, so an attacker
+        // cannot learn anything about valid ballot IDs. The comparisons take
+        // the same time however much of the proof is right.
+        let owned = match &recall.proof {
+            OwnershipProof::Signature(signature) => {
                 let true_signature =
                     Receipt::from_ballot(ballot.ballot.clone(), election).signature;
-                true_signature == recall.signature
-            })
-            .ok_or_else(|| Error::not_found(format!("Ballot with ID '{}'", recall.ballot_id)))?;
+                constant_time_eq(&true_signature.to_bytes(), &signature.to_bytes())
+            }
+            OwnershipProof::RecallCode(code) => {
+                let true_code = calc_recall_code(
+                    hmac_secret,
+                    voter_id,
+                    election.id,
+                    ballot.question_id,
+                    ballot.ballot_id,
+                );
+                constant_time_eq(true_code.as_bytes(), code.as_bytes())
+            }
+        };
+        if !owned {
+            return Err(hidden_ballot(
+                recall.ballot_id,
+                "ownership proof is wrong",
+                request_id,
+            ));
+        }
         ballots.push(ballot);
     }
     Ok(ballots)
@@ -725,30 +1572,50 @@ async fn recall_ballots(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use backend_test::backend_test;
     use chrono::{Duration, Utc};
     use dre_ip::{DreipPublicKey, DreipScalar, Serializable};
     use mongodb::Database;
     use rand::Rng;
     use rocket::{
-        futures::{StreamExt, TryStreamExt},
-        http::ContentType,
+        futures::{future::join_all, StreamExt, TryStreamExt},
+        http::{uri::Origin, ContentType, Header},
         local::asynchronous::Client,
         serde::json::serde_json,
     };
 
+    use crate::logging::CLIENT_OP_ID_HEADER;
     use crate::model::api::election::ElectionDescription;
     use crate::model::{
         api::{
-            election::{ElectionResults, QuestionSpec},
-            receipt::Signature,
+            auth::{
+                csrf_cookie, VoterChallengeRequest, VoterVerifyRequest, WithCsrf, CSRF_HEADER,
+                STEP_UP_COOKIE,
+            },
+            board::BoardHeadDesc,
+            election::{
+                ElectionResults, ElectionResultsChanges, QuestionSpec, ReceiptError,
+                VerificationError,
+            },
+            idempotency::IDEMPOTENCY_KEY_HEADER,
+            otp::{Challenge, CHALLENGE_COOKIE},
+            pagination::Paginated,
+            receipt::{confirmation_phrase, Signature, RECEIPT_SCHEMA_VERSION},
             sms::Sms,
         },
         common::{
             ballot::{Audited, Confirmed, Unconfirmed},
+            board::BoardError,
             election::QuestionId,
         },
-        db::election::Election,
+        db::{
+            candidate_totals::{fold_totals, CandidateTotals, NewCandidateTotals},
+            election::Election,
+            rate_limit::hash_key,
+            voter::NewVoter,
+        },
         mongodb::u32_id_filter,
     };
 
@@ -765,6 +1632,9 @@ mod tests {
             .insert_many(vec![&election1, &election2], None)
             .await
             .unwrap();
+        crate::testdata::insert_zero_totals(db, &election1)
+            .await
+            .unwrap();
 
         // Create the associated counters.
         let mut counters = Vec::new();
@@ -783,8 +1653,7 @@ mod tests {
             .unwrap();
 
         // Allow the voter to vote on one of the two questions.
-        let voters = Coll::<Voter>::from_db(db);
-        let mut voter = voters
+        let voter = Coll::<Voter>::from_db(db)
             .find_one(
                 doc! {
                     "sms_hmac": Sms::example_hmac(client).to_bytestring(),
@@ -805,23 +1674,40 @@ mod tests {
                 }
             })
             .unwrap();
-        voter.allowed_questions.insert(
-            election1.id,
-            AllowedQuestions {
+        let voter_election = VoterElection {
+            voter_id: voter.id,
+            election_id: election1.id,
+            allowed_questions: AllowedQuestions {
                 confirmed: HashMap::from_iter(vec![(allowed_question, false)]),
             },
-        );
-        voters
-            .replace_one(
-                doc! {
-                    "_id": *voter.id,
-                },
-                &voter,
+            joins: None,
+            consent: None,
+        };
+        Coll::<VoterElection>::from_db(db)
+            .insert_one(&voter_election, None)
+            .await
+            .unwrap();
+        (election1.id, allowed_question)
+    }
+
+    /// Get the questions the example voter may answer in the given election,
+    /// or `None` if they have not joined it.
+    async fn example_allowed_questions(
+        client: &Client,
+        db: &Database,
+        election_id: ElectionId,
+    ) -> Option<AllowedQuestions> {
+        let voter = Coll::<Voter>::from_db(db)
+            .find_one(
+                doc! {"sms_hmac": Sms::example_hmac(client).to_bytestring()},
                 None,
             )
             .await
+            .unwrap()
             .unwrap();
-        (election1.id, allowed_question)
+        VoterElection::allowed_questions(&Coll::from_db(db), voter.id, election_id, None)
+            .await
+            .unwrap()
     }
 
     /// Dump the current state of the database to stdout; useful for debugging.
@@ -833,6 +1719,15 @@ mod tests {
             println!("{:?}", voter);
         }
 
+        println!("\nVoter Elections:");
+        let mut voter_elections = Coll::<VoterElection>::from_db(db)
+            .find(None, None)
+            .await
+            .unwrap();
+        while let Some(Ok(voter_election)) = voter_elections.next().await {
+            println!("{:?}", voter_election);
+        }
+
         println!("\nElections:");
         let mut elections = Coll::<Election>::from_db(db)
             .find(None, None)
@@ -883,7 +1778,7 @@ mod tests {
         }
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn has_joined(client: Client, db: Database) {
         let election = Election::published_example();
         Coll::<Election>::from_db(&db)
@@ -901,6 +1796,7 @@ mod tests {
         // Join the election (no groups).
         let response = client
             .post(uri!(join_election(election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body("{}")
             .dispatch()
@@ -916,7 +1812,7 @@ mod tests {
         assert!(joined);
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn join_all_groups(client: Client, db: Database) {
         let election = Election::published_example();
         Coll::<Election>::from_db(&db)
@@ -925,13 +1821,10 @@ mod tests {
             .unwrap();
 
         // Check no questions are allowed.
-        let sms_hmac = Sms::example_hmac(&client);
-        let voter = Coll::<Voter>::from_db(&db)
-            .find_one(doc! {"sms_hmac": sms_hmac.to_bytestring()}, None)
-            .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(voter.allowed_questions, HashMap::new());
+        assert_eq!(
+            example_allowed_questions(&client, &db, election.id).await,
+            None
+        );
 
         // Join as many groups as we can.
         let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![
@@ -946,6 +1839,7 @@ mod tests {
         ]);
         let response = client
             .post(uri!(join_election(election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&joins).unwrap())
             .dispatch()
@@ -954,20 +1848,16 @@ mod tests {
         assert!(response.body().is_none());
 
         // Check all questions are allowed.
-        let voter = Coll::<Voter>::from_db(&db)
-            .find_one(doc! {"sms_hmac": sms_hmac.to_bytestring()}, None)
+        let joined = example_allowed_questions(&client, &db, election.id)
             .await
-            .unwrap()
             .unwrap();
         assert_eq!(
-            voter.allowed_questions[&election.id]
-                .keys()
-                .collect::<HashSet<_>>(),
+            joined.keys().collect::<HashSet<_>>(),
             election.questions.keys().collect()
         );
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn join_one_group(client: Client, db: Database) {
         let election = Election::published_example();
         Coll::<Election>::from_db(&db)
@@ -976,13 +1866,10 @@ mod tests {
             .unwrap();
 
         // Check no questions are allowed.
-        let sms_hmac = Sms::example_hmac(&client);
-        let voter = Coll::<Voter>::from_db(&db)
-            .find_one(doc! {"sms_hmac": sms_hmac.to_bytestring()}, None)
-            .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(voter.allowed_questions, HashMap::new());
+        assert_eq!(
+            example_allowed_questions(&client, &db, election.id).await,
+            None
+        );
 
         // Join one group only.
         let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
@@ -991,6 +1878,7 @@ mod tests {
         )]);
         let response = client
             .post(uri!(join_election(election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&joins).unwrap())
             .dispatch()
@@ -999,15 +1887,11 @@ mod tests {
         assert!(response.body().is_none());
 
         // Check the correct questions are allowed.
-        let voter = Coll::<Voter>::from_db(&db)
-            .find_one(doc! {"sms_hmac": sms_hmac.to_bytestring()}, None)
+        let joined = example_allowed_questions(&client, &db, election.id)
             .await
-            .unwrap()
             .unwrap();
         assert_eq!(
-            voter.allowed_questions[&election.id]
-                .keys()
-                .collect::<HashSet<_>>(),
+            joined.keys().collect::<HashSet<_>>(),
             election
                 .questions
                 .iter()
@@ -1025,7 +1909,216 @@ mod tests {
         );
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
+    async fn pre_join_election(client: Client, db: Database) {
+        // Publish an election that starts tomorrow, which can be joined up to
+        // two days early, and one that can't be joined early.
+        let start_time = Utc::now() + Duration::try_days(1).unwrap();
+        let mut election = Election::published_example();
+        election.metadata.start_time = start_time;
+        election.metadata.pre_join_minutes = Some(2 * 24 * 60);
+        let mut no_pre_join = Election::published_example();
+        no_pre_join.metadata.start_time = start_time;
+        Coll::<Election>::from_db(&db)
+            .insert_many([&election, &no_pre_join], None)
+            .await
+            .unwrap();
+
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Societies".to_string(),
+            HashSet::from_iter(vec!["Quidditch".to_string()]),
+        )]);
+        let join = |election_id| {
+            client
+                .post(uri!(join_election(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&joins).unwrap())
+                .dispatch()
+        };
+        let question_id = election
+            .questions
+            .values()
+            .find(|q| q.description == QuestionSpec::example1().description)
+            .unwrap()
+            .id;
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let cast = || {
+            client
+                .post(uri!(cast_ballots(election.id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_specs).unwrap())
+                .dispatch()
+        };
+
+        // Only the election allowing it can be joined before it starts.
+        assert_eq!(join(no_pre_join.id).await.status(), Status::NotFound);
+        assert_eq!(join(election.id).await.status(), Status::Ok);
+        let response = client.get(uri!(has_joined(election.id))).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "true");
+        let response = client.get(uri!(get_allowed(election.id))).dispatch().await;
+        let allowed: AllowedQuestions =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(allowed.confirmed.contains_key(&question_id));
+
+        // Voting still waits for the start.
+        assert_eq!(cast().await.status(), Status::NotFound);
+
+        // Once it starts, the voter can vote.
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election.id),
+                doc! {"$set": {"start_time": Utc::now() - Duration::try_minutes(1).unwrap()}},
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(cast().await.status(), Status::Ok);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn join_with_consent(client: Client, db: Database) {
+        let mut election = Election::published_example();
+        election.metadata.consent_text = Some("We will process your data.".to_string());
+        election.metadata.consent_version = 2;
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        let join = |consent_version| {
+            let request = JoinRequest {
+                joins: HashMap::from_iter(vec![(
+                    "Societies".to_string(),
+                    HashSet::from_iter(vec!["Quidditch".to_string()]),
+                )]),
+                consent_version,
+            };
+            client
+                .post(uri!(join_election(election.id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&request).unwrap())
+                .dispatch()
+        };
+        let expected_body = serde_json::json!({
+            "code": "consent_required",
+            "consent_text": "We will process your data.",
+            "consent_version": 2,
+        });
+
+        // Without accepting the consent text, the voter is given it.
+        let response = join(None).await;
+        assert_eq!(response.status(), Status::PreconditionRequired);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body, expected_body);
+
+        // Accepting an old version is not enough.
+        let response = join(Some(1)).await;
+        assert_eq!(response.status(), Status::PreconditionRequired);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body, expected_body);
+        let response = client.get(uri!(has_joined(election.id))).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "false");
+
+        // Accepting the current version joins, recording when it was accepted.
+        let before = DateTime::now();
+        assert_eq!(join(Some(2)).await.status(), Status::Ok);
+        let voter_election = Coll::<VoterElection>::from_db(&db)
+            .find_one(doc! { "election_id": election.id }, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let consent = voter_election.consent.unwrap();
+        assert_eq!(consent.version, 2);
+        assert!(consent.accepted_at >= before);
+        assert!(!voter_election.allowed_questions.confirmed.is_empty());
+    }
+
+    #[backend_test(db = "isolated")]
+    async fn join_full_election(client: Client, db: Database) {
+        const MAX_VOTERS: u32 = 3;
+        let mut election = Election::published_example();
+        election.metadata.max_voters = Some(MAX_VOTERS);
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        // Create more voters than the election can take.
+        let config = client.rocket().state::<Config>().unwrap();
+        let new_voters = (0..2 * MAX_VOTERS)
+            .map(|i| {
+                let sms = format!("+4412345678{i:02}").parse().unwrap();
+                NewVoter::new(sms, config)
+            })
+            .collect::<Vec<_>>();
+        let result = Coll::<NewVoter>::from_db(&db)
+            .insert_many(&new_voters, None)
+            .await
+            .unwrap();
+        let joining_voters = new_voters
+            .into_iter()
+            .enumerate()
+            .map(|(i, voter)| Voter {
+                id: result.inserted_ids[&i].as_object_id().unwrap().into(),
+                voter,
+            })
+            .collect::<Vec<_>>();
+
+        // Have them all join at once.
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Societies".to_string(),
+            HashSet::from_iter(vec!["Quidditch".to_string()]),
+        )]);
+        let body = serde_json::to_string(&joins).unwrap();
+        let responses = join_all(joining_voters.iter().map(|voter| {
+            let csrf = csrf_cookie(config);
+            client
+                .post(uri!(join_election(election.id)))
+                .cookie(AuthToken::new(voter).into_cookie(config))
+                .header(Header::new(CSRF_HEADER, csrf.value().to_string()))
+                .cookie(csrf)
+                .header(ContentType::JSON)
+                .body(body.clone())
+                .dispatch()
+        }))
+        .await;
+        let statuses = responses
+            .iter()
+            .map(|response| response.status())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses.iter().filter(|s| **s == Status::Ok).count(),
+            MAX_VOTERS as usize
+        );
+        assert_eq!(
+            statuses.iter().filter(|s| **s == Status::Conflict).count(),
+            joining_voters.len() - MAX_VOTERS as usize
+        );
+
+        // Exactly the cap have joined, and the count agrees.
+        let joined = Coll::<VoterElection>::from_db(&db)
+            .count_documents(doc! { "election_id": election.id }, None)
+            .await
+            .unwrap();
+        assert_eq!(joined, u64::from(MAX_VOTERS));
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election.id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(election.metadata.joined_count, MAX_VOTERS);
+    }
+
+    #[backend_test(voter, db = "isolated")]
     async fn bad_joins(client: Client, db: Database) {
         let election = Election::published_example();
         Coll::<Election>::from_db(&db)
@@ -1040,6 +2133,7 @@ mod tests {
         )]);
         let response = client
             .post(uri!(join_election(rand::thread_rng().gen::<u32>())))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&joins).unwrap())
             .dispatch()
@@ -1053,77 +2147,1005 @@ mod tests {
         )]);
         let response = client
             .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Try to join a non-existent group.
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Societies".to_string(),
+            HashSet::from_iter(vec!["Foo".to_string()]),
+        )]);
+        let response = client
+            .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Try to join two mutually-exclusive groups.
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Courses".to_string(),
+            HashSet::from_iter(vec!["CompSci", "Maths"].into_iter().map(String::from)),
+        )]);
+        let response = client
+            .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        // Try to join an election twice.
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Societies".to_string(),
+            HashSet::from_iter(vec!["Quidditch".to_string()]),
+        )]);
+        let response = client
+            .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client
+            .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn preview_all_groups(client: Client, db: Database) {
+        let election = Election::published_example();
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        // Preview joining as many groups as we can.
+        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![
+            (
+                "Societies".to_string(),
+                HashSet::from_iter(vec!["Quidditch", "Moongolf"].into_iter().map(String::from)),
+            ),
+            (
+                "Courses".to_string(),
+                HashSet::from_iter(vec!["CompSci".to_string()]),
+            ),
+        ]);
+        let response = client
+            .post(uri!(eligibility_preview(election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&joins).unwrap())
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let allowed: AllowedQuestions = serde_json::from_str(&raw_response).unwrap();
+
+        // Check all questions would be allowed, and none confirmed.
+        assert_eq!(
+            allowed.keys().collect::<HashSet<_>>(),
+            election.questions.keys().collect()
+        );
+        assert!(allowed.values().all(|confirmed| !confirmed));
+
+        // Check the voter has not actually joined.
+        assert_eq!(
+            example_allowed_questions(&client, &db, election.id).await,
+            None
+        );
+
+        // Joining for real gives the same questions.
+        let response = client
+            .post(uri!(join_election(election.id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&joins).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let joined = example_allowed_questions(&client, &db, election.id)
+            .await
+            .unwrap();
+        assert_eq!(joined, allowed);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn preview_bad_joins(client: Client, db: Database) {
+        let election = Election::published_example();
+        Coll::<Election>::from_db(&db)
+            .insert_one(&election, None)
+            .await
+            .unwrap();
+
+        async fn preview(
+            client: &Client,
+            election_id: ElectionId,
+            electorate: &str,
+            groups: &[&str],
+        ) -> Status {
+            let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+                electorate.to_string(),
+                groups.iter().map(|g| g.to_string()).collect(),
+            )]);
+            client
+                .post(uri!(eligibility_preview(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&joins).unwrap())
+                .dispatch()
+                .await
+                .status()
+        }
+
+        // Non-existent election.
+        let status = preview(
+            &client,
+            rand::thread_rng().gen::<u32>(),
+            "Societies",
+            &["Quidditch"],
+        )
+        .await;
+        assert_eq!(status, Status::NotFound);
+
+        // Non-existent electorate.
+        let status = preview(&client, election.id, "Foo", &["Quidditch"]).await;
+        assert_eq!(status, Status::NotFound);
+
+        // Non-existent group.
+        let status = preview(&client, election.id, "Societies", &["Foo"]).await;
+        assert_eq!(status, Status::NotFound);
+
+        // Two mutually-exclusive groups.
+        let status = preview(&client, election.id, "Courses", &["CompSci", "Maths"]).await;
+        assert_eq!(status, Status::UnprocessableEntity);
+
+        // Previewing writes nothing, so can be repeated freely.
+        for _ in 0..2 {
+            let status = preview(&client, election.id, "Societies", &["Quidditch"]).await;
+            assert_eq!(status, Status::Ok);
+        }
+        assert_eq!(
+            example_allowed_questions(&client, &db, election.id).await,
+            None
+        );
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn get_allowed(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Get the allowed questions.
+        let response = client.get(uri!(get_allowed(election_id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        // Ensure they are correct.
+        let raw_response = response.into_string().await.unwrap();
+        let allowed: AllowedQuestions = serde_json::from_str(&raw_response).unwrap();
+        let expected = HashMap::from_iter(vec![(question_id, false)]);
+        assert_eq!(allowed.confirmed, expected);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn ballot_orders(client: Client, db: Database) {
+        let (election_id, _) = insert_test_data(&client, &db).await;
+        let config = client.rocket().state::<Config>().unwrap();
+
+        // Give a question plenty of candidates, so that two voters' orders
+        // coincide with negligible probability.
+        let candidates = (0..10)
+            .map(|i| format!("Candidate {i}"))
+            .collect::<Vec<_>>();
+        let elections = Coll::<Election>::from_db(&db);
+        let election = elections
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let question_id = *election.questions.keys().next().unwrap();
+        let field = format!("questions.{question_id}.candidates");
+        elections
+            .update_one(
+                u32_id_filter(election_id),
+                doc! { "$set": { field: &candidates } },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Without the flag, every voter gets the canonical order.
+        let order = |voter: Option<&Voter>| {
+            let mut request = client.get(uri!(ballot_order(election_id, question_id)));
+            if let Some(voter) = voter {
+                request = request.cookie(AuthToken::new(voter).into_cookie(config));
+            }
+            async move {
+                let response = request.dispatch().await;
+                assert_eq!(response.status(), Status::Ok);
+                serde_json::from_str::<Vec<CandidateId>>(&response.into_string().await.unwrap())
+                    .unwrap()
+            }
+        };
+        assert_eq!(order(None).await, candidates);
+
+        // With it, a voter gets a stable permutation of the candidates.
+        elections
+            .update_one(
+                u32_id_filter(election_id),
+                doc! { "$set": { "randomise_candidate_order": true } },
+                None,
+            )
+            .await
+            .unwrap();
+        let first = order(None).await;
+        assert_eq!(order(None).await, first);
+        assert_ne!(first, candidates);
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, candidates);
+
+        // Another voter gets a different order.
+        let other = NewVoter::new("+441234567800".parse().unwrap(), config);
+        let result = Coll::<NewVoter>::from_db(&db)
+            .insert_one(&other, None)
+            .await
+            .unwrap();
+        let other = Voter {
+            id: result.inserted_id.as_object_id().unwrap().into(),
+            voter: other,
+        };
+        let second = order(Some(&other)).await;
+        assert_ne!(second, first);
+        assert_eq!(order(Some(&other)).await, second);
+
+        // Unknown questions have no order.
+        let response = client
+            .get(uri!(ballot_order(election_id, question_id + 100)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_ballots(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Vote on the question we are allowed to.
+        let candidate_id = "Chris Riches".to_string();
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: candidate_id.clone(),
+            write_in_name: None,
+        }];
+
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
+
+        // Ensure the response is a valid receipt.
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+
+        // Ensure the receipt passes validation.
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        // Validate PWFs.
+        assert!(receipt
+            .crypto
+            .verify(
+                election.crypto.g1,
+                election.crypto.g2,
+                receipt.ballot_id.to_le_bytes()
+            )
+            .is_ok());
+        // Validate signature.
+        assert!(election
+            .crypto
+            .public_key
+            .verify(&receipt.message(), &receipt.signature));
+
+        // Ensure the ballot in the database is correct.
+        let ballot = Coll::<Ballot<Unconfirmed>>::from_db(&db)
+            .find_one(
+                doc! {
+                    "ballot_id": receipt.ballot_id,
+                    "election_id": receipt.election_id,
+                    "question_id": receipt.question_id,
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        // Check metadata.
+        assert_eq!(ballot.election_id, election_id);
+        assert_eq!(ballot.question_id, question_id);
+        assert!(
+            ballot.creation_time < Utc::now()
+                && ballot.creation_time + Duration::try_minutes(1).unwrap() > Utc::now()
+        );
+        let mut yes_votes = 0;
+        for (candidate, vote) in ballot.crypto.votes.iter() {
+            // Check the vote value is correct.
+            if candidate == &candidate_id {
+                assert_eq!(vote.secrets.v, DreipScalar::one());
+                yes_votes += 1;
+            } else {
+                assert_eq!(vote.secrets.v, DreipScalar::zero());
+            }
+            // Check the receipt matches the internal vote.
+            let receipt_vote = receipt.crypto.votes.get(candidate).unwrap();
+            assert_eq!(vote.R, receipt_vote.R);
+            assert_eq!(vote.Z, receipt_vote.Z);
+            assert_eq!(vote.pwf, receipt_vote.pwf);
+            // Check the public values were correctly calculated from the secrets.
+            assert_eq!(vote.R, election.crypto.g2 * vote.secrets.r);
+            assert_eq!(
+                vote.Z,
+                election.crypto.g1 * (vote.secrets.r + vote.secrets.v)
+            );
+        }
+        // Check there was exactly one yes vote (already guaranteed by the PWF, but sanity checks are good).
+        assert_eq!(yes_votes, 1);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn find_ballot_by_phrase(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        assert_eq!(
+            receipt.confirmation_phrase,
+            confirmation_phrase(&receipt.confirmation_code)
+        );
+
+        // The ballot is found by its phrase, however it is typed.
+        let typed_phrases = [
+            receipt.confirmation_phrase.clone(),
+            receipt
+                .confirmation_phrase
+                .replace('-', "%20")
+                .to_uppercase(),
+        ];
+        for phrase in typed_phrases {
+            let response = client
+                .get(format!(
+                    "/elections/{election_id}/{question_id}/ballots?phrase={phrase}"
+                ))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            let raw_response = response.into_string().await.unwrap();
+            let found: Paginated<PublicReceipt> = serde_json::from_str(&raw_response).unwrap();
+            assert_eq!(found.pagination.total, 1);
+            match &found.items[..] {
+                [PublicReceipt::Unconfirmed(stub)] => {
+                    assert_eq!(stub.ballot_id, receipt.ballot_id);
+                    assert_eq!(stub.confirmation_code, receipt.confirmation_code);
+                    assert_eq!(stub.confirmation_phrase, receipt.confirmation_phrase);
+                }
+                other => panic!("Expected just the cast ballot, got {other:?}"),
+            }
+        }
+
+        // Other phrases find nothing, and non-phrases are refused.
+        let other_phrase = if receipt.confirmation_phrase == "zoo-zoo-zoo-zoo" {
+            "abandon-abandon-abandon-abandon"
+        } else {
+            "zoo-zoo-zoo-zoo"
+        };
+        let response = client
+            .get(format!(
+                "/elections/{election_id}/{question_id}/ballots?phrase={other_phrase}"
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let found: Paginated<PublicReceipt> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(found.pagination.total, 0);
+        assert!(found.items.is_empty());
+        let response = client
+            .get(format!(
+                "/elections/{election_id}/{question_id}/ballots?phrase=not-a-phrase"
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_duplicate_candidates(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Simulate a duplicate candidate slipping through into a published election.
+        let candidates = format!("questions.{}.candidates", question_id);
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election_id),
+                doc! {"$push": {candidates: "Chris Riches"}},
+                None,
+            )
+            .await
+            .unwrap();
+
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_translated(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Translate the candidate's display name, as though it had been published so.
+        let candidate_translations = format!("questions.{question_id}.candidate_translations");
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election_id),
+                doc! {"$set": {candidate_translations: {"Chris Riches": {"cy": "Chris Rhys"}}}},
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Display names are not candidates.
+        let cast = |candidate: &str| {
+            let ballot_specs = vec![BallotSpec {
+                question: question_id,
+                candidate: candidate.to_string(),
+                write_in_name: None,
+            }];
+            client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_specs).unwrap())
+                .dispatch()
+        };
+        assert_eq!(cast("Chris Rhys").await.status(), Status::NotFound);
+
+        // Voting for the canonical candidate is unaffected, and receipts stay canonical.
+        let response = cast("Chris Riches").await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        assert!(!raw_response.contains("Rhys"));
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(receipt
+            .crypto
+            .verify(
+                election.crypto.g1,
+                election.crypto.g2,
+                receipt.ballot_id.to_le_bytes()
+            )
+            .is_ok());
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn abandon_past_deadline(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let voter = Coll::<Voter>::from_db(&db)
+            .find_one(
+                doc! {
+                    "sms_hmac": Sms::example_hmac(&client).to_bytestring(),
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let expired = Deadline::after(std::time::Duration::ZERO);
+
+        // A cast that runs out of time is abandoned with a 503, casting nothing.
+        let result = cast(
+            &election,
+            ballot_specs.clone(),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            db_client,
+            expired,
+            RequestId::next(),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        assert_eq!(result.unwrap_err().status(), Status::ServiceUnavailable);
+        let ballots = Coll::<AnyBallot>::from_db(&db)
+            .count_documents(doc! { "election_id": election_id }, None)
+            .await
+            .unwrap();
+        assert_eq!(ballots, 0);
+
+        // Cast in time, then run out of time confirming; the ballot is left
+        // unconfirmed and its vote uncounted.
+        let receipts = cast(
+            &election,
+            ballot_specs,
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            db_client,
+            Deadline::after(std::time::Duration::from_secs(30)),
+            RequestId::next(),
+        )
+        .await
+        .unwrap();
+        let recalls = receipts
+            .into_iter()
+            .map(|receipt| BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id: receipt.question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            })
+            .collect::<Vec<_>>();
+        let result = confirm(
+            voter.id,
+            &election,
+            &recalls,
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            client.rocket().state::<TotalsFolder>().unwrap(),
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            db_client,
+            client.rocket().state::<Config>().unwrap().hmac_secret(),
+            expired,
+            RequestId::next(),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        let unconfirmed = Coll::<Ballot<Unconfirmed>>::from_db(&db)
+            .count_documents(
+                doc! { "election_id": election_id, "state": Unconfirmed },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(unconfirmed, 1);
+        let confirmed = Coll::<Ballot<Confirmed>>::from_db(&db)
+            .count_documents(
+                doc! { "election_id": election_id, "state": Confirmed },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(confirmed, 0);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_write_in(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Allow write-ins on the question, as though it had been published so.
+        let allow_write_in = format!("questions.{question_id}.allow_write_in");
+        let candidates = format!("questions.{question_id}.candidates");
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election_id),
+                doc! {
+                    "$set": {allow_write_in: true},
+                    "$push": {candidates: WRITE_IN_CANDIDATE},
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let write_in_totals =
+            NewCandidateTotals::new(election_id, question_id, WRITE_IN_CANDIDATE.to_string());
+        crate::testdata::store_totals(&db, [write_in_totals])
+            .await
+            .unwrap();
+
+        // Names only go with write-ins, and write-ins need a sensible name.
+        let long_name = "x".repeat(MAX_WRITE_IN_NAME_LENGTH + 1);
+        for (candidate, name) in [
+            ("Chris Riches", Some("Minerva McGonagall")),
+            (WRITE_IN_CANDIDATE, None),
+            (WRITE_IN_CANDIDATE, Some("   ")),
+            (WRITE_IN_CANDIDATE, Some(long_name.as_str())),
+        ] {
+            let ballot_specs = vec![BallotSpec {
+                question: question_id,
+                candidate: candidate.to_string(),
+                write_in_name: name.map(str::to_string),
+            }];
+            let response = client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_specs).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity, "{name:?}");
+        }
+
+        // Write in a name, and confirm it.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: WRITE_IN_CANDIDATE.to_string(),
+            write_in_name: Some("  Minerva McGonagall ".to_string()),
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // The name is kept with the ballot, trimmed.
+        let ballot = Coll::<Ballot<Confirmed>>::from_db(&db)
+            .find_one(
+                doc! {
+                    "election_id": election_id,
+                    "question_id": question_id,
+                    "ballot_id": receipt.ballot_id,
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ballot.write_in_name.as_deref(), Some("Minerva McGonagall"));
+
+        // The receipts and dump verify as usual, and the name is not published.
+        let response = client
+            .get(uri!(crate::api::public::question_dump(
+                election_id,
+                question_id,
+                Option::<bool>::None
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        assert!(!raw_response.contains("McGonagall"));
+        let dump: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert!(dump.confirmed.contains_key(&receipt.ballot_id));
+        dump.verify().unwrap();
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn suspended(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let elections = Coll::<Election>::from_db(&db);
+        let set_suspended = |suspended: bool| {
+            elections.update_one(
+                u32_id_filter(election_id),
+                doc! {"$set": {"suspended": suspended}},
+                None,
+            )
+        };
+
+        // Casting is rejected while suspended.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        set_suspended(true).await.unwrap();
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "election_suspended");
+
+        // Public reads still work, and expose the flag.
+        let response = client
+            .get(format!("/elections/{}", election_id))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let description: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(description.suspended);
+
+        // Cast once resumed.
+        set_suspended(false).await.unwrap();
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        }];
+
+        // Confirming is rejected while suspended.
+        set_suspended(true).await.unwrap();
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        // Confirm once resumed.
+        set_suspended(false).await.unwrap();
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn frozen(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let voters = Coll::<Voter>::from_db(&db);
+        let sms_hmac = Sms::example_hmac(&client);
+        let set_frozen = |frozen: bool| {
+            voters.update_one(
+                doc! {"sms_hmac": sms_hmac.to_bytestring()},
+                doc! {"$set": {"frozen": frozen}},
+                None,
+            )
+        };
+        let post = |uri: Origin<'static>, body: String| {
+            client
+                .post(uri)
+                .csrf()
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+        };
+
+        // Cast a ballot.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let ballot_specs = serde_json::to_string(&ballot_specs).unwrap();
+        let response = post(uri!(cast_ballots(election_id)), ballot_specs.clone()).await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let ballot_recalls = serde_json::to_string(&vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        }])
+        .unwrap();
+
+        // Once frozen, the voter can neither confirm, audit, cast nor join.
+        set_frozen(true).await.unwrap();
+        for (uri, body) in [
+            (uri!(confirm_ballots(election_id)), &ballot_recalls),
+            (uri!(audit_ballots(election_id)), &ballot_recalls),
+            (uri!(cast_ballots(election_id)), &ballot_specs),
+            (uri!(join_election(election_id)), &"{}".to_string()),
+        ] {
+            let response = post(uri, body.clone()).await;
+            assert_eq!(response.status(), Status::Forbidden);
+            let body: serde_json::Value =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+            assert_eq!(body["code"], "voter_frozen");
+        }
+
+        // Reads still work.
+        let response = client.get(uri!(get_allowed(election_id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // Once unfrozen, the ballot can be confirmed.
+        set_frozen(false).await.unwrap();
+        let response = post(uri!(confirm_ballots(election_id)), ballot_recalls).await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn step_up(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election_id),
+                doc! {"$set": {"requires_step_up": true}},
+                None,
+            )
+            .await
+            .unwrap();
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+
+        // Casting without stepping up is rejected, telling the client to step up.
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(body["code"], "step_up_required");
 
-        // Try to join a non-existent group.
-        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
-            "Societies".to_string(),
-            HashSet::from_iter(vec!["Foo".to_string()]),
-        )]);
+        // Step up by re-verifying the SMS number.
         let response = client
-            .post(uri!(join_election(election.id)))
+            .post(uri!(crate::api::auth::step_up_challenge(election_id)))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&joins).unwrap())
+            .body(serde_json::to_string(&VoterChallengeRequest::example()).unwrap())
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::NotFound);
-
-        // Try to join two mutually-exclusive groups.
-        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
-            "Courses".to_string(),
-            HashSet::from_iter(vec!["CompSci", "Maths"].into_iter().map(String::from)),
-        )]);
+        assert_eq!(response.status(), Status::Accepted);
+        let cookie = client.cookies().get_private(CHALLENGE_COOKIE).unwrap();
+        let config = client.rocket().state::<Config>().unwrap();
+        let challenge = Challenge::from_cookie(&cookie, config).unwrap();
         let response = client
-            .post(uri!(join_election(election.id)))
+            .post(uri!(crate::api::auth::step_up_verify(election_id)))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&joins).unwrap())
+            .body(serde_json::to_string(&VoterVerifyRequest::example(challenge.code)).unwrap())
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.status(), Status::Ok);
+        assert!(client.cookies().get(STEP_UP_COOKIE).is_some());
 
-        // Try to join an election twice.
-        let joins: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
-            "Societies".to_string(),
-            HashSet::from_iter(vec!["Quidditch".to_string()]),
-        )]);
+        // Casting now succeeds.
         let response = client
-            .post(uri!(join_election(election.id)))
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&joins).unwrap())
+            .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
+
+        // The token is scoped to the election it was issued for.
+        let other_election = Election::published_example();
+        Coll::<Election>::from_db(&db)
+            .insert_one(&other_election, None)
+            .await
+            .unwrap();
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(other_election.id),
+                doc! {"$set": {"requires_step_up": true}},
+                None,
+            )
+            .await
+            .unwrap();
         let response = client
-            .post(uri!(join_election(election.id)))
+            .post(uri!(cast_ballots(other_election.id)))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&joins).unwrap())
+            .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Forbidden);
     }
 
-    #[backend_test(voter)]
-    async fn get_allowed(client: Client, db: Database) {
-        let (election_id, question_id) = insert_test_data(&client, &db).await;
-
-        // Get the allowed questions.
-        let response = client.get(uri!(get_allowed(election_id))).dispatch().await;
-        assert_eq!(response.status(), Status::Ok);
-        assert!(response.body().is_some());
-
-        // Ensure they are correct.
-        let raw_response = response.into_string().await.unwrap();
-        let allowed: AllowedQuestions = serde_json::from_str(&raw_response).unwrap();
-        let expected = HashMap::from_iter(vec![(question_id, false)]);
-        assert_eq!(allowed.confirmed, expected);
-    }
-
-    #[backend_test(voter)]
-    async fn cast_ballots(client: Client, db: Database) {
+    #[backend_test(voter, db = "isolated")]
+    async fn audit(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
 
         // Vote on the question we are allowed to.
@@ -1131,205 +3153,483 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: candidate_id.clone(),
+            write_in_name: None,
         }];
 
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
         assert!(response.body().is_some());
+        let raw_response = response.into_string().await.unwrap();
+        let first_receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+
+        // Audit the ballot.
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: first_receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(first_receipt.signature),
+        }];
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.body().is_some());
 
         // Ensure the response is a valid receipt.
         let raw_response = response.into_string().await.unwrap();
-        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+        let second_receipt: Receipt<Audited> = serde_json::from_str::<Vec<_>>(&raw_response)
             .unwrap()
             .into_iter()
             .next()
             .unwrap();
 
-        // Ensure the receipt passes validation.
+        // Ensure the receipts match.
         let election = Coll::<Election>::from_db(&db)
             .find_one(u32_id_filter(election_id), None)
             .await
             .unwrap()
             .unwrap();
+        assert_eq!(first_receipt.ballot_id, second_receipt.ballot_id);
+        assert_eq!(first_receipt.crypto.pwf, second_receipt.crypto.pwf);
+        assert_eq!(
+            first_receipt.confirmation_code,
+            second_receipt.confirmation_code
+        );
+        for (candidate, vote1) in first_receipt.crypto.votes.iter() {
+            let vote2 = second_receipt.crypto.votes.get(candidate).unwrap();
+            assert_eq!(vote1.pwf, vote2.pwf);
+            assert_eq!(vote1.R, vote2.R);
+            assert_eq!(vote1.Z, vote2.Z);
+            assert_eq!(vote1.R, election.crypto.g2 * vote2.secrets.r);
+            assert_eq!(
+                vote1.Z,
+                election.crypto.g1 * (vote2.secrets.r + vote2.secrets.v)
+            );
+        }
+
         // Validate PWFs.
-        assert!(receipt
+        assert!(second_receipt
             .crypto
             .verify(
                 election.crypto.g1,
                 election.crypto.g2,
-                receipt.ballot_id.to_le_bytes()
+                second_receipt.ballot_id.to_le_bytes()
             )
             .is_ok());
         // Validate signature.
-        let mut msg = receipt.crypto.to_bytes();
-        msg.extend(receipt.ballot_id.to_le_bytes());
-        msg.extend(receipt.election_id.to_le_bytes());
-        msg.extend(receipt.question_id.to_le_bytes());
-        msg.extend(receipt.confirmation_code.as_bytes());
-        msg.extend(receipt.state.as_ref());
-        msg.extend(Into::<Vec<u8>>::into(&receipt.state_data));
-        assert!(election.crypto.public_key.verify(&msg, &receipt.signature));
+        assert!(election
+            .crypto
+            .public_key
+            .verify(&second_receipt.message(), &second_receipt.signature));
 
-        // Ensure the ballot in the database is correct.
-        let ballot = Coll::<Ballot<Unconfirmed>>::from_db(&db)
-            .find_one(
-                doc! {
-                    "ballot_id": receipt.ballot_id,
-                    "election_id": receipt.election_id,
-                    "question_id": receipt.question_id,
-                },
-                None,
+        // Check the candidate totals weren't affected.
+        let totals: Vec<CandidateTotals> = Coll::<CandidateTotals>::from_db(&db)
+            .find(None, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        for total in totals.iter() {
+            assert_eq!(total.crypto.r_sum, DreipScalar::zero());
+            assert_eq!(total.crypto.tally, DreipScalar::zero());
+        }
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn idempotent_requests(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let post = |uri, key: &'static str, body: String| {
+            client
+                .post(uri)
+                .csrf()
+                .header(ContentType::JSON)
+                .header(Header::new(IDEMPOTENCY_KEY_HEADER, key))
+                .body(body)
+                .dispatch()
+        };
+        let board_entries = Coll::<BoardEntry>::from_db(&db);
+
+        // Retrying a cast gives the same receipts, without casting again.
+        let cast = || {
+            post(
+                uri!(cast_ballots(election_id)),
+                "cast",
+                serde_json::to_string(&ballot_specs).unwrap(),
             )
+        };
+        let response = cast().await;
+        assert_eq!(response.status(), Status::Ok);
+        let cast_response = response.into_string().await.unwrap();
+        let response = cast().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), cast_response);
+        assert_eq!(board_entries.count_documents(None, None).await.unwrap(), 1);
+        let receipt = serde_json::from_str::<Vec<Receipt<Unconfirmed>>>(&cast_response)
+            .unwrap()
+            .remove(0);
+
+        // Cast another ballot, for the mismatch case below.
+        let response = post(
+            uri!(cast_ballots(election_id)),
+            "cast again",
+            serde_json::to_string(&ballot_specs).unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let other_receipt = serde_json::from_str::<Vec<Receipt<Unconfirmed>>>(&raw_response)
+            .unwrap()
+            .remove(0);
+
+        // Retrying a confirm gives an identical receipt, without writing anything.
+        let recalls = |receipt: &Receipt<Unconfirmed>| {
+            serde_json::to_string(&vec![BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(receipt.signature.clone()),
+            }])
+            .unwrap()
+        };
+        let response = post(
+            uri!(confirm_ballots(election_id)),
+            "confirm",
+            recalls(&receipt),
+        )
+        .await;
+        assert_eq!(response.status(), Status::Ok);
+        let confirm_response = response.into_string().await.unwrap();
+        let entries_before = board_entries.count_documents(None, None).await.unwrap();
+        let head_before = Coll::<BoardHead>::from_db(&db)
+            .find_one(None, None)
+            .await
+            .unwrap()
+            .map(|head| head.head);
+        let response = post(
+            uri!(confirm_ballots(election_id)),
+            "confirm",
+            recalls(&receipt),
+        )
+        .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), confirm_response);
+        assert_eq!(
+            board_entries.count_documents(None, None).await.unwrap(),
+            entries_before
+        );
+        let head_after = Coll::<BoardHead>::from_db(&db)
+            .find_one(None, None)
             .await
             .unwrap()
+            .map(|head| head.head);
+        assert_eq!(head_after, head_before);
+        let stored = Coll::<IdempotentResponse>::from_db(&db)
+            .count_documents(None, None)
+            .await
             .unwrap();
-        // Check metadata.
-        assert_eq!(ballot.election_id, election_id);
-        assert_eq!(ballot.question_id, question_id);
-        assert!(
-            ballot.creation_time < Utc::now()
-                && ballot.creation_time + Duration::try_minutes(1).unwrap() > Utc::now()
+        assert_eq!(stored, 3);
+
+        // Without the key, the retry fails as the ballot is no longer unconfirmed.
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(recalls(&receipt))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Reusing the key for a different request is refused.
+        let response = post(
+            uri!(confirm_ballots(election_id)),
+            "confirm",
+            recalls(&other_receipt),
+        )
+        .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(
+            board_entries.count_documents(None, None).await.unwrap(),
+            entries_before
         );
-        let mut yes_votes = 0;
-        for (candidate, vote) in ballot.crypto.votes.iter() {
-            // Check the vote value is correct.
-            if candidate == &candidate_id {
-                assert_eq!(vote.secrets.v, DreipScalar::one());
-                yes_votes += 1;
-            } else {
-                assert_eq!(vote.secrets.v, DreipScalar::zero());
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn client_op_ids(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let ballot_specs = serde_json::to_string(&vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }])
+        .unwrap();
+        let cast = |op_id: Option<&'static str>| {
+            let mut request = client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(&ballot_specs);
+            if let Some(op_id) = op_id {
+                request = request.header(Header::new(CLIENT_OP_ID_HEADER, op_id));
             }
-            // Check the receipt matches the internal vote.
-            let receipt_vote = receipt.crypto.votes.get(candidate).unwrap();
-            assert_eq!(vote.R, receipt_vote.R);
-            assert_eq!(vote.Z, receipt_vote.Z);
-            assert_eq!(vote.pwf, receipt_vote.pwf);
-            // Check the public values were correctly calculated from the secrets.
-            assert_eq!(vote.R, election.crypto.g2 * vote.secrets.r);
+            request.dispatch()
+        };
+        let op_id = "1b4e28ba-2fa1-11d2-883f-0016d3cca427";
+
+        // The op ID is echoed from the request ID, which prefixes every log
+        // line. A retry is answered with the ballot the first attempt cast.
+        let mut ballot_ids = Vec::new();
+        for op_id in [op_id, "1B4E28BA-2FA1-11D2-883F-0016D3CCA427"] {
+            let response = cast(Some(op_id)).await;
+            assert_eq!(response.status(), Status::Ok);
             assert_eq!(
-                vote.Z,
-                election.crypto.g1 * (vote.secrets.r + vote.secrets.v)
+                response.headers().get_one(CLIENT_OP_ID_HEADER),
+                Some("1b4e28ba-2fa1-11d2-883f-0016d3cca427")
             );
+            let raw_response = response.into_string().await.unwrap();
+            let receipts: Vec<Receipt<Unconfirmed>> = serde_json::from_str(&raw_response).unwrap();
+            ballot_ids.push(receipts[0].ballot_id);
+        }
+        assert_eq!(ballot_ids[0], ballot_ids[1]);
+
+        // Nothing is echoed without one.
+        let response = cast(None).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one(CLIENT_OP_ID_HEADER), None);
+
+        // Malformed op IDs are rejected.
+        for op_id in ["", "not-a-uuid", "1b4e28ba2fa111d2883f0016d3cca427"] {
+            let response = cast(Some(op_id)).await;
+            assert_eq!(response.status(), Status::BadRequest);
+            assert_eq!(response.headers().get_one(CLIENT_OP_ID_HEADER), None);
+        }
+        let cast_count = Coll::<BoardEntry>::from_db(&db)
+            .count_documents(None, None)
+            .await
+            .unwrap();
+        assert_eq!(cast_count, 3);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cbor_receipts(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let accept_cbor = || Header::new("Accept", "application/cbor");
+        let cbor_type = ContentType::new("application", "cbor");
+
+        // Cast a ballot, then replay the response as CBOR.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let cast = |cbor: bool| {
+            let mut request = client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .header(Header::new(IDEMPOTENCY_KEY_HEADER, "cast"))
+                .body(serde_json::to_string(&ballot_specs).unwrap());
+            if cbor {
+                request = request.header(accept_cbor());
+            }
+            request.dispatch()
+        };
+        let response = cast(false).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        let json = response.into_bytes().await.unwrap();
+        let response = cast(true).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(cbor_type.clone()));
+        let cbor = response.into_bytes().await.unwrap();
+        assert!(cbor.len() < json.len());
+
+        // Both decode to the same receipts, which verify.
+        let from_json: Vec<Receipt<Unconfirmed>> = serde_json::from_slice(&json).unwrap();
+        let from_cbor: Vec<Receipt<Unconfirmed>> = ciborium::from_reader(&cbor[..]).unwrap();
+        assert_eq!(from_cbor, from_json);
+        let receipt = from_cbor.into_iter().next().unwrap();
+        assert!(election
+            .crypto
+            .public_key
+            .verify(&receipt.message(), &receipt.signature));
+
+        // Confirming can answer in CBOR too.
+        let recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature.clone()),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .header(accept_cbor())
+            .body(serde_json::to_string(&recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(cbor_type.clone()));
+        let cbor = response.into_bytes().await.unwrap();
+        let confirmed: Vec<Receipt<Confirmed>> = ciborium::from_reader(&cbor[..]).unwrap();
+        let confirmed = confirmed.into_iter().next().unwrap();
+        assert!(election
+            .crypto
+            .public_key
+            .verify(&confirmed.message(), &confirmed.signature));
+
+        // As can the public lookup, which gives the same receipt either way.
+        let lookup = |cbor: bool| {
+            let mut request = client.get(uri!(crate::api::public::election_question_ballot(
+                election_id,
+                question_id,
+                receipt.ballot_id
+            )));
+            if cbor {
+                request = request.header(accept_cbor());
+            }
+            request.dispatch()
+        };
+        let response = lookup(false).await;
+        assert_eq!(response.status(), Status::Ok);
+        let json = response.into_bytes().await.unwrap();
+        let response = lookup(true).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(cbor_type));
+        let cbor = response.into_bytes().await.unwrap();
+        assert!(cbor.len() < json.len());
+        // Each lookup is signed afresh, so compare the decoded receipts
+        // less their signatures, which must both verify.
+        let receipts: [PublicReceipt; 2] = [
+            serde_json::from_slice(&json).unwrap(),
+            ciborium::from_reader(&cbor[..]).unwrap(),
+        ];
+        for receipt in receipts {
+            let PublicReceipt::Confirmed(mut public) = receipt else {
+                panic!("Ballot should be confirmed");
+            };
+            assert!(election
+                .crypto
+                .public_key
+                .verify(&public.message(), &public.signature));
+            public.signature = confirmed.signature.clone();
+            assert_eq!(public, confirmed);
         }
-        // Check there was exactly one yes vote (already guaranteed by the PWF, but sanity checks are good).
-        assert_eq!(yes_votes, 1);
     }
 
-    #[backend_test(voter)]
-    async fn audit(client: Client, db: Database) {
+    #[backend_test(voter, db = "isolated")]
+    async fn state_change_times(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
 
-        // Vote on the question we are allowed to.
-        let candidate_id = "Chris Riches".to_string();
+        // Freshly cast ballots have not changed state.
         let ballot_specs = vec![BallotSpec {
             question: question_id,
-            candidate: candidate_id.clone(),
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
         }];
-
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
-        assert!(response.body().is_some());
         let raw_response = response.into_string().await.unwrap();
-        let first_receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+        let receipt = serde_json::from_str::<Vec<Receipt<Unconfirmed>>>(&raw_response)
             .unwrap()
-            .into_iter()
-            .next()
-            .unwrap();
+            .remove(0);
+        assert_eq!(receipt.state_changed_at, None);
+        assert!(!raw_response.contains("state_changed_at"));
 
-        // Audit the ballot.
-        let ballot_recalls = vec![BallotRecall {
-            ballot_id: first_receipt.ballot_id,
+        // Confirming records when it happened.
+        let before = Utc::now();
+        let recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
             question_id,
-            signature: first_receipt.signature,
+            proof: OwnershipProof::Signature(receipt.signature.clone()),
         }];
         let response = client
-            .post(uri!(audit_ballots(election_id)))
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
-            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .body(serde_json::to_string(&recalls).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::Ok);
-        assert!(response.body().is_some());
-
-        // Ensure the response is a valid receipt.
+        let after = Utc::now();
         let raw_response = response.into_string().await.unwrap();
-        let second_receipt: Receipt<Audited> = serde_json::from_str::<Vec<_>>(&raw_response)
+        let mut confirmed = serde_json::from_str::<Vec<Receipt<Confirmed>>>(&raw_response)
             .unwrap()
-            .into_iter()
-            .next()
-            .unwrap();
-
-        // Ensure the receipts match.
-        let election = Coll::<Election>::from_db(&db)
-            .find_one(u32_id_filter(election_id), None)
+            .remove(0);
+        // The database only stores milliseconds.
+        let changed_at = confirmed.state_changed_at.unwrap();
+        assert!(before - Duration::try_milliseconds(1).unwrap() <= changed_at);
+        assert!(changed_at <= after);
+        let ballot = Coll::<Ballot<Confirmed>>::from_db(&db)
+            .find_one(doc! { "ballot_id": receipt.ballot_id }, None)
             .await
             .unwrap()
             .unwrap();
-        assert_eq!(first_receipt.ballot_id, second_receipt.ballot_id);
-        assert_eq!(first_receipt.crypto.pwf, second_receipt.crypto.pwf);
-        assert_eq!(
-            first_receipt.confirmation_code,
-            second_receipt.confirmation_code
-        );
-        for (candidate, vote1) in first_receipt.crypto.votes.iter() {
-            let vote2 = second_receipt.crypto.votes.get(candidate).unwrap();
-            assert_eq!(vote1.pwf, vote2.pwf);
-            assert_eq!(vote1.R, vote2.R);
-            assert_eq!(vote1.Z, vote2.Z);
-            assert_eq!(vote1.R, election.crypto.g2 * vote2.secrets.r);
-            assert_eq!(
-                vote1.Z,
-                election.crypto.g1 * (vote2.secrets.r + vote2.secrets.v)
-            );
-        }
+        assert_eq!(ballot.state_changed_at.unwrap().to_chrono(), changed_at);
 
-        // Validate PWFs.
-        assert!(second_receipt
+        // The public receipt shows it too.
+        let response = client
+            .get(uri!(crate::api::public::election_question_ballot(
+                election_id,
+                question_id,
+                receipt.ballot_id
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let public: PublicReceipt =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let PublicReceipt::Confirmed(public) = public else {
+            panic!("Ballot should be confirmed");
+        };
+        assert_eq!(public.state_changed_at, Some(changed_at));
+
+        // The time is not signed, so the signature verifies with or without it.
+        assert!(election
             .crypto
-            .verify(
-                election.crypto.g1,
-                election.crypto.g2,
-                second_receipt.ballot_id.to_le_bytes()
-            )
-            .is_ok());
-        // Validate signature.
-        let mut msg = second_receipt.crypto.to_bytes();
-        msg.extend(second_receipt.ballot_id.to_le_bytes());
-        msg.extend(second_receipt.election_id.to_le_bytes());
-        msg.extend(second_receipt.question_id.to_le_bytes());
-        msg.extend(second_receipt.confirmation_code.as_bytes());
-        msg.extend(second_receipt.state.as_ref());
-        msg.extend(Into::<Vec<u8>>::into(&second_receipt.state_data));
+            .public_key
+            .verify(&confirmed.message(), &confirmed.signature));
+        confirmed.state_changed_at = None;
         assert!(election
             .crypto
             .public_key
-            .verify(&msg, &second_receipt.signature));
-
-        // Check the candidate totals weren't affected.
-        let totals: Vec<CandidateTotals> = Coll::<CandidateTotals>::from_db(&db)
-            .find(None, None)
-            .await
-            .unwrap()
-            .try_collect()
-            .await
-            .unwrap();
-        for total in totals.iter() {
-            assert_eq!(total.crypto.r_sum, DreipScalar::zero());
-            assert_eq!(total.crypto.tally, DreipScalar::zero());
-        }
+            .verify(&confirmed.message(), &confirmed.signature));
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn confirm(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
 
@@ -1338,10 +3638,12 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: candidate_id.clone(),
+            write_in_name: None,
         }];
 
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1359,10 +3661,11 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: first_receipt.ballot_id,
             question_id,
-            signature: first_receipt.signature,
+            proof: OwnershipProof::Signature(first_receipt.signature),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
@@ -1401,21 +3704,32 @@ mod tests {
             )
             .is_ok());
         // Validate signature.
-        let mut msg = second_receipt.crypto.to_bytes();
-        msg.extend(second_receipt.ballot_id.to_le_bytes());
-        msg.extend(second_receipt.election_id.to_le_bytes());
-        msg.extend(second_receipt.question_id.to_le_bytes());
-        msg.extend(second_receipt.confirmation_code.as_bytes());
-        msg.extend(second_receipt.state.as_ref());
-        msg.extend(Into::<Vec<u8>>::into(&second_receipt.state_data));
         assert!(election
             .crypto
             .public_key
-            .verify(&msg, &second_receipt.signature));
+            .verify(&second_receipt.message(), &second_receipt.signature));
+
+        // Fold the vote into the totals, rather than waiting for the totals
+        // folder to.
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        fold_totals(
+            election_id,
+            question_id,
+            &Coll::from_db(&db),
+            &Coll::from_db(&db),
+            &mut db_client.start_session(None).await.unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
 
-        // Check the candidate totals are correct.
+        // Check the candidate totals are correct. Every question now has
+        // totals, so only look at the one that was voted on.
         let candidate_totals: Vec<CandidateTotals> = Coll::<CandidateTotals>::from_db(&db)
-            .find(None, None)
+            .find(
+                doc! {"election_id": election_id, "question_id": question_id},
+                None,
+            )
             .await
             .unwrap()
             .try_collect()
@@ -1435,23 +3749,433 @@ mod tests {
             totals.insert(total.candidate_name.clone(), total.into());
         }
         let results = ElectionResults {
+            schema_version: RECEIPT_SCHEMA_VERSION,
             election: ElectionDescription::from(election).crypto,
             audited: HashMap::new(),
+            redacted: HashMap::new(),
             confirmed: ballots,
             totals: Some(totals),
+            skipped: 0,
+            board: None,
+            last_event_seq: 0,
+            dump_signature: None,
+        };
+
+        assert!(results.verify().is_ok());
+
+        // Results from a future schema version are refused.
+        let mut future = results.clone();
+        future.schema_version = RECEIPT_SCHEMA_VERSION + 1;
+        assert_eq!(
+            future.verify(),
+            Err(VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion {
+                    version: RECEIPT_SCHEMA_VERSION + 1
+                }
+            ))
+        );
+
+        // Ensure the question is marked as answered.
+        let response = client.get(uri!(get_allowed(election_id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let allowed: AllowedQuestions = serde_json::from_str(&raw_response).unwrap();
+        assert!(allowed.confirmed[&question_id]);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn confirm_hides_ballots(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let receipt: Receipt<Unconfirmed> =
+            serde_json::from_str::<Vec<_>>(&response.into_string().await.unwrap())
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+        let confirm = |ballot_id: BallotId, signature: Signature| {
+            let ballot_recalls = vec![BallotRecall {
+                ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(signature),
+            }];
+            client
+                .post(uri!(confirm_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_recalls).unwrap())
+                .dispatch()
         };
 
-        assert!(results.verify().is_ok());
+        // A ballot that doesn't exist.
+        let response = confirm(receipt.ballot_id + 1000, receipt.signature).await;
+        let wrong_ballot = (response.status(), response.into_bytes().await);
+
+        // A ballot with the wrong signature.
+        let mut signature = receipt.signature.to_bytes();
+        signature[0] = signature[0].wrapping_add(1);
+        let signature = Signature::from_bytes(&signature).unwrap();
+        let response = confirm(receipt.ballot_id, signature).await;
+        let wrong_signature = (response.status(), response.into_bytes().await);
+
+        // A ballot in an election the voter is no longer joined to.
+        Coll::<VoterElection>::from_db(&db)
+            .delete_many(doc! { "election_id": election_id }, None)
+            .await
+            .unwrap();
+        let response = confirm(receipt.ballot_id, receipt.signature).await;
+        let not_joined = (response.status(), response.into_bytes().await);
+
+        // All look exactly the same.
+        assert_eq!(wrong_ballot.0, Status::NotFound);
+        assert_eq!(wrong_ballot, wrong_signature);
+        assert_eq!(wrong_ballot, not_joined);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_confirm_events(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Capture every event emitted from now on.
+        let (sender, mut receiver) = rocket::tokio::sync::mpsc::unbounded_channel();
+        client
+            .rocket()
+            .state::<EventBus>()
+            .unwrap()
+            .register("test capture", move |record| {
+                let sender = sender.clone();
+                async move {
+                    sender.send(record.event).unwrap();
+                }
+            });
+
+        // Cast and confirm a ballot.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // A rejected request emits nothing.
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_ne!(response.status(), Status::Ok);
+
+        // The events arrive in order.
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let event =
+                rocket::tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            events.push(event);
+        }
+        let ballot_id = receipt.ballot_id;
+        assert_eq!(
+            events,
+            vec![
+                DomainEvent::BallotCast {
+                    election_id,
+                    question_id,
+                    ballot_id,
+                },
+                DomainEvent::BallotConfirmed {
+                    election_id,
+                    question_id,
+                    ballot_id,
+                },
+            ]
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn cast_double_submit(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let cast = |candidate: &str| {
+            let ballot_specs = vec![BallotSpec {
+                question: question_id,
+                candidate: candidate.to_string(),
+                write_in_name: None,
+            }];
+            client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_specs).unwrap())
+                .dispatch()
+        };
+        let receipt = |raw_response: String| {
+            serde_json::from_str::<Vec<Receipt<Unconfirmed>>>(&raw_response)
+                .unwrap()
+                .pop()
+                .unwrap()
+        };
+
+        // Cast a ballot.
+        let response = cast("Chris Riches").await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one(DUPLICATE_CAST_HEADER), None);
+        let first = receipt(response.into_string().await.unwrap());
+
+        // Casting it again straight away gives back the same ballot.
+        let response = cast("Chris Riches").await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.headers().get_one(DUPLICATE_CAST_HEADER),
+            Some("true")
+        );
+        assert_eq!(
+            receipt(response.into_string().await.unwrap()).ballot_id,
+            first.ballot_id
+        );
+        let cast_count = Coll::<Ballot<Unconfirmed>>::from_db(&db)
+            .count_documents(doc! {"election_id": election_id}, None)
+            .await
+            .unwrap();
+        assert_eq!(cast_count, 1);
+
+        // A different vote is cast as normal.
+        let response = cast("Parry Hotter").await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one(DUPLICATE_CAST_HEADER), None);
+        assert_ne!(
+            receipt(response.into_string().await.unwrap()).ballot_id,
+            first.ballot_id
+        );
+
+        // Once the original is audited, casting it again gives a new ballot.
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: first.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(first.signature),
+        }];
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = cast("Chris Riches").await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one(DUPLICATE_CAST_HEADER), None);
+        assert_ne!(
+            receipt(response.into_string().await.unwrap()).ballot_id,
+            first.ballot_id
+        );
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn concurrent_confirms(client: Client, db: Database) {
+        const VOTERS: usize = 50;
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let election = Coll::<Election>::from_db(&db)
+            .find_one(u32_id_filter(election_id), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let candidates = &election.questions[&question_id].candidates;
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        let totals_folder = client.rocket().state::<TotalsFolder>().unwrap();
+        let hmac_secret = client.rocket().state::<Config>().unwrap().hmac_secret();
+        let request_id = RequestId::next();
+
+        // Create voters allowed to vote on the question, each casting one ballot.
+        let new_voters = (0..VOTERS)
+            .map(|_| NewVoter {
+                sms_hmac: rand::random::<[u8; 32]>().to_vec(),
+                frozen: false,
+                freeze: None,
+            })
+            .collect::<Vec<_>>();
+        let voter_ids = Coll::<NewVoter>::from_db(&db)
+            .insert_many(new_voters, None)
+            .await
+            .unwrap()
+            .inserted_ids
+            .into_values()
+            .map(|voter_id| Id::from(voter_id.as_object_id().unwrap()))
+            .collect::<Vec<_>>();
+        let voter_elections = voter_ids.iter().map(|voter_id| VoterElection {
+            voter_id: *voter_id,
+            election_id,
+            allowed_questions: AllowedQuestions {
+                confirmed: HashMap::from_iter(vec![(question_id, false)]),
+            },
+            joins: None,
+            consent: None,
+        });
+        Coll::<VoterElection>::from_db(&db)
+            .insert_many(voter_elections, None)
+            .await
+            .unwrap();
+        let mut voters_and_recalls = Vec::with_capacity(VOTERS);
+        for voter_id in voter_ids {
+            let ballot_specs = vec![BallotSpec {
+                question: question_id,
+                candidate: candidates[voters_and_recalls.len() % candidates.len()].clone(),
+                write_in_name: None,
+            }];
+            let receipt = cast(
+                &election,
+                ballot_specs,
+                &Coll::from_db(&db),
+                &Coll::from_db(&db),
+                &Coll::from_db(&db),
+                &Coll::from_db(&db),
+                db_client,
+                Deadline::after(std::time::Duration::from_secs(30)),
+                request_id,
+            )
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+            let recall = BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            };
+            voters_and_recalls.push((voter_id, recall));
+        }
+        assert_eq!(voters_and_recalls.len(), VOTERS);
+
+        // Count the ballots sequentially, as a single transaction used to.
+        let unconfirmed: Vec<Ballot<Unconfirmed>> = Coll::from_db(&db)
+            .find(
+                doc! { "election_id": election_id, "state": Unconfirmed },
+                None,
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(unconfirmed.len(), VOTERS);
+        let mut expected = candidates
+            .iter()
+            .map(|candidate| (candidate.clone(), dre_ip::CandidateTotals::default()))
+            .collect::<HashMap<_, _>>();
+        for ballot in unconfirmed {
+            let mut totals_map = expected
+                .iter_mut()
+                .map(|(candidate, totals)| (candidate.clone(), totals))
+                .collect::<HashMap<_, _>>();
+            ballot.confirm(&mut totals_map);
+        }
+
+        // Confirm all the ballots concurrently.
+        let confirms = voters_and_recalls.into_iter().map(|(voter_id, recall)| {
+            let election = &election;
+            let db = &db;
+            async move {
+                super::confirm(
+                    voter_id,
+                    election,
+                    &[recall],
+                    &Coll::from_db(db),
+                    &Coll::from_db(db),
+                    &Coll::from_db(db),
+                    &Coll::from_db(db),
+                    totals_folder,
+                    &Coll::from_db(db),
+                    &Coll::from_db(db),
+                    db_client,
+                    hmac_secret,
+                    Deadline::after(std::time::Duration::from_secs(30)),
+                    RequestId::next(),
+                )
+                .await
+            }
+        });
+        for result in join_all(confirms).await {
+            assert_eq!(result.unwrap().len(), 1);
+        }
 
-        // Ensure the question is marked as answered.
-        let response = client.get(uri!(get_allowed(election_id))).dispatch().await;
-        assert_eq!(response.status(), Status::Ok);
-        let raw_response = response.into_string().await.unwrap();
-        let allowed: AllowedQuestions = serde_json::from_str(&raw_response).unwrap();
-        assert!(allowed.confirmed[&question_id]);
+        // Every delta is folded in the background, and the totals match the
+        // sequential count byte for byte.
+        let mut waited = 0;
+        loop {
+            let deltas = Coll::<TotalsDelta>::from_db(&db)
+                .count_documents(doc! { "election_id": election_id }, None)
+                .await
+                .unwrap();
+            if deltas == 0 {
+                break;
+            }
+            assert!(waited < 100, "{deltas} deltas were never folded");
+            rocket::tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            waited += 1;
+        }
+        let totals: Vec<CandidateTotals> = Coll::from_db(&db)
+            .find(
+                doc! { "election_id": election_id, "question_id": question_id },
+                None,
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(totals.len(), candidates.len());
+        for total in totals {
+            assert_eq!(total.confirmed_ballot_count, Some(VOTERS as u64));
+            let expected = &expected[&total.candidate_name];
+            assert_eq!(
+                serde_json::to_vec(&total.crypto).unwrap(),
+                serde_json::to_vec(expected).unwrap()
+            );
+        }
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn bad_casts(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
 
@@ -1459,6 +4183,7 @@ mod tests {
         let ballot_specs: Vec<BallotSpec> = Vec::new();
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1469,9 +4194,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: rand::thread_rng().gen(),
             candidate: "John Smith".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1482,9 +4209,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: "Nobody".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1500,9 +4229,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: "Chris Riches".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(inactive_election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1517,7 +4248,116 @@ mod tests {
         assert_eq!(ballots.try_collect::<Vec<_>>().await.unwrap().len(), 0);
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
+    async fn request_limits(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let config = client.rocket().state::<Config>().unwrap();
+
+        // Try casting more ballots than allowed in one request.
+        let ballot_specs = (0..=config.max_cast_ballots())
+            .map(|_| BallotSpec {
+                question: question_id,
+                candidate: "Chris Riches".to_string(),
+                write_in_name: None,
+            })
+            .collect::<Vec<_>>();
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        assert!(response
+            .into_string()
+            .await
+            .unwrap()
+            .contains("max_cast_ballots"));
+
+        // Cast a single ballot to get a valid recall.
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs[..1]).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let ballot_recall = serde_json::to_value(BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        })
+        .unwrap();
+        let ballot_recalls = vec![ballot_recall; config.max_recall_ballots() + 1];
+
+        // Try auditing and confirming more ballots than allowed in one request.
+        for uri in [
+            uri!(audit_ballots(election_id)),
+            uri!(confirm_ballots(election_id)),
+        ] {
+            let response = client
+                .post(uri)
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_recalls).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::PayloadTooLarge);
+            assert!(response
+                .into_string()
+                .await
+                .unwrap()
+                .contains("max_recall_ballots"));
+        }
+
+        // Try joining or previewing too many electorates, then too many groups.
+        let too_many_electorates: HashMap<String, HashSet<String>> = (0..=config
+            .max_join_electorates())
+            .map(|i| (format!("Electorate {i}"), HashSet::new()))
+            .collect();
+        let too_many_groups: HashMap<String, HashSet<String>> = HashMap::from_iter(vec![(
+            "Societies".to_string(),
+            (0..=config.max_join_groups())
+                .map(|i| format!("Group {i}"))
+                .collect(),
+        )]);
+        for (joins, option) in [
+            (too_many_electorates, "max_join_electorates"),
+            (too_many_groups, "max_join_groups"),
+        ] {
+            for uri in [
+                uri!(join_election(election_id)),
+                uri!(eligibility_preview(election_id)),
+            ] {
+                let response = client
+                    .post(uri)
+                    .csrf()
+                    .header(ContentType::JSON)
+                    .body(serde_json::to_string(&joins).unwrap())
+                    .dispatch()
+                    .await;
+                assert_eq!(response.status(), Status::PayloadTooLarge);
+                assert!(response.into_string().await.unwrap().contains(option));
+            }
+        }
+
+        // Ensure only the single ballot was cast.
+        let ballots = Coll::<Ballot<Unconfirmed>>::from_db(&db)
+            .find(None, None)
+            .await
+            .unwrap();
+        assert_eq!(ballots.try_collect::<Vec<_>>().await.unwrap().len(), 1);
+    }
+
+    #[backend_test(voter, db = "isolated")]
     async fn bad_votes(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
 
@@ -1536,9 +4376,11 @@ mod tests {
                 .unwrap()
                 .candidates[0]
                 .clone(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(inactive_election.id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1548,6 +4390,7 @@ mod tests {
         // Vote on a non-existent election.
         let response = client
             .post(uri!(cast_ballots(rand::thread_rng().gen::<u32>())))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1558,9 +4401,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: rand::thread_rng().gen(),
             candidate: "Alice".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1586,9 +4431,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: not_allowed_question,
             candidate: "Alice".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1599,9 +4446,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: "Alice".to_string(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1613,9 +4462,11 @@ mod tests {
         let ballot_specs = vec![BallotSpec {
             question: question_id,
             candidate: candidate_id.clone(),
+            write_in_name: None,
         }];
         let response = client
             .post(uri!(cast_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_specs).unwrap())
             .dispatch()
@@ -1633,10 +4484,11 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: rand::thread_rng().gen(),
             question_id,
-            signature: first_receipt.signature,
+            proof: OwnershipProof::Signature(first_receipt.signature),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
@@ -1647,66 +4499,435 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: first_receipt.ballot_id,
             question_id: rand::thread_rng().gen(),
-            signature: first_receipt.signature,
+            proof: OwnershipProof::Signature(first_receipt.signature),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Try to confirm the wrong signature.
+        let mut signature = first_receipt.signature.to_bytes();
+        signature[0] = signature[0].wrapping_add(1);
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: first_receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(Signature::from_bytes(&signature).unwrap()),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Correctly confirm.
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: first_receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(first_receipt.signature),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // Try to confirm again.
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Try to audit after confirming.
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
             .await;
         assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn recall_by_recall_code(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let config = client.rocket().state::<Config>().unwrap();
+
+        // Cast a vote.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+
+        // Giving both a signature and a code, or neither, is rejected.
+        let mut both = serde_json::to_value(BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature.clone()),
+        })
+        .unwrap();
+        let recall_code = receipt.recall_code.clone().unwrap();
+        both["recall_code"] = recall_code.clone().into();
+        let mut neither = both.clone();
+        neither.as_object_mut().unwrap().remove("signature");
+        neither.as_object_mut().unwrap().remove("recall_code");
+        for recall in [both, neither] {
+            let response = client
+                .post(uri!(confirm_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&vec![recall]).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::UnprocessableEntity);
+        }
+
+        // Try to confirm with the wrong code. Codes are base32, so never contain '1'.
+        let wrong_code = format!("1{}", &recall_code[1..]);
+        let wrong_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::RecallCode(wrong_code),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&wrong_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // Correctly confirm with the code.
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::RecallCode(recall_code),
+        }];
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let confirmed: Vec<Receipt<Confirmed>> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].ballot_id, receipt.ballot_id);
+        assert_eq!(confirmed[0].confirmation_code, receipt.confirmation_code);
+        assert_eq!(confirmed[0].recall_code, None);
+
+        // Code recalls are rate-limited, counting the two above.
+        for _ in 2..config.max_code_recalls() {
+            let response = client
+                .post(uri!(audit_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&wrong_recalls).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::NotFound);
+        }
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&wrong_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert!(response
+            .into_string()
+            .await
+            .unwrap()
+            .contains("max_code_recalls"));
+
+        // Each rejection is recorded against the voter, without their ID.
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&wrong_recalls).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::TooManyRequests);
+        let voter = Coll::<Voter>::from_db(&db)
+            .find_one(
+                doc! {"sms_hmac": Sms::example_hmac(&client).to_bytestring()},
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let events: Vec<RateLimitEvent> = Coll::<RateLimitEvent>::from_db(&db)
+            .find(None, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        for event in &events {
+            assert_eq!(event.limiter, CODE_RECALL_LIMITER);
+            assert_eq!(event.key_hash, hash_key(&voter.id.to_string()));
+        }
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn recall_code_is_private(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+        let config = client.rocket().state::<Config>().unwrap();
+
+        // Cast a vote.
+        let ballot_specs = vec![BallotSpec {
+            question: question_id,
+            candidate: "Chris Riches".to_string(),
+            write_in_name: None,
+        }];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let recall_code = receipt.recall_code.clone().unwrap();
+
+        // Anyone can read the confirmation code, but not the recall code.
+        let response = client
+            .get(uri!(crate::api::public::election_question_ballot(
+                election_id,
+                question_id,
+                receipt.ballot_id
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        assert!(!raw_response.contains(&recall_code));
+        let public: PublicReceipt = serde_json::from_str(&raw_response).unwrap();
+        let PublicReceipt::Unconfirmed(public) = public else {
+            panic!("Ballot should be unconfirmed");
+        };
+
+        // Another voter joins the election, able to answer the same question.
+        let other = NewVoter::new("+441234567800".parse().unwrap(), config);
+        let result = Coll::<NewVoter>::from_db(&db)
+            .insert_one(&other, None)
+            .await
+            .unwrap();
+        let other = Voter {
+            id: result.inserted_id.as_object_id().unwrap().into(),
+            voter: other,
+        };
+        Coll::<VoterElection>::from_db(&db)
+            .insert_one(
+                VoterElection {
+                    voter_id: other.id,
+                    election_id,
+                    allowed_questions: AllowedQuestions {
+                        confirmed: HashMap::from_iter(vec![(question_id, false)]),
+                    },
+                    joins: None,
+                    consent: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let as_other = |uri, body: String| {
+            let csrf = csrf_cookie(config);
+            client
+                .post(uri)
+                .cookie(AuthToken::new(&other).into_cookie(config))
+                .header(Header::new(CSRF_HEADER, csrf.value().to_string()))
+                .cookie(csrf)
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+        };
+
+        // The confirmation code is no longer accepted as proof of ownership.
+        let old_recalls = serde_json::json!([{
+            "ballot_id": receipt.ballot_id,
+            "question_id": question_id,
+            "confirmation_code": &public.confirmation_code,
+        }]);
+        let response = as_other(uri!(confirm_ballots(election_id)), old_recalls.to_string()).await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        // Nor is it a recall code, and even the real recall code only works
+        // for the voter who cast the ballot.
+        for code in [public.confirmation_code.clone(), recall_code.clone()] {
+            let recalls = serde_json::to_string(&vec![BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::RecallCode(code),
+            }])
+            .unwrap();
+            for uri in [
+                uri!(confirm_ballots(election_id)),
+                uri!(audit_ballots(election_id)),
+            ] {
+                let response = as_other(uri, recalls.clone()).await;
+                assert_eq!(response.status(), Status::NotFound);
+            }
+        }
+
+        // The ballot is untouched, and the other voter has still not voted.
+        let ballot = Coll::<AnyBallot>::from_db(&db)
+            .find_one(doc! { "ballot_id": receipt.ballot_id }, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(ballot, AnyBallot::Unconfirmed(_)));
+        let allowed =
+            VoterElection::allowed_questions(&Coll::from_db(&db), other.id, election_id, None)
+                .await
+                .unwrap()
+                .unwrap();
+        assert!(!allowed.confirmed[&question_id]);
 
-        // Try to confirm the wrong signature.
-        let mut signature = first_receipt.signature.to_bytes();
-        signature[0] = signature[0].wrapping_add(1);
+        // The voter who cast it can still confirm it with their recall code.
         let ballot_recalls = vec![BallotRecall {
-            ballot_id: first_receipt.ballot_id,
+            ballot_id: receipt.ballot_id,
             question_id,
-            signature: Signature::from_bytes(&signature).unwrap(),
+            proof: OwnershipProof::RecallCode(recall_code),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.status(), Status::Ok);
+    }
 
-        // Correctly confirm.
-        let ballot_recalls = vec![BallotRecall {
-            ballot_id: first_receipt.ballot_id,
-            question_id,
-            signature: first_receipt.signature,
-        }];
-        let response = client
-            .post(uri!(confirm_ballots(election_id)))
-            .header(ContentType::JSON)
-            .body(serde_json::to_string(&ballot_recalls).unwrap())
-            .dispatch()
-            .await;
+    #[backend_test(voter, db = "isolated")]
+    async fn question_close(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        let cast = || async {
+            let ballot_specs = vec![BallotSpec {
+                question: question_id,
+                candidate: "Chris Riches".to_string(),
+                write_in_name: None,
+            }];
+            client
+                .post(uri!(cast_ballots(election_id)))
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_specs).unwrap())
+                .dispatch()
+                .await
+        };
+        let set_close = |end_time: DateTime| {
+            let db = &db;
+            async move {
+                let field = format!("questions.{question_id}.end_time");
+                Coll::<Election>::from_db(db)
+                    .update_one(
+                        u32_id_filter(election_id),
+                        doc! { "$set": { field: end_time } },
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+        };
+
+        // Before the question closes, we can vote on it.
+        set_close(DateTime::from_chrono(
+            Utc::now() + Duration::try_minutes(1).unwrap(),
+        ))
+        .await;
+        let response = cast().await;
         assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipt: Receipt<Unconfirmed> = serde_json::from_str::<Vec<_>>(&raw_response)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
 
-        // Try to confirm again.
-        let response = client
-            .post(uri!(confirm_ballots(election_id)))
-            .header(ContentType::JSON)
-            .body(serde_json::to_string(&ballot_recalls).unwrap())
-            .dispatch()
-            .await;
+        // Once it has closed, we can't, even though the election is still open.
+        set_close(DateTime::from_chrono(
+            Utc::now() - Duration::try_minutes(1).unwrap(),
+        ))
+        .await;
+        let response = cast().await;
         assert_eq!(response.status(), Status::NotFound);
+        let ballot_recalls = vec![BallotRecall {
+            ballot_id: receipt.ballot_id,
+            question_id,
+            proof: OwnershipProof::Signature(receipt.signature),
+        }];
+        for uri in [
+            uri!(audit_ballots(election_id)),
+            uri!(confirm_ballots(election_id)),
+        ] {
+            let response = client
+                .post(uri)
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_recalls).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::NotFound);
+        }
 
-        // Try to audit after confirming.
+        // The close time is published with the question.
         let response = client
-            .post(uri!(audit_ballots(election_id)))
-            .header(ContentType::JSON)
-            .body(serde_json::to_string(&ballot_recalls).unwrap())
+            .get(format!("/elections/{}", election_id))
             .dispatch()
             .await;
-        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.status(), Status::Ok);
+        let description: ElectionDescription =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(description.questions[&question_id].end_time.unwrap() < Utc::now());
     }
 
-    #[backend_test(voter)]
+    #[backend_test(voter, db = "isolated")]
     async fn cant_vote_twice(client: Client, db: Database) {
         let (election_id, question_id) = insert_test_data(&client, &db).await;
 
@@ -1720,9 +4941,11 @@ mod tests {
             let ballot_specs = vec![BallotSpec {
                 question: question_id,
                 candidate: candidate_id.clone(),
+                write_in_name: None,
             }];
             let response = client
                 .post(uri!(cast_ballots(election_id)))
+                .csrf()
                 .header(ContentType::JSON)
                 .body(serde_json::to_string(&ballot_specs).unwrap())
                 .dispatch()
@@ -1742,10 +4965,11 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: receipt.ballot_id,
             question_id,
-            signature: receipt.signature,
+            proof: OwnershipProof::Signature(receipt.signature),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
@@ -1757,10 +4981,11 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: receipt.ballot_id,
             question_id,
-            signature: receipt.signature,
+            proof: OwnershipProof::Signature(receipt.signature),
         }];
         let response = client
             .post(uri!(audit_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
@@ -1772,10 +4997,11 @@ mod tests {
         let ballot_recalls = vec![BallotRecall {
             ballot_id: receipt.ballot_id,
             question_id,
-            signature: receipt.signature,
+            proof: OwnershipProof::Signature(receipt.signature),
         }];
         let response = client
             .post(uri!(confirm_ballots(election_id)))
+            .csrf()
             .header(ContentType::JSON)
             .body(serde_json::to_string(&ballot_recalls).unwrap())
             .dispatch()
@@ -1802,4 +5028,410 @@ mod tests {
             .unwrap();
         assert_eq!(confirmed_num, 1);
     }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn receipt_bundle(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Cast two ballots.
+        let ballot_specs = vec![
+            BallotSpec {
+                question: question_id,
+                candidate: "Chris Riches".to_string(),
+                write_in_name: None,
+            },
+            BallotSpec {
+                question: question_id,
+                candidate: "Parry Hotter".to_string(),
+                write_in_name: None,
+            },
+        ];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipts: Vec<Receipt<Unconfirmed>> = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(receipts.len(), 2);
+
+        // Audit the first and confirm the second.
+        for (receipt, endpoint) in [
+            (&receipts[0], uri!(audit_ballots(election_id))),
+            (&receipts[1], uri!(confirm_ballots(election_id))),
+        ] {
+            let ballot_recalls = vec![BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            }];
+            let response = client
+                .post(endpoint)
+                .csrf()
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&ballot_recalls).unwrap())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let ballot_refs = receipts
+            .iter()
+            .map(|receipt| BallotReference {
+                ballot_id: receipt.ballot_id,
+                question_id,
+            })
+            .collect::<Vec<_>>();
+
+        // We can't get a bundle while the election is still running.
+        let response = client
+            .post(uri!(receipt_bundle(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_refs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        // End the election.
+        Coll::<Election>::from_db(&db)
+            .update_one(
+                u32_id_filter(election_id),
+                doc! {"$set": {"end_time": Utc::now() - Duration::try_minutes(1).unwrap()}},
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Get the bundle.
+        let response = client
+            .post(uri!(receipt_bundle(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_refs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let bundle: ReceiptBundle = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(bundle.election_id, election_id);
+        assert_eq!(bundle.receipts.len(), 2);
+        assert!(matches!(bundle.receipts[0], PublicReceipt::Audited(_)));
+        assert!(matches!(bundle.receipts[1], PublicReceipt::Confirmed(_)));
+        assert_eq!(bundle.verify(), Ok(()));
+
+        // Tamper with one of the receipts.
+        let mut tampered = bundle.clone();
+        if let PublicReceipt::Confirmed(receipt) = &mut tampered.receipts[1] {
+            receipt.confirmation_code = receipts[0].confirmation_code.clone();
+        }
+        assert_eq!(
+            tampered.verify(),
+            Err(VerificationError::Receipt(ReceiptError::ConfirmationCode {
+                ballot_id: receipts[1].ballot_id
+            }))
+        );
+
+        // Receipts from a future schema version are refused.
+        let mut future = bundle.clone();
+        if let PublicReceipt::Audited(receipt) = &mut future.receipts[0] {
+            receipt.schema_version = RECEIPT_SCHEMA_VERSION + 1;
+        }
+        assert_eq!(
+            future.receipts[0].schema_version(),
+            RECEIPT_SCHEMA_VERSION + 1
+        );
+        assert_eq!(
+            future.verify(),
+            Err(VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion {
+                    version: RECEIPT_SCHEMA_VERSION + 1
+                }
+            ))
+        );
+
+        // Tamper with the bundle itself.
+        let mut tampered = bundle;
+        tampered.receipts.pop();
+        assert_eq!(tampered.verify(), Err(VerificationError::BundleSignature));
+
+        // Unknown ballots are rejected.
+        let ballot_refs = vec![BallotReference {
+            ballot_id: rand::thread_rng().gen(),
+            question_id,
+        }];
+        let response = client
+            .post(uri!(receipt_bundle(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_refs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn dump_changes(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Cast two ballots and audit one.
+        let ballot_specs = vec![
+            BallotSpec {
+                question: question_id,
+                candidate: "Chris Riches".to_string(),
+                write_in_name: None,
+            },
+            BallotSpec {
+                question: question_id,
+                candidate: "Parry Hotter".to_string(),
+                write_in_name: None,
+            },
+        ];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipts: Vec<Receipt<Unconfirmed>> = serde_json::from_str(&raw_response).unwrap();
+        let recalls = receipts
+            .iter()
+            .map(|receipt| BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            })
+            .collect::<Vec<_>>();
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&recalls[..1]).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // Take a baseline from the full dump.
+        let response = client
+            .get(format!("/elections/{}/{}/dump", election_id, question_id))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let baseline: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(baseline.last_event_seq, 3);
+        assert_eq!(baseline.audited.len(), 1);
+        let changes_uri = |since: u64| {
+            format!(
+                "/elections/{}/{}/dump/changes?since={}",
+                election_id, question_id, since
+            )
+        };
+
+        // Nothing has changed since.
+        let response = client
+            .get(changes_uri(baseline.last_event_seq))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let changes: ElectionResultsChanges = serde_json::from_str(&raw_response).unwrap();
+        assert!(changes.audited.is_empty());
+        assert!(changes.confirmed.is_empty());
+        assert_eq!(changes.last_event_seq, baseline.last_event_seq);
+
+        // Confirm the other ballot.
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&recalls[1..]).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // Only that ballot has changed, to confirmed.
+        let response = client
+            .get(changes_uri(baseline.last_event_seq))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let changes: ElectionResultsChanges = serde_json::from_str(&raw_response).unwrap();
+        assert!(changes.audited.is_empty());
+        assert_eq!(changes.confirmed.len(), 1);
+        let confirmed = &changes.confirmed[&receipts[1].ballot_id];
+        assert_eq!(confirmed.ballot_id, receipts[1].ballot_id);
+        assert_eq!(confirmed.crypto, receipts[1].crypto);
+        assert_eq!(changes.last_event_seq, baseline.last_event_seq + 1);
+
+        // Everything published is included from the start.
+        let response = client.get(changes_uri(0)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let changes: ElectionResultsChanges = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(changes.audited.len(), 1);
+        assert_eq!(changes.confirmed.len(), 1);
+
+        // Unknown questions are not found.
+        let response = client
+            .get(format!(
+                "/elections/{}/{}/dump/changes?since=0",
+                election_id,
+                rand::thread_rng().gen::<u32>()
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[backend_test(voter, db = "isolated")]
+    async fn board_chain(client: Client, db: Database) {
+        let (election_id, question_id) = insert_test_data(&client, &db).await;
+
+        // Cast two ballots, audit one and confirm the other.
+        let ballot_specs = vec![
+            BallotSpec {
+                question: question_id,
+                candidate: "Chris Riches".to_string(),
+                write_in_name: None,
+            },
+            BallotSpec {
+                question: question_id,
+                candidate: "Parry Hotter".to_string(),
+                write_in_name: None,
+            },
+        ];
+        let response = client
+            .post(uri!(cast_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&ballot_specs).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let receipts: Vec<Receipt<Unconfirmed>> = serde_json::from_str(&raw_response).unwrap();
+        let recalls = receipts
+            .iter()
+            .map(|receipt| BallotRecall {
+                ballot_id: receipt.ballot_id,
+                question_id,
+                proof: OwnershipProof::Signature(receipt.signature),
+            })
+            .collect::<Vec<_>>();
+        let response = client
+            .post(uri!(audit_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&recalls[..1]).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client
+            .post(uri!(confirm_ballots(election_id)))
+            .csrf()
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&recalls[1..]).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // The dump should contain the chain, in order, and verify.
+        let dump_uri = format!("/elections/{}/{}/dump", election_id, question_id);
+        let response = client.get(dump_uri.clone()).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let results: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        let board = results.board.clone().unwrap();
+        assert_eq!(board.length, 4);
+        let expected = [
+            (receipts[0].ballot_id, "Unconfirmed"),
+            (receipts[1].ballot_id, "Unconfirmed"),
+            (receipts[0].ballot_id, "Audited"),
+            (receipts[1].ballot_id, "Confirmed"),
+        ];
+        for (event, (ballot_id, state)) in board.events.iter().zip(expected) {
+            assert_eq!(event.ballot_id, ballot_id);
+            assert_eq!(event.state, state);
+        }
+        assert_eq!(results.verify(), Ok(()));
+
+        // The published head should match.
+        let response = client
+            .get(format!(
+                "/elections/{}/{}/board-head",
+                election_id, question_id
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let raw_response = response.into_string().await.unwrap();
+        let head: BoardHeadDesc = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(head.length, board.length);
+        assert_eq!(head.head, board.head);
+
+        // Tamper with a stored event and check it is caught.
+        let board_entries = Coll::<BoardEntry>::from_db(&db);
+        let entry_filter = |position: i64| {
+            doc! {
+                "election_id": election_id,
+                "question_id": question_id,
+                "position": position,
+            }
+        };
+        let update = doc! {
+            "$set": {
+                "confirmation_code": &receipts[0].confirmation_code,
+            }
+        };
+        let result = board_entries
+            .update_one(entry_filter(1), update, None)
+            .await
+            .unwrap();
+        assert_eq!(result.modified_count, 1);
+        let response = client.get(dump_uri.clone()).dispatch().await;
+        let raw_response = response.into_string().await.unwrap();
+        let tampered: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(
+            tampered.verify(),
+            Err(VerificationError::Board(BoardError::Hash { position: 1 }))
+        );
+
+        // Restore it, then delete an event instead.
+        let update = doc! {
+            "$set": {
+                "confirmation_code": &receipts[1].confirmation_code,
+            }
+        };
+        board_entries
+            .update_one(entry_filter(1), update, None)
+            .await
+            .unwrap();
+        let result = board_entries
+            .delete_one(entry_filter(2), None)
+            .await
+            .unwrap();
+        assert_eq!(result.deleted_count, 1);
+        let response = client.get(dump_uri).dispatch().await;
+        let raw_response = response.into_string().await.unwrap();
+        let tampered: ElectionResults = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(
+            tampered.verify(),
+            Err(VerificationError::Board(BoardError::Position {
+                position: 2
+            }))
+        );
+        // The tampered chain no longer extends the original.
+        assert_eq!(
+            tampered.board.unwrap().extends(&board),
+            Err(BoardError::Diverged { position: 2 })
+        );
+    }
 }
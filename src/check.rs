@@ -0,0 +1,216 @@
+use std::fmt::{self, Display, Formatter};
+
+use mongodb::Database;
+use rocket::figment::Figment;
+
+use crate::{
+    config::{AwsFairing, Config, DatabaseFairing},
+    model::{
+        db::{
+            election::ElectionFinalizers,
+            hmac_check::{mismatch_message, HmacCheck, HmacStatus},
+        },
+        mongodb::Coll,
+    },
+};
+
+/// The outcome of a single startup check.
+#[derive(Debug)]
+pub struct CheckOutcome {
+    /// Short name of what was checked.
+    pub name: &'static str,
+    /// A description of what was found on success, or what went wrong on failure.
+    pub result: Result<String, String>,
+}
+
+/// The outcomes of all startup checks, in the order they were run.
+#[derive(Debug)]
+pub struct CheckReport {
+    pub outcomes: Vec<CheckOutcome>,
+}
+
+impl CheckReport {
+    /// Did every check pass?
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.result.is_ok())
+    }
+
+    /// The process exit code to report: 0 if every check passed, otherwise 1.
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+impl Display for CheckReport {
+    /// Format the report as a table, one check per line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<12}{:<8}DETAIL", "CHECK", "RESULT")?;
+        for outcome in &self.outcomes {
+            let (result, detail) = match &outcome.result {
+                Ok(detail) => ("PASS", detail),
+                Err(detail) => ("FAIL", detail),
+            };
+            writeln!(f, "{:<12}{:<8}{}", outcome.name, result, detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the same checks as ignition, using the same code paths as the
+/// fairings, without launching the server.
+///
+/// If `aws_dry_run` is set, the AWS config is loaded but AWS is not contacted.
+pub async fn run_checks(figment: &Figment, aws_dry_run: bool) -> CheckReport {
+    check_all(figment, aws_dry_run).await.0
+}
+
+/// Run all checks, also returning the database if the connection succeeded.
+async fn check_all(figment: &Figment, aws_dry_run: bool) -> (CheckReport, Option<Database>) {
+    let mut outcomes = Vec::new();
+
+    // Config.
+    let config = Config::from_figment(figment);
+    outcomes.push(CheckOutcome {
+        name: "config",
+        result: config
+            .as_ref()
+            .map(|_| "Application config is valid".to_string())
+            .map_err(|e| format!("Failed to load application config: {e}")),
+    });
+
+    // Database.
+    let db = match DatabaseFairing::probe(figment).await {
+        Ok((_, db, _, _)) => {
+            outcomes.push(CheckOutcome {
+                name: "database",
+                result: Ok(format!(
+                    "Connected to database `{}` and ensured indexes exist",
+                    db.name()
+                )),
+            });
+            Some(db)
+        }
+        Err(e) => {
+            outcomes.push(CheckOutcome {
+                name: "database",
+                result: Err(e.to_string()),
+            });
+            None
+        }
+    };
+
+    // HMAC secret.
+    let hmac = match (&config, &db) {
+        (Ok(config), Some(db)) => HmacCheck::status(&Coll::from_db(db), config.hmac_secret())
+            .await
+            .map_err(|e| format!("Failed to check `hmac_secret`: {e}"))
+            .and_then(|status| match status {
+                HmacStatus::Missing => Ok("`hmac_secret` will be recorded at launch".to_string()),
+                HmacStatus::Matches(_) => Ok("`hmac_secret` is unchanged".to_string()),
+                HmacStatus::Mismatch(_) if config.accept_hmac_rotation() => {
+                    Ok("`hmac_secret` has changed, and the change will be accepted".to_string())
+                }
+                HmacStatus::Mismatch(check) => Err(mismatch_message(&check)),
+            }),
+        (Err(_), _) => Err("Application config was not available".to_string()),
+        (_, None) => Err("Database was not available".to_string()),
+    };
+    outcomes.push(CheckOutcome {
+        name: "hmac",
+        result: hmac,
+    });
+
+    // AWS.
+    let aws = match AwsFairing::probe(figment) {
+        Ok(sms_router) => {
+            let route_count = sms_router.routes().len();
+            if aws_dry_run {
+                Ok(format!("Loaded {route_count} SMS routes (dry run)"))
+            } else {
+                sms_router
+                    .check_credentials()
+                    .await
+                    .map(|_| format!("Credentials accepted for {route_count} SMS routes"))
+            }
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    outcomes.push(CheckOutcome {
+        name: "aws",
+        result: aws,
+    });
+
+    // Finalizers.
+    let finalizers = match &db {
+        Some(db) => ElectionFinalizers::elections_needing_finalizers(db)
+            .await
+            .map(|elections| format!("{} elections need finalizers", elections.len()))
+            .map_err(|e| format!("Failed to enumerate election finalizers: {e}")),
+        None => Err("Database was not available".to_string()),
+    };
+    outcomes.push(CheckOutcome {
+        name: "finalizers",
+        result: finalizers,
+    });
+
+    (CheckReport { outcomes }, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::tokio;
+
+    use super::*;
+
+    /// Run the checks, dropping any database they created.
+    fn check(figment: Figment) -> CheckReport {
+        crate::logging::init_test_logging();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (report, db) = check_all(&figment, true).await;
+            if let Some(db) = db {
+                db.drop(None).await.unwrap();
+            }
+            report
+        })
+    }
+
+    #[test]
+    fn checks_pass() {
+        let report = check(rocket::Config::figment());
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.exit_code(), 0);
+
+        let output = report.to_string();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("CHECK       RESULT  DETAIL"));
+        let names = ["config", "database", "hmac", "aws", "finalizers"];
+        for (line, name) in lines[1..].iter().zip(names) {
+            assert!(line.starts_with(&format!("{name:<12}PASS    ")), "{line}");
+        }
+        assert!(lines[3].ends_with("will be recorded at launch"));
+        assert!(lines[4].ends_with("(dry run)"));
+        assert!(lines[5].ends_with("0 elections need finalizers"));
+    }
+
+    #[test]
+    fn checks_fail() {
+        let figment = rocket::Config::figment().merge(("db_uri", "not a database"));
+        let report = check(figment);
+        assert!(!report.passed());
+        assert_eq!(report.exit_code(), 1);
+
+        let output = report.to_string();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with("config      PASS    "));
+        assert!(lines[2].starts_with("database    FAIL    "));
+        assert!(lines[3].starts_with("hmac        FAIL    Database was not available"));
+        assert!(lines[4].starts_with("aws         PASS    "));
+        assert!(lines[5].starts_with("finalizers  FAIL    Database was not available"));
+    }
+}
@@ -1,23 +1,52 @@
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
 use aws_config::{BehaviorVersion, SdkConfig};
 use aws_credential_types::provider::SharedCredentialsProvider;
+#[cfg(not(test))]
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client as S3Client;
+#[cfg(all(feature = "otp", not(test)))]
+use aws_sdk_sns::types::MessageAttributeValue;
 use aws_sdk_sns::{
     config::{Credentials, Region},
     Client as SnsClient,
 };
 use chrono::Duration;
-use mongodb::Client as MongoClient;
+use ipnet::IpNet;
+use mongodb::{options::ClientOptions, Client as MongoClient, Database};
+use phonenumber::country;
 use rocket::futures::TryFutureExt;
 use rocket::{
     fairing::{Fairing, Info, Kind},
+    figment::{Error as FigmentError, Figment},
     Build, Rocket,
 };
 use serde::Deserialize;
 
 use crate::model::{
-    db::admin::ensure_admin_exists,
-    mongodb::{ensure_election_id_counter_exists, ensure_indexes_exist, Coll},
+    api::sms::{match_route, Sms, SmsRoute, SmsTemplates, DEFAULT_LANGUAGE},
+    common::secret::SecretString,
+    db::{
+        admin::ensure_admin_exists,
+        ballot::{ensure_audited_candidates_exist, ensure_confirmation_phrases_exist},
+        candidate_totals::{ensure_confirmed_counts_exist, ensure_published_totals_exist},
+        challenge_delivery::SmsOutcome,
+        election::ensure_question_slugs_exist,
+        field_encryption::{self, FieldKey},
+        maintenance::ensure_archive_times_exist,
+        voter_election::ensure_voter_elections_split,
+    },
+    mongodb::{
+        ensure_election_id_counter_consistent, ensure_election_id_counter_exists,
+        ensure_indexes_exist, ClockSkewMonitor, Coll, CommandMonitor, SkewThresholds,
+    },
 };
 
+#[cfg(all(feature = "otp", not(test)))]
+use crate::model::db::challenge_delivery::DeliveryStatus;
+
 /// Application configuration, derived from `Rocket.toml` and `ROCKET_*`
 /// environment variables. This struct becomes managed state and can be
 /// inspected by any endpoint.
@@ -27,13 +56,131 @@ pub struct Config {
     hostname: String,
     otp_ttl: u32,
     auth_ttl: u32,
+    #[serde(default = "default_step_up_ttl")]
+    step_up_ttl: u32,
+    #[serde(default)]
+    sms_templates: SmsTemplates,
+    #[serde(default = "default_sms_language")]
+    sms_default_language: String,
+    #[serde(default)]
+    allowed_sms_prefixes: Vec<String>,
+    #[serde(default)]
+    default_sms_region: Option<String>,
+    #[serde(default = "default_max_sms_delivery_failures")]
+    max_sms_delivery_failures: u32,
+    #[serde(default = "default_sms_queue_enabled")]
+    sms_queue_enabled: bool,
+    #[serde(default = "default_sms_queue_capacity")]
+    sms_queue_capacity: u32,
+    #[serde(default = "default_sms_queue_senders")]
+    sms_queue_senders: u32,
+    #[serde(default = "default_secure_cookies")]
+    secure_cookies: bool,
+    #[serde(default)]
+    cookie_domain: Option<String>,
+    #[serde(default = "default_cookie_path")]
+    cookie_path: String,
+    #[serde(default = "default_maintenance_enabled")]
+    maintenance_enabled: bool,
+    #[serde(default = "default_maintenance_retention_days")]
+    maintenance_retention_days: u16,
+    #[serde(default)]
+    audited_secret_retention_days: Option<u32>,
+    #[serde(default = "default_max_cast_ballots")]
+    max_cast_ballots: u32,
+    #[serde(default = "default_max_recall_ballots")]
+    max_recall_ballots: u32,
+    #[serde(default = "default_max_code_recalls")]
+    max_code_recalls: u32,
+    #[serde(default = "default_max_join_electorates")]
+    max_join_electorates: u32,
+    #[serde(default = "default_max_join_groups")]
+    max_join_groups: u32,
+    #[serde(default = "default_end_warning_lead_minutes")]
+    end_warning_lead_minutes: u32,
+    #[serde(default = "default_end_warning_threshold")]
+    end_warning_threshold: u64,
+    #[serde(default)]
+    admin_webhook_url: Option<String>,
+    #[serde(default = "default_rehearsal_ttl_minutes")]
+    rehearsal_ttl_minutes: u32,
+    #[serde(default)]
+    trusted_proxies: Vec<IpNet>,
+    #[serde(default = "default_public_query_timeout_ms")]
+    public_query_timeout_ms: u32,
+    #[serde(default = "default_vote_request_timeout_ms")]
+    vote_request_timeout_ms: u32,
+    #[serde(default = "default_max_voter_import_bytes")]
+    max_voter_import_bytes: u32,
+    #[serde(default = "default_duplicate_cast_window_secs")]
+    duplicate_cast_window_secs: u32,
+    #[serde(default)]
+    archive_bucket: Option<String>,
+    #[serde(default)]
+    archive_prefix: String,
+    #[serde(default)]
+    accept_hmac_rotation: bool,
     // secrets
-    jwt_secret: String,
-    recaptcha_secret: String,
-    hmac_secret: String,
+    jwt_secret: SecretString,
+    recaptcha_secret: SecretString,
+    hmac_secret: SecretString,
+    #[serde(default)]
+    field_encryption_key: Option<SecretString>,
 }
 
 impl Config {
+    /// Load the config from the given figment.
+    pub fn from_figment(figment: &Figment) -> Result<Self, FigmentError> {
+        let config: Self = figment.extract()?;
+
+        // Request limits of zero would reject every request.
+        let limits = [
+            ("max_cast_ballots", config.max_cast_ballots),
+            ("max_recall_ballots", config.max_recall_ballots),
+            ("max_code_recalls", config.max_code_recalls),
+            ("max_join_electorates", config.max_join_electorates),
+            ("max_join_groups", config.max_join_groups),
+            (
+                "max_sms_delivery_failures",
+                config.max_sms_delivery_failures,
+            ),
+            ("sms_queue_capacity", config.sms_queue_capacity),
+            ("sms_queue_senders", config.sms_queue_senders),
+            ("public_query_timeout_ms", config.public_query_timeout_ms),
+            ("vote_request_timeout_ms", config.vote_request_timeout_ms),
+            ("max_voter_import_bytes", config.max_voter_import_bytes),
+        ];
+        for (name, limit) in limits {
+            if limit == 0 {
+                return Err(FigmentError::from(format!("`{name}` must be at least 1")));
+            }
+        }
+
+        if let Some(region) = &config.default_sms_region {
+            if region.parse::<country::Id>().is_err() {
+                return Err(FigmentError::from(format!(
+                    "`default_sms_region` must be a two-letter region code, e.g. GB, not '{region}'"
+                )));
+            }
+        }
+
+        // Browsers ignore a cookie path that is not absolute.
+        if !config.cookie_path.starts_with('/') {
+            return Err(FigmentError::from(
+                "`cookie_path` must start with '/'".to_string(),
+            ));
+        }
+
+        // A malformed key would otherwise only be noticed when first used.
+        if let Some(Err(e)) = config.field_encryption_key() {
+            return Err(FigmentError::from(format!(
+                "`field_encryption_key` is invalid: {e}"
+            )));
+        }
+
+        Ok(config)
+    }
+
     /// The hostname the site is running on.
     /// Used in the reCAPTCHA verification API.
     pub fn hostname(&self) -> &str {
@@ -46,28 +193,315 @@ impl Config {
         Duration::try_seconds(self.otp_ttl.into()).unwrap()
     }
 
+    /// Valid lifetime of step-up token cookies in seconds.
+    pub fn step_up_ttl(&self) -> Duration {
+        // Unwrap safe: u32 is not big enough to exceed the bounds of Duration.
+        Duration::try_seconds(self.step_up_ttl.into()).unwrap()
+    }
+
     /// Valid lifetime of auth token cookies in seconds.
     pub fn auth_ttl(&self) -> Duration {
         // Unwrap safe: u32 is not big enough to exceed the bounds of Duration.
         Duration::try_seconds(self.auth_ttl.into()).unwrap()
     }
 
+    /// OTP SMS message templates by language code.
+    pub fn sms_templates(&self) -> &SmsTemplates {
+        &self.sms_templates
+    }
+
+    /// Language of the SMS template used when the voter doesn't request one,
+    /// or requests one that isn't configured.
+    pub fn sms_default_language(&self) -> &str {
+        &self.sms_default_language
+    }
+
+    /// E.164 prefixes that voters' SMS numbers must start with to register.
+    /// Empty if any number is allowed.
+    pub fn allowed_sms_prefixes(&self) -> &[String] {
+        &self.allowed_sms_prefixes
+    }
+
+    /// The region, e.g. `GB`, of SMS numbers given without a country code.
+    /// If there is none, numbers must be given with their country code.
+    pub fn default_sms_region(&self) -> Option<country::Id> {
+        // Already checked to be valid when loading.
+        self.default_sms_region
+            .as_deref()
+            .map(|region| region.parse().unwrap())
+    }
+
+    /// Should cookies be marked `Secure`, i.e. only sent over HTTPS?
+    /// Only disable this for local development.
+    pub fn secure_cookies(&self) -> bool {
+        self.secure_cookies
+    }
+
+    /// The domain cookies are scoped to, so that they are shared with its
+    /// subdomains, if any. Otherwise they are only sent to the API's own host.
+    pub fn cookie_domain(&self) -> Option<&str> {
+        self.cookie_domain
+            .as_deref()
+            .map(|domain| domain.trim_start_matches('.'))
+            .filter(|domain| !domain.is_empty())
+    }
+
+    /// The path cookies are scoped to.
+    pub fn cookie_path(&self) -> &str {
+        &self.cookie_path
+    }
+
+    /// Should database maintenance run automatically every day?
+    pub fn maintenance_enabled(&self) -> bool {
+        self.maintenance_enabled
+    }
+
+    /// How long after an election is archived its voters may be purged.
+    pub fn maintenance_retention(&self) -> Duration {
+        // Unwrap safe: u16 days is not big enough to exceed the bounds of Duration.
+        Duration::try_days(self.maintenance_retention_days.into()).unwrap()
+    }
+
+    /// How long after an election is archived its audited ballots keep their
+    /// secrets, if they are ever removed.
+    pub fn audited_secret_retention(&self) -> Option<Duration> {
+        // Unwrap safe: u32 days is not big enough to exceed the bounds of Duration.
+        self.audited_secret_retention_days
+            .map(|days| Duration::try_days(days.into()).unwrap())
+    }
+
+    /// Number of consecutive OTP challenges to a number that may fail to be
+    /// delivered before voters are told to contact support instead.
+    pub fn max_sms_delivery_failures(&self) -> u32 {
+        self.max_sms_delivery_failures
+    }
+
+    /// Are OTP challenges queued to be sent in the background, rather than
+    /// sent before responding to the voter?
+    pub fn sms_queue_enabled(&self) -> bool {
+        self.sms_queue_enabled
+    }
+
+    /// Maximum number of OTP challenges that may wait to be sent, beyond which
+    /// voters are told to try again later.
+    pub fn sms_queue_capacity(&self) -> usize {
+        self.sms_queue_capacity as usize
+    }
+
+    /// Number of tasks sending queued OTP challenges in parallel.
+    pub fn sms_queue_senders(&self) -> usize {
+        self.sms_queue_senders as usize
+    }
+
+    /// Maximum number of ballots a voter may cast in one request.
+    pub fn max_cast_ballots(&self) -> usize {
+        self.max_cast_ballots as usize
+    }
+
+    /// Maximum number of ballots a voter may audit or confirm in one request.
+    pub fn max_recall_ballots(&self) -> usize {
+        self.max_recall_ballots as usize
+    }
+
+    /// Maximum number of ballots a voter may recall by recall code,
+    /// rather than by signature, in an hour.
+    pub fn max_code_recalls(&self) -> usize {
+        self.max_code_recalls as usize
+    }
+
+    /// Maximum number of electorates a voter may name when joining an election.
+    pub fn max_join_electorates(&self) -> usize {
+        self.max_join_electorates as usize
+    }
+
+    /// Maximum total number of groups a voter may name when joining an election.
+    pub fn max_join_groups(&self) -> usize {
+        self.max_join_groups as usize
+    }
+
+    /// How long before an election ends to warn admins if many ballots are
+    /// still unconfirmed.
+    pub fn end_warning_lead(&self) -> Duration {
+        // Unwrap safe: u32 minutes is not big enough to exceed the bounds of Duration.
+        Duration::try_minutes(self.end_warning_lead_minutes.into()).unwrap()
+    }
+
+    /// Admins are only warned about an election ending if it has more
+    /// unconfirmed ballots than this.
+    pub fn end_warning_threshold(&self) -> u64 {
+        self.end_warning_threshold
+    }
+
+    /// The S3 bucket that archived elections' signed dumps are uploaded to,
+    /// if any.
+    pub fn archive_bucket(&self) -> Option<&str> {
+        self.archive_bucket.as_deref()
+    }
+
+    /// The prefix of the keys that archived elections are uploaded under,
+    /// without any trailing slash. Empty if they are uploaded at the top of
+    /// the bucket.
+    pub fn archive_prefix(&self) -> &str {
+        self.archive_prefix.trim_end_matches('/')
+    }
+
+    /// URL to POST admin notifications to, if any.
+    pub fn admin_webhook_url(&self) -> Option<&str> {
+        self.admin_webhook_url.as_deref()
+    }
+
+    /// How long a rehearsal election is kept before it is deleted.
+    pub fn rehearsal_ttl(&self) -> Duration {
+        // Unwrap safe: u32 minutes is not big enough to exceed the bounds of Duration.
+        Duration::try_minutes(self.rehearsal_ttl_minutes.into()).unwrap()
+    }
+
+    /// Address ranges of the proxies, e.g. load balancers, that are trusted
+    /// to report the client IP of the requests they forward.
+    pub fn trusted_proxies(&self) -> &[IpNet] {
+        &self.trusted_proxies
+    }
+
+    /// How long the database may spend on each of the heavier queries of a
+    /// public endpoint, such as listing or dumping ballots.
+    pub fn public_query_timeout(&self) -> StdDuration {
+        StdDuration::from_millis(self.public_query_timeout_ms.into())
+    }
+
+    /// How long a request to cast, audit or confirm ballots may take before
+    /// its uncommitted changes are abandoned.
+    pub fn vote_request_timeout(&self) -> StdDuration {
+        StdDuration::from_millis(self.vote_request_timeout_ms.into())
+    }
+
+    /// Maximum size of a CSV electoral roll that admins may import in one go.
+    pub fn max_voter_import_bytes(&self) -> u64 {
+        self.max_voter_import_bytes.into()
+    }
+
+    /// How long after casting a ballot an identical cast by the same voter is
+    /// treated as an accidental double-submission. Zero disables this.
+    pub fn duplicate_cast_window(&self) -> StdDuration {
+        StdDuration::from_secs(self.duplicate_cast_window_secs.into())
+    }
+
     /// Secret key used to encrypt JWTs.
     pub fn jwt_secret(&self) -> &[u8] {
-        self.jwt_secret.as_bytes()
+        self.jwt_secret.expose().as_bytes()
     }
 
     /// Secret key for reCAPTCHA verification.
     pub fn recaptcha_secret(&self) -> &str {
-        &self.recaptcha_secret
+        self.recaptcha_secret.expose().as_str()
     }
 
     /// Secret key used to sign HMACs.
     pub fn hmac_secret(&self) -> &[u8] {
-        self.hmac_secret.as_bytes()
+        self.hmac_secret.expose().as_bytes()
+    }
+
+    /// Should a change of `hmac_secret` since the database was first used be
+    /// accepted, orphaning every existing voter, rather than refusing to launch?
+    pub fn accept_hmac_rotation(&self) -> bool {
+        self.accept_hmac_rotation
+    }
+
+    /// Key for encrypting audited ballot secrets at rest, if configured.
+    pub fn field_encryption_key(&self) -> Option<Result<FieldKey, String>> {
+        self.field_encryption_key
+            .as_ref()
+            .map(|key| FieldKey::from_base64(key.expose()))
     }
 }
 
+fn default_step_up_ttl() -> u32 {
+    600
+}
+
+fn default_sms_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_secure_cookies() -> bool {
+    true
+}
+
+fn default_cookie_path() -> String {
+    "/".to_string()
+}
+
+fn default_maintenance_enabled() -> bool {
+    true
+}
+
+fn default_maintenance_retention_days() -> u16 {
+    90
+}
+
+fn default_max_sms_delivery_failures() -> u32 {
+    3
+}
+
+fn default_sms_queue_enabled() -> bool {
+    true
+}
+
+fn default_sms_queue_capacity() -> u32 {
+    10_000
+}
+
+fn default_sms_queue_senders() -> u32 {
+    8
+}
+
+fn default_max_cast_ballots() -> u32 {
+    20
+}
+
+fn default_max_recall_ballots() -> u32 {
+    20
+}
+
+fn default_max_code_recalls() -> u32 {
+    10
+}
+
+fn default_max_join_electorates() -> u32 {
+    10
+}
+
+fn default_max_join_groups() -> u32 {
+    50
+}
+
+fn default_end_warning_lead_minutes() -> u32 {
+    60
+}
+
+fn default_end_warning_threshold() -> u64 {
+    10
+}
+
+fn default_rehearsal_ttl_minutes() -> u32 {
+    60
+}
+
+fn default_public_query_timeout_ms() -> u32 {
+    10_000
+}
+
+fn default_vote_request_timeout_ms() -> u32 {
+    30_000
+}
+
+fn default_max_voter_import_bytes() -> u32 {
+    10 * 1024 * 1024
+}
+
+fn default_duplicate_cast_window_secs() -> u32 {
+    30
+}
+
 /// A fairing that loads the application config and puts it in managed state.
 /// This could easily be achieved using `AdHoc::config`, but is written out
 /// explicitly for symmetry with the other fairings and control over error
@@ -85,7 +519,7 @@ impl Fairing for ConfigFairing {
 
     async fn on_ignite(&self, mut rocket: Rocket<Build>) -> rocket::fairing::Result {
         // Load the config.
-        let config = match rocket.figment().extract::<Config>() {
+        let config = match Config::from_figment(rocket.figment()) {
             Ok(config) => config,
             Err(e) => {
                 error!("Failed to load application config");
@@ -94,24 +528,214 @@ impl Fairing for ConfigFairing {
             }
         };
 
+        // Install the field encryption key, if any, before any ballots are
+        // read or written. The config has already checked that it is valid.
+        if let Some(Ok(key)) = config.field_encryption_key() {
+            if let Err(e) = field_encryption::install(key) {
+                error!("{e}");
+                return Err(rocket);
+            }
+            info!("Audited ballot secrets will be encrypted at rest");
+        }
+
         // Manage the state.
         rocket = rocket.manage(config);
         Ok(rocket)
     }
 }
 
+/// Why a fairing could not be set up.
+#[derive(Debug)]
+pub enum ProbeError {
+    /// The named part of the config could not be loaded.
+    Config(&'static str, FigmentError),
+    /// Anything else, described.
+    Other(String),
+}
+
+impl ProbeError {
+    /// Log the error, pretty-printing config errors as Rocket does.
+    fn log(self) {
+        match self {
+            Self::Config(name, e) => {
+                error!("Failed to load {name} config");
+                rocket::config::pretty_print_error(e);
+            }
+            Self::Other(e) => error!("{e}"),
+        }
+    }
+}
+
+impl From<String> for ProbeError {
+    fn from(e: String) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl Display for ProbeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(name, e) => write!(f, "Failed to load {name} config: {e}"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
 /// Configuration for the database.
 #[derive(Deserialize)]
 struct DbConfig {
+    // non-secrets
+    #[serde(default = "default_slow_query_threshold_ms")]
+    slow_query_threshold_ms: u64,
+    #[serde(default = "default_clock_skew_warn_ms")]
+    clock_skew_warn_ms: u32,
+    #[serde(default = "default_clock_skew_max_ms")]
+    clock_skew_max_ms: u32,
+    #[serde(default = "default_clock_skew_check_interval_secs")]
+    clock_skew_check_interval_secs: u32,
+    #[serde(default)]
+    accept_clock_skew: bool,
+    /// The database a test asked for with `#[backend_test(db = "isolated")]`.
+    #[cfg(test)]
+    #[serde(default)]
+    test_db_name: Option<String>,
     // secrets
     db_uri: String,
 }
 
+fn default_slow_query_threshold_ms() -> u64 {
+    250
+}
+
+fn default_clock_skew_warn_ms() -> u32 {
+    2000
+}
+
+fn default_clock_skew_max_ms() -> u32 {
+    60_000
+}
+
+fn default_clock_skew_check_interval_secs() -> u32 {
+    600
+}
+
 /// A fairing that loads the MongoDB config, connects to the database,
-/// performs any setup necessary, and places both a `Client` and a `Database`
-/// into managed state.
+/// performs any setup necessary, and places a `Client`, a `Database`, the
+/// `CommandMonitor` timing its commands, and the `ClockSkewMonitor` comparing
+/// its clock with ours into managed state.
 pub struct DatabaseFairing;
 
+impl DatabaseFairing {
+    /// Load the database config, connect, and perform setup, returning the
+    /// connection on success or a description of what went wrong.
+    pub async fn probe(
+        figment: &Figment,
+    ) -> Result<(MongoClient, Database, CommandMonitor, ClockSkewMonitor), ProbeError> {
+        // Load the config.
+        let config = figment
+            .extract::<DbConfig>()
+            .map_err(|e| ProbeError::Config("database", e))?;
+        if config.clock_skew_warn_ms > config.clock_skew_max_ms {
+            return Err(ProbeError::Other(
+                "Failed to load database config: `clock_skew_warn_ms` must not exceed \
+                 `clock_skew_max_ms`"
+                    .to_string(),
+            ));
+        }
+        if config.clock_skew_check_interval_secs == 0 {
+            return Err(ProbeError::Other(
+                "Failed to load database config: `clock_skew_check_interval_secs` must be at \
+                 least 1"
+                    .to_string(),
+            ));
+        }
+        info!("Loaded database config, connecting...");
+        #[cfg(not(test))]
+        let db_name = get_database_name();
+        #[cfg(test)]
+        let db_name = config.test_db_name.unwrap_or_else(get_database_name);
+        // Construct the connection, timing every command.
+        let mut options = ClientOptions::parse(config.db_uri)
+            .await
+            .map_err(|e| format!("Failed to connect to database: {e}"))?;
+        let monitor = CommandMonitor::new(StdDuration::from_millis(config.slow_query_threshold_ms));
+        options.command_event_handler = Some(Arc::new(monitor.clone()));
+        let client = MongoClient::with_options(options)
+            .map_err(|e| format!("Failed to connect to database: {e}"))?;
+        let db = client.database(&db_name);
+
+        // Ensure the required indexes exist.
+        ensure_indexes_exist(&db)
+            .await
+            .map_err(|e| format!("Failed to create indexes: {e}"))?;
+
+        // Ensure there is at least one admin user and the global election ID counter exists
+        // and is ahead of every existing election.
+        let admins = Coll::from_db(&db);
+        let counters = Coll::from_db(&db);
+        let elections = Coll::from_db(&db);
+        ensure_admin_exists(&admins)
+            .and_then(|_| ensure_election_id_counter_exists(&counters))
+            .and_then(|_| ensure_election_id_counter_consistent(&counters, &elections))
+            .await
+            .map_err(|e| format!("Failed to set up admins and the election ID counter: {e}"))?;
+
+        // Elections published before candidate totals were created at publish
+        // time may be missing some.
+        ensure_published_totals_exist(&client, &db)
+            .await
+            .map_err(|e| format!("Failed to create missing candidate totals: {e}"))?;
+
+        // Totals stored before confirmed ballots were counted lack the count.
+        ensure_confirmed_counts_exist(&client, &db)
+            .await
+            .map_err(|e| format!("Failed to fill in totals' confirmed ballot counts: {e}"))?;
+
+        // Voters stored before joining an election had its own collection
+        // have their allowed questions embedded.
+        ensure_voter_elections_split(&db).await.map_err(|e| {
+            format!("Failed to move voters' elections into their own collection: {e}")
+        })?;
+
+        // Ballots stored before their confirmation phrases were lack them, so
+        // could not be found by phrase.
+        ensure_confirmation_phrases_exist(&db)
+            .await
+            .map_err(|e| format!("Failed to fill in ballots' confirmation phrases: {e}"))?;
+
+        // Ballots audited before their revealed candidates were recorded lack
+        // them, so could not be filtered by candidate.
+        ensure_audited_candidates_exist(&db)
+            .await
+            .map_err(|e| format!("Failed to fill in audited ballots' candidates: {e}"))?;
+
+        // Elections archived before the time was recorded lack it, so would
+        // never have their audited ballots redacted.
+        ensure_archive_times_exist(&db)
+            .await
+            .map_err(|e| format!("Failed to fill in elections' archive times: {e}"))?;
+
+        // Questions stored before slugs existed lack them, so their slugs
+        // would otherwise be derived afresh on every read.
+        ensure_question_slugs_exist(&db)
+            .await
+            .map_err(|e| format!("Failed to fill in questions' slugs: {e}"))?;
+
+        // Elections open and close by our clock, but some timestamps and
+        // expiries come from the database's, so they must agree.
+        let thresholds = SkewThresholds {
+            warn: Duration::try_milliseconds(config.clock_skew_warn_ms.into()).unwrap(),
+            max: Duration::try_milliseconds(config.clock_skew_max_ms.into()).unwrap(),
+        };
+        let interval = Duration::try_seconds(config.clock_skew_check_interval_secs.into()).unwrap();
+        let clock = ClockSkewMonitor::new(db.clone(), thresholds, interval);
+        clock.check_at_launch(config.accept_clock_skew).await?;
+        info!("...database connection online!");
+
+        Ok((client, db, monitor, clock))
+    }
+}
+
 #[rocket::async_trait]
 impl Fairing for DatabaseFairing {
     fn info(&self) -> Info {
@@ -122,46 +746,20 @@ impl Fairing for DatabaseFairing {
     }
 
     async fn on_ignite(&self, mut rocket: Rocket<Build>) -> rocket::fairing::Result {
-        // Load the config.
-        let config = match rocket.figment().extract::<DbConfig>() {
-            Ok(config) => config,
+        let (client, db, monitor, clock) = match Self::probe(rocket.figment()).await {
+            Ok(connection) => connection,
             Err(e) => {
-                error!("Failed to load database config");
-                rocket::config::pretty_print_error(e);
-                return Err(rocket);
-            }
-        };
-        info!("Loaded database config, connecting...");
-        // Construct the connection.
-        let client = match MongoClient::with_uri_str(config.db_uri).await {
-            Ok(client) => client,
-            Err(e) => {
-                error!("Failed to connect to database: {e}");
+                e.log();
                 return Err(rocket);
             }
         };
-        let db = client.database(&get_database_name());
-
-        // Ensure the required indexes exist.
-        if let Err(e) = ensure_indexes_exist(&db).await {
-            error!("Failed to connect to database: {e}");
-            return Err(rocket);
-        }
-
-        // Ensure there is at least one admin user and the global election ID counter exists.
-        let admins = Coll::from_db(&db);
-        let counters = Coll::from_db(&db);
-        if let Err(e) = ensure_admin_exists(&admins)
-            .and_then(|_| ensure_election_id_counter_exists(&counters))
-            .await
-        {
-            error!("Failed to connect to database: {e}");
-            return Err(rocket);
-        }
-        info!("...database connection online!");
 
         // Manage the state.
-        rocket = rocket.manage(client).manage(db);
+        rocket = rocket
+            .manage(client)
+            .manage(db)
+            .manage(monitor)
+            .manage(clock);
         Ok(rocket)
     }
 }
@@ -172,8 +770,8 @@ fn get_database_name() -> String {
     "dreip".to_string()
 }
 
-/// Get the name of the database to use (test version).
-/// Use a random name to avoid collisions between tests.
+/// Get the name of the database to use (test version), unless the test asked
+/// for one. Use a random name to avoid collisions between tests.
 #[cfg(test)]
 fn get_database_name() -> String {
     let random: u32 = rand::random();
@@ -188,50 +786,297 @@ struct AwsConfig {
     // non-secrets
     aws_region: String,
     aws_access_key_id: String,
+    #[serde(default)]
+    sms_sender_id: Option<String>,
+    #[serde(default)]
+    sms_routes: Vec<SmsRoute>,
     // secrets
     aws_secret_access_key: String,
 }
 
-/// A fairing that loads the AWS config and places an SNS `Client` into
-/// managed state.
+/// SNS clients for sending SMS messages, routed by the recipient's country
+/// calling code. This is placed into managed state by [`AwsFairing`].
+/// Cloning the router gives another handle to the same clients.
+#[derive(Clone)]
+pub struct SmsRouter {
+    /// Routes and their clients. The default route comes first, with an empty
+    /// prefix that matches any number.
+    routes: Vec<(SmsRoute, SnsClient)>,
+}
+
+impl SmsRouter {
+    /// Get the route and client to send to the given number with, using the
+    /// longest matching prefix, or the default route if none match.
+    pub fn route(&self, sms: &Sms) -> (&SmsRoute, &SnsClient) {
+        let prefixes = self.routes.iter().map(|(route, _)| route.prefix.as_str());
+        let index = match_route(prefixes, &sms.to_string()).unwrap_or(0);
+        let (route, client) = &self.routes[index];
+        (route, client)
+    }
+
+    /// The effective routing table, most specific first, ending with the default.
+    pub fn routes(&self) -> Vec<SmsRoute> {
+        let mut routes = self
+            .routes
+            .iter()
+            .map(|(route, _)| route.clone())
+            .collect::<Vec<_>>();
+        routes.sort_by(|a, b| {
+            b.prefix
+                .len()
+                .cmp(&a.prefix.len())
+                .then(a.prefix.cmp(&b.prefix))
+        });
+        routes
+    }
+
+    /// Check that the AWS credentials are accepted in every route's region,
+    /// using a cheap read-only call.
+    pub async fn check_credentials(&self) -> Result<(), String> {
+        for (route, client) in &self.routes {
+            client
+                .get_sms_attributes()
+                .attributes("DefaultSMSType")
+                .send()
+                .await
+                .map_err(|e| format!("AWS rejected request in {}: {e}", route.region))?;
+        }
+        Ok(())
+    }
+}
+
+/// Something that can send SMS messages.
+#[rocket::async_trait]
+pub trait SmsNotifier: Send + Sync {
+    /// Send the message to the given number, reporting whether it was sent.
+    async fn send_sms(&self, sms: &Sms, message: String) -> SmsOutcome;
+}
+
+/// Messages are sent via the SNS configuration for the recipient's country,
+/// unless they have opted out of SMS. Nothing is actually sent in tests.
+#[rocket::async_trait]
+impl SmsNotifier for SmsRouter {
+    #[cfg_attr(any(not(feature = "otp"), test), allow(unused_variables))]
+    async fn send_sms(&self, sms: &Sms, message: String) -> SmsOutcome {
+        let (route, client) = self.route(sms);
+        debug!(
+            "Routing SMS via region {} (prefix '{}')",
+            route.region, route.prefix
+        );
+        #[cfg(all(feature = "otp", not(test)))]
+        {
+            // SNS accepts messages to numbers that have opted out, but never
+            // delivers them.
+            let opted_out = client
+                .check_if_phone_number_is_opted_out()
+                .phone_number(sms.to_string())
+                .send()
+                .await;
+            match opted_out {
+                Ok(output) if output.is_opted_out() => {
+                    warn!("SMS number {} has opted out", sms.redacted());
+                    return SmsOutcome::not_sent(DeliveryStatus::OptedOut);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "Failed to check SMS opt-out via region {}: {e}",
+                    route.region
+                ),
+            }
+
+            let mut publish = client
+                .publish()
+                .phone_number(sms.to_string())
+                .message(message);
+            if let Some(sender_id) = &route.sender_id {
+                let attribute = match MessageAttributeValue::builder()
+                    .data_type("String")
+                    .string_value(sender_id)
+                    .build()
+                {
+                    Ok(attribute) => attribute,
+                    Err(e) => {
+                        error!("Invalid SMS sender ID for region {}: {e}", route.region);
+                        return SmsOutcome::not_sent(DeliveryStatus::Failed);
+                    }
+                };
+                publish = publish.message_attributes("AWS.SNS.SMS.SenderID", attribute);
+            }
+            match publish.send().await {
+                Ok(output) => SmsOutcome::sent(output.message_id().map(ToString::to_string)),
+                Err(e)
+                    if e.as_service_error()
+                        .is_some_and(|e| e.is_throttled_exception()) =>
+                {
+                    warn!("SNS throttled SMS via region {}", route.region);
+                    SmsOutcome::throttled()
+                }
+                Err(e)
+                    if e.as_service_error().is_some_and(|e| {
+                        e.is_invalid_parameter_exception()
+                            || e.is_invalid_parameter_value_exception()
+                    }) =>
+                {
+                    warn!("SNS refused SMS number {}: {e}", sms.redacted());
+                    SmsOutcome::not_sent(DeliveryStatus::InvalidNumber)
+                }
+                Err(e) => {
+                    error!("Failed to send SMS via region {}: {e}", route.region);
+                    SmsOutcome::not_sent(DeliveryStatus::Failed)
+                }
+            }
+        }
+        #[cfg(any(not(feature = "otp"), test))]
+        SmsOutcome::sent(None)
+    }
+}
+
+/// Somewhere to keep archived elections for the long term.
+#[rocket::async_trait]
+pub trait ArchiveUploader: Send + Sync {
+    /// Store the given JSON under the given key, replacing anything already there.
+    async fn upload(&self, key: &str, json: Vec<u8>) -> Result<(), String>;
+}
+
+/// An S3 bucket for keeping archived elections. This is placed into managed
+/// state by [`AwsFairing`] if an archive bucket is configured.
+#[derive(Clone)]
+#[cfg_attr(test, allow(dead_code))]
+pub struct S3Archive {
+    client: S3Client,
+    bucket: String,
+}
+
+/// Objects are uploaded to the bucket, except in tests.
+#[rocket::async_trait]
+impl ArchiveUploader for S3Archive {
+    #[cfg_attr(test, allow(unused_variables))]
+    async fn upload(&self, key: &str, json: Vec<u8>) -> Result<(), String> {
+        #[cfg(not(test))]
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type("application/json")
+            .body(ByteStream::from(json))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload '{key}' to bucket {}: {e}", self.bucket))?;
+        Ok(())
+    }
+}
+
+/// A fairing that loads the AWS config and places an [`SmsRouter`] into
+/// managed state, along with an [`S3Archive`] if one is configured.
 pub struct AwsFairing;
 
+impl AwsFairing {
+    /// Load the AWS config and construct a client for each SMS route,
+    /// returning a description of what went wrong on failure.
+    /// This does not contact AWS.
+    pub fn probe(figment: &Figment) -> Result<SmsRouter, ProbeError> {
+        // Load the config.
+        let config = figment
+            .extract::<AwsConfig>()
+            .map_err(|e| ProbeError::Config("AWS", e))?;
+        for route in &config.sms_routes {
+            route
+                .validate()
+                .map_err(|e| format!("Failed to load AWS config: {e}"))?;
+        }
+
+        // Construct a connection for each route, all sharing the same credentials.
+        let credentials = config.credentials();
+        let default_route = SmsRoute {
+            prefix: String::new(),
+            region: config.aws_region,
+            sender_id: config.sms_sender_id,
+        };
+        let routes = std::iter::once(default_route)
+            .chain(config.sms_routes)
+            .map(|route| {
+                let aws_config = SdkConfig::builder()
+                    .region(Region::new(route.region.clone()))
+                    .credentials_provider(credentials.clone())
+                    .behavior_version(BehaviorVersion::latest())
+                    .build();
+                (route, SnsClient::new(&aws_config))
+            })
+            .collect::<Vec<_>>();
+        info!("Loaded Amazon SNS config with {} SMS routes", routes.len());
+
+        Ok(SmsRouter { routes })
+    }
+
+    /// Load the AWS config and construct a client for the given archive
+    /// bucket, in the default region, returning a description of what went
+    /// wrong on failure. This does not contact AWS.
+    pub fn probe_archive(figment: &Figment, bucket: &str) -> Result<S3Archive, ProbeError> {
+        let config = figment
+            .extract::<AwsConfig>()
+            .map_err(|e| ProbeError::Config("AWS", e))?;
+        let aws_config = SdkConfig::builder()
+            .region(Region::new(config.aws_region.clone()))
+            .credentials_provider(config.credentials())
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        info!("Loaded Amazon S3 config for archive bucket {bucket}");
+        Ok(S3Archive {
+            client: S3Client::new(&aws_config),
+            bucket: bucket.to_string(),
+        })
+    }
+}
+
+impl AwsConfig {
+    /// Credentials shared by every AWS client.
+    fn credentials(&self) -> SharedCredentialsProvider {
+        SharedCredentialsProvider::new(Credentials::new(
+            self.aws_access_key_id.clone(),
+            self.aws_secret_access_key.clone(),
+            None,
+            None,
+            "rocket config",
+        ))
+    }
+}
+
 #[rocket::async_trait]
 impl Fairing for AwsFairing {
     fn info(&self) -> Info {
         Info {
-            name: "AWS SNS",
+            name: "AWS",
             kind: Kind::Ignite,
         }
     }
 
     async fn on_ignite(&self, mut rocket: Rocket<Build>) -> rocket::fairing::Result {
-        // Load the config.
-        let config = match rocket.figment().extract::<AwsConfig>() {
-            Ok(config) => config,
+        let sms_router = match Self::probe(rocket.figment()) {
+            Ok(sms_router) => sms_router,
             Err(e) => {
-                error!("Failed to load AWS config");
-                rocket::config::pretty_print_error(e);
+                e.log();
                 return Err(rocket);
             }
         };
-        // Construct the connection.
-        let aws_config = SdkConfig::builder()
-            .region(Region::new(config.aws_region))
-            .credentials_provider(SharedCredentialsProvider::new(Credentials::new(
-                config.aws_access_key_id,
-                config.aws_secret_access_key,
-                None,
-                None,
-                "rocket config",
-            )))
-            .behavior_version(BehaviorVersion::latest())
-            .build();
-        let client = SnsClient::new(&aws_config);
-        info!("Loaded Amazon SNS config");
+
+        // Only set up S3 if archives are to be uploaded to it.
+        let archive_bucket = rocket
+            .state::<Config>()
+            .and_then(|config| config.archive_bucket())
+            .map(str::to_string);
+        if let Some(bucket) = archive_bucket {
+            let archive = match Self::probe_archive(rocket.figment(), &bucket) {
+                Ok(archive) => archive,
+                Err(e) => {
+                    e.log();
+                    return Err(rocket);
+                }
+            };
+            rocket = rocket.manage(archive);
+        }
 
         // Manage the state.
-        rocket = rocket.manage(client);
+        rocket = rocket.manage(sms_router);
         Ok(rocket)
     }
 }
@@ -5,20 +5,63 @@ use mongodb::{
     error::{Error as DbError, ErrorKind as DbErrorKind},
 };
 use rocket::{
-    http::{Status, StatusClass},
+    http::{Header, Status, StatusClass},
+    request::Outcome,
     response::Responder,
+    serde::json::{json, Json, Value},
+    Catcher, Request,
 };
 use std::sync::Arc;
 use thiserror::Error;
 
-use crate::{logging::RequestId, model::api::auth::RecaptchaError};
+mod retry;
+
+pub use retry::{with_retries, READ_RETRIES};
+
+use crate::{
+    logging::RequestId,
+    model::{api::auth::RecaptchaError, common::election::ElectionId},
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// How many seconds clients are told to wait before retrying when the database
+/// is temporarily unavailable.
+pub const RETRY_AFTER_SECS: u32 = 1;
+
+/// How many seconds voters are told to wait before asking for another OTP
+/// challenge when too many are already waiting to be sent.
+pub const SMS_QUEUE_RETRY_AFTER_SECS: u32 = 5;
+
+/// Server error code meaning a command exceeded its `maxTimeMS`.
+const MAX_TIME_MS_EXPIRED: i32 = 50;
+
+/// Server error codes meaning the command failed because of a replica set
+/// election, shutdown, or network trouble, and may well succeed if repeated.
+const TRANSIENT_ERROR_CODES: &[i32] = &[
+    6,     // HostUnreachable
+    7,     // HostNotFound
+    89,    // NetworkTimeout
+    91,    // ShutdownInProgress
+    189,   // PrimarySteppedDown
+    262,   // ExceededTimeLimit
+    9001,  // SocketException
+    10058, // LegacyNotPrimary
+    10107, // NotWritablePrimary
+    11600, // InterruptedAtShutdown
+    11602, // InterruptedDueToReplStateChange
+    13435, // NotPrimaryNoSecondaryOk
+    13436, // NotPrimaryOrSecondary
+];
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error(transparent)]
     Db(DbError),
+    #[error("Database temporarily unavailable: {0}")]
+    Unavailable(DbError),
+    #[error("Database query took too long: {0}")]
+    TimedOut(DbError),
     #[error(transparent)]
     Oid(#[from] OidError),
     #[error(transparent)]
@@ -29,6 +72,24 @@ pub enum Error {
     Recaptcha(#[from] RecaptchaError),
     #[error("{0}: {1}")]
     Status(Status, String),
+    #[error("Voting in election {0} is suspended")]
+    Suspended(ElectionId),
+    #[error("Election {0} requires step-up verification")]
+    StepUpRequired(ElectionId),
+    #[error("Admin must change their password first")]
+    PasswordChangeRequired,
+    #[error("Codes sent to this SMS number are not being delivered; please contact support")]
+    SmsUndeliverable,
+    #[error("Too many codes are waiting to be sent; please try again shortly")]
+    SmsQueueFull,
+    #[error("Voter has been frozen by an administrator; please contact support")]
+    VoterFrozen,
+    #[error("Voters must accept version {version} of the consent text to join")]
+    ConsentRequired { text: String, version: u32 },
+    #[error("Request took too long, so its changes were abandoned; please try again")]
+    DeadlineExceeded,
+    #[error("Invalid election spec: {0}")]
+    InvalidElectionSpec(String),
 }
 
 impl From<DbError> for Error {
@@ -42,6 +103,8 @@ impl From<DbError> for Error {
             };
             let wrapped = arc.downcast::<Self>().unwrap();
             Arc::into_inner(wrapped).expect("multiple refs to DbError")
+        } else if is_timed_out_db(&err) {
+            Self::TimedOut(err)
         } else {
             Self::Db(err)
         }
@@ -58,10 +121,20 @@ impl Error {
         Self::Status(Status::NotFound, cause)
     }
 
+    /// Is this a database error which may well not recur if the operation is repeated?
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Db(err) | Error::Unavailable(err) => is_transient_db(err),
+            _ => false,
+        }
+    }
+
     /// Get the HTTP response status associated with this error.
     pub fn status(&self) -> Status {
         match self {
             Error::Db(_) => Status::InternalServerError,
+            Error::Unavailable(_) => Status::ServiceUnavailable,
+            Error::TimedOut(_) => Status::GatewayTimeout,
             Error::Oid(_) | Error::Argon2(_) => Status::BadRequest,
             Error::Jwt(err) => match err.kind() {
                 JwtErrorKind::ExpiredSignature | JwtErrorKind::ImmatureSignature => {
@@ -74,20 +147,199 @@ impl Error {
                 _ => Status::Unauthorized,
             },
             Error::Status(status, _) => *status,
+            Error::Suspended(_) | Error::SmsQueueFull | Error::DeadlineExceeded => {
+                Status::ServiceUnavailable
+            }
+            Error::StepUpRequired(_) | Error::PasswordChangeRequired | Error::VoterFrozen => {
+                Status::Forbidden
+            }
+            Error::SmsUndeliverable | Error::InvalidElectionSpec(_) => Status::UnprocessableEntity,
+            Error::ConsentRequired { .. } => Status::PreconditionRequired,
         }
     }
+
+    /// Get the machine-readable code for this error, if it has one.
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            Error::Suspended(_) => Some("election_suspended"),
+            Error::StepUpRequired(_) => Some("step_up_required"),
+            Error::Unavailable(_) => Some("database_unavailable"),
+            Error::TimedOut(_) => Some("query_timeout"),
+            Error::PasswordChangeRequired => Some("password_change_required"),
+            Error::SmsUndeliverable => Some("sms_undeliverable"),
+            Error::SmsQueueFull => Some("sms_queue_full"),
+            Error::VoterFrozen => Some("voter_frozen"),
+            Error::ConsentRequired { .. } => Some("consent_required"),
+            Error::DeadlineExceeded => Some("deadline_exceeded"),
+            Error::InvalidElectionSpec(_) => Some("invalid_election_spec"),
+            _ => None,
+        }
+    }
+
+    /// How many seconds the client should wait before retrying, if this error
+    /// is expected to clear up by itself.
+    pub fn retry_after(&self) -> Option<u32> {
+        match self {
+            Error::Unavailable(_) => Some(RETRY_AFTER_SECS),
+            Error::SmsQueueFull => Some(SMS_QUEUE_RETRY_AFTER_SECS),
+            Error::DeadlineExceeded => Some(RETRY_AFTER_SECS),
+            _ => None,
+        }
+    }
+
+    /// Fail a request guard with this error.
+    ///
+    /// Rocket only passes the status of a failed guard on to the catchers, so
+    /// the error's code is stashed in the request for [`forbidden`] to find.
+    pub fn fail_guard<T>(self, req: &Request<'_>) -> Outcome<T, Self> {
+        let id = req.local_cache(|| RequestId::for_request(req));
+        warn!("  req{id} {self}");
+        req.local_cache(|| GuardErrorCode(self.code()));
+        Outcome::Error((self.status(), self))
+    }
+}
+
+/// The code of the error which failed a request guard, if any.
+struct GuardErrorCode(Option<&'static str>);
+
+/// The JSON body for an error with a machine-readable code.
+fn coded_body(code: &str, id: &RequestId) -> Json<Value> {
+    let mut body = json!({ "code": code });
+    if let Some(op_id) = id.client_op_id {
+        body["op_id"] = json!(op_id.to_string());
+    }
+    Json(body)
+}
+
+/// Our error catchers, to be registered at the root.
+pub fn catchers() -> Vec<Catcher> {
+    catchers![forbidden]
+}
+
+/// Report coded errors from request guards in the same way as coded errors
+/// from handlers. Other 403s get a body like Rocket's default catcher's.
+#[catch(403)]
+fn forbidden(req: &Request<'_>) -> Json<Value> {
+    let id = req.local_cache(|| RequestId::for_request(req));
+    match req.local_cache(|| GuardErrorCode(None)).0 {
+        Some(code) => coded_body(code, id),
+        None => Json(json!({
+            "error": {
+                "code": 403,
+                "reason": "Forbidden",
+                "description": "The server refused to authorize the request.",
+            }
+        })),
+    }
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
     fn respond_to(self, req: &'r rocket::Request<'_>) -> rocket::response::Result<'o> {
         let status = self.status();
-        let id = req.local_cache(RequestId::next);
+        let id = req.local_cache(|| RequestId::for_request(req));
         let log_msg = format!("  req{id} {self}");
         if status.class() == StatusClass::ServerError {
             error!("{log_msg}");
         } else {
             warn!("{log_msg}");
         }
-        Err(status)
+        let retry_after = self.retry_after();
+        let mut body = match self.code() {
+            Some(code) => coded_body(code, id),
+            None => return Err(status),
+        };
+        match self {
+            // Give voters the consent text to accept, so they need not fetch it.
+            Error::ConsentRequired { text, version } => {
+                body["consent_text"] = json!(text);
+                body["consent_version"] = json!(version);
+            }
+            // Tell admins what to fix.
+            Error::InvalidElectionSpec(detail) => body["detail"] = json!(detail),
+            _ => {}
+        }
+        let mut response = (status, body).respond_to(req)?;
+        if let Some(secs) = retry_after {
+            response.set_header(Header::new("Retry-After", secs.to_string()));
+        }
+        Ok(response)
+    }
+}
+
+/// Did the database give up on a command for exceeding its `maxTimeMS`?
+fn is_timed_out_db(err: &DbError) -> bool {
+    matches!(&*err.kind, DbErrorKind::Command(command) if command.code == MAX_TIME_MS_EXPIRED)
+}
+
+/// Classify a database error as transient, from its labels or kind.
+fn is_transient_db(err: &DbError) -> bool {
+    if err.contains_label("TransientTransactionError") || err.contains_label("RetryableWriteError")
+    {
+        return true;
+    }
+    match &*err.kind {
+        DbErrorKind::Io(_)
+        | DbErrorKind::ConnectionPoolCleared { .. }
+        | DbErrorKind::ServerSelection { .. } => true,
+        DbErrorKind::Command(command) => TRANSIENT_ERROR_CODES.contains(&command.code),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+    use mongodb::{
+        bson::{doc, from_document},
+        error::CommandError,
+    };
+
+    use super::*;
+
+    fn command_error(code: i32, code_name: &str) -> DbError {
+        let command: CommandError = from_document(doc! {
+            "code": code,
+            "codeName": code_name,
+            "errmsg": "injected",
+        })
+        .unwrap();
+        DbErrorKind::Command(command).into()
+    }
+
+    #[test]
+    fn transient_classification() {
+        // Network trouble and replica set elections are transient.
+        let io = DbError::from(IoError::new(IoErrorKind::ConnectionReset, "injected"));
+        assert!(Error::Db(io).is_transient());
+        assert!(Error::Db(command_error(10107, "NotWritablePrimary")).is_transient());
+        assert!(Error::Db(command_error(11602, "InterruptedDueToReplStateChange")).is_transient());
+
+        // Anything else is permanent.
+        assert!(!Error::Db(command_error(11000, "DuplicateKey")).is_transient());
+        assert!(!Error::Db(command_error(2, "BadValue")).is_transient());
+        assert!(!Error::Db(DbError::custom("injected")).is_transient());
+        assert!(!Error::not_found("Election".to_string()).is_transient());
+
+        // Only errors which have exhausted their retries are reported as unavailable.
+        let not_primary = Error::Db(command_error(10107, "NotWritablePrimary"));
+        assert_eq!(not_primary.status(), Status::InternalServerError);
+        let unavailable = Error::Unavailable(command_error(10107, "NotWritablePrimary"));
+        assert_eq!(unavailable.status(), Status::ServiceUnavailable);
+        assert_eq!(unavailable.code(), Some("database_unavailable"));
+    }
+
+    #[test]
+    fn timeout_classification() {
+        // Commands that exceed their time limit are reported as such, and not retried.
+        let timed_out = Error::from(command_error(50, "MaxTimeMSExpired"));
+        assert!(matches!(timed_out, Error::TimedOut(_)));
+        assert!(!timed_out.is_transient());
+        assert_eq!(timed_out.status(), Status::GatewayTimeout);
+        assert_eq!(timed_out.code(), Some("query_timeout"));
+
+        // Other command failures are not.
+        let failed = Error::from(command_error(2, "BadValue"));
+        assert!(matches!(failed, Error::Db(_)));
     }
 }
@@ -0,0 +1,116 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use rocket::tokio::time::sleep;
+
+use super::{is_transient_db, Error, Result};
+
+/// How many times read-only handlers retry a transient database failure.
+pub const READ_RETRIES: u32 = 2;
+
+/// Base delay before the first retry, doubled for each subsequent one.
+const BASE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Run `op`, retrying it up to `retries` more times if it fails with a transient
+/// database error, with jittered exponential backoff in between.
+///
+/// If every attempt fails transiently, the last error is surfaced as
+/// [`Error::Unavailable`], so the client is told to try again later.
+/// This must only be used for operations that are safe to repeat, such as
+/// reads: writes should rely on their transactions' own retries instead.
+pub async fn with_retries<T, F, Fut>(retries: u32, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(Error::Db(err)) if is_transient_db(&err) => {
+                if attempt == retries {
+                    return Err(Error::Unavailable(err));
+                }
+                let backoff = BASE_BACKOFF * 2_u32.pow(attempt);
+                let jitter = rand::thread_rng().gen_range(Duration::ZERO..=backoff);
+                warn!(
+                    "Transient database error on attempt {}, retrying in {:?}: {}",
+                    attempt + 1,
+                    backoff + jitter,
+                    err
+                );
+                sleep(backoff + jitter).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use mongodb::error::Error as DbError;
+    use rocket::http::Status;
+
+    use super::*;
+
+    fn transient() -> Error {
+        Error::Db(DbError::from(IoError::new(
+            IoErrorKind::ConnectionReset,
+            "connection reset by peer",
+        )))
+    }
+
+    #[rocket::async_test]
+    async fn succeeds_after_transient_failures() {
+        let attempts = AtomicU32::new(0);
+        let result = with_retries(READ_RETRIES, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) < READ_RETRIES {
+                Err(transient())
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), READ_RETRIES + 1);
+    }
+
+    #[rocket::async_test]
+    async fn gives_up_as_unavailable() {
+        let attempts = AtomicU32::new(0);
+        let result = with_retries(READ_RETRIES, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(transient())
+        })
+        .await;
+        let err = result.unwrap_err();
+        assert!(matches!(err, Error::Unavailable(_)));
+        assert_eq!(err.status(), Status::ServiceUnavailable);
+        assert_eq!(attempts.load(Ordering::SeqCst), READ_RETRIES + 1);
+    }
+
+    #[rocket::async_test]
+    async fn permanent_errors_are_not_retried() {
+        let attempts = AtomicU32::new(0);
+        let result = with_retries(READ_RETRIES, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::Db(DbError::custom("duplicate key")))
+        })
+        .await;
+        assert_eq!(result.unwrap_err().status(), Status::InternalServerError);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let attempts = AtomicU32::new(0);
+        let result = with_retries(READ_RETRIES, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::not_found("Election".to_string()))
+        })
+        .await;
+        assert_eq!(result.unwrap_err().status(), Status::NotFound);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
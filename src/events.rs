@@ -0,0 +1,161 @@
+use std::future::Future;
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    tokio::{
+        self,
+        sync::broadcast::{self, error::RecvError},
+    },
+    Build, Rocket,
+};
+
+use crate::{
+    logging::RequestId,
+    model::common::{
+        ballot::BallotId,
+        election::{ElectionId, QuestionId},
+    },
+};
+
+/// How many events a subscriber may fall behind by before it starts missing them.
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Something that happened in the domain, which other features may want to react to.
+/// Events are only emitted once the change they describe has been committed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainEvent {
+    /// A voter cast a ballot.
+    BallotCast {
+        election_id: ElectionId,
+        question_id: QuestionId,
+        ballot_id: BallotId,
+    },
+    /// A voter confirmed a ballot.
+    BallotConfirmed {
+        election_id: ElectionId,
+        question_id: QuestionId,
+        ballot_id: BallotId,
+    },
+    /// A voter audited a ballot. Ballots audited by a finalizer are covered by
+    /// [`DomainEvent::QuestionFinalized`] and [`DomainEvent::ElectionFinalized`] instead.
+    BallotAudited {
+        election_id: ElectionId,
+        question_id: QuestionId,
+        ballot_id: BallotId,
+    },
+    /// An admin published a draft election.
+    ElectionPublished { election_id: ElectionId },
+    /// An admin archived an election.
+    ElectionArchived { election_id: ElectionId },
+    /// A question that closes early was finalized, auditing `audited` ballots.
+    QuestionFinalized {
+        election_id: ElectionId,
+        question_id: QuestionId,
+        audited: usize,
+    },
+    /// An election was finalized, auditing `audited` ballots in this run.
+    ElectionFinalized {
+        election_id: ElectionId,
+        audited: usize,
+    },
+}
+
+/// A domain event, along with the request that caused it, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRecord {
+    pub request_id: Option<RequestId>,
+    pub event: DomainEvent,
+}
+
+/// A bus on which domain events are broadcast to every registered subscriber.
+/// Cloning the bus gives another handle to the same channel.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventRecord>,
+}
+
+impl EventBus {
+    /// Create a bus with no subscribers.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Emit an event to all subscribers.
+    /// This never fails; an event with nobody to hear it is simply dropped.
+    pub fn emit(&self, event: DomainEvent, request_id: Option<RequestId>) {
+        let record = EventRecord { request_id, event };
+        if let Err(broadcast::error::SendError(record)) = self.sender.send(record) {
+            trace!("No subscribers for event {:?}", record.event);
+        }
+    }
+
+    /// Register an async subscriber, which is called with each event emitted
+    /// from now on, in order, until the bus is dropped.
+    /// The subscriber runs in its own task, so a slow or failing subscriber
+    /// cannot hold up the emitter; if it falls too far behind, it misses events.
+    pub fn register<F, Fut>(&self, name: &'static str, mut subscriber: F)
+    where
+        F: FnMut(EventRecord) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut receiver = self.sender.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(record) => subscriber(record).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Event subscriber {name} fell behind and missed {missed} events")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            trace!("Event subscriber {name} stopped");
+        });
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Log significant changes to elections.
+async fn log_election_changes(record: EventRecord) {
+    let req = match record.request_id {
+        Some(request_id) => format!("req{request_id} "),
+        None => String::new(),
+    };
+    match record.event {
+        DomainEvent::ElectionPublished { election_id } => {
+            warn!("  {req}Published election {election_id}")
+        }
+        DomainEvent::ElectionArchived { election_id } => {
+            warn!("  {req}Archived election {election_id}")
+        }
+        _ => {}
+    }
+}
+
+/// A fairing that places an [`EventBus`] into managed state, with the
+/// built-in subscribers registered.
+/// This fairing must be attached before any fairing that emits events or
+/// registers subscribers of its own.
+pub struct EventBusFairing;
+
+#[rocket::async_trait]
+impl Fairing for EventBusFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Event Bus",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let events = EventBus::new();
+        events.register("election logger", log_election_changes);
+        Ok(rocket.manage(events))
+    }
+}
@@ -1,29 +1,59 @@
+#[cfg(feature = "server")]
 #[macro_use]
 extern crate rocket;
 
+// Rocket re-exports the logging macros, but we still need them without it.
+#[cfg(not(feature = "server"))]
+#[macro_use]
+extern crate log;
+
 #[cfg(test)]
 #[macro_use]
 extern crate backend_test;
 
+#[cfg(feature = "server")]
 use rocket::{
     shield::{NoSniff, Shield},
     Build, Rocket,
 };
 
+#[cfg(feature = "server")]
 pub mod api;
+#[cfg(feature = "server")]
+pub mod check;
+#[cfg(feature = "server")]
 pub mod config;
+#[cfg(feature = "server")]
 pub mod error;
+#[cfg(feature = "server")]
+pub mod events;
+#[cfg(feature = "server")]
 pub mod logging;
+#[cfg(feature = "verification-core")]
 pub mod model;
+#[cfg(feature = "server")]
 pub mod scheduled_task;
+#[cfg(any(test, feature = "dev-tools"))]
+pub mod testdata;
 
+#[cfg(feature = "server")]
 pub fn build() -> Rocket<Build> {
     rocket::build()
         .mount("/", api::routes())
+        .register("/", error::catchers())
         .attach(Shield::default().disable::<NoSniff>())
         .attach(logging::LoggerFairing)
         .attach(config::ConfigFairing) // Must come before most other fairings.
         .attach(config::DatabaseFairing)
+        .attach(model::db::hmac_check::HmacCheckFairing)
         .attach(config::AwsFairing)
+        .attach(events::EventBusFairing) // Must come before fairings that emit events.
+        .attach(scheduled_task::TaskRegistryFairing) // Must come before fairings that schedule tasks.
+        .attach(model::mongodb::ClockSkewFairing)
         .attach(model::db::election::ElectionFinalizerFairing)
+        .attach(model::db::maintenance::MaintenanceFairing)
+        .attach(model::db::candidate_totals::TotalsFolderFairing)
+        .attach(api::archive_export::ArchiveExportFairing)
+        .attach(api::recent_casts::RecentCastsFairing)
+        .attach(api::sms_queue::SmsQueueFairing)
 }
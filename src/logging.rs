@@ -1,19 +1,36 @@
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
 use rocket::{
     fairing::{Fairing, Info, Kind},
-    http::StatusClass,
+    http::{Header, Status, StatusClass},
     request::{FromRequest, Outcome},
     Data, Orbit, Request, Response, Rocket,
 };
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::{api::client_ip::ClientIp, error::Error};
+
+/// Name of the header in which a client may send a UUID identifying the
+/// operation a request is part of, so that it can be found in the logs.
+pub const CLIENT_OP_ID_HEADER: &str = "X-Client-Op-Id";
+
 /// A unique identifier for a particular request.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct RequestId(pub usize);
+pub struct RequestId {
+    /// The server-generated ID.
+    pub id: usize,
+    /// The client-generated operation ID, if the request carried a valid one.
+    /// Retries of an operation share it, unlike the server-generated ID.
+    pub client_op_id: Option<ClientOpId>,
+}
 
 impl Display for RequestId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self.client_op_id {
+            Some(op_id) => write!(f, "{} op={}", self.id, op_id),
+            None => write!(f, "{}", self.id),
+        }
     }
 }
 
@@ -21,7 +38,22 @@ impl RequestId {
     /// Atomically get the next ID. This wraps around back to zero if you somehow exceed a usize.
     pub fn next() -> RequestId {
         static REQUEST_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
-        RequestId(REQUEST_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+        RequestId {
+            id: REQUEST_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+            client_op_id: None,
+        }
+    }
+
+    /// Get the next ID for the given request, along with its client operation
+    /// ID if it has a valid one.
+    pub fn for_request(req: &Request<'_>) -> RequestId {
+        RequestId {
+            client_op_id: req
+                .headers()
+                .get_one(CLIENT_OP_ID_HEADER)
+                .and_then(|op_id| op_id.parse().ok()),
+            ..Self::next()
+        }
     }
 }
 
@@ -31,7 +63,64 @@ impl<'r> FromRequest<'r> for RequestId {
     type Error = (); // No errors possible, use the `!` type once stabilised.
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        Outcome::Success(*req.local_cache(RequestId::next))
+        Outcome::Success(*req.local_cache(|| RequestId::for_request(req)))
+    }
+}
+
+/// A client-generated UUID identifying an operation, such as a vote which may
+/// be retried, sent in the [`CLIENT_OP_ID_HEADER`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ClientOpId([u8; 16]);
+
+impl FromStr for ClientOpId {
+    type Err = ();
+
+    /// Parse a UUID in its hyphenated form, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 36 || [8, 13, 18, 23].iter().any(|&i| bytes[i] != b'-') {
+            return Err(());
+        }
+        let hex = s.split('-').collect::<String>();
+        let decoded = HEXLOWER_PERMISSIVE.decode(hex.as_bytes()).map_err(|_| ())?;
+        decoded.try_into().map(Self).map_err(|_| ())
+    }
+}
+
+impl Display for ClientOpId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let hex = HEXLOWER.encode(&self.0);
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+/// A request guard that rejects requests with a malformed client operation ID.
+/// A well-formed one is carried by the [`RequestId`].
+pub struct ClientOpIdChecked;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientOpIdChecked {
+    type Error = Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one(CLIENT_OP_ID_HEADER) {
+            Some(op_id) if op_id.parse::<ClientOpId>().is_err() => Outcome::Error((
+                Status::BadRequest,
+                Error::Status(
+                    Status::BadRequest,
+                    format!("{} header must be a UUID", CLIENT_OP_ID_HEADER),
+                ),
+            )),
+            _ => Outcome::Success(ClientOpIdChecked),
+        }
     }
 }
 
@@ -60,19 +149,17 @@ impl Fairing for LoggerFairing {
     }
 
     async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
-        // Assign an ID.
-        let id = req.local_cache(RequestId::next);
-        // Get the HTTP method.
-        let method = req.method();
-        // Get the request URI.
-        let uri = req.uri();
         // Log the incoming request.
-        info!("->req{id} {method} {uri}");
+        info!("{}", request_log_line(req));
     }
 
     async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
         // Get the ID.
-        let id = req.local_cache(RequestId::next);
+        let id = req.local_cache(|| RequestId::for_request(req));
+        // Echo the client operation ID, so clients can tell retries apart.
+        if let Some(op_id) = id.client_op_id {
+            res.set_header(Header::new(CLIENT_OP_ID_HEADER, op_id.to_string()));
+        }
         // Get the response code.
         let code = res.status();
         // Get the matched route.
@@ -100,6 +187,19 @@ impl Fairing for LoggerFairing {
     }
 }
 
+/// The line logged for an incoming request, assigning it an ID.
+pub(crate) fn request_log_line(req: &Request<'_>) -> String {
+    // Assign an ID.
+    let id = req.local_cache(|| RequestId::for_request(req));
+    // Get the HTTP method.
+    let method = req.method();
+    // Get the request URI.
+    let uri = req.uri();
+    // Get the client, as seen through any trusted proxies.
+    let ip = ClientIp::for_request(req);
+    format!("->req{id} {method} {uri} from {ip}")
+}
+
 /// Initialise the test logger, if not already done so.
 #[cfg(test)]
 #[inline(always)]
@@ -107,3 +207,50 @@ pub(crate) fn init_test_logging() {
     const TARGETS: &[&str] = &["dre_ip", "dreip_backend"];
     log4rs_test_utils::test_logging::init_logging_once_for(TARGETS.iter().cloned(), None, None);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_op_ids() {
+        let op_id = "1B4E28BA-2FA1-11D2-883F-0016D3CCA427"
+            .parse::<ClientOpId>()
+            .unwrap();
+        assert_eq!(op_id.to_string(), "1b4e28ba-2fa1-11d2-883f-0016d3cca427");
+
+        let malformed = [
+            "",
+            "1b4e28ba2fa111d2883f0016d3cca427",
+            "1b4e28ba-2fa1-11d2-883f-0016d3cca42",
+            "1b4e28ba-2fa1-11d2-883f-0016d3cca4270",
+            "1b4e28ba-2fa1-11d2-883f-0016d3cca42g",
+            "1b4e28ba-2fa1-11d2-883f+0016d3cca427",
+            "1b4e28b-a2fa1-11d2-883f-0016d3cca427",
+        ];
+        for op_id in malformed {
+            assert!(op_id.parse::<ClientOpId>().is_err(), "{op_id}");
+        }
+
+        // Every request in an operation logs it, alongside its own ID.
+        let first = RequestId {
+            client_op_id: Some(op_id),
+            ..RequestId::next()
+        };
+        let retry = RequestId {
+            client_op_id: Some(op_id),
+            ..RequestId::next()
+        };
+        assert_ne!(first.id, retry.id);
+        assert_eq!(
+            first.to_string(),
+            format!("{} op=1b4e28ba-2fa1-11d2-883f-0016d3cca427", first.id)
+        );
+        assert_eq!(
+            retry.to_string(),
+            format!("{} op=1b4e28ba-2fa1-11d2-883f-0016d3cca427", retry.id)
+        );
+        let plain = RequestId::next();
+        assert_eq!(plain.to_string(), plain.id.to_string());
+    }
+}
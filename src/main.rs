@@ -1,9 +1,25 @@
+use dreip_backend::{
+    config::{Config, DatabaseFairing},
+    model::db::field_encryption,
+};
 use log::{error, info, LevelFilter};
 use rocket::Error as RocketError;
 
-async fn run() -> Result<(), RocketError> {
+async fn run(accept_hmac_rotation: bool, accept_clock_skew: bool) -> Result<(), RocketError> {
     info!("Configuring server...");
-    let rocket = dreip_backend::build().ignite().await?;
+    let mut rocket = dreip_backend::build();
+    if accept_hmac_rotation {
+        let figment = rocket
+            .figment()
+            .clone()
+            .merge(("accept_hmac_rotation", true));
+        rocket = rocket.configure(figment);
+    }
+    if accept_clock_skew {
+        let figment = rocket.figment().clone().merge(("accept_clock_skew", true));
+        rocket = rocket.configure(figment);
+    }
+    let rocket = rocket.ignite().await?;
     info!("...server configured!");
     // Disable rocket logging from now on.
     log4rs_dynamic_filters::DynamicLevelFilter::set("rocket", LevelFilter::Off);
@@ -11,8 +27,72 @@ async fn run() -> Result<(), RocketError> {
     Ok(())
 }
 
+/// Run the startup self-test, print the results, and exit without serving.
+async fn check(aws_dry_run: bool) -> ! {
+    info!("Running startup checks...");
+    let report = dreip_backend::check::run_checks(&rocket::Config::figment(), aws_dry_run).await;
+    print!("{report}");
+    std::process::exit(report.exit_code())
+}
+
+/// Seal the secrets of audited ballots stored before `field_encryption_key`
+/// was configured, and exit without serving.
+async fn seal_ballots() -> ! {
+    let figment = rocket::Config::figment();
+    let key = match Config::from_figment(&figment) {
+        Ok(config) => config.field_encryption_key(),
+        Err(e) => {
+            error!("Failed to load application config: {e}");
+            std::process::exit(1)
+        }
+    };
+    let Some(Ok(key)) = key else {
+        error!("No `field_encryption_key` is configured, so there is nothing to seal with");
+        std::process::exit(1)
+    };
+    field_encryption::install(key).expect("No other key can have been installed");
+    let db = match DatabaseFairing::probe(&figment).await {
+        Ok((_, db, _, _)) => db,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1)
+        }
+    };
+    match field_encryption::seal_existing_ballots(&db).await {
+        Ok(_) => std::process::exit(0),
+        Err(e) => {
+            error!("Failed to seal ballots: {e}");
+            std::process::exit(1)
+        }
+    }
+}
+
 #[rocket::main]
 async fn main() {
+    // Parse arguments.
+    let mut check_mode = false;
+    let mut aws_dry_run = false;
+    let mut seal_mode = false;
+    let mut accept_hmac_rotation = false;
+    let mut accept_clock_skew = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check_mode = true,
+            "--aws-dry-run" => aws_dry_run = true,
+            "--seal-ballots" => seal_mode = true,
+            "--accept-hmac-rotation" => accept_hmac_rotation = true,
+            "--accept-clock-skew" => accept_clock_skew = true,
+            _ => {
+                eprintln!("Unrecognised argument: {arg}");
+                eprintln!(
+                    "Usage: dreip-backend [--check [--aws-dry-run] | --seal-ballots | \
+                     [--accept-hmac-rotation] [--accept-clock-skew]]"
+                );
+                std::process::exit(2)
+            }
+        }
+    }
+
     // Set up logging.
     log4rs::init_file(
         "log4rs.yaml",
@@ -21,8 +101,15 @@ async fn main() {
     .expect("Failed to initialise logging");
     info!("Initialised logging");
 
+    if check_mode {
+        check(aws_dry_run).await;
+    }
+    if seal_mode {
+        seal_ballots().await;
+    }
+
     // Launch server.
-    if let Err(err) = run().await {
+    if let Err(err) = run(accept_hmac_rotation, accept_clock_skew).await {
         error!("{err}");
         error!("Critical failure, shutting down");
         std::process::exit(1)
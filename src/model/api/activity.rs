@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::api::counter::CounterStatus;
+
+/// Recent voting activity in an election, for live monitoring.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityReport {
+    /// Start of the first bucket.
+    pub window_start: DateTime<Utc>,
+    /// End of the last bucket. This is the end of the bucket containing the
+    /// time of the request, so buckets line up between successive reports.
+    pub window_end: DateTime<Utc>,
+    /// Length of each bucket, in seconds.
+    pub bucket_secs: u64,
+    /// Activity in each bucket, oldest first, including empty buckets.
+    pub buckets: Vec<ActivityBucket>,
+    /// Each question's ballot counter, by ascending question ID.
+    pub counters: Vec<CounterStatus>,
+}
+
+/// Voting activity within one bucket of an [`ActivityReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityBucket {
+    /// Start of the bucket; it ends where the next one starts.
+    pub start: DateTime<Utc>,
+    /// Number of ballots cast.
+    pub cast: u64,
+    /// Number of ballots audited.
+    pub audited: u64,
+    /// Number of ballots confirmed.
+    pub confirmed: u64,
+}
@@ -1,19 +1,25 @@
+#[cfg(feature = "server")]
 use argon2::{Config as HashConfig, Variant, Version};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::model::db::admin::NewAdmin;
+use crate::model::common::secret::SecretString;
+#[cfg(feature = "server")]
+use crate::model::db::{admin::NewAdmin, admin_token::AdminToken};
 
 pub const MIN_PASSWORD_LENGTH: usize = 8;
 
 /// Raw admin credentials, received from a user. These are never stored directly,
 /// since the password is in plaintext.
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AdminCredentials {
     pub username: String,
-    pub password: String,
+    pub password: SecretString,
 }
 
+#[cfg(feature = "server")]
 impl TryFrom<AdminCredentials> for NewAdmin {
     type Error = ();
 
@@ -21,35 +27,92 @@ impl TryFrom<AdminCredentials> for NewAdmin {
     /// This enforces that the username is non-empty, and the password meets minimum length.
     fn try_from(cred: AdminCredentials) -> Result<Self, Self::Error> {
         // Check credentials are acceptable.
-        if cred.username.is_empty() || cred.password.len() < MIN_PASSWORD_LENGTH {
+        if cred.username.is_empty() || cred.password.expose().len() < MIN_PASSWORD_LENGTH {
             return Err(());
         }
 
-        // Parameters chosen according to RFC9106:
-        // * 4 lanes as a sensible default.
-        // * 64 MiB mem_cost as the "first recommended" option of 2 GiB is excessive.
-        // * 3 rounds of time_cost to offset the lower mem_cost as recommended.
-        // * Argon2i as this is recommended for password hashing.
-        let mut salt = [0_u8; 16];
-        rand::thread_rng().fill(&mut salt);
-        let config = HashConfig {
-            ad: &[],
-            hash_length: 32,
-            lanes: 4,
-            mem_cost: 65536,
-            secret: &[],
-            time_cost: 3,
-            variant: Variant::Argon2i,
-            version: Version::Version13,
-        };
-        let password_hash = argon2::hash_encoded(cred.password.as_bytes(), &salt, &config).unwrap();
         Ok(Self {
             username: cred.username,
-            password_hash,
+            password_hash: hash_password(cred.password.expose()),
+            must_change_password: false,
         })
     }
 }
 
+/// Hash a password for storage.
+#[cfg(feature = "server")]
+pub fn hash_password(password: &str) -> String {
+    // Parameters chosen according to RFC9106:
+    // * 4 lanes as a sensible default.
+    // * 64 MiB mem_cost as the "first recommended" option of 2 GiB is excessive.
+    // * 3 rounds of time_cost to offset the lower mem_cost as recommended.
+    // * Argon2i as this is recommended for password hashing.
+    let mut salt = [0_u8; 16];
+    rand::thread_rng().fill(&mut salt);
+    let config = HashConfig {
+        ad: &[],
+        hash_length: 32,
+        lanes: 4,
+        mem_cost: 65536,
+        secret: &[],
+        time_cost: 3,
+        variant: Variant::Argon2i,
+        version: Version::Version13,
+    };
+    argon2::hash_encoded(password.as_bytes(), &salt, &config).unwrap()
+}
+
+/// A request by an admin to change their own password.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AdminPasswordChange {
+    pub current_password: SecretString,
+    pub new_password: SecretString,
+}
+
+/// A request to create a personal access token for the requesting admin.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AdminTokenSpec {
+    /// A name to tell the token apart from the admin's others.
+    pub name: String,
+    /// When the token stops working, if ever.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// API-friendly representation of a personal access token, without its secret.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AdminTokenDesc {
+    /// Token ID.
+    pub id: String,
+    /// Token name.
+    pub name: String,
+    /// When the token was created.
+    pub created_at: DateTime<Utc>,
+    /// When the token stops working, if ever.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "server")]
+impl From<AdminToken> for AdminTokenDesc {
+    fn from(token: AdminToken) -> Self {
+        Self {
+            id: token.id.to_string(),
+            name: token.token.name,
+            created_at: token.token.created_at,
+            expires_at: token.token.expires_at.map(|time| time.to_chrono()),
+        }
+    }
+}
+
+/// A newly-created personal access token, with the secret to send as a bearer
+/// token. The secret cannot be retrieved again.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NewAdminTokenDesc {
+    #[serde(flatten)]
+    pub token: AdminTokenDesc,
+    pub secret: String,
+}
+
 #[cfg(test)]
 mod examples {
     use super::*;
@@ -84,3 +147,16 @@ mod examples {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_debug() {
+        let credentials = AdminCredentials::example1();
+        let debug = format!("{:?}", credentials);
+        assert!(debug.contains("alice112"));
+        assert!(!debug.contains("dreip4lyfe"));
+    }
+}
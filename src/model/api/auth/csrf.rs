@@ -0,0 +1,111 @@
+use data_encoding::BASE64URL_NOPAD;
+use rand::Rng;
+use rocket::{
+    http::{Cookie, Status},
+    request::{FromRequest, Outcome},
+    Request,
+};
+
+use crate::{config::Config, error::Error};
+
+use super::{
+    token::{bearer_token, build_cookie},
+    CSRF_COOKIE, CSRF_HEADER,
+};
+
+/// Create a cookie holding a fresh random CSRF token, to be issued on login.
+///
+/// Unlike the auth token, this is readable by the frontend, which must echo it
+/// back in the [`CSRF_HEADER`] header of every state-changing request.
+pub fn csrf_cookie(config: &Config) -> Cookie<'static> {
+    let token: [u8; 32] = rand::thread_rng().gen();
+    build_cookie(
+        CSRF_COOKIE,
+        BASE64URL_NOPAD.encode(&token),
+        config.auth_ttl(),
+        false,
+        config,
+    )
+}
+
+/// A request guard implementing double-submit CSRF protection.
+///
+/// A malicious site can cause the browser to send our cookies, but cannot read
+/// them, so a request whose [`CSRF_HEADER`] header matches the [`CSRF_COOKIE`]
+/// cookie must have come from our own frontend.
+///
+/// Requests authenticated by a personal access token are exempt, as a browser
+/// never sends the `Authorization` header on its own.
+pub struct CsrfChecked;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfChecked {
+    type Error = Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if bearer_token(req).is_some() {
+            return Outcome::Success(CsrfChecked);
+        }
+        let cookie = req.cookies().get(CSRF_COOKIE);
+        let header = req.headers().get_one(CSRF_HEADER);
+        match (cookie, header) {
+            (Some(cookie), Some(header))
+                if constant_time_eq(cookie.value().as_bytes(), header.as_bytes()) =>
+            {
+                Outcome::Success(CsrfChecked)
+            }
+            _ => Outcome::Error((
+                Status::Forbidden,
+                Error::Status(
+                    Status::Forbidden,
+                    format!("Missing or mismatched {} header", CSRF_HEADER),
+                ),
+            )),
+        }
+    }
+}
+
+/// Compare two byte strings without leaking the position of the first difference.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Helpers for tests.
+#[cfg(test)]
+pub mod testing {
+    use rocket::{http::Header, local::asynchronous::LocalRequest};
+
+    use super::*;
+
+    /// Echo the tracked client's CSRF cookie back in the header, as the frontend would.
+    pub trait WithCsrf {
+        fn csrf(self) -> Self;
+    }
+
+    impl WithCsrf for LocalRequest<'_> {
+        fn csrf(self) -> Self {
+            let token = self
+                .inner()
+                .cookies()
+                .get(CSRF_COOKIE)
+                .map(|cookie| cookie.value().to_string());
+            match token {
+                Some(token) => self.header(Header::new(CSRF_HEADER, token)),
+                None => self,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -1,6 +1,30 @@
+#[cfg(feature = "server")]
+mod csrf;
 mod request;
+#[cfg(feature = "server")]
+mod scope;
+#[cfg(feature = "server")]
+mod step_up;
+#[cfg(feature = "server")]
 mod token;
+#[cfg(feature = "server")]
 mod user;
 
-pub use request::{RecaptchaError, VoterChallengeRequest, VoterVerifyRequest};
-pub use token::{AuthToken, AUTH_TOKEN_COOKIE};
+#[cfg(test)]
+pub use csrf::testing::WithCsrf;
+#[cfg(feature = "server")]
+pub use csrf::{constant_time_eq, csrf_cookie, CsrfChecked};
+#[cfg(feature = "server")]
+pub use request::RecaptchaError;
+pub use request::{VoterChallengeRequest, VoterVerifyRequest};
+#[cfg(feature = "server")]
+pub use scope::ScopedCookies;
+#[cfg(feature = "server")]
+pub use step_up::{StepUp, StepUpToken, STEP_UP_COOKIE};
+#[cfg(feature = "server")]
+pub use token::{build_cookie, AuthToken, AUTH_TOKEN_COOKIE};
+
+/// Name of the cookie holding the CSRF token issued on login.
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Name of the header in which the CSRF token must be echoed back.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
@@ -1,32 +1,74 @@
+#[cfg(feature = "server")]
+use std::net::IpAddr;
+
+#[cfg(feature = "server")]
 #[cfg_attr(any(not(feature = "otp"), test), allow(unused_imports))]
 use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "server")]
+use phonenumber::country;
+#[cfg(feature = "server")]
 use reqwest;
+#[cfg(feature = "server")]
+use rocket::http::Status;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
 use thiserror::Error;
 
-use crate::model::api::{otp::Code, sms::Sms};
+#[cfg(feature = "server")]
+use crate::error::Error as ApiError;
+use crate::model::api::{
+    otp::Code,
+    sms::{Sms, SmsInput},
+};
 
 #[cfg(any(not(feature = "otp"), test))]
 const TEST_RECAPTCHA_RESPONSE: &str = "this response will succeed in test mode";
 
 /// reCAPTCHA tokens older than this many minutes are not accepted.
+#[cfg(feature = "server")]
 #[cfg_attr(any(not(feature = "otp"), test), allow(dead_code))]
 const MAX_TOKEN_LIFE_MINUTES: i64 = 3;
 
 /// An authentication request for a specific SMS number.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoterChallengeRequest {
-    sms: Sms, // Deliberately not public, so it can only be extracted via `verify()`
+    sms: SmsInput, // Deliberately not public, so it can only be extracted via `verify()`
     g_recaptcha_response: String,
+    /// Language code selecting the SMS template.
+    #[serde(default)]
+    pub lang: Option<String>,
 }
 
 impl VoterChallengeRequest {
-    /// Verify the reCAPTCHA, revealing the SMS if successful.
-    /// This can only be attempted once, due to the reCAPTCHA API.
-    pub async fn verify(self, secret: &str, hostname: &str) -> Result<Sms, RecaptchaError> {
-        verify_recaptcha(self.g_recaptcha_response, secret, hostname)
-            .await
-            .map(|_| self.sms)
+    /// Create a request, as a client.
+    pub fn new(sms: Sms, g_recaptcha_response: String, lang: Option<String>) -> Self {
+        Self {
+            sms: sms.into(),
+            g_recaptcha_response,
+            lang,
+        }
+    }
+
+    /// Verify the reCAPTCHA, revealing the SMS if successful. Numbers without a
+    /// country code are read as being from `default_region`.
+    /// This can only be attempted once, due to the reCAPTCHA API, so an invalid
+    /// number is rejected before it is attempted.
+    #[cfg(feature = "server")]
+    pub async fn verify(
+        self,
+        secret: &str,
+        hostname: &str,
+        remote_ip: Option<IpAddr>,
+        default_region: Option<country::Id>,
+    ) -> Result<Sms, ApiError> {
+        let sms = self.sms.parse_in(default_region).map_err(|e| {
+            ApiError::Status(
+                Status::UnprocessableEntity,
+                format!("Invalid SMS number: {e}"),
+            )
+        })?;
+        verify_recaptcha(self.g_recaptcha_response, secret, hostname, remote_ip).await?;
+        Ok(sms)
     }
 }
 
@@ -39,21 +81,38 @@ pub struct VoterVerifyRequest {
 }
 
 impl VoterVerifyRequest {
+    /// Create a request, as a client.
+    pub fn new(code: Code, g_recaptcha_response: String) -> Self {
+        Self {
+            code,
+            g_recaptcha_response,
+        }
+    }
+
     /// Verify the reCAPTCHA, revealing the code if successful.
     /// This can only be attempted once, due to the reCAPTCHA API.
-    pub async fn verify(self, secret: &str, hostname: &str) -> Result<Code, RecaptchaError> {
-        verify_recaptcha(self.g_recaptcha_response, secret, hostname)
+    #[cfg(feature = "server")]
+    pub async fn verify(
+        self,
+        secret: &str,
+        hostname: &str,
+        remote_ip: Option<IpAddr>,
+    ) -> Result<Code, RecaptchaError> {
+        verify_recaptcha(self.g_recaptcha_response, secret, hostname, remote_ip)
             .await
             .map(|_| self.code)
     }
 }
 
 /// Verify the given reCAPTCHA response by contacting the google API.
+/// The client's IP, if known, is passed on to help it spot abuse.
+#[cfg(feature = "server")]
 #[cfg_attr(any(not(feature = "otp"), test), allow(unused_variables))]
 async fn verify_recaptcha(
     response: String,
     secret: &str,
     hostname: &str,
+    remote_ip: Option<IpAddr>,
 ) -> Result<(), RecaptchaError> {
     // In test mode, just check the dummy value is equal to some string.
     #[cfg(any(not(feature = "otp"), test))]
@@ -69,6 +128,7 @@ async fn verify_recaptcha(
         let parameters = RecaptchaVerifyRequest {
             secret: secret.to_string(),
             response,
+            remoteip: remote_ip.map(|ip| ip.to_string()),
         };
         let response: RecaptchaVerifyResponse = client
             .post("https://www.google.com/recaptcha/api/siteverify")
@@ -102,6 +162,7 @@ async fn verify_recaptcha(
 }
 
 /// Possible errors resulting from verifying a reCAPTCHA token.
+#[cfg(feature = "server")]
 #[derive(Debug, Error)]
 pub enum RecaptchaError {
     /// Failed to contact the google verification API.
@@ -119,15 +180,20 @@ pub enum RecaptchaError {
 }
 
 /// A reCAPTCHA verification request to send to the google API.
+#[cfg(feature = "server")]
 #[derive(Serialize, Deserialize)]
 struct RecaptchaVerifyRequest {
     /// API connection key.
     pub secret: String,
     /// The reCAPTCHA token from the client.
     pub response: String,
+    /// The client's IP address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remoteip: Option<String>,
 }
 
 /// A reCAPTCHA verification response from the google API.
+#[cfg(feature = "server")]
 #[derive(Serialize, Deserialize)]
 struct RecaptchaVerifyResponse {
     /// Did the reCAPTCHA successfully verify?
@@ -150,15 +216,17 @@ mod examples {
     impl VoterChallengeRequest {
         pub fn example() -> Self {
             Self {
-                sms: Sms::example(),
+                sms: Sms::example().into(),
                 g_recaptcha_response: TEST_RECAPTCHA_RESPONSE.to_string(),
+                lang: None,
             }
         }
 
         pub fn example_invalid() -> Self {
             Self {
-                sms: Sms::example(),
+                sms: Sms::example().into(),
                 g_recaptcha_response: "not valid".to_string(),
+                lang: None,
             }
         }
     }
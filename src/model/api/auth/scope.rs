@@ -0,0 +1,102 @@
+use std::convert::Infallible;
+
+use rocket::{
+    http::{Cookie, CookieJar},
+    request::{FromRequest, Outcome},
+    Request,
+};
+
+use crate::config::Config;
+
+/// A request's cookies, which are added and removed with the domain and path
+/// they are scoped to in the config.
+///
+/// Browsers reject a cookie whose domain does not contain the host that set
+/// it, so the configured domain is only used when the request's host is within
+/// it. Otherwise the cookies are left host-only rather than breaking auth.
+pub struct ScopedCookies<'r> {
+    jar: &'r CookieJar<'r>,
+    domain: Option<String>,
+    path: String,
+}
+
+impl<'r> ScopedCookies<'r> {
+    /// Scope the given cookies for a request to the given host, if known.
+    pub fn new(jar: &'r CookieJar<'r>, config: &Config, host: Option<&str>) -> Self {
+        let domain = config.cookie_domain().and_then(|domain| {
+            if host.is_some_and(|host| host_within(host, domain)) {
+                Some(domain.to_string())
+            } else {
+                warn!(
+                    "Not scoping cookies to '{}', which does not contain the request host {:?}",
+                    domain, host
+                );
+                None
+            }
+        });
+        Self {
+            jar,
+            domain,
+            path: config.cookie_path().to_string(),
+        }
+    }
+
+    /// Give a cookie our scope.
+    fn scope(&self, mut cookie: Cookie<'static>) -> Cookie<'static> {
+        cookie.set_path(self.path.clone());
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie
+    }
+
+    /// Add a cookie.
+    pub fn add(&self, cookie: Cookie<'static>) {
+        self.jar.add(self.scope(cookie));
+    }
+
+    /// Add a cookie, encrypted so that it can be neither read nor forged.
+    pub fn add_private(&self, cookie: Cookie<'static>) {
+        self.jar.add_private(self.scope(cookie));
+    }
+
+    /// Remove the cookie with the given name. Browsers only remove a cookie
+    /// whose domain and path match, so this must be scoped just as it was added.
+    pub fn remove(&self, name: &'static str) {
+        self.jar.remove(self.scope(Cookie::from(name)));
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ScopedCookies<'r> {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Unwrap is safe as `Config` is always managed.
+        let config = req.rocket().state::<Config>().unwrap();
+        let host = req.host().map(|host| host.domain().as_str());
+        Outcome::Success(Self::new(req.cookies(), config, host))
+    }
+}
+
+/// Is the given host the given domain, or one of its subdomains?
+fn host_within(host: &str, domain: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let domain = domain.to_ascii_lowercase();
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_within_domain() {
+        assert!(host_within("vote.example.ac.uk", "vote.example.ac.uk"));
+        assert!(host_within("api.vote.example.ac.uk", "vote.example.ac.uk"));
+        assert!(host_within("API.Vote.Example.ac.uk", "vote.example.ac.uk"));
+        assert!(!host_within("evilvote.example.ac.uk", "vote.example.ac.uk"));
+        assert!(!host_within("example.ac.uk", "vote.example.ac.uk"));
+        assert!(!host_within("localhost", "vote.example.ac.uk"));
+    }
+}
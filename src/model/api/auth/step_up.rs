@@ -0,0 +1,113 @@
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rocket::{
+    http::Cookie,
+    request::{FromRequest, Outcome},
+    Request, State,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::Error,
+    model::{common::election::ElectionId, db::election::Election, mongodb::Id},
+};
+
+use super::token::build_cookie;
+
+pub const STEP_UP_COOKIE: &str = "step_up_token";
+
+/// A short-lived token proving that a voter recently re-verified their SMS
+/// number, scoped to a single election.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepUpToken {
+    /// The voter who stepped up.
+    pub voter_id: Id,
+    /// The election they stepped up for.
+    #[serde(rename = "eid")]
+    pub election_id: ElectionId,
+}
+
+impl StepUpToken {
+    /// Create a new step-up token for the given voter and election.
+    pub fn new(voter_id: Id, election_id: ElectionId) -> Self {
+        Self {
+            voter_id,
+            election_id,
+        }
+    }
+
+    /// Serialize this token into a cookie.
+    pub fn into_cookie(self, config: &Config) -> Cookie<'static> {
+        let claims = Claims {
+            token: self,
+            expire_at: Utc::now() + config.step_up_ttl(),
+        };
+
+        let token = jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.jwt_secret()),
+        )
+        .expect("JWT encoding is infallible with default settings");
+
+        build_cookie(STEP_UP_COOKIE, token, config.step_up_ttl(), true, config)
+    }
+
+    /// Deserialize a token from a cookie.
+    pub fn from_cookie(cookie: &Cookie<'static>, config: &Config) -> Result<Self, Error> {
+        let token = jsonwebtoken::decode(
+            cookie.value(),
+            &DecodingKey::from_secret(config.jwt_secret()),
+            &Validation::default(),
+        )
+        .map(|claims: TokenData<Claims>| claims.claims.token)?;
+        Ok(token)
+    }
+}
+
+/// Cookie claims: the token itself plus an expiry datetime.
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    #[serde(flatten)]
+    token: StepUpToken,
+    #[serde(rename = "exp", with = "ts_seconds")]
+    expire_at: DateTime<Utc>,
+}
+
+/// A request guard holding the voter's step-up token, if they have a valid one.
+///
+/// This never fails, since most elections do not require step-up; endpoints
+/// that act in an election must call [`StepUp::require`] once they have it.
+pub struct StepUp(Option<StepUpToken>);
+
+impl StepUp {
+    /// Fail with [`Error::StepUpRequired`] if the election requires step-up
+    /// and the given voter has not stepped up for it.
+    pub fn require(&self, election: &Election, voter_id: Id) -> Result<(), Error> {
+        if !election.metadata.requires_step_up {
+            return Ok(());
+        }
+        match &self.0 {
+            Some(token) if token.election_id == election.id && token.voter_id == voter_id => Ok(()),
+            _ => Err(Error::StepUpRequired(election.id)),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for StepUp {
+    type Error = Error;
+
+    /// Get the step-up token from the cookie, treating an invalid or expired
+    /// token as absent.
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Unwrap is safe as `Config` is always managed.
+        let config = req.guard::<&State<Config>>().await.unwrap();
+        let token = req
+            .cookies()
+            .get(STEP_UP_COOKIE)
+            .and_then(|cookie| StepUpToken::from_cookie(cookie, config).ok());
+        Outcome::Success(StepUp(token))
+    }
+}
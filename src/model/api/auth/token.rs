@@ -1,9 +1,10 @@
 use std::marker::PhantomData;
 
-use chrono::{serde::ts_seconds, DateTime, Utc};
+use chrono::{serde::ts_seconds, DateTime, Duration as ChronoDuration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, TokenData, Validation};
+use mongodb::bson::doc;
 use rocket::{
-    http::{Cookie, SameSite, Status},
+    http::{Cookie, Method, SameSite, Status},
     outcome::{try_outcome, IntoOutcome},
     request::{FromRequest, Outcome},
     time::Duration,
@@ -14,7 +15,11 @@ use serde::{Deserialize, Serialize};
 use crate::config::Config;
 use crate::error::Error;
 use crate::model::{
-    db::{admin::Admin, voter::Voter},
+    db::{
+        admin::Admin,
+        admin_token::{hash_secret, AdminToken},
+        voter::Voter,
+    },
     mongodb::{Coll, Id},
 };
 
@@ -22,6 +27,39 @@ use super::user::{Rights, User};
 
 pub const AUTH_TOKEN_COOKIE: &str = "auth_token";
 
+/// The only endpoints an admin who must change their password may use.
+const PASSWORD_CHANGE_ENDPOINTS: [(Method, &str); 2] = [
+    (Method::Put, "/admins/me/password"),
+    (Method::Delete, "/auth"),
+];
+
+/// Get the secret of the personal access token sent in the request's
+/// `Authorization: Bearer` header, if there is one.
+pub fn bearer_token<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+    let header = req.headers().get_one("Authorization")?;
+    let (scheme, secret) = header.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("Bearer").then(|| secret.trim())
+}
+
+/// Build a cookie with our standard attributes: `SameSite=Strict`, `HttpOnly`
+/// unless it needs to be readable by the frontend, and `Secure` unless disabled
+/// in the config for local development. Its domain and path are set when it is
+/// added, by [`ScopedCookies`](super::ScopedCookies).
+pub fn build_cookie(
+    name: &'static str,
+    value: String,
+    max_age: ChronoDuration,
+    http_only: bool,
+    config: &Config,
+) -> Cookie<'static> {
+    Cookie::build((name, value))
+        .max_age(Duration::seconds(max_age.num_seconds()))
+        .http_only(http_only)
+        .secure(config.secure_cookies())
+        .same_site(SameSite::Strict)
+        .build()
+}
+
 /// An authentication token representing a specific user with specific rights.
 #[derive(Serialize, Deserialize)]
 pub struct AuthToken<U> {
@@ -66,11 +104,7 @@ where
         )
         .expect("JWT encoding is infallible with default settings");
 
-        Cookie::build((AUTH_TOKEN_COOKIE, token))
-            .max_age(Duration::seconds(config.auth_ttl().num_seconds()))
-            .http_only(true)
-            .same_site(SameSite::Strict)
-            .build()
+        build_cookie(AUTH_TOKEN_COOKIE, token, config.auth_ttl(), true, config)
     }
 
     /// Deserialize a token from a cookie.
@@ -101,21 +135,41 @@ where
 {
     type Error = Error;
 
-    /// Get an [`AuthToken`] from the cookie and verify that it has the correct rights for this user
-    /// type.
+    /// Get an [`AuthToken`] from the cookie, or for admins from a personal access
+    /// token, and verify that it has the correct rights for this user type.
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         // Unwrap is safe as `Config` is always managed.
         let config = req.guard::<&State<Config>>().await.unwrap();
+        let db = req.guard::<&State<mongodb::Database>>().await.unwrap();
 
-        // Forward to any routes that do not require an authentication token.
-        let cookie = try_outcome!(req
-            .cookies()
-            .get(AUTH_TOKEN_COOKIE)
-            .or_forward(Status::Unauthorized));
-
-        // Decode the token.
-        let token: Self =
-            try_outcome!(Self::from_cookie(cookie, config).or_forward(Status::Unauthorized));
+        let token: Self = if let Some(secret) = bearer_token(req) {
+            // Only admins have personal access tokens. A bearer token takes
+            // precedence over any cookie, as `CsrfChecked` relies on it.
+            if U::RIGHTS != Rights::Admin {
+                return Outcome::Forward(Status::Unauthorized);
+            }
+            let access_token = Coll::<AdminToken>::from_db(db)
+                .find_one(doc! { "secret_hash": hash_secret(secret) }, None)
+                .await;
+            match access_token {
+                Ok(Some(access_token)) if !access_token.is_expired() => Self {
+                    id: access_token.admin_id,
+                    rights: Rights::Admin,
+                    phantom: PhantomData,
+                },
+                Ok(_) => return Outcome::Forward(Status::Unauthorized),
+                Err(e) => return Outcome::Error((Status::InternalServerError, e.into())),
+            }
+        } else {
+            // Forward to any routes that do not require an authentication token.
+            let cookie = try_outcome!(req
+                .cookies()
+                .get(AUTH_TOKEN_COOKIE)
+                .or_forward(Status::Unauthorized));
+
+            // Decode the token.
+            try_outcome!(Self::from_cookie(cookie, config).or_forward(Status::Unauthorized))
+        };
 
         // Check it represents the correct rights.
         if !token.permits(U::RIGHTS) {
@@ -123,7 +177,6 @@ where
         }
 
         // Check the user actually exists.
-        let db = req.guard::<&State<mongodb::Database>>().await.unwrap();
         match token.rights {
             Rights::Voter => {
                 let voter = Coll::<Voter>::from_db(db)
@@ -140,6 +193,13 @@ where
                     .find_one(token.id.as_doc(), None)
                     .await;
                 match admin {
+                    Ok(Some(admin))
+                        if admin.must_change_password
+                            && !PASSWORD_CHANGE_ENDPOINTS
+                                .contains(&(req.method(), req.uri().path().as_str())) =>
+                    {
+                        Error::PasswordChangeRequired.fail_guard(req)
+                    }
                     Ok(Some(_)) => Outcome::Success(token),
                     Ok(None) => Outcome::Forward(Status::Unauthorized),
                     Err(e) => Outcome::Error((Status::InternalServerError, e.into())),
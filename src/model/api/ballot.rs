@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::model::{
@@ -5,21 +6,103 @@ use crate::model::{
     common::{ballot::BallotId, election::QuestionId},
 };
 
+/// Name of the header set to `true` on the response to a cast that repeated
+/// one made moments before, which carries the receipts of the original ballots.
+pub const DUPLICATE_CAST_HEADER: &str = "X-Duplicate-Cast";
+
 /// A ballot that the voter wishes to cast, representing a specific candidate
 /// for a specific question.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BallotSpec {
     pub question: QuestionId,
     pub candidate: String,
+    /// The name written in, if the candidate is the write-in candidate. This is
+    /// not part of the ballot's cryptography or receipt, so cannot be verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_in_name: Option<String>,
 }
 
 /// A ballot that the voter wishes to recall in order to audit or confirm.
 /// The ballot is identified by its ID and question ID, and ownership of this
-/// ballot is verified by the signature, which only the owning voter will have.
+/// ballot is verified by its receipt's signature or recall code, which only
+/// the owning voter will have. Exactly one of the two must be given.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "BallotRecallFields")]
 pub struct BallotRecall {
     pub ballot_id: BallotId,
     pub question_id: QuestionId,
+    #[serde(flatten)]
+    pub proof: OwnershipProof,
+}
+
+/// Proof that the voter recalling a ballot owns it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnershipProof {
+    /// The signature from the ballot's receipt.
     #[serde(with = "dre_ip::group::serde_bytestring")]
-    pub signature: Signature,
+    Signature(Signature),
+    /// The recall code from the ballot's receipt. This is much shorter than a
+    /// signature, so recalls using it are rate-limited.
+    RecallCode(String),
+}
+
+/// [`BallotRecall`] as sent by clients, before checking that exactly one
+/// proof of ownership was given.
+#[derive(Deserialize)]
+struct BallotRecallFields {
+    ballot_id: BallotId,
+    question_id: QuestionId,
+    #[serde(default)]
+    signature: Option<SignatureBytes>,
+    #[serde(default)]
+    recall_code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignatureBytes(#[serde(with = "dre_ip::group::serde_bytestring")] Signature);
+
+impl TryFrom<BallotRecallFields> for BallotRecall {
+    type Error = &'static str;
+
+    fn try_from(fields: BallotRecallFields) -> Result<Self, Self::Error> {
+        let proof = match (fields.signature, fields.recall_code) {
+            (Some(SignatureBytes(signature)), None) => OwnershipProof::Signature(signature),
+            (None, Some(code)) => OwnershipProof::RecallCode(code),
+            (Some(_), Some(_)) => return Err("give either `signature` or `recall_code`, not both"),
+            (None, None) => return Err("missing `signature` or `recall_code`"),
+        };
+        Ok(Self {
+            ballot_id: fields.ballot_id,
+            question_id: fields.question_id,
+            proof,
+        })
+    }
+}
+
+/// A reference to a ballot on the bulletin board, identified by its ID and question ID.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BallotReference {
+    pub ballot_id: BallotId,
+    pub question_id: QuestionId,
+}
+
+/// How many confirmed ballots wrote in the same name for a question's write-in
+/// candidate. Unlike the tallies, these cannot be verified, since the names
+/// are not part of any ballot's cryptography.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteInCount {
+    pub name: String,
+    pub count: u64,
+}
+
+/// Counts of a question's ballots in each state.
+/// These reveal nothing about vote content, so are safe to publish at any time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BallotStats {
+    pub unconfirmed: u64,
+    pub audited: u64,
+    pub confirmed: u64,
+    /// Creation time of the most recent ballot, if there are any.
+    pub last_activity: Option<DateTime<Utc>>,
 }
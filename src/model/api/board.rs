@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use crate::model::db::board::BoardHead;
+
+/// API-friendly representation of a bulletin board head.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardHeadDesc {
+    pub election_id: u32,
+    pub question_id: u32,
+    /// Number of events in the chain.
+    pub length: u64,
+    /// Hash of the last event in the chain.
+    pub head: String,
+}
+
+#[cfg(feature = "server")]
+impl From<BoardHead> for BoardHeadDesc {
+    fn from(head: BoardHead) -> Self {
+        Self {
+            election_id: head.election_id,
+            question_id: head.question_id,
+            length: head.length,
+            head: head.head,
+        }
+    }
+}
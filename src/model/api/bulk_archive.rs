@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::election::ElectionId;
+
+/// A request to archive many elections at once, e.g. at the end of a term.
+///
+/// Exactly one of `election_ids` and `ended_before` must be given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkArchiveRequest {
+    /// Archive exactly these elections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub election_ids: Option<Vec<ElectionId>>,
+    /// Archive every election that ended before this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_before: Option<DateTime<Utc>>,
+    /// Only report what would be archived, without archiving anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What happened to one election of a bulk archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkArchiveResult {
+    /// Election ID.
+    pub election_id: ElectionId,
+    /// What happened to it.
+    #[serde(flatten)]
+    pub outcome: BulkArchiveOutcome,
+}
+
+/// The outcome of archiving one election of a bulk archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BulkArchiveOutcome {
+    /// The election was archived and its finalizer run.
+    Archived,
+    /// The election was archived, but its finalizer failed, and will be
+    /// retried later.
+    FinalizationPending {
+        /// What went wrong.
+        error: String,
+    },
+    /// The election would have been archived, but this was a dry run.
+    WouldArchive,
+    /// The election was left alone.
+    Skipped {
+        /// Why it was left alone.
+        reason: String,
+    },
+    /// Archiving the election was attempted, but failed.
+    Failed {
+        /// What went wrong.
+        error: String,
+    },
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use dre_ip::DreipPrivateKey;
+use dre_ip::{DreipPublicKey, Serializable};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "server")]
+use crate::model::{api::election::ElectionDescription, db::election::Election};
+use crate::model::{
+    api::{
+        election::{
+            check_group, verify_receipt_full, verify_unconfirmed_stub, ElectionCrypto,
+            VerificationError,
+        },
+        receipt::{PublicReceipt, Signature},
+    },
+    common::election::ElectionId,
+};
+
+/// A downloadable bundle of a voter's receipts, signed by the election key.
+/// This allows the voter to demonstrate that their ballots were included on
+/// the bulletin board, even if the board later becomes unavailable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptBundle {
+    /// Election ID.
+    pub election_id: ElectionId,
+    /// Election cryptographic data needed for verification.
+    pub election: ElectionCrypto,
+    /// The current public receipt of each referenced ballot.
+    pub receipts: Vec<PublicReceipt>,
+    /// When the bundle was produced.
+    pub timestamp: DateTime<Utc>,
+    /// Signature over the bundle hash.
+    #[serde(with = "dre_ip::group::serde_bytestring")]
+    pub signature: Signature,
+}
+
+impl ReceiptBundle {
+    /// Construct and sign a bundle of the given receipts.
+    #[cfg(feature = "server")]
+    pub fn new(election: &Election, receipts: Vec<PublicReceipt>) -> Self {
+        let timestamp = Utc::now();
+        let hash = bundle_hash(election.id, &receipts, &timestamp);
+        let signature = election.crypto.private_key.expose().sign(&hash);
+
+        Self {
+            election_id: election.id,
+            election: ElectionDescription::from(election.clone()).crypto,
+            receipts,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Verify every receipt in the bundle, and the bundle signature itself.
+    pub fn verify(&self) -> Result<(), VerificationError> {
+        check_group(&self.election.group)?;
+        for receipt in &self.receipts {
+            match receipt {
+                PublicReceipt::Unconfirmed(stub) => verify_unconfirmed_stub(stub, &self.election)?,
+                PublicReceipt::Audited(receipt) => verify_receipt_full(receipt, &self.election)?,
+                PublicReceipt::AuditedRedacted(receipt) => {
+                    verify_receipt_full(receipt, &self.election)?
+                }
+                PublicReceipt::Confirmed(receipt) => verify_receipt_full(receipt, &self.election)?,
+            }
+        }
+        debug!("Verified {} bundled receipts", self.receipts.len());
+
+        let hash = bundle_hash(self.election_id, &self.receipts, &self.timestamp);
+        if !self.election.public_key.verify(&hash, &self.signature) {
+            return Err(VerificationError::BundleSignature);
+        }
+        debug!("Verified bundle signature");
+
+        Ok(())
+    }
+}
+
+/// Hash the contents of a bundle.
+/// Each receipt is already signed over its full contents, so binding the
+/// receipt signatures is sufficient to cover the receipts themselves.
+fn bundle_hash(
+    election_id: ElectionId,
+    receipts: &[PublicReceipt],
+    timestamp: &DateTime<Utc>,
+) -> Vec<u8> {
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(election_id.to_le_bytes());
+    hasher.update(timestamp.timestamp_millis().to_le_bytes());
+    for receipt in receipts {
+        let (ballot_id, question_id, signature) = match receipt {
+            PublicReceipt::Unconfirmed(stub) => (stub.ballot_id, stub.question_id, &stub.signature),
+            PublicReceipt::Audited(r) => (r.ballot_id, r.question_id, &r.signature),
+            PublicReceipt::AuditedRedacted(r) => (r.ballot_id, r.question_id, &r.signature),
+            PublicReceipt::Confirmed(r) => (r.ballot_id, r.question_id, &r.signature),
+        };
+        hasher.update(ballot_id.to_le_bytes());
+        hasher.update(question_id.to_le_bytes());
+        hasher.update(signature.to_bytes());
+    }
+    hasher.finalize().to_vec()
+}
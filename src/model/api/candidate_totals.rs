@@ -1,7 +1,12 @@
-use dre_ip::DreipGroup as DreipGroupTrait;
+use dre_ip::{DreipGroup as DreipGroupTrait, Serializable};
 use serde::{Deserialize, Serialize};
 
-use crate::model::{common::election::DreipGroup, db::candidate_totals::CandidateTotals};
+#[cfg(feature = "server")]
+use crate::model::db::candidate_totals::CandidateTotals;
+use crate::model::{
+    api::ballot::BallotStats,
+    common::election::{DreipGroup, QuorumReport},
+};
 
 /// API-friendly representation of candidate totals.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -15,8 +20,19 @@ pub struct CandidateTotalsDesc {
     /// Sum of randoms.
     #[serde(with = "dre_ip::group::serde_bytestring")]
     pub r_sum: <DreipGroup as DreipGroupTrait>::Scalar,
+    /// Number of confirmed ballots counted in the tally.
+    /// Zero in dumps taken before it was recorded.
+    #[serde(default)]
+    pub confirmed_ballot_count: u64,
+    /// Ballot counts for the whole question, included by the totals endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ballot_stats: Option<BallotStats>,
+    /// Whether the whole question met its quorum, included by the totals endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumReport>,
 }
 
+#[cfg(feature = "server")]
 impl From<CandidateTotals> for CandidateTotalsDesc {
     fn from(totals: CandidateTotals) -> Self {
         Self {
@@ -25,6 +41,44 @@ impl From<CandidateTotals> for CandidateTotalsDesc {
             candidate_name: totals.totals.candidate_name,
             tally: totals.totals.crypto.tally,
             r_sum: totals.totals.crypto.r_sum,
+            confirmed_ballot_count: totals.totals.confirmed_ballot_count.unwrap_or_default(),
+            ballot_stats: None,
+            quorum: None,
+        }
+    }
+}
+
+/// Convert a tally `Scalar` to a u64, or `None` if it is too big to fit.
+pub fn tally_to_u64(tally_scalar: <DreipGroup as DreipGroupTrait>::Scalar) -> Option<u64> {
+    const BYTES: usize = 8;
+    let bytes = Serializable::to_bytes(&tally_scalar);
+
+    // Any bytes beyond the last eight must be zero.
+    let split = bytes.len().saturating_sub(BYTES);
+    let (excess, low) = bytes.split_at(split);
+    if excess.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    // Pad to exactly eight bytes.
+    let mut u64_bytes = [0; BYTES];
+    u64_bytes[BYTES - low.len()..].copy_from_slice(low);
+    Some(u64::from_be_bytes(u64_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use dre_ip::DreipScalar;
+
+    use super::*;
+
+    #[test]
+    fn small_tallies_convert() {
+        type Scalar = <DreipGroup as DreipGroupTrait>::Scalar;
+        let mut tally = Scalar::zero();
+        for expected in 0..300 {
+            assert_eq!(tally_to_u64(tally), Some(expected));
+            tally += Scalar::one();
         }
     }
 }
@@ -0,0 +1,47 @@
+use rocket::{
+    http::{ContentType, Header},
+    response::{self, Responder, Response},
+    serde::json::Json,
+    Request,
+};
+use serde::Serialize;
+
+/// Is CBOR the client's preferred response format?
+///
+/// CBOR is a compact binary alternative to JSON, e.g. for receipts that must
+/// fit into a QR code. It is only used when asked for with
+/// `Accept: application/cbor`; JSON remains the default.
+pub fn prefers_cbor(req: &Request<'_>) -> bool {
+    req.accept().is_some_and(|accept| {
+        let preferred = accept.preferred().media_type();
+        preferred.top() == "application" && preferred.sub() == "cbor"
+    })
+}
+
+/// Serialise a value as CBOR.
+pub fn to_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).expect("Serialisation is infallible");
+    bytes
+}
+
+/// A response serialised as CBOR if the client prefers it, or JSON otherwise.
+pub struct Negotiated<T>(pub T);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = if prefers_cbor(req) {
+            (ContentType::new("application", "cbor"), to_cbor(&self.0)).respond_to(req)?
+        } else {
+            Json(self.0).respond_to(req)?
+        };
+        vary_on_accept(&mut response);
+        Ok(response)
+    }
+}
+
+/// Mark a response as depending on the `Accept` header, so that caches do
+/// not mix up the two formats.
+pub fn vary_on_accept(response: &mut Response<'_>) {
+    response.set_header(Header::new("Vary", "Accept"));
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A measurement of how far the database server's clock is ahead of this
+/// server's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSkew {
+    /// When the measurement was taken, by this server's clock.
+    pub measured_at: DateTime<Utc>,
+    /// How far the database server's clock is ahead, in milliseconds; negative
+    /// if it is behind.
+    pub skew_ms: i64,
+    /// How long the database took to reply, in milliseconds. The skew is only
+    /// accurate to within half of this.
+    pub round_trip_ms: u64,
+}
+
+/// The most recent clock skew measurement, and what is made of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSkewReport {
+    /// Skew beyond this many milliseconds, either way, is logged as a warning.
+    pub warn_threshold_ms: u64,
+    /// Skew beyond this many milliseconds, either way, refuses launch unless
+    /// accepted, and is logged as an error.
+    pub max_threshold_ms: u64,
+    /// How often the skew is measured again, in seconds.
+    pub check_interval_secs: u64,
+    /// The most recent measurement, if any has succeeded.
+    pub last: Option<ClockSkew>,
+}
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::election::ElectionId;
+
+/// How many of an election's voters have accepted each version of its consent text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentSummary {
+    pub election_id: ElectionId,
+    /// The current consent text, if voters must accept one to join.
+    pub consent_text: Option<String>,
+    /// The current version of the consent text.
+    pub consent_version: u32,
+    /// Number of joined voters who accepted each version, by version.
+    pub accepted: HashMap<u32, u64>,
+    /// Number of joined voters who accepted a version other than the current
+    /// one. They remain joined, but have not accepted the current text.
+    pub stale: u64,
+    /// Number of joined voters who accepted no version, having joined while
+    /// the election required no consent.
+    pub not_accepted: u64,
+}
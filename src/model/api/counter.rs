@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::{ballot::BallotId, election::QuestionId};
+
+/// A question's ballot counter, alongside the ballots actually stored for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterStatus {
+    pub question_id: QuestionId,
+    /// The next ballot ID the counter will hand out, or `None` if the counter is missing.
+    pub next: Option<BallotId>,
+    /// The highest ballot ID stored for the question, or `None` if it has no ballots.
+    pub max_ballot_id: Option<BallotId>,
+    /// Is the counter missing, or will it hand out a ballot ID that is already taken?
+    pub mismatch: bool,
+}
+
+impl CounterStatus {
+    pub fn new(
+        question_id: QuestionId,
+        next: Option<BallotId>,
+        max_ballot_id: Option<BallotId>,
+    ) -> Self {
+        let mismatch = match (next, max_ballot_id) {
+            (None, _) => true,
+            (Some(next), Some(max)) => next <= max,
+            (Some(_), None) => false,
+        };
+        Self {
+            question_id,
+            next,
+            max_ballot_id,
+            mismatch,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// Timings of the database commands issued since the server started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbStats {
+    /// Commands taking longer than this many milliseconds are logged as slow.
+    pub slow_threshold_ms: u64,
+    /// Statistics for each command on each collection, ordered by collection
+    /// then command.
+    pub commands: Vec<CommandStats>,
+    /// The most recent slow commands, most recent first.
+    pub recent_slow: Vec<SlowCommand>,
+}
+
+/// Timings of a single command on a single collection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandStats {
+    /// Collection the command ran against, or `-` for commands on the whole
+    /// database, such as committing a transaction.
+    pub collection: String,
+    /// Name of the command, e.g. `find` or `aggregate`.
+    pub command: String,
+    /// Number of times the command completed, successfully or not.
+    pub count: u64,
+    /// Number of times the command failed.
+    pub failures: u64,
+    /// Number of times the command was slower than the threshold.
+    pub slow: u64,
+    /// Total time spent in the command, in milliseconds.
+    pub total_ms: u64,
+    /// Longest time spent in a single command, in milliseconds.
+    pub max_ms: u64,
+    /// Cumulative histogram of durations, Prometheus-style: each bucket counts
+    /// the commands taking at most its `le_ms`.
+    pub buckets: Vec<DurationBucket>,
+}
+
+/// A bucket of a duration histogram.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DurationBucket {
+    /// Upper bound of the bucket in milliseconds, or `None` for the final
+    /// bucket, which counts every command.
+    pub le_ms: Option<u64>,
+    /// Number of commands taking at most `le_ms`.
+    pub count: u64,
+}
+
+/// A command that took longer than the slow threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlowCommand {
+    /// Collection the command ran against.
+    pub collection: String,
+    /// Name of the command.
+    pub command: String,
+    /// How long the command took, in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the command failed.
+    pub failed: bool,
+    /// The comment the command was tagged with, identifying the request that
+    /// issued it, if any.
+    pub comment: Option<String>,
+}
+
+/// The structured log line for a slow command.
+impl Display for SlowCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Slow database command: collection={} command={} duration_ms={} failed={} comment={}",
+            self.collection,
+            self.command,
+            self.duration_ms,
+            self.failed,
+            self.comment.as_deref().unwrap_or("-"),
+        )
+    }
+}
@@ -2,21 +2,27 @@ use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, Utc};
 use dre_ip::DreipGroup as DreipGroupTrait;
+#[cfg(feature = "server")]
 use mongodb::bson::{doc, Document};
+#[cfg(feature = "server")]
 use rocket::{FromFormField, UriDisplayQuery};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::model::{
-    common::election::{DreipGroup, ElectionState, Electorate},
-    db::election::{Election, ElectionMetadata, Question},
+use crate::model::common::election::{
+    CandidateId, DreipGroup, ElectionId, ElectionState, Electorate, QuestionId, QuorumReport,
+    QuorumSpec, ResultVisibility, Translations, DREIP_GROUP_NAME,
+};
+#[cfg(feature = "server")]
+use crate::model::db::election::{
+    ArchiveExport, Election, ElectionFinalization, ElectionMetadata, Question,
 };
 
 /// An API-friendly representation of the relationship between the current time
 /// and an election's start/end times.
 /// This obviously goes out of date if stored, so only use it transiently.
-#[derive(
-    Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, FromFormField, UriDisplayQuery,
-)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField, UriDisplayQuery))]
 pub enum ElectionTiming {
     /// The start time is in the future.
     Future,
@@ -26,6 +32,7 @@ pub enum ElectionTiming {
     Past,
 }
 
+#[cfg(feature = "server")]
 impl ElectionTiming {
     /// Get the status of the given election.
     pub fn for_metadata(election: &ElectionMetadata) -> Self {
@@ -58,14 +65,64 @@ impl ElectionTiming {
 pub struct ElectionDescription {
     /// Election unique ID.
     pub id: u32,
-    /// Election name.
+    /// Election name, translated if a language was requested.
     pub name: String,
+    /// The canonical name, if `name` has been translated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_name: Option<String>,
+    /// Translations of the name, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub name_translations: Translations,
     /// Election state.
     pub state: ElectionState,
     /// Election start time.
     pub start_time: DateTime<Utc>,
     /// Election end time.
     pub end_time: DateTime<Utc>,
+    /// IANA name of the timezone the election's times were given in, if any,
+    /// for showing them as local times.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Is voting temporarily suspended?
+    pub suspended: bool,
+    /// Must voters re-verify their SMS number before voting?
+    pub requires_step_up: bool,
+    /// Does each voter see each question's candidates in their own order?
+    #[serde(default)]
+    pub randomise_candidate_order: bool,
+    /// The most voters who may join the election, if it is capped.
+    #[serde(default)]
+    pub max_voters: Option<u32>,
+    /// Voters may join this many minutes before the election starts, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_join_minutes: Option<u32>,
+    /// Until when ballots and dumps are hidden from non-admins, if set;
+    /// only shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embargo_public_board_until: Option<DateTime<Utc>>,
+    /// Text voters must accept before joining the election, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_text: Option<String>,
+    /// Version of the consent text, which voters must give when joining.
+    #[serde(default)]
+    pub consent_version: u32,
+    /// Number of voters who have joined the election; only shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joined_count: Option<u32>,
+    /// Display metadata for frontends, once the election is published;
+    /// always shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branding: Option<Value>,
+    /// Which admins created and modified the election; only shown to admins.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub authorship: Option<ElectionAuthorship>,
+    /// What the finalizer has done, once it has run; only shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalization: Option<FinalizationSummary>,
+    /// The upload of the election's signed dumps to the archive bucket, once
+    /// it has been archived; only shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_export: Option<ArchiveExportSummary>,
     /// Election electorates by name.
     pub electorates: HashMap<String, Electorate>,
     /// Election questions.
@@ -76,6 +133,10 @@ pub struct ElectionDescription {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ElectionCrypto {
+    /// Name of the DRE-ip group, so that data for other groups is not misread.
+    /// Data from before this was recorded is always for [`DREIP_GROUP_NAME`].
+    #[serde(default = "default_group")]
+    pub group: String,
     /// First generator.
     #[serde(with = "dre_ip::group::serde_bytestring")]
     pub g1: <DreipGroup as DreipGroupTrait>::Point,
@@ -87,8 +148,71 @@ pub struct ElectionCrypto {
     pub public_key: <DreipGroup as DreipGroupTrait>::PublicKey,
 }
 
+/// Helper for serde defaults.
+fn default_group() -> String {
+    DREIP_GROUP_NAME.to_string()
+}
+
+#[cfg(feature = "server")]
+impl From<&Election> for ElectionCrypto {
+    fn from(election: &Election) -> Self {
+        Self {
+            group: DREIP_GROUP_NAME.to_string(),
+            g1: election.crypto.g1,
+            g2: election.crypto.g2,
+            public_key: election.crypto.public_key,
+        }
+    }
+}
+
+/// Everything needed to verify the receipts of a single question, for
+/// verifiers that have no use for the rest of the election description.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionCrypto {
+    /// ID of the election the question is in.
+    pub election_id: ElectionId,
+    /// Question ID.
+    pub question_id: QuestionId,
+    /// The question's candidates, each of which every ballot has a vote for.
+    pub candidates: Vec<CandidateId>,
+    /// The election's cryptographic configuration.
+    #[serde(flatten)]
+    pub crypto: ElectionCrypto,
+}
+
+impl QuestionCrypto {
+    /// Does a ballot with votes for the given candidates belong to this question?
+    pub fn has_candidates<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a CandidateId>,
+    ) -> bool {
+        let candidates = candidates.into_iter().collect::<HashSet<_>>();
+        candidates.len() == self.candidates.len()
+            && self
+                .candidates
+                .iter()
+                .all(|candidate| candidates.contains(candidate))
+    }
+}
+
+#[cfg(feature = "server")]
+impl QuestionCrypto {
+    /// Get the crypto of the given question of the election, if it has one by that ID.
+    pub fn for_question(election: &Election, question_id: QuestionId) -> Option<Self> {
+        let question = election.questions.get(&question_id)?;
+        Some(Self {
+            election_id: election.id,
+            question_id,
+            candidates: question.candidates.clone(),
+            crypto: ElectionCrypto::from(election),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
 impl From<Election> for ElectionDescription {
     fn from(election: Election) -> Self {
+        let branding = public_branding(&election.metadata);
         let questions = election
             .questions
             .into_iter()
@@ -98,16 +222,185 @@ impl From<Election> for ElectionDescription {
         Self {
             id: election.id,
             name: election.metadata.name,
+            canonical_name: None,
+            name_translations: election.metadata.name_translations,
             state: election.metadata.state,
             start_time: election.metadata.start_time,
             end_time: election.metadata.end_time,
+            timezone: election.metadata.timezone,
+            suspended: election.metadata.suspended,
+            requires_step_up: election.metadata.requires_step_up,
+            randomise_candidate_order: election.metadata.randomise_candidate_order,
+            max_voters: election.metadata.max_voters,
+            pre_join_minutes: election.metadata.pre_join_minutes,
+            embargo_public_board_until: None,
+            consent_text: election.metadata.consent_text,
+            consent_version: election.metadata.consent_version,
+            joined_count: None,
+            branding,
+            authorship: None,
+            finalization: None,
+            archive_export: None,
             electorates: election.electorates,
             questions,
-            crypto: ElectionCrypto {
-                g1: election.crypto.g1,
-                g2: election.crypto.g2,
-                public_key: election.crypto.public_key,
-            },
+            crypto: ElectionCrypto::from(&election),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl ElectionDescription {
+    /// Describe the election for an admin, including its authorship,
+    /// finalization, archive export, public board embargo, and how many voters
+    /// have joined.
+    pub fn for_admin(mut election: Election) -> Self {
+        let authorship = ElectionAuthorship::from(&election.metadata);
+        let finalization = election.finalization.take().map(FinalizationSummary::from);
+        let archive_export = election
+            .archive_export
+            .take()
+            .map(ArchiveExportSummary::from);
+        Self {
+            embargo_public_board_until: election
+                .metadata
+                .embargo_public_board_until
+                .map(|until| until.to_chrono()),
+            joined_count: Some(election.metadata.joined_count),
+            branding: election.metadata.branding.clone(),
+            authorship: Some(authorship),
+            finalization,
+            archive_export,
+            ..election.into()
+        }
+    }
+}
+
+impl ElectionDescription {
+    /// Swap the name and question texts for their translations into `lang`,
+    /// where there are any, keeping the canonical strings alongside.
+    pub fn localise(&mut self, lang: &str) {
+        localise(
+            &mut self.name,
+            &mut self.canonical_name,
+            &self.name_translations,
+            lang,
+        );
+        for question in self.questions.values_mut() {
+            question.localise(lang);
+        }
+    }
+}
+
+/// Swap `display` for its translation into `lang`, if there is one, moving the
+/// canonical string into `canonical`.
+fn localise(
+    display: &mut String,
+    canonical: &mut Option<String>,
+    translations: &Translations,
+    lang: &str,
+) {
+    if let Some(translation) = translations.get(lang) {
+        let original = std::mem::replace(display, translation.clone());
+        canonical.get_or_insert(original);
+    }
+}
+
+/// An election's branding, if it may be shown to anyone: drafts' is only shown to admins.
+#[cfg(feature = "server")]
+fn public_branding(metadata: &ElectionMetadata) -> Option<Value> {
+    match metadata.state {
+        ElectionState::Draft => None,
+        ElectionState::Published | ElectionState::Archived => metadata.branding.clone(),
+    }
+}
+
+/// Which admins created and last modified an election.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectionAuthorship {
+    /// Username of the admin who created the election.
+    pub created_by: String,
+    /// Username of the admin who last modified the election, if any.
+    pub last_modified_by: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl From<&ElectionMetadata> for ElectionAuthorship {
+    fn from(metadata: &ElectionMetadata) -> Self {
+        Self {
+            created_by: metadata.created_by.clone(),
+            last_modified_by: metadata.last_modified_by.clone(),
+        }
+    }
+}
+
+/// What an election's finalizer has done.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalizationSummary {
+    /// When the finalizer first succeeded, if it has.
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Total number of unconfirmed ballots audited.
+    pub ballots_audited: u32,
+    /// Number of times the finalizer has run.
+    pub attempts: u32,
+    /// The error from the most recent run, if it failed.
+    pub last_error: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl From<ElectionFinalization> for FinalizationSummary {
+    fn from(finalization: ElectionFinalization) -> Self {
+        Self {
+            completed_at: finalization.completed_at.map(|time| time.to_chrono()),
+            ballots_audited: finalization.ballots_audited,
+            attempts: finalization.attempts,
+            last_error: finalization.last_error,
+        }
+    }
+}
+
+/// Whether an archived election's signed dumps have been uploaded to the
+/// archive bucket.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveExportStatus {
+    /// Waiting to be uploaded, once the election has been finalized.
+    Pending,
+    /// Every object has been uploaded.
+    Uploaded,
+    /// The most recent attempt failed, and will be retried.
+    Failed,
+}
+
+/// The upload of an archived election's signed dumps to the archive bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveExportSummary {
+    pub status: ArchiveExportStatus,
+    /// When every object was last uploaded, if they have been.
+    pub uploaded_at: Option<DateTime<Utc>>,
+    /// Keys of the objects uploaded: the election's description, then a dump
+    /// of each question.
+    pub object_keys: Vec<String>,
+    /// Number of times uploading has been attempted.
+    pub attempts: u32,
+    /// The error from the most recent attempt, if it failed.
+    pub last_error: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl From<ArchiveExport> for ArchiveExportSummary {
+    fn from(export: ArchiveExport) -> Self {
+        let status = if export.last_error.is_some() {
+            ArchiveExportStatus::Failed
+        } else if export.uploaded_at.is_some() {
+            ArchiveExportStatus::Uploaded
+        } else {
+            ArchiveExportStatus::Pending
+        };
+        Self {
+            status,
+            uploaded_at: export.uploaded_at.map(|time| time.to_chrono()),
+            object_keys: export.object_keys,
+            attempts: export.attempts,
+            last_error: export.last_error,
         }
     }
 }
@@ -117,48 +410,231 @@ impl From<Election> for ElectionDescription {
 pub struct ElectionSummary {
     /// Election unique ID.
     pub id: u32,
-    /// Election name.
+    /// Election name, translated if a language was requested.
     pub name: String,
+    /// The canonical name, if `name` has been translated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_name: Option<String>,
+    /// Translations of the name, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub name_translations: Translations,
     /// Election state.
     pub state: ElectionState,
     /// Election start time.
     pub start_time: DateTime<Utc>,
     /// Election end time.
     pub end_time: DateTime<Utc>,
+    /// Is voting temporarily suspended?
+    pub suspended: bool,
+    /// Must voters re-verify their SMS number before voting?
+    pub requires_step_up: bool,
+    /// The most voters who may join the election, if it is capped.
+    #[serde(default)]
+    pub max_voters: Option<u32>,
+    /// Voters may join this many minutes before the election starts, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_join_minutes: Option<u32>,
+    /// Number of voters who have joined the election; only shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joined_count: Option<u32>,
+    /// Display metadata for frontends, once the election is published;
+    /// always shown to admins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branding: Option<Value>,
+    /// Which admins created and modified the election; only shown to admins.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub authorship: Option<ElectionAuthorship>,
 }
 
+#[cfg(feature = "server")]
 impl From<Election> for ElectionSummary {
     fn from(election: Election) -> Self {
+        let branding = public_branding(&election.metadata);
         Self {
             id: election.id,
             name: election.metadata.name,
+            canonical_name: None,
+            name_translations: election.metadata.name_translations,
             state: election.metadata.state,
             start_time: election.metadata.start_time,
             end_time: election.metadata.end_time,
+            suspended: election.metadata.suspended,
+            requires_step_up: election.metadata.requires_step_up,
+            max_voters: election.metadata.max_voters,
+            pre_join_minutes: election.metadata.pre_join_minutes,
+            joined_count: None,
+            branding,
+            authorship: None,
         }
     }
 }
 
+#[cfg(feature = "server")]
+impl ElectionSummary {
+    /// Summarise the election for an admin, including its authorship and
+    /// how many voters have joined.
+    pub fn for_admin(election: Election) -> Self {
+        let authorship = ElectionAuthorship::from(&election.metadata);
+        Self {
+            joined_count: Some(election.metadata.joined_count),
+            branding: election.metadata.branding.clone(),
+            authorship: Some(authorship),
+            ..election.into()
+        }
+    }
+}
+
+impl ElectionSummary {
+    /// Swap the name for its translation into `lang`, if there is one,
+    /// keeping the canonical name alongside.
+    pub fn localise(&mut self, lang: &str) {
+        localise(
+            &mut self.name,
+            &mut self.canonical_name,
+            &self.name_translations,
+            lang,
+        );
+    }
+}
+
 /// An API-friendly description of a question.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct QuestionDescription {
     /// Question unique ID, in API-friendly form.
     pub id: u32,
-    /// Question text.
+    /// A stable handle for the question, unique within the election, which
+    /// survives modifications that keep the description.
+    #[serde(default)]
+    pub slug: String,
+    /// Question text, translated if a language was requested.
     pub description: String,
+    /// The canonical question text, if `description` has been translated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_description: Option<String>,
+    /// Translations of the question text, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub description_translations: Translations,
     /// A voter must be in at least one of these electorate groups to vote on this question.
     pub constraints: HashMap<String, HashSet<String>>,
-    /// Candidates / possible answers for this question.
+    /// Candidates / possible answers for this question. These are always
+    /// canonical, since they are what ballots are cast for.
     pub candidates: Vec<String>,
+    /// Translations of candidates' display names, by candidate then language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub candidate_translations: HashMap<CandidateId, Translations>,
+    /// The name to show for each candidate, if a language was requested:
+    /// its translation, or the candidate itself if it has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidate_display_names: Option<HashMap<CandidateId, String>>,
+    /// When voting on this question closes, if before the end of the election.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+    /// May voters write in a candidate? Once published, write-ins are votes
+    /// for the reserved write-in candidate.
+    #[serde(default)]
+    pub allow_write_in: bool,
+    /// How much of the results the public may see once the election has ended.
+    #[serde(default)]
+    pub result_visibility: ResultVisibility,
+    /// How many voters must answer the question for its result to be binding,
+    /// if there is a quorum. This is only reported on once the election ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumSpec>,
+    /// Whether the question met its quorum, once the election has ended.
+    /// Only included in descriptions of single elections in which some
+    /// question has a quorum.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum_report: Option<QuorumReport>,
 }
 
+#[cfg(feature = "server")]
 impl From<Question> for QuestionDescription {
     fn from(question: Question) -> Self {
         Self {
             id: question.id,
+            slug: question.slug,
             description: question.description,
+            canonical_description: None,
+            description_translations: question.description_translations,
             constraints: question.constraints,
             candidates: question.candidates,
+            candidate_translations: question.candidate_translations,
+            candidate_display_names: None,
+            end_time: question.end_time.map(|end_time| end_time.to_chrono()),
+            allow_write_in: question.allow_write_in,
+            result_visibility: question.result_visibility,
+            quorum: question.quorum,
+            quorum_report: None,
+        }
+    }
+}
+
+impl QuestionDescription {
+    /// Swap the question text for its translation into `lang`, if there is
+    /// one, keeping the canonical text alongside, and give each candidate's
+    /// display name in `lang`.
+    pub fn localise(&mut self, lang: &str) {
+        localise(
+            &mut self.description,
+            &mut self.canonical_description,
+            &self.description_translations,
+            lang,
+        );
+        let display_names = self
+            .candidates
+            .iter()
+            .map(|candidate| {
+                let display_name = self
+                    .candidate_translations
+                    .get(candidate)
+                    .and_then(|translations| translations.get(lang))
+                    .unwrap_or(candidate);
+                (candidate.clone(), display_name.clone())
+            })
+            .collect();
+        self.candidate_display_names = Some(display_names);
+    }
+}
+
+/// The outcome of modifying an election.
+#[derive(Debug, Clone, Serialize)]
+pub struct ElectionModification {
+    /// The modified election.
+    #[serde(flatten)]
+    pub election: ElectionDescription,
+    /// How many voters who had already joined had their allowed questions
+    /// remapped to the new question IDs and worked out again for their groups,
+    /// or cleared so that they must re-join.
+    pub voters_affected: u64,
+    /// Warning: questions, by their old IDs, whose description no longer
+    /// appears in the election. Voters who had joined can no longer vote on them.
+    pub dropped_questions: Vec<DroppedQuestion>,
+}
+
+/// A question removed or reworded by modifying an election.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedQuestion {
+    /// The question's ID before the modification.
+    pub id: u32,
+    /// Question text.
+    pub description: String,
+}
+
+#[cfg(feature = "server")]
+impl From<&Question> for DroppedQuestion {
+    fn from(question: &Question) -> Self {
+        Self {
+            id: question.id,
+            description: question.description.clone(),
         }
     }
 }
+
+/// The outcome of publishing an election.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ElectionPublication {
+    /// Something about the election the admin probably did not intend, such
+    /// as voting having already started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
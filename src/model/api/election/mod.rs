@@ -1,10 +1,21 @@
 mod desc;
 mod results;
+#[cfg(feature = "client-types")]
 mod spec;
 
-pub use desc::{ElectionCrypto, ElectionDescription, ElectionSummary, ElectionTiming};
+pub use desc::{
+    ArchiveExportStatus, ArchiveExportSummary, DroppedQuestion, ElectionAuthorship, ElectionCrypto,
+    ElectionDescription, ElectionModification, ElectionPublication, ElectionSummary,
+    ElectionTiming, FinalizationSummary, QuestionCrypto, QuestionDescription,
+};
 pub use results::{
-    verify_receipt_extras, verify_receipt_full, BallotError, EffectiveBallotId, ElectionResults,
-    ReceiptError, VerificationError, VoteError,
+    check_group, check_schema_version, verify_public_receipt_for_question, verify_receipt_extras,
+    verify_receipt_for_question, verify_receipt_full, verify_unconfirmed_stub, BallotError,
+    BoardDiffError, DumpSignature, EffectiveBallotId, ElectionResults, ElectionResultsChanges,
+    ReceiptError, VerificationError, VerificationPhase, VerificationProgress, VerificationWarning,
+    VoteError,
 };
-pub use spec::{ElectionSpec, QuestionSpec};
+#[cfg(feature = "server")]
+pub use spec::{branding_to_bson, check_branding};
+#[cfg(feature = "client-types")]
+pub use spec::{ElectionSpec, ElectionSpecInput, ElectorateSpec, QuestionSpec, MAX_BRANDING_SIZE};
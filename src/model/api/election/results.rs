@@ -1,19 +1,35 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use data_encoding::BASE32;
+#[cfg(feature = "server")]
+use dre_ip::DreipPrivateKey;
 use dre_ip::{CandidateTotals, DreipPublicKey, VerificationError as InternalError};
+#[cfg(feature = "verification")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sha2::{Digest, Sha256};
 
+#[cfg(feature = "server")]
+use crate::model::db::election::Election;
 use crate::model::{
     api::{
-        candidate_totals::CandidateTotalsDesc,
-        election::ElectionCrypto,
-        receipt::{Receipt, CONFIRMATION_CODE_LENGTH},
+        candidate_totals::{tally_to_u64, CandidateTotalsDesc},
+        election::{ElectionCrypto, QuestionCrypto},
+        receipt::{
+            confirmation_phrase, default_schema_version, PublicReceipt, Receipt, Signature,
+            UnconfirmedStub, CONFIRMATION_CODE_LENGTH, RECEIPT_SCHEMA_VERSION,
+        },
     },
     common::{
-        ballot::{Audited, BallotId, BallotState, Confirmed},
-        election::CandidateId,
+        ballot::{
+            Audited, AuditedRedacted, BallotId, BallotState, Confirmed, Unconfirmed, AUDITED,
+            AUDITED_REDACTED, CONFIRMED,
+        },
+        board::{BoardChain, BoardError},
+        canonical_json,
+        election::{CandidateId, DREIP_GROUP_NAME},
     },
 };
 
@@ -28,12 +44,19 @@ pub enum ReceiptError {
     Signature { ballot_id: BallotId },
     /// The confirmation code was wrong.
     ConfirmationCode { ballot_id: BallotId },
+    /// The confirmation phrase did not match the confirmation code.
+    ConfirmationPhrase { ballot_id: BallotId },
     /// The revealed candidate was wrong.
     RevealedCandidate {
         ballot_id: BallotId,
         claimed_candidate: CandidateId,
         true_candidate: CandidateId,
     },
+    /// The receipt schema version is not one this verifier understands.
+    UnsupportedVersion { version: u8 },
+    /// The receipt is for a different election or question than the one it
+    /// was verified against.
+    WrongQuestion { ballot_id: BallotId },
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -47,6 +70,72 @@ pub enum VerificationError {
     /// The set of candidates does not match between the ballots
     /// and the proposed tallies.
     WrongCandidates,
+    /// The signature over a receipt bundle was wrong.
+    BundleSignature,
+    /// The signature over a dump was wrong.
+    DumpSignature,
+    /// A dump was required to be signed, but was not.
+    MissingDumpSignature,
+    /// The bulletin board hash chain was inconsistent.
+    Board(BoardError),
+    /// The data is for a DRE-ip group this verifier does not understand.
+    UnsupportedGroup { group: String },
+}
+
+/// Something amiss with election results that does not make them invalid.
+/// Warnings are listed by [`ElectionResults::warnings`] for verifiers to show,
+/// but do not fail verification, since dumps taken before confirmed ballots
+/// were counted do not have the counts checked.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum VerificationWarning {
+    /// A candidate's totals count a different number of confirmed ballots than
+    /// the dump contains.
+    ConfirmedBallotCount {
+        candidate_id: CandidateId,
+        claimed: u64,
+        actual: u64,
+    },
+    /// The candidates' tallies do not add up to the number of confirmed
+    /// ballots, or are too big to add up at all.
+    TallySum {
+        tally_sum: Option<u64>,
+        confirmed: u64,
+    },
+}
+
+/// A way in which a later dump of a question is inconsistent with an earlier
+/// one. Ballots may only be added, move from unconfirmed to audited or
+/// confirmed, or have their audited secrets redacted, so anything else means
+/// the board has been tampered with.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum BoardDiffError {
+    /// The dumps are for different elections, or the election crypto changed.
+    DifferentElection,
+    /// A ballot in the earlier dump is missing from the later one.
+    RemovedBallot { ballot_id: BallotId },
+    /// A ballot's crypto or confirmation code changed between the dumps.
+    MutatedCrypto { ballot_id: BallotId },
+    /// A ballot moved between audited and confirmed, or its secrets reappeared
+    /// after being redacted.
+    IllegalTransition {
+        ballot_id: BallotId,
+        from: &'static str,
+        to: &'static str,
+    },
+    /// The change in the tallies does not match the number of confirmed
+    /// ballots added, or the totals were removed.
+    TotalsInconsistent {
+        added_confirmed: u64,
+        tally_increase: Option<u64>,
+    },
+    /// The later bulletin board does not extend the earlier one.
+    Board(BoardError),
+}
+
+impl From<BoardError> for VerificationError {
+    fn from(err: BoardError) -> Self {
+        VerificationError::Board(err)
+    }
 }
 
 impl From<InternalError<EffectiveBallotId, CandidateId>> for VerificationError {
@@ -78,24 +167,219 @@ impl From<InternalError<EffectiveBallotId, CandidateId>> for VerificationError {
 /// All election results needed for verification.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ElectionResults {
+    /// The receipt schema version of the dump.
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u8,
     /// Election cryptographic data needed for verification.
     pub election: ElectionCrypto,
     /// All audited receipts.
     pub audited: HashMap<BallotId, Receipt<Audited>>,
+    /// All audited receipts whose secrets have been redacted.
+    /// Not present in dumps with none.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default)]
+    pub redacted: HashMap<BallotId, Receipt<AuditedRedacted>>,
     /// All confirmed receipts.
     pub confirmed: HashMap<BallotId, Receipt<Confirmed>>,
     /// Claimed candidate totals.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub totals: Option<HashMap<CandidateId, CandidateTotalsDesc>>,
+    /// Number of ballots left out of the dump because they were in an
+    /// unrecognised state.
+    #[serde(skip_serializing_if = "is_zero")]
+    #[serde(default)]
+    pub skipped: u64,
+    /// The question's bulletin board hash chain.
+    /// Not present in dumps taken before the chain was introduced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub board: Option<BoardChain>,
+    /// Sequence number of the latest ballot change included in the dump,
+    /// from which a mirror can request subsequent changes.
+    #[serde(default)]
+    pub last_event_seq: u64,
+    /// Signature by the election key over the canonical form of the rest of
+    /// the dump. Only present if a signed dump was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub dump_signature: Option<DumpSignature>,
+}
+
+/// A signature over an [`ElectionResults`] dump.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpSignature(#[serde(with = "dre_ip::group::serde_bytestring")] pub Signature);
+
+/// The receipts of a question's ballots that changed after a given sequence
+/// number, so that mirrors can update a dump incrementally.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectionResultsChanges {
+    /// The receipt schema version of the changes.
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u8,
+    /// Receipts of ballots that have changed to audited.
+    pub audited: HashMap<BallotId, Receipt<Audited>>,
+    /// Receipts of ballots that have changed to audited, and since been redacted.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default)]
+    pub redacted: HashMap<BallotId, Receipt<AuditedRedacted>>,
+    /// Receipts of ballots that have changed to confirmed.
+    pub confirmed: HashMap<BallotId, Receipt<Confirmed>>,
+    /// Number of changed ballots left out because they were in an
+    /// unrecognised state.
+    #[serde(skip_serializing_if = "is_zero")]
+    #[serde(default)]
+    pub skipped: u64,
+    /// Sequence number of the latest ballot change, from which to request
+    /// the next set of changes.
+    pub last_event_seq: u64,
+}
+
+/// Helper for `skip_serializing_if`.
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// A phase of [`ElectionResults`] verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationPhase {
+    /// Verifying the confirmed ballots against the candidate totals.
+    Totals,
+    /// Verifying each receipt's proofs, confirmation code, and signature.
+    Receipts,
+    /// Verifying the bulletin board hash chain against the receipts.
+    Board,
+}
+
+/// Progress through [`ElectionResults`] verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationProgress {
+    /// A phase of verification has started.
+    Started(VerificationPhase),
+    /// Another receipt has been verified. Receipts may be verified in parallel,
+    /// so this may be reported from any thread.
+    Receipt { verified: usize, total: usize },
+}
+
+/// A receipt awaiting verification.
+enum ReceiptCheck<'a> {
+    /// A confirmed receipt whose ballot has already been verified against the totals.
+    ConfirmedExtras(&'a Receipt<Confirmed>),
+    ConfirmedFull(&'a Receipt<Confirmed>),
+    AuditedFull(&'a Receipt<Audited>),
+    RedactedFull(&'a Receipt<AuditedRedacted>),
+}
+
+impl ReceiptCheck<'_> {
+    fn ballot_id(&self) -> BallotId {
+        match self {
+            ReceiptCheck::ConfirmedExtras(receipt) | ReceiptCheck::ConfirmedFull(receipt) => {
+                receipt.ballot_id
+            }
+            ReceiptCheck::AuditedFull(receipt) => receipt.ballot_id,
+            ReceiptCheck::RedactedFull(receipt) => receipt.ballot_id,
+        }
+    }
+
+    fn verify(&self, crypto: &ElectionCrypto) -> Result<(), VerificationError> {
+        match self {
+            ReceiptCheck::ConfirmedExtras(receipt) => verify_receipt_extras(*receipt, crypto),
+            ReceiptCheck::ConfirmedFull(receipt) => verify_receipt_full(*receipt, crypto),
+            ReceiptCheck::AuditedFull(receipt) => verify_receipt_full(*receipt, crypto),
+            ReceiptCheck::RedactedFull(receipt) => verify_receipt_full(*receipt, crypto),
+        }
+    }
 }
 
 impl ElectionResults {
-    /// Verify the election results.
+    /// Sign the dump with the election's private key.
+    #[cfg(feature = "server")]
+    pub fn sign(&mut self, election: &Election) {
+        let signature = election
+            .crypto
+            .private_key
+            .expose()
+            .sign(&self.signed_message());
+        self.dump_signature = Some(DumpSignature(signature));
+    }
+
+    /// Verify the dump's signature against the election public key it contains.
+    /// This is separate from [`Self::verify`], since dumps need not be signed.
+    pub fn verify_signature(&self) -> Result<(), VerificationError> {
+        check_group(&self.election.group)?;
+        let DumpSignature(signature) = self
+            .dump_signature
+            .as_ref()
+            .ok_or(VerificationError::MissingDumpSignature)?;
+        if !self
+            .election
+            .public_key
+            .verify(&self.signed_message(), signature)
+        {
+            return Err(VerificationError::DumpSignature);
+        }
+        debug!("Verified dump signature");
+        Ok(())
+    }
+
+    /// The canonical form of everything in the dump except its signature.
+    fn signed_message(&self) -> Vec<u8> {
+        let mut value = serde_json::to_value(self).expect("Serialisation is infallible");
+        if let Value::Object(fields) = &mut value {
+            fields.remove("dump_signature");
+        }
+        canonical_json::to_vec(&value).expect("Serialisation is infallible")
+    }
+
+    /// Verify the election results, failing with the first problem found.
     pub fn verify(&self) -> Result<(), VerificationError> {
+        self.verify_with_progress(|_| {})
+    }
+
+    /// Verify the election results, reporting progress to the given callback,
+    /// and failing with the first problem found.
+    pub fn verify_with_progress<F>(&self, progress: F) -> Result<(), VerificationError>
+    where
+        F: Fn(VerificationProgress) + Sync,
+    {
+        match self.verify_all_with_progress(progress).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Verify the election results, returning every problem found rather than
+    /// stopping at the first. The results are valid iff this is empty.
+    pub fn verify_all(&self) -> Vec<VerificationError> {
+        self.verify_all_with_progress(|_| {})
+    }
+
+    /// Verify the election results, reporting progress to the given callback,
+    /// and returning every problem found.
+    ///
+    /// Problems are listed in the order they are checked: the totals, then the
+    /// receipts by ascending ballot ID, then the bulletin board. With the
+    /// `verification` feature, receipts are verified in parallel.
+    /// An unsupported schema version or group is reported alone, since nothing
+    /// else in the dump can be understood.
+    pub fn verify_all_with_progress<F>(&self, progress: F) -> Vec<VerificationError>
+    where
+        F: Fn(VerificationProgress) + Sync,
+    {
+        // Refuse versions we do not understand, rather than failing confusingly later.
+        if let Err(err) = check_schema_version(self.schema_version) {
+            return vec![err];
+        }
+        if let Err(err) = check_group(&self.election.group) {
+            return vec![err];
+        }
+        let mut errors = Vec::new();
+
         // See if we have the totals or not.
+        let mut totals_verified = false;
         if let Some(totals) = &self.totals {
             debug!("Candidate totals are present");
+            progress(VerificationProgress::Started(VerificationPhase::Totals));
             // Verify the confirmed ballots and candidate totals.
             let confirmed = self
                 .confirmed
@@ -117,30 +401,372 @@ impl ElectionResults {
                 .collect::<HashMap<_, _>>();
 
             // Verify the ballot-specific data and the totals.
-            dre_ip::verify_election(self.election.g1, self.election.g2, &confirmed, &totals)?;
-            debug!("Verified confirmed ballots and candidate totals");
-
-            // Verify the receipt-specific data.
-            for receipt in self.confirmed.values() {
-                verify_receipt_extras(receipt, &self.election)?;
+            match dre_ip::verify_election(self.election.g1, self.election.g2, &confirmed, &totals) {
+                Ok(()) => {
+                    totals_verified = true;
+                    debug!("Verified confirmed ballots and candidate totals");
+                }
+                Err(err) => errors.push(err.into()),
+            }
+            for warning in self.warnings() {
+                warn!("Verification warning: {:?}", warning);
             }
-            debug!("Verified confirmed receipts");
         } else {
             debug!("Candidate totals are not present");
-            // Verify all the confirmed receipts.
-            for receipt in self.confirmed.values() {
-                verify_receipt_full(receipt, &self.election)?;
+        }
+
+        // Verify all the receipts. If the totals failed, the confirmed ballots
+        // are checked in full, so that every bad ballot is found; the one
+        // already reported is not repeated.
+        progress(VerificationProgress::Started(VerificationPhase::Receipts));
+        for err in self.verify_receipts(totals_verified, cfg!(feature = "verification"), &progress)
+        {
+            if !errors.contains(&err) {
+                errors.push(err);
+            }
+        }
+        debug!("Verified audited and confirmed receipts");
+
+        // Verify the bulletin board if present.
+        if let Some(board) = &self.board {
+            progress(VerificationProgress::Started(VerificationPhase::Board));
+            let board_errors = board
+                .verify()
+                .err()
+                .into_iter()
+                .chain(self.verify_board_contents(board));
+            errors.extend(board_errors.map(VerificationError::from));
+            debug!("Verified bulletin board hash chain");
+        } else {
+            debug!("Bulletin board hash chain is not present");
+        }
+
+        errors
+    }
+
+    /// Cross-check the confirmed ballot counts in the totals against the
+    /// confirmed receipts and the tallies, returning anything amiss.
+    /// Totals are only present once the election has ended, so there is
+    /// nothing to check before then.
+    pub fn warnings(&self) -> Vec<VerificationWarning> {
+        let Some(totals) = &self.totals else {
+            return Vec::new();
+        };
+        let confirmed = self.confirmed.len() as u64;
+        let mut candidates = totals.iter().collect::<Vec<_>>();
+        candidates.sort_by_key(|(candidate_id, _)| *candidate_id);
+        let mut warnings = candidates
+            .into_iter()
+            .filter(|(_, totals)| totals.confirmed_ballot_count != confirmed)
+            .map(
+                |(candidate_id, totals)| VerificationWarning::ConfirmedBallotCount {
+                    candidate_id: candidate_id.clone(),
+                    claimed: totals.confirmed_ballot_count,
+                    actual: confirmed,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        // Each confirmed ballot is a vote for exactly one candidate.
+        let tally_sum = totals.values().try_fold(0u64, |sum, totals| {
+            sum.checked_add(tally_to_u64(totals.tally)?)
+        });
+        if tally_sum != Some(confirmed) {
+            warnings.push(VerificationWarning::TallySum {
+                tally_sum,
+                confirmed,
+            });
+        }
+        warnings
+    }
+
+    /// Compare an earlier dump of a question against a later one, returning
+    /// every way in which the later dump is not simply the earlier one with
+    /// ballots added or moved out of the unconfirmed state.
+    ///
+    /// Neither dump is verified here; callers should verify both first.
+    /// Problems are listed by ascending ballot ID, followed by the totals and
+    /// then the bulletin board.
+    pub fn diff(old: &Self, new: &Self) -> Vec<BoardDiffError> {
+        if old.election != new.election {
+            return vec![BoardDiffError::DifferentElection];
+        }
+
+        let mut ballot_errors = Vec::new();
+        for (&ballot_id, receipt) in &old.audited {
+            let err = match (new.audited.get(&ballot_id), new.redacted.get(&ballot_id)) {
+                (Some(later), _)
+                    if later.crypto == receipt.crypto
+                        && later.confirmation_code == receipt.confirmation_code =>
+                {
+                    continue
+                }
+                // Redaction may only remove the secrets.
+                (None, Some(later))
+                    if later.crypto == Audited::remove_external_secrets(&receipt.crypto)
+                        && later.confirmation_code == receipt.confirmation_code
+                        && later.state_data.candidate == receipt.state_data.candidate =>
+                {
+                    continue
+                }
+                (Some(_), _) | (None, Some(_)) => BoardDiffError::MutatedCrypto { ballot_id },
+                (None, None) if new.confirmed.contains_key(&ballot_id) => {
+                    BoardDiffError::IllegalTransition {
+                        ballot_id,
+                        from: AUDITED,
+                        to: CONFIRMED,
+                    }
+                }
+                (None, None) => BoardDiffError::RemovedBallot { ballot_id },
+            };
+            ballot_errors.push((ballot_id, err));
+        }
+        for (&ballot_id, receipt) in &old.redacted {
+            let err = match new.redacted.get(&ballot_id) {
+                Some(later)
+                    if later.crypto == receipt.crypto
+                        && later.confirmation_code == receipt.confirmation_code
+                        && later.state_data.candidate == receipt.state_data.candidate =>
+                {
+                    continue
+                }
+                Some(_) => BoardDiffError::MutatedCrypto { ballot_id },
+                None if new.audited.contains_key(&ballot_id) => BoardDiffError::IllegalTransition {
+                    ballot_id,
+                    from: AUDITED_REDACTED,
+                    to: AUDITED,
+                },
+                None if new.confirmed.contains_key(&ballot_id) => {
+                    BoardDiffError::IllegalTransition {
+                        ballot_id,
+                        from: AUDITED_REDACTED,
+                        to: CONFIRMED,
+                    }
+                }
+                None => BoardDiffError::RemovedBallot { ballot_id },
+            };
+            ballot_errors.push((ballot_id, err));
+        }
+        for (&ballot_id, receipt) in &old.confirmed {
+            let err = match new.confirmed.get(&ballot_id) {
+                Some(later)
+                    if later.crypto == receipt.crypto
+                        && later.confirmation_code == receipt.confirmation_code =>
+                {
+                    continue
+                }
+                Some(_) => BoardDiffError::MutatedCrypto { ballot_id },
+                None if new.audited.contains_key(&ballot_id)
+                    || new.redacted.contains_key(&ballot_id) =>
+                {
+                    BoardDiffError::IllegalTransition {
+                        ballot_id,
+                        from: CONFIRMED,
+                        to: AUDITED,
+                    }
+                }
+                None => BoardDiffError::RemovedBallot { ballot_id },
+            };
+            ballot_errors.push((ballot_id, err));
+        }
+        // The receipts are unordered, and each ballot has at most one problem.
+        ballot_errors.sort_unstable_by_key(|(ballot_id, _)| *ballot_id);
+        let mut errors = ballot_errors
+            .into_iter()
+            .map(|(_, err)| err)
+            .collect::<Vec<_>>();
+
+        // Each added confirmed ballot is a vote for exactly one candidate.
+        // Before the totals are published, the old ballots are all there is.
+        fn tally_sum(totals: &HashMap<CandidateId, CandidateTotalsDesc>) -> Option<u64> {
+            totals.values().try_fold(0u64, |sum, totals| {
+                sum.checked_add(tally_to_u64(totals.tally)?)
+            })
+        }
+        let added_confirmed = new
+            .confirmed
+            .keys()
+            .filter(|ballot_id| !old.confirmed.contains_key(ballot_id))
+            .count() as u64;
+        let old_sum = match &old.totals {
+            Some(totals) => tally_sum(totals),
+            None => Some(old.confirmed.len() as u64),
+        };
+        let new_sum = match &new.totals {
+            Some(totals) => tally_sum(totals),
+            None if old.totals.is_none() => old_sum.map(|sum| sum + added_confirmed),
+            None => None,
+        };
+        let tally_increase = old_sum
+            .zip(new_sum)
+            .and_then(|(old_sum, new_sum)| new_sum.checked_sub(old_sum));
+        if tally_increase != Some(added_confirmed) {
+            errors.push(BoardDiffError::TotalsInconsistent {
+                added_confirmed,
+                tally_increase,
+            });
+        }
+
+        // The later chain must be the earlier one with events appended.
+        if let Some(earlier) = &old.board {
+            match &new.board {
+                Some(board) => {
+                    if let Err(err) = board.extends(earlier) {
+                        errors.push(BoardDiffError::Board(err));
+                    }
+                }
+                None => errors.push(BoardDiffError::Board(BoardError::Diverged { position: 0 })),
             }
-            debug!("Verified confirmed ballots and receipts");
         }
 
-        // Verify all the audited receipts.
-        for receipt in self.audited.values() {
-            verify_receipt_full(receipt, &self.election)?;
+        errors
+    }
+
+    /// Verify every receipt, in parallel if requested and supported, returning
+    /// the errors in order of ballot ID.
+    /// Confirmed ballots are only checked in full if they have not already
+    /// been verified against the totals.
+    fn verify_receipts<F>(
+        &self,
+        totals_verified: bool,
+        parallel: bool,
+        progress: &F,
+    ) -> Vec<VerificationError>
+    where
+        F: Fn(VerificationProgress) + Sync,
+    {
+        let mut checks = self
+            .confirmed
+            .values()
+            .map(|receipt| {
+                if totals_verified {
+                    ReceiptCheck::ConfirmedExtras(receipt)
+                } else {
+                    ReceiptCheck::ConfirmedFull(receipt)
+                }
+            })
+            .chain(self.audited.values().map(ReceiptCheck::AuditedFull))
+            .chain(self.redacted.values().map(ReceiptCheck::RedactedFull))
+            .collect::<Vec<_>>();
+        // Sort so that the errors are in order of ballot ID.
+        checks.sort_unstable_by_key(ReceiptCheck::ballot_id);
+
+        let total = checks.len();
+        let verified = AtomicUsize::new(0);
+        let check = |receipt: &ReceiptCheck| {
+            let result = receipt.verify(&self.election);
+            let verified = verified.fetch_add(1, Ordering::Relaxed) + 1;
+            progress(VerificationProgress::Receipt { verified, total });
+            result.err()
+        };
+
+        #[cfg(feature = "verification")]
+        if parallel {
+            // Collecting an indexed parallel iterator preserves the order.
+            return checks.par_iter().filter_map(check).collect();
         }
-        debug!("Verified audited ballots and receipts");
+        #[cfg(not(feature = "verification"))]
+        let _ = parallel; // Parallel verification is not available.
 
+        checks.iter().filter_map(check).collect()
+    }
+
+    /// Check that the chain and the receipts agree: every receipt must have been
+    /// cast and then audited or confirmed on the chain with the same confirmation
+    /// code, and every audit or confirmation on the chain must have a receipt.
+    /// Returns every disagreement found.
+    fn verify_board_contents(&self, board: &BoardChain) -> Vec<BoardError> {
+        fn has_events<S: BallotState>(
+            board: &BoardChain,
+            ballot_id: BallotId,
+            confirmation_code: &str,
+            state: S,
+        ) -> bool {
+            let cast = board.find_event(ballot_id, Unconfirmed);
+            let last = board.find_event(ballot_id, state);
+            matches!(
+                (cast, last),
+                (Some(cast), Some(last))
+                    if cast.position < last.position
+                        && cast.confirmation_code == confirmation_code
+                        && last.confirmation_code == confirmation_code
+            )
+        }
+
+        // The receipts are unordered, so sort the missing ballots by ID.
+        let mut missing = Vec::new();
+        let audited_receipts = self
+            .audited
+            .values()
+            .map(|receipt| (receipt.ballot_id, &receipt.confirmation_code))
+            .chain(
+                self.redacted
+                    .values()
+                    .map(|receipt| (receipt.ballot_id, &receipt.confirmation_code)),
+            );
+        // Redaction is not an event, so redacted ballots were simply audited.
+        for (ballot_id, confirmation_code) in audited_receipts {
+            if !has_events(board, ballot_id, confirmation_code, Audited) {
+                missing.push(ballot_id);
+            }
+        }
+        for receipt in self.confirmed.values() {
+            if !has_events(
+                board,
+                receipt.ballot_id,
+                &receipt.confirmation_code,
+                Confirmed,
+            ) {
+                missing.push(receipt.ballot_id);
+            }
+        }
+        missing.sort_unstable();
+        let mut errors = missing
+            .into_iter()
+            .map(|ballot_id| BoardError::MissingEvent { ballot_id })
+            .collect::<Vec<_>>();
+
+        let audited = String::from_utf8_lossy(Audited.as_ref());
+        let confirmed = String::from_utf8_lossy(Confirmed.as_ref());
+        for event in &board.events {
+            let present = if event.state == audited {
+                self.audited.contains_key(&event.ballot_id)
+                    || self.redacted.contains_key(&event.ballot_id)
+            } else if event.state == confirmed {
+                self.confirmed.contains_key(&event.ballot_id)
+            } else {
+                true // Unconfirmed ballots are not included in the dump.
+            };
+            if !present {
+                errors.push(BoardError::MissingReceipt {
+                    ballot_id: event.ballot_id,
+                });
+            }
+        }
+
+        errors
+    }
+}
+
+/// Check that the given receipt schema version is one this verifier understands.
+pub fn check_schema_version(version: u8) -> Result<(), VerificationError> {
+    if (1..=RECEIPT_SCHEMA_VERSION).contains(&version) {
+        Ok(())
+    } else {
+        Err(VerificationError::Receipt(
+            ReceiptError::UnsupportedVersion { version },
+        ))
+    }
+}
+
+/// Check that the given DRE-ip group is the one this verifier understands.
+pub fn check_group(group: &str) -> Result<(), VerificationError> {
+    if group == DREIP_GROUP_NAME {
         Ok(())
+    } else {
+        Err(VerificationError::UnsupportedGroup {
+            group: group.to_string(),
+        })
     }
 }
 
@@ -154,6 +780,8 @@ where
     for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
     for<'a> &'a <S as BallotState>::ReceiptData: Into<Vec<u8>>,
 {
+    check_schema_version(receipt.schema_version)?;
+
     // Verify PWFs.
     receipt
         .crypto
@@ -174,6 +802,8 @@ where
     for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
     for<'a> &'a <S as BallotState>::ReceiptData: Into<Vec<u8>>,
 {
+    check_schema_version(receipt.schema_version)?;
+
     // Verify the extra data.
     S::verify_receipt_data(receipt)?;
 
@@ -190,16 +820,17 @@ where
             ballot_id: receipt.ballot_id,
         }));
     }
+    check_confirmation_phrase(
+        receipt.ballot_id,
+        &receipt.confirmation_code,
+        &receipt.confirmation_phrase,
+    )?;
 
     // Verify signature.
-    let mut msg = receipt.crypto.to_bytes();
-    msg.extend(receipt.ballot_id.to_le_bytes());
-    msg.extend(receipt.election_id.to_le_bytes());
-    msg.extend(receipt.question_id.to_le_bytes());
-    msg.extend(receipt.confirmation_code.as_bytes());
-    msg.extend(receipt.state.as_ref());
-    msg.extend(Into::<Vec<u8>>::into(&receipt.state_data));
-    if !crypto.public_key.verify(&msg, &receipt.signature) {
+    if !crypto
+        .public_key
+        .verify(&receipt.message(), &receipt.signature)
+    {
         return Err(VerificationError::Receipt(ReceiptError::Signature {
             ballot_id: receipt.ballot_id,
         }));
@@ -207,3 +838,472 @@ where
 
     Ok(())
 }
+
+/// Check that a confirmation phrase is the one derived from the confirmation
+/// code. The phrase is not signed, so a wrong one could mislead a voter
+/// comparing it. Receipts issued before phrases existed have none to check.
+fn check_confirmation_phrase(
+    ballot_id: BallotId,
+    confirmation_code: &str,
+    phrase: &str,
+) -> Result<(), VerificationError> {
+    if !phrase.is_empty() && phrase != confirmation_phrase(confirmation_code) {
+        return Err(VerificationError::Receipt(
+            ReceiptError::ConfirmationPhrase { ballot_id },
+        ));
+    }
+    Ok(())
+}
+
+/// Verify the signature of an unconfirmed ballot stub.
+/// The stub contains no crypto, so the confirmation code cannot be checked,
+/// though its confirmation phrase can.
+pub fn verify_unconfirmed_stub(
+    stub: &UnconfirmedStub,
+    crypto: &ElectionCrypto,
+) -> Result<(), VerificationError> {
+    check_schema_version(stub.schema_version)?;
+    check_confirmation_phrase(
+        stub.ballot_id,
+        &stub.confirmation_code,
+        &stub.confirmation_phrase,
+    )?;
+
+    if !crypto.public_key.verify(&stub.message(), &stub.signature) {
+        return Err(VerificationError::Receipt(ReceiptError::Signature {
+            ballot_id: stub.ballot_id,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Verify an individual receipt using only the crypto of its question.
+pub fn verify_receipt_for_question<S>(
+    receipt: &Receipt<S>,
+    crypto: &QuestionCrypto,
+) -> Result<(), VerificationError>
+where
+    S: BallotState,
+    for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
+    for<'a> &'a <S as BallotState>::ReceiptData: Into<Vec<u8>>,
+{
+    check_question(
+        receipt.ballot_id,
+        receipt.election_id,
+        receipt.question_id,
+        crypto,
+    )?;
+    if !crypto.has_candidates(receipt.crypto.votes.keys()) {
+        return Err(VerificationError::WrongCandidates);
+    }
+    verify_receipt_full(receipt, &crypto.crypto)
+}
+
+/// Verify a public receipt of any state using only the crypto of its question.
+pub fn verify_public_receipt_for_question(
+    receipt: &PublicReceipt,
+    crypto: &QuestionCrypto,
+) -> Result<(), VerificationError> {
+    match receipt {
+        PublicReceipt::Unconfirmed(stub) => {
+            check_question(stub.ballot_id, stub.election_id, stub.question_id, crypto)?;
+            verify_unconfirmed_stub(stub, &crypto.crypto)
+        }
+        PublicReceipt::Audited(receipt) => verify_receipt_for_question(receipt, crypto),
+        PublicReceipt::AuditedRedacted(receipt) => verify_receipt_for_question(receipt, crypto),
+        PublicReceipt::Confirmed(receipt) => verify_receipt_for_question(receipt, crypto),
+    }
+}
+
+/// Check that a receipt's IDs match the question it is being verified against.
+fn check_question(
+    ballot_id: BallotId,
+    election_id: u32,
+    question_id: u32,
+    crypto: &QuestionCrypto,
+) -> Result<(), VerificationError> {
+    check_group(&crypto.crypto.group)?;
+    if election_id != crypto.election_id || question_id != crypto.question_id {
+        return Err(VerificationError::Receipt(ReceiptError::WrongQuestion {
+            ballot_id,
+        }));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rocket::serde::json::serde_json;
+
+    use crate::model::common::ballot::RedactedExtraData;
+
+    use super::*;
+
+    /// Every well-formed example dump, valid or otherwise.
+    const EXAMPLE_DUMPS: &[&str] = &[
+        "election.json",
+        "election_board.json",
+        "election_board_earlier.json",
+        "election_board_rewritten.json",
+        "election_board_tampered.json",
+        "election_future_version.json",
+        "election_inprogress.json",
+        "election_invalid_candidate.json",
+        "election_invalid_conf_code.json",
+        "election_invalid_multiple.json",
+        "election_invalid_signature.json",
+        "election_invalid_totals.json",
+    ];
+
+    fn load(name: &str) -> ElectionResults {
+        let raw = std::fs::read_to_string(format!("example_dumps/{name}")).unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    /// Strip the secrets from an audited receipt, without re-signing it.
+    fn redact(receipt: &Receipt<Audited>) -> Receipt<AuditedRedacted> {
+        Receipt {
+            schema_version: receipt.schema_version,
+            crypto: Audited::remove_external_secrets(&receipt.crypto),
+            ballot_id: receipt.ballot_id,
+            election_id: receipt.election_id,
+            question_id: receipt.question_id,
+            confirmation_code: receipt.confirmation_code.clone(),
+            confirmation_phrase: receipt.confirmation_phrase.clone(),
+            state: AuditedRedacted,
+            state_data: RedactedExtraData {
+                candidate: receipt.state_data.candidate.clone(),
+                redacted: true,
+            },
+            state_changed_at: receipt.state_changed_at,
+            recall_code: None,
+            signature: receipt.signature.clone(),
+        }
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        crate::logging::init_test_logging();
+        for name in EXAMPLE_DUMPS {
+            let results = load(name);
+            assert_eq!(
+                results.verify_receipts(false, true, &|_| {}),
+                results.verify_receipts(false, false, &|_| {}),
+                "{name}"
+            );
+        }
+
+        // Every failing ballot is reported, in order.
+        let mut results = load("election_inprogress.json");
+        let mut ballot_ids = results.confirmed.keys().copied().collect::<Vec<_>>();
+        ballot_ids.sort_unstable();
+        for ballot_id in &ballot_ids[1..] {
+            let receipt = results.confirmed.get_mut(ballot_id).unwrap();
+            receipt.confirmation_code = "tampered".to_string();
+        }
+        let expected = ballot_ids[1..]
+            .iter()
+            .map(|&ballot_id| {
+                VerificationError::Receipt(ReceiptError::ConfirmationCode { ballot_id })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(results.verify_receipts(false, true, &|_| {}), expected);
+        assert_eq!(results.verify_receipts(false, false, &|_| {}), expected);
+    }
+
+    #[test]
+    fn all_errors() {
+        crate::logging::init_test_logging();
+        assert_eq!(load("election.json").verify_all(), vec![]);
+        assert_eq!(load("election_board.json").verify_all(), vec![]);
+
+        // Independent problems in different phases are all reported, in order.
+        let results = load("election_invalid_multiple.json");
+        let expected = vec![
+            VerificationError::Tally {
+                candidate_id: "Parry Hotter".to_string(),
+            },
+            VerificationError::Receipt(ReceiptError::ConfirmationCode { ballot_id: 11 }),
+        ];
+        assert_eq!(results.verify_all(), expected);
+        assert_eq!(results.verify(), Err(expected[0].clone()));
+
+        // Every bad confirmed receipt is reported, not just the first.
+        let mut results = load("election.json");
+        let mut ballot_ids = results.confirmed.keys().copied().collect::<Vec<_>>();
+        ballot_ids.sort_unstable();
+        for ballot_id in &ballot_ids[..2] {
+            let receipt = results.confirmed.get_mut(ballot_id).unwrap();
+            receipt.confirmation_code = "tampered".to_string();
+        }
+        assert_eq!(
+            results.verify_all(),
+            ballot_ids[..2]
+                .iter()
+                .map(|&ballot_id| {
+                    VerificationError::Receipt(ReceiptError::ConfirmationCode { ballot_id })
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // Nothing else is checked for an unsupported version.
+        let results = load("election_future_version.json");
+        assert_eq!(
+            results.verify_all(),
+            vec![VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion { version: 2 }
+            )]
+        );
+    }
+
+    #[test]
+    fn confirmed_count_warnings() {
+        crate::logging::init_test_logging();
+
+        // Dumps taken before confirmed ballots were counted still verify, with
+        // a warning for each candidate.
+        let mut results = load("election.json");
+        let confirmed = results.confirmed.len() as u64;
+        assert_eq!(results.verify_all(), vec![]);
+        let mut candidates = results
+            .totals
+            .as_ref()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        candidates.sort();
+        assert_eq!(
+            results.warnings(),
+            candidates
+                .iter()
+                .map(|candidate_id| VerificationWarning::ConfirmedBallotCount {
+                    candidate_id: candidate_id.clone(),
+                    claimed: 0,
+                    actual: confirmed,
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // Correct counts agree with the receipts and the tallies.
+        for totals in results.totals.as_mut().unwrap().values_mut() {
+            totals.confirmed_ballot_count = confirmed;
+        }
+        assert_eq!(results.warnings(), vec![]);
+
+        // A missing receipt is noticed, without failing verification.
+        let ballot_id = *results.confirmed.keys().next().unwrap();
+        results.confirmed.remove(&ballot_id);
+        let warnings = results.warnings();
+        assert!(warnings.contains(&VerificationWarning::TallySum {
+            tally_sum: Some(confirmed),
+            confirmed: confirmed - 1,
+        }));
+        assert_eq!(warnings.len(), candidates.len() + 1);
+    }
+
+    #[test]
+    fn group() {
+        crate::logging::init_test_logging();
+        // Dumps from before the group was recorded are for the only one we support.
+        for name in EXAMPLE_DUMPS {
+            assert_eq!(load(name).election.group, DREIP_GROUP_NAME, "{name}");
+        }
+        let mut results = load("election.json");
+        assert_eq!(results.verify_all(), vec![]);
+
+        // Any other group is refused outright.
+        results.election.group = "secp256k1".to_string();
+        assert_eq!(
+            results.verify_all(),
+            vec![VerificationError::UnsupportedGroup {
+                group: "secp256k1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn redacted_receipts() {
+        crate::logging::init_test_logging();
+        let mut results = load("election_board.json");
+        let audited = results.audited.remove(&10).unwrap();
+        results.redacted.insert(10, redact(&audited));
+
+        // The proofs and the board still check out without the secrets, but the
+        // receipt must be signed as redacted.
+        assert_eq!(
+            results.verify_all(),
+            vec![VerificationError::Receipt(ReceiptError::Signature {
+                ballot_id: 10
+            })]
+        );
+    }
+
+    #[test]
+    fn confirmation_phrases() {
+        crate::logging::init_test_logging();
+        let mut results = load("election.json");
+        let mut ballot_ids = results.confirmed.keys().copied().collect::<Vec<_>>();
+        ballot_ids.sort_unstable();
+
+        // The right phrase verifies, as does none at all for older receipts.
+        let receipt = results.confirmed.get_mut(&ballot_ids[0]).unwrap();
+        receipt.confirmation_phrase = confirmation_phrase(&receipt.confirmation_code);
+        assert_eq!(results.verify_all(), vec![]);
+
+        // A phrase for another code is caught, though it is not signed.
+        let other_code = results.confirmed[&ballot_ids[1]].confirmation_code.clone();
+        let receipt = results.confirmed.get_mut(&ballot_ids[0]).unwrap();
+        receipt.confirmation_phrase = confirmation_phrase(&other_code);
+        assert_eq!(
+            results.verify_all(),
+            vec![VerificationError::Receipt(
+                ReceiptError::ConfirmationPhrase {
+                    ballot_id: ballot_ids[0]
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn diff() {
+        crate::logging::init_test_logging();
+        let earlier = load("election_board_earlier.json");
+        let later = load("election_board.json");
+
+        // Appending ballots and publishing the totals is fine.
+        assert_eq!(ElectionResults::diff(&earlier, &earlier), vec![]);
+        assert_eq!(ElectionResults::diff(&earlier, &later), vec![]);
+
+        // Removing a ballot is caught.
+        let mut removed = later.clone();
+        removed.confirmed.remove(&2);
+        assert_eq!(
+            ElectionResults::diff(&earlier, &removed),
+            vec![BoardDiffError::RemovedBallot { ballot_id: 2 }]
+        );
+
+        // As is swapping a ballot's crypto for another's.
+        let mut mutated = later.clone();
+        let other = mutated.confirmed[&4].crypto.clone();
+        mutated.confirmed.get_mut(&3).unwrap().crypto = other;
+        assert_eq!(
+            ElectionResults::diff(&earlier, &mutated),
+            vec![BoardDiffError::MutatedCrypto { ballot_id: 3 }]
+        );
+
+        // And going back on a confirmation.
+        let mut regressed = later.clone();
+        regressed.confirmed.remove(&2);
+        let mut audited = regressed.audited[&10].clone();
+        audited.ballot_id = 2;
+        regressed.audited.insert(2, audited);
+        assert_eq!(
+            ElectionResults::diff(&earlier, &regressed),
+            vec![BoardDiffError::IllegalTransition {
+                ballot_id: 2,
+                from: CONFIRMED,
+                to: AUDITED,
+            }]
+        );
+
+        // Redacting an audited ballot's secrets is fine, but nothing else may
+        // change, and the secrets may not come back.
+        let mut redacted = later.clone();
+        let audited = redacted.audited.remove(&10).unwrap();
+        redacted.redacted.insert(10, redact(&audited));
+        assert_eq!(ElectionResults::diff(&later, &redacted), vec![]);
+        assert_eq!(ElectionResults::diff(&redacted, &redacted), vec![]);
+        assert_eq!(
+            ElectionResults::diff(&redacted, &later),
+            vec![BoardDiffError::IllegalTransition {
+                ballot_id: 10,
+                from: AUDITED_REDACTED,
+                to: AUDITED,
+            }]
+        );
+        let mut relabelled = redacted.clone();
+        relabelled
+            .redacted
+            .get_mut(&10)
+            .unwrap()
+            .state_data
+            .candidate = "Nobody".to_string();
+        assert_eq!(
+            ElectionResults::diff(&later, &relabelled),
+            vec![BoardDiffError::MutatedCrypto { ballot_id: 10 }]
+        );
+
+        // Dropping the totals or the board once published is also caught.
+        let mut untallied = later.clone();
+        untallied.totals = None;
+        untallied.board = None;
+        assert_eq!(
+            ElectionResults::diff(&later, &untallied),
+            vec![
+                BoardDiffError::TotalsInconsistent {
+                    added_confirmed: 0,
+                    tally_increase: None,
+                },
+                BoardDiffError::Board(BoardError::Diverged { position: 0 }),
+            ]
+        );
+
+        // Dumps of different elections cannot be compared.
+        let mut other = later;
+        other.election.g1 = other.election.g2;
+        assert_eq!(
+            ElectionResults::diff(&earlier, &other),
+            vec![BoardDiffError::DifferentElection]
+        );
+    }
+
+    #[test]
+    fn progress() {
+        crate::logging::init_test_logging();
+        let results = load("election_board.json");
+        let events = Mutex::new(Vec::new());
+        results
+            .verify_with_progress(|event| events.lock().unwrap().push(event))
+            .unwrap();
+        let events = events.into_inner().unwrap();
+
+        // Every phase is started in order.
+        let phases = events
+            .iter()
+            .filter_map(|event| match event {
+                VerificationProgress::Started(phase) => Some(*phase),
+                VerificationProgress::Receipt { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            vec![
+                VerificationPhase::Totals,
+                VerificationPhase::Receipts,
+                VerificationPhase::Board
+            ]
+        );
+
+        // Every receipt is counted exactly once.
+        let total = results.audited.len() + results.confirmed.len();
+        let mut verified = events
+            .iter()
+            .filter_map(|event| match event {
+                VerificationProgress::Receipt {
+                    verified,
+                    total: reported_total,
+                } => {
+                    assert_eq!(*reported_total, total);
+                    Some(*verified)
+                }
+                VerificationProgress::Started(_) => None,
+            })
+            .collect::<Vec<_>>();
+        verified.sort_unstable();
+        assert_eq!(verified, (1..=total).collect::<Vec<_>>());
+    }
+}
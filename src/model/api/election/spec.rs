@@ -1,30 +1,339 @@
 use std::collections::{HashMap, HashSet};
 
-use chrono::{DateTime, Utc};
+use chrono::{
+    DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, SecondsFormat, TimeZone,
+    Timelike, Utc,
+};
+use chrono_tz::Tz;
+#[cfg(feature = "server")]
+use mongodb::bson;
+#[cfg(feature = "server")]
 use rand::{CryptoRng, RngCore};
+#[cfg(feature = "server")]
+use rocket::http::Status;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::model::{
-    common::election::{ElectionId, ElectionState, Electorate, QuestionId},
-    db::election::{Election, ElectionMetadata, Question},
+use crate::model::common::election::{
+    CandidateId, Electorate, QuorumSpec, ResultVisibility, Translations,
+};
+#[cfg(feature = "server")]
+use crate::{
+    error::Error,
+    model::{
+        common::election::{
+            check_translations, ElectionId, ElectionState, QuestionId, WRITE_IN_CANDIDATE,
+        },
+        db::election::{Election, ElectionMetadata, Question},
+    },
 };
 
+/// The largest branding accepted, in bytes of serialised JSON.
+pub const MAX_BRANDING_SIZE: usize = 16 * 1024;
+
 /// An election specification.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// When deserialised, the start and end times may be given without a UTC
+/// offset, as local times in the spec's timezone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ElectionSpecInput")]
 pub struct ElectionSpec {
     /// Election name.
     pub name: String,
+    /// Translations of the name, by language code.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub name_translations: Translations,
     /// Election start time.
     pub start_time: DateTime<Utc>,
     /// Election end time.
     pub end_time: DateTime<Utc>,
+    /// IANA name of the timezone the election's times were given in, such as
+    /// `Europe/London`, so that frontends can show them as local times.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Must voters re-verify their SMS number before voting in this election?
+    pub requires_step_up: bool,
+    /// Should each voter see each question's candidates in their own order?
+    pub randomise_candidate_order: bool,
+    /// The most voters who may join the election, if it is capped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_voters: Option<u32>,
+    /// Voters may join the election up to this many minutes before it starts,
+    /// though they still cannot vote until it does. If unset, voters can only
+    /// join once it has started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_join_minutes: Option<u32>,
+    /// Hide the election's ballots and dumps from everyone but admins until
+    /// this time. If unset, they are public as soon as there are any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embargo_public_board_until: Option<DateTime<Utc>>,
+    /// Text, such as a data processing notice, that voters must accept before
+    /// joining the election. If unset, no acceptance is needed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consent_text: Option<String>,
+    /// Version of the consent text, which voters must give to accept it.
+    pub consent_version: u32,
+    /// Display metadata for frontends, such as a logo URL or theme colour.
+    /// This is passed through verbatim, and is not part of any signed data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branding: Option<Value>,
     /// Election electorates.
     pub electorates: Vec<Electorate>,
     /// Election questions specifications.
     pub questions: Vec<QuestionSpec>,
 }
 
+/// An election specification as submitted, before its times are resolved.
+///
+/// Times with a UTC offset are taken as given. Times without one are local
+/// times in `timezone`, which must then be given; they must be whole minutes,
+/// and must happen exactly once in that timezone, so not during the hour
+/// skipped or repeated when the clocks change.
+///
+/// Handlers take this rather than an [`ElectionSpec`], and convert it
+/// themselves, so that they can explain why a spec was refused.
+#[derive(Deserialize)]
+pub struct ElectionSpecInput {
+    name: String,
+    #[serde(default)]
+    name_translations: Translations,
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    requires_step_up: bool,
+    #[serde(default)]
+    randomise_candidate_order: bool,
+    #[serde(default)]
+    max_voters: Option<u32>,
+    #[serde(default)]
+    pre_join_minutes: Option<u32>,
+    #[serde(default)]
+    embargo_public_board_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    consent_text: Option<String>,
+    #[serde(default)]
+    consent_version: u32,
+    #[serde(default)]
+    branding: Option<Value>,
+    electorates: Vec<Electorate>,
+    questions: Vec<QuestionSpec>,
+}
+
+impl TryFrom<ElectionSpecInput> for ElectionSpec {
+    type Error = String;
+
+    fn try_from(input: ElectionSpecInput) -> Result<Self, Self::Error> {
+        let timezone = match &input.timezone {
+            Some(name) => Some(
+                name.parse::<Tz>()
+                    .map_err(|_| format!("Unknown timezone '{name}'"))?,
+            ),
+            None => None,
+        };
+        if input.consent_text.as_deref().is_some_and(str::is_empty) {
+            return Err("consent_text must not be empty".to_string());
+        }
+        if input.max_voters == Some(0) {
+            return Err("max_voters must be at least 1, or omitted for no cap".to_string());
+        }
+        Ok(Self {
+            name: input.name,
+            name_translations: input.name_translations,
+            start_time: SpecTime::parse("start_time", &input.start_time)?
+                .resolve("start_time", timezone)?,
+            end_time: SpecTime::parse("end_time", &input.end_time)?
+                .resolve("end_time", timezone)?,
+            timezone: input.timezone,
+            requires_step_up: input.requires_step_up,
+            randomise_candidate_order: input.randomise_candidate_order,
+            max_voters: input.max_voters,
+            pre_join_minutes: input.pre_join_minutes,
+            embargo_public_board_until: input.embargo_public_board_until,
+            consent_text: input.consent_text,
+            consent_version: input.consent_version,
+            branding: input.branding,
+            electorates: input.electorates,
+            questions: input.questions,
+        })
+    }
+}
+
+/// A time in an election spec, as given: an instant, or a local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpecTime {
+    Instant(DateTime<Utc>),
+    Local(NaiveDateTime),
+}
+
+impl SpecTime {
+    /// Parse a time as given, with or without a UTC offset. `field` names the
+    /// time in any error.
+    fn parse(field: &str, time: &str) -> Result<Self, String> {
+        if let Ok(instant) = time.parse::<DateTime<FixedOffset>>() {
+            return Ok(Self::Instant(instant.with_timezone(&Utc)));
+        }
+        time.parse::<NaiveDateTime>()
+            .map(Self::Local)
+            .map_err(|_| format!("{field} '{time}' is not a date and time"))
+    }
+
+    /// Resolve this time to an instant, reading a local time in the given
+    /// timezone. `field` names the time in any error.
+    fn resolve(self, field: &str, timezone: Option<Tz>) -> Result<DateTime<Utc>, String> {
+        let local = match self {
+            Self::Instant(instant) => return Ok(instant),
+            Self::Local(local) => local,
+        };
+        let Some(timezone) = timezone else {
+            return Err(format!(
+                "{field} {local} has no UTC offset, so needs a timezone"
+            ));
+        };
+        if local.second() != 0 || local.nanosecond() != 0 {
+            return Err(format!("{field} {local} must be a whole minute"));
+        }
+        let format = |instant: DateTime<Utc>| instant.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let (problem, earlier, later) = match timezone.from_local_datetime(&local) {
+            LocalResult::Single(instant) => return Ok(instant.with_timezone(&Utc)),
+            LocalResult::Ambiguous(earlier, later) => (
+                "happens twice",
+                earlier.with_timezone(&Utc),
+                later.with_timezone(&Utc),
+            ),
+            LocalResult::None => {
+                // The time was skipped as the clocks went forward, so could be
+                // meant with the offset from either side of the change.
+                let offset_at =
+                    |time: NaiveDateTime| timezone.offset_from_utc_datetime(&time).fix();
+                let day = Duration::try_days(1).unwrap();
+                (
+                    "does not exist",
+                    (local - offset_at(local + day)).and_utc(),
+                    (local - offset_at(local - day)).and_utc(),
+                )
+            }
+        };
+        Err(format!(
+            "{field} {local} {problem} in {timezone} as the clocks change, \
+             so could be {} or {}; give it with a UTC offset instead",
+            format(earlier),
+            format(later),
+        ))
+    }
+}
+
+#[cfg(feature = "server")]
 impl ElectionSpec {
+    /// Check that the spec's branding, if any, is acceptable.
+    pub fn check_branding(&self) -> Result<(), Error> {
+        match &self.branding {
+            Some(branding) => check_branding(branding),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that each question closes within the election's voting window.
+    pub fn check_question_end_times(&self) -> Result<(), Error> {
+        for question in &self.questions {
+            if let Some(end_time) = question.end_time {
+                if end_time <= self.start_time || end_time > self.end_time {
+                    return Err(Error::Status(
+                        Status::UnprocessableEntity,
+                        format!(
+                            "Question '{}' must close after the election starts and no later than it ends",
+                            question.description
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that no question names the reserved write-in candidate itself.
+    pub fn check_candidates(&self) -> Result<(), Error> {
+        for question in &self.questions {
+            if question.candidates.iter().any(|c| c == WRITE_IN_CANDIDATE) {
+                return Err(Error::Status(
+                    Status::UnprocessableEntity,
+                    format!(
+                        "Question '{}' has a candidate named '{}', which is reserved for write-ins",
+                        question.description, WRITE_IN_CANDIDATE
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every translation is for a well-formed language code and is
+    /// not empty, and that candidates' translations are for candidates of
+    /// their question.
+    pub fn check_translations(&self) -> Result<(), Error> {
+        let invalid = |e| Error::Status(Status::UnprocessableEntity, e);
+        check_translations("the election name", &self.name_translations).map_err(invalid)?;
+        for question in &self.questions {
+            let what = format!("question '{}'", question.description);
+            check_translations(&what, &question.description_translations).map_err(invalid)?;
+            for (candidate, translations) in &question.candidate_translations {
+                if !question.candidates.contains(candidate) {
+                    return Err(invalid(format!(
+                        "Question '{}' has translations for '{candidate}', which is not one of \
+                         its candidates",
+                        question.description
+                    )));
+                }
+                let what = format!("candidate '{candidate}' of {what}");
+                check_translations(&what, translations).map_err(invalid)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that no two questions share a description, since questions are
+    /// matched by description when an election is modified.
+    pub fn check_question_descriptions(&self) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for question in &self.questions {
+            if !seen.insert(&question.description) && !duplicates.contains(&&question.description) {
+                duplicates.push(&question.description);
+            }
+        }
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Status(
+            Status::UnprocessableEntity,
+            format!(
+                "Question descriptions must be unique, but these are repeated: {}",
+                duplicates
+                    .iter()
+                    .map(|description| format!("'{description}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ))
+    }
+
+    /// Check that each question's quorum, if any, could be both met and missed.
+    pub fn check_quorums(&self) -> Result<(), Error> {
+        for question in &self.questions {
+            if let Some(Err(e)) = question.quorum.map(QuorumSpec::check) {
+                return Err(Error::Status(
+                    Status::UnprocessableEntity,
+                    format!(
+                        "Question '{}' has an invalid quorum: {e}",
+                        question.description
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Convert this spec into a proper Election with unique IDs.
     pub fn into_election(self, election_id: ElectionId, rng: impl RngCore + CryptoRng) -> Election {
         let electorates = self
@@ -32,7 +341,17 @@ impl ElectionSpec {
             .into_iter()
             .map(|electorate| (electorate.name.clone(), electorate))
             .collect();
-        Election::new(
+        let requires_step_up = self.requires_step_up;
+        let randomise_candidate_order = self.randomise_candidate_order;
+        let max_voters = self.max_voters;
+        let pre_join_minutes = self.pre_join_minutes;
+        let embargo_public_board_until = self.embargo_public_board_until;
+        let consent_text = self.consent_text;
+        let consent_version = self.consent_version;
+        let timezone = self.timezone;
+        let branding = self.branding;
+        let name_translations = self.name_translations;
+        let mut election = Election::new(
             election_id,
             self.name,
             self.start_time,
@@ -47,51 +366,224 @@ impl ElectionSpec {
                 })
                 .collect(),
             rng,
-        )
+        );
+        election.metadata.requires_step_up = requires_step_up;
+        election.metadata.randomise_candidate_order = randomise_candidate_order;
+        election.metadata.max_voters = max_voters;
+        election.metadata.pre_join_minutes = pre_join_minutes;
+        election.metadata.embargo_public_board_until =
+            embargo_public_board_until.map(bson::DateTime::from_chrono);
+        election.metadata.consent_text = consent_text;
+        election.metadata.consent_version = consent_version;
+        election.metadata.timezone = timezone;
+        election.metadata.branding = branding;
+        election.metadata.name_translations = name_translations;
+        election
     }
 }
 
+/// Recover the spec of an election, such that [`ElectionSpec::into_election`]
+/// gives back an equivalent election, modulo its ID and keys.
+/// Questions are ordered by ID, and electorates by name.
+#[cfg(feature = "server")]
+impl From<Election> for ElectionSpec {
+    fn from(election: Election) -> Self {
+        let mut electorates = election.electorates.into_values().collect::<Vec<_>>();
+        electorates.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        let mut questions = election.questions.into_values().collect::<Vec<_>>();
+        questions.sort_unstable_by_key(|question| question.id);
+        Self {
+            name: election.metadata.name,
+            name_translations: election.metadata.name_translations,
+            start_time: election.metadata.start_time,
+            end_time: election.metadata.end_time,
+            timezone: election.metadata.timezone,
+            requires_step_up: election.metadata.requires_step_up,
+            randomise_candidate_order: election.metadata.randomise_candidate_order,
+            max_voters: election.metadata.max_voters,
+            pre_join_minutes: election.metadata.pre_join_minutes,
+            embargo_public_board_until: election
+                .metadata
+                .embargo_public_board_until
+                .map(bson::DateTime::to_chrono),
+            consent_text: election.metadata.consent_text,
+            consent_version: election.metadata.consent_version,
+            branding: election.metadata.branding,
+            electorates,
+            questions: questions.into_iter().map(QuestionSpec::from).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
 impl From<ElectionSpec> for ElectionMetadata {
     fn from(spec: ElectionSpec) -> Self {
         Self {
             name: spec.name,
+            name_translations: spec.name_translations,
             state: ElectionState::Draft,
             start_time: spec.start_time,
             end_time: spec.end_time,
+            timezone: spec.timezone,
+            suspended: false,
+            requires_step_up: spec.requires_step_up,
+            randomise_candidate_order: spec.randomise_candidate_order,
+            max_voters: spec.max_voters,
+            pre_join_minutes: spec.pre_join_minutes,
+            embargo_public_board_until: spec
+                .embargo_public_board_until
+                .map(bson::DateTime::from_chrono),
+            consent_text: spec.consent_text,
+            consent_version: spec.consent_version,
+            joined_count: 0,
+            branding: spec.branding,
+            created_by: String::new(),
+            last_modified_by: None,
+            rehearsal: false,
+            archived_at: None,
         }
     }
 }
 
+/// Check that branding is a JSON object no larger than [`MAX_BRANDING_SIZE`],
+/// which can be stored in the database.
+#[cfg(feature = "server")]
+pub fn check_branding(branding: &Value) -> Result<(), Error> {
+    if !branding.is_object() {
+        return Err(Error::Status(
+            Status::UnprocessableEntity,
+            "Branding must be a JSON object".to_string(),
+        ));
+    }
+    branding_to_bson(branding)?;
+    let size = serde_json::to_vec(branding)
+        .expect("Serialisation is infallible")
+        .len();
+    if size > MAX_BRANDING_SIZE {
+        return Err(Error::Status(
+            Status::PayloadTooLarge,
+            format!(
+                "Branding is {} bytes, but at most {} are allowed",
+                size, MAX_BRANDING_SIZE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Convert branding to BSON for storage. This fails for numbers BSON cannot
+/// represent, such as integers above `i64::MAX`.
+#[cfg(feature = "server")]
+pub fn branding_to_bson(branding: &Value) -> Result<bson::Bson, Error> {
+    bson::to_bson(branding).map_err(|err| {
+        Error::Status(
+            Status::UnprocessableEntity,
+            format!("Branding cannot be stored: {err}"),
+        )
+    })
+}
+
 /// A question specification.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct QuestionSpec {
     /// Question text.
     pub description: String,
+    /// Translations of the question text, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub description_translations: Translations,
     /// A voter must be in at least one of these electorate groups to vote on this question.
     pub constraints: HashMap<String, HashSet<String>>,
     /// Candidates / possible answers for this question.
     pub candidates: Vec<String>,
+    /// Translations of candidates' display names, by candidate then language
+    /// code. Votes are always for the candidate itself.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub candidate_translations: HashMap<CandidateId, Translations>,
+    /// When voting on this question closes, if before the end of the election.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+    /// May voters write in a candidate of their own?
+    #[serde(default)]
+    pub allow_write_in: bool,
+    /// How much of the results the public may see once the election has ended.
+    #[serde(default)]
+    pub result_visibility: ResultVisibility,
+    /// How many voters must answer the question for its result to be binding,
+    /// if there is a quorum. This is only reported on once the election ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumSpec>,
 }
 
+#[cfg(feature = "server")]
 impl QuestionSpec {
     /// Convert this spec into a question with the given unique ID.
     pub fn into_question(self, id: QuestionId) -> Question {
         Question {
             id,
+            slug: String::new(),
             description: self.description,
+            description_translations: self.description_translations,
             constraints: self.constraints,
             candidates: self.candidates,
+            candidate_translations: self.candidate_translations,
+            end_time: self.end_time.map(bson::DateTime::from_chrono),
+            allow_write_in: self.allow_write_in,
+            result_visibility: self.result_visibility,
+            quorum: self.quorum,
+            last_dump_at: None,
+            last_dump_signature: None,
         }
     }
 }
 
-/// Example data for tests.
-#[cfg(test)]
+#[cfg(feature = "server")]
+impl From<Question> for QuestionSpec {
+    /// The write-in candidate is left out, since it is added on publication.
+    fn from(question: Question) -> Self {
+        Self {
+            description: question.description,
+            description_translations: question.description_translations,
+            constraints: question.constraints,
+            candidate_translations: question.candidate_translations,
+            candidates: question
+                .candidates
+                .into_iter()
+                .filter(|candidate| candidate != WRITE_IN_CANDIDATE)
+                .collect(),
+            end_time: question.end_time.map(bson::DateTime::to_chrono),
+            allow_write_in: question.allow_write_in,
+            result_visibility: question.result_visibility,
+            quorum: question.quorum,
+        }
+    }
+}
+
+/// An electorate specification, for creating or replacing a single electorate
+/// of an existing election. The name is given separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectorateSpec {
+    /// Contained groups.
+    pub groups: HashSet<String>,
+    /// Whether the groups are mutually exclusive.
+    pub is_mutex: bool,
+}
+
+impl ElectorateSpec {
+    /// Convert this spec into an electorate with the given name.
+    pub fn into_electorate(self, name: String) -> Electorate {
+        Electorate {
+            name,
+            groups: self.groups,
+            is_mutex: self.is_mutex,
+        }
+    }
+}
+
+/// Example data for tests and development seeding.
+#[cfg(any(test, feature = "dev-tools"))]
 mod examples {
     use super::*;
 
-    use chrono::{Duration, Timelike, Utc};
-
     macro_rules! midnight_today {
         () => {{
             Utc::now()
@@ -109,8 +601,18 @@ mod examples {
             let end_time = start_time + Duration::try_days(30).unwrap();
             Self {
                 name: "Test Election 1".to_string(),
+                name_translations: Translations::new(),
                 start_time,
                 end_time,
+                requires_step_up: false,
+                randomise_candidate_order: false,
+                max_voters: None,
+                pre_join_minutes: None,
+                embargo_public_board_until: None,
+                consent_text: None,
+                consent_version: 0,
+                timezone: None,
+                branding: None,
                 electorates: vec![Electorate::example1(), Electorate::example2()],
                 questions: vec![
                     QuestionSpec::example1(),
@@ -126,8 +628,18 @@ mod examples {
             let end_time = start_time + Duration::try_days(30).unwrap();
             Self {
                 name: "Test Election 2".to_string(),
+                name_translations: Translations::new(),
                 start_time,
                 end_time,
+                requires_step_up: false,
+                randomise_candidate_order: false,
+                max_voters: None,
+                pre_join_minutes: None,
+                embargo_public_board_until: None,
+                consent_text: None,
+                consent_version: 0,
+                timezone: None,
+                branding: None,
                 electorates: vec![Electorate::example1()],
                 questions: vec![QuestionSpec::example1(), QuestionSpec::example2()],
             }
@@ -138,8 +650,18 @@ mod examples {
             let end_time = start_time + Duration::try_days(7).unwrap();
             Self {
                 name: "Test Election 3".to_string(),
+                name_translations: Translations::new(),
                 start_time,
                 end_time,
+                requires_step_up: false,
+                randomise_candidate_order: false,
+                max_voters: None,
+                pre_join_minutes: None,
+                embargo_public_board_until: None,
+                consent_text: None,
+                consent_version: 0,
+                timezone: None,
+                branding: None,
                 electorates: vec![Electorate::example1()],
                 questions: vec![QuestionSpec::example1(), QuestionSpec::example2()],
             }
@@ -155,6 +677,12 @@ mod examples {
                     HashSet::from_iter(vec!["Quidditch".to_string()]),
                 )]),
                 candidates: vec!["Chris Riches".to_string(), "Parry Hotter".to_string()],
+                end_time: None,
+                allow_write_in: false,
+                result_visibility: ResultVisibility::Full,
+                quorum: None,
+                description_translations: Translations::new(),
+                candidate_translations: HashMap::new(),
             }
         }
 
@@ -166,6 +694,12 @@ mod examples {
                     HashSet::from_iter(vec!["Moongolf".to_string()]),
                 )]),
                 candidates: vec!["John Smith".to_string(), "Jane Doe".to_string()],
+                end_time: None,
+                allow_write_in: false,
+                result_visibility: ResultVisibility::Full,
+                quorum: None,
+                description_translations: Translations::new(),
+                candidate_translations: HashMap::new(),
             }
         }
 
@@ -183,6 +717,12 @@ mod examples {
                     ),
                 ]),
                 candidates: vec!["Yes".to_string(), "No".to_string()],
+                end_time: None,
+                allow_write_in: false,
+                result_visibility: ResultVisibility::Full,
+                quorum: None,
+                description_translations: Translations::new(),
+                candidate_translations: HashMap::new(),
             }
         }
 
@@ -191,7 +731,172 @@ mod examples {
                 description: "Should this question really be open to everyone?".to_string(),
                 constraints: HashMap::new(),
                 candidates: vec!["Definitely".to_string(), "Absolutely".to_string()],
+                end_time: None,
+                allow_write_in: false,
+                result_visibility: ResultVisibility::Full,
+                quorum: None,
+                description_translations: Translations::new(),
+                candidate_translations: HashMap::new(),
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Parse the example spec with the given times and timezone.
+    fn parse(
+        start_time: &str,
+        end_time: &str,
+        timezone: Option<&str>,
+    ) -> Result<ElectionSpec, String> {
+        let mut spec = serde_json::to_value(ElectionSpec::current_example()).unwrap();
+        spec["start_time"] = json!(start_time);
+        spec["end_time"] = json!(end_time);
+        if let Some(timezone) = timezone {
+            spec["timezone"] = json!(timezone);
+        }
+        serde_json::from_value(spec).map_err(|err| err.to_string())
+    }
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn utc_times() {
+        // Round-trips exactly as before timezones were supported.
+        let example = ElectionSpec::current_example();
+        let json = serde_json::to_string(&example).unwrap();
+        assert!(!json.contains("timezone"));
+        assert_eq!(
+            serde_json::from_str::<ElectionSpec>(&json).unwrap(),
+            example
+        );
+
+        // Offsets are respected, with or without a timezone.
+        let spec = parse("2024-03-31T00:30:00Z", "2024-04-01T09:00:00+01:00", None).unwrap();
+        assert_eq!(spec.start_time, utc("2024-03-31T00:30:00Z"));
+        assert_eq!(spec.end_time, utc("2024-04-01T08:00:00Z"));
+        assert_eq!(spec.timezone, None);
+        let spec = parse(
+            "2024-03-31T00:30:00Z",
+            "2024-04-01T09:00:00+01:00",
+            Some("America/New_York"),
+        )
+        .unwrap();
+        assert_eq!(spec.start_time, utc("2024-03-31T00:30:00Z"));
+        assert_eq!(spec.end_time, utc("2024-04-01T08:00:00Z"));
+    }
+
+    #[test]
+    fn local_times() {
+        // Local times are converted from the zone's offset at the time.
+        let spec = parse(
+            "2024-03-01T09:00:00",
+            "2024-06-01T17:00:00",
+            Some("Europe/London"),
+        )
+        .unwrap();
+        assert_eq!(spec.start_time, utc("2024-03-01T09:00:00Z"));
+        assert_eq!(spec.end_time, utc("2024-06-01T16:00:00Z"));
+        assert_eq!(spec.timezone.as_deref(), Some("Europe/London"));
+
+        // They need a known timezone, and must be whole minutes.
+        let error = parse("2024-03-01T09:00:00", "2024-06-01T17:00:00", None).unwrap_err();
+        assert!(error.contains("needs a timezone"), "{error}");
+        let error = parse(
+            "2024-03-01T09:00:00",
+            "2024-06-01T17:00:00",
+            Some("Europe/Londinium"),
+        )
+        .unwrap_err();
+        assert!(error.contains("Unknown timezone"), "{error}");
+        let error = parse(
+            "2024-03-01T09:00:30",
+            "2024-06-01T17:00:00",
+            Some("Europe/London"),
+        )
+        .unwrap_err();
+        assert!(error.contains("whole minute"), "{error}");
+    }
+
+    #[test]
+    fn spring_forward_gap() {
+        // Clocks in London go from 01:00 to 02:00 on 31 March 2024.
+        let error = parse(
+            "2024-03-31T01:30:00",
+            "2024-04-01T17:00:00",
+            Some("Europe/London"),
+        )
+        .unwrap_err();
+        assert!(error.contains("does not exist"), "{error}");
+        assert!(error.contains("2024-03-31T00:30:00Z"), "{error}");
+        assert!(error.contains("2024-03-31T01:30:00Z"), "{error}");
+
+        // Either side of the gap is fine.
+        let spec = parse(
+            "2024-03-31T00:59:00",
+            "2024-03-31T02:00:00",
+            Some("Europe/London"),
+        )
+        .unwrap();
+        assert_eq!(spec.start_time, utc("2024-03-31T00:59:00Z"));
+        assert_eq!(spec.end_time, utc("2024-03-31T01:00:00Z"));
+    }
+
+    #[test]
+    fn fall_back_ambiguity() {
+        // Clocks in London go from 02:00 back to 01:00 on 27 October 2024.
+        let error = parse(
+            "2024-10-01T09:00:00",
+            "2024-10-27T01:30:00",
+            Some("Europe/London"),
+        )
+        .unwrap_err();
+        assert!(error.contains("end_time"), "{error}");
+        assert!(error.contains("happens twice"), "{error}");
+        assert!(error.contains("2024-10-27T00:30:00Z"), "{error}");
+        assert!(error.contains("2024-10-27T01:30:00Z"), "{error}");
+
+        // Giving the offset resolves it.
+        let spec = parse(
+            "2024-10-01T09:00:00",
+            "2024-10-27T01:30:00+00:00",
+            Some("Europe/London"),
+        )
+        .unwrap();
+        assert_eq!(spec.start_time, utc("2024-10-01T08:00:00Z"));
+        assert_eq!(spec.end_time, utc("2024-10-27T01:30:00Z"));
+    }
+
+    #[test]
+    fn duplicate_question_descriptions() {
+        let mut spec = ElectionSpec::current_example();
+        assert!(spec.check_question_descriptions().is_ok());
+
+        // Each repeated description is listed once.
+        spec.questions = vec![
+            QuestionSpec::example1(),
+            QuestionSpec::example2(),
+            QuestionSpec::example1(),
+            QuestionSpec::example1(),
+            QuestionSpec::example2(),
+        ];
+        let error = spec.check_question_descriptions().unwrap_err();
+        let Error::Status(status, message) = error else {
+            panic!("Unexpected error {error:?}");
+        };
+        assert_eq!(status, Status::UnprocessableEntity);
+        let expected = format!(
+            "'{}', '{}'",
+            QuestionSpec::example1().description,
+            QuestionSpec::example2().description
+        );
+        assert!(message.ends_with(&expected), "{message}");
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::election::QuestionId;
+
+/// What an election's finalizer would do if it ran now: the unconfirmed
+/// ballots it would audit, by how long ago they were cast.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalizePreview {
+    /// When the ballots were counted; their ages are relative to this.
+    pub as_of: DateTime<Utc>,
+    /// Unconfirmed ballots across every question.
+    pub total: UnconfirmedByAge,
+    /// Unconfirmed ballots of each question that has any.
+    pub questions: HashMap<QuestionId, UnconfirmedByAge>,
+}
+
+/// Unconfirmed ballots, by how long ago they were cast. Recent ones were
+/// probably cast by voters still deciding whether to confirm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnconfirmedByAge {
+    /// Cast within the last five minutes.
+    pub under_5_minutes: u64,
+    /// Cast between five minutes and an hour ago.
+    pub under_1_hour: u64,
+    /// Cast over an hour ago.
+    pub older: u64,
+}
+
+impl UnconfirmedByAge {
+    /// The number of ballots of any age.
+    pub fn total(&self) -> u64 {
+        self.under_5_minutes + self.under_1_hour + self.older
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    api::{ballot::BallotStats, election::QuestionDescription},
+    common::election::{CandidateId, QuorumReport},
+};
+
+/// Everything a results page shows for a finished election, in one response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FullResults {
+    pub election_id: u32,
+    pub election_name: String,
+    /// Results for every question, by ascending question ID.
+    pub questions: Vec<QuestionResults>,
+}
+
+/// The results of a single question.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionResults {
+    /// The question, including its candidates.
+    pub question: QuestionDescription,
+    /// Number of votes for each candidate. Empty if the question's tallies
+    /// are withheld from the public, leaving only its winners.
+    pub tallies: HashMap<CandidateId, u64>,
+    /// Number of confirmed ballots.
+    pub confirmed: u64,
+    /// Number of audited ballots.
+    pub audited: u64,
+    /// The candidates with the most votes, in question order.
+    /// Empty if nobody voted.
+    pub winners: Vec<CandidateId>,
+    /// Whether more than one candidate has the most votes.
+    pub tied: bool,
+    /// Whether enough voters answered the question to meet its quorum.
+    pub quorum: QuorumReport,
+}
+
+impl QuestionResults {
+    /// Work out a question's winners from its tallies, and whether it met its
+    /// quorum given how many voters were eligible to answer it. Candidates
+    /// without a tally have no votes.
+    pub fn new(
+        question: QuestionDescription,
+        mut tallies: HashMap<CandidateId, u64>,
+        stats: &BallotStats,
+        eligible_voters: u64,
+    ) -> Self {
+        for candidate in &question.candidates {
+            tallies.entry(candidate.clone()).or_default();
+        }
+        let most_votes = tallies.values().copied().max().unwrap_or_default();
+        let winners = if most_votes == 0 {
+            Vec::new()
+        } else {
+            question
+                .candidates
+                .iter()
+                .filter(|candidate| tallies[*candidate] == most_votes)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let quorum = QuorumReport::new(question.quorum, eligible_voters, stats.confirmed);
+        Self {
+            question,
+            tallies,
+            confirmed: stats.confirmed,
+            audited: stats.audited,
+            tied: winners.len() > 1,
+            winners,
+            quorum,
+        }
+    }
+
+    /// Withhold the tallies from the public, leaving only the winners.
+    pub fn withhold_tallies(&mut self) {
+        self.tallies.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde_json::json;
+
+    use crate::model::common::election::{QuorumSpec, ResultVisibility};
+
+    use super::*;
+
+    fn question() -> QuestionDescription {
+        QuestionDescription {
+            id: 1,
+            slug: "best-colour".to_string(),
+            description: "Best colour?".to_string(),
+            canonical_description: None,
+            description_translations: HashMap::new(),
+            constraints: HashMap::from([(
+                "Societies".to_string(),
+                HashSet::from(["Art".to_string()]),
+            )]),
+            candidates: vec!["Red".to_string(), "Green".to_string(), "Blue".to_string()],
+            candidate_translations: HashMap::new(),
+            candidate_display_names: None,
+            end_time: None,
+            allow_write_in: false,
+            result_visibility: ResultVisibility::Full,
+            quorum: None,
+            quorum_report: None,
+        }
+    }
+
+    fn stats() -> BallotStats {
+        BallotStats {
+            unconfirmed: 1,
+            audited: 2,
+            confirmed: 7,
+            last_activity: None,
+        }
+    }
+
+    fn tallies(red: u64, green: u64) -> HashMap<CandidateId, u64> {
+        HashMap::from([("Red".to_string(), red), ("Green".to_string(), green)])
+    }
+
+    #[test]
+    fn single_winner() {
+        let results = QuestionResults::new(question(), tallies(3, 4), &stats(), 10);
+        assert_eq!(results.winners, vec!["Green".to_string()]);
+        assert!(!results.tied);
+        // Candidates nobody voted for are still listed.
+        assert_eq!(results.tallies["Blue"], 0);
+        assert_eq!(results.confirmed, 7);
+        assert_eq!(results.audited, 2);
+        // Without a quorum, there is none to meet.
+        assert_eq!(results.quorum.quorum_met, None);
+        assert_eq!(results.quorum.eligible_voters, 10);
+    }
+
+    #[test]
+    fn tied_winners() {
+        let results = QuestionResults::new(question(), tallies(4, 4), &stats(), 10);
+        assert_eq!(
+            results.winners,
+            vec!["Red".to_string(), "Green".to_string()]
+        );
+        assert!(results.tied);
+    }
+
+    #[test]
+    fn no_votes_no_winners() {
+        let results = QuestionResults::new(question(), HashMap::new(), &stats(), 10);
+        assert!(results.winners.is_empty());
+        assert!(!results.tied);
+        assert_eq!(results.tallies.len(), 3);
+    }
+
+    #[test]
+    fn withheld_tallies() {
+        let mut question = question();
+        question.result_visibility = ResultVisibility::WinnerOnly;
+        let mut results = QuestionResults::new(question, tallies(3, 4), &stats(), 10);
+        results.withhold_tallies();
+        assert!(results.tallies.is_empty());
+        assert_eq!(results.winners, vec!["Green".to_string()]);
+        assert!(!results.tied);
+    }
+
+    #[test]
+    fn quorum() {
+        let mut question = question();
+        question.quorum = Some(QuorumSpec::Percentage(50));
+        let results = QuestionResults::new(question.clone(), tallies(3, 4), &stats(), 14);
+        assert_eq!(results.quorum.quorum_met, Some(true));
+        let results = QuestionResults::new(question, tallies(3, 4), &stats(), 15);
+        assert_eq!(results.quorum.required, Some(8));
+        assert_eq!(results.quorum.quorum_met, Some(false));
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let results = FullResults {
+            election_id: 2,
+            election_name: "Colours".to_string(),
+            questions: vec![QuestionResults::new(
+                question(),
+                tallies(3, 4),
+                &stats(),
+                10,
+            )],
+        };
+        let value = serde_json::to_value(&results).unwrap();
+        assert_eq!(value["questions"][0]["tallies"]["Green"], json!(4));
+        assert_eq!(value["questions"][0]["winners"], json!(["Green"]));
+        assert_eq!(value["questions"][0]["tied"], json!(false));
+        assert_eq!(value["questions"][0]["question"]["id"], json!(1));
+        let parsed: FullResults = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, results);
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use crate::model::db::election::Election;
+use crate::model::{
+    api::{
+        ballot::BallotStats,
+        counter::CounterStatus,
+        election::{ArchiveExportSummary, ElectionTiming, FinalizationSummary},
+    },
+    common::election::{CandidateId, ElectionState},
+};
+
+/// Everything an operator needs to tell whether an election is healthy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectionHealth {
+    pub election_id: u32,
+    pub state: ElectionState,
+    pub timing: ElectionTiming,
+    pub suspended: bool,
+    /// Is a finalizer scheduled to audit unconfirmed ballots when the election ends?
+    pub finalizer_scheduled: bool,
+    /// What the finalizer has done, once it has run.
+    pub finalization: Option<FinalizationSummary>,
+    /// The upload of the election's signed dumps to the archive bucket, once
+    /// it has been archived, if there is a bucket.
+    pub archive_export: Option<ArchiveExportSummary>,
+    /// Health of each question, by ascending question ID.
+    pub questions: Vec<QuestionHealth>,
+    /// Descriptions of everything found wrong, empty if the election is healthy.
+    pub problems: Vec<String>,
+    /// Is nothing wrong?
+    pub healthy: bool,
+}
+
+/// The health of a single question.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionHealth {
+    /// The question's ballot counter, alongside the ballots stored for it.
+    #[serde(flatten)]
+    pub counter: CounterStatus,
+    /// Number of the question's ballots in each state.
+    pub ballots: BallotStats,
+    /// Candidates without candidate totals, in question order. Totals are
+    /// only created when an election is published, so drafts lack them all.
+    pub missing_totals: Vec<CandidateId>,
+}
+
+#[cfg(feature = "server")]
+impl ElectionHealth {
+    /// Assess the health of the given election from its questions' health and
+    /// whether it has a finalizer scheduled.
+    pub fn new(
+        election: Election,
+        finalizer_scheduled: bool,
+        questions: Vec<QuestionHealth>,
+    ) -> Self {
+        let state = election.metadata.state;
+        let mut problems = Vec::new();
+        for question in &questions {
+            if question.counter.mismatch {
+                problems.push(format!(
+                    "Ballot counter for question {} is missing or behind its ballots",
+                    question.counter.question_id
+                ));
+            }
+            if state != ElectionState::Draft && !question.missing_totals.is_empty() {
+                problems.push(format!(
+                    "Question {} has no candidate totals for {}",
+                    question.counter.question_id,
+                    question.missing_totals.join(", ")
+                ));
+            }
+        }
+
+        // Rehearsals are never finalized, and drafts are only once published.
+        let finalization = election.finalization;
+        let completed = finalization
+            .as_ref()
+            .is_some_and(|finalization| finalization.completed_at.is_some());
+        if state != ElectionState::Draft
+            && !election.metadata.rehearsal
+            && !completed
+            && !finalizer_scheduled
+        {
+            problems.push("No finalizer is scheduled".to_string());
+        }
+        if let Some(error) = finalization
+            .as_ref()
+            .filter(|_| !completed)
+            .and_then(|finalization| finalization.last_error.as_ref())
+        {
+            problems.push(format!("Finalizer failed: {error}"));
+        }
+        let archive_export = election.archive_export.map(ArchiveExportSummary::from);
+        if let Some(error) = archive_export
+            .as_ref()
+            .and_then(|export| export.last_error.as_ref())
+        {
+            problems.push(format!("Archive export failed: {error}"));
+        }
+
+        Self {
+            election_id: election.id,
+            state,
+            timing: ElectionTiming::for_metadata(&election.metadata),
+            suspended: election.metadata.suspended,
+            finalizer_scheduled,
+            finalization: finalization.map(FinalizationSummary::from),
+            archive_export,
+            questions,
+            healthy: problems.is_empty(),
+            problems,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+
+    use super::*;
+    use crate::model::db::election::{ArchiveExport, ElectionFinalization};
+
+    fn question(counter: CounterStatus, missing_totals: &[&str]) -> QuestionHealth {
+        QuestionHealth {
+            counter,
+            ballots: BallotStats::default(),
+            missing_totals: missing_totals.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn healthy() {
+        let election = Election::published_example();
+        let questions = vec![
+            question(CounterStatus::new(1, Some(4), Some(3)), &[]),
+            question(CounterStatus::new(2, Some(1), None), &[]),
+        ];
+        let health = ElectionHealth::new(election, true, questions);
+        assert!(health.healthy);
+        assert!(health.problems.is_empty());
+        assert_eq!(health.timing, ElectionTiming::Current);
+    }
+
+    #[test]
+    fn unhealthy() {
+        let election = Election::published_example();
+        let questions = vec![
+            question(CounterStatus::new(1, Some(2), Some(3)), &[]),
+            question(CounterStatus::new(2, None, None), &[]),
+            question(CounterStatus::new(3, Some(1), None), &["Red", "Blue"]),
+        ];
+        let health = ElectionHealth::new(election, false, questions);
+        assert!(!health.healthy);
+        assert_eq!(
+            health.problems,
+            [
+                "Ballot counter for question 1 is missing or behind its ballots",
+                "Ballot counter for question 2 is missing or behind its ballots",
+                "Question 3 has no candidate totals for Red, Blue",
+                "No finalizer is scheduled",
+            ]
+        );
+    }
+
+    #[test]
+    fn expected_gaps() {
+        // Drafts have no totals or finalizer yet.
+        let draft = Election::draft_example();
+        let questions = vec![question(CounterStatus::new(1, Some(1), None), &["Red"])];
+        assert!(ElectionHealth::new(draft, false, questions).healthy);
+
+        // Finalized elections no longer need a finalizer, unless it failed.
+        let mut finalized = Election::archived_example();
+        finalized.finalization = Some(ElectionFinalization {
+            completed_at: Some(DateTime::now()),
+            ..Default::default()
+        });
+        assert!(ElectionHealth::new(finalized, false, Vec::new()).healthy);
+        let mut failed = Election::archived_example();
+        failed.finalization = Some(ElectionFinalization {
+            attempts: 1,
+            last_error: Some("Database unavailable".to_string()),
+            ..Default::default()
+        });
+        let health = ElectionHealth::new(failed, true, Vec::new());
+        assert_eq!(health.problems, ["Finalizer failed: Database unavailable"]);
+
+        // Failing to upload it to the archive bucket is a problem until retried.
+        let mut unexported = Election::archived_example();
+        unexported.finalization = Some(ElectionFinalization {
+            completed_at: Some(DateTime::now()),
+            ..Default::default()
+        });
+        unexported.archive_export = Some(ArchiveExport {
+            attempts: 1,
+            last_error: Some("Access denied".to_string()),
+            ..Default::default()
+        });
+        let health = ElectionHealth::new(unexported, false, Vec::new());
+        assert_eq!(health.problems, ["Archive export failed: Access denied"]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::marker::PhantomData;
+
+use data_encoding::HEXLOWER;
+use mongodb::bson::{doc, DateTime};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome},
+    response::{self, content::RawJson, Responder},
+    serde::json::serde_json,
+    Request,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::Error,
+    logging::RequestId,
+    model::{
+        api::cbor::{prefers_cbor, vary_on_accept, Negotiated},
+        common::election::ElectionId,
+        db::idempotency::IdempotentResponse,
+        mongodb::{is_duplicate_key_error, Coll, Id},
+    },
+};
+
+/// Name of the header in which a client may send a key identifying a request,
+/// so that retries of it are answered with the original response.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The longest idempotency key accepted.
+const MAX_KEY_LENGTH: usize = 255;
+
+/// A request guard holding the request's idempotency key, if it has one.
+pub struct IdempotencyKey(Option<String>);
+
+impl IdempotencyKey {
+    /// Scope the key, if there is one, to the given voter and endpoint,
+    /// fingerprinting the request it was sent with.
+    pub fn scope<R: Serialize>(
+        &self,
+        voter_id: Id,
+        endpoint: &str,
+        election_id: ElectionId,
+        request: &R,
+    ) -> Option<IdempotencyScope> {
+        let key = self.0.as_ref()?;
+        let mut hasher = Sha256::new();
+        hasher.update(election_id.to_le_bytes());
+        hasher.update(serde_json::to_vec(request).expect("Serialisation is infallible"));
+        Some(IdempotencyScope {
+            id: format!("{}:{}:{}", voter_id, endpoint, key),
+            fingerprint: HEXLOWER.encode(&hasher.finalize()),
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one(IDEMPOTENCY_KEY_HEADER) {
+            None => Outcome::Success(IdempotencyKey(None)),
+            Some(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => {
+                Outcome::Success(IdempotencyKey(Some(key.to_string())))
+            }
+            Some(_) => Outcome::Error((
+                Status::BadRequest,
+                Error::Status(
+                    Status::BadRequest,
+                    format!(
+                        "{} header must be 1 to {} bytes long",
+                        IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+                    ),
+                ),
+            )),
+        }
+    }
+}
+
+/// Where the response to a request with an idempotency key is stored.
+pub struct IdempotencyScope {
+    id: String,
+    fingerprint: String,
+}
+
+impl IdempotencyScope {
+    /// Get the stored response to an earlier request with the same key, if any.
+    /// Fails if the earlier request was a different one.
+    pub async fn replay<T>(
+        &self,
+        responses: &Coll<IdempotentResponse>,
+        request_id: RequestId,
+    ) -> Result<Option<Idempotent<T>>, Error> {
+        let response = match responses.find_one(doc! { "_id": &self.id }, None).await? {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+        if response.fingerprint != self.fingerprint {
+            return Err(Error::Status(
+                Status::UnprocessableEntity,
+                format!(
+                    "{} was already used for a different request",
+                    IDEMPOTENCY_KEY_HEADER
+                ),
+            ));
+        }
+        info!("  req{request_id} Replaying stored response");
+        Ok(Some(Idempotent::from_body(response.body)))
+    }
+
+    /// Store the response for replay.
+    ///
+    /// This happens once the request has succeeded, so failures are only logged.
+    pub async fn store<T>(
+        self,
+        response: &Idempotent<T>,
+        responses: &Coll<IdempotentResponse>,
+        request_id: RequestId,
+    ) {
+        let stored = IdempotentResponse {
+            id: self.id,
+            fingerprint: self.fingerprint,
+            body: response.body.clone(),
+            created_at: DateTime::now(),
+        };
+        let result = responses.insert_one(stored, None).await;
+        // A concurrent retry may have got there first, which is fine.
+        if !is_duplicate_key_error(result.as_ref()) {
+            if let Err(err) = result {
+                error!("  req{request_id} Failed to store response for replay: {err}");
+            }
+        }
+    }
+}
+
+/// A JSON response which may be stored and replayed under an idempotency key.
+/// The body is serialised up front, so that a replay is byte-for-byte identical.
+/// Clients preferring CBOR get the same body re-encoded, which is just as stable.
+pub struct Idempotent<T> {
+    body: String,
+    _type: PhantomData<T>,
+}
+
+impl<T: Serialize> Idempotent<T> {
+    pub fn new(value: &T) -> Self {
+        Self::from_body(serde_json::to_string(value).expect("Serialisation is infallible"))
+    }
+}
+
+impl<T> Idempotent<T> {
+    fn from_body(body: String) -> Self {
+        Self {
+            body,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<'r, T: Serialize + DeserializeOwned> Responder<'r, 'static> for Idempotent<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if prefers_cbor(req) {
+            let value: T = serde_json::from_str(&self.body).map_err(|err| {
+                error!("Failed to decode stored response: {err}");
+                Status::InternalServerError
+            })?;
+            return Negotiated(value).respond_to(req);
+        }
+        let mut response = RawJson(self.body).respond_to(req)?;
+        vary_on_accept(&mut response);
+        Ok(response)
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use crate::model::db::maintenance::MaintenanceReport;
+
+/// API-friendly representation of a maintenance run report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceReportDesc {
+    /// When the run started.
+    pub run_at: DateTime<Utc>,
+    /// Number of voters removed.
+    pub voters_purged: u64,
+    /// Number of ballot counters removed.
+    pub counters_purged: u64,
+    /// Number of audited ballots whose secrets were removed.
+    pub ballots_redacted: u64,
+}
+
+#[cfg(feature = "server")]
+impl From<MaintenanceReport> for MaintenanceReportDesc {
+    fn from(report: MaintenanceReport) -> Self {
+        Self {
+            run_at: report.run_at,
+            voters_purged: report.voters_purged,
+            counters_purged: report.counters_purged,
+            ballots_redacted: report.ballots_redacted,
+        }
+    }
+}
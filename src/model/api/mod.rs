@@ -4,13 +4,59 @@
 //!
 //! - IDs are serialised as hex strings.
 //! - Datetimes are serialised as timestamps.
+//!
+//! Only the types needed for verification are available with just the
+//! `verification-core` feature, so that they can be built for the browser.
 
+#[cfg(feature = "client-types")]
+pub mod activity;
+#[cfg(feature = "client-types")]
 pub mod admin;
+#[cfg(feature = "client-types")]
 pub mod auth;
 pub mod ballot;
+#[cfg(feature = "client-types")]
+pub mod board;
+#[cfg(feature = "client-types")]
+pub mod bulk_archive;
+#[cfg(feature = "client-types")]
+pub mod bundle;
 pub mod candidate_totals;
+#[cfg(feature = "server")]
+pub mod cbor;
+#[cfg(feature = "client-types")]
+pub mod clock_skew;
+#[cfg(feature = "client-types")]
+pub mod consent;
+#[cfg(feature = "client-types")]
+pub mod counter;
+#[cfg(feature = "client-types")]
+pub mod db_stats;
 pub mod election;
+#[cfg(feature = "client-types")]
+pub mod finalize_preview;
+#[cfg(feature = "client-types")]
+pub mod full_results;
+#[cfg(feature = "client-types")]
+pub mod health;
+#[cfg(feature = "server")]
+pub mod idempotency;
+#[cfg(feature = "client-types")]
+pub mod maintenance;
+#[cfg(feature = "client-types")]
 pub mod otp;
+#[cfg(feature = "client-types")]
 pub mod pagination;
+#[cfg(feature = "client-types")]
+pub mod rate_limit;
 pub mod receipt;
+#[cfg(feature = "client-types")]
+pub mod rehearsal;
+#[cfg(feature = "client-types")]
+pub mod retention;
+#[cfg(feature = "client-types")]
 pub mod sms;
+#[cfg(feature = "client-types")]
+pub mod task;
+#[cfg(feature = "client-types")]
+pub mod voter;
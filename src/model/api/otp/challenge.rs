@@ -4,16 +4,18 @@ use jsonwebtoken::{
 };
 use mongodb::bson::doc;
 use rocket::{
-    http::{Cookie, SameSite, Status},
+    http::{Cookie, Status},
     outcome::{try_outcome, IntoOutcome},
     request::{self, FromRequest},
-    time::Duration,
     Request, State,
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{config::Config, model::api::sms::Sms};
+use crate::{
+    config::Config,
+    model::api::{auth::build_cookie, sms::Sms},
+};
 
 use super::code::Code;
 
@@ -41,19 +43,13 @@ impl Challenge {
             challenge: self,
             expire_at: Utc::now() + config.otp_ttl(),
         };
-        Cookie::build((
-            CHALLENGE_COOKIE,
-            jsonwebtoken::encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(config.jwt_secret()),
-            )
-            .unwrap(),
-        ))
-        .max_age(Duration::seconds(config.otp_ttl().num_seconds()))
-        .http_only(true)
-        .same_site(SameSite::Strict)
-        .build()
+        let token = jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.jwt_secret()),
+        )
+        .unwrap();
+        build_cookie(CHALLENGE_COOKIE, token, config.otp_ttl(), true, config)
     }
 
     /// Deserialize a challenge from a cookie.
@@ -1,18 +1,22 @@
+#[cfg(feature = "server")]
 use std::borrow::Cow;
 use std::convert::TryInto;
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
 use std::ops::Deref;
 use std::str::FromStr;
 
 use rand::distributions::{Distribution, Uniform};
+#[cfg(feature = "server")]
 use rocket::form::error::ErrorKind;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub const LENGTH: usize = 6;
 
-/// A one-time-password code.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A one-time-password code. This is wiped from memory when dropped, and
+/// redacted from `Debug` output.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Code {
     #[serde(with = "serialize_code")]
     code: [u8; LENGTH],
@@ -31,6 +35,20 @@ impl Code {
     }
 }
 
+impl Drop for Code {
+    fn drop(&mut self) {
+        self.code.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Code {}
+
+impl Debug for Code {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("Code([REDACTED])")
+    }
+}
+
 impl Deref for Code {
     type Target = [u8; LENGTH];
 
@@ -138,6 +156,7 @@ pub enum ParseError {
     InvalidChar(char),
 }
 
+#[cfg(feature = "server")]
 impl From<ParseError> for ErrorKind<'_> {
     fn from(err: ParseError) -> Self {
         match err {
@@ -152,3 +171,15 @@ impl From<ParseError> for ErrorKind<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_debug() {
+        let code: Code = "123456".parse().unwrap();
+        assert_eq!(format!("{:?}", code), "Code([REDACTED])");
+        assert_eq!(code.to_string(), "123456");
+    }
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "server")]
 mod challenge;
 mod code;
 
+#[cfg(feature = "server")]
 pub use challenge::{Challenge, ChallengeError, CHALLENGE_COOKIE};
 pub use code::{Code, LENGTH as CODE_LENGTH};
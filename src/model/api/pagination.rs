@@ -2,19 +2,48 @@
 // Silence these warnings.
 #![allow(clippy::needless_borrows_for_generic_args)]
 
-use rocket::form::{self, DataField, Errors, FromForm, FromFormField, ValueField};
+#[cfg(feature = "server")]
+use mongodb::bson::{doc, Document};
+#[cfg(feature = "server")]
+use rocket::{
+    form::{self, DataField, Errors, FromForm, FromFormField, ValueField},
+    http::{
+        impl_from_uri_param_identity,
+        uri::fmt::{Formatter, Ignorable, Query, UriDisplay},
+    },
+};
 use serde::{Deserialize, Serialize};
 
 /// Max page size of a single paginated response.
 pub const MAX_PAGE_SIZE: u32 = 100;
 
-/// Pagination request from a client - which page do they want, and how big?
-#[derive(Debug, UriDisplayQuery)]
+/// Page size of a paginated response if the client does not ask for one.
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Pagination request from a client - which page do they want, how big, and
+/// in what order?
+#[derive(Debug)]
+#[cfg_attr(feature = "server", derive(UriDisplayQuery))]
 pub struct PaginationRequest {
     /// Which page is this?
     pub page_num: u32,
     /// How big is each page?
     pub page_size: u32,
+    /// Which field to sort by, if any. Each listing allows only some fields.
+    pub sort_by: Option<String>,
+    /// Which way to sort, ascending by default.
+    pub order: Option<SortOrder>,
+}
+
+impl Default for PaginationRequest {
+    fn default() -> Self {
+        Self {
+            page_num: 1,
+            page_size: DEFAULT_PAGE_SIZE,
+            sort_by: None,
+            order: None,
+        }
+    }
 }
 
 impl PaginationRequest {
@@ -34,6 +63,7 @@ impl PaginationRequest {
             page_num: self.page_num,
             page_size: self.page_size(),
             total,
+            skipped: 0,
         }
     }
 
@@ -46,65 +76,167 @@ impl PaginationRequest {
     }
 }
 
+#[cfg(feature = "server")]
+impl PaginationRequest {
+    /// The requested sort as a `MongoDB` sort document, with ties broken by ID
+    /// so that pages are stable, or `None` if no sort was requested.
+    ///
+    /// Fails if the field is not one of those `allowed` for the listing, or
+    /// if an order is given without a field.
+    pub fn sort(&self, allowed: &[&str]) -> Result<Option<Document>, String> {
+        let Some(field) = &self.sort_by else {
+            if self.order.is_some() {
+                return Err("An order can only be given with a field to sort by".to_string());
+            }
+            return Ok(None);
+        };
+        if !allowed.contains(&field.as_str()) {
+            return Err(format!(
+                "Cannot sort by '{field}': expected one of {}",
+                allowed.join(", ")
+            ));
+        }
+        let direction = match self.order.unwrap_or_default() {
+            SortOrder::Asc => 1,
+            SortOrder::Desc => -1,
+        };
+        Ok(Some(doc! { field: direction, "_id": direction }))
+    }
+}
+
+/// Which way to sort a paginated listing.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField, UriDisplayQuery))]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Smallest or earliest first.
+    #[default]
+    #[cfg_attr(feature = "server", field(value = "asc"))]
+    Asc,
+    /// Largest or latest first.
+    #[cfg_attr(feature = "server", field(value = "desc"))]
+    Desc,
+}
+
 /// Context struct for parsing from requests.
+#[cfg(feature = "server")]
 pub struct Context<'f> {
-    page_num: u32,
-    page_size: u32,
+    request: PaginationRequest,
+    /// Was any pagination or sorting field given?
+    present: bool,
     errors: Errors<'f>,
 }
 
+#[cfg(feature = "server")]
+impl<'f> Context<'f> {
+    /// Store a parsed field, or its errors.
+    fn store<T>(
+        &mut self,
+        result: form::Result<'f, T>,
+        set: impl FnOnce(&mut PaginationRequest, T),
+    ) {
+        self.present = true;
+        match result {
+            Ok(value) => set(&mut self.request, value),
+            Err(errs) => self.errors.extend(errs),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
 #[rocket::async_trait]
 impl<'r> FromForm<'r> for PaginationRequest {
     type Context = Context<'r>;
 
     fn init(_opts: form::Options) -> Self::Context {
         Context {
-            page_num: 1,
-            page_size: 50,
+            request: PaginationRequest::default(),
+            present: false,
             errors: Errors::default(),
         }
     }
 
     fn push_value(ctxt: &mut Self::Context, field: ValueField<'r>) {
-        if field.name == "page_num" {
-            match u32::from_value(field) {
-                Ok(page_num) => ctxt.page_num = page_num,
-                Err(errs) => ctxt.errors.extend(errs),
-            }
-        } else if field.name == "page_size" {
-            match u32::from_value(field) {
-                Ok(page_size) => ctxt.page_size = page_size,
-                Err(errs) => ctxt.errors.extend(errs),
-            }
+        match field.name.key_lossy().as_str() {
+            "page_num" => ctxt.store(u32::from_value(field), |req, n| req.page_num = n),
+            "page_size" => ctxt.store(u32::from_value(field), |req, n| req.page_size = n),
+            "sort_by" => ctxt.store(String::from_value(field), |req, f| req.sort_by = Some(f)),
+            "order" => ctxt.store(SortOrder::from_value(field), |req, o| req.order = Some(o)),
+            _ => {}
         }
     }
 
     async fn push_data(ctxt: &mut Self::Context, field: DataField<'r, '_>) {
-        if field.name == "page_num" {
-            match u32::from_data(field).await {
-                Ok(page_num) => ctxt.page_num = page_num,
-                Err(errs) => ctxt.errors.extend(errs),
-            }
-        } else if field.name == "page_size" {
-            match u32::from_data(field).await {
-                Ok(page_size) => ctxt.page_size = page_size,
-                Err(errs) => ctxt.errors.extend(errs),
-            }
+        match field.name.key_lossy().as_str() {
+            "page_num" => ctxt.store(u32::from_data(field).await, |req, n| req.page_num = n),
+            "page_size" => ctxt.store(u32::from_data(field).await, |req, n| req.page_size = n),
+            "sort_by" => ctxt.store(String::from_data(field).await, |req, f| {
+                req.sort_by = Some(f)
+            }),
+            "order" => ctxt.store(SortOrder::from_data(field).await, |req, o| {
+                req.order = Some(o)
+            }),
+            _ => {}
         }
     }
 
     fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
         if ctxt.errors.is_empty() {
-            Ok(Self {
-                page_num: ctxt.page_num,
-                page_size: ctxt.page_size,
-            })
+            Ok(ctxt.request)
         } else {
             Err(ctxt.errors)
         }
     }
 }
 
+/// Pagination request for a listing that predates pagination, and so lists
+/// every item, unpaginated and unsorted, unless any pagination or sorting
+/// field is given.
+#[derive(Debug, Default)]
+pub struct OptionalPagination(pub Option<PaginationRequest>);
+
+#[cfg(feature = "server")]
+#[rocket::async_trait]
+impl<'r> FromForm<'r> for OptionalPagination {
+    type Context = Context<'r>;
+
+    fn init(opts: form::Options) -> Self::Context {
+        PaginationRequest::init(opts)
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'r>) {
+        PaginationRequest::push_value(ctxt, field)
+    }
+
+    async fn push_data(ctxt: &mut Self::Context, field: DataField<'r, '_>) {
+        PaginationRequest::push_data(ctxt, field).await
+    }
+
+    fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
+        if ctxt.present {
+            PaginationRequest::finalize(ctxt).map(|request| Self(Some(request)))
+        } else {
+            Ok(Self(None))
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl UriDisplay<Query> for OptionalPagination {
+    fn fmt(&self, f: &mut Formatter<'_, Query>) -> std::fmt::Result {
+        match &self.0 {
+            Some(request) => UriDisplay::fmt(request, f),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl Ignorable<Query> for OptionalPagination {}
+
+#[cfg(feature = "server")]
+impl_from_uri_param_identity!([Query] OptionalPagination);
+
 /// Pagination response to a client - which page did you actually get, how big
 /// is it actually, and how many items are there in total?
 #[derive(Debug, Serialize, Deserialize)]
@@ -115,6 +247,11 @@ pub struct PaginationResponse {
     pub page_size: u32,
     /// How many items are there in total?
     pub total: u64,
+    /// How many items on this page were left out because they could not be
+    /// interpreted? Only ballot listings leave any out, for ballots in an
+    /// unrecognised state; everything else always has 0.
+    #[serde(default)]
+    pub skipped: u64,
 }
 
 /// A paginated vector of T, with pagination metadata.
@@ -123,3 +260,12 @@ pub struct Paginated<T> {
     pub items: Vec<T>,
     pub pagination: PaginationResponse,
 }
+
+/// A listing that is only paginated if the client asked for it; see
+/// [`OptionalPagination`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MaybePaginated<T> {
+    Paginated(Paginated<T>),
+    All(Vec<T>),
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// Recent rate limit rejections, by limiter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitSummary {
+    /// Length of the summarised window, ending now, in seconds.
+    pub window_secs: u64,
+    /// Limiters that rejected requests during the window, by name.
+    /// Limiters that rejected nothing are omitted.
+    pub limiters: Vec<LimiterSummary>,
+}
+
+/// Recent rejections by a single rate limiter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimiterSummary {
+    /// Name of the limiter.
+    pub limiter: String,
+    /// Number of requests it rejected.
+    pub rejections: u64,
+    /// The keys with the most rejections, most first.
+    pub top_keys: Vec<KeyRejections>,
+}
+
+/// Rejections of requests with a single rate limiter key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRejections {
+    /// Hex SHA-256 hash of the key.
+    pub key_hash: String,
+    /// Number of requests with the key that were rejected.
+    pub rejections: u64,
+}
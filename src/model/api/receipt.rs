@@ -1,26 +1,159 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
 use data_encoding::BASE32;
-use dre_ip::{DreipGroup as DreipGroupTrait, DreipPrivateKey};
+use dre_ip::DreipGroup as DreipGroupTrait;
+#[cfg(feature = "server")]
+use dre_ip::DreipPrivateKey;
+#[cfg(feature = "server")]
+use hmac::Mac;
+#[cfg(feature = "server")]
+use mongodb::bson::Bson;
+#[cfg(feature = "server")]
+use rocket::{FromFormField, UriDisplayQuery};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::model::{
-    common::{
-        ballot::{Audited, BallotCrypto, BallotState, Confirmed, Unconfirmed},
-        election::DreipGroup,
+use crate::model::common::{
+    ballot::{
+        Audited, AuditedRedacted, BallotCrypto, BallotId, BallotState, Confirmed, Unconfirmed,
     },
+    election::{DreipGroup, ElectionId, QuestionId},
+};
+#[cfg(feature = "server")]
+use crate::model::{
     db::{
         ballot::{AnyBallot, BallotCore},
         election::Election,
+        voter::HmacSha256,
     },
+    mongodb::Id,
 };
 
 pub type Signature = <DreipGroup as DreipGroupTrait>::Signature;
 
 pub const CONFIRMATION_CODE_LENGTH: usize = 50;
 
+/// The number of characters in a recall code, which carry 80 bits.
+pub const RECALL_CODE_LENGTH: usize = 16;
+
+/// Label prefixed to the input of every recall code's HMAC, so that recall
+/// codes never coincide with other HMACs keyed by the same secret.
+#[cfg(feature = "server")]
+const RECALL_CODE_DOMAIN: &[u8] = b"dreip-backend recall code";
+
+/// The number of words in a confirmation phrase.
+pub const CONFIRMATION_PHRASE_LENGTH: usize = 4;
+
+/// The words that confirmation phrases are made of, one per line: the 2048-word
+/// BIP-39 English list, in which every word is identified by its first four letters.
+const PHRASE_WORDS: &str = include_str!("phrase_words.txt");
+
+/// The receipt schema version produced by this server.
+/// This must be bumped whenever the byte layout of the signed message changes,
+/// so that verifiers can refuse receipts they do not understand.
+pub const RECEIPT_SCHEMA_VERSION: u8 = 1;
+
+/// The schema version of receipts produced before versioning was introduced.
+pub fn default_schema_version() -> u8 {
+    1
+}
+
+/// The message signed by the election key to produce a receipt's signature.
+///
+/// For a full receipt, this is the concatenation of:
+/// 1. the ballot's crypto, as given by [`BallotCrypto::to_bytes`], including
+///    the secrets only if the receipt reveals them;
+/// 2. the ballot ID, election ID, and question ID, each as 4 little-endian bytes;
+/// 3. the confirmation code, as ASCII;
+/// 4. the ballot state, as the ASCII string `Unconfirmed`, `Audited`,
+///    `AuditedRedacted`, or `Confirmed`;
+/// 5. the state's extra data: the UTF-8 candidate ID for audited ballots,
+///    redacted or not, and nothing otherwise.
+///
+/// An [`UnconfirmedStub`] has no crypto, so only parts 2 to 4 are signed.
+/// Any change to this layout must bump [`RECEIPT_SCHEMA_VERSION`].
+///
+/// A receipt's `state_changed_at` is deliberately not signed: it is only
+/// informational, and receipts issued before it was recorded must still verify.
+/// Nor is its `confirmation_phrase`, which is derived from the signed
+/// confirmation code by [`confirmation_phrase`], and checked against it by
+/// verification instead.
+pub struct ReceiptMessage;
+
+impl ReceiptMessage {
+    /// Encode the signed message of a full receipt.
+    pub fn encode<S>(
+        crypto: &BallotCrypto<S::ExposedSecrets>,
+        ballot_id: BallotId,
+        election_id: ElectionId,
+        question_id: QuestionId,
+        confirmation_code: &str,
+        state: S,
+        state_data: &S::ReceiptData,
+    ) -> Vec<u8>
+    where
+        S: BallotState,
+        for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
+        for<'a> &'a <S as BallotState>::ReceiptData: Into<Vec<u8>>,
+    {
+        let mut msg = crypto.to_bytes();
+        Self::extend(
+            &mut msg,
+            ballot_id,
+            election_id,
+            question_id,
+            confirmation_code,
+            state,
+        );
+        msg.extend(Into::<Vec<u8>>::into(state_data));
+        msg
+    }
+
+    /// Encode the signed message of an unconfirmed ballot stub.
+    pub fn encode_stub(
+        ballot_id: BallotId,
+        election_id: ElectionId,
+        question_id: QuestionId,
+        confirmation_code: &str,
+        state: Unconfirmed,
+    ) -> Vec<u8> {
+        let mut msg = Vec::new();
+        Self::extend(
+            &mut msg,
+            ballot_id,
+            election_id,
+            question_id,
+            confirmation_code,
+            state,
+        );
+        msg
+    }
+
+    /// Append the parts common to full receipts and stubs.
+    fn extend(
+        msg: &mut Vec<u8>,
+        ballot_id: BallotId,
+        election_id: ElectionId,
+        question_id: QuestionId,
+        confirmation_code: &str,
+        state: impl AsRef<[u8]>,
+    ) {
+        msg.extend(ballot_id.to_le_bytes());
+        msg.extend(election_id.to_le_bytes());
+        msg.extend(question_id.to_le_bytes());
+        msg.extend(confirmation_code.as_bytes());
+        msg.extend(state.as_ref());
+    }
+}
+
 /// A receipt. Audited receipts will contain the secret values; any other type will not.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Receipt<S: BallotState> {
+    /// The receipt schema version.
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u8,
     /// The cryptographic data.
     #[serde(flatten)]
     pub crypto: BallotCrypto<S::ExposedSecrets>,
@@ -33,16 +166,51 @@ pub struct Receipt<S: BallotState> {
     /// A hash of the IDs and the public crypto elements,
     /// encoded in base32 and truncated to 50 characters.
     pub confirmation_code: String,
+    /// The confirmation code as a short phrase, for voters to compare.
+    /// Not covered by the signature, but checked against the code by verification;
+    /// empty for receipts issued before this existed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub confirmation_phrase: String,
     /// The current state of the ballot.
     pub state: S,
     /// Extra data specific to this ballot state.
     #[serde(flatten)]
     pub state_data: S::ReceiptData,
+    /// When the ballot was audited or confirmed, if it has been and this was
+    /// recorded. Not covered by the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_changed_at: Option<DateTime<Utc>>,
+    /// A short secret which the voter may give instead of the signature to
+    /// audit or confirm the ballot. Only the receipts returned to the voter
+    /// casting the ballot have this; it is never published, and is not
+    /// covered by the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recall_code: Option<String>,
     /// The signature.
     #[serde(with = "dre_ip::group::serde_bytestring")]
     pub signature: Signature,
 }
 
+impl<S: BallotState> Receipt<S>
+where
+    for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
+    for<'a> &'a <S as BallotState>::ReceiptData: Into<Vec<u8>>,
+{
+    /// The message this receipt's signature is over.
+    pub fn message(&self) -> Vec<u8> {
+        ReceiptMessage::encode(
+            &self.crypto,
+            self.ballot_id,
+            self.election_id,
+            self.question_id,
+            &self.confirmation_code,
+            self.state,
+            &self.state_data,
+        )
+    }
+}
+
+#[cfg(feature = "server")]
 impl<S: BallotState> Receipt<S>
 where
     for<'a> &'a <S as BallotState>::ExposedSecrets: Into<Vec<u8>>,
@@ -51,33 +219,40 @@ where
     /// Construct a receipt from the given ballot.
     pub fn from_ballot(ballot: BallotCore<S>, election: &Election) -> Self {
         // Get any extra data.
-        let state_data = S::receipt_data(&ballot.crypto);
+        let state_data = S::receipt_data(&ballot.crypto, ballot.state_data.as_ref());
 
         // Calculate the confirmation code.
         let confirmation_code = calc_confirmation_code(&ballot);
+        let state_changed_at = ballot.state_changed_at.map(|time| time.to_chrono());
 
         // Convert the ballot from internal to receipt representation.
         let crypto = S::internal_to_receipt(ballot.crypto);
 
         // Sign the receipt.
-        let mut msg = crypto.to_bytes();
-        msg.extend(ballot.ballot_id.to_le_bytes());
-        msg.extend(ballot.election_id.to_le_bytes());
-        msg.extend(ballot.question_id.to_le_bytes());
-        msg.extend(confirmation_code.as_bytes());
-        msg.extend(ballot.state.as_ref());
-        msg.extend(Into::<Vec<u8>>::into(&state_data));
-        let signature = election.crypto.private_key.sign(&msg);
+        let msg = ReceiptMessage::encode(
+            &crypto,
+            ballot.ballot_id,
+            ballot.election_id,
+            ballot.question_id,
+            &confirmation_code,
+            ballot.state,
+            &state_data,
+        );
+        let signature = election.crypto.private_key.expose().sign(&msg);
 
         // Construct the result.
         Self {
+            schema_version: RECEIPT_SCHEMA_VERSION,
             crypto,
             ballot_id: ballot.ballot_id,
             election_id: ballot.election_id,
             question_id: ballot.question_id,
+            confirmation_phrase: confirmation_phrase(&confirmation_code),
             confirmation_code,
             state: ballot.state,
             state_data,
+            state_changed_at,
+            recall_code: None,
             signature,
         }
     }
@@ -88,6 +263,9 @@ where
 /// but we can still show the IDs and confirmation code on the bulletin board.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct UnconfirmedStub {
+    /// The receipt schema version.
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u8,
     /// Ballot ID.
     pub ballot_id: u32,
     /// Election ID.
@@ -97,6 +275,11 @@ pub struct UnconfirmedStub {
     /// A hash of the IDs and the public crypto elements,
     /// encoded in base32 and truncated to 50 characters.
     pub confirmation_code: String,
+    /// The confirmation code as a short phrase, for voters to compare.
+    /// Not covered by the signature, but checked against the code by verification;
+    /// empty for stubs issued before this existed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub confirmation_phrase: String,
     /// The current state of the ballot.
     pub state: Unconfirmed,
     /// The signature.
@@ -105,24 +288,39 @@ pub struct UnconfirmedStub {
 }
 
 impl UnconfirmedStub {
+    /// The message this stub's signature is over.
+    pub fn message(&self) -> Vec<u8> {
+        ReceiptMessage::encode_stub(
+            self.ballot_id,
+            self.election_id,
+            self.question_id,
+            &self.confirmation_code,
+            self.state,
+        )
+    }
+
+    #[cfg(feature = "server")]
     pub fn from_ballot(ballot: BallotCore<Unconfirmed>, election: &Election) -> Self {
         // Calculate the confirmation code.
         let confirmation_code = calc_confirmation_code(&ballot);
 
         // Sign the receipt.
-        let mut msg = Vec::new();
-        msg.extend(ballot.ballot_id.to_le_bytes());
-        msg.extend(ballot.election_id.to_le_bytes());
-        msg.extend(ballot.question_id.to_le_bytes());
-        msg.extend(confirmation_code.as_bytes());
-        msg.extend(ballot.state.as_ref());
-        let signature = election.crypto.private_key.sign(&msg);
+        let msg = ReceiptMessage::encode_stub(
+            ballot.ballot_id,
+            ballot.election_id,
+            ballot.question_id,
+            &confirmation_code,
+            ballot.state,
+        );
+        let signature = election.crypto.private_key.expose().sign(&msg);
 
         // Construct the result.
         Self {
+            schema_version: RECEIPT_SCHEMA_VERSION,
             ballot_id: ballot.ballot_id,
             election_id: ballot.election_id,
             question_id: ballot.question_id,
+            confirmation_phrase: confirmation_phrase(&confirmation_code),
             confirmation_code,
             state: ballot.state,
             signature,
@@ -131,34 +329,85 @@ impl UnconfirmedStub {
 }
 
 /// A receipt that is suitable for public display.
-/// With the untagged representation, `Receipt<Audited>` and
-/// `Receipt<Confirmed>` can both directly deserialize to this type.
+/// With the untagged representation, `Receipt<Audited>`,
+/// `Receipt<AuditedRedacted>` and `Receipt<Confirmed>` can all directly
+/// deserialize to this type.
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum PublicReceipt {
     Unconfirmed(UnconfirmedStub),
     Audited(Receipt<Audited>),
+    AuditedRedacted(Receipt<AuditedRedacted>),
     Confirmed(Receipt<Confirmed>),
 }
 
 impl PublicReceipt {
-    pub fn from_ballot(ballot: AnyBallot, election: &Election) -> Self {
-        match ballot {
+    /// Construct a public receipt from the given ballot.
+    /// Returns `None` if the ballot is in an unrecognised state.
+    #[cfg(feature = "server")]
+    pub fn from_ballot(ballot: AnyBallot, election: &Election) -> Option<Self> {
+        let receipt = match ballot {
             AnyBallot::Unconfirmed(ballot) => {
                 PublicReceipt::Unconfirmed(UnconfirmedStub::from_ballot(ballot.ballot, election))
             }
             AnyBallot::Audited(ballot) => {
                 PublicReceipt::Audited(Receipt::from_ballot(ballot.ballot, election))
             }
+            AnyBallot::AuditedRedacted(ballot) => {
+                PublicReceipt::AuditedRedacted(Receipt::from_ballot(ballot.ballot, election))
+            }
             AnyBallot::Confirmed(ballot) => {
                 PublicReceipt::Confirmed(Receipt::from_ballot(ballot.ballot, election))
             }
+            AnyBallot::Unknown(_) => return None,
+        };
+        Some(receipt)
+    }
+
+    /// The receipt schema version.
+    pub fn schema_version(&self) -> u8 {
+        match self {
+            PublicReceipt::Unconfirmed(stub) => stub.schema_version,
+            PublicReceipt::Audited(receipt) => receipt.schema_version,
+            PublicReceipt::AuditedRedacted(receipt) => receipt.schema_version,
+            PublicReceipt::Confirmed(receipt) => receipt.schema_version,
+        }
+    }
+
+    /// The ballot ID.
+    pub fn ballot_id(&self) -> u32 {
+        match self {
+            PublicReceipt::Unconfirmed(stub) => stub.ballot_id,
+            PublicReceipt::Audited(receipt) => receipt.ballot_id,
+            PublicReceipt::AuditedRedacted(receipt) => receipt.ballot_id,
+            PublicReceipt::Confirmed(receipt) => receipt.ballot_id,
+        }
+    }
+}
+
+/// A ballot state by which public receipts can be filtered.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField, UriDisplayQuery))]
+pub enum ReceiptState {
+    Unconfirmed,
+    Audited,
+    Confirmed,
+}
+
+#[cfg(feature = "server")]
+impl From<ReceiptState> for Bson {
+    fn from(state: ReceiptState) -> Self {
+        match state {
+            ReceiptState::Unconfirmed => Unconfirmed.into(),
+            ReceiptState::Audited => Audited.into(),
+            ReceiptState::Confirmed => Confirmed.into(),
         }
     }
 }
 
 /// Calculate the confirmation code.
-fn calc_confirmation_code<S: BallotState>(ballot: &BallotCore<S>) -> String {
+#[cfg(feature = "server")]
+pub fn calc_confirmation_code<S: BallotState>(ballot: &BallotCore<S>) -> String {
     let mut hasher: Sha256 = Sha256::new();
     hasher.update(S::remove_internal_secrets(&ballot.crypto).to_bytes());
     hasher.update(ballot.ballot_id.to_le_bytes());
@@ -168,3 +417,313 @@ fn calc_confirmation_code<S: BallotState>(ballot: &BallotCore<S>) -> String {
     confirmation_code.truncate(CONFIRMATION_CODE_LENGTH);
     confirmation_code
 }
+
+/// Calculate the recall code by which the given voter may recall a ballot.
+///
+/// This is an HMAC of the ballot's IDs and the voter's ID, keyed by the HMAC
+/// secret, so only the server can derive it, and it only works for the voter
+/// who cast the ballot. Unlike the confirmation code, it is never published,
+/// so knowing it shows ownership of the ballot.
+#[cfg(feature = "server")]
+pub fn calc_recall_code(
+    secret: &[u8],
+    voter_id: Id,
+    election_id: ElectionId,
+    question_id: QuestionId,
+    ballot_id: BallotId,
+) -> String {
+    let mut hmac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    hmac.update(RECALL_CODE_DOMAIN);
+    hmac.update(&voter_id.to_bytes());
+    hmac.update(&election_id.to_le_bytes());
+    hmac.update(&question_id.to_le_bytes());
+    hmac.update(&ballot_id.to_le_bytes());
+    let mut recall_code = BASE32.encode(&hmac.finalize().into_bytes());
+    recall_code.truncate(RECALL_CODE_LENGTH);
+    recall_code
+}
+
+/// The words that confirmation phrases are made of, in order.
+fn phrase_words() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| PHRASE_WORDS.lines().collect())
+}
+
+/// Derive the short phrase by which voters can recognise a confirmation code.
+///
+/// The first 44 bits of the SHA-256 hash of the code's ASCII bytes are taken
+/// 11 bits at a time, most significant first, each picking a word from the
+/// word list; the words are joined with hyphens. With only 44 bits, different
+/// ballots can share a phrase, so it only helps to find a ballot, and the code
+/// remains what is signed.
+pub fn confirmation_phrase(confirmation_code: &str) -> String {
+    let hash = Sha256::digest(confirmation_code.as_bytes());
+    let mut bits = u64::from_be_bytes(hash[..8].try_into().unwrap()); // Always 32 bytes.
+    let words = phrase_words();
+    let mut phrase = Vec::with_capacity(CONFIRMATION_PHRASE_LENGTH);
+    for _ in 0..CONFIRMATION_PHRASE_LENGTH {
+        phrase.push(words[(bits >> 53) as usize]);
+        bits <<= 11;
+    }
+    phrase.join("-")
+}
+
+/// Normalise a phrase typed by a voter to the form given by
+/// [`confirmation_phrase`]: case is ignored, and the words may be separated by
+/// hyphens or whitespace. Returns `None` if it is not a phrase at all.
+pub fn normalize_confirmation_phrase(phrase: &str) -> Option<String> {
+    let words = phrase_words();
+    let phrase: Vec<String> = phrase
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let valid = phrase.len() == CONFIRMATION_PHRASE_LENGTH
+        && phrase
+            .iter()
+            .all(|word| words.binary_search(&word.as_str()).is_ok());
+    valid.then(|| phrase.join("-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use dre_ip::{DreipPublicKey, NoSecrets};
+    use rocket::serde::json::serde_json;
+
+    use crate::model::{
+        api::{cbor::to_cbor, election::ElectionResults},
+        common::ballot::RedactedExtraData,
+    };
+
+    use super::*;
+
+    /// The message layout after the crypto, for ballot 0x04030201 of election 5,
+    /// question 6, with confirmation code "CODE".
+    fn expected_tail(state: &str, state_data: &str) -> Vec<u8> {
+        let mut tail = vec![1, 2, 3, 4, 5, 0, 0, 0, 6, 0, 0, 0];
+        tail.extend(b"CODE");
+        tail.extend(state.as_bytes());
+        tail.extend(state_data.as_bytes());
+        tail
+    }
+
+    fn load_receipts() -> ElectionResults {
+        let raw = std::fs::read_to_string("example_dumps/election.json").unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    fn relabel<S: BallotState>(receipt: &mut Receipt<S>) {
+        receipt.ballot_id = 0x04030201;
+        receipt.election_id = 5;
+        receipt.question_id = 6;
+        receipt.confirmation_code = "CODE".to_string();
+        // The phrase is not signed, so has no effect on the layout.
+        receipt.confirmation_phrase = "unsigned".to_string();
+    }
+
+    #[test]
+    fn unconfirmed_stub_layout() {
+        let msg = ReceiptMessage::encode_stub(0x04030201, 5, 6, "CODE", Unconfirmed);
+        assert_eq!(msg, expected_tail("Unconfirmed", ""));
+        assert_eq!(
+            msg,
+            b"\x01\x02\x03\x04\x05\x00\x00\x00\x06\x00\x00\x00CODEUnconfirmed"
+        );
+    }
+
+    #[test]
+    fn unconfirmed_layout() {
+        // Unconfirmed receipts reveal exactly the same crypto as confirmed ones.
+        let results = load_receipts();
+        let confirmed = results.confirmed.values().next().unwrap();
+        let msg = ReceiptMessage::encode(
+            &confirmed.crypto,
+            0x04030201,
+            5,
+            6,
+            "CODE",
+            Unconfirmed,
+            &NoSecrets(()),
+        );
+        let mut expected = confirmed.crypto.to_bytes();
+        expected.extend(expected_tail("Unconfirmed", ""));
+        assert_eq!(msg, expected);
+    }
+
+    #[test]
+    fn audited_layout() {
+        let results = load_receipts();
+        let mut receipt = results.audited.values().next().unwrap().clone();
+        relabel(&mut receipt);
+        receipt.state_data.candidate = "Chris Riches".to_string();
+        let mut expected = receipt.crypto.to_bytes();
+        expected.extend(expected_tail("Audited", "Chris Riches"));
+        assert_eq!(receipt.message(), expected);
+    }
+
+    #[test]
+    fn redacted_layout() {
+        // Redacted receipts sign the public crypto under their own state name.
+        let results = load_receipts();
+        let audited = results.audited.values().next().unwrap();
+        let mut receipt = Receipt {
+            schema_version: RECEIPT_SCHEMA_VERSION,
+            crypto: Audited::remove_external_secrets(&audited.crypto),
+            ballot_id: audited.ballot_id,
+            election_id: audited.election_id,
+            question_id: audited.question_id,
+            confirmation_code: audited.confirmation_code.clone(),
+            confirmation_phrase: String::new(),
+            state: AuditedRedacted,
+            state_data: RedactedExtraData {
+                candidate: "Chris Riches".to_string(),
+                redacted: true,
+            },
+            state_changed_at: None,
+            recall_code: None,
+            signature: audited.signature.clone(),
+        };
+        relabel(&mut receipt);
+        let mut expected = receipt.crypto.to_bytes();
+        expected.extend(expected_tail("AuditedRedacted", "Chris Riches"));
+        assert_eq!(receipt.message(), expected);
+
+        // It is published as audited, but still reads back as redacted.
+        let json = serde_json::to_value(PublicReceipt::AuditedRedacted(receipt.clone())).unwrap();
+        assert_eq!(json["state"], "Audited");
+        assert_eq!(json["redacted"], true);
+        let parsed: PublicReceipt = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, PublicReceipt::AuditedRedacted(receipt));
+    }
+
+    #[test]
+    fn confirmed_layout() {
+        let results = load_receipts();
+        let mut receipt = results.confirmed.values().next().unwrap().clone();
+        relabel(&mut receipt);
+        let mut expected = receipt.crypto.to_bytes();
+        expected.extend(expected_tail("Confirmed", ""));
+        assert_eq!(receipt.message(), expected);
+    }
+
+    #[test]
+    fn existing_signatures() {
+        // Receipts issued before the encoder was extracted must still verify.
+        let results = load_receipts();
+        for receipt in results.audited.values() {
+            assert!(results
+                .election
+                .public_key
+                .verify(&receipt.message(), &receipt.signature));
+        }
+        for receipt in results.confirmed.values() {
+            assert!(results
+                .election
+                .public_key
+                .verify(&receipt.message(), &receipt.signature));
+        }
+    }
+
+    #[test]
+    fn fixture_known_answers() {
+        // Digests of whole signed messages, computed outside this crate from the
+        // fixture's JSON, and checked there against the fixture's signatures.
+        let digest = |msg: Vec<u8>| data_encoding::HEXLOWER.encode(&Sha256::digest(msg));
+        let results = load_receipts();
+        let audited = &results.audited[&11];
+        assert_eq!(audited.message().len(), 719);
+        assert_eq!(
+            digest(audited.message()),
+            "02eccf42000a43519d7388f19f6b00fe079a307c0d5e33d91707e9478e1ffa3b"
+        );
+        let confirmed = &results.confirmed[&4];
+        assert_eq!(confirmed.message().len(), 581);
+        assert_eq!(
+            digest(confirmed.message()),
+            "0e386d59b0f5e9a5b439406e3ba4bdef9c361bdb4b04982da990f4ef3f69def3"
+        );
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        // CBOR receipts decode to exactly their JSON twins, so verify identically,
+        // while being smaller.
+        let results = load_receipts();
+        let receipts = results
+            .audited
+            .values()
+            .cloned()
+            .map(PublicReceipt::Audited)
+            .chain(
+                results
+                    .confirmed
+                    .values()
+                    .cloned()
+                    .map(PublicReceipt::Confirmed),
+            );
+        for receipt in receipts {
+            let json = serde_json::to_vec(&receipt).unwrap();
+            let cbor = to_cbor(&receipt);
+            assert!(cbor.len() < json.len());
+            let from_json: PublicReceipt = serde_json::from_slice(&json).unwrap();
+            let from_cbor: PublicReceipt = ciborium::from_reader(&cbor[..]).unwrap();
+            assert_eq!(from_cbor, from_json);
+            let (message, signature) = match &from_cbor {
+                PublicReceipt::Audited(receipt) => (receipt.message(), &receipt.signature),
+                PublicReceipt::Confirmed(receipt) => (receipt.message(), &receipt.signature),
+                PublicReceipt::Unconfirmed(_) | PublicReceipt::AuditedRedacted(_) => {
+                    unreachable!()
+                }
+            };
+            assert!(results.election.public_key.verify(&message, signature));
+        }
+
+        // The same goes for a whole dump.
+        let json = serde_json::to_vec(&results).unwrap();
+        let cbor = to_cbor(&results);
+        assert!(cbor.len() < json.len());
+        let from_cbor: ElectionResults = ciborium::from_reader(&cbor[..]).unwrap();
+        assert_eq!(from_cbor, results);
+        assert_eq!(from_cbor.verify(), Ok(()));
+    }
+
+    #[test]
+    fn confirmation_phrases() {
+        assert_eq!(phrase_words().len(), 2048);
+        assert!(phrase_words().windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Known answers.
+        assert_eq!(confirmation_phrase(""), "together-mail-awful-cradle");
+        assert_eq!(
+            confirmation_phrase("CODE"),
+            "amateur-exchange-unfold-fashion"
+        );
+        assert_eq!(
+            confirmation_phrase("ABCDEFGHIJKLMNOPQRSTUVWXYZ234567ABCDEFGHIJKLMNOPQR"),
+            "minor-caught-inmate-company"
+        );
+
+        // Typed phrases are normalised.
+        let phrase = Some("amateur-exchange-unfold-fashion".to_string());
+        assert_eq!(
+            normalize_confirmation_phrase("amateur-exchange-unfold-fashion"),
+            phrase
+        );
+        assert_eq!(
+            normalize_confirmation_phrase("  Amateur exchange-UNFOLD   fashion "),
+            phrase
+        );
+        assert_eq!(
+            normalize_confirmation_phrase("amateur-exchange-unfold"),
+            None
+        );
+        assert_eq!(
+            normalize_confirmation_phrase("amateur-exchange-unfold-fashion-zoo"),
+            None
+        );
+        assert_eq!(
+            normalize_confirmation_phrase("amateur-exchange-unfold-fashions"),
+            None
+        );
+    }
+}
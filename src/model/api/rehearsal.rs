@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::election::{ElectionId, QuestionId};
+#[cfg(feature = "server")]
+use crate::model::db::rehearsal::RehearsalReport;
+
+/// A request to rehearse an election with synthetic voters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RehearsalSpec {
+    /// Number of synthetic voters.
+    pub voters: u32,
+    /// Chance that each synthetic ballot is confirmed rather than audited.
+    pub confirm_probability: f32,
+}
+
+/// The outcome of verifying one question of a rehearsal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionVerification {
+    /// Question ID.
+    pub question_id: QuestionId,
+    /// Did the question's results verify?
+    pub verified: bool,
+    /// Why verification failed, if it did.
+    pub error: Option<String>,
+}
+
+/// API-friendly representation of a rehearsal report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RehearsalReportDesc {
+    /// ID of the election that was rehearsed.
+    pub election_id: ElectionId,
+    /// ID of the throwaway rehearsal election.
+    pub rehearsal_election_id: ElectionId,
+    /// Number of synthetic voters.
+    pub voters: u32,
+    /// Chance that each synthetic ballot was confirmed rather than audited.
+    pub confirm_probability: f32,
+    /// When the rehearsal started.
+    pub started_at: DateTime<Utc>,
+    /// When the rehearsal finished, if it has.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// When the rehearsal election is deleted.
+    pub expires_at: DateTime<Utc>,
+    /// Number of synthetic ballots confirmed.
+    pub ballots_confirmed: u32,
+    /// Number of synthetic ballots audited.
+    pub ballots_audited: u32,
+    /// The outcome of verifying each question, once finished.
+    pub questions: Vec<QuestionVerification>,
+    /// Did the rehearsal finish, with every question verifying?
+    pub verified: bool,
+    /// Why the rehearsal stopped early, if it did.
+    pub error: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl From<RehearsalReport> for RehearsalReportDesc {
+    fn from(report: RehearsalReport) -> Self {
+        Self {
+            election_id: report.election_id,
+            rehearsal_election_id: report.rehearsal_election_id,
+            voters: report.voters,
+            confirm_probability: report.confirm_probability,
+            started_at: report.started_at,
+            finished_at: report.finished_at.map(|time| time.to_chrono()),
+            expires_at: report.expires_at,
+            ballots_confirmed: report.ballots_confirmed,
+            ballots_audited: report.ballots_audited,
+            questions: report.questions,
+            verified: report.verified,
+            error: report.error,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::election::{ElectionId, ElectionState, QuestionId};
+#[cfg(feature = "server")]
+use crate::model::db::election::Election;
+
+/// Whether an election's data has been exported, and so whether it may be
+/// deleted without acknowledging that it was not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub election_id: ElectionId,
+    pub state: ElectionState,
+    /// Could the election be deleted right now without acknowledging that
+    /// its data was never exported? Only drafts, and archived elections with
+    /// a signed dump of every question, can be.
+    pub deletable: bool,
+    /// When a signed dump of each question was last served since the
+    /// election was archived, ordered by question ID.
+    pub questions: Vec<QuestionRetention>,
+}
+
+/// When a question's data was last exported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionRetention {
+    pub question_id: QuestionId,
+    /// When a signed dump of the question was last served, if ever.
+    pub last_dump_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "server")]
+impl From<&Election> for RetentionReport {
+    fn from(election: &Election) -> Self {
+        let mut questions = election
+            .questions
+            .values()
+            .map(|question| QuestionRetention {
+                question_id: question.id,
+                last_dump_at: question.last_dump_at.map(|time| time.to_chrono()),
+            })
+            .collect::<Vec<_>>();
+        questions.sort_unstable_by_key(|question| question.question_id);
+        Self {
+            election_id: election.id,
+            state: election.metadata.state,
+            deletable: election.metadata.state == ElectionState::Draft || election.is_exported(),
+            questions,
+        }
+    }
+}
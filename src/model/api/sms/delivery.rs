@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// What became of the OTP challenges sent to voters' numbers. Each number is
+/// counted once, by its last challenge.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmsDeliverySummary {
+    /// Numbers whose last challenge was sent, but whose code was not verified.
+    pub sent: u64,
+    /// Numbers whose last challenge's code was verified.
+    pub delivered: u64,
+    /// Numbers that have opted out of receiving SMS.
+    pub opted_out: u64,
+    /// Numbers that SNS does not accept.
+    pub invalid_number: u64,
+    /// Numbers whose last challenge failed to send.
+    pub failed: u64,
+    /// Numbers with so many undeliverable challenges in a row that their
+    /// voters are told to contact support instead.
+    pub undeliverable: u64,
+}
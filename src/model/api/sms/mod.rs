@@ -0,0 +1,318 @@
+use std::{ops::Deref, str::FromStr};
+
+#[cfg(feature = "server")]
+use hmac::Mac;
+#[cfg(feature = "server")]
+use mongodb::bson::{to_bson, Bson};
+use phonenumber::{country, metadata::DATABASE, Mode, PhoneNumber, Type};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(feature = "server")]
+use crate::{config::Config, model::db::voter::HmacSha256};
+
+mod delivery;
+mod queue;
+mod route;
+mod template;
+
+pub use delivery::SmsDeliverySummary;
+pub use queue::SmsQueueStatus;
+pub use route::{match_route, RouteError, SmsRoute};
+pub use template::{SmsTemplates, TemplateError, DEFAULT_LANGUAGE, MAX_SMS_LENGTH};
+
+/// A voter's SMS number, always normalised to E.164, so that however it was
+/// typed, the same number gives the same `Sms`, and so the same HMAC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Sms {
+    inner: PhoneNumber,
+}
+
+impl Deref for Sms {
+    type Target = PhoneNumber;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Sms {
+    /// Parse a number as a voter or admin typed it. Numbers without a country
+    /// code are read as national numbers of `default_region`, and rejected if
+    /// there is none.
+    pub fn parse_in(s: &str, default_region: Option<country::Id>) -> Result<Self, SmsError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(SmsError::Empty);
+        }
+        let parsed = phonenumber::parse(default_region, s)?;
+        if parsed.extension().is_some() {
+            return Err(SmsError::Extension);
+        }
+        // Reparse the E.164 form to drop any trace of how it was typed.
+        let e164 = parsed.format().mode(Mode::E164).to_string();
+        Ok(Self {
+            inner: phonenumber::parse(None, &e164)?,
+        })
+    }
+
+    /// The E.164 form of the number, e.g. `+447700900123`.
+    pub fn e164(&self) -> String {
+        self.format().mode(Mode::E164).to_string()
+    }
+
+    /// The region the number belongs to, e.g. `GB`, if known.
+    pub fn region(&self) -> Option<country::Id> {
+        self.country().id()
+    }
+
+    /// The HMAC of the number's E.164 form, which identifies its voter.
+    #[cfg(feature = "server")]
+    pub fn into_hmac(self, config: &Config) -> Vec<u8> {
+        let mut hmac = HmacSha256::new_from_slice(config.hmac_secret())
+            .expect("HMAC can take key of any size");
+        hmac.update(self.e164().as_bytes());
+        hmac.finalize().into_bytes().to_vec()
+    }
+
+    /// Does the E.164 form of this number start with any of the given prefixes?
+    /// An empty list of prefixes allows every number.
+    pub fn matches_prefixes(&self, prefixes: &[String]) -> bool {
+        let e164 = self.e164();
+        prefixes.is_empty()
+            || prefixes
+                .iter()
+                .any(|prefix| e164.starts_with(prefix.as_str()))
+    }
+
+    /// Could this number plausibly receive SMS? Numbers known to be landlines,
+    /// VoIP, or other services that cannot are rejected, but those whose type
+    /// is ambiguous or unknown are given the benefit of the doubt.
+    pub fn can_receive_sms(&self) -> bool {
+        !matches!(
+            self.number_type(&DATABASE),
+            Type::FixedLine
+                | Type::Voip
+                | Type::TollFree
+                | Type::PremiumRate
+                | Type::SharedCost
+                | Type::Uan
+                | Type::Voicemail
+                | Type::Pager
+                | Type::Emergency
+        )
+    }
+
+    /// A form of the number safe to log, showing only its last 3 digits.
+    pub fn redacted(&self) -> String {
+        let e164 = self.e164();
+        let last_digits = &e164[e164.len().saturating_sub(3)..];
+        format!("***{last_digits}")
+    }
+}
+
+/// Parse a number in international format, i.e. with its country code.
+impl FromStr for Sms {
+    type Err = SmsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_in(s, None)
+    }
+}
+
+impl TryFrom<String> for Sms {
+    type Error = SmsError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Sms> for String {
+    fn from(sms: Sms) -> Self {
+        sms.e164()
+    }
+}
+
+/// An SMS number as a voter typed it, which may be in their national format,
+/// so can only be parsed once the deployment's default region is known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SmsInput(String);
+
+impl SmsInput {
+    /// Parse the number; see [`Sms::parse_in`].
+    pub fn parse_in(&self, default_region: Option<country::Id>) -> Result<Sms, SmsError> {
+        Sms::parse_in(&self.0, default_region)
+    }
+}
+
+impl From<Sms> for SmsInput {
+    fn from(sms: Sms) -> Self {
+        Self(sms.e164())
+    }
+}
+
+/// Why an SMS number was rejected.
+#[derive(Debug, Error)]
+pub enum SmsError {
+    #[error("SMS number is empty")]
+    Empty,
+    #[error("SMS number cannot have an extension")]
+    Extension,
+    #[error(transparent)]
+    Parse(#[from] phonenumber::ParseError),
+}
+
+#[cfg(feature = "server")]
+impl From<Sms> for Bson {
+    fn from(sms: Sms) -> Self {
+        to_bson(&sms).unwrap() // Valid because `PhoneNumber` serialization doesn't fail
+    }
+}
+
+/// Example data for tests.
+#[cfg(test)]
+mod examples {
+    use rocket::local::asynchronous::Client;
+
+    use super::*;
+
+    impl Sms {
+        pub fn example() -> Self {
+            "+447400123456".parse().unwrap()
+        }
+
+        pub fn example_hmac(client: &Client) -> Vec<u8> {
+            Self::example().into_hmac(client.rocket().state::<Config>().unwrap())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefixes(prefixes: &[&str]) -> Vec<String> {
+        prefixes.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn matches_prefixes() {
+        let uk_mobile = prefixes(&["+447"]);
+        let sms: Sms = "+447700900123".parse().unwrap();
+        assert!(sms.matches_prefixes(&uk_mobile));
+        assert!(sms.matches_prefixes(&prefixes(&["+1", "+44"])));
+        assert!(!sms.matches_prefixes(&prefixes(&["+1", "+4420"])));
+
+        // Landlines and other countries are rejected.
+        let landline: Sms = "+442079460000".parse().unwrap();
+        assert!(!landline.matches_prefixes(&uk_mobile));
+        let us: Sms = "+15555550100".parse().unwrap();
+        assert!(!us.matches_prefixes(&uk_mobile));
+
+        // No prefixes allows everything.
+        assert!(us.matches_prefixes(&[]));
+    }
+
+    #[test]
+    fn can_receive_sms() {
+        // Mobiles can.
+        assert!(Sms::example().can_receive_sms());
+        let us: Sms = "+12015550123".parse().unwrap();
+        assert!(us.can_receive_sms(), "US numbers may be either");
+
+        // Landlines, VoIP, and freephone numbers cannot.
+        for number in ["+442079460000", "+445612345678", "+448001234567"] {
+            let sms: Sms = number.parse().unwrap();
+            assert!(!sms.can_receive_sms(), "{number}");
+        }
+    }
+
+    #[test]
+    fn redacted() {
+        assert_eq!(Sms::example().redacted(), "***456");
+        let formatted: Sms = "+44 7700 900-123".parse().unwrap();
+        assert_eq!(formatted.redacted(), "***123");
+    }
+
+    #[test]
+    fn parse_national() {
+        let international: Sms = "+447700900123".parse().unwrap();
+        for number in [
+            "07700 900123",
+            "07700900123",
+            "07700-900-123",
+            "(07700) 900123",
+            "  07700 900123\n",
+            "\t07700 900123 ",
+            // International formats are still accepted.
+            "+447700900123",
+            "+44 7700 900123",
+            "+44 07700 900123",
+        ] {
+            let sms = Sms::parse_in(number, Some(country::Id::GB)).unwrap();
+            assert_eq!(sms.e164(), "+447700900123", "{number:?}");
+            assert_eq!(sms, international, "{number:?}");
+            assert_eq!(sms.region(), Some(country::Id::GB), "{number:?}");
+        }
+
+        // The default region only applies to numbers without a country code.
+        let us = Sms::parse_in("+1 201-555-0123", Some(country::Id::GB)).unwrap();
+        assert_eq!(us.e164(), "+12015550123");
+        assert_eq!(us.region(), Some(country::Id::US));
+        let us = Sms::parse_in("(201) 555-0123", Some(country::Id::US)).unwrap();
+        assert_eq!(us.e164(), "+12015550123");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        // National formats need a default region.
+        assert!(Sms::parse_in("07700 900123", None).is_err());
+        assert!("07700 900123".parse::<Sms>().is_err());
+
+        for number in ["", "   ", "\n"] {
+            assert!(
+                matches!(
+                    Sms::parse_in(number, Some(country::Id::GB)),
+                    Err(SmsError::Empty)
+                ),
+                "{number:?}"
+            );
+        }
+        assert!(matches!(
+            Sms::parse_in("+44 7700 900123 ext. 5", None),
+            Err(SmsError::Extension)
+        ));
+        for number in ["not a number", "+", "+999 123456"] {
+            assert!(
+                Sms::parse_in(number, Some(country::Id::GB)).is_err(),
+                "{number:?}"
+            );
+        }
+
+        // Deserialising rejects them just the same.
+        assert!(serde_json::from_str::<Sms>("\"07700 900123\"").is_err());
+        assert!(serde_json::from_str::<Sms>("\"\"").is_err());
+        let sms: Sms = serde_json::from_str("\" +44 7700 900123 \"").unwrap();
+        assert_eq!(serde_json::to_string(&sms).unwrap(), "\"+447700900123\"");
+    }
+
+    #[test]
+    fn matches_prefixes_normalised() {
+        // Formatting is normalised away before matching.
+        let uk_mobile = prefixes(&["+447"]);
+        for number in [
+            "+44 7700 900123",
+            "+44 7700-900-123",
+            "+44 (7700) 900123",
+            // Trunk prefix included after the country code.
+            "+44 07700 900123",
+        ] {
+            let sms: Sms = number.parse().unwrap();
+            assert_eq!(sms.to_string(), "+447700900123", "{number}");
+            assert!(sms.matches_prefixes(&uk_mobile), "{number}");
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// The state of the queue that OTP challenges wait in to be sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmsQueueStatus {
+    /// Are challenges queued, rather than sent before responding to voters?
+    pub enabled: bool,
+    /// Number of challenges waiting to be sent.
+    pub depth: u64,
+    /// Most challenges that may wait at once; beyond this, voters are told to
+    /// try again later.
+    pub capacity: u64,
+    /// Queued challenges sent since the server started.
+    pub sent: u64,
+    /// Queued challenges that could not be sent since the server started.
+    pub failed: u64,
+    /// Times a queued challenge was retried after SNS throttled it.
+    pub throttle_retries: u64,
+    /// Queued challenges given up on because SNS kept throttling them, which
+    /// are also counted as failed. These are not held against the number.
+    pub throttled_out: u64,
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A rule for which AWS SNS configuration to send SMS messages through,
+/// based on the country calling code prefix of the recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmsRoute {
+    /// Prefix of the E.164 number, e.g. `+44`. The default route has an empty prefix.
+    pub prefix: String,
+    /// AWS region to send from.
+    pub region: String,
+    /// Alphanumeric sender ID, if the destination country supports one.
+    #[serde(default)]
+    pub sender_id: Option<String>,
+}
+
+impl SmsRoute {
+    /// Check that the prefix is a `+` followed by at least one digit.
+    pub fn validate(&self) -> Result<(), RouteError> {
+        let valid = self
+            .prefix
+            .strip_prefix('+')
+            .map(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if valid {
+            Ok(())
+        } else {
+            Err(RouteError::InvalidPrefix(self.prefix.clone()))
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RouteError {
+    #[error("SMS route prefix '{0}' is not of the form `+<digits>`")]
+    InvalidPrefix(String),
+}
+
+/// Find the index of the longest prefix matching the given E.164 number.
+/// Returns `None` if no prefix matches.
+pub fn match_route<'a>(prefixes: impl IntoIterator<Item = &'a str>, number: &str) -> Option<usize> {
+    prefixes
+        .into_iter()
+        .enumerate()
+        .filter(|(_, prefix)| number.starts_with(prefix))
+        .max_by_key(|(_, prefix)| prefix.len())
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(prefix: &str, region: &str) -> SmsRoute {
+        SmsRoute {
+            prefix: prefix.to_string(),
+            region: region.to_string(),
+            sender_id: None,
+        }
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let prefixes = ["+1", "+44", "+4420"];
+        assert_eq!(match_route(prefixes, "+441234567890"), Some(1));
+        assert_eq!(match_route(prefixes, "+442012345678"), Some(2));
+        assert_eq!(match_route(prefixes, "+15555550100"), Some(0));
+        assert_eq!(match_route(prefixes, "+33123456789"), None);
+    }
+
+    #[test]
+    fn default_fallback() {
+        // The default route matches everything, but loses to any other match.
+        let prefixes = ["", "+1"];
+        assert_eq!(match_route(prefixes, "+33123456789"), Some(0));
+        assert_eq!(match_route(prefixes, "+15555550100"), Some(1));
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(route("+44", "eu-west-2").validate(), Ok(()));
+        for prefix in ["", "+", "44", "+4a"] {
+            assert_eq!(
+                route(prefix, "eu-west-2").validate(),
+                Err(RouteError::InvalidPrefix(prefix.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Placeholder substituted with the OTP code.
+pub const CODE_PLACEHOLDER: &str = "{code}";
+
+/// Language used when none is requested and none is configured.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Template used when no templates are configured.
+pub const DEFAULT_TEMPLATE: &str = "Voter registration code: {code}";
+
+/// Maximum length of a single SMS, in characters.
+pub const MAX_SMS_LENGTH: usize = 160;
+
+/// SMS message templates by language code.
+/// Every template is guaranteed to contain `CODE_PLACEHOLDER`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "HashMap<String, String>")]
+pub struct SmsTemplates {
+    templates: HashMap<String, String>,
+}
+
+impl SmsTemplates {
+    /// Render the message for the given code, using the template for `lang` if present,
+    /// otherwise the template for `default_lang`, otherwise `DEFAULT_TEMPLATE`.
+    /// Messages longer than `MAX_SMS_LENGTH` are truncated, so long as the code survives.
+    pub fn render(&self, lang: Option<&str>, default_lang: &str, code: &str) -> String {
+        let template = lang
+            .and_then(|lang| {
+                let template = self.templates.get(lang);
+                if template.is_none() {
+                    debug!("No SMS template for language '{lang}', using default");
+                }
+                template
+            })
+            .or_else(|| self.templates.get(default_lang))
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_TEMPLATE);
+
+        let message = template.replace(CODE_PLACEHOLDER, code);
+        if message.chars().count() <= MAX_SMS_LENGTH {
+            return message;
+        }
+
+        warn!("Rendered SMS exceeds {MAX_SMS_LENGTH} characters; truncating");
+        let truncated = message.chars().take(MAX_SMS_LENGTH).collect::<String>();
+        if truncated.contains(code) {
+            truncated
+        } else {
+            warn!("Truncation would remove the code; falling back to the default template");
+            DEFAULT_TEMPLATE.replace(CODE_PLACEHOLDER, code)
+        }
+    }
+}
+
+impl Default for SmsTemplates {
+    fn default() -> Self {
+        Self {
+            templates: HashMap::from([(
+                DEFAULT_LANGUAGE.to_string(),
+                DEFAULT_TEMPLATE.to_string(),
+            )]),
+        }
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for SmsTemplates {
+    type Error = TemplateError;
+
+    fn try_from(templates: HashMap<String, String>) -> Result<Self, Self::Error> {
+        for (lang, template) in &templates {
+            if !template.contains(CODE_PLACEHOLDER) {
+                return Err(TemplateError::MissingPlaceholder(lang.clone()));
+            }
+        }
+        Ok(Self { templates })
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("SMS template for language '{0}' does not contain the `{{code}}` placeholder")]
+    MissingPlaceholder(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "123456";
+
+    fn templates() -> SmsTemplates {
+        HashMap::from([
+            ("en".to_string(), "Your code: {code}".to_string()),
+            ("fr".to_string(), "Votre code : {code}".to_string()),
+        ])
+        .try_into()
+        .unwrap()
+    }
+
+    #[test]
+    fn default_matches_original_message() {
+        let message = SmsTemplates::default().render(None, DEFAULT_LANGUAGE, CODE);
+        assert_eq!(message, format!("Voter registration code: {}", CODE));
+    }
+
+    #[test]
+    fn substitution() {
+        let templates = templates();
+        assert_eq!(
+            templates.render(Some("fr"), "en", CODE),
+            "Votre code : 123456"
+        );
+        assert_eq!(templates.render(None, "fr", CODE), "Votre code : 123456");
+        assert_eq!(templates.render(None, "en", CODE), "Your code: 123456");
+    }
+
+    #[test]
+    fn unknown_language() {
+        let templates = templates();
+        assert_eq!(
+            templates.render(Some("de"), "en", CODE),
+            "Your code: 123456"
+        );
+        assert_eq!(
+            templates.render(Some("de"), "de", CODE),
+            format!("Voter registration code: {}", CODE)
+        );
+    }
+
+    #[test]
+    fn missing_placeholder() {
+        let result =
+            SmsTemplates::try_from(HashMap::from([("en".to_string(), "No code".to_string())]));
+        assert_eq!(
+            result,
+            Err(TemplateError::MissingPlaceholder("en".to_string()))
+        );
+    }
+
+    #[test]
+    fn overly_long() {
+        // Multi-byte characters must not be split.
+        let padding = "é".repeat(MAX_SMS_LENGTH);
+        let templates: SmsTemplates = HashMap::from([
+            ("start".to_string(), format!("{{code}} {}", padding)),
+            ("end".to_string(), format!("{} {{code}}", padding)),
+        ])
+        .try_into()
+        .unwrap();
+
+        // Truncated, keeping the code.
+        let message = templates.render(Some("start"), "en", CODE);
+        assert_eq!(message.chars().count(), MAX_SMS_LENGTH);
+        assert!(message.starts_with(CODE));
+
+        // Truncation would remove the code, so fall back.
+        let message = templates.render(Some("end"), "en", CODE);
+        assert_eq!(message, format!("Voter registration code: {}", CODE));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where a scheduled task is in its life.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskState {
+    /// Waiting for its scheduled time, or to be triggered early.
+    Pending,
+    /// Triggered and still running.
+    Running,
+    /// Ran to completion.
+    Completed,
+    /// Cancelled before it could complete.
+    Cancelled,
+}
+
+/// API-friendly description of a scheduled task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskDesc {
+    /// Unique name of the task, e.g. `election-5-finalizer`.
+    pub name: String,
+    /// When the task runs, unless triggered early.
+    pub scheduled_for: DateTime<Utc>,
+    /// Current state of the task.
+    pub state: TaskState,
+    /// Whether admins may trigger the task before it is due.
+    pub allow_early: bool,
+}
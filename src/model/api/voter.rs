@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::common::allowed_questions::AllowedQuestions;
+
+/// What support staff may see about a voter, limited to a single election.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterLookup {
+    /// Has the voter joined the election?
+    pub joined: bool,
+    /// The questions the voter may answer in the election, and whether they
+    /// have confirmed a ballot for each. Empty if they have not joined.
+    pub allowed_questions: AllowedQuestions,
+}
+
+/// An admin's request to freeze a voter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterFreezeRequest {
+    /// Why the voter is being frozen, kept on the voter for other admins.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// At most this many rejected rows are described in a [`VoterImportReport`].
+pub const MAX_REPORTED_IMPORT_ERRORS: usize = 100;
+
+/// The outcome of importing voters from a CSV electoral roll.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterImportReport {
+    /// Voters who did not exist yet, and were created already joined to the election.
+    pub created: u64,
+    /// Existing voters who were joined to the election.
+    pub updated: u64,
+    /// Voters who had already joined the election, and so were left alone.
+    pub skipped: u64,
+    /// How many rows were rejected.
+    pub rejected: u64,
+    /// Why rows were rejected, for the first [`MAX_REPORTED_IMPORT_ERRORS`] of them.
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Why a row of an imported electoral roll was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRowError {
+    /// The line of the CSV file the row starts on, counting the header as line 1.
+    pub line: u64,
+    /// What was wrong with it.
+    pub error: String,
+}
+
+/// How many voters registered before and after `hmac_secret` was last
+/// changed. Those registered before can no longer be recognised, so returning
+/// voters among them will have been registered again as new voters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HmacRotationReport {
+    /// When a change of `hmac_secret` was last accepted, if ever.
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// Voters registered before the change, who are orphaned by it.
+    /// Zero if it has never changed.
+    pub voters_before: u64,
+    /// Voters registered since the change, or ever if it has never changed.
+    pub voters_after: u64,
+}
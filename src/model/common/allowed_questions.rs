@@ -1,9 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 
+#[cfg(feature = "server")]
+use rocket::http::Status;
 use serde::{Deserialize, Serialize};
 
-use crate::model::{common::election::QuestionId, mongodb::serde_string_map};
+use crate::model::common::{election::QuestionId, serde_string_map};
+#[cfg(feature = "server")]
+use crate::{
+    error::{Error, Result},
+    model::db::election::Election,
+};
+
+/// The groups a voter claims membership of, keyed by electorate name.
+pub type Joins = HashMap<String, HashSet<String>>;
+
+/// A request to join an election: the groups to join, alongside the version of
+/// the election's consent text the voter accepts, if it has one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinRequest {
+    #[serde(flatten)]
+    pub joins: Joins,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_version: Option<u32>,
+}
 
 /// The questions that a voter may answer for a particular election.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -14,6 +34,104 @@ pub struct AllowedQuestions {
     pub confirmed: HashMap<QuestionId, bool>,
 }
 
+impl AllowedQuestions {
+    /// Work out which questions of the given election a voter joining the given
+    /// groups may answer, none of which will have been confirmed yet.
+    ///
+    /// Fails with 404 if an electorate or group does not exist, or 422 if more
+    /// than one group of a mutex electorate is joined.
+    #[cfg(feature = "server")]
+    pub fn for_joins(election: &Election, joins: &Joins) -> Result<Self> {
+        // Check that electorates and groups exist and meet mutex requirements
+        for (electorate_name, groups) in joins {
+            let electorate = election.electorates.get(electorate_name).ok_or_else(|| {
+                Error::not_found(format!("Electorate with name '{}'", electorate_name))
+            })?;
+
+            if electorate.is_mutex && groups.len() > 1 {
+                return Err(Error::Status(
+                    Status::UnprocessableEntity,
+                    format!(
+                        "Cannot join more than one group in mutex electorate {}",
+                        electorate_name
+                    ),
+                ));
+            }
+
+            let invalid_groups: Vec<_> = groups.difference(&electorate.groups).collect();
+            if !invalid_groups.is_empty() {
+                return Err(Error::not_found(format!(
+                    "Groups for electorate '{}' with the following names '{:?}'",
+                    electorate_name, invalid_groups
+                )));
+            }
+        }
+
+        // Find questions restricted to those groups
+        let confirmed = election
+            .questions
+            .iter()
+            .filter_map(|(question_id, question)| {
+                // The question is allowed if it has no constraints, or if at least one of its constraints is satisfied.
+                let question_allowed = question.constraints.is_empty()
+                    || question
+                        .constraints
+                        .iter()
+                        .any(|(electorate_name, groups)| {
+                            let electorate_joined = joins.get(electorate_name);
+                            if let Some(joined) = electorate_joined {
+                                !groups.is_disjoint(joined)
+                            } else {
+                                false
+                            }
+                        });
+                if question_allowed {
+                    Some((*question_id, false))
+                } else {
+                    None
+                }
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok(Self { confirmed })
+    }
+
+    /// Carry these over to a modified version of the election, given a mapping
+    /// from old to new question IDs. Questions with no new ID are dropped.
+    pub fn remap(&self, mapping: &HashMap<QuestionId, QuestionId>) -> Self {
+        let confirmed = self
+            .confirmed
+            .iter()
+            .filter_map(|(old_id, confirmed)| {
+                mapping.get(old_id).map(|new_id| (*new_id, *confirmed))
+            })
+            .collect();
+        Self { confirmed }
+    }
+
+    /// Work these out again for a modified version of the election, for a
+    /// voter who joined the given groups, keeping whether the questions carried
+    /// over by `mapping` were confirmed.
+    ///
+    /// Fails as for [`AllowedQuestions::for_joins`] if the groups can no
+    /// longer be joined.
+    #[cfg(feature = "server")]
+    pub fn recompute(
+        &self,
+        modified: &Election,
+        joins: &Joins,
+        mapping: &HashMap<QuestionId, QuestionId>,
+    ) -> Result<Self> {
+        let mut allowed = Self::for_joins(modified, joins)?;
+        for (question_id, confirmed) in self.remap(mapping).confirmed {
+            if let Some(allowed_confirmed) = allowed.confirmed.get_mut(&question_id) {
+                *allowed_confirmed = confirmed;
+            }
+        }
+        Ok(allowed)
+    }
+}
+
 impl Deref for AllowedQuestions {
     type Target = HashMap<QuestionId, bool>;
 
@@ -4,8 +4,12 @@ use dre_ip::{
     Ballot as DreipBallot, DreipGroup as DreipGroupTrait, DreipScalar, NoSecrets, SecretsPresent,
     VoteSecrets,
 };
+#[cfg(feature = "server")]
 use mongodb::bson::{to_bson, Bson};
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use serde_unit_struct::{Deserialize_unit_struct, Serialize_unit_struct};
 
 use crate::model::api::election::ReceiptError;
@@ -44,8 +48,12 @@ pub trait BallotState: Copy + AsRef<[u8]> {
         external: &BallotCrypto<Self::ExposedSecrets>,
     ) -> BallotCrypto<NoSecrets>;
 
-    /// Retrieve the extra receipt data.
-    fn receipt_data(internal: &BallotCrypto<Self::InternalSecrets>) -> Self::ReceiptData;
+    /// Retrieve the extra receipt data, given the audited candidate stored
+    /// alongside the ballot, if any.
+    fn receipt_data(
+        internal: &BallotCrypto<Self::InternalSecrets>,
+        stored: Option<&AuditExtraData>,
+    ) -> Self::ReceiptData;
 
     /// Verify the extra receipt data.
     fn verify_receipt_data(receipt: &Receipt<Self>) -> Result<(), VerificationError>;
@@ -75,6 +83,7 @@ impl AsRef<[u8]> for Unconfirmed {
     }
 }
 
+#[cfg(feature = "server")]
 impl From<Unconfirmed> for Bson {
     fn from(state: Unconfirmed) -> Self {
         to_bson(&state).expect("Serialisation is infallible")
@@ -105,7 +114,10 @@ impl BallotState for Unconfirmed {
         external.clone()
     }
 
-    fn receipt_data(_: &BallotCrypto<Self::InternalSecrets>) -> Self::ReceiptData {
+    fn receipt_data(
+        _: &BallotCrypto<Self::InternalSecrets>,
+        _: Option<&AuditExtraData>,
+    ) -> Self::ReceiptData {
         NoSecrets(())
     }
 
@@ -118,7 +130,8 @@ impl BallotState for Unconfirmed {
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize_unit_struct, Serialize_unit_struct)]
 pub struct Audited;
 
-const AUDITED: &str = "Audited";
+/// The name of the audited state, as used in receipts and on the board.
+pub const AUDITED: &str = "Audited";
 
 impl AsRef<[u8]> for Audited {
     fn as_ref(&self) -> &[u8] {
@@ -126,6 +139,7 @@ impl AsRef<[u8]> for Audited {
     }
 }
 
+#[cfg(feature = "server")]
 impl From<Audited> for Bson {
     fn from(state: Audited) -> Self {
         to_bson(&state).expect("Serialisation is infallible")
@@ -156,10 +170,15 @@ impl BallotState for Audited {
         external.clone().confirm(None)
     }
 
+    /// The candidate is always read from the secrets, rather than trusting any
+    /// stored copy.
     /// This assumes that the ballot is well-formed, i.e. there is a yes-candidate.
     /// If there is not, then the receipt is garbage and will not pass verification anyway,
     /// so we arbitrarily return the first candidate to avoid a panic.
-    fn receipt_data(internal: &BallotCrypto<Self::InternalSecrets>) -> Self::ReceiptData {
+    fn receipt_data(
+        internal: &BallotCrypto<Self::InternalSecrets>,
+        _: Option<&AuditExtraData>,
+    ) -> Self::ReceiptData {
         for (candidate, vote) in &internal.votes {
             if vote.secrets.v == <DreipGroup as DreipGroupTrait>::Scalar::one() {
                 return AuditExtraData {
@@ -176,7 +195,7 @@ impl BallotState for Audited {
     }
 
     fn verify_receipt_data(receipt: &Receipt<Self>) -> Result<(), VerificationError> {
-        let correct_extra_data = Self::receipt_data(&receipt.crypto);
+        let correct_extra_data = Self::receipt_data(&receipt.crypto, None);
         if receipt.state_data == correct_extra_data {
             Ok(())
         } else {
@@ -191,11 +210,119 @@ impl BallotState for Audited {
     }
 }
 
+/// Extra data for the receipts of redacted audited ballots: the candidate,
+/// which can no longer be checked against the secrets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactedExtraData {
+    pub candidate: CandidateId,
+    /// Always true. Marks the receipt as redacted, and tells it apart from a
+    /// receipt that is merely missing its secrets.
+    pub redacted: bool,
+}
+
+impl<'a> From<&'a RedactedExtraData> for Vec<u8> {
+    fn from(data: &'a RedactedExtraData) -> Self {
+        data.candidate.clone().into_bytes()
+    }
+}
+
+/// Marker type for audited ballots whose secrets have been removed after the
+/// retention period.
+///
+/// These are stored and published with the audited state, so they are still
+/// counted and filtered as audited, but their signed message uses
+/// [`AUDITED_REDACTED`] so it cannot be mistaken for that of a full receipt.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct AuditedRedacted;
+
+/// The name of the redacted state, as used in signed receipt messages.
+pub const AUDITED_REDACTED: &str = "AuditedRedacted";
+
+impl AsRef<[u8]> for AuditedRedacted {
+    fn as_ref(&self) -> &[u8] {
+        AUDITED_REDACTED.as_bytes()
+    }
+}
+
+impl Serialize for AuditedRedacted {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(AUDITED)
+    }
+}
+
+impl<'de> Deserialize<'de> for AuditedRedacted {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = String::deserialize(deserializer)?;
+        if state == AUDITED {
+            Ok(AuditedRedacted)
+        } else {
+            Err(de::Error::invalid_value(
+                de::Unexpected::Str(&state),
+                &AUDITED,
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<AuditedRedacted> for Bson {
+    fn from(state: AuditedRedacted) -> Self {
+        to_bson(&state).expect("Serialisation is infallible")
+    }
+}
+
+/// Redacted ballots have had their secrets erased, but still reveal the
+/// candidate they were audited for.
+impl BallotState for AuditedRedacted {
+    type InternalSecrets = NoSecrets;
+    type ExposedSecrets = NoSecrets;
+    type ReceiptData = RedactedExtraData;
+
+    fn internal_to_receipt(
+        internal: BallotCrypto<Self::InternalSecrets>,
+    ) -> BallotCrypto<Self::ExposedSecrets> {
+        internal
+    }
+
+    fn remove_internal_secrets(
+        internal: &BallotCrypto<Self::InternalSecrets>,
+    ) -> BallotCrypto<NoSecrets> {
+        internal.clone()
+    }
+
+    fn remove_external_secrets(
+        external: &BallotCrypto<Self::ExposedSecrets>,
+    ) -> BallotCrypto<NoSecrets> {
+        external.clone()
+    }
+
+    /// The candidate can only come from the stored copy, which redaction
+    /// always records. If it is somehow missing, the candidate is left empty.
+    fn receipt_data(
+        _: &BallotCrypto<Self::InternalSecrets>,
+        stored: Option<&AuditExtraData>,
+    ) -> Self::ReceiptData {
+        RedactedExtraData {
+            candidate: stored
+                .map(|data| data.candidate.clone())
+                .unwrap_or_default(),
+            redacted: true,
+        }
+    }
+
+    /// Without the secrets, the candidate cannot be checked; the signature
+    /// still covers it.
+    fn verify_receipt_data(_receipt: &Receipt<Self>) -> Result<(), VerificationError> {
+        Ok(())
+    }
+}
+
 /// Marker type for confirmed ballots.
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Deserialize_unit_struct, Serialize_unit_struct)]
 pub struct Confirmed;
 
-const CONFIRMED: &str = "Confirmed";
+/// The name of the confirmed state, as used in receipts and on the board.
+pub const CONFIRMED: &str = "Confirmed";
 
 impl AsRef<[u8]> for Confirmed {
     fn as_ref(&self) -> &[u8] {
@@ -203,6 +330,7 @@ impl AsRef<[u8]> for Confirmed {
     }
 }
 
+#[cfg(feature = "server")]
 impl From<Confirmed> for Bson {
     fn from(state: Confirmed) -> Self {
         to_bson(&state).expect("Serialisation is infallible")
@@ -233,7 +361,10 @@ impl BallotState for Confirmed {
         external.clone()
     }
 
-    fn receipt_data(_: &BallotCrypto<Self::InternalSecrets>) -> Self::ReceiptData {
+    fn receipt_data(
+        _: &BallotCrypto<Self::InternalSecrets>,
+        _: Option<&AuditExtraData>,
+    ) -> Self::ReceiptData {
         NoSecrets(())
     }
 
@@ -0,0 +1,238 @@
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::model::common::ballot::{BallotId, BallotState};
+
+/// The hash preceding the first event of every chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A single ballot event (cast, audit, or confirm) on a question's bulletin board.
+///
+/// Rather than hashing the ballot crypto directly, each event commits to the
+/// ballot's confirmation code, which is itself a hash of the public crypto and
+/// the IDs. This lets the chain be recomputed from public data alone, even for
+/// ballots that are still unconfirmed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardEvent {
+    /// Zero-based position of this event in the chain.
+    pub position: u64,
+    /// Ballot ID.
+    pub ballot_id: BallotId,
+    /// The state the ballot entered with this event.
+    pub state: String,
+    /// The ballot's confirmation code.
+    pub confirmation_code: String,
+    /// Chain hash up to and including this event.
+    pub hash: String,
+}
+
+impl BoardEvent {
+    /// Create the event following `prev_hash` at the given position.
+    pub fn new<S: BallotState>(
+        position: u64,
+        prev_hash: &str,
+        ballot_id: BallotId,
+        state: S,
+        confirmation_code: String,
+    ) -> Self {
+        let state = String::from_utf8_lossy(state.as_ref()).into_owned();
+        let hash = chain_hash(prev_hash, ballot_id, state.as_bytes(), &confirmation_code);
+        Self {
+            position,
+            ballot_id,
+            state,
+            confirmation_code,
+            hash,
+        }
+    }
+
+    /// Recompute this event's hash from its contents and the previous hash.
+    fn expected_hash(&self, prev_hash: &str) -> String {
+        chain_hash(
+            prev_hash,
+            self.ballot_id,
+            self.state.as_bytes(),
+            &self.confirmation_code,
+        )
+    }
+}
+
+/// Compute `H(prev_hash || ballot_id || state || confirmation_code)`.
+fn chain_hash(
+    prev_hash: &str,
+    ballot_id: BallotId,
+    state: &[u8],
+    confirmation_code: &str,
+) -> String {
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(ballot_id.to_le_bytes());
+    hasher.update(state);
+    hasher.update(confirmation_code.as_bytes());
+    HEXLOWER.encode(&hasher.finalize())
+}
+
+/// Errors in a bulletin board hash chain.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum BoardError {
+    /// An event was not at the position it claims, i.e. events were inserted or removed.
+    Position { position: u64 },
+    /// An event's hash does not follow from its contents and predecessor.
+    Hash { position: u64 },
+    /// The published head does not match the end of the chain.
+    Head,
+    /// A receipt has no matching event on the chain.
+    MissingEvent { ballot_id: BallotId },
+    /// The chain has an event for a ballot whose receipt is missing.
+    MissingReceipt { ballot_id: BallotId },
+    /// The chain does not extend an earlier version of itself.
+    Diverged { position: u64 },
+}
+
+/// A question's complete bulletin board hash chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardChain {
+    /// Number of events in the chain.
+    pub length: u64,
+    /// Hash of the last event, or [`GENESIS_HASH`] if there are none.
+    pub head: String,
+    /// All events, in chain order.
+    pub events: Vec<BoardEvent>,
+}
+
+impl BoardChain {
+    /// Recompute the chain and check that it ends at the claimed head.
+    pub fn verify(&self) -> Result<(), BoardError> {
+        let mut prev_hash = GENESIS_HASH;
+        for (position, event) in (0..).zip(&self.events) {
+            if event.position != position {
+                return Err(BoardError::Position { position });
+            }
+            if event.expected_hash(prev_hash) != event.hash {
+                return Err(BoardError::Hash { position });
+            }
+            prev_hash = event.hash.as_str();
+        }
+        if self.length != self.events.len() as u64 || self.head != prev_hash {
+            return Err(BoardError::Head);
+        }
+
+        Ok(())
+    }
+
+    /// Check that this chain is an append-only extension of an earlier one,
+    /// i.e. that no events have been removed, altered, or reordered since.
+    pub fn extends(&self, earlier: &BoardChain) -> Result<(), BoardError> {
+        for (position, event) in (0..).zip(&earlier.events) {
+            match self.events.get(position as usize) {
+                Some(later) if later == event => {}
+                _ => return Err(BoardError::Diverged { position }),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the event moving the given ballot into the given state.
+    pub fn find_event(&self, ballot_id: BallotId, state: impl BallotState) -> Option<&BoardEvent> {
+        let state = String::from_utf8_lossy(state.as_ref());
+        self.events
+            .iter()
+            .find(|event| event.ballot_id == ballot_id && event.state == state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::common::ballot::{Confirmed, Unconfirmed};
+
+    fn example_chain() -> BoardChain {
+        let mut events = Vec::new();
+        let mut head = GENESIS_HASH.to_string();
+        for ballot_id in 1..=3 {
+            let cast = BoardEvent::new(
+                events.len() as u64,
+                &head,
+                ballot_id,
+                Unconfirmed,
+                format!("CODE{ballot_id}"),
+            );
+            let confirm = BoardEvent::new(
+                events.len() as u64 + 1,
+                &cast.hash,
+                ballot_id,
+                Confirmed,
+                format!("CODE{ballot_id}"),
+            );
+            head = confirm.hash.clone();
+            events.push(cast);
+            events.push(confirm);
+        }
+        BoardChain {
+            length: events.len() as u64,
+            head,
+            events,
+        }
+    }
+
+    #[test]
+    fn valid_chain() {
+        let chain = example_chain();
+        assert_eq!(chain.verify(), Ok(()));
+        assert!(chain.find_event(2, Confirmed).is_some());
+        assert!(chain.find_event(4, Unconfirmed).is_none());
+
+        let empty = BoardChain {
+            length: 0,
+            head: GENESIS_HASH.to_string(),
+            events: Vec::new(),
+        };
+        assert_eq!(empty.verify(), Ok(()));
+        assert_eq!(chain.extends(&empty), Ok(()));
+    }
+
+    #[test]
+    fn tampered_chain() {
+        // Altered event contents.
+        let mut chain = example_chain();
+        chain.events[2].confirmation_code = "FORGED".to_string();
+        assert_eq!(chain.verify(), Err(BoardError::Hash { position: 2 }));
+
+        // Removed event.
+        let mut chain = example_chain();
+        chain.events.remove(1);
+        assert_eq!(chain.verify(), Err(BoardError::Position { position: 1 }));
+
+        // Truncated chain with the old head.
+        let mut chain = example_chain();
+        chain.events.pop();
+        chain.length -= 1;
+        assert_eq!(chain.verify(), Err(BoardError::Head));
+    }
+
+    #[test]
+    fn extension() {
+        let full = example_chain();
+        let mut earlier = full.clone();
+        earlier.events.truncate(3);
+        earlier.length = 3;
+        earlier.head = earlier.events[2].hash.clone();
+        assert_eq!(earlier.verify(), Ok(()));
+        assert_eq!(full.extends(&earlier), Ok(()));
+        assert_eq!(
+            earlier.extends(&full),
+            Err(BoardError::Diverged { position: 3 })
+        );
+
+        // Rewriting history, even consistently, is caught.
+        let mut rewritten = full.clone();
+        rewritten.events[0] = BoardEvent::new(0, GENESIS_HASH, 1, Unconfirmed, "OTHER".into());
+        assert_eq!(
+            rewritten.extends(&earlier),
+            Err(BoardError::Diverged { position: 0 })
+        );
+    }
+}
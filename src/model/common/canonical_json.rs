@@ -0,0 +1,95 @@
+//! Canonical JSON serialisation, for signing JSON documents.
+//!
+//! The canonical form is compact JSON with the keys of every object sorted by
+//! their UTF-8 bytes, so it does not depend on the order in which fields or map
+//! entries happen to be serialised.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serialise a value in canonical form.
+pub fn to_vec<T: Serialize>(value: &T) -> serde_json::Result<Vec<u8>> {
+    let value = serde_json::to_value(value)?;
+    let mut out = Vec::new();
+    write_value(&value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut Vec<u8>) -> serde_json::Result<()> {
+    match value {
+        Value::Object(fields) => {
+            let mut keys = fields.keys().collect::<Vec<_>>();
+            keys.sort_unstable();
+            out.push(b'{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, key)?;
+                out.push(b':');
+                write_value(&fields[key], out)?;
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(b']');
+        }
+        scalar => serde_json::to_writer(&mut *out, scalar)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Forwards {
+        alpha: u32,
+        beta: Vec<HashMap<String, bool>>,
+        gamma: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Backwards {
+        gamma: Option<String>,
+        beta: Vec<HashMap<String, bool>>,
+        alpha: u32,
+    }
+
+    #[test]
+    fn field_order_independent() {
+        let map = (0..20)
+            .map(|i| (format!("key{i}"), i % 2 == 0))
+            .collect::<HashMap<_, _>>();
+        let forwards = Forwards {
+            alpha: 1,
+            beta: vec![map.clone()],
+            gamma: Some("\"quoted\" ü".to_string()),
+        };
+        let backwards = Backwards {
+            gamma: Some("\"quoted\" ü".to_string()),
+            beta: vec![map],
+            alpha: 1,
+        };
+        assert_eq!(to_vec(&forwards).unwrap(), to_vec(&backwards).unwrap());
+
+        // Arrays keep their order, and nested objects are sorted too.
+        let value = json!({ "b": [3, 1, { "z": null, "a": 1.5 }], "a": "x" });
+        assert_eq!(
+            String::from_utf8(to_vec(&value).unwrap()).unwrap(),
+            r#"{"a":"x","b":[3,1,{"a":1.5,"z":null}]}"#
+        );
+    }
+}
@@ -14,8 +14,8 @@ pub struct Electorate {
     pub is_mutex: bool,
 }
 
-/// Example test data.
-#[cfg(test)]
+/// Example test data, also used for development seeding.
+#[cfg(any(test, feature = "dev-tools"))]
 mod examples {
     use super::*;
 
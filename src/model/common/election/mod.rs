@@ -1,14 +1,142 @@
 mod electorate;
+mod quorum;
 mod state;
+mod translation;
+mod visibility;
+
+use sha2::{Digest, Sha256};
 
 pub use electorate::Electorate;
+pub use quorum::{QuorumReport, QuorumSpec};
 pub use state::ElectionState;
+pub use translation::{
+    check_lang_code, check_translations, LangCode, Translations, MAX_LANG_CODE_LENGTH,
+};
+pub use visibility::ResultVisibility;
 
 /// We implement our DRE-ip over the P-256 elliptic curve.
 pub type DreipGroup = dre_ip::group::p256::NistP256;
+/// The name identifying [`DreipGroup`] in election descriptions and dumps.
+pub const DREIP_GROUP_NAME: &str = "NIST-P256";
 /// Our election IDs are integers.
 pub type ElectionId = u32;
 /// Our question IDs are integers.
 pub type QuestionId = u32;
 /// Our candidate IDs (names) are strings.
 pub type CandidateId = String;
+/// The candidate added to questions that allow write-ins when their election
+/// is published. Its votes are tallied like any other candidate's, and the
+/// names written in are kept alongside the ballots.
+pub const WRITE_IN_CANDIDATE: &str = "__write_in__";
+
+/// Label prefixed to every hash in [`ballot_order`], so that the server secret,
+/// which also keys voters' SMS HMACs, is never hashed in the same way for two
+/// purposes.
+const BALLOT_ORDER_DOMAIN: &[u8] = b"dreip-backend ballot order";
+
+/// Order a question's candidates for a voter, so that no candidate is always
+/// listed first.
+///
+/// Each candidate is sorted by a hash of [`BALLOT_ORDER_DOMAIN`], the server
+/// secret, the voter, the election, the question and the candidate itself.
+/// The order is therefore the same every time for one voter, but unpredictable
+/// and different across voters. Ties, which are vanishingly unlikely, keep the
+/// canonical order.
+pub fn ballot_order(
+    candidates: &[CandidateId],
+    secret: &[u8],
+    voter_id: &[u8],
+    election_id: ElectionId,
+    question_id: QuestionId,
+) -> Vec<CandidateId> {
+    let mut keyed = Sha256::new();
+    keyed.update(BALLOT_ORDER_DOMAIN);
+    // Length-prefix the variable-length inputs so they cannot run together.
+    keyed.update((secret.len() as u64).to_le_bytes());
+    keyed.update(secret);
+    keyed.update((voter_id.len() as u64).to_le_bytes());
+    keyed.update(voter_id);
+    keyed.update(election_id.to_le_bytes());
+    keyed.update(question_id.to_le_bytes());
+
+    let mut ordered = candidates
+        .iter()
+        .map(|candidate| {
+            let key = keyed.clone().chain_update(candidate.as_bytes()).finalize();
+            (key, candidate)
+        })
+        .collect::<Vec<_>>();
+    ordered.sort_by(|(a, _), (b, _)| a.cmp(b));
+    ordered
+        .into_iter()
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn candidates() -> Vec<CandidateId> {
+        ["Alice", "Bob", "Carol", "Dave"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn ballot_order_deterministic() {
+        let candidates = candidates();
+        let order = ballot_order(&candidates, b"secret", b"voter", 1, 2);
+        assert_eq!(order, ballot_order(&candidates, b"secret", b"voter", 1, 2));
+
+        // It is a permutation of the candidates.
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, candidates);
+
+        // Any change of input gives an independent order; with 10 candidates,
+        // these all coincide with negligible probability.
+        let many = (0..10)
+            .map(|i| format!("Candidate {i}"))
+            .collect::<Vec<_>>();
+        let order = ballot_order(&many, b"secret", b"voter", 1, 2);
+        assert_ne!(order, ballot_order(&many, b"secret2", b"voter", 1, 2));
+        assert_ne!(order, ballot_order(&many, b"secret", b"voter2", 1, 2));
+        assert_ne!(order, ballot_order(&many, b"secret", b"voter", 3, 2));
+        assert_ne!(order, ballot_order(&many, b"secret", b"voter", 1, 3));
+    }
+
+    #[test]
+    fn ballot_order_known_answer() {
+        // Computed independently, with the domain label. Without it, the order
+        // would be Bob, Alice, Carol, Dave.
+        assert_eq!(
+            ballot_order(&candidates(), b"secret", b"voter", 1, 2),
+            ["Carol", "Alice", "Bob", "Dave"]
+        );
+    }
+
+    #[test]
+    fn ballot_order_distribution() {
+        // Over many voters, each candidate is listed first about equally often.
+        const VOTERS: u32 = 4000;
+        let candidates = candidates();
+        let mut firsts = HashMap::<CandidateId, u32>::new();
+        for voter in 0..VOTERS {
+            let order = ballot_order(&candidates, b"secret", &voter.to_le_bytes(), 1, 1);
+            *firsts.entry(order[0].clone()).or_default() += 1;
+        }
+        let expected = VOTERS / candidates.len() as u32;
+        for candidate in &candidates {
+            let count = firsts[candidate];
+            // Over 8 standard deviations from the mean, so this never fails by chance.
+            assert!(
+                count.abs_diff(expected) < 220,
+                "{candidate} was first {count} times, expected about {expected}"
+            );
+        }
+    }
+}
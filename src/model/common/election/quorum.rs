@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// How many voters must confirm a ballot on a question for its result to be
+/// binding. This is only reported on; it has no effect on voting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuorumSpec {
+    /// At least this many voters.
+    Count(u32),
+    /// At least this percentage of the voters eligible to answer the question,
+    /// rounded up.
+    Percentage(u8),
+}
+
+impl QuorumSpec {
+    /// Check that the quorum is one that could be both met and missed.
+    pub fn check(self) -> Result<(), String> {
+        match self {
+            Self::Count(0) => Err("A quorum count must be at least 1".to_string()),
+            Self::Percentage(percentage) if percentage == 0 || percentage > 100 => Err(format!(
+                "A quorum percentage must be from 1 to 100, not {percentage}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of confirmed ballots needed to meet the quorum, given how many
+    /// voters may answer the question.
+    pub fn required(self, eligible_voters: u64) -> u64 {
+        match self {
+            Self::Count(count) => count.into(),
+            Self::Percentage(percentage) => (eligible_voters * u64::from(percentage)).div_ceil(100),
+        }
+    }
+}
+
+/// Whether enough voters answered a question to meet its quorum, along with
+/// the numbers that decided it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumReport {
+    /// Was the quorum met? `None` if the question has no quorum.
+    pub quorum_met: Option<bool>,
+    /// Number of confirmed ballots needed to meet the quorum, if any.
+    pub required: Option<u64>,
+    /// Number of voters who joined the election in a group allowed to answer
+    /// the question.
+    pub eligible_voters: u64,
+    /// Number of confirmed ballots, which is the number of voters who answered.
+    pub confirmed_ballots: u64,
+}
+
+impl QuorumReport {
+    /// Judge the given quorum, if any, on the question's turnout.
+    pub fn new(quorum: Option<QuorumSpec>, eligible_voters: u64, confirmed_ballots: u64) -> Self {
+        let required = quorum.map(|quorum| quorum.required(eligible_voters));
+        Self {
+            quorum_met: required.map(|required| confirmed_ballots >= required),
+            required,
+            eligible_voters,
+            confirmed_ballots,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_ballots() {
+        assert_eq!(QuorumSpec::Count(3).required(100), 3);
+        assert_eq!(QuorumSpec::Percentage(10).required(100), 10);
+        assert_eq!(QuorumSpec::Percentage(10).required(101), 11);
+        assert_eq!(QuorumSpec::Percentage(10).required(0), 0);
+
+        assert!(QuorumSpec::Count(0).check().is_err());
+        assert!(QuorumSpec::Percentage(0).check().is_err());
+        assert!(QuorumSpec::Percentage(101).check().is_err());
+        assert!(QuorumSpec::Percentage(100).check().is_ok());
+    }
+
+    #[test]
+    fn reports() {
+        let report = QuorumReport::new(Some(QuorumSpec::Count(3)), 5, 2);
+        assert_eq!(report.quorum_met, Some(false));
+        assert_eq!(report.required, Some(3));
+        assert_eq!(
+            QuorumReport::new(Some(QuorumSpec::Count(3)), 5, 3).quorum_met,
+            Some(true)
+        );
+
+        let report = QuorumReport::new(None, 5, 0);
+        assert_eq!((report.quorum_met, report.required), (None, None));
+    }
+}
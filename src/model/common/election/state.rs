@@ -1,3 +1,4 @@
+#[cfg(feature = "server")]
 use mongodb::bson::{to_bson, Bson};
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +13,7 @@ pub enum ElectionState {
     Archived,
 }
 
+#[cfg(feature = "server")]
 impl From<ElectionState> for Bson {
     fn from(state: ElectionState) -> Self {
         to_bson(&state).expect("Serialisation is infallible")
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+/// A language code, such as `cy` or `en-GB`.
+pub type LangCode = String;
+
+/// Translations of a display string, by language code. The canonical string
+/// is kept separately, and is what ballots, receipts and constraints use.
+pub type Translations = HashMap<LangCode, String>;
+
+/// The longest language code accepted.
+pub const MAX_LANG_CODE_LENGTH: usize = 35;
+
+/// Check that `code` is a well-formed language code: a primary language of
+/// two or three lowercase letters, then any number of subtags of one to eight
+/// letters or digits, separated by hyphens, e.g. `cy`, `en-GB` or `zh-Hant-TW`.
+pub fn check_lang_code(code: &str) -> Result<(), String> {
+    let invalid = || format!("'{code}' is not a language code, such as 'cy' or 'en-GB'");
+    if code.len() > MAX_LANG_CODE_LENGTH {
+        return Err(invalid());
+    }
+    let mut subtags = code.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(invalid());
+    }
+    for subtag in subtags {
+        if !(1..=8).contains(&subtag.len()) || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+/// Check that every translation is keyed by a well-formed language code and
+/// is not empty. `what` names the translated string in any error.
+pub fn check_translations(what: &str, translations: &Translations) -> Result<(), String> {
+    for (lang, translation) in translations {
+        check_lang_code(lang).map_err(|e| format!("Translation of {what}: {e}"))?;
+        if translation.is_empty() {
+            return Err(format!(
+                "Translation of {what} into '{lang}' must not be empty"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lang_codes() {
+        for code in ["cy", "en", "en-GB", "zh-Hant-TW", "sgn-BE-FR", "es-419"] {
+            assert_eq!(check_lang_code(code), Ok(()), "{code}");
+        }
+        for code in [
+            "",
+            "c",
+            "welsh",
+            "CY",
+            "en_GB",
+            "en-",
+            "-GB",
+            "en--GB",
+            "en-abcdefghi",
+            "cy!",
+        ] {
+            assert!(check_lang_code(code).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn translations() {
+        let ok = Translations::from([("cy".to_string(), "Etholiad".to_string())]);
+        assert_eq!(check_translations("the name", &ok), Ok(()));
+
+        let bad_code = Translations::from([("Welsh".to_string(), "Etholiad".to_string())]);
+        let error = check_translations("the name", &bad_code).unwrap_err();
+        assert!(error.contains("'Welsh' is not a language code"), "{error}");
+
+        let empty = Translations::from([("cy".to_string(), String::new())]);
+        let error = check_translations("the name", &empty).unwrap_err();
+        assert!(error.contains("into 'cy' must not be empty"), "{error}");
+    }
+}
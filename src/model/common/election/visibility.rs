@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// How much of a question's results the public may see once its election has
+/// ended. Admins always see everything.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResultVisibility {
+    /// Totals and winners are public.
+    #[default]
+    Full,
+    /// Only the winners are public; the totals are withheld, though the
+    /// ballots are still published so that they can be verified.
+    WinnerOnly,
+    /// Nothing is public beyond what is shown while the election is in progress.
+    AdminOnly,
+}
+
+impl ResultVisibility {
+    /// May the public see the question's totals?
+    pub fn shows_totals(self) -> bool {
+        self == Self::Full
+    }
+
+    /// May the public see the question's winners?
+    pub fn shows_winners(self) -> bool {
+        self != Self::AdminOnly
+    }
+}
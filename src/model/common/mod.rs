@@ -1,5 +1,12 @@
 //! Types compatible with both API and DB.
 
+#[cfg(feature = "client-types")]
 pub mod allowed_questions;
 pub mod ballot;
+pub mod board;
+pub mod canonical_json;
 pub mod election;
+#[cfg(feature = "client-types")]
+pub mod secret;
+#[cfg(feature = "client-types")]
+pub mod serde_string_map;
@@ -0,0 +1,95 @@
+use std::fmt::{Debug, Formatter};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+/// A secret value, such as a password or private key.
+///
+/// The contents are wiped from memory when dropped, and redacted from `Debug`
+/// output, so they can't leak via logging. Access to the contents must be
+/// explicit via [`Secret::expose`]. Serialisation is transparent.
+///
+/// Wrapped types must zeroize themselves on drop; heap-allocated types can be
+/// made to do so with [`Zeroizing`], e.g. [`SecretString`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret<T: ZeroizeOnDrop>(T);
+
+/// A secret string, such as a password.
+pub type SecretString = Secret<Zeroizing<String>>;
+
+impl<T: ZeroizeOnDrop> Secret<T> {
+    pub fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    /// Access the secret contents.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+// The only field zeroizes on drop, so we do too.
+impl<T: ZeroizeOnDrop> ZeroizeOnDrop for Secret<T> {}
+
+impl<T: ZeroizeOnDrop> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(string: String) -> Self {
+        Self::new(Zeroizing::new(string))
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(string: &str) -> Self {
+        string.to_string().into()
+    }
+}
+
+impl<T: ZeroizeOnDrop + Serialize> Serialize for Secret<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: ZeroizeOnDrop + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{from_bson, to_bson, Bson};
+
+    use super::*;
+
+    #[test]
+    fn redacted_debug() {
+        let secret = SecretString::from("hunter22");
+        assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+        assert_eq!(
+            format!("{:#?}", Some(&secret)),
+            "Some(\n    Secret([REDACTED]),\n)"
+        );
+        assert_eq!(secret.expose().as_str(), "hunter22");
+    }
+
+    #[test]
+    fn transparent_serde() {
+        let secret = SecretString::from("hunter22");
+        let bson = to_bson(&secret).unwrap();
+        assert_eq!(bson, Bson::String("hunter22".to_string()));
+        let parsed: SecretString = from_bson(bson).unwrap();
+        assert_eq!(parsed, secret);
+    }
+}
@@ -0,0 +1,44 @@
+//! Ser/deserialize a [`HashMap<K, V>`](std::collections::HashMap) as a
+//! [`HashMap<String, V>`](std::collections::HashMap) where `K: Display + FromStr`.
+//!
+//! Use via the attribute `#[serde(with = ...)]`.
+//! This is useful for BSON, since document keys must be strings but we may want
+//! to use different key types internally.
+//! In other words, any [`HashMap<K, V>`](std::collections::HashMap) we want to store in `MongoDB`
+//! must either have `K = String` or be annotated with this module.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Display,
+    V: Serialize,
+    S: serde::Serializer,
+{
+    let string_map = map
+        .iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect::<HashMap<_, _>>();
+
+    serde::Serialize::serialize(&string_map, serializer)
+}
+
+pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+where
+    K: FromStr + Eq + Hash,
+    V: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    HashMap::<String, V>::deserialize(deserializer).and_then(|string_map| {
+        string_map
+            .into_iter()
+            .map(|(s, v)| s.parse().map(|k| (k, v)))
+            .collect::<Result<_, _>>()
+            .map_err(|_| serde::de::Error::custom("failed to parse key"))
+    })
+}
@@ -1,12 +1,13 @@
 use std::ops::{Deref, DerefMut};
 
-use mongodb::error::Error as DbError;
+use mongodb::{bson::doc, error::Error as DbError};
 use serde::{Deserialize, Serialize};
 
 use crate::model::mongodb::{Coll, Id};
 
 pub const DEFAULT_ADMIN_USERNAME: &str = "replace-this-admin-asap";
-/// Password is "insecure".
+pub const DEFAULT_ADMIN_PASSWORD: &str = "insecure";
+/// Hash of [`DEFAULT_ADMIN_PASSWORD`].
 pub const DEFAULT_ADMIN_PASSWORD_HASH: &str = "$argon2i$v=19$m=4096,t=1,p=1$cG1lcGY0N2oyTDRHeThpWQ$tRcNjW70NB7V71nXdj6w2VrwrG9Hq9irrmyhMIVCp3o";
 
 /// Core admin user data.
@@ -14,6 +15,9 @@ pub const DEFAULT_ADMIN_PASSWORD_HASH: &str = "$argon2i$v=19$m=4096,t=1,p=1$cG1l
 pub struct AdminCore {
     pub username: String,
     pub password_hash: String,
+    /// Must the admin change their password before doing anything else?
+    #[serde(default)]
+    pub must_change_password: bool,
 }
 
 impl AdminCore {
@@ -30,6 +34,7 @@ impl Default for AdminCore {
         Self {
             username: DEFAULT_ADMIN_USERNAME.to_string(),
             password_hash: DEFAULT_ADMIN_PASSWORD_HASH.to_string(),
+            must_change_password: true,
         }
     }
 }
@@ -62,6 +67,8 @@ impl DerefMut for Admin {
 
 /// Ensure at least one admin user exists. If none do, create a default admin
 /// with known credentials, allowing the system to be bootstrapped.
+/// The default admin must change their password before doing anything else,
+/// and until they do, we complain loudly at every startup.
 pub async fn ensure_admin_exists(admins: &Coll<NewAdmin>) -> Result<(), DbError> {
     debug!("Ensuring at least one admin user exists");
     let num_admins = admins.count_documents(None, None).await?;
@@ -71,6 +78,19 @@ pub async fn ensure_admin_exists(admins: &Coll<NewAdmin>) -> Result<(), DbError>
         warn!("Created default admin user - this must be replaced ASAP");
     }
 
+    let default_admin = admins
+        .find_one(doc! { "username": DEFAULT_ADMIN_USERNAME }, None)
+        .await?;
+    if default_admin.is_some_and(|admin| admin.verify_password(DEFAULT_ADMIN_PASSWORD)) {
+        warn!("****************************************************************");
+        warn!(
+            "* The default admin '{}' still has the default password!",
+            DEFAULT_ADMIN_USERNAME
+        );
+        warn!("* Log in and change it, or create another admin and delete it.");
+        warn!("****************************************************************");
+    }
+
     Ok(())
 }
 
@@ -84,6 +104,7 @@ mod examples {
             Self {
                 username: "alice112".to_string(),
                 password_hash: "$argon2i$v=19$m=4096,t=2,p=1$T1pCQllCT2hGRTR0M2N0MQ$WEW073jjInrJFZ6h2kLX6hxqBCDFGh/NNJhbhWP/Dlo".to_string(),
+                must_change_password: false,
             }
         }
 
@@ -91,6 +112,7 @@ mod examples {
             Self {
                 username: "bobthesuperadmin".to_string(),
                 password_hash: "$argon2i$v=19$m=4096,t=2,p=1$T1pCQllCT2hGRTR0M2N0MQ$ixygmz+0rD8rpITYQ5tZYHtBhR7UJrCSx/8MzYg8NqM".to_string(),
+                must_change_password: false,
             }
         }
     }
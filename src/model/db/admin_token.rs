@@ -0,0 +1,86 @@
+use std::ops::{Deref, DerefMut};
+
+use chrono::{DateTime, Utc};
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use mongodb::bson::{self, serde_helpers::chrono_datetime_as_bson_datetime};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::model::mongodb::Id;
+
+/// Core personal access token data, as stored in the database.
+///
+/// Only a hash of the secret is stored; the secret itself is shown to the
+/// admin once, when the token is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminTokenCore {
+    /// The admin this token authenticates as.
+    pub admin_id: Id,
+    /// A name chosen by the admin, to tell their tokens apart.
+    pub name: String,
+    /// Hex SHA-256 hash of the secret.
+    pub secret_hash: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    /// When the token stops working, if ever. Expired tokens are also deleted
+    /// by a TTL index, but that only runs periodically.
+    pub expires_at: Option<bson::DateTime>,
+}
+
+impl AdminTokenCore {
+    /// Create a token for the given admin, returning it along with its secret.
+    pub fn generate(
+        admin_id: Id,
+        name: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> (Self, String) {
+        let secret: [u8; 32] = rand::thread_rng().gen();
+        let secret = BASE64URL_NOPAD.encode(&secret);
+        let token = Self {
+            admin_id,
+            name,
+            secret_hash: hash_secret(&secret),
+            created_at: Utc::now(),
+            expires_at: expires_at.map(bson::DateTime::from_chrono),
+        };
+        (token, secret)
+    }
+
+    /// Has this token expired?
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expiry| expiry.to_chrono() <= Utc::now())
+    }
+}
+
+/// Hash a token secret for storage or lookup.
+pub fn hash_secret(secret: &str) -> String {
+    HEXLOWER.encode(&Sha256::digest(secret.as_bytes()))
+}
+
+/// A new token ready for DB insertion is just [`AdminToken`] without an ID.
+pub type NewAdminToken = AdminTokenCore;
+
+/// A personal access token from the database, with its unique ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminToken {
+    #[serde(rename = "_id")]
+    pub id: Id,
+    #[serde(flatten)]
+    pub token: AdminTokenCore,
+}
+
+impl Deref for AdminToken {
+    type Target = AdminTokenCore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.token
+    }
+}
+
+impl DerefMut for AdminToken {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.token
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use mongodb::{bson::serde_helpers::chrono_datetime_as_bson_datetime, error::Error as DbError};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    logging::RequestId,
+    model::mongodb::{Coll, Id},
+};
+
+/// A record of a sensitive action taken by an admin, kept for accountability.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    #[serde(rename = "_id")]
+    pub id: Id,
+    /// When the action was taken.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub time: DateTime<Utc>,
+    /// Username of the admin who took the action.
+    pub admin: String,
+    /// What was done, e.g. `voter_lookup`.
+    pub action: String,
+    /// Details of the action. Must not contain personal data in the clear.
+    pub detail: String,
+}
+
+impl AuditLogEntry {
+    /// Create an entry for an action being taken now.
+    pub fn new(admin: String, action: impl Into<String>, detail: String) -> Self {
+        Self {
+            id: Id::new(),
+            time: Utc::now(),
+            admin,
+            action: action.into(),
+            detail,
+        }
+    }
+
+    /// Write this entry to the audit log.
+    pub async fn record(
+        self,
+        audit_log: &Coll<AuditLogEntry>,
+        request_id: RequestId,
+    ) -> Result<(), DbError> {
+        audit_log.insert_one(&self, None).await?;
+        info!(
+            "  req{} Audit: admin {} {}: {}",
+            request_id, self.admin, self.action, self.detail
+        );
+        Ok(())
+    }
+}
@@ -3,21 +3,36 @@ use std::ops::{Deref, DerefMut};
 
 use chrono::{DateTime, Utc};
 use dre_ip::{Ballot as DreipBallot, CandidateTotals};
-use mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use mongodb::{
+    bson::{self, doc, serde_helpers::chrono_datetime_as_bson_datetime, Document},
+    error::Error as DbError,
+    Database,
+};
 use rand::{CryptoRng, RngCore};
-use serde::{Deserialize, Serialize};
+use rocket::futures::TryStreamExt;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::model::{
+    api::receipt::{calc_confirmation_code, confirmation_phrase},
     common::{
-        ballot::{Audited, BallotCrypto, BallotId, BallotState, Confirmed, Unconfirmed},
+        ballot::{
+            AuditExtraData, Audited, AuditedRedacted, BallotCrypto, BallotId, BallotState,
+            Confirmed, Unconfirmed, AUDITED,
+        },
         election::{CandidateId, DreipGroup, ElectionId, QuestionId},
     },
-    db::election::Election,
-    mongodb::Id,
+    db::{
+        election::Election,
+        field_encryption::{self, FieldKey},
+    },
+    mongodb::{Coll, Id},
 };
 
 /// Core ballot data, as stored in the database.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+///
+/// If a [`FieldKey`] is installed, the vote secrets of audited ballots are
+/// sealed with it when serialised, and unsealed when deserialised.
+#[derive(Debug, Clone)]
 pub struct BallotCore<S: BallotState> {
     /// Ballot ID. Unlike most IDs, this is an incrementing index, as it will
     /// be directly seen by voters and needs to be user-friendly.
@@ -27,13 +42,154 @@ pub struct BallotCore<S: BallotState> {
     /// Foreign Key question ID.
     pub question_id: QuestionId,
     /// Ballot creation time, used to automatically expire unconfirmed votes.
-    #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub creation_time: DateTime<Utc>,
+    /// Per-question sequence number of the latest change to this ballot: the
+    /// length of the question's bulletin board once that change was appended.
+    /// Zero for ballots last changed before this was recorded.
+    pub last_event_seq: u64,
     /// The cryptographic data.
-    #[serde(flatten)]
     pub crypto: BallotCrypto<S::InternalSecrets>,
     /// The current state of the ballot.
     pub state: S,
+    /// The candidate revealed by auditing, so that audited ballots can be
+    /// filtered by candidate. Absent in other states. Ballots audited before
+    /// this was recorded lack it until [`ensure_audited_candidates_exist`]
+    /// fills it in.
+    ///
+    /// Unlike the vote secrets, this is never sealed: every audited receipt
+    /// and dump publishes it anyway, and it must stay readable by the index
+    /// that the candidate filter uses.
+    pub state_data: Option<AuditExtraData>,
+    /// When the ballot was audited or confirmed. Absent for unconfirmed
+    /// ballots, and for ballots which changed state before this was recorded.
+    pub state_changed_at: Option<bson::DateTime>,
+    /// The name written in by the voter, for a vote for the write-in
+    /// candidate. This is outside the cryptography and the receipt, so cannot
+    /// be verified, and is dropped if the ballot is audited.
+    pub write_in_name: Option<String>,
+}
+
+/// The database layout of a [`BallotCore`], with its cryptographic data as a
+/// raw document so that its secrets can be sealed.
+#[derive(Deserialize, Serialize)]
+struct StoredBallotCore<S> {
+    ballot_id: BallotId,
+    election_id: ElectionId,
+    question_id: QuestionId,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    creation_time: DateTime<Utc>,
+    #[serde(default)]
+    last_event_seq: u64,
+    #[serde(flatten)]
+    crypto: Document,
+    state: S,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state_data: Option<AuditExtraData>,
+    /// Absent for ballots which changed state before this was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state_changed_at: Option<bson::DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    write_in_name: Option<String>,
+    /// The phrase derived from the ballot's confirmation code, stored so that
+    /// ballots can be found by it. Only ever written; it is recalculated when
+    /// needed. Absent for ballots stored before this was recorded, until
+    /// [`ensure_confirmation_phrases_exist`] fills it in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confirmation_phrase: Option<String>,
+    /// Set on audited ballots whose secrets have been removed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    redacted: bool,
+}
+
+impl<S: BallotState> BallotCore<S> {
+    /// Associated data binding sealed secrets to this ballot, so that they
+    /// cannot be moved to another.
+    fn sealing_aad(
+        election_id: ElectionId,
+        question_id: QuestionId,
+        ballot_id: BallotId,
+    ) -> String {
+        format!("{election_id}/{question_id}/{ballot_id}")
+    }
+
+    /// Convert to the database layout, sealing the secrets of audited ballots
+    /// if a key is given.
+    fn to_stored(&self, key: Option<&FieldKey>) -> Result<StoredBallotCore<S>, String> {
+        let mut crypto = bson::to_document(&self.crypto)
+            .map_err(|e| format!("Failed to serialise ballot crypto: {e}"))?;
+        if let Some(key) = key {
+            if self.state.as_ref() == Audited.as_ref() {
+                let aad = Self::sealing_aad(self.election_id, self.question_id, self.ballot_id);
+                key.seal(&mut crypto, &aad)?;
+            }
+        }
+        Ok(StoredBallotCore {
+            ballot_id: self.ballot_id,
+            election_id: self.election_id,
+            question_id: self.question_id,
+            creation_time: self.creation_time,
+            last_event_seq: self.last_event_seq,
+            crypto,
+            state: self.state,
+            state_data: self.state_data.clone(),
+            state_changed_at: self.state_changed_at,
+            write_in_name: self.write_in_name.clone(),
+            confirmation_phrase: Some(confirmation_phrase(&calc_confirmation_code(self))),
+            redacted: self.state.as_ref() == AuditedRedacted.as_ref(),
+        })
+    }
+
+    /// Convert from the database layout, unsealing the secrets with the given
+    /// key if they are sealed.
+    fn from_stored(
+        mut stored: StoredBallotCore<S>,
+        key: Option<&FieldKey>,
+    ) -> Result<Self, String> {
+        // Audited ballots with and without secrets share a state, so only the
+        // flag tells them apart.
+        if stored.redacted != (stored.state.as_ref() == AuditedRedacted.as_ref()) {
+            return Err("Ballot redaction does not match its state".to_string());
+        }
+        if stored
+            .crypto
+            .contains_key(field_encryption::SEALED_SECRETS_FIELD)
+        {
+            let key = key.ok_or_else(|| {
+                "Ballot secrets are sealed, but no field encryption key is configured".to_string()
+            })?;
+            let aad = Self::sealing_aad(stored.election_id, stored.question_id, stored.ballot_id);
+            key.unseal(&mut stored.crypto, &aad)?;
+        }
+        let crypto = bson::from_document(stored.crypto)
+            .map_err(|e| format!("Failed to deserialise ballot crypto: {e}"))?;
+        Ok(BallotCore {
+            ballot_id: stored.ballot_id,
+            election_id: stored.election_id,
+            question_id: stored.question_id,
+            creation_time: stored.creation_time,
+            last_event_seq: stored.last_event_seq,
+            crypto,
+            state: stored.state,
+            state_data: stored.state_data,
+            state_changed_at: stored.state_changed_at,
+            write_in_name: stored.write_in_name,
+        })
+    }
+}
+
+impl<S: BallotState + Serialize> Serialize for BallotCore<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.to_stored(field_encryption::installed())
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, S: BallotState + Deserialize<'de>> Deserialize<'de> for BallotCore<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = StoredBallotCore::deserialize(deserializer)?;
+        Self::from_stored(stored, field_encryption::installed()).map_err(de::Error::custom)
+    }
 }
 
 impl BallotCore<Unconfirmed> {
@@ -61,20 +217,29 @@ impl BallotCore<Unconfirmed> {
             election_id,
             question_id,
             creation_time,
+            last_event_seq: 0,
             crypto,
             state: Unconfirmed,
+            state_data: None,
+            state_changed_at: None,
+            write_in_name: None,
         })
     }
 
     /// Audit this ballot.
     pub fn audit(self) -> BallotCore<Audited> {
+        let state_data = Audited::receipt_data(&self.crypto, None);
         BallotCore {
             ballot_id: self.ballot_id,
             election_id: self.election_id,
             question_id: self.question_id,
             creation_time: self.creation_time,
+            last_event_seq: self.last_event_seq,
             crypto: self.crypto,
             state: Audited,
+            state_data: Some(state_data),
+            state_changed_at: Some(bson::DateTime::now()),
+            write_in_name: None,
         }
     }
 
@@ -88,8 +253,32 @@ impl BallotCore<Unconfirmed> {
             election_id: self.election_id,
             question_id: self.question_id,
             creation_time: self.creation_time,
+            last_event_seq: self.last_event_seq,
             crypto: self.crypto.confirm(totals.into()),
             state: Confirmed,
+            state_data: None,
+            state_changed_at: Some(bson::DateTime::now()),
+            write_in_name: self.write_in_name,
+        }
+    }
+}
+
+impl BallotCore<Audited> {
+    /// Remove this ballot's secrets, keeping the candidate they revealed.
+    /// This is not a change of state on the bulletin board.
+    pub fn redact(self) -> BallotCore<AuditedRedacted> {
+        let state_data = Audited::receipt_data(&self.crypto, None);
+        BallotCore {
+            ballot_id: self.ballot_id,
+            election_id: self.election_id,
+            question_id: self.question_id,
+            creation_time: self.creation_time,
+            last_event_seq: self.last_event_seq,
+            crypto: Audited::remove_internal_secrets(&self.crypto),
+            state: AuditedRedacted,
+            state_data: Some(state_data),
+            state_changed_at: self.state_changed_at,
+            write_in_name: None,
         }
     }
 }
@@ -128,6 +317,24 @@ impl Ballot<Unconfirmed> {
     }
 }
 
+impl Ballot<Audited> {
+    pub fn redact(self) -> Ballot<AuditedRedacted> {
+        Ballot {
+            internal_id: self.internal_id,
+            ballot: self.ballot.redact(),
+        }
+    }
+
+    /// This ballot as it would be stored with its secrets sealed with the
+    /// given key, regardless of the installed key.
+    pub(crate) fn sealed_with(&self, key: &FieldKey) -> Result<Document, String> {
+        let mut stored = bson::to_document(&self.ballot.to_stored(Some(key))?)
+            .map_err(|e| format!("Failed to serialise ballot: {e}"))?;
+        stored.insert("_id", *self.internal_id);
+        Ok(stored)
+    }
+}
+
 impl<S: BallotState> Deref for Ballot<S> {
     type Target = BallotCore<S>;
 
@@ -145,10 +352,363 @@ impl<S: BallotState> DerefMut for Ballot<S> {
 /// A ballot in any state.
 /// With the untagged representation, all `Ballot<S: BallotState>` can directly
 /// deserialize to this type.
+/// Any document that fails to match a known state falls through to `Unknown`,
+/// so a single bad document cannot break an entire query.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum AnyBallot {
     Unconfirmed(Ballot<Unconfirmed>),
     Audited(Ballot<Audited>),
+    AuditedRedacted(Ballot<AuditedRedacted>),
     Confirmed(Ballot<Confirmed>),
+    Unknown(UnknownBallot),
+}
+
+/// A ballot document that could not be interpreted as any known state,
+/// e.g. due to a state introduced by a newer server version or a manual DB edit.
+/// We keep just enough to identify the offending document.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnknownBallot {
+    /// The database ID of the offending document.
+    #[serde(rename = "_id")]
+    pub internal_id: Id,
+    /// The raw state string.
+    pub state: String,
+}
+
+/// Store the confirmation phrase of every ballot stored before phrases were,
+/// so that they can be found by it, returning how many were filled in.
+/// Ballots in an unknown state are left alone.
+pub async fn ensure_confirmation_phrases_exist(db: &Database) -> Result<usize, DbError> {
+    debug!("Ensuring ballots have confirmation phrases");
+    let ballots = Coll::<AnyBallot>::from_db(db);
+    let mut missing = ballots
+        .find(doc! { "confirmation_phrase": { "$exists": false } }, None)
+        .await?;
+    let mut filled = 0;
+    while let Some(ballot) = missing.try_next().await? {
+        let (internal_id, code) = match &ballot {
+            AnyBallot::Unconfirmed(ballot) => (ballot.internal_id, calc_confirmation_code(ballot)),
+            AnyBallot::Audited(ballot) => (ballot.internal_id, calc_confirmation_code(ballot)),
+            AnyBallot::AuditedRedacted(ballot) => {
+                (ballot.internal_id, calc_confirmation_code(ballot))
+            }
+            AnyBallot::Confirmed(ballot) => (ballot.internal_id, calc_confirmation_code(ballot)),
+            AnyBallot::Unknown(_) => continue,
+        };
+        ballots
+            .update_one(
+                internal_id.as_doc(),
+                doc! { "$set": { "confirmation_phrase": confirmation_phrase(&code) } },
+                None,
+            )
+            .await?;
+        filled += 1;
+    }
+    if filled > 0 {
+        warn!("Filled in the confirmation phrases of {} ballots", filled);
+    }
+    Ok(filled)
+}
+
+/// Store the candidate revealed by every ballot audited before it was
+/// recorded, so that it can be filtered by candidate, returning how many were
+/// filled in. Ballots which cannot be read are left alone.
+pub async fn ensure_audited_candidates_exist(db: &Database) -> Result<usize, DbError> {
+    debug!("Ensuring audited ballots have revealed candidates");
+    let ballots = Coll::<AnyBallot>::from_db(db);
+    let filter = doc! {
+        "state": AUDITED,
+        "redacted": { "$ne": true },
+        "state_data": { "$exists": false },
+    };
+    let mut missing = ballots.find(filter, None).await?;
+    let mut filled = 0;
+    while let Some(ballot) = missing.try_next().await? {
+        let AnyBallot::Audited(ballot) = ballot else {
+            continue;
+        };
+        let state_data = Audited::receipt_data(&ballot.crypto, None);
+        let state_data = bson::to_bson(&state_data).unwrap(); // Cannot fail.
+        ballots
+            .update_one(
+                ballot.internal_id.as_doc(),
+                doc! { "$set": { "state_data": state_data } },
+                None,
+            )
+            .await?;
+        filled += 1;
+    }
+    if filled > 0 {
+        warn!(
+            "Filled in the revealed candidates of {} audited ballots",
+            filled
+        );
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::BASE64;
+    use mongodb::bson::{from_document, to_document, Bson};
+    use serde::de::DeserializeOwned;
+
+    use crate::model::mongodb::MongoCollection;
+
+    use super::*;
+
+    fn key(byte: u8) -> FieldKey {
+        FieldKey::from_base64(&BASE64.encode(&[byte; 32])).unwrap()
+    }
+
+    fn unconfirmed_ballot() -> BallotCore<Unconfirmed> {
+        let election = Election::published_example();
+        let question = election.questions.values().next().unwrap();
+        let mut candidates = question.candidates.iter().cloned();
+        let yes = candidates.next().unwrap();
+        BallotCore::new(
+            1,
+            question.id,
+            yes,
+            candidates,
+            &election,
+            rand::thread_rng(),
+        )
+        .unwrap()
+    }
+
+    /// The vote secrets of a ballot, as they would be stored in plaintext.
+    fn plaintext_secrets(ballot: &BallotCore<Audited>) -> Vec<String> {
+        let crypto = to_document(&ballot.crypto).unwrap();
+        crypto
+            .get_document("votes")
+            .unwrap()
+            .values()
+            .flat_map(|vote| {
+                let vote = vote.as_document().unwrap();
+                [vote.get("r").unwrap(), vote.get("v").unwrap()]
+            })
+            .map(Bson::to_string)
+            .collect()
+    }
+
+    fn stored<S: BallotState + Serialize>(
+        ballot: &BallotCore<S>,
+        key: Option<&FieldKey>,
+    ) -> Document {
+        to_document(&ballot.to_stored(key).unwrap()).unwrap()
+    }
+
+    fn load<S: BallotState + DeserializeOwned>(
+        stored: Document,
+        key: Option<&FieldKey>,
+    ) -> Result<BallotCore<S>, String> {
+        BallotCore::from_stored(from_document(stored).unwrap(), key)
+    }
+
+    #[test]
+    fn storage_format_unchanged_without_key() {
+        let ballot = unconfirmed_ballot().audit();
+        let stored = stored(&ballot, None);
+
+        // The crypto is stored exactly as the underlying DRE-ip ballot would be.
+        let crypto = to_document(&ballot.crypto).unwrap();
+        for (field, value) in &crypto {
+            assert_eq!(stored.get(field), Some(value));
+        }
+        assert!(!stored.contains_key(field_encryption::SEALED_SECRETS_FIELD));
+
+        // And it round-trips.
+        let loaded: BallotCore<Audited> = load(stored, None).unwrap();
+        assert_eq!(to_document(&loaded.crypto).unwrap(), crypto);
+    }
+
+    #[test]
+    fn audited_secrets_sealed_with_key() {
+        let key = key(1);
+        let ballot = unconfirmed_ballot().audit();
+        let stored = stored(&ballot, Some(&key));
+
+        // No secrets are stored in plaintext.
+        assert!(stored.contains_key(field_encryption::SEALED_SECRETS_FIELD));
+        let raw = stored.to_string();
+        for secret in plaintext_secrets(&ballot) {
+            assert!(!raw.contains(&secret));
+        }
+
+        // They round-trip with the same key.
+        let loaded: BallotCore<Audited> = load(stored.clone(), Some(&key)).unwrap();
+        assert_eq!(
+            to_document(&loaded.crypto).unwrap(),
+            to_document(&ballot.crypto).unwrap()
+        );
+
+        // But cannot be read without it, with another key, or on another ballot.
+        assert!(load::<Audited>(stored.clone(), None).is_err());
+        assert!(load::<Audited>(stored.clone(), Some(&self::key(2))).is_err());
+        let mut moved = stored;
+        moved.insert("ballot_id", 2);
+        assert!(load::<Audited>(moved, Some(&key)).is_err());
+    }
+
+    #[test]
+    fn other_states_not_sealed() {
+        let key = key(1);
+        let ballot = unconfirmed_ballot();
+        assert!(!stored(&ballot, Some(&key)).contains_key(field_encryption::SEALED_SECRETS_FIELD));
+        let ballot = ballot.confirm(&mut HashMap::new());
+        assert!(!stored(&ballot, Some(&key)).contains_key(field_encryption::SEALED_SECRETS_FIELD));
+    }
+
+    #[test]
+    fn redaction_removes_secrets() {
+        let key = key(1);
+        let ballot = unconfirmed_ballot().audit();
+        let candidate = ballot.state_data.clone().unwrap();
+        let redacted = ballot.clone().redact();
+        let stored = stored(&redacted, Some(&key));
+
+        // Nothing secret is left, sealed or otherwise.
+        assert_eq!(stored.get_bool("redacted"), Ok(true));
+        assert_eq!(stored.get_str("state"), Ok("Audited"));
+        assert!(!stored.contains_key(field_encryption::SEALED_SECRETS_FIELD));
+        let raw = stored.to_string();
+        for secret in plaintext_secrets(&ballot) {
+            assert!(!raw.contains(&secret));
+        }
+
+        // The redacted ballot keeps its candidate, and is only read as redacted.
+        let loaded: BallotCore<AuditedRedacted> = load(stored.clone(), Some(&key)).unwrap();
+        assert_eq!(loaded.state_data, Some(candidate));
+        assert!(load::<Audited>(stored, Some(&key)).is_err());
+        assert!(load::<AuditedRedacted>(self::stored(&ballot, None), None).is_err());
+    }
+
+    #[backend_test]
+    async fn seal_existing_ballots(db: Database) -> anyhow::Result<()> {
+        // Seed an audited ballot stored before a key was configured, and a
+        // redacted one, which has no secrets to seal.
+        let key = key(1);
+        let audited = Ballot {
+            internal_id: Id::new(),
+            ballot: unconfirmed_ballot().audit(),
+        };
+        let redacted = Ballot {
+            internal_id: Id::new(),
+            ballot: unconfirmed_ballot().audit(),
+        }
+        .redact();
+        let raw_ballots = db.collection::<Document>(AnyBallot::NAME);
+        raw_ballots
+            .insert_many([to_document(&audited)?, to_document(&redacted)?], None)
+            .await?;
+
+        // Only the audited ballot is sealed, leaving no secrets in plaintext.
+        assert_eq!(
+            field_encryption::seal_existing_ballots_with(&db, &key).await?,
+            1
+        );
+        let mut sealed = raw_ballots
+            .find_one(audited.internal_id.as_doc(), None)
+            .await?
+            .unwrap();
+        assert!(sealed.contains_key(field_encryption::SEALED_SECRETS_FIELD));
+        let raw = sealed.to_string();
+        for secret in plaintext_secrets(&audited) {
+            assert!(!raw.contains(&secret));
+        }
+        let redacted = raw_ballots
+            .find_one(redacted.internal_id.as_doc(), None)
+            .await?
+            .unwrap();
+        assert!(!redacted.contains_key(field_encryption::SEALED_SECRETS_FIELD));
+
+        // The sealed ballot still reads back the same with the key.
+        sealed.remove("_id");
+        let loaded: BallotCore<Audited> = load(sealed, Some(&key)).unwrap();
+        assert_eq!(
+            to_document(&loaded.crypto).unwrap(),
+            to_document(&audited.crypto).unwrap()
+        );
+        assert_eq!(loaded.state_data, audited.state_data);
+
+        // Rerunning is harmless.
+        assert_eq!(
+            field_encryption::seal_existing_ballots_with(&db, &key).await?,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[backend_test]
+    async fn legacy_confirmation_phrases(db: Database) -> anyhow::Result<()> {
+        // Seed a ballot stored before its confirmation phrase was.
+        let ballot = Ballot {
+            internal_id: Id::new(),
+            ballot: unconfirmed_ballot(),
+        };
+        let mut legacy = to_document(&ballot).unwrap();
+        assert!(legacy.remove("confirmation_phrase").is_some());
+        let raw_ballots = db.collection::<Document>(AnyBallot::NAME);
+        raw_ballots.insert_one(legacy, None).await?;
+
+        // Its phrase is filled in, so it can be found by it.
+        assert_eq!(ensure_confirmation_phrases_exist(&db).await?, 1);
+        let phrase = confirmation_phrase(&calc_confirmation_code(&ballot));
+        let found = raw_ballots
+            .find_one(doc! { "confirmation_phrase": &phrase }, None)
+            .await?;
+        assert_eq!(
+            found.and_then(|doc| doc.get_object_id("_id").ok()),
+            Some(*ballot.internal_id)
+        );
+
+        // Rerunning is harmless.
+        assert_eq!(ensure_confirmation_phrases_exist(&db).await?, 0);
+
+        Ok(())
+    }
+
+    #[backend_test]
+    async fn legacy_audited_candidates(db: Database) -> anyhow::Result<()> {
+        // Seed a ballot audited before its revealed candidate was recorded,
+        // and an unconfirmed one, which has none to record.
+        let audited = Ballot {
+            internal_id: Id::new(),
+            ballot: unconfirmed_ballot().audit(),
+        };
+        let candidate = audited.state_data.clone().unwrap();
+        let mut legacy = to_document(&audited).unwrap();
+        assert!(legacy.remove("state_data").is_some());
+        let unconfirmed = Ballot {
+            internal_id: Id::new(),
+            ballot: unconfirmed_ballot(),
+        };
+        let raw_ballots = db.collection::<Document>(AnyBallot::NAME);
+        raw_ballots
+            .insert_many([legacy, to_document(&unconfirmed).unwrap()], None)
+            .await?;
+
+        // Only the audited ballot's candidate is filled in, so it can be found by it.
+        assert_eq!(ensure_audited_candidates_exist(&db).await?, 1);
+        let found = raw_ballots
+            .find_one(doc! { "state_data.candidate": &candidate.candidate }, None)
+            .await?;
+        assert_eq!(
+            found.and_then(|doc| doc.get_object_id("_id").ok()),
+            Some(*audited.internal_id)
+        );
+        let unconfirmed = raw_ballots
+            .find_one(unconfirmed.internal_id.as_doc(), None)
+            .await?
+            .unwrap();
+        assert!(!unconfirmed.contains_key("state_data"));
+
+        // Rerunning is harmless.
+        assert_eq!(ensure_audited_candidates_exist(&db).await?, 0);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use mongodb::{
+    bson::doc,
+    error::Error as DbError,
+    options::{FindOneOptions, ReplaceOptions},
+    ClientSession,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    logging::RequestId,
+    model::{
+        api::receipt::calc_confirmation_code,
+        common::{
+            ballot::BallotState,
+            board::{BoardEvent, GENESIS_HASH},
+            election::{ElectionId, QuestionId},
+        },
+        db::ballot::BallotCore,
+        mongodb::{Coll, Id, RequestComment},
+    },
+};
+
+/// Get the unique ID for the board head of the given question.
+pub fn board_head_id(election_id: ElectionId, question_id: QuestionId) -> String {
+    format!("board:{}:{}", election_id, question_id)
+}
+
+/// The current head of a question's bulletin board hash chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardHead {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub election_id: ElectionId,
+    pub question_id: QuestionId,
+    /// Number of events in the chain.
+    pub length: u64,
+    /// Hash of the last event in the chain.
+    pub head: String,
+}
+
+impl BoardHead {
+    /// The head of a chain with no events yet.
+    pub fn genesis(election_id: ElectionId, question_id: QuestionId) -> Self {
+        Self {
+            id: board_head_id(election_id, question_id),
+            election_id,
+            question_id,
+            length: 0,
+            head: GENESIS_HASH.to_string(),
+        }
+    }
+}
+
+/// A single event on a question's bulletin board hash chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardEntry {
+    #[serde(rename = "_id")]
+    pub id: Id,
+    pub election_id: ElectionId,
+    pub question_id: QuestionId,
+    #[serde(flatten)]
+    pub event: BoardEvent,
+}
+
+/// Append an event for each of the given ballots, in order, to their questions'
+/// hash chains. The ballots should be in the state they are being moved into,
+/// and are stamped with their new `last_event_seq`.
+///
+/// This must be called in the same transaction that writes the ballots, and
+/// before writing them so that they are stored with the stamp. This ensures the
+/// chain can never disagree with the ballots themselves.
+/// Concurrent appends to the same chain conflict on the head document, causing
+/// the transaction to be retried.
+pub async fn append_to_board<'a, S: BallotState + 'a>(
+    ballots: impl IntoIterator<Item = &'a mut BallotCore<S>>,
+    board_heads: &Coll<BoardHead>,
+    board_entries: &Coll<BoardEntry>,
+    session: &mut ClientSession,
+    request_id: Option<RequestId>,
+) -> Result<(), DbError> {
+    // Map from (election, question) to (original length, updated head).
+    let mut heads = HashMap::new();
+    let mut new_entries = Vec::new();
+    for ballot in ballots {
+        let key = (ballot.election_id, ballot.question_id);
+        if !heads.contains_key(&key) {
+            let filter = doc! {
+                "_id": board_head_id(ballot.election_id, ballot.question_id),
+            };
+            let options = request_id.map(FindOneOptions::for_request);
+            let head = board_heads
+                .find_one_with_session(filter, options, session)
+                .await?
+                .unwrap_or_else(|| BoardHead::genesis(ballot.election_id, ballot.question_id));
+            heads.insert(key, (head.length, head));
+        }
+        let (_, head) = heads.get_mut(&key).unwrap(); // Just inserted if missing.
+
+        let event = BoardEvent::new(
+            head.length,
+            &head.head,
+            ballot.ballot_id,
+            ballot.state,
+            calc_confirmation_code(ballot),
+        );
+        head.length += 1;
+        head.head = event.hash.clone();
+        ballot.last_event_seq = head.length;
+        new_entries.push(BoardEntry {
+            id: Id::new(),
+            election_id: ballot.election_id,
+            question_id: ballot.question_id,
+            event,
+        });
+    }
+    if new_entries.is_empty() {
+        return Ok(());
+    }
+
+    board_entries
+        .insert_many_with_session(&new_entries, None, session)
+        .await?;
+    for (original_length, head) in heads.into_values() {
+        let filter = doc! {
+            "_id": &head.id,
+            // Concurrency: only match if nobody else has appended since we read.
+            "length": original_length as i64,
+        };
+        let mut options = ReplaceOptions::builder().upsert(true).build();
+        if let Some(request_id) = request_id {
+            options = options.with_request_id(request_id);
+        }
+        board_heads
+            .replace_one_with_session(filter, &head, options, session)
+            .await?;
+    }
+    if let Some(request_id) = request_id {
+        trace!(
+            "  req{} Appended {} events to the bulletin board",
+            request_id,
+            new_entries.len()
+        );
+    }
+
+    Ok(())
+}
@@ -1,11 +1,34 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
 
 use dre_ip::CandidateTotals as DreipTotals;
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    error::Error as DbError,
+    options::{FindOptions, ReplaceOptions},
+    Client, ClientSession, Database,
+};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    futures::{FutureExt, TryStreamExt},
+    tokio::{self, sync::Notify},
+    Build, Rocket,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::model::{
-    common::election::{CandidateId, DreipGroup, ElectionId, QuestionId},
-    mongodb::Id,
+use crate::{
+    logging::RequestId,
+    model::{
+        common::{
+            ballot::{Confirmed, Unconfirmed},
+            election::{CandidateId, DreipGroup, ElectionId, ElectionState, QuestionId},
+        },
+        db::{ballot::Ballot, election::Election},
+        mongodb::{Coll, Id, RequestComment},
+    },
 };
 
 /// Core candidate totals data, linked to a specific election->question->candidate.
@@ -16,6 +39,11 @@ pub struct CandidateTotalsCore {
     pub candidate_name: CandidateId,
     #[serde(flatten)]
     pub crypto: DreipTotals<DreipGroup>,
+    /// Number of confirmed ballots counted in the totals.
+    /// Only missing from totals stored before it was recorded, until
+    /// [`ensure_confirmed_counts_exist`] fills it in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_ballot_count: Option<u64>,
 }
 
 impl CandidateTotalsCore {
@@ -30,6 +58,7 @@ impl CandidateTotalsCore {
             question_id,
             candidate_name,
             crypto: DreipTotals::default(),
+            confirmed_ballot_count: Some(0),
         }
     }
 }
@@ -60,3 +89,566 @@ impl DerefMut for CandidateTotals {
         &mut self.totals
     }
 }
+
+/// A confirmed ballot's contribution to its question's candidate totals.
+///
+/// Confirming a ballot records one of these rather than updating the totals
+/// directly, so that concurrent confirmations of the same question do not all
+/// conflict on its totals. They are later folded into the totals in the
+/// background by a [`TotalsFolder`], and readers of the totals must include
+/// any not yet folded.
+///
+/// Like an unconfirmed ballot, a delta reveals its vote, so nothing in it
+/// identifies its ballot: its ID is random rather than an ordinary object ID,
+/// which would give away when it was confirmed. It is deleted as soon as it
+/// has been folded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalsDelta {
+    #[serde(rename = "_id")]
+    pub id: Id,
+    pub election_id: ElectionId,
+    pub question_id: QuestionId,
+    pub totals: HashMap<CandidateId, DreipTotals<DreipGroup>>,
+}
+
+impl TotalsDelta {
+    /// Confirm a ballot, returning it along with its contribution to the
+    /// totals of the given candidates.
+    pub fn confirm<'a>(
+        ballot: Ballot<Unconfirmed>,
+        candidates: impl IntoIterator<Item = &'a CandidateId>,
+    ) -> (Ballot<Confirmed>, Self) {
+        let mut totals = candidates
+            .into_iter()
+            .map(|candidate| (candidate.clone(), DreipTotals::default()))
+            .collect::<HashMap<_, _>>();
+        let (election_id, question_id) = (ballot.election_id, ballot.question_id);
+        let confirmed = {
+            let mut totals_map = totals
+                .iter_mut()
+                .map(|(candidate, totals)| (candidate.clone(), totals))
+                .collect::<HashMap<_, _>>();
+            ballot.confirm(&mut totals_map)
+        };
+        let delta = Self {
+            id: ObjectId::from_bytes(rand::random()).into(),
+            election_id,
+            question_id,
+            totals,
+        };
+        (confirmed, delta)
+    }
+}
+
+/// Add the given deltas to a question's candidate totals, keyed by candidate,
+/// creating zero totals for any candidate that has none yet.
+pub fn apply_deltas<'a>(
+    totals: &mut HashMap<CandidateId, CandidateTotals>,
+    deltas: impl IntoIterator<Item = &'a TotalsDelta>,
+) {
+    for delta in deltas {
+        for (candidate, delta_totals) in &delta.totals {
+            let totals = totals
+                .entry(candidate.clone())
+                .or_insert_with(|| CandidateTotals {
+                    id: Id::new(),
+                    totals: NewCandidateTotals::new(
+                        delta.election_id,
+                        delta.question_id,
+                        candidate.clone(),
+                    ),
+                });
+            totals.crypto.tally += delta_totals.tally;
+            totals.crypto.r_sum += delta_totals.r_sum;
+            // Each delta is one confirmed ballot.
+            if let Some(count) = &mut totals.confirmed_ballot_count {
+                *count += 1;
+            }
+        }
+    }
+}
+
+/// Create zero totals for every candidate of every question in the given
+/// election that does not have totals yet, returning how many were created.
+///
+/// Totals are created when an election is published, so that they exist
+/// before any ballot is confirmed, and so that candidates nobody votes for
+/// still have totals.
+pub async fn create_zero_totals(
+    election: &Election,
+    candidate_totals: &Coll<CandidateTotals>,
+    session: &mut ClientSession,
+) -> Result<usize, DbError> {
+    let existing = candidate_totals
+        .find_with_session(doc! { "election_id": election.id }, None, session)
+        .await?
+        .stream(session)
+        .map_ok(|total| (total.question_id, total.totals.candidate_name))
+        .try_collect::<HashSet<_>>()
+        .await?;
+    let missing = election
+        .questions
+        .values()
+        .flat_map(|question| {
+            question
+                .candidates
+                .iter()
+                .map(|candidate| (question.id, candidate))
+        })
+        .filter(|(question_id, candidate)| {
+            !existing.contains(&(*question_id, candidate.to_string()))
+        })
+        .map(|(question_id, candidate)| {
+            NewCandidateTotals::new(election.id, question_id, candidate.clone())
+        })
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        candidate_totals
+            .clone_with_type::<NewCandidateTotals>()
+            .insert_many_with_session(&missing, None, session)
+            .await?;
+    }
+    Ok(missing.len())
+}
+
+/// Create zero totals for every published election that is missing some,
+/// e.g. one published before totals were created at publish time, returning
+/// how many were created.
+pub async fn ensure_published_totals_exist(
+    client: &Client,
+    db: &Database,
+) -> Result<usize, DbError> {
+    debug!("Ensuring published elections have candidate totals");
+    let elections = Coll::<Election>::from_db(db);
+    let candidate_totals = Coll::<CandidateTotals>::from_db(db);
+    let mut session = client.start_session(None).await?;
+    let mut published = elections
+        .find(doc! { "state": ElectionState::Published }, None)
+        .await?;
+    let mut created = 0;
+    while let Some(election) = published.try_next().await? {
+        created += create_zero_totals(&election, &candidate_totals, &mut session).await?;
+    }
+    if created > 0 {
+        warn!("Created {} missing candidate totals", created);
+    }
+    Ok(created)
+}
+
+/// Fill in the confirmed ballot count of any candidate totals stored before it
+/// was recorded, returning how many questions' totals were filled in.
+///
+/// The count is the question's confirmed ballots less its pending deltas, which
+/// are counted when they are folded. Each question is filled in by its own
+/// transaction, which conflicts with any concurrent fold of the question.
+pub async fn ensure_confirmed_counts_exist(
+    client: &Client,
+    db: &Database,
+) -> Result<usize, DbError> {
+    debug!("Ensuring candidate totals have confirmed ballot counts");
+    let candidate_totals = Coll::<CandidateTotals>::from_db(db);
+    let totals_deltas = Coll::<TotalsDelta>::from_db(db);
+    let ballots = Coll::<Ballot<Confirmed>>::from_db(db);
+    let questions = candidate_totals
+        .find(
+            doc! { "confirmed_ballot_count": { "$exists": false } },
+            None,
+        )
+        .await?
+        .map_ok(|total| (total.election_id, total.question_id))
+        .try_collect::<HashSet<_>>()
+        .await?;
+
+    let mut session = client.start_session(None).await?;
+    for &(election_id, question_id) in &questions {
+        session
+            .with_transaction(
+                (&candidate_totals, &totals_deltas, &ballots),
+                move |session, (candidate_totals, totals_deltas, ballots)| {
+                    async move {
+                        let confirmed = ballots
+                            .count_documents_with_session(
+                                doc! {
+                                    "election_id": election_id,
+                                    "question_id": question_id,
+                                    "state": Confirmed,
+                                },
+                                None,
+                                session,
+                            )
+                            .await?;
+                        let pending = totals_deltas
+                            .count_documents_with_session(
+                                doc! {
+                                    "election_id": election_id,
+                                    "question_id": question_id,
+                                },
+                                None,
+                                session,
+                            )
+                            .await?;
+                        let count = confirmed.saturating_sub(pending) as i64;
+                        candidate_totals
+                            .update_many_with_session(
+                                doc! {
+                                    "election_id": election_id,
+                                    "question_id": question_id,
+                                    "confirmed_ballot_count": { "$exists": false },
+                                },
+                                doc! { "$set": { "confirmed_ballot_count": count } },
+                                None,
+                                session,
+                            )
+                            .await?;
+                        Ok(())
+                    }
+                    .boxed()
+                },
+                None,
+            )
+            .await?;
+    }
+    if !questions.is_empty() {
+        warn!(
+            "Filled in confirmed ballot counts for {} questions",
+            questions.len()
+        );
+    }
+    Ok(questions.len())
+}
+
+/// Delete the totals of any candidates or questions no longer in the given
+/// election, returning how many were deleted.
+///
+/// This must only be used before the election has started, as it would
+/// otherwise delete votes.
+pub async fn delete_stale_totals(
+    election: &Election,
+    candidate_totals: &Coll<CandidateTotals>,
+    session: &mut ClientSession,
+) -> Result<u64, DbError> {
+    let mut filter = doc! {
+        "election_id": election.id,
+    };
+    let current = election
+        .questions
+        .values()
+        .map(|question| {
+            doc! {
+                "question_id": question.id,
+                "candidate_name": { "$in": question.candidates.clone() },
+            }
+        })
+        .collect::<Vec<_>>();
+    // `$nor` must not be empty; with no questions, every total is stale.
+    if !current.is_empty() {
+        filter.insert("$nor", current);
+    }
+    let result = candidate_totals
+        .delete_many_with_session(filter, None, session)
+        .await?;
+    Ok(result.deleted_count)
+}
+
+/// Read a question's candidate totals, keyed by candidate, including the
+/// deltas that have not been folded into them yet.
+///
+/// The session should be a snapshot session, so that a concurrent fold can
+/// neither hide a delta nor cause it to be counted twice. Each query is given
+/// up on after `max_time`, if any.
+pub async fn question_totals(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    candidate_totals: &Coll<CandidateTotals>,
+    totals_deltas: &Coll<TotalsDelta>,
+    session: &mut ClientSession,
+    max_time: Option<StdDuration>,
+    request_id: RequestId,
+) -> Result<HashMap<CandidateId, CandidateTotals>, DbError> {
+    let filter = doc! {
+        "election_id": election_id,
+        "question_id": question_id,
+    };
+    let options = FindOptions::builder()
+        .max_time(max_time)
+        .build()
+        .with_request_id(request_id);
+    let mut totals = HashMap::new();
+    let mut totals_cursor = candidate_totals
+        .find_with_session(filter.clone(), options.clone(), session)
+        .await?;
+    while let Some(total) = totals_cursor.next(session).await {
+        let total = total?;
+        totals.insert(total.candidate_name.clone(), total);
+    }
+    let deltas = totals_deltas
+        .find_with_session(filter, options, session)
+        .await?
+        .stream(session)
+        .try_collect::<Vec<_>>()
+        .await?;
+    apply_deltas(&mut totals, &deltas);
+    Ok(totals)
+}
+
+/// Read the candidate totals of every question in an election, keyed by
+/// question and then candidate, including the deltas that have not been folded
+/// into them yet.
+///
+/// As for [`question_totals`], the session should be a snapshot session.
+pub async fn election_totals(
+    election_id: ElectionId,
+    candidate_totals: &Coll<CandidateTotals>,
+    totals_deltas: &Coll<TotalsDelta>,
+    session: &mut ClientSession,
+    request_id: RequestId,
+) -> Result<HashMap<QuestionId, HashMap<CandidateId, CandidateTotals>>, DbError> {
+    let filter = doc! {
+        "election_id": election_id,
+    };
+    let mut totals = HashMap::<_, HashMap<_, _>>::new();
+    let mut totals_cursor = candidate_totals
+        .find_with_session(
+            filter.clone(),
+            FindOptions::for_request(request_id),
+            session,
+        )
+        .await?;
+    while let Some(total) = totals_cursor.next(session).await {
+        let total = total?;
+        totals
+            .entry(total.question_id)
+            .or_default()
+            .insert(total.candidate_name.clone(), total);
+    }
+    let mut deltas = totals_deltas
+        .find_with_session(filter, FindOptions::for_request(request_id), session)
+        .await?;
+    while let Some(delta) = deltas.next(session).await {
+        let delta = delta?;
+        apply_deltas(totals.entry(delta.question_id).or_default(), [&delta]);
+    }
+    Ok(totals)
+}
+
+/// Fold all of a question's pending deltas into its candidate totals, returning
+/// how many were folded.
+///
+/// This runs its own transaction. Concurrent folds of the same question
+/// conflict on the deltas they both delete, causing the transaction to be
+/// retried, so each delta is counted exactly once. Deltas recorded while a
+/// fold is running are left for the next one.
+pub async fn fold_totals(
+    election_id: ElectionId,
+    question_id: QuestionId,
+    candidate_totals: &Coll<CandidateTotals>,
+    totals_deltas: &Coll<TotalsDelta>,
+    session: &mut ClientSession,
+    request_id: Option<RequestId>,
+) -> Result<usize, DbError> {
+    let mut folded = 0;
+    session
+        .with_transaction(
+            (
+                election_id,
+                question_id,
+                candidate_totals,
+                totals_deltas,
+                request_id,
+                &mut folded,
+            ),
+            |session,
+             (election_id, question_id, candidate_totals, totals_deltas, request_id, folded)| {
+                async move {
+                    let filter = doc! {
+                        "election_id": *election_id,
+                        "question_id": *question_id,
+                    };
+                    let deltas = totals_deltas
+                        .find_with_session(
+                            filter.clone(),
+                            request_id.map(FindOptions::for_request),
+                            session,
+                        )
+                        .await?
+                        .stream(session)
+                        .try_collect::<Vec<_>>()
+                        .await?;
+                    **folded = deltas.len();
+                    if deltas.is_empty() {
+                        return Ok(());
+                    }
+
+                    let mut totals = candidate_totals
+                        .find_with_session(
+                            filter,
+                            request_id.map(FindOptions::for_request),
+                            session,
+                        )
+                        .await?
+                        .stream(session)
+                        .map_ok(|total| (total.candidate_name.clone(), total))
+                        .try_collect::<HashMap<_, _>>()
+                        .await?;
+                    // Totals are created when the election is published, so
+                    // they must exist for every candidate a delta counts.
+                    if let Some(candidate) = deltas
+                        .iter()
+                        .flat_map(|delta| delta.totals.keys())
+                        .find(|candidate| !totals.contains_key(*candidate))
+                    {
+                        error!(
+                            "Election {} question {} has no candidate totals for '{}'",
+                            election_id, question_id, candidate
+                        );
+                        return Err(DbError::custom(format!(
+                            "Missing candidate totals for '{}' in election {} question {}",
+                            candidate, election_id, question_id
+                        )));
+                    }
+                    apply_deltas(&mut totals, &deltas);
+
+                    // Write updated candidate totals.
+                    for total in totals.values() {
+                        let options = request_id.map(ReplaceOptions::for_request);
+                        let result = candidate_totals
+                            .replace_one_with_session(total.id.as_doc(), total, options, session)
+                            .await?;
+                        assert_eq!(result.matched_count, 1);
+                    }
+
+                    // Delete the folded deltas, conflicting with any concurrent fold.
+                    let ids = deltas.iter().map(|delta| *delta.id).collect::<Vec<_>>();
+                    let result = totals_deltas
+                        .delete_many_with_session(doc! { "_id": { "$in": ids } }, None, session)
+                        .await?;
+                    assert_eq!(result.deleted_count as usize, deltas.len());
+                    Ok(())
+                }
+                .boxed()
+            },
+            None,
+        )
+        .await?;
+    Ok(folded)
+}
+
+/// The questions with deltas waiting to be folded, shared with the folding task.
+#[derive(Default)]
+struct PendingFolds {
+    questions: Mutex<HashSet<(ElectionId, QuestionId)>>,
+    signal: Notify,
+}
+
+/// Folds totals deltas into the candidate totals in the background, so that
+/// confirming a ballot need not wait for it, nor conflict with other
+/// confirmations' folds.
+///
+/// Questions asked to be folded while a fold is running are folded once it has
+/// finished, however many times they were asked for, so a burst of
+/// confirmations of a question is folded together. A fold that fails leaves
+/// its deltas for the next one, or for the election's finalizer; readers of
+/// the totals include them in the meantime.
+#[derive(Clone)]
+pub struct TotalsFolder {
+    pending: Arc<PendingFolds>,
+}
+
+impl TotalsFolder {
+    /// Start folding the deltas recorded in the given database.
+    pub fn start(client: Client, db: &Database) -> Self {
+        let pending = Arc::new(PendingFolds::default());
+        tokio::spawn(fold_pending(
+            pending.clone(),
+            client,
+            Coll::from_db(db),
+            Coll::from_db(db),
+        ));
+        Self { pending }
+    }
+
+    /// Ask for the given questions' deltas to be folded, without waiting.
+    pub fn request(
+        &self,
+        election_id: ElectionId,
+        question_ids: impl IntoIterator<Item = QuestionId>,
+    ) {
+        self.pending.questions.lock().unwrap().extend(
+            question_ids
+                .into_iter()
+                .map(|question_id| (election_id, question_id)),
+        );
+        self.pending.signal.notify_one();
+    }
+}
+
+/// Fold each question as it is asked for, for as long as the server runs.
+async fn fold_pending(
+    pending: Arc<PendingFolds>,
+    client: Client,
+    candidate_totals: Coll<CandidateTotals>,
+    totals_deltas: Coll<TotalsDelta>,
+) {
+    loop {
+        pending.signal.notified().await;
+        let questions = mem::take(&mut *pending.questions.lock().unwrap());
+        if questions.is_empty() {
+            continue;
+        }
+        let mut session = match client.start_session(None).await {
+            Ok(session) => session,
+            Err(e) => {
+                warn!("Failed to start a session to fold totals: {}", e);
+                continue;
+            }
+        };
+        for (election_id, question_id) in questions {
+            match fold_totals(
+                election_id,
+                question_id,
+                &candidate_totals,
+                &totals_deltas,
+                &mut session,
+                None,
+            )
+            .await
+            {
+                Ok(folded) => trace!(
+                    "Folded {} totals deltas for election {} question {}",
+                    folded,
+                    election_id,
+                    question_id
+                ),
+                Err(e) => warn!(
+                    "Failed to fold totals for election {} question {}: {}",
+                    election_id, question_id, e
+                ),
+            }
+        }
+    }
+}
+
+/// A fairing that starts a [`TotalsFolder`] and places it into managed state.
+/// This fairing depends on the database being available in managed state, and
+/// so must be attached after the fairing responsible for that.
+pub struct TotalsFolderFairing;
+
+#[rocket::async_trait]
+impl Fairing for TotalsFolderFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Totals Folder",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let (Some(client), Some(db)) = (rocket.state::<Client>(), rocket.state::<Database>())
+        else {
+            error!("Database was not available when setting up the totals folder");
+            return Err(rocket);
+        };
+        let folder = TotalsFolder::start(client.clone(), db);
+        Ok(rocket.manage(folder))
+    }
+}
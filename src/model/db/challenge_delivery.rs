@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use mongodb::bson::{to_bson, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// How long the delivery record of a number is kept after its last challenge,
+/// so that numbers which could not be reached are eventually retried.
+pub const CHALLENGE_DELIVERY_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// What became of the last OTP challenge sent to a number, and how many in a
+/// row could not be delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeDelivery {
+    /// The HMAC of the number, as for [`Voter::sms_hmac`](super::voter::Voter).
+    #[serde(with = "dre_ip::group::serde_bytestring")]
+    pub sms_hmac: Vec<u8>,
+    /// The region the number belongs to, e.g. `GB`, to help diagnose delivery
+    /// problems. Kept here rather than on the voter, who needs only the HMAC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// The SNS message ID of the last challenge, if it was sent.
+    pub message_id: Option<String>,
+    /// What became of the last challenge.
+    pub status: DeliveryStatus,
+    /// Number of consecutive challenges that could not be delivered because of
+    /// the number itself. Failures of SNS are not held against the number.
+    pub failures: u32,
+    /// When the last challenge was sent or verified; the record expires after
+    /// [`CHALLENGE_DELIVERY_LIFETIME`].
+    pub updated_at: DateTime,
+}
+
+/// What became of an OTP challenge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Accepted by SNS, but not yet known to have arrived.
+    Sent,
+    /// Known to have arrived, as its code was verified.
+    Delivered,
+    /// Refused, as the number has opted out of receiving SMS.
+    OptedOut,
+    /// Refused, as SNS does not accept the number.
+    InvalidNumber,
+    /// SNS failed to send it.
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Did the challenge fail to reach the voter?
+    pub fn is_failure(self) -> bool {
+        matches!(self, Self::OptedOut | Self::InvalidNumber | Self::Failed)
+    }
+
+    /// Did the challenge fail because of the number itself, so that sending
+    /// more to it is likely to fail too?
+    pub fn is_number_failure(self) -> bool {
+        matches!(self, Self::OptedOut | Self::InvalidNumber)
+    }
+}
+
+impl From<DeliveryStatus> for Bson {
+    fn from(status: DeliveryStatus) -> Self {
+        to_bson(&status).expect("Serialisation is infallible")
+    }
+}
+
+/// The outcome of sending a single SMS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsOutcome {
+    /// What became of the message: never [`DeliveryStatus::Delivered`], as
+    /// that is only known later.
+    pub status: DeliveryStatus,
+    /// The SNS message ID, if it was sent.
+    pub message_id: Option<String>,
+    /// Was it refused because SNS is throttling us, so may well be sent if
+    /// tried again later?
+    pub throttled: bool,
+}
+
+impl SmsOutcome {
+    /// A message that was sent with the given SNS message ID.
+    pub fn sent(message_id: Option<String>) -> Self {
+        Self {
+            status: DeliveryStatus::Sent,
+            message_id,
+            throttled: false,
+        }
+    }
+
+    /// A message that was not sent, for the given reason.
+    pub fn not_sent(status: DeliveryStatus) -> Self {
+        Self {
+            status,
+            message_id: None,
+            throttled: false,
+        }
+    }
+
+    /// A message that was not sent because SNS is throttling us.
+    pub fn throttled() -> Self {
+        Self {
+            status: DeliveryStatus::Failed,
+            message_id: None,
+            throttled: true,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use mongodb::bson::{to_bson, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A record of uploading an archived election's signed dumps to the archive
+/// bucket, stored on the election.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ArchiveExport {
+    /// When every object was last uploaded, if they have been.
+    pub uploaded_at: Option<DateTime>,
+    /// Keys of the objects uploaded, once they all have been.
+    pub object_keys: Vec<String>,
+    /// Number of times uploading has been attempted.
+    pub attempts: u32,
+    /// The error from the most recent attempt, if it failed.
+    pub last_error: Option<String>,
+}
+
+impl From<ArchiveExport> for Bson {
+    fn from(export: ArchiveExport) -> Self {
+        to_bson(&export).expect("Serialisation is infallible")
+    }
+}
@@ -1,18 +1,37 @@
 use std::collections::{HashMap, HashSet};
 
-use chrono::{DateTime, Utc};
-use dre_ip::Election as DreipElection;
+use chrono::{DateTime, Duration, Utc};
+use dre_ip::{DreipGroup as DreipGroupTrait, Election as DreipElection};
+use mongodb::{
+    bson::{self, doc, Bson, Document},
+    error::Error as DbError,
+    Database,
+};
 use rand::{CryptoRng, RngCore};
+use rocket::futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::model::{
-    common::election::{
-        CandidateId, DreipGroup, ElectionId, ElectionState, Electorate, QuestionId,
+    api::election::DumpSignature,
+    common::{
+        election::{
+            CandidateId, DreipGroup, ElectionId, ElectionState, Electorate, QuestionId, QuorumSpec,
+            ResultVisibility, Translations, WRITE_IN_CANDIDATE,
+        },
+        secret::Secret,
+        serde_string_map,
     },
-    mongodb::serde_string_map,
+    mongodb::{u32_id_filter, Coll},
+};
+
+use super::{
+    archive_export::ArchiveExport, end_warning::EndWarning, finalization::ElectionFinalization,
+    metadata::ElectionMetadata,
 };
 
-use super::metadata::ElectionMetadata;
+/// The longest slug derived from a question's description, before any suffix
+/// needed to make it unique.
+const MAX_QUESTION_SLUG_LENGTH: usize = 48;
 
 /// Core election data, as stored in the database.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -29,7 +48,17 @@ pub struct Election {
     #[serde(with = "serde_string_map")]
     pub questions: HashMap<QuestionId, Question>,
     /// Election cryptographic configuration.
-    pub crypto: DreipElection<DreipGroup>,
+    pub crypto: ElectionKeys,
+    /// What the finalizer has done, once it has run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalization: Option<ElectionFinalization>,
+    /// The warning sent about unconfirmed ballots shortly before the end, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_warning: Option<EndWarning>,
+    /// The upload of the election's signed dumps to the archive bucket, once
+    /// it has been archived, if there is a bucket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_export: Option<ArchiveExport>,
 }
 
 impl Election {
@@ -43,26 +72,288 @@ impl Election {
         questions: HashMap<QuestionId, Question>,
         rng: impl RngCore + CryptoRng,
     ) -> Self {
-        let crypto = DreipElection::new(
-            &[
-                name.as_bytes(),
-                &start_time.timestamp().to_le_bytes(),
-                &end_time.timestamp().to_le_bytes(),
-            ],
-            rng,
-        );
+        let crypto = ElectionKeys::generate(&name, start_time, end_time, rng);
 
-        Self {
+        let mut election = Self {
             id,
             metadata: ElectionMetadata {
                 name,
+                name_translations: Translations::new(),
                 state: ElectionState::Draft,
                 start_time,
                 end_time,
+                timezone: None,
+                suspended: false,
+                requires_step_up: false,
+                randomise_candidate_order: false,
+                max_voters: None,
+                pre_join_minutes: None,
+                embargo_public_board_until: None,
+                consent_text: None,
+                consent_version: 0,
+                joined_count: 0,
+                branding: None,
+                created_by: String::new(),
+                last_modified_by: None,
+                rehearsal: false,
+                archived_at: None,
             },
             electorates,
             questions,
             crypto,
+            finalization: None,
+            end_warning: None,
+            archive_export: None,
+        };
+        election.assign_question_slugs();
+        election
+    }
+
+    /// Replace the election's keys with freshly generated ones, leaving
+    /// everything else untouched.
+    pub fn rotate_keys(&mut self, rng: impl RngCore + CryptoRng) {
+        self.crypto = ElectionKeys::generate(
+            &self.metadata.name,
+            self.metadata.start_time,
+            self.metadata.end_time,
+            rng,
+        );
+    }
+
+    /// Has a signed dump of every question been served since the election was
+    /// archived, so that its data is known to have been exported?
+    pub fn is_exported(&self) -> bool {
+        self.metadata.state == ElectionState::Archived
+            && self
+                .questions
+                .values()
+                .all(|question| question.last_dump_at.is_some())
+    }
+
+    /// Find the questions whose constraints would name a group that no longer
+    /// exists if the given electorate were left with only the given groups, or
+    /// removed entirely if `None`. Sorted by ID.
+    pub fn questions_orphaned_by(
+        &self,
+        electorate: &str,
+        remaining_groups: Option<&HashSet<String>>,
+    ) -> Vec<QuestionId> {
+        let mut orphaned =
+            self.questions
+                .values()
+                .filter(|question| {
+                    question.constraints.get(electorate).is_some_and(
+                        |groups| match remaining_groups {
+                            Some(remaining) => !groups.is_subset(remaining),
+                            None => true,
+                        },
+                    )
+                })
+                .map(|question| question.id)
+                .collect::<Vec<_>>();
+        orphaned.sort_unstable();
+        orphaned
+    }
+
+    /// Match each of this election's questions to the question with the same
+    /// description in a modified version of it, mapping old IDs to new IDs.
+    /// Questions whose description no longer appears are left out.
+    pub fn question_mapping(&self, modified: &Election) -> HashMap<QuestionId, QuestionId> {
+        let mut old_questions = self.questions.values().collect::<Vec<_>>();
+        old_questions.sort_unstable_by_key(|question| question.id);
+        let mut new_questions = modified.questions.values().collect::<Vec<_>>();
+        new_questions.sort_unstable_by_key(|question| question.id);
+
+        // Match duplicate descriptions in order of ID.
+        let mut mapping = HashMap::new();
+        for old in old_questions {
+            if let Some(index) = new_questions
+                .iter()
+                .position(|new| new.description == old.description)
+            {
+                mapping.insert(old.id, new_questions.remove(index).id);
+            }
+        }
+        mapping
+    }
+
+    /// Would a voter's groups allow them different questions in a modified
+    /// version of this election, given the mapping from
+    /// [`Election::question_mapping`]? This is so if the electorates changed,
+    /// or any question carried over has different constraints.
+    pub fn constraints_changed(
+        &self,
+        modified: &Election,
+        mapping: &HashMap<QuestionId, QuestionId>,
+    ) -> bool {
+        self.electorates != modified.electorates
+            || mapping.iter().any(|(old_id, new_id)| {
+                match (self.questions.get(old_id), modified.questions.get(new_id)) {
+                    (Some(old), Some(new)) => old.constraints != new.constraints,
+                    _ => true,
+                }
+            })
+    }
+
+    /// Give each question without a slug one derived from its description,
+    /// suffixed if needed to make it unique within the election. Questions are
+    /// taken in order of ID, so the same questions always get the same slugs.
+    pub fn assign_question_slugs(&mut self) {
+        let mut taken = self
+            .questions
+            .values()
+            .filter(|question| !question.slug.is_empty())
+            .map(|question| question.slug.clone())
+            .collect::<HashSet<_>>();
+        let mut unassigned = self
+            .questions
+            .values_mut()
+            .filter(|question| question.slug.is_empty())
+            .collect::<Vec<_>>();
+        unassigned.sort_unstable_by_key(|question| question.id);
+        for question in unassigned {
+            let base = question_slug(&question.description);
+            let mut slug = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(slug.clone()) {
+                slug = format!("{base}-{suffix}");
+                suffix += 1;
+            }
+            question.slug = slug;
+        }
+    }
+
+    /// Keep the slugs of `previous`'s questions for the questions of this
+    /// modified version of it with the same descriptions, and give the rest
+    /// fresh ones.
+    pub fn keep_question_slugs(&mut self, previous: &Election) {
+        let mapping = previous.question_mapping(self);
+        for question in self.questions.values_mut() {
+            question.slug.clear();
+        }
+        for (old_id, new_id) in mapping {
+            if let (Some(old), Some(new)) = (
+                previous.questions.get(&old_id),
+                self.questions.get_mut(&new_id),
+            ) {
+                new.slug = old.slug.clone();
+            }
+        }
+        self.assign_question_slugs();
+    }
+
+    /// Add the write-in candidate to every question that allows write-ins and
+    /// does not have it yet, returning the IDs of those questions, sorted.
+    pub fn add_write_in_candidates(&mut self) -> Vec<QuestionId> {
+        let mut added = self
+            .questions
+            .values_mut()
+            .filter(|question| question.allow_write_in)
+            .filter(|question| !question.candidates.iter().any(|c| c == WRITE_IN_CANDIDATE))
+            .map(|question| {
+                question.candidates.push(WRITE_IN_CANDIDATE.to_string());
+                question.id
+            })
+            .collect::<Vec<_>>();
+        added.sort_unstable();
+        added
+    }
+
+    /// Can voters vote in this election at the given time?
+    /// It must be published, not a rehearsal, and within its start and end
+    /// times. Suspension is not considered.
+    pub fn is_votable_at(&self, now: DateTime<Utc>) -> bool {
+        self.is_open_between(self.metadata.start_time, now)
+    }
+
+    /// Can voters join this election at the given time?
+    /// As for [`Election::is_votable_at`], except that voters may also join up
+    /// to [`ElectionMetadata::pre_join_minutes`] before it starts, if set.
+    pub fn is_joinable_at(&self, now: DateTime<Utc>) -> bool {
+        let opens = match self.metadata.pre_join_minutes {
+            Some(minutes) => {
+                self.metadata.start_time
+                    - Duration::try_minutes(minutes.into()).expect("u32 minutes fit")
+            }
+            None => self.metadata.start_time,
+        };
+        self.is_open_between(opens, now)
+    }
+
+    /// Is the election's public board hidden from non-admins at the given time?
+    /// The embargo lifts by itself once
+    /// [`ElectionMetadata::embargo_public_board_until`] has passed.
+    pub fn is_board_embargoed_at(&self, now: DateTime<Utc>) -> bool {
+        self.metadata
+            .embargo_public_board_until
+            .is_some_and(|until| now < until.to_chrono())
+    }
+
+    /// Is this a published, non-rehearsal election, and is `now` between the
+    /// given opening time and its end time?
+    fn is_open_between(&self, opens: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.metadata.state == ElectionState::Published
+            && !self.metadata.rehearsal
+            && opens <= now
+            && now < self.metadata.end_time
+    }
+}
+
+/// Election cryptographic configuration. This is a [`DreipElection`] with the
+/// private key kept in a [`Secret`], and is stored in exactly the same format.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(from = "DreipElection<DreipGroup>", into = "DreipElection<DreipGroup>")]
+pub struct ElectionKeys {
+    /// First generator.
+    pub g1: <DreipGroup as DreipGroupTrait>::Point,
+    /// Second generator.
+    pub g2: <DreipGroup as DreipGroupTrait>::Point,
+    /// Signing key.
+    pub private_key: Secret<<DreipGroup as DreipGroupTrait>::PrivateKey>,
+    /// Verification key.
+    pub public_key: <DreipGroup as DreipGroupTrait>::PublicKey,
+}
+
+impl ElectionKeys {
+    /// Generate a fresh keypair for an election with the given name and times.
+    pub fn generate(
+        name: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        rng: impl RngCore + CryptoRng,
+    ) -> Self {
+        DreipElection::new(
+            &[
+                name.as_bytes(),
+                &start_time.timestamp().to_le_bytes(),
+                &end_time.timestamp().to_le_bytes(),
+            ],
+            rng,
+        )
+        .into()
+    }
+}
+
+impl From<DreipElection<DreipGroup>> for ElectionKeys {
+    fn from(election: DreipElection<DreipGroup>) -> Self {
+        Self {
+            g1: election.g1,
+            g2: election.g2,
+            private_key: Secret::new(election.private_key),
+            public_key: election.public_key,
+        }
+    }
+}
+
+impl From<ElectionKeys> for DreipElection<DreipGroup> {
+    fn from(keys: ElectionKeys) -> Self {
+        // This copy of the key only lives as long as serialisation, and is
+        // wiped on drop just like the original.
+        Self {
+            g1: keys.g1,
+            g2: keys.g2,
+            private_key: keys.private_key.expose().clone(),
+            public_key: keys.public_key,
         }
     }
 }
@@ -72,16 +363,122 @@ impl Election {
 pub struct Question {
     /// Unique ID.
     pub id: QuestionId,
+    /// A stable handle for the question, unique within the election. It is
+    /// derived from the description when the question is created, and kept
+    /// by modifications that keep the description. Questions stored before
+    /// slugs existed are given theirs at startup.
+    #[serde(default)]
+    pub slug: String,
     /// Question text.
     pub description: String,
+    /// Translations of the question text, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub description_translations: Translations,
     /// A voter must be in at least one of these electorate groups to vote on this question.
     pub constraints: HashMap<String, HashSet<String>>,
     /// Candidates / possible answers for this question.
     pub candidates: Vec<CandidateId>,
+    /// Translations of candidates' display names, by candidate then language
+    /// code. Votes are always for the candidate itself.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub candidate_translations: HashMap<CandidateId, Translations>,
+    /// When voting on this question closes, if before the end of the election.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<bson::DateTime>,
+    /// May voters write in a candidate? If so, [`WRITE_IN_CANDIDATE`] is added
+    /// to the candidates when the election is published.
+    #[serde(default)]
+    pub allow_write_in: bool,
+    /// How much of the results the public may see once the election has ended.
+    #[serde(default)]
+    pub result_visibility: ResultVisibility,
+    /// How many voters must answer the question for its result to be binding,
+    /// if there is a quorum. This is only reported on once the election ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumSpec>,
+    /// When a signed dump of this question was last served, if ever since the
+    /// election was archived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_dump_at: Option<bson::DateTime>,
+    /// The signature of that dump.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_dump_signature: Option<DumpSignature>,
 }
 
-#[cfg(test)]
-mod tests {
+impl Question {
+    /// Has voting on this question closed early?
+    /// This does not consider the election's own end time.
+    pub fn is_closed(&self) -> bool {
+        self.end_time
+            .is_some_and(|end_time| end_time.to_chrono() <= Utc::now())
+    }
+}
+
+/// Store a slug for every question stored before slugs existed, returning how
+/// many were filled in. Each is the slug the question would have been given
+/// when created, and is then kept like any other.
+pub async fn ensure_question_slugs_exist(db: &Database) -> Result<usize, DbError> {
+    debug!("Ensuring questions have slugs");
+    let elections = Coll::<Election>::from_db(db);
+    let filter = doc! {
+        "$expr": {
+            "$in": ["", {
+                "$map": {
+                    "input": { "$objectToArray": "$questions" },
+                    "in": { "$ifNull": ["$$this.v.slug", ""] },
+                },
+            }],
+        },
+    };
+    let mut missing = elections.find(filter, None).await?;
+    let mut filled = 0;
+    while let Some(mut election) = missing.try_next().await? {
+        let unassigned = election
+            .questions
+            .values()
+            .filter(|question| question.slug.is_empty())
+            .map(|question| question.id)
+            .collect::<Vec<_>>();
+        election.assign_question_slugs();
+        let slugs = unassigned
+            .iter()
+            .map(|id| {
+                let slug = election.questions[id].slug.clone();
+                (format!("questions.{id}.slug"), Bson::String(slug))
+            })
+            .collect::<Document>();
+        elections
+            .update_one(u32_id_filter(election.id), doc! { "$set": slugs }, None)
+            .await?;
+        filled += unassigned.len();
+    }
+    if filled > 0 {
+        warn!("Filled in the slugs of {} questions", filled);
+    }
+    Ok(filled)
+}
+
+/// Derive a slug from a question's description: its ASCII letters and digits,
+/// lowercased, with hyphens between words.
+fn question_slug(description: &str) -> String {
+    let mut slug = String::new();
+    for c in description.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_QUESTION_SLUG_LENGTH);
+    match slug.trim_end_matches('-') {
+        "" => "question".to_string(),
+        slug => slug.to_string(),
+    }
+}
+
+/// Example data for tests and development seeding.
+#[cfg(any(test, feature = "dev-tools"))]
+mod examples {
     use super::*;
 
     use crate::model::api::election::ElectionSpec;
@@ -104,7 +501,168 @@ mod tests {
             let mut rng = rand::thread_rng();
             let mut example: Self = ElectionSpec::past_example().into_election(rng.next_u32(), rng);
             example.metadata.state = ElectionState::Archived;
+            example.metadata.archived_at = Some(bson::DateTime::now());
             example
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{from_document, to_document};
+
+    use crate::model::mongodb::MongoCollection;
+
+    use super::*;
+
+    #[test]
+    fn votable_and_joinable() {
+        let mut election = Election::draft_example();
+        let start = election.metadata.start_time;
+        let end = election.metadata.end_time;
+        let minutes = |m| Duration::try_minutes(m).unwrap();
+        let before_start = start - minutes(30);
+
+        // Drafts are neither.
+        assert!(!election.is_votable_at(start));
+        assert!(!election.is_joinable_at(start));
+
+        // Published elections can be joined and voted in between the start
+        // and end times only.
+        election.metadata.state = ElectionState::Published;
+        for (now, open) in [
+            (before_start, false),
+            (start - minutes(1), false),
+            (start, true),
+            (end - minutes(1), true),
+            (end, false),
+        ] {
+            assert_eq!(election.is_votable_at(now), open, "{now}");
+            assert_eq!(election.is_joinable_at(now), open, "{now}");
+        }
+
+        // With pre-joining, voters can join within the window before the
+        // start, but still not vote.
+        election.metadata.pre_join_minutes = Some(60);
+        assert!(election.is_joinable_at(before_start));
+        assert!(!election.is_votable_at(before_start));
+        assert!(!election.is_joinable_at(start - minutes(61)));
+        assert!(election.is_joinable_at(start - minutes(60)));
+        assert!(election.is_joinable_at(start));
+        assert!(!election.is_joinable_at(end));
+
+        // Rehearsals and archived elections are neither.
+        election.metadata.rehearsal = true;
+        assert!(!election.is_joinable_at(start));
+        assert!(!election.is_votable_at(start));
+        election.metadata.rehearsal = false;
+        election.metadata.state = ElectionState::Archived;
+        assert!(!election.is_joinable_at(before_start));
+        assert!(!election.is_votable_at(start));
+    }
+
+    #[test]
+    fn board_embargo() {
+        let mut election = Election::published_example();
+        let now = Utc::now();
+        assert!(!election.is_board_embargoed_at(now));
+
+        let until = now + Duration::try_hours(1).unwrap();
+        election.metadata.embargo_public_board_until = Some(bson::DateTime::from_chrono(until));
+        assert!(election.is_board_embargoed_at(now));
+        assert!(!election.is_board_embargoed_at(until));
+    }
+
+    #[test]
+    fn question_slugs() {
+        for (description, slug) in [
+            ("Who should be captain?", "who-should-be-captain"),
+            ("  Course Rep (CompSci) -- 2024 ", "course-rep-compsci-2024"),
+            ("Pwy ddylai fod yn gapten?", "pwy-ddylai-fod-yn-gapten"),
+            ("???", "question"),
+        ] {
+            assert_eq!(question_slug(description), slug, "{description}");
+        }
+        let long = question_slug(&"word ".repeat(20));
+        assert!(long.len() <= MAX_QUESTION_SLUG_LENGTH);
+        assert!(!long.ends_with('-'));
+
+        // Slugs are made unique within the election, in order of ID.
+        let mut election = Election::draft_example();
+        let ids = {
+            let mut ids = election.questions.keys().copied().collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        };
+        for question in election.questions.values_mut() {
+            question.slug.clear();
+        }
+        election.questions.get_mut(&ids[1]).unwrap().description = "Who!".to_string();
+        election.questions.get_mut(&ids[0]).unwrap().description = "Who?".to_string();
+        election.assign_question_slugs();
+        assert_eq!(election.questions[&ids[0]].slug, "who");
+        assert_eq!(election.questions[&ids[1]].slug, "who-2");
+    }
+
+    #[backend_test]
+    async fn legacy_question_slugs(db: Database) -> anyhow::Result<()> {
+        // Seed an election with one question stored before slugs existed,
+        // and one whose questions all have slugs.
+        let legacy = Election::draft_example();
+        let ids = {
+            let mut ids = legacy.questions.keys().copied().collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        };
+        let mut raw_legacy = to_document(&legacy)?;
+        raw_legacy
+            .get_document_mut("questions")?
+            .get_document_mut(ids[0].to_string())?
+            .remove("slug");
+        let current = Election::published_example();
+        let raw_elections = db.collection::<Document>(Election::NAME);
+        raw_elections
+            .insert_many([raw_legacy, to_document(&current)?], None)
+            .await?;
+
+        // Only the missing slug is filled in, as it would have been on creation.
+        assert_eq!(ensure_question_slugs_exist(&db).await?, 1);
+        let elections = Coll::<Election>::from_db(&db);
+        for election in [legacy, current] {
+            let stored = elections
+                .find_one(u32_id_filter(election.id), None)
+                .await?
+                .unwrap();
+            assert_eq!(stored.questions, election.questions);
+        }
+
+        // Rerunning is harmless.
+        assert_eq!(ensure_question_slugs_exist(&db).await?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let election = Election::published_example();
+        let debug = format!("{:?}", election);
+        assert!(debug.contains("private_key: Secret([REDACTED])"));
+    }
+
+    #[test]
+    fn storage_format_unchanged() {
+        let election = Election::published_example();
+        let stored = to_document(&election).unwrap();
+
+        // The keys are stored exactly as the underlying DRE-ip election would be.
+        let dreip: DreipElection<DreipGroup> = election.crypto.clone().into();
+        assert_eq!(
+            stored.get_document("crypto").unwrap(),
+            &to_document(&dreip).unwrap()
+        );
+
+        // And they round-trip.
+        let loaded: Election = from_document(stored).unwrap();
+        assert_eq!(loaded, election);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime as ChronoDateTime, Duration, Utc};
+use mongodb::{
+    bson::{doc, to_bson, Bson, DateTime},
+    Database,
+};
+use rocket::futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    model::{
+        common::{
+            ballot::Unconfirmed,
+            election::{ElectionId, ElectionState, QuestionId},
+            serde_string_map,
+        },
+        db::ballot::AnyBallot,
+        mongodb::{u32_id_filter, Coll},
+    },
+};
+
+use super::Election;
+
+/// Settings for warning admins that an election is about to end while many of
+/// its ballots are still unconfirmed, so they can remind voters to confirm.
+#[derive(Debug, Clone)]
+pub struct EndWarningSettings {
+    /// How long before the end of an election to check.
+    pub lead: Duration,
+    /// Only warn if there are more unconfirmed ballots than this.
+    pub threshold: u64,
+    /// Where to POST the warning, if anywhere.
+    pub webhook_url: Option<String>,
+}
+
+/// A record of an end warning being sent, stored on the election so that it is
+/// only ever sent once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndWarning {
+    /// When the warning was sent.
+    pub sent_at: DateTime,
+    /// Number of unconfirmed ballots for each question with any.
+    #[serde(with = "serde_string_map")]
+    pub unconfirmed: HashMap<QuestionId, u64>,
+}
+
+impl From<EndWarning> for Bson {
+    fn from(warning: EndWarning) -> Self {
+        to_bson(&warning).expect("Serialisation is infallible")
+    }
+}
+
+/// The body POSTed to the admin webhook.
+#[derive(Serialize)]
+struct EndWarningNotification<'a> {
+    election_id: ElectionId,
+    name: &'a str,
+    end_time: ChronoDateTime<Utc>,
+    total_unconfirmed: u64,
+    unconfirmed: &'a HashMap<QuestionId, u64>,
+}
+
+/// Number of unconfirmed ballots for a single question.
+#[derive(Deserialize)]
+struct QuestionCount {
+    #[serde(rename = "_id")]
+    question_id: QuestionId,
+    count: u64,
+}
+
+/// Warn admins if the given election has more unconfirmed ballots than the
+/// threshold, unless it has already been warned about or is no longer published.
+pub(super) async fn warn_if_unconfirmed(
+    election_id: ElectionId,
+    db: Database,
+    settings: EndWarningSettings,
+) {
+    if let Err(e) = check(election_id, &db, &settings).await {
+        error!("End warning for election {election_id} failed: {e}");
+    }
+}
+
+/// Do the work of [`warn_if_unconfirmed`], so that errors are handled in one place.
+async fn check(
+    election_id: ElectionId,
+    db: &Database,
+    settings: &EndWarningSettings,
+) -> Result<(), Error> {
+    let elections = Coll::<Election>::from_db(db);
+    let election = match elections.find_one(u32_id_filter(election_id), None).await? {
+        Some(election) => election,
+        None => return Ok(()),
+    };
+    if election.metadata.state != ElectionState::Published || election.end_warning.is_some() {
+        return Ok(());
+    }
+
+    // Count the unconfirmed ballots for each question.
+    let pipeline = [
+        doc! {
+            "$match": {
+                "election_id": election_id,
+                "state": Unconfirmed,
+            }
+        },
+        doc! {
+            "$group": {
+                "_id": "$question_id",
+                "count": {"$sum": 1},
+            }
+        },
+    ];
+    let unconfirmed = Coll::<AnyBallot>::from_db(db)
+        .aggregate(pipeline, None)
+        .await?
+        .with_type::<QuestionCount>()
+        .map_ok(|count| (count.question_id, count.count))
+        .try_collect::<HashMap<_, _>>()
+        .await?;
+    let total_unconfirmed = unconfirmed.values().sum::<u64>();
+    if total_unconfirmed <= settings.threshold {
+        debug!("Election {election_id} is ending with {total_unconfirmed} unconfirmed ballots");
+        return Ok(());
+    }
+
+    let mut questions = unconfirmed.iter().collect::<Vec<_>>();
+    questions.sort_unstable();
+    warn!(
+        "Election {} ends at {} with {} unconfirmed ballots (per question: {:?})",
+        election_id, election.metadata.end_time, total_unconfirmed, questions
+    );
+
+    // Record the warning before notifying, so that a failed notification is
+    // not retried on every restart.
+    let warning = EndWarning {
+        sent_at: DateTime::now(),
+        unconfirmed,
+    };
+    let update = doc! {
+        "$set": {
+            "end_warning": warning.clone(),
+        }
+    };
+    elections
+        .update_one(u32_id_filter(election_id), update, None)
+        .await?;
+
+    if let Some(url) = &settings.webhook_url {
+        let notification = EndWarningNotification {
+            election_id,
+            name: &election.metadata.name,
+            end_time: election.metadata.end_time,
+            total_unconfirmed,
+            unconfirmed: &warning.unconfirmed,
+        };
+        let result = reqwest::Client::new()
+            .post(url)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            error!("Failed to send end warning for election {election_id} to webhook: {e}");
+        }
+    }
+
+    Ok(())
+}
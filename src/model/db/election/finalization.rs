@@ -0,0 +1,21 @@
+use mongodb::bson::{to_bson, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A record of an election's finalizer runs, stored on the election.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ElectionFinalization {
+    /// When the finalizer first succeeded, if it has.
+    pub completed_at: Option<DateTime>,
+    /// Total number of unconfirmed ballots audited across all runs.
+    pub ballots_audited: u32,
+    /// Number of times the finalizer has run.
+    pub attempts: u32,
+    /// The error from the most recent run, if it failed.
+    pub last_error: Option<String>,
+}
+
+impl From<ElectionFinalization> for Bson {
+    fn from(finalization: ElectionFinalization) -> Self {
+        to_bson(&finalization).expect("Serialisation is infallible")
+    }
+}
@@ -1,5 +1,10 @@
 use chrono::{Duration, Utc};
-use mongodb::{bson::doc, error::Error as DbError, Database};
+use mongodb::{
+    bson::{doc, DateTime, Document},
+    error::Error as DbError,
+    options::AggregateOptions,
+    Client, Database,
+};
 use rocket::futures::TryStreamExt;
 use rocket::{
     fairing::{Fairing, Info, Kind},
@@ -8,35 +13,67 @@ use rocket::{
     tokio::sync::Mutex,
     Build, Rocket,
 };
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::{
+    config::Config,
     error::Error,
+    events::{DomainEvent, EventBus},
     model::{
+        api::finalize_preview::{FinalizePreview, UnconfirmedByAge},
         common::{
             ballot::{Audited, Unconfirmed},
-            election::{ElectionId, ElectionState},
+            election::{ElectionId, ElectionState, QuestionId},
+        },
+        db::{
+            ballot::{AnyBallot, Ballot},
+            board::{append_to_board, BoardEntry, BoardHead},
+            candidate_totals::{fold_totals, TotalsDelta},
+            election::{Election, ElectionFinalization},
         },
-        db::{ballot::Ballot, election::Election},
-        mongodb::Coll,
+        mongodb::{u32_id_filter, Coll},
     },
-    scheduled_task::ScheduledTask,
+    scheduled_task::{ScheduledTask, TaskRegistry},
 };
 
+use super::end_warning::{warn_if_unconfirmed, EndWarningSettings};
+
 /// Map from election IDs to finalizer tasks.
 type TaskMap = HashMap<ElectionId, ScheduledTask<Result<(), Error>>>;
 
+/// Map from election and question IDs to finalizer tasks for questions that
+/// close before their election ends.
+type QuestionTaskMap = HashMap<(ElectionId, QuestionId), ScheduledTask<()>>;
+
+/// Map from election IDs to end warning tasks.
+type WarningMap = HashMap<ElectionId, ScheduledTask<()>>;
+
 /// Election finalizers: scheduled tasks for auditing unconfirmed ballots at the end of an election.
+/// Each finalizer is accompanied by a task to warn admins shortly beforehand if
+/// many ballots are still unconfirmed, and by a task for each question that
+/// closes early, to audit that question's unconfirmed ballots when it closes.
 pub struct ElectionFinalizers {
     tasks: Arc<Mutex<TaskMap>>,
+    question_tasks: Arc<Mutex<QuestionTaskMap>>,
+    warnings: Arc<Mutex<WarningMap>>,
+    end_warning: EndWarningSettings,
+    events: EventBus,
+    registry: TaskRegistry,
 }
 
 impl ElectionFinalizers {
-    /// Create an empty set of election finalizers.
-    pub fn new() -> Self {
+    /// Create an empty set of election finalizers, which emit their outcomes on
+    /// `events` and register their tasks with `registry`.
+    pub fn new(end_warning: EndWarningSettings, events: EventBus, registry: TaskRegistry) -> Self {
         Self {
             tasks: Default::default(),
+            question_tasks: Default::default(),
+            warnings: Default::default(),
+            end_warning,
+            events,
+            registry,
         }
     }
 
@@ -45,23 +82,44 @@ impl ElectionFinalizers {
         self.tasks.lock().await.contains_key(&election)
     }
 
-    /// Schedule a finalizer for every published and archived election.
-    pub async fn schedule_elections(&self, db: &Database) -> Result<(), DbError> {
-        // Get all the relevant elections.
+    /// Does the given question have an early finalizer scheduled?
+    pub async fn has_question_finalizer(&self, election: ElectionId, question: QuestionId) -> bool {
+        self.question_tasks
+            .lock()
+            .await
+            .contains_key(&(election, question))
+    }
+
+    /// Does the given election have an end warning scheduled?
+    pub async fn has_end_warning(&self, election: ElectionId) -> bool {
+        self.warnings.lock().await.contains_key(&election)
+    }
+
+    /// Get every election that needs a finalizer: those published or archived.
+    /// Rehearsals are never finalized, since they are deleted once they expire.
+    pub async fn elections_needing_finalizers(db: &Database) -> Result<Vec<Election>, DbError> {
         let filter = doc! {
             "$or": [{"state": ElectionState::Published}, {"state": ElectionState::Archived}],
+            "rehearsal": {"$ne": true},
         };
-        let all_elections: Vec<_> = Coll::<Election>::from_db(db)
+        Coll::<Election>::from_db(db)
             .find(filter, None)
             .await?
             .try_collect()
-            .await?;
+            .await
+    }
+
+    /// Schedule a finalizer for every published and archived election.
+    pub async fn schedule_elections(
+        &self,
+        db_client: &Client,
+        db: &Database,
+    ) -> Result<(), DbError> {
+        // Get all the relevant elections.
+        let all_elections = Self::elections_needing_finalizers(db).await?;
         // Add all of them.
         for election in all_elections {
-            let unconfirmed_ballots = Coll::<Ballot<Unconfirmed>>::from_db(db);
-            let audited_ballots = Coll::<Ballot<Audited>>::from_db(db);
-            self.schedule_election(unconfirmed_ballots, audited_ballots, &election)
-                .await;
+            self.schedule_election(db_client, db, &election).await;
         }
 
         Ok(())
@@ -69,17 +127,22 @@ impl ElectionFinalizers {
 
     /// Schedule a finalizer for the given election.
     /// If one already exists, it will be rescheduled.
-    pub async fn schedule_election(
-        &self,
-        unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
-        audited_ballots: Coll<Ballot<Audited>>,
-        election: &Election,
-    ) {
+    /// The finalizer carries on from the election's recorded finalization, if any.
+    /// An end warning is also (re)scheduled, unless one has already been sent
+    /// or it is too late, as are finalizers for questions that close early.
+    pub async fn schedule_election(&self, db_client: &Client, db: &Database, election: &Election) {
+        self.schedule_end_warning(db, election).await;
+        self.schedule_question_finalizers(db_client, db, election)
+            .await;
+
         let finalizer = Self::finalizer(
             election.id,
-            unconfirmed_ballots,
-            audited_ballots,
+            db_client.clone(),
+            db.clone(),
             self.tasks.clone(),
+            self.events.clone(),
+            self.registry.clone(),
+            election.finalization.clone().unwrap_or_default(),
         );
         // Schedule the finalizer and keep track of it.
         let mut tasks_locked = self.tasks.lock().await;
@@ -96,14 +159,153 @@ but hints that assumptions made elsewhere might be incorrect"
                 return;
             }
         }
-        let finalizer_task = ScheduledTask::new(finalizer, election.metadata.end_time);
+        // Finalizing early would audit ballots still being voted on, so admins
+        // must archive the election instead.
+        let finalizer_task = ScheduledTask::new(
+            finalizer_name(election.id),
+            finalizer,
+            election.metadata.end_time,
+        );
+        self.registry.register(&finalizer_task, false);
         tasks_locked.insert(election.id, finalizer_task);
     }
 
-    /// Immediately trigger the finalizer for the given election.
+    /// Schedule an end warning for the given election, replacing any existing one.
+    async fn schedule_end_warning(&self, db: &Database, election: &Election) {
+        let mut warnings_locked = self.warnings.lock().await;
+        if let Some(task) = warnings_locked.remove(&election.id) {
+            task.cancel().await;
+        }
+
+        let warn_at = election.metadata.end_time - self.end_warning.lead;
+        if election.metadata.state != ElectionState::Published
+            || election.end_warning.is_some()
+            || warn_at <= Utc::now()
+        {
+            return;
+        }
+        let election_id = election.id;
+        let db = db.clone();
+        let settings = self.end_warning.clone();
+        let warnings = self.warnings.clone();
+        let warning = async move {
+            warn_if_unconfirmed(election_id, db, settings).await;
+            warnings.lock().await.remove(&election_id);
+            trace!("End warning completed; removed self from list");
+        };
+        let warning_task = ScheduledTask::new(
+            format!("election-{election_id}-end-warning"),
+            warning,
+            warn_at,
+        );
+        self.registry.register(&warning_task, true);
+        warnings_locked.insert(election.id, warning_task);
+    }
+
+    /// Schedule a finalizer for each question of the given election that closes
+    /// before the election ends, replacing any existing ones.
+    /// These only audit the question's unconfirmed ballots; if one fails, the
+    /// election's own finalizer audits whatever is left when the election ends.
+    async fn schedule_question_finalizers(
+        &self,
+        db_client: &Client,
+        db: &Database,
+        election: &Election,
+    ) {
+        self.cancel_question_finalizers(election.id).await;
+        if election.metadata.state != ElectionState::Published {
+            return;
+        }
+
+        let mut question_tasks_locked = self.question_tasks.lock().await;
+        for question in election.questions.values() {
+            let end_time = match question.end_time {
+                Some(end_time) if end_time.to_chrono() < election.metadata.end_time => {
+                    end_time.to_chrono()
+                }
+                _ => continue,
+            };
+            let key = (election.id, question.id);
+            let db_client = db_client.clone();
+            let db = db.clone();
+            let question_tasks = self.question_tasks.clone();
+            let events = self.events.clone();
+            let finalizer = async move {
+                let (election_id, question_id) = key;
+                debug!("Running finalizer for question {question_id} of election {election_id}");
+                let filter = unconfirmed_filter(election_id, Some(question_id));
+                let mut ballots_audited = 0;
+                match audit_unconfirmed(filter, &db_client, &db, &mut ballots_audited).await {
+                    Ok(num_ballots) => {
+                        warn!(
+                            "Finalized question {question_id} of election {election_id}, audited {num_ballots} ballots"
+                        );
+                        events.emit(
+                            DomainEvent::QuestionFinalized {
+                                election_id,
+                                question_id,
+                                audited: num_ballots,
+                            },
+                            None,
+                        );
+                    }
+                    Err(e) => error!(
+                        "Finalizer for question {question_id} of election {election_id} failed \
+after auditing {ballots_audited} ballots; the rest will be audited when the election ends: {e}"
+                    ),
+                }
+                question_tasks.lock().await.remove(&key);
+                trace!("Question finalizer completed; removed self from list");
+            };
+            // Auditing a question's ballots early would cut off voters still
+            // answering it, so this waits for the question to close.
+            let finalizer_task = ScheduledTask::new(
+                format!("election-{}-question-{}-finalizer", key.0, key.1),
+                finalizer,
+                end_time,
+            );
+            self.registry.register(&finalizer_task, false);
+            question_tasks_locked.insert(key, finalizer_task);
+        }
+    }
+
+    /// Cancel the finalizers for the given election's questions, if any.
+    async fn cancel_question_finalizers(&self, election_id: ElectionId) {
+        let mut question_tasks_locked = self.question_tasks.lock().await;
+        let keys = question_tasks_locked
+            .keys()
+            .filter(|(id, _)| *id == election_id)
+            .copied()
+            .collect::<Vec<_>>();
+        for key in keys {
+            if let Some(task) = question_tasks_locked.remove(&key) {
+                task.cancel().await;
+            }
+        }
+    }
+
+    /// Cancel the finalizers and end warning for the given election, if any.
+    pub async fn cancel_election(&self, election_id: ElectionId) {
+        if let Some(task) = self.tasks.lock().await.remove(&election_id) {
+            task.cancel().await;
+        }
+        self.cancel_question_finalizers(election_id).await;
+        if let Some(task) = self.warnings.lock().await.remove(&election_id) {
+            task.cancel().await;
+        }
+    }
+
+    /// Immediately trigger the finalizer for the given election, which records
+    /// its outcome on the election before this returns.
     /// If the finalizer was not previously scheduled (or already completed),
     /// this will have no effect.
+    /// Any pending end warning or question finalizers are cancelled, since
+    /// they would come too late.
     pub async fn finalize_election(&self, election_id: ElectionId) -> Result<(), Error> {
+        if let Some(task) = self.warnings.lock().await.remove(&election_id) {
+            task.cancel().await;
+        }
+        self.cancel_question_finalizers(election_id).await;
         let mut tasks_locked = self.tasks.lock().await;
         let task = tasks_locked.remove(&election_id);
         drop(tasks_locked); // Avoid deadlock, as the finalizer needs the lock too.
@@ -121,91 +323,311 @@ but hints that assumptions made elsewhere might be incorrect"
         }
     }
 
-    /// Finalize the given election by auditing all unconfirmed ballots.
+    /// Finalize the given election by auditing all unconfirmed ballots, then
+    /// record the outcome of this run, added to `finalization`, on the election.
     /// Since this is a recursive async function, we must use `BoxFuture` to
     /// avoid an infinitely-recursive state machine.
     fn finalizer(
         election_id: ElectionId,
-        unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
-        audited_ballots: Coll<Ballot<Audited>>,
+        db_client: Client,
+        db: Database,
         tasks: Arc<Mutex<TaskMap>>,
+        events: EventBus,
+        registry: TaskRegistry,
+        mut finalization: ElectionFinalization,
     ) -> BoxFuture<'static, Result<(), Error>> {
         /// Nested function for error handling.
+        /// Counts each audited ballot in `ballots_audited` as it goes, so
+        /// that a partial audit is still counted.
         async fn finalize(
             election_id: ElectionId,
-            unconfirmed_ballots: Coll<Ballot<Unconfirmed>>,
-            audited_ballots: Coll<Ballot<Audited>>,
-        ) -> Result<(), Error> {
+            db_client: &Client,
+            db: &Database,
+            ballots_audited: &mut u32,
+        ) -> Result<usize, Error> {
             debug!("Running finalizer for election {election_id}");
-            // Get all unconfirmed ballots.
-            let filter = doc! {
-                "election_id": election_id,
-                "state": Unconfirmed,
-            };
-            let ballots: Vec<_> = unconfirmed_ballots
-                .find(filter, None)
+            // Audit all unconfirmed ballots.
+            let filter = unconfirmed_filter(election_id, None);
+            let num_ballots = audit_unconfirmed(filter, db_client, db, ballots_audited).await?;
+
+            // Fold any totals deltas that confirmations left behind, so the
+            // final totals are complete.
+            let mut session = db_client.start_session(None).await?;
+            let candidate_totals = Coll::from_db(db);
+            let totals_deltas = Coll::<TotalsDelta>::from_db(db);
+            let question_ids = totals_deltas
+                .find(doc! { "election_id": election_id }, None)
                 .await?
-                .try_collect()
+                .map_ok(|delta| delta.question_id)
+                .try_collect::<HashSet<_>>()
+                .await?;
+            for question_id in question_ids {
+                let folded = fold_totals(
+                    election_id,
+                    question_id,
+                    &candidate_totals,
+                    &totals_deltas,
+                    &mut session,
+                    None,
+                )
                 .await?;
-            // Audit them. Bulk operation support isn't in rust-mongodb yet,
-            // so we have to do them individually.
-            // We deliberately do not do this in a transaction as a partial
-            // audit is still better than nothing.
-            let num_ballots = ballots.len();
-            for ballot in ballots {
-                let ballot = ballot.audit();
-                let result = audited_ballots
-                    .replace_one(ballot.internal_id.as_doc(), &ballot, None)
-                    .await?;
-                assert_eq!(result.modified_count, 1);
+                debug!("Finalizer folded {folded} totals deltas for question {question_id}");
             }
+
             if num_ballots > 0 {
                 warn!("Finalized election {election_id}, audited {num_ballots} ballots");
             } else {
                 debug!("Finalizer for election {election_id} had nothing to do");
             }
+            Ok(num_ballots)
+        }
+
+        /// Record the finalizer's progress on the election.
+        async fn record(
+            election_id: ElectionId,
+            db: &Database,
+            finalization: ElectionFinalization,
+        ) -> Result<(), DbError> {
+            let update = doc! {
+                "$set": {
+                    "finalization": finalization,
+                }
+            };
+            Coll::<Election>::from_db(db)
+                .update_one(u32_id_filter(election_id), update, None)
+                .await?;
             Ok(())
         }
 
         async move {
-            let result = finalize(election_id, unconfirmed_ballots.clone(), audited_ballots.clone()).await;
+            finalization.attempts += 1;
+            let result = finalize(election_id, &db_client, &db, &mut finalization.ballots_audited).await;
+            match &result {
+                Ok(_) => {
+                    finalization.completed_at.get_or_insert_with(DateTime::now);
+                    finalization.last_error = None;
+                }
+                Err(e) => finalization.last_error = Some(e.to_string()),
+            }
+            if let Err(e) = record(election_id, &db, finalization.clone()).await {
+                error!("Failed to record finalization of election {election_id}: {e}");
+            }
             match result {
-                Ok(()) => {
+                Ok(num_ballots) => {
                     tasks.lock().await.remove(&election_id);
                     trace!("Finalizer completed; removed self from list");
+                    events.emit(
+                        DomainEvent::ElectionFinalized {
+                            election_id,
+                            audited: num_ballots,
+                        },
+                        None,
+                    );
+                    Ok(())
                 }
-                Err(ref e) => {
+                Err(e) => {
                     error!("Finalizer for election {election_id} failed, unconfirmed ballots might be leaked: {e}");
                     // Re-schedule the finalizer.
                     let retry = Self::finalizer(
                         election_id,
-                        unconfirmed_ballots,
-                        audited_ballots,
+                        db_client,
+                        db,
                         tasks.clone(),
+                        events,
+                        registry.clone(),
+                        finalization,
                     );
                     const RETRY_INTERVAL_SECONDS: i64 = 300;
                     let retry_time = Utc::now() + Duration::try_seconds(RETRY_INTERVAL_SECONDS).unwrap();
                     let mut tasks_locked = tasks.lock().await;
-                    let finalizer_task = ScheduledTask::new(retry, retry_time);
+                    let finalizer_task = ScheduledTask::new(finalizer_name(election_id), retry, retry_time);
+                    // Voting is over by the time a finalizer runs, so a retry may be triggered early.
+                    registry.register(&finalizer_task, true);
                     tasks_locked.insert(election_id, finalizer_task);
                     warn!("Failed finalizer will be retried in {RETRY_INTERVAL_SECONDS} seconds");
+                    Err(e)
                 }
             }
-            result
         }.boxed()
     }
 }
 
-impl Default for ElectionFinalizers {
-    fn default() -> Self {
-        Self::new()
+/// The name of the given election's finalizer task.
+fn finalizer_name(election_id: ElectionId) -> String {
+    format!("election-{election_id}-finalizer")
+}
+
+/// Filter for the unconfirmed ballots that finalizing the given election, or
+/// just one of its questions, audits.
+fn unconfirmed_filter(election_id: ElectionId, question_id: Option<QuestionId>) -> Document {
+    let mut filter = doc! {
+        "election_id": election_id,
+        "state": Unconfirmed,
+    };
+    if let Some(question_id) = question_id {
+        filter.insert("question_id", question_id);
     }
+    filter
+}
+
+/// How long ago an unconfirmed ballot was cast, as counted by [`preview_finalization`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BallotAge {
+    Under5Minutes,
+    Under1Hour,
+    Older,
+}
+
+impl BallotAge {
+    /// The count of ballots of this age.
+    fn count_in(self, counts: &mut UnconfirmedByAge) -> &mut u64 {
+        match self {
+            BallotAge::Under5Minutes => &mut counts.under_5_minutes,
+            BallotAge::Under1Hour => &mut counts.under_1_hour,
+            BallotAge::Older => &mut counts.older,
+        }
+    }
+}
+
+/// The unconfirmed ballots of one question and age, as produced by
+/// [`preview_finalization`].
+#[derive(Deserialize)]
+struct AgeGroup {
+    #[serde(rename = "_id")]
+    key: AgeGroupKey,
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct AgeGroupKey {
+    question_id: QuestionId,
+    age: BallotAge,
+}
+
+/// Count the unconfirmed ballots that finalizing the given election would
+/// audit, by question and by how long before `now` they were cast, without
+/// changing anything.
+pub async fn preview_finalization(
+    election_id: ElectionId,
+    ballots: &Coll<AnyBallot>,
+    now: chrono::DateTime<Utc>,
+    options: impl Into<Option<AggregateOptions>>,
+) -> Result<FinalizePreview, DbError> {
+    let five_minutes_ago = DateTime::from_chrono(now - Duration::try_minutes(5).unwrap());
+    let an_hour_ago = DateTime::from_chrono(now - Duration::try_hours(1).unwrap());
+    let pipeline = [
+        doc! {
+            "$match": unconfirmed_filter(election_id, None),
+        },
+        doc! {
+            "$group": {
+                "_id": {
+                    "question_id": "$question_id",
+                    "age": {
+                        "$switch": {
+                            "branches": [
+                                {
+                                    "case": {"$gte": ["$creation_time", five_minutes_ago]},
+                                    "then": "under_5_minutes",
+                                },
+                                {
+                                    "case": {"$gte": ["$creation_time", an_hour_ago]},
+                                    "then": "under_1_hour",
+                                },
+                            ],
+                            "default": "older",
+                        }
+                    },
+                },
+                "count": {"$sum": 1},
+            }
+        },
+    ];
+    let mut cursor = ballots
+        .aggregate(pipeline, options)
+        .await?
+        .with_type::<AgeGroup>();
+
+    let mut total = UnconfirmedByAge::default();
+    let mut questions = HashMap::<_, UnconfirmedByAge>::new();
+    while let Some(group) = cursor.try_next().await? {
+        let age = group.key.age;
+        *age.count_in(&mut total) += group.count;
+        *age.count_in(questions.entry(group.key.question_id).or_default()) += group.count;
+    }
+    Ok(FinalizePreview {
+        as_of: now,
+        total,
+        questions,
+    })
+}
+
+/// Audit the unconfirmed ballots matching the given filter, returning how many
+/// there were. Counts each audited ballot in `ballots_audited` as it goes, so
+/// that a partial audit is still counted.
+async fn audit_unconfirmed(
+    filter: Document,
+    db_client: &Client,
+    db: &Database,
+    ballots_audited: &mut u32,
+) -> Result<usize, Error> {
+    let unconfirmed_ballots = Coll::<Ballot<Unconfirmed>>::from_db(db);
+    let audited_ballots = Coll::<Ballot<Audited>>::from_db(db);
+    let board_heads = Coll::<BoardHead>::from_db(db);
+    let board_entries = Coll::<BoardEntry>::from_db(db);
+    let ballots: Vec<_> = unconfirmed_ballots
+        .find(filter, None)
+        .await?
+        .try_collect()
+        .await?;
+    // Audit them. Bulk operation support isn't in rust-mongodb yet,
+    // so we have to do them individually.
+    // We deliberately use a transaction per ballot rather than one for
+    // the whole lot, as a partial audit is still better than nothing.
+    // Each transaction also appends the audit to the bulletin board.
+    let num_ballots = ballots.len();
+    let mut session = db_client.start_session(None).await?;
+    for ballot in ballots {
+        let mut ballot = ballot.audit();
+        session
+            .with_transaction(
+                (&mut ballot, &audited_ballots, &board_heads, &board_entries),
+                |session, (ballot, audited_ballots, board_heads, board_entries)| {
+                    async move {
+                        append_to_board(
+                            [&mut ballot.ballot],
+                            board_heads,
+                            board_entries,
+                            session,
+                            None,
+                        )
+                        .await?;
+                        let result = audited_ballots
+                            .replace_one_with_session(
+                                ballot.internal_id.as_doc(),
+                                &**ballot,
+                                None,
+                                session,
+                            )
+                            .await?;
+                        assert_eq!(result.modified_count, 1);
+                        Ok(())
+                    }
+                    .boxed()
+                },
+                None,
+            )
+            .await?;
+        *ballots_audited += 1;
+    }
+    Ok(num_ballots)
 }
 
 /// A fairing that schedules finalizers for all applicable elections
 /// during Rocket ignition, and places an `ElectionFinalizers` into managed state.
-/// This fairing depends on the database being available in managed state,
-/// and so must be attached after the fairing responsible for that.
+/// This fairing depends on the config, database and event bus being available in
+/// managed state, and so must be attached after the fairings responsible for those.
 pub struct ElectionFinalizerFairing;
 
 #[rocket::async_trait]
@@ -220,15 +642,33 @@ impl Fairing for ElectionFinalizerFairing {
     async fn on_ignite(&self, mut rocket: Rocket<Build>) -> rocket::fairing::Result {
         // Create an election finalizer for every election that needs one.
         info!("Scheduling election finalizers...");
-        let election_finalizers = ElectionFinalizers::new();
-        let db = match rocket.state::<Database>() {
-            Some(db) => db,
-            None => {
-                error!("Database was not available when scheduling finalizers");
+        let (config, db_client, db, events, registry) = match (
+            rocket.state::<Config>(),
+            rocket.state::<Client>(),
+            rocket.state::<Database>(),
+            rocket.state::<EventBus>(),
+            rocket.state::<TaskRegistry>(),
+        ) {
+            (Some(config), Some(db_client), Some(db), Some(events), Some(registry)) => {
+                (config, db_client, db, events, registry)
+            }
+            _ => {
+                error!(
+                    "Config, database, event bus or task registry was not available when scheduling finalizers"
+                );
                 return Err(rocket);
             }
         };
-        if let Err(e) = election_finalizers.schedule_elections(db).await {
+        let election_finalizers = ElectionFinalizers::new(
+            EndWarningSettings {
+                lead: config.end_warning_lead(),
+                threshold: config.end_warning_threshold(),
+                webhook_url: config.admin_webhook_url().map(str::to_string),
+            },
+            events.clone(),
+            registry.clone(),
+        );
+        if let Err(e) = election_finalizers.schedule_elections(db_client, db).await {
             error!("Failed to schedule election finalizers: {e}");
             return Err(rocket);
         }
@@ -1,14 +1,20 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
-use mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use mongodb::bson::{self, serde_helpers::chrono_datetime_as_bson_datetime};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::model::common::election::ElectionState;
+use crate::model::common::election::{ElectionState, Translations};
 
 /// A view on just the election's top-level metadata.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct ElectionMetadata {
     /// Election name.
     pub name: String,
+    /// Translations of the name, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub name_translations: Translations,
     /// Election state.
     pub state: ElectionState,
     /// Election start time.
@@ -17,4 +23,56 @@ pub struct ElectionMetadata {
     /// Election end time.
     #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub end_time: DateTime<Utc>,
+    /// IANA name of the timezone the election's times were given in, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Is voting temporarily suspended?
+    #[serde(default)]
+    pub suspended: bool,
+    /// Must voters re-verify their SMS number before voting?
+    #[serde(default)]
+    pub requires_step_up: bool,
+    /// Does each voter see each question's candidates in their own order?
+    #[serde(default)]
+    pub randomise_candidate_order: bool,
+    /// The most voters who may join the election, if it is capped.
+    #[serde(default)]
+    pub max_voters: Option<u32>,
+    /// Voters may join this many minutes before the election starts, if set.
+    #[serde(default)]
+    pub pre_join_minutes: Option<u32>,
+    /// Until this time, the election's ballots and dumps are hidden from
+    /// everyone but admins, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embargo_public_board_until: Option<bson::DateTime>,
+    /// Text voters must accept before joining the election, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_text: Option<String>,
+    /// Version of the consent text, which voters must give when joining.
+    #[serde(default)]
+    pub consent_version: u32,
+    /// Number of voters who have joined the election.
+    #[serde(default)]
+    pub joined_count: u32,
+    /// Display metadata for frontends, passed through verbatim.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branding: Option<Value>,
+    /// Username of the admin who created the election.
+    /// Empty for elections created before this was recorded.
+    #[serde(default)]
+    pub created_by: String,
+    /// Username of the admin who last modified the election, if any.
+    #[serde(default)]
+    pub last_modified_by: Option<String>,
+    /// Is this a throwaway copy of another election, made to rehearse it?
+    /// Rehearsals are hidden from everyone but admins.
+    #[serde(default)]
+    pub rehearsal: bool,
+    /// When the election was archived, if it has been. Elections archived
+    /// before this was recorded are treated as archived when the server was
+    /// upgraded; see [`ensure_archive_times_exist`].
+    ///
+    /// [`ensure_archive_times_exist`]: crate::model::db::maintenance::ensure_archive_times_exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<bson::DateTime>,
 }
@@ -1,7 +1,13 @@
+mod archive_export;
 mod base;
+mod end_warning;
+mod finalization;
 mod finalizer;
 mod metadata;
 
-pub use base::{Election, Question};
-pub use finalizer::{ElectionFinalizerFairing, ElectionFinalizers};
+pub use archive_export::ArchiveExport;
+pub use base::{ensure_question_slugs_exist, Election, ElectionKeys, Question};
+pub use end_warning::{EndWarning, EndWarningSettings};
+pub use finalization::ElectionFinalization;
+pub use finalizer::{preview_finalization, ElectionFinalizerFairing, ElectionFinalizers};
 pub use metadata::ElectionMetadata;
@@ -0,0 +1,230 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::OnceLock;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use mongodb::{
+    bson::{doc, Bson, Document},
+    error::Error as DbError,
+    Database,
+};
+use rand::RngCore;
+use rocket::futures::TryStreamExt;
+use zeroize::Zeroizing;
+
+use crate::model::{
+    common::ballot::Audited,
+    db::ballot::Ballot,
+    mongodb::{Coll, MongoCollection},
+};
+
+/// Field under which a ballot's sealed vote secrets are stored.
+pub const SEALED_SECRETS_FIELD: &str = "sealed_secrets";
+
+/// The per-vote fields holding a ballot's secrets.
+const SECRET_FIELDS: [&str; 2] = ["r", "v"];
+
+/// Length of an AES-GCM nonce, in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// The key installed for this process by [`install`], if any.
+static INSTALLED_KEY: OnceLock<FieldKey> = OnceLock::new();
+
+/// A key for encrypting ballot secrets at rest, held outside the database.
+///
+/// When one is configured, the vote secrets of audited ballots are sealed
+/// with AES-256-GCM whenever they are written to the database, and unsealed
+/// whenever they are read, so nothing else needs to know about it.
+pub struct FieldKey {
+    cipher: Aes256Gcm,
+}
+
+impl FieldKey {
+    /// Parse a key from 32 base64-encoded bytes.
+    pub fn from_base64(key: &str) -> Result<Self, String> {
+        let bytes = Zeroizing::new(
+            BASE64
+                .decode(key.trim().as_bytes())
+                .map_err(|e| format!("not valid base64: {e}"))?,
+        );
+        let cipher = Aes256Gcm::new_from_slice(&bytes)
+            .map_err(|_| format!("expected 32 bytes, got {}", bytes.len()))?;
+        Ok(Self { cipher })
+    }
+
+    /// Seal the vote secrets of a serialised ballot's `votes`, binding them to
+    /// the given associated data, e.g. the ballot's identity.
+    ///
+    /// Does nothing if there is nothing to seal.
+    pub fn seal(&self, crypto: &mut Document, aad: &str) -> Result<(), String> {
+        let Ok(votes) = crypto.get_document_mut("votes") else {
+            return Ok(());
+        };
+        let mut secrets = Document::new();
+        for (candidate, vote) in votes.iter_mut() {
+            let Bson::Document(vote) = vote else {
+                continue;
+            };
+            let mut vote_secrets = Document::new();
+            for field in SECRET_FIELDS {
+                if let Some(secret) = vote.remove(field) {
+                    vote_secrets.insert(field, secret);
+                }
+            }
+            if !vote_secrets.is_empty() {
+                secrets.insert(candidate.clone(), vote_secrets);
+            }
+        }
+        if secrets.is_empty() {
+            return Ok(());
+        }
+
+        let mut plaintext = Zeroizing::new(Vec::new());
+        secrets
+            .to_writer(&mut *plaintext)
+            .map_err(|e| format!("Failed to serialise ballot secrets: {e}"))?;
+        let mut nonce = [0; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let payload = Payload {
+            msg: &plaintext,
+            aad: aad.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| "Failed to encrypt ballot secrets".to_string())?;
+        crypto.insert(
+            SEALED_SECRETS_FIELD,
+            doc! {
+                "nonce": BASE64URL_NOPAD.encode(&nonce),
+                "ciphertext": BASE64URL_NOPAD.encode(&ciphertext),
+            },
+        );
+        Ok(())
+    }
+
+    /// Unseal vote secrets sealed by [`FieldKey::seal`] with the same
+    /// associated data, restoring them to the ballot's `votes`.
+    ///
+    /// Does nothing if the secrets are not sealed.
+    pub fn unseal(&self, crypto: &mut Document, aad: &str) -> Result<(), String> {
+        let Some(sealed) = crypto.remove(SEALED_SECRETS_FIELD) else {
+            return Ok(());
+        };
+        let malformed = || "Malformed sealed ballot secrets".to_string();
+        let Bson::Document(sealed) = sealed else {
+            return Err(malformed());
+        };
+        let decode = |field| {
+            sealed
+                .get_str(field)
+                .ok()
+                .and_then(|encoded| BASE64URL_NOPAD.decode(encoded.as_bytes()).ok())
+                .ok_or_else(malformed)
+        };
+        let nonce = decode("nonce")?;
+        if nonce.len() != NONCE_LENGTH {
+            return Err(malformed());
+        }
+        let ciphertext = decode("ciphertext")?;
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: aad.as_bytes(),
+        };
+        let plaintext = Zeroizing::new(
+            self.cipher
+                .decrypt(Nonce::from_slice(&nonce), payload)
+                .map_err(|_| "Failed to decrypt ballot secrets: wrong key?".to_string())?,
+        );
+        let secrets = Document::from_reader(&mut plaintext.as_slice()).map_err(|_| malformed())?;
+
+        let votes = crypto.get_document_mut("votes").map_err(|_| malformed())?;
+        for (candidate, vote_secrets) in secrets {
+            let (Some(Bson::Document(vote)), Bson::Document(vote_secrets)) =
+                (votes.get_mut(&candidate), vote_secrets)
+            else {
+                return Err(malformed());
+            };
+            vote.extend(vote_secrets);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for FieldKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FieldKey([REDACTED])")
+    }
+}
+
+/// Install the key used to seal and unseal ballot secrets for the rest of
+/// this process. The key cannot be changed once installed.
+pub fn install(key: FieldKey) -> Result<(), String> {
+    INSTALLED_KEY
+        .set(key)
+        .map_err(|_| "A field encryption key is already installed".to_string())
+}
+
+/// The installed field encryption key, if any.
+pub fn installed() -> Option<&'static FieldKey> {
+    INSTALLED_KEY.get()
+}
+
+/// Seal the secrets of all audited ballots stored before a key was installed,
+/// returning how many were sealed.
+///
+/// Ballots are rewritten one at a time, so this can safely be interrupted and
+/// run again. Fails if no key is installed.
+pub async fn seal_existing_ballots(db: &Database) -> Result<u64, DbError> {
+    let Some(key) = installed() else {
+        return Err(DbError::custom("No field encryption key is installed"));
+    };
+    seal_existing_ballots_with(db, key).await
+}
+
+/// Seal the secrets of all unsealed audited ballots with the given key, as
+/// for [`seal_existing_ballots`].
+pub(crate) async fn seal_existing_ballots_with(
+    db: &Database,
+    key: &FieldKey,
+) -> Result<u64, DbError> {
+    let ballots = Coll::<Ballot<Audited>>::from_db(db);
+    let raw_ballots = db.collection::<Document>(Ballot::<Audited>::NAME);
+    // Redacted ballots have no secrets left to seal.
+    let unsealed = doc! {
+        "state": Audited,
+        SEALED_SECRETS_FIELD: { "$exists": false },
+        "redacted": { "$ne": true },
+    };
+    let mut cursor = ballots.find(unsealed, None).await?;
+    let mut sealed = 0;
+    while let Some(ballot) = cursor.try_next().await? {
+        let stored = ballot.sealed_with(key).map_err(DbError::custom)?;
+        raw_ballots
+            .replace_one(ballot.internal_id.as_doc(), stored, None)
+            .await?;
+        sealed += 1;
+    }
+    info!("Sealed the secrets of {} audited ballots", sealed);
+    Ok(sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_parsing() {
+        assert!(FieldKey::from_base64(&BASE64.encode(&[0; 32])).is_ok());
+        assert!(FieldKey::from_base64(&BASE64.encode(&[0; 16])).is_err());
+        assert!(FieldKey::from_base64("not base64!").is_err());
+        let debug = format!(
+            "{:?}",
+            FieldKey::from_base64(&BASE64.encode(&[0; 32])).unwrap()
+        );
+        assert_eq!(debug, "FieldKey([REDACTED])");
+    }
+}
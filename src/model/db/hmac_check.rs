@@ -0,0 +1,257 @@
+use hmac::Mac;
+use mongodb::{
+    bson::{doc, DateTime},
+    error::Error as DbError,
+    options::{FindOneOptions, ReplaceOptions},
+    Database,
+};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    Build, Rocket,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    model::{
+        db::voter::HmacSha256,
+        mongodb::{is_duplicate_key_error, Coll},
+    },
+};
+
+/// The ID of the only document in the collection.
+const HMAC_CHECK_ID: &str = "hmac_check";
+
+/// The known plaintext whose HMAC is stored.
+const HMAC_CHECK_PLAINTEXT: &[u8] = b"dreip-backend hmac_secret check";
+
+/// A known-plaintext HMAC, written when the database is first used, so that a
+/// change of `hmac_secret` can be detected.
+///
+/// Voters are identified by the HMAC of their SMS number, so changing the
+/// secret silently orphans every existing voter: returning voters are treated
+/// as new ones, and lose the elections they had joined.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HmacCheck {
+    #[serde(rename = "_id")]
+    id: String,
+    /// The HMAC of [`HMAC_CHECK_PLAINTEXT`] with the current secret.
+    #[serde(with = "dre_ip::group::serde_bytestring")]
+    hmac: Vec<u8>,
+    /// When the document was first written.
+    pub created_at: DateTime,
+    /// When a change of secret was last accepted, if ever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated_at: Option<DateTime>,
+}
+
+/// How the stored HMAC compares with the configured secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HmacStatus {
+    /// Nothing is stored yet.
+    Missing,
+    /// The stored HMAC was computed with the configured secret.
+    Matches(HmacCheck),
+    /// The stored HMAC was computed with a different secret.
+    Mismatch(HmacCheck),
+}
+
+impl HmacCheck {
+    /// A new check for the given secret.
+    fn new(secret: &[u8]) -> Self {
+        Self {
+            id: HMAC_CHECK_ID.to_string(),
+            hmac: Self::compute(secret),
+            created_at: DateTime::now(),
+            rotated_at: None,
+        }
+    }
+
+    /// The HMAC of the known plaintext with the given secret.
+    fn compute(secret: &[u8]) -> Vec<u8> {
+        let mut hmac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+        hmac.update(HMAC_CHECK_PLAINTEXT);
+        hmac.finalize().into_bytes().to_vec()
+    }
+
+    /// Get the stored check, if there is one.
+    pub async fn get(
+        checks: &Coll<HmacCheck>,
+        options: impl Into<Option<FindOneOptions>>,
+    ) -> Result<Option<HmacCheck>, DbError> {
+        checks
+            .find_one(doc! { "_id": HMAC_CHECK_ID }, options)
+            .await
+    }
+
+    /// Compare the stored check with the given secret, without changing it.
+    pub async fn status(checks: &Coll<HmacCheck>, secret: &[u8]) -> Result<HmacStatus, DbError> {
+        let expected = Self::compute(secret);
+        Ok(match Self::get(checks, None).await? {
+            None => HmacStatus::Missing,
+            Some(check) if check.hmac == expected => HmacStatus::Matches(check),
+            Some(check) => HmacStatus::Mismatch(check),
+        })
+    }
+}
+
+/// Describe why a mismatched secret is refused, and how to proceed.
+pub fn mismatch_message(check: &HmacCheck) -> String {
+    format!(
+        "The configured `hmac_secret` is not the one this database has used since {}: it has \
+         probably been changed. Every existing voter would be treated as a new one. Restore the \
+         previous secret, or, to orphan the existing voters deliberately, launch once with \
+         `--accept-hmac-rotation` or `accept_hmac_rotation = true`",
+        check.rotated_at.unwrap_or(check.created_at).to_chrono()
+    )
+}
+
+/// Check that `hmac_secret` has not changed since the database was first used,
+/// storing the check if this is the first time.
+///
+/// If it has changed, this fails unless `accept_rotation` is set, in which
+/// case the check is rewritten for the new secret.
+pub async fn ensure_hmac_secret_unchanged(
+    db: &Database,
+    secret: &[u8],
+    accept_rotation: bool,
+) -> Result<(), String> {
+    let checks = Coll::<HmacCheck>::from_db(db);
+    let failed = |e| format!("Failed to check `hmac_secret`: {e}");
+    let mut status = HmacCheck::status(&checks, secret).await.map_err(failed)?;
+    if status == HmacStatus::Missing {
+        info!("Storing `hmac_secret` check for a new database");
+        let result = checks.insert_one(HmacCheck::new(secret), None).await;
+        // Another instance starting at the same time may have stored one first.
+        if !is_duplicate_key_error(result.as_ref()) {
+            result.map_err(failed)?;
+        }
+        status = HmacCheck::status(&checks, secret).await.map_err(failed)?;
+    }
+
+    match status {
+        HmacStatus::Missing => Err("The `hmac_secret` check was not stored".to_string()),
+        HmacStatus::Matches(_) => {
+            if accept_rotation {
+                warn!(
+                    "`accept_hmac_rotation` is set, but `hmac_secret` has not changed; unset it \
+                     so that future changes are caught"
+                );
+            }
+            Ok(())
+        }
+        HmacStatus::Mismatch(check) if !accept_rotation => Err(mismatch_message(&check)),
+        HmacStatus::Mismatch(check) => {
+            let rotated = HmacCheck {
+                created_at: check.created_at,
+                rotated_at: Some(DateTime::now()),
+                ..HmacCheck::new(secret)
+            };
+            checks
+                .replace_one(
+                    doc! { "_id": HMAC_CHECK_ID },
+                    &rotated,
+                    ReplaceOptions::builder().upsert(true).build(),
+                )
+                .await
+                .map_err(failed)?;
+            warn!("**************************************************************");
+            warn!("Accepted a change of `hmac_secret`. Every voter registered before");
+            warn!("now is orphaned: returning voters will be treated as new ones and");
+            warn!("will not see the elections they joined. Unset");
+            warn!("`accept_hmac_rotation` now, so that future changes are caught.");
+            warn!("**************************************************************");
+            Ok(())
+        }
+    }
+}
+
+/// Refuses to launch if `hmac_secret` has changed since the database was
+/// first used, unless the change is accepted.
+pub struct HmacCheckFairing;
+
+#[rocket::async_trait]
+impl Fairing for HmacCheckFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "HMAC Secret Check",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let (config, db) = match (rocket.state::<Config>(), rocket.state::<Database>()) {
+            (Some(config), Some(db)) => (config, db),
+            _ => {
+                error!("Config or database was not available when checking `hmac_secret`");
+                return Err(rocket);
+            }
+        };
+        if let Err(e) =
+            ensure_hmac_secret_unchanged(db, config.hmac_secret(), config.accept_hmac_rotation())
+                .await
+        {
+            error!("{e}");
+            return Err(rocket);
+        }
+        Ok(rocket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{error::ErrorKind, figment::Figment, local::asynchronous::Client};
+
+    use super::*;
+
+    /// Build a client on the given database, or fail to ignite.
+    async fn client(db_name: &str, secret: &str, accept_rotation: bool) -> Option<Client> {
+        let figment = rocket::Config::figment()
+            .merge(("test_db_name", db_name))
+            .merge(("hmac_secret", secret))
+            .merge(("accept_hmac_rotation", accept_rotation));
+        match Client::tracked(crate::build().configure(figment)).await {
+            Ok(client) => Some(client),
+            Err(e) => {
+                assert!(matches!(e.kind(), ErrorKind::FailedFairings(_)));
+                None
+            }
+        }
+    }
+
+    fn first_secret(figment: Figment) -> Figment {
+        figment.merge(("hmac_secret", "first secret"))
+    }
+
+    /// Later clients share the test's database, each with its own config.
+    #[backend_test(db = "isolated", config = "first_secret")]
+    async fn refuse_rotation(db: Database) {
+        let db_name = db.name();
+
+        // The check is stored when the database is first used.
+        let checks = Coll::<HmacCheck>::from_db(&db);
+        let original = HmacCheck::get(&checks, None).await.unwrap().unwrap();
+        assert_eq!(original.rotated_at, None);
+        assert!(client(db_name, "first secret", false).await.is_some());
+
+        // A changed secret is refused, leaving the check alone.
+        assert!(client(db_name, "second secret", false).await.is_none());
+        assert_eq!(
+            HmacCheck::status(&checks, b"first secret").await.unwrap(),
+            HmacStatus::Matches(original.clone())
+        );
+
+        // Unless the change is accepted, which rewrites the check.
+        assert!(client(db_name, "second secret", true).await.is_some());
+        let rotated = match HmacCheck::status(&checks, b"second secret").await.unwrap() {
+            HmacStatus::Matches(rotated) => rotated,
+            status => panic!("Rotation was not stored: {status:?}"),
+        };
+        assert_eq!(rotated.created_at, original.created_at);
+        assert!(rotated.rotated_at.is_some());
+
+        // From then on, the new secret is expected, and the old one refused.
+        assert!(client(db_name, "second secret", false).await.is_some());
+        assert!(client(db_name, "first secret", false).await.is_none());
+    }
+}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+/// How long a response is kept for replay to retries of its request.
+pub const IDEMPOTENT_RESPONSE_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A successful response, stored so that retries of its request carrying the
+/// same idempotency key can be answered without being reprocessed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotentResponse {
+    /// The voter, endpoint, and idempotency key the response is stored under.
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// Hash of the request, so that a different request reusing the key is refused.
+    pub fingerprint: String,
+    /// The serialised response body.
+    pub body: String,
+    /// When the response was stored; it expires after [`IDEMPOTENT_RESPONSE_LIFETIME`].
+    pub created_at: DateTime,
+}
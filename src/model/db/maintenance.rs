@@ -0,0 +1,483 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime},
+    error::Error as DbError,
+    options::{FindOneOptions, FindOptions},
+    Database,
+};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    futures::{
+        future::{BoxFuture, FutureExt},
+        TryStreamExt,
+    },
+    tokio::sync::Mutex,
+    Build, Rocket,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::Error,
+    model::{
+        common::{
+            ballot::{Audited, AuditedRedacted},
+            election::{ElectionId, ElectionState},
+        },
+        db::{ballot::Ballot, election::Election, voter::Voter, voter_election::VoterElection},
+        mongodb::{Coll, Counter, Id},
+    },
+    scheduled_task::{ScheduledTask, TaskRegistry},
+};
+
+/// How often maintenance runs automatically.
+const MAINTENANCE_INTERVAL_HOURS: i64 = 24;
+
+/// The name of the scheduled maintenance task.
+const MAINTENANCE_TASK_NAME: &str = "maintenance";
+
+/// A summary of what a maintenance run purged, as stored in the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    /// When the run started.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub run_at: DateTime<Utc>,
+    /// Number of voters removed.
+    pub voters_purged: u64,
+    /// Number of ballot counters removed.
+    pub counters_purged: u64,
+    /// Number of audited ballots whose secrets were removed.
+    /// Absent for runs from before this was done.
+    #[serde(default)]
+    pub ballots_redacted: u64,
+}
+
+impl MaintenanceReport {
+    /// Get the report of the most recent maintenance run, if there has been one.
+    pub async fn latest(
+        reports: &Coll<MaintenanceReport>,
+        mut options: FindOneOptions,
+    ) -> Result<Option<Self>, Error> {
+        options.sort = Some(doc! { "run_at": -1 });
+        Ok(reports.find_one(None, options).await?)
+    }
+}
+
+/// Just enough of an election to decide whether its data can be purged.
+#[derive(Deserialize)]
+struct ElectionRetention {
+    #[serde(rename = "_id")]
+    id: ElectionId,
+    state: ElectionState,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    end_time: DateTime<Utc>,
+    #[serde(default)]
+    archived_at: Option<bson::DateTime>,
+}
+
+/// Record the time of every archived election that was archived before the
+/// time was, returning how many were filled in. Their real archive time is
+/// unknown, so they are treated as archived now: audited ballots' secrets are
+/// then kept for at least the full retention period, rather than redacted
+/// straight away for elections archived long after they ended.
+pub async fn ensure_archive_times_exist(db: &Database) -> Result<u64, DbError> {
+    debug!("Ensuring archived elections have archive times");
+    let filled = Coll::<Election>::from_db(db)
+        .update_many(
+            doc! {
+                "state": ElectionState::Archived,
+                "archived_at": { "$exists": false },
+            },
+            doc! { "$set": { "archived_at": bson::DateTime::now() } },
+            None,
+        )
+        .await?
+        .modified_count;
+    if filled > 0 {
+        warn!("Filled in the archive times of {} elections", filled);
+    }
+    Ok(filled)
+}
+
+/// Periodic database maintenance: purging voters and counters that are no
+/// longer associated with any live election, and optionally removing the
+/// secrets of audited ballots in long-archived elections.
+pub struct Maintenance {
+    db: Database,
+    retention: Duration,
+    secret_retention: Option<Duration>,
+    task: Arc<Mutex<Option<ScheduledTask<()>>>>,
+    registry: TaskRegistry,
+}
+
+impl Maintenance {
+    /// Create a maintenance handle with nothing scheduled, which registers its
+    /// task with `registry` when scheduled.
+    /// Audited ballots are only redacted if `secret_retention` is given.
+    pub fn new(
+        db: Database,
+        retention: Duration,
+        secret_retention: Option<Duration>,
+        registry: TaskRegistry,
+    ) -> Self {
+        Self {
+            db,
+            retention,
+            secret_retention,
+            task: Default::default(),
+            registry,
+        }
+    }
+
+    /// Run maintenance now, store its report and return it.
+    pub async fn run(&self) -> Result<MaintenanceReport, Error> {
+        Self::purge(&self.db, self.retention, self.secret_retention).await
+    }
+
+    /// Schedule maintenance to run one interval after the last run,
+    /// or one interval from now if it has never run, and daily thereafter.
+    pub async fn schedule(&self) -> Result<(), Error> {
+        let interval = Duration::try_hours(MAINTENANCE_INTERVAL_HOURS).unwrap();
+        let last_run = MaintenanceReport::latest(&Coll::from_db(&self.db), Default::default())
+            .await?
+            .map_or_else(Utc::now, |report| report.run_at);
+        let task = Self::scheduled(
+            self.db.clone(),
+            self.retention,
+            self.secret_retention,
+            self.task.clone(),
+            self.registry.clone(),
+        );
+        let task = ScheduledTask::new(MAINTENANCE_TASK_NAME, task, last_run + interval);
+        self.registry.register(&task, true);
+        *self.task.lock().await = Some(task);
+        Ok(())
+    }
+
+    /// Run maintenance, then reschedule for the next interval.
+    /// Since this is a recursive async function, we must use `BoxFuture` to
+    /// avoid an infinitely-recursive state machine.
+    fn scheduled(
+        db: Database,
+        retention: Duration,
+        secret_retention: Option<Duration>,
+        task: Arc<Mutex<Option<ScheduledTask<()>>>>,
+        registry: TaskRegistry,
+    ) -> BoxFuture<'static, ()> {
+        async move {
+            if let Err(e) = Self::purge(&db, retention, secret_retention).await {
+                error!("Scheduled maintenance failed: {e}");
+            }
+            let interval = Duration::try_hours(MAINTENANCE_INTERVAL_HOURS).unwrap();
+            let next = Self::scheduled(
+                db,
+                retention,
+                secret_retention,
+                task.clone(),
+                registry.clone(),
+            );
+            let next = ScheduledTask::new(MAINTENANCE_TASK_NAME, next, Utc::now() + interval);
+            registry.register(&next, true);
+            *task.lock().await = Some(next);
+        }
+        .boxed()
+    }
+
+    /// Remove voters and ballot counters that are no longer needed:
+    /// - Voters registered before the retention period, all of whose elections
+    ///   have been deleted or archived with an end time before the retention period.
+    /// - Ballot counters for elections that no longer exist.
+    ///
+    /// If `secret_retention` is given, also remove the secrets of audited
+    /// ballots in elections archived before that period.
+    async fn purge(
+        db: &Database,
+        retention: Duration,
+        secret_retention: Option<Duration>,
+    ) -> Result<MaintenanceReport, Error> {
+        debug!("Running database maintenance");
+        let run_at = Utc::now();
+        let cutoff = run_at - retention;
+
+        // Read counters and voters before elections, so that anything created
+        // concurrently with an election is not mistaken for an orphan.
+        let counters = Coll::<Counter>::from_db(db);
+        let all_counters: Vec<Counter> = counters
+            .find(doc! { "_id": { "$regex": "^bid:" } }, None)
+            .await?
+            .try_collect()
+            .await?;
+        let voters = Coll::<Voter>::from_db(db);
+        let all_voters: Vec<Voter> = voters.find(None, None).await?.try_collect().await?;
+        let voter_elections = Coll::<VoterElection>::from_db(db);
+        let mut joined: HashMap<Id, Vec<ElectionId>> = HashMap::new();
+        let mut all_voter_elections = voter_elections.find(None, None).await?;
+        while let Some(voter_election) = all_voter_elections.try_next().await? {
+            joined
+                .entry(voter_election.voter_id)
+                .or_default()
+                .push(voter_election.election_id);
+        }
+
+        // Find which elections exist, and which of those are still live.
+        let options = FindOptions::builder()
+            .projection(doc! { "_id": 1, "state": 1, "end_time": 1, "archived_at": 1 })
+            .build();
+        let elections: Vec<ElectionRetention> = Coll::<Election>::from_db(db)
+            .clone_with_type::<ElectionRetention>()
+            .find(None, options)
+            .await?
+            .try_collect()
+            .await?;
+        let existing: HashSet<ElectionId> = elections.iter().map(|e| e.id).collect();
+        let live: HashSet<ElectionId> = elections
+            .iter()
+            .filter(|e| !(e.state == ElectionState::Archived && e.end_time < cutoff))
+            .map(|e| e.id)
+            .collect();
+
+        // Purge voters with no live elections.
+        let expired_voters: Vec<ObjectId> = all_voters
+            .into_iter()
+            .filter(|voter| {
+                voter.id.timestamp().to_chrono() < cutoff
+                    && joined
+                        .get(&voter.id)
+                        .into_iter()
+                        .flatten()
+                        .all(|election_id| !live.contains(election_id))
+            })
+            .map(|voter| voter.id.into())
+            .collect();
+        let voters_purged = if expired_voters.is_empty() {
+            0
+        } else {
+            // Spare any voter that has joined a live election since we looked.
+            let live = live.into_iter().collect::<Vec<_>>();
+            let spared: HashSet<ObjectId> = voter_elections
+                .distinct(
+                    "voter_id",
+                    doc! {
+                        "voter_id": { "$in": expired_voters.clone() },
+                        "election_id": { "$in": live.clone() },
+                    },
+                    None,
+                )
+                .await?
+                .into_iter()
+                .filter_map(|voter_id| voter_id.as_object_id())
+                .collect();
+            let expired_voters = expired_voters
+                .into_iter()
+                .filter(|voter_id| !spared.contains(voter_id))
+                .collect::<Vec<_>>();
+            voter_elections
+                .delete_many(
+                    doc! {
+                        "voter_id": { "$in": expired_voters.clone() },
+                        "election_id": { "$nin": live },
+                    },
+                    None,
+                )
+                .await?;
+            voters
+                .delete_many(doc! { "_id": { "$in": expired_voters } }, None)
+                .await?
+                .deleted_count
+        };
+
+        // Purge ballot counters for elections that no longer exist.
+        let orphaned_counters: Vec<String> = all_counters
+            .into_iter()
+            .filter(|counter| {
+                counter
+                    .id
+                    .split(':')
+                    .nth(1)
+                    .and_then(|election_id| election_id.parse::<ElectionId>().ok())
+                    .is_some_and(|election_id| !existing.contains(&election_id))
+            })
+            .map(|counter| counter.id)
+            .collect();
+        let counters_purged = if orphaned_counters.is_empty() {
+            0
+        } else {
+            counters
+                .delete_many(doc! { "_id": { "$in": orphaned_counters } }, None)
+                .await?
+                .deleted_count
+        };
+
+        // Redact audited ballots in elections archived for long enough, however
+        // long ago they ended. A period reaching before the start of time
+        // redacts nothing.
+        let secret_cutoff = secret_retention.and_then(|period| run_at.checked_sub_signed(period));
+        let ballots_redacted = match secret_cutoff {
+            Some(secret_cutoff) => {
+                let secret_cutoff = bson::DateTime::from_chrono(secret_cutoff);
+                let expired = elections
+                    .iter()
+                    .filter(|e| {
+                        e.state == ElectionState::Archived
+                            && e.archived_at.is_some_and(|at| at < secret_cutoff)
+                    })
+                    .map(|e| e.id)
+                    .collect();
+                Self::redact_audited(db, expired).await?
+            }
+            None => 0,
+        };
+
+        let report = MaintenanceReport {
+            run_at,
+            voters_purged,
+            counters_purged,
+            ballots_redacted,
+        };
+        Coll::<MaintenanceReport>::from_db(db)
+            .insert_one(&report, None)
+            .await?;
+        info!(
+            "Maintenance purged {voters_purged} voters and {counters_purged} counters, \
+            and redacted {ballots_redacted} audited ballots"
+        );
+        Ok(report)
+    }
+
+    /// Remove the secrets of the audited ballots in the given elections that
+    /// still have them, returning how many were redacted.
+    ///
+    /// Ballots are rewritten one at a time, so this can safely be interrupted
+    /// and run again.
+    async fn redact_audited(db: &Database, election_ids: Vec<ElectionId>) -> Result<u64, Error> {
+        if election_ids.is_empty() {
+            return Ok(0);
+        }
+        let audited_ballots = Coll::<Ballot<Audited>>::from_db(db);
+        let redacted_ballots = Coll::<Ballot<AuditedRedacted>>::from_db(db);
+        let filter = doc! {
+            "election_id": { "$in": election_ids },
+            "state": Audited,
+            "redacted": { "$ne": true },
+        };
+        let mut cursor = audited_ballots.find(filter, None).await?;
+        let mut redacted = 0;
+        while let Some(ballot) = cursor.try_next().await? {
+            let ballot = ballot.redact();
+            redacted_ballots
+                .replace_one(ballot.internal_id.as_doc(), &ballot, None)
+                .await?;
+            redacted += 1;
+        }
+        Ok(redacted)
+    }
+}
+
+/// A fairing that places a [`Maintenance`] into managed state and, if enabled
+/// in the config, schedules it to run daily.
+/// This fairing depends on the config and database being available in managed
+/// state, and so must be attached after the fairings responsible for those.
+pub struct MaintenanceFairing;
+
+#[rocket::async_trait]
+impl Fairing for MaintenanceFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Maintenance",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, mut rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let (config, db, registry) = match (
+            rocket.state::<Config>(),
+            rocket.state::<Database>(),
+            rocket.state::<TaskRegistry>(),
+        ) {
+            (Some(config), Some(db), Some(registry)) => (config, db, registry),
+            _ => {
+                error!(
+                    "Config, database or task registry was not available when scheduling maintenance"
+                );
+                return Err(rocket);
+            }
+        };
+        let maintenance = Maintenance::new(
+            db.clone(),
+            config.maintenance_retention(),
+            config.audited_secret_retention(),
+            registry.clone(),
+        );
+        if config.maintenance_enabled() {
+            if let Err(e) = maintenance.schedule().await {
+                error!("Failed to schedule maintenance: {e}");
+                return Err(rocket);
+            }
+            info!("Scheduled daily maintenance");
+        } else {
+            info!("Scheduled maintenance is disabled");
+        }
+
+        // Manage the state.
+        rocket = rocket.manage(maintenance);
+        Ok(rocket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::Document;
+
+    use crate::model::mongodb::{u32_id_filter, MongoCollection};
+
+    use super::*;
+
+    #[backend_test]
+    async fn legacy_archive_times(db: Database) -> anyhow::Result<()> {
+        // Seed elections archived before the time was recorded, and one since.
+        let mut legacy = Election::archived_example();
+        legacy.metadata.archived_at = None;
+        let published = Election::published_example();
+        let recent = Election::archived_example();
+        let archived_at = recent.metadata.archived_at;
+        let elections = Coll::<Election>::from_db(&db);
+        elections
+            .insert_many([&legacy, &published, &recent], None)
+            .await?;
+        let raw_elections = db.collection::<Document>(Election::NAME);
+        let raw_election = |id| {
+            let raw_elections = &raw_elections;
+            async move {
+                raw_elections
+                    .find_one(u32_id_filter(id), None)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        assert!(!raw_election(legacy.id).await.contains_key("archived_at"));
+
+        // Only the legacy election is filled in, and only once.
+        let before = bson::DateTime::now();
+        assert_eq!(ensure_archive_times_exist(&db).await?, 1);
+        let filled = raw_election(legacy.id)
+            .await
+            .get_datetime("archived_at")?
+            .to_owned();
+        assert!(filled >= before);
+        assert!(!raw_election(published.id).await.contains_key("archived_at"));
+        assert_eq!(
+            raw_election(recent.id)
+                .await
+                .get_datetime("archived_at")
+                .ok(),
+            archived_at.as_ref()
+        );
+        assert_eq!(ensure_archive_times_exist(&db).await?, 0);
+
+        Ok(())
+    }
+}
@@ -4,7 +4,19 @@
 //! - IDs and datetimes are serialised in `MongoDB`'s own format.
 
 pub mod admin;
+pub mod admin_token;
+pub mod audit;
 pub mod ballot;
+pub mod board;
 pub mod candidate_totals;
+pub mod challenge_delivery;
 pub mod election;
+pub mod field_encryption;
+pub mod hmac_check;
+pub mod idempotency;
+pub mod maintenance;
+pub mod rate_limit;
+pub mod recall_attempt;
+pub mod rehearsal;
 pub mod voter;
+pub mod voter_election;
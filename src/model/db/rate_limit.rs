@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use data_encoding::HEXLOWER;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{logging::RequestId, model::mongodb::Coll};
+
+/// How long rate limit rejections are kept for the admin summary.
+pub const RATE_LIMIT_EVENT_LIFETIME: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A request rejected by a rate limiter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitEvent {
+    /// Name of the limiter that rejected the request.
+    pub limiter: String,
+    /// Hex SHA-256 hash of the key the limiter counts by, so that repeat
+    /// offenders can be spotted without storing e.g. voter IDs.
+    pub key_hash: String,
+    /// The rejected request, as it appears in the logs.
+    pub request_id: String,
+    /// When the request was rejected; it expires after
+    /// [`RATE_LIMIT_EVENT_LIFETIME`].
+    pub created_at: DateTime,
+}
+
+impl RateLimitEvent {
+    pub fn new(limiter: &str, key: &str, request_id: RequestId) -> Self {
+        Self {
+            limiter: limiter.to_string(),
+            key_hash: hash_key(key),
+            request_id: request_id.to_string(),
+            created_at: DateTime::now(),
+        }
+    }
+}
+
+/// Hash a rate limiter key as it is stored in [`RateLimitEvent::key_hash`].
+pub fn hash_key(key: &str) -> String {
+    HEXLOWER.encode(&Sha256::digest(key.as_bytes()))
+}
+
+/// Something that is told whenever a rate limiter rejects a request.
+#[rocket::async_trait]
+pub trait RateLimitObserver: Sync {
+    /// Record that the named limiter rejected the request with the given key.
+    async fn record(&self, limiter: &str, key: &str, request_id: RequestId);
+}
+
+/// Rejections are stored for the admin summary. Failing to store one is
+/// logged but does not otherwise affect the rejected request.
+#[rocket::async_trait]
+impl RateLimitObserver for Coll<RateLimitEvent> {
+    async fn record(&self, limiter: &str, key: &str, request_id: RequestId) {
+        let event = RateLimitEvent::new(limiter, key, request_id);
+        if let Err(e) = self.insert_one(event, None).await {
+            error!(
+                "  req{} Failed to record {} rate limit rejection: {}",
+                request_id, limiter, e
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{common::election::ElectionId, mongodb::Id};
+
+/// The window over which a voter's recall codes are rate-limited.
+pub const RECALL_ATTEMPT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Name of the recall code rate limiter, as recorded in its rejections.
+pub const CODE_RECALL_LIMITER: &str = "code_recalls";
+
+/// A voter's attempt to recall a ballot by its recall code, recorded so
+/// that codes, which are much shorter than signatures, cannot be guessed
+/// quickly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecallAttempt {
+    pub voter_id: Id,
+    pub election_id: ElectionId,
+    /// When the attempt was made; it expires after [`RECALL_ATTEMPT_WINDOW`].
+    pub created_at: DateTime,
+}
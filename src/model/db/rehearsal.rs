@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{serde_helpers::chrono_datetime_as_bson_datetime, DateTime as BsonDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{api::rehearsal::QuestionVerification, common::election::ElectionId};
+
+/// A report on an election rehearsal, as stored in the database.
+/// It is created when the rehearsal starts, and updated when it finishes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RehearsalReport {
+    /// ID of the throwaway rehearsal election, which is unique to the report.
+    #[serde(rename = "_id")]
+    pub rehearsal_election_id: ElectionId,
+    /// ID of the election that was rehearsed.
+    pub election_id: ElectionId,
+    /// Number of synthetic voters.
+    pub voters: u32,
+    /// Chance that each synthetic ballot is confirmed rather than audited.
+    pub confirm_probability: f32,
+    /// When the rehearsal started.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub started_at: DateTime<Utc>,
+    /// When the rehearsal finished, if it has.
+    pub finished_at: Option<BsonDateTime>,
+    /// When the rehearsal election is deleted.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+    /// Number of synthetic ballots confirmed.
+    pub ballots_confirmed: u32,
+    /// Number of synthetic ballots audited.
+    pub ballots_audited: u32,
+    /// The outcome of verifying each question, once finished.
+    pub questions: Vec<QuestionVerification>,
+    /// Did the rehearsal finish, with every question verifying?
+    pub verified: bool,
+    /// Why the rehearsal stopped early, if it did.
+    pub error: Option<String>,
+}
@@ -1,18 +1,14 @@
-use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
+use chrono::{DateTime, Utc};
 use hmac::Hmac;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, serde_helpers::chrono_datetime_as_bson_datetime};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 
 use crate::{
     config::Config,
-    model::{
-        api::sms::Sms,
-        common::{allowed_questions::AllowedQuestions, election::ElectionId},
-        mongodb::{serde_string_map, Id},
-    },
+    model::{api::sms::Sms, mongodb::Id},
 };
 
 pub type HmacSha256 = Hmac<Sha256>;
@@ -23,10 +19,13 @@ pub struct VoterCore {
     /// Voter unique ID: the HMAC of their SMS number.
     #[serde(with = "dre_ip::group::serde_bytestring")]
     pub sms_hmac: Vec<u8>,
-    /// Maps election IDs to the IDs of questions the voter can answer for that election.
-    /// This is populated according to their group constraints when they join an election.
-    #[serde(with = "serde_string_map")]
-    pub allowed_questions: HashMap<ElectionId, AllowedQuestions>,
+    /// Whether an admin has frozen the voter, barring them from joining
+    /// elections and casting, confirming or auditing ballots.
+    #[serde(default)]
+    pub frozen: bool,
+    /// Why and when the voter was frozen, if they are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze: Option<VoterFreeze>,
 }
 
 impl VoterCore {
@@ -35,11 +34,22 @@ impl VoterCore {
         Self {
             // Do not directly store potentially sensitive phone number data
             sms_hmac: sms.into_hmac(config),
-            allowed_questions: HashMap::new(),
+            frozen: false,
+            freeze: None,
         }
     }
 }
 
+/// The record of an admin freezing a voter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterFreeze {
+    /// When the voter was frozen.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub time: DateTime<Utc>,
+    /// Why the voter was frozen, if the admin gave a reason.
+    pub reason: Option<String>,
+}
+
 /// A voter without an ID.
 pub type NewVoter = VoterCore;
 
@@ -75,7 +85,8 @@ mod examples {
         pub fn example(config: &Config) -> Self {
             Self {
                 sms_hmac: Sms::example().into_hmac(config),
-                allowed_questions: HashMap::new(),
+                frozen: false,
+                freeze: None,
             }
         }
     }
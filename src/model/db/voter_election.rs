@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use mongodb::{
+    bson::{doc, DateTime, Document},
+    error::Error as DbError,
+    options::{AggregateOptions, CountOptions, FindOneOptions, UpdateOptions},
+    Database,
+};
+use rocket::futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    common::{
+        allowed_questions::{AllowedQuestions, Joins},
+        election::{ElectionId, QuestionId},
+        serde_string_map,
+    },
+    db::voter::Voter,
+    mongodb::{Coll, Id},
+};
+
+/// A voter's membership of an election they have joined, as stored in the
+/// database. There is at most one per voter and election.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterElection {
+    pub voter_id: Id,
+    pub election_id: ElectionId,
+    /// The questions the voter may answer, populated according to their group
+    /// constraints when they joined.
+    pub allowed_questions: AllowedQuestions,
+    /// The groups the voter joined, so that their allowed questions can be
+    /// worked out again if the election is modified before it opens. Missing
+    /// for voters who joined before this was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joins: Option<Joins>,
+    /// The consent text the voter accepted when they joined, if the election
+    /// required it at the time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent: Option<ConsentAcceptance>,
+}
+
+/// A voter's acceptance of an election's consent text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentAcceptance {
+    /// The version of the text they accepted.
+    pub version: u32,
+    /// When they accepted it.
+    pub accepted_at: DateTime,
+}
+
+impl VoterElection {
+    /// Filter for the given voter's membership of the given election.
+    pub fn filter(voter_id: Id, election_id: ElectionId) -> Document {
+        doc! {
+            "voter_id": voter_id,
+            "election_id": election_id,
+        }
+    }
+
+    /// The field recording whether the voter has confirmed a ballot for the given question.
+    pub fn confirmed_field(question_id: QuestionId) -> String {
+        format!("allowed_questions.{}", question_id)
+    }
+
+    /// Get the questions the given voter may answer in the given election, or
+    /// `None` if they have not joined it.
+    pub async fn allowed_questions(
+        voter_elections: &Coll<VoterElection>,
+        voter_id: Id,
+        election_id: ElectionId,
+        options: impl Into<Option<FindOneOptions>>,
+    ) -> Result<Option<AllowedQuestions>, DbError> {
+        let voter_election = voter_elections
+            .find_one(Self::filter(voter_id, election_id), options)
+            .await?;
+        Ok(voter_election.map(|voter_election| voter_election.allowed_questions))
+    }
+
+    /// Count the voters who joined the given election allowed to answer the
+    /// given question.
+    pub async fn eligible_voters(
+        voter_elections: &Coll<VoterElection>,
+        election_id: ElectionId,
+        question_id: QuestionId,
+        options: impl Into<Option<CountOptions>>,
+    ) -> Result<u64, DbError> {
+        let filter = doc! {
+            "election_id": election_id,
+            Self::confirmed_field(question_id): { "$exists": true },
+        };
+        voter_elections.count_documents(filter, options).await
+    }
+
+    /// Count the voters who joined the given election allowed to answer each
+    /// of its questions. Questions nobody may answer are left out.
+    pub async fn eligible_voters_by_question(
+        voter_elections: &Coll<VoterElection>,
+        election_id: ElectionId,
+        options: impl Into<Option<AggregateOptions>>,
+    ) -> Result<HashMap<QuestionId, u64>, DbError> {
+        let pipeline = [
+            doc! { "$match": { "election_id": election_id } },
+            doc! { "$project": { "question": { "$objectToArray": "$allowed_questions" } } },
+            doc! { "$unwind": "$question" },
+            doc! { "$group": { "_id": "$question.k", "count": { "$sum": 1 } } },
+        ];
+        let mut counts = voter_elections
+            .aggregate(pipeline, options)
+            .await?
+            .with_type::<QuestionCount>();
+        let mut eligible = HashMap::new();
+        while let Some(QuestionCount { question_id, count }) = counts.try_next().await? {
+            // Allowed questions are keyed by their IDs as strings.
+            if let Ok(question_id) = question_id.parse() {
+                eligible.insert(question_id, count);
+            }
+        }
+        Ok(eligible)
+    }
+}
+
+/// Number of voters allowed to answer a single question, as produced by the
+/// aggregation in [`VoterElection::eligible_voters_by_question`].
+#[derive(Deserialize)]
+struct QuestionCount {
+    #[serde(rename = "_id")]
+    question_id: String,
+    count: u64,
+}
+
+/// Just enough of a voter stored before voter elections had their own
+/// collection to move its allowed questions there.
+#[derive(Deserialize)]
+struct EmbeddedAllowedQuestions {
+    #[serde(rename = "_id")]
+    id: Id,
+    #[serde(with = "serde_string_map")]
+    allowed_questions: HashMap<ElectionId, AllowedQuestions>,
+}
+
+/// Move the allowed questions that voters stored before voter elections had
+/// their own collection into it, returning how many voters were moved.
+///
+/// Each voter's elections are written before their embedded map is removed,
+/// and never overwrite existing ones, so this is safe to rerun if interrupted.
+pub async fn ensure_voter_elections_split(db: &Database) -> Result<usize, DbError> {
+    debug!("Ensuring voters' allowed questions are split out");
+    let voters = Coll::<Voter>::from_db(db).clone_with_type::<EmbeddedAllowedQuestions>();
+    let voter_elections = Coll::<VoterElection>::from_db(db);
+    let mut embedded = voters
+        .find(doc! { "allowed_questions": { "$exists": true } }, None)
+        .await?;
+    let mut moved = 0;
+    while let Some(voter) = embedded.try_next().await? {
+        for (election_id, allowed_questions) in voter.allowed_questions {
+            let allowed_questions = mongodb::bson::to_bson(&allowed_questions).unwrap(); // Cannot fail.
+            voter_elections
+                .update_one(
+                    VoterElection::filter(voter.id, election_id),
+                    doc! { "$setOnInsert": { "allowed_questions": allowed_questions } },
+                    UpdateOptions::builder().upsert(true).build(),
+                )
+                .await?;
+        }
+        voters
+            .update_one(
+                voter.id.as_doc(),
+                doc! { "$unset": { "allowed_questions": "" } },
+                None,
+            )
+            .await?;
+        moved += 1;
+    }
+    if moved > 0 {
+        warn!("Split out the allowed questions of {} voters", moved);
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{db::voter::NewVoter, mongodb::MongoCollection};
+
+    use super::*;
+
+    #[backend_test]
+    async fn legacy_voters_split(db: Database) -> anyhow::Result<()> {
+        // Seed voters in the legacy layout: one who joined two elections,
+        // having confirmed one question, and one who never joined.
+        let joined = Id::new();
+        let never_joined = Id::new();
+        let legacy_voter = |id: Id, allowed_questions: Document| {
+            let mut voter = mongodb::bson::to_document(&NewVoter {
+                sms_hmac: id.to_bytes(),
+                frozen: false,
+                freeze: None,
+            })
+            .unwrap();
+            voter.insert("_id", id);
+            voter.insert("allowed_questions", allowed_questions);
+            voter
+        };
+        let legacy_voters = db.collection::<Document>(Voter::NAME);
+        legacy_voters
+            .insert_many(
+                [
+                    legacy_voter(
+                        joined,
+                        doc! {
+                            "1": { "1": true, "2": false },
+                            "2": {},
+                        },
+                    ),
+                    legacy_voter(never_joined, doc! {}),
+                ],
+                None,
+            )
+            .await?;
+
+        // Both are moved, keeping the voters themselves.
+        assert_eq!(ensure_voter_elections_split(&db).await?, 2);
+        let voter_elections = Coll::<VoterElection>::from_db(&db);
+        let first = VoterElection::allowed_questions(&voter_elections, joined, 1, None).await?;
+        assert_eq!(
+            first.map(|allowed| allowed.confirmed),
+            Some(HashMap::from([(1, true), (2, false)]))
+        );
+        let second = VoterElection::allowed_questions(&voter_elections, joined, 2, None).await?;
+        assert_eq!(second, Some(AllowedQuestions::default()));
+        assert_eq!(
+            voter_elections
+                .count_documents(doc! { "voter_id": never_joined }, None)
+                .await?,
+            0
+        );
+        let voters = Coll::<Voter>::from_db(&db);
+        assert_eq!(voters.count_documents(None, None).await?, 2);
+        assert_eq!(
+            legacy_voters
+                .count_documents(doc! { "allowed_questions": { "$exists": true } }, None)
+                .await?,
+            0
+        );
+
+        // Rerunning is harmless, and does not undo later confirmations.
+        voter_elections
+            .update_one(
+                VoterElection::filter(joined, 1),
+                doc! { "$set": { VoterElection::confirmed_field(2): true } },
+                None,
+            )
+            .await?;
+        assert_eq!(ensure_voter_elections_split(&db).await?, 0);
+        let first = VoterElection::allowed_questions(&voter_elections, joined, 1, None).await?;
+        assert_eq!(
+            first.map(|allowed| allowed.confirmed),
+            Some(HashMap::from([(1, true), (2, true)]))
+        );
+
+        Ok(())
+    }
+}
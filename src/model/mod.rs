@@ -1,4 +1,6 @@
 pub mod api;
 pub mod common;
+#[cfg(feature = "server")]
 pub mod db;
+#[cfg(feature = "server")]
 pub mod mongodb;
@@ -0,0 +1,324 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Duration, Utc};
+use mongodb::{bson::doc, Database};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    futures::future::{BoxFuture, FutureExt},
+    Build, Rocket,
+};
+
+use crate::{
+    model::api::clock_skew::{ClockSkew, ClockSkewReport},
+    scheduled_task::{ScheduledTask, TaskRegistry},
+};
+
+/// The name of the scheduled clock skew check.
+const CLOCK_SKEW_TASK_NAME: &str = "clock_skew_check";
+
+/// How far this server's clock may differ from the database server's.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SkewThresholds {
+    /// Skew beyond this, either way, is logged as a warning.
+    pub warn: Duration,
+    /// Skew beyond this, either way, refuses launch unless accepted.
+    pub max: Duration,
+}
+
+/// How serious a measured skew is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SkewLevel {
+    /// Within the warning threshold.
+    Normal,
+    /// Beyond the warning threshold, but within the maximum.
+    Warn,
+    /// Beyond the maximum.
+    Excessive,
+}
+
+impl SkewThresholds {
+    /// How serious the given skew is.
+    pub fn level(&self, skew: Duration) -> SkewLevel {
+        let skew = skew.abs();
+        if skew > self.max {
+            SkewLevel::Excessive
+        } else if skew > self.warn {
+            SkewLevel::Warn
+        } else {
+            SkewLevel::Normal
+        }
+    }
+}
+
+/// Estimate the skew from a request sent at `sent` and answered at `received`,
+/// both by this server's clock, reporting the database server's time as
+/// `server_time`. The server is assumed to have read its clock halfway through.
+pub fn estimate_skew(
+    sent: DateTime<Utc>,
+    server_time: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> ClockSkew {
+    let round_trip = received - sent;
+    let midpoint = sent + round_trip / 2;
+    ClockSkew {
+        measured_at: received,
+        skew_ms: (server_time - midpoint).num_milliseconds(),
+        round_trip_ms: round_trip.num_milliseconds().max(0) as u64,
+    }
+}
+
+/// Measure how far the database server's clock is ahead of this server's.
+pub async fn measure_skew(db: &Database) -> Result<ClockSkew, String> {
+    let failed = |e| format!("Failed to read the database server's time: {e}");
+    let sent = Utc::now();
+    let reply = match db.run_command(doc! { "hello": 1 }, None).await {
+        Ok(reply) => reply,
+        // Servers before 4.4.2 only know the legacy name.
+        Err(_) => db
+            .run_command(doc! { "isMaster": 1 }, None)
+            .await
+            .map_err(failed)?,
+    };
+    let received = Utc::now();
+    let server_time = reply
+        .get_datetime("localTime")
+        .map_err(|e| format!("The database server did not report its time: {e}"))?;
+    Ok(estimate_skew(sent, server_time.to_chrono(), received))
+}
+
+/// Decide whether to launch with a skew measured at the given level.
+fn launch_verdict(level: SkewLevel, skew: &ClockSkew, accept: bool) -> Result<(), String> {
+    match level {
+        SkewLevel::Excessive if !accept => Err(format!(
+            "The database server's clock is {}ms ahead of this server's, beyond the maximum, so \
+             elections would open and close at the wrong times. Fix the clocks, or, to launch \
+             anyway, set `accept_clock_skew = true` or launch with `--accept-clock-skew`",
+            skew.skew_ms
+        )),
+        SkewLevel::Excessive => {
+            warn!("`accept_clock_skew` is set, so launching despite the clock skew");
+            Ok(())
+        }
+        SkewLevel::Normal | SkewLevel::Warn => Ok(()),
+    }
+}
+
+/// Measures the skew between this server's clock and the database server's,
+/// since elections open and close by this server's clock, while some
+/// timestamps and expiries come from the database's. Keeps the most recent
+/// measurement so that it can be served.
+///
+/// This is placed into managed state when connecting to the database.
+#[derive(Clone)]
+pub struct ClockSkewMonitor {
+    db: Database,
+    thresholds: SkewThresholds,
+    interval: Duration,
+    last: Arc<Mutex<Option<ClockSkew>>>,
+}
+
+impl ClockSkewMonitor {
+    /// Create a monitor with nothing measured, which measures again every
+    /// `interval` once scheduled.
+    pub fn new(db: Database, thresholds: SkewThresholds, interval: Duration) -> Self {
+        Self {
+            db,
+            thresholds,
+            interval,
+            last: Arc::default(),
+        }
+    }
+
+    /// Measure the skew at launch, failing if it is excessive, unless `accept`
+    /// is set.
+    pub async fn check_at_launch(&self, accept: bool) -> Result<(), String> {
+        let skew = measure_skew(&self.db).await?;
+        let level = self.record(skew.clone());
+        launch_verdict(level, &skew, accept)
+    }
+
+    /// Measure the skew now, log it if it is large, and keep it.
+    pub async fn check(&self) -> Result<ClockSkew, String> {
+        let skew = measure_skew(&self.db).await?;
+        self.record(skew.clone());
+        Ok(skew)
+    }
+
+    /// Log the given measurement if it is large, and keep it.
+    fn record(&self, skew: ClockSkew) -> SkewLevel {
+        let level = self
+            .thresholds
+            .level(Duration::try_milliseconds(skew.skew_ms).unwrap_or(Duration::MAX));
+        match level {
+            SkewLevel::Normal => debug!(
+                "The database server's clock is {}ms ahead (round trip {}ms)",
+                skew.skew_ms, skew.round_trip_ms
+            ),
+            SkewLevel::Warn => warn!(
+                "The database server's clock is {}ms ahead (round trip {}ms), beyond the \
+                 warning threshold of {}ms",
+                skew.skew_ms,
+                skew.round_trip_ms,
+                self.thresholds.warn.num_milliseconds()
+            ),
+            SkewLevel::Excessive => error!(
+                "The database server's clock is {}ms ahead (round trip {}ms), beyond the \
+                 maximum of {}ms: elections will open and close at the wrong times",
+                skew.skew_ms,
+                skew.round_trip_ms,
+                self.thresholds.max.num_milliseconds()
+            ),
+        }
+        *self.last.lock().unwrap_or_else(PoisonError::into_inner) = Some(skew);
+        level
+    }
+
+    /// Describe the thresholds and the most recent measurement.
+    pub fn report(&self) -> ClockSkewReport {
+        ClockSkewReport {
+            warn_threshold_ms: self.thresholds.warn.num_milliseconds() as u64,
+            max_threshold_ms: self.thresholds.max.num_milliseconds() as u64,
+            check_interval_secs: self.interval.num_seconds() as u64,
+            last: self
+                .last
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        }
+    }
+
+    /// Schedule the skew to be measured again one interval from now, and
+    /// every interval thereafter.
+    pub fn schedule(&self, registry: TaskRegistry) {
+        let task = ScheduledTask::new(
+            CLOCK_SKEW_TASK_NAME,
+            self.clone().scheduled(registry.clone()),
+            Utc::now() + self.interval,
+        );
+        registry.register(&task, true);
+    }
+
+    /// Measure the skew, then reschedule for the next interval.
+    /// Since this is a recursive async function, we must use `BoxFuture` to
+    /// avoid an infinitely-recursive state machine.
+    fn scheduled(self, registry: TaskRegistry) -> BoxFuture<'static, ()> {
+        async move {
+            if let Err(e) = self.check().await {
+                error!("Scheduled clock skew check failed: {e}");
+            }
+            self.schedule(registry);
+        }
+        .boxed()
+    }
+}
+
+/// A fairing that schedules the [`ClockSkewMonitor`] placed into managed state
+/// when connecting to the database to measure the skew periodically.
+/// This fairing must be attached after the fairings responsible for the
+/// database and task registry.
+pub struct ClockSkewFairing;
+
+#[rocket::async_trait]
+impl Fairing for ClockSkewFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Clock Skew Check",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        match (
+            rocket.state::<ClockSkewMonitor>(),
+            rocket.state::<TaskRegistry>(),
+        ) {
+            (Some(monitor), Some(registry)) => monitor.schedule(registry.clone()),
+            _ => {
+                error!(
+                    "Clock skew monitor or task registry was not available when scheduling clock \
+                     skew checks"
+                );
+                return Err(rocket);
+            }
+        }
+        Ok(rocket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn thresholds() -> SkewThresholds {
+        SkewThresholds {
+            warn: Duration::try_seconds(2).unwrap(),
+            max: Duration::try_seconds(60).unwrap(),
+        }
+    }
+
+    #[test]
+    fn skew_estimated_from_midpoint() {
+        let sent = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let received = sent + Duration::try_milliseconds(100).unwrap();
+
+        // The server read its clock halfway through the round trip.
+        let server_time = sent + Duration::try_milliseconds(50).unwrap();
+        let skew = estimate_skew(sent, server_time, received);
+        assert_eq!(skew.skew_ms, 0);
+        assert_eq!(skew.round_trip_ms, 100);
+        assert_eq!(skew.measured_at, received);
+
+        // A server ahead or behind.
+        let ahead = server_time + Duration::try_seconds(3).unwrap();
+        assert_eq!(estimate_skew(sent, ahead, received).skew_ms, 3000);
+        let behind = server_time - Duration::try_seconds(90).unwrap();
+        assert_eq!(estimate_skew(sent, behind, received).skew_ms, -90_000);
+    }
+
+    #[test]
+    fn skew_levels() {
+        let thresholds = thresholds();
+        for (skew_ms, level) in [
+            (0, SkewLevel::Normal),
+            (2000, SkewLevel::Normal),
+            (-2000, SkewLevel::Normal),
+            (2001, SkewLevel::Warn),
+            (-5000, SkewLevel::Warn),
+            (60_000, SkewLevel::Warn),
+            (60_001, SkewLevel::Excessive),
+            (-120_000, SkewLevel::Excessive),
+        ] {
+            let skew = Duration::try_milliseconds(skew_ms).unwrap();
+            assert_eq!(thresholds.level(skew), level, "{skew_ms}ms");
+        }
+    }
+
+    #[test]
+    fn excessive_skew_refused_unless_accepted() {
+        let skew = ClockSkew {
+            measured_at: Utc::now(),
+            skew_ms: -90_000,
+            round_trip_ms: 5,
+        };
+        assert!(launch_verdict(SkewLevel::Normal, &skew, false).is_ok());
+        assert!(launch_verdict(SkewLevel::Warn, &skew, false).is_ok());
+        assert!(launch_verdict(SkewLevel::Excessive, &skew, false).is_err());
+        assert!(launch_verdict(SkewLevel::Excessive, &skew, true).is_ok());
+    }
+
+    #[backend_test]
+    async fn measured_at_launch(db: Database) {
+        // The test database runs on this machine, so shares its clock.
+        let monitor = ClockSkewMonitor::new(db, thresholds(), Duration::try_minutes(5).unwrap());
+        assert_eq!(monitor.report().last, None);
+        monitor.check_at_launch(false).await.unwrap();
+        let report = monitor.report();
+        assert_eq!(report.warn_threshold_ms, 2000);
+        assert_eq!(report.max_threshold_ms, 60_000);
+        assert_eq!(report.check_interval_secs, 300);
+        let skew = report.last.unwrap();
+        assert!(skew.skew_ms.abs() < 2000, "{skew:?}");
+    }
+}
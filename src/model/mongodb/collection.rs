@@ -1,7 +1,13 @@
 use std::ops::Deref;
+use std::time::Duration;
 
 use mongodb::{
-    bson::doc, error::Error as DbError, options::IndexOptions, Collection, Database, IndexModel,
+    bson::doc,
+    error::Error as DbError,
+    options::{
+        CollectionOptions, IndexOptions, ReadPreference, ReadPreferenceOptions, SelectionCriteria,
+    },
+    Collection, Database, IndexModel,
 };
 use rocket::{
     request::{self, FromRequest, Request},
@@ -12,10 +18,21 @@ use crate::model::{
     common::ballot::BallotState,
     db::{
         admin::{Admin, NewAdmin},
+        admin_token::{AdminToken, NewAdminToken},
+        audit::AuditLogEntry,
         ballot::{AnyBallot, Ballot, BallotCore},
-        candidate_totals::{CandidateTotals, NewCandidateTotals},
+        board::{BoardEntry, BoardHead},
+        candidate_totals::{CandidateTotals, NewCandidateTotals, TotalsDelta},
+        challenge_delivery::{ChallengeDelivery, CHALLENGE_DELIVERY_LIFETIME},
         election::{Election, ElectionMetadata},
+        hmac_check::HmacCheck,
+        idempotency::{IdempotentResponse, IDEMPOTENT_RESPONSE_LIFETIME},
+        maintenance::MaintenanceReport,
+        rate_limit::{RateLimitEvent, RATE_LIMIT_EVENT_LIFETIME},
+        recall_attempt::{RecallAttempt, RECALL_ATTEMPT_WINDOW},
+        rehearsal::RehearsalReport,
         voter::{NewVoter, Voter},
+        voter_election::VoterElection,
     },
 };
 
@@ -38,6 +55,18 @@ where
     pub fn from_db(db: &Database) -> Self {
         Self(db.collection(T::NAME))
     }
+
+    /// Get a handle on this collection in the given database whose reads prefer
+    /// secondaries, falling back to the primary if none are available.
+    pub fn read_secondary_preferred(db: &Database) -> Self {
+        let read_preference = ReadPreference::SecondaryPreferred {
+            options: ReadPreferenceOptions::default(),
+        };
+        let options = CollectionOptions::builder()
+            .selection_criteria(SelectionCriteria::ReadPreference(read_preference))
+            .build();
+        Self(db.collection_with_options(T::NAME, options))
+    }
 }
 
 // `Derive(Clone)` would only derive if `T: Clone`, but we don't need that bound.
@@ -71,6 +100,37 @@ where
     }
 }
 
+/// A database collection of the given type whose reads prefer secondaries.
+///
+/// Only for read-only endpoints that can tolerate slightly stale data, so that
+/// their traffic does not compete with vote writes on the primary.
+pub struct SecondaryColl<T>(Coll<T>);
+
+impl<T> Deref for SecondaryColl<T> {
+    type Target = Coll<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T> FromRequest<'r> for SecondaryColl<T>
+where
+    T: MongoCollection,
+{
+    type Error = ();
+
+    /// Get the database connection from the managed state and wrap it in a
+    /// collection that prefers secondaries.
+    ///
+    /// Panics iff the [`Database`] is not managed by [`rocket::Rocket`].
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let db = req.guard::<&State<Database>>().await.unwrap();
+        request::Outcome::Success(SecondaryColl(Coll::read_secondary_preferred(db)))
+    }
+}
+
 // Admin collections
 const ADMINS: &str = "admins";
 impl MongoCollection for Admin {
@@ -79,6 +139,19 @@ impl MongoCollection for Admin {
 impl MongoCollection for NewAdmin {
     const NAME: &'static str = ADMINS;
 }
+const ADMIN_TOKENS: &str = "admin_tokens";
+impl MongoCollection for AdminToken {
+    const NAME: &'static str = ADMIN_TOKENS;
+}
+impl MongoCollection for NewAdminToken {
+    const NAME: &'static str = ADMIN_TOKENS;
+}
+
+// Audit log collection
+const AUDIT_LOG: &str = "audit_log";
+impl MongoCollection for AuditLogEntry {
+    const NAME: &'static str = AUDIT_LOG;
+}
 
 // Voter collections
 const VOTERS: &str = "voters";
@@ -89,6 +162,12 @@ impl MongoCollection for NewVoter {
     const NAME: &'static str = VOTERS;
 }
 
+// Voter election collection
+const VOTER_ELECTIONS: &str = "voter_elections";
+impl MongoCollection for VoterElection {
+    const NAME: &'static str = VOTER_ELECTIONS;
+}
+
 // Election collections
 const ELECTIONS: &str = "elections";
 impl MongoCollection for Election {
@@ -110,6 +189,16 @@ impl MongoCollection for AnyBallot {
     const NAME: &'static str = BALLOTS;
 }
 
+// Bulletin board collections
+const BOARD_HEADS: &str = "board_heads";
+impl MongoCollection for BoardHead {
+    const NAME: &'static str = BOARD_HEADS;
+}
+const BOARD_ENTRIES: &str = "board_entries";
+impl MongoCollection for BoardEntry {
+    const NAME: &'static str = BOARD_ENTRIES;
+}
+
 // Candidate totals collections
 const CANDIDATE_TOTALS: &str = "candidate_totals";
 impl MongoCollection for CandidateTotals {
@@ -118,6 +207,16 @@ impl MongoCollection for CandidateTotals {
 impl MongoCollection for NewCandidateTotals {
     const NAME: &'static str = CANDIDATE_TOTALS;
 }
+const TOTALS_DELTAS: &str = "totals_deltas";
+impl MongoCollection for TotalsDelta {
+    const NAME: &'static str = TOTALS_DELTAS;
+}
+
+// Challenge delivery collection
+const CHALLENGE_DELIVERIES: &str = "challenge_deliveries";
+impl MongoCollection for ChallengeDelivery {
+    const NAME: &'static str = CHALLENGE_DELIVERIES;
+}
 
 // Counter collection
 const COUNTERS: &str = "counters";
@@ -125,6 +224,42 @@ impl MongoCollection for Counter {
     const NAME: &'static str = COUNTERS;
 }
 
+// HMAC check collection
+const HMAC_CHECK: &str = "hmac_check";
+impl MongoCollection for HmacCheck {
+    const NAME: &'static str = HMAC_CHECK;
+}
+
+// Idempotent response collection
+const IDEMPOTENT_RESPONSES: &str = "idempotent_responses";
+impl MongoCollection for IdempotentResponse {
+    const NAME: &'static str = IDEMPOTENT_RESPONSES;
+}
+
+// Maintenance report collection
+const MAINTENANCE_REPORTS: &str = "maintenance_reports";
+impl MongoCollection for MaintenanceReport {
+    const NAME: &'static str = MAINTENANCE_REPORTS;
+}
+
+// Rate limit event collection
+const RATE_LIMIT_EVENTS: &str = "rate_limit_events";
+impl MongoCollection for RateLimitEvent {
+    const NAME: &'static str = RATE_LIMIT_EVENTS;
+}
+
+// Recall attempt collection
+const RECALL_ATTEMPTS: &str = "recall_attempts";
+impl MongoCollection for RecallAttempt {
+    const NAME: &'static str = RECALL_ATTEMPTS;
+}
+
+// Rehearsal report collection
+const REHEARSAL_REPORTS: &str = "rehearsal_reports";
+impl MongoCollection for RehearsalReport {
+    const NAME: &'static str = REHEARSAL_REPORTS;
+}
+
 /// Ensure that all the required indexes exist on the given database.
 ///
 /// This operation is idempotent.
@@ -142,6 +277,21 @@ pub async fn ensure_indexes_exist(db: &Database) -> Result<(), DbError> {
         .create_index(voter_index, None)
         .await?;
 
+    // Voter election collection: one per voter and election, also found by
+    // election alone when the election is modified or deleted.
+    let voter_election_index = IndexModel::builder()
+        .keys(doc! {"voter_id": 1, "election_id": 1})
+        .options(unique.clone())
+        .build();
+    let voter_election_by_election_index =
+        IndexModel::builder().keys(doc! {"election_id": 1}).build();
+    Coll::<VoterElection>::from_db(db)
+        .create_indexes(
+            [voter_election_index, voter_election_by_election_index],
+            None,
+        )
+        .await?;
+
     // Admin collection.
     let admin_index = IndexModel::builder()
         .keys(doc! {"username": 1})
@@ -151,6 +301,27 @@ pub async fn ensure_indexes_exist(db: &Database) -> Result<(), DbError> {
         .create_index(admin_index, None)
         .await?;
 
+    // Admin token collection: look tokens up by secret, and expire them.
+    let admin_token_index = IndexModel::builder()
+        .keys(doc! {"secret_hash": 1})
+        .options(unique.clone())
+        .build();
+    let admin_token_owner_index = IndexModel::builder().keys(doc! {"admin_id": 1}).build();
+    let admin_token_expiry_index = IndexModel::builder()
+        .keys(doc! {"expires_at": 1})
+        .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+        .build();
+    Coll::<AdminToken>::from_db(db)
+        .create_indexes(
+            [
+                admin_token_index,
+                admin_token_owner_index,
+                admin_token_expiry_index,
+            ],
+            None,
+        )
+        .await?;
+
     // Ballot collection.
     let ballot_index = IndexModel::builder()
         .keys(doc! {"election_id": 1, "question_id": 1, "ballot_id": 1})
@@ -159,6 +330,33 @@ pub async fn ensure_indexes_exist(db: &Database) -> Result<(), DbError> {
     Coll::<AnyBallot>::from_db(db)
         .create_index(ballot_index, None)
         .await?;
+    let ballot_changes_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "question_id": 1, "last_event_seq": 1})
+        .build();
+    Coll::<AnyBallot>::from_db(db)
+        .create_index(ballot_changes_index, None)
+        .await?;
+    let ballot_state_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "question_id": 1, "state": 1, "state_data.candidate": 1})
+        .build();
+    Coll::<AnyBallot>::from_db(db)
+        .create_index(ballot_state_index, None)
+        .await?;
+    let ballot_phrase_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "question_id": 1, "confirmation_phrase": 1})
+        .build();
+    Coll::<AnyBallot>::from_db(db)
+        .create_index(ballot_phrase_index, None)
+        .await?;
+
+    // Bulletin board collection.
+    let board_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "question_id": 1, "position": 1})
+        .options(unique.clone())
+        .build();
+    Coll::<BoardEntry>::from_db(db)
+        .create_index(board_index, None)
+        .await?;
 
     // Candidate totals collection.
     let totals_index = IndexModel::builder()
@@ -169,5 +367,132 @@ pub async fn ensure_indexes_exist(db: &Database) -> Result<(), DbError> {
         .create_index(totals_index, None)
         .await?;
 
+    // Totals delta collection.
+    let deltas_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "question_id": 1})
+        .build();
+    Coll::<TotalsDelta>::from_db(db)
+        .create_index(deltas_index, None)
+        .await?;
+
+    // Challenge delivery collection: one record per number, expired once it
+    // has not been challenged for a while.
+    let delivery_index = IndexModel::builder()
+        .keys(doc! {"sms_hmac": 1})
+        .options(unique.clone())
+        .build();
+    let delivery_expiry_index = IndexModel::builder()
+        .keys(doc! {"updated_at": 1})
+        .options(
+            IndexOptions::builder()
+                .expire_after(CHALLENGE_DELIVERY_LIFETIME)
+                .build(),
+        )
+        .build();
+    Coll::<ChallengeDelivery>::from_db(db)
+        .create_indexes([delivery_index, delivery_expiry_index], None)
+        .await?;
+
+    // Rehearsal report collection.
+    let rehearsal_index = IndexModel::builder()
+        .keys(doc! {"election_id": 1, "started_at": -1})
+        .build();
+    Coll::<RehearsalReport>::from_db(db)
+        .create_index(rehearsal_index, None)
+        .await?;
+
+    // Idempotent response collection: expire stored responses.
+    let expiry = IndexOptions::builder()
+        .expire_after(IDEMPOTENT_RESPONSE_LIFETIME)
+        .build();
+    let expiry_index = IndexModel::builder()
+        .keys(doc! {"created_at": 1})
+        .options(expiry)
+        .build();
+    Coll::<IdempotentResponse>::from_db(db)
+        .create_index(expiry_index, None)
+        .await?;
+
+    // Recall attempt collection: count each voter's recent attempts, and
+    // expire them once they no longer count.
+    let recall_attempt_index = IndexModel::builder()
+        .keys(doc! {"voter_id": 1, "created_at": 1})
+        .build();
+    let recall_attempt_expiry_index = IndexModel::builder()
+        .keys(doc! {"created_at": 1})
+        .options(
+            IndexOptions::builder()
+                .expire_after(RECALL_ATTEMPT_WINDOW)
+                .build(),
+        )
+        .build();
+    Coll::<RecallAttempt>::from_db(db)
+        .create_indexes([recall_attempt_index, recall_attempt_expiry_index], None)
+        .await?;
+
+    // Rate limit event collection: summarise recent rejections by limiter,
+    // and expire them once they are too old to be summarised.
+    let rate_limit_index = IndexModel::builder()
+        .keys(doc! {"limiter": 1, "created_at": 1})
+        .build();
+    let rate_limit_expiry_index = IndexModel::builder()
+        .keys(doc! {"created_at": 1})
+        .options(
+            IndexOptions::builder()
+                .expire_after(RATE_LIMIT_EVENT_LIFETIME)
+                .build(),
+        )
+        .build();
+    Coll::<RateLimitEvent>::from_db(db)
+        .create_indexes([rate_limit_index, rate_limit_expiry_index], None)
+        .await?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::asynchronous::Client;
+
+    use super::*;
+
+    /// Does the collection prefer to read from secondaries?
+    fn prefers_secondary<T>(coll: &Coll<T>) -> bool {
+        matches!(
+            coll.selection_criteria(),
+            Some(SelectionCriteria::ReadPreference(
+                ReadPreference::SecondaryPreferred { .. }
+            ))
+        )
+    }
+
+    #[backend_test]
+    async fn read_preferences(db: Database) {
+        let primary = Coll::<Election>::from_db(&db);
+        assert!(primary.selection_criteria().is_none());
+
+        let secondary = Coll::<Election>::read_secondary_preferred(&db);
+        assert!(prefers_secondary(&secondary));
+        assert_eq!(secondary.name(), primary.name());
+
+        // Reads still work, falling back to the primary if there are no secondaries.
+        assert_eq!(secondary.count_documents(None, None).await.unwrap(), 0);
+    }
+
+    #[backend_test]
+    async fn guards_resolve(client: Client) {
+        let request = client.get("/");
+
+        let primary = Coll::<AnyBallot>::from_request(request.inner())
+            .await
+            .succeeded()
+            .unwrap();
+        assert!(!prefers_secondary(&primary));
+
+        let secondary = SecondaryColl::<AnyBallot>::from_request(request.inner())
+            .await
+            .succeeded()
+            .unwrap();
+        assert!(prefers_secondary(&secondary));
+    }
+}
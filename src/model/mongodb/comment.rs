@@ -0,0 +1,159 @@
+use mongodb::{
+    bson::Bson,
+    options::{
+        AggregateOptions, CountOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions,
+        ReplaceOptions, UpdateOptions,
+    },
+};
+
+use crate::logging::RequestId;
+
+/// Operation options that can carry a `$comment`.
+///
+/// Tagging operations with the ID of the request that issued them lets slow
+/// queries in the MongoDB profiler be tied back to the request log. The comment
+/// is purely informational and does not change query semantics.
+pub trait RequestComment: Default {
+    /// Attach the request ID to these options as a comment.
+    fn with_request_id(self, request_id: RequestId) -> Self;
+
+    /// Default options, tagged with the request ID.
+    fn for_request(request_id: RequestId) -> Self {
+        Self::default().with_request_id(request_id)
+    }
+}
+
+/// The comment attached on behalf of the given request, matching the log prefix.
+pub fn request_comment(request_id: RequestId) -> String {
+    format!("req{request_id}")
+}
+
+impl RequestComment for FindOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(request_comment(request_id));
+        self
+    }
+}
+
+impl RequestComment for FindOneOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(request_comment(request_id));
+        self
+    }
+}
+
+impl RequestComment for AggregateOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(Bson::String(request_comment(request_id)));
+        self
+    }
+}
+
+impl RequestComment for CountOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(Bson::String(request_comment(request_id)));
+        self
+    }
+}
+
+impl RequestComment for UpdateOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(Bson::String(request_comment(request_id)));
+        self
+    }
+}
+
+impl RequestComment for ReplaceOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(Bson::String(request_comment(request_id)));
+        self
+    }
+}
+
+impl RequestComment for FindOneAndUpdateOptions {
+    fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.comment = Some(Bson::String(request_comment(request_id)));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use mongodb::{
+        bson::{doc, Document},
+        event::command::{CommandEventHandler, CommandStartedEvent},
+        options::ClientOptions,
+        Client, Database,
+    };
+
+    use super::*;
+    use crate::model::{db::voter::Voter, mongodb::Coll};
+
+    /// Records the comment of every `find` and `count` command issued.
+    #[derive(Default)]
+    struct CommentRecorder {
+        comments: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    impl CommandEventHandler for CommentRecorder {
+        fn handle_command_started_event(&self, event: CommandStartedEvent) {
+            let comment = event
+                .command
+                .get_str("comment")
+                .ok()
+                .map(ToString::to_string);
+            self.comments
+                .lock()
+                .unwrap()
+                .push((event.command_name, comment));
+        }
+    }
+
+    #[backend_test]
+    async fn comment_attached(db: Database) {
+        // Connect a second client to the same database, listening for commands.
+        let db_uri: String = rocket::Config::figment().extract_inner("db_uri").unwrap();
+        let mut options = ClientOptions::parse(db_uri).await.unwrap();
+        let recorder = Arc::new(CommentRecorder::default());
+        options.command_event_handler = Some(recorder.clone());
+        let client = Client::with_options(options).unwrap();
+        let voters = Coll::<Voter>::from_db(&client.database(db.name()));
+
+        // Issue some tagged and untagged commands.
+        let request_id = RequestId {
+            id: 42,
+            client_op_id: None,
+        };
+        voters
+            .find_one(doc! {}, FindOneOptions::for_request(request_id))
+            .await
+            .unwrap();
+        voters
+            .count_documents(doc! {}, CountOptions::for_request(request_id))
+            .await
+            .unwrap();
+        voters.find_one(doc! {}, None).await.unwrap();
+
+        let comments = recorder.comments.lock().unwrap().clone();
+        let expected = vec![
+            ("find".to_string(), Some("req42".to_string())),
+            ("aggregate".to_string(), Some("req42".to_string())),
+            ("find".to_string(), None),
+        ];
+        assert_eq!(comments, expected);
+
+        // Ensure the tagged query still behaves identically.
+        let untagged = db
+            .collection::<Document>("voters")
+            .count_documents(doc! {}, None)
+            .await
+            .unwrap();
+        let tagged = voters
+            .count_documents(doc! {}, CountOptions::for_request(request_id))
+            .await
+            .unwrap();
+        assert_eq!(tagged, untagged);
+    }
+}
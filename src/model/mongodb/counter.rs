@@ -1,7 +1,7 @@
 use mongodb::{
     bson::doc,
     error::Error as DbError,
-    options::{FindOneAndUpdateOptions, ReturnDocument, UpdateOptions},
+    options::{FindOneAndUpdateOptions, FindOneOptions, ReturnDocument, UpdateOptions},
 };
 use rocket::http::Status;
 use serde::{Deserialize, Serialize};
@@ -9,12 +9,16 @@ use serde::{Deserialize, Serialize};
 use crate::error::Error;
 use crate::model::{
     common::election::{ElectionId, QuestionId},
-    mongodb::Coll,
+    db::election::Election,
+    mongodb::{is_duplicate_key_error, Coll},
 };
 
 /// The unique ID of the counter for election IDs.
 pub const ELECTION_ID_COUNTER_ID: &str = "eid";
 
+/// The first value of every counter.
+const FIRST_VALUE: u32 = 1;
+
 /// Get the unique ID for the ballot counter for the given question.
 pub fn ballot_counter_id(election_id: ElectionId, question_id: QuestionId) -> String {
     format!("bid:{}:{}", election_id, question_id)
@@ -35,26 +39,42 @@ impl Counter {
     }
 
     /// Reserve `count` unique IDs, starting at the returned value.
+    ///
+    /// A missing counter is atomically created as if it had started at
+    /// [`FIRST_VALUE`], so that a missing counter cannot fail a request.
     pub async fn reserve(counters: &Coll<Self>, id: &str, count: u32) -> Result<u32, Error> {
         let filter = doc! {
             "_id": id
         };
-        let update = doc! {
-            "$inc": { "next": count }
-        };
+        let update = vec![doc! {
+            "$set": {
+                "next": { "$add": [{ "$ifNull": ["$next", FIRST_VALUE] }, count] }
+            }
+        }];
         let options: FindOneAndUpdateOptions = FindOneAndUpdateOptions::builder()
-            .return_document(ReturnDocument::Before)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
             .build();
-        let counter = counters
-            .find_one_and_update(filter, update, options)
-            .await?
-            .ok_or_else(|| {
-                Error::Status(
-                    Status::InternalServerError,
-                    format!("Failed to find counter with ID `{}`", id),
-                )
-            })?;
-        Ok(counter.next)
+        let result = counters
+            .find_one_and_update(filter.clone(), update.clone(), options.clone())
+            .await;
+        // If another request created the counter at the same time, ours lost
+        // the race, and retrying will increment theirs.
+        let counter = match result {
+            Err(e) if is_duplicate_key_error(Err::<(), _>(&e)) => {
+                counters
+                    .find_one_and_update(filter, update, options)
+                    .await?
+            }
+            result => result?,
+        }
+        .ok_or_else(|| {
+            Error::Status(
+                Status::InternalServerError,
+                format!("Failed to find counter with ID `{}`", id),
+            )
+        })?;
+        Ok(counter.next - count)
     }
 }
 
@@ -71,21 +91,84 @@ pub async fn ensure_election_id_counter_exists(counters: &Coll<Counter>) -> Resu
         }
     };
     let options: UpdateOptions = UpdateOptions::builder().upsert(true).build();
-    let result = counters.update_one(filter, update, options).await?;
-    if result.upserted_id.is_some() {
+    let result = counters.update_one(filter, update, options).await;
+    // Another instance booting against the same fresh database created it first.
+    if is_duplicate_key_error(result.as_ref()) {
+        debug!("Election ID counter was created concurrently");
+        return Ok(());
+    }
+    if result?.upserted_id.is_some() {
         warn!("The database looks empty; created election ID counter");
     }
     Ok(())
 }
 
+/// Ensure that the global election ID counter is past every existing
+/// election's ID, advancing it if not, e.g. if it was lost and recreated.
+///
+/// The counter must already exist.
+pub async fn ensure_election_id_counter_consistent(
+    counters: &Coll<Counter>,
+    elections: &Coll<Election>,
+) -> Result<(), DbError> {
+    debug!("Ensuring election ID counter is consistent");
+    let options = FindOneOptions::builder()
+        .sort(doc! { "_id": -1 })
+        .projection(doc! { "_id": 1 })
+        .build();
+    let Some(latest) = elections
+        .clone_with_type::<LatestElection>()
+        .find_one(None, options)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    // Only ever move the counter forwards, in case elections are being
+    // created concurrently.
+    let next = i64::from(latest.id) + 1;
+    let filter = doc! {
+        "_id": ELECTION_ID_COUNTER_ID,
+    };
+    let update = doc! {
+        "$max": { "next": next }
+    };
+    let result = counters.update_one(filter, update, None).await?;
+    if result.modified_count > 0 {
+        error!("****************************************************************");
+        error!(
+            "* The election ID counter was behind the latest election ID {}.",
+            latest.id
+        );
+        error!(
+            "* It has been advanced to {}; find out how it fell behind.",
+            next
+        );
+        error!("****************************************************************");
+    }
+    Ok(())
+}
+
+/// The ID of the latest election, as found by
+/// [`ensure_election_id_counter_consistent`].
+#[derive(Deserialize)]
+struct LatestElection {
+    #[serde(rename = "_id")]
+    id: ElectionId,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use mongodb::Database;
+    use rocket::tokio;
+
+    /// Number of tasks racing on the same counter.
+    const TASKS: u32 = 20;
 
     #[backend_test]
-    async fn counter_increment(db: Database) {
+    async fn counter_increment(db: Database) -> anyhow::Result<()> {
         const START: u32 = 5;
         const ID: &str = "unique";
 
@@ -95,18 +178,116 @@ mod tests {
             next: START,
         };
         let counters = Coll::<Counter>::from_db(&db);
-        counters.insert_one(counter, None).await.unwrap();
+        counters.insert_one(counter, None).await?;
 
         // Get the next value.
-        let next = Counter::next(&counters, ID).await.unwrap();
+        let next = Counter::next(&counters, ID).await?;
         assert_eq!(next, START);
 
         // Check the counter was incremented.
         let counter = counters
             .find_one(doc! {"_id": ID}, None)
-            .await
-            .unwrap()
-            .unwrap();
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("counter {ID} disappeared"))?;
         assert_eq!(counter.next, START + 1);
+
+        Ok(())
+    }
+
+    #[backend_test]
+    async fn concurrent_increments_create_missing_counter(db: Database) -> anyhow::Result<()> {
+        const ID: &str = "missing";
+        let counters = Coll::<Counter>::from_db(&db);
+
+        // Race many tasks on a counter that doesn't exist yet.
+        let handles = (0..TASKS)
+            .map(|_| {
+                let counters = counters.clone();
+                tokio::spawn(async move { Counter::next(&counters, ID).await })
+            })
+            .collect::<Vec<_>>();
+        let mut values = Vec::new();
+        for handle in handles {
+            values.push(handle.await??);
+        }
+
+        // Every task got a distinct value, starting from the first.
+        values.sort_unstable();
+        assert_eq!(
+            values,
+            (FIRST_VALUE..FIRST_VALUE + TASKS).collect::<Vec<_>>()
+        );
+        let counter = counters
+            .find_one(doc! {"_id": ID}, None)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("counter {ID} not created"))?;
+        assert_eq!(counter.next, FIRST_VALUE + TASKS);
+
+        Ok(())
+    }
+
+    #[backend_test]
+    async fn concurrent_election_id_counter_creation(db: Database) -> anyhow::Result<()> {
+        let counters = Coll::<Counter>::from_db(&db);
+
+        // Every instance booting at once succeeds.
+        let handles = (0..TASKS)
+            .map(|_| {
+                let counters = counters.clone();
+                tokio::spawn(async move { ensure_election_id_counter_exists(&counters).await })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await??;
+        }
+
+        assert_eq!(counters.count_documents(None, None).await?, 1);
+        assert_eq!(
+            Counter::next(&counters, ELECTION_ID_COUNTER_ID).await?,
+            FIRST_VALUE
+        );
+
+        Ok(())
+    }
+
+    async fn election_id_counter_next(counters: &Coll<Counter>) -> anyhow::Result<u32> {
+        counters
+            .find_one(doc! {"_id": ELECTION_ID_COUNTER_ID}, None)
+            .await?
+            .map(|counter| counter.next)
+            .ok_or_else(|| anyhow::anyhow!("election ID counter disappeared"))
+    }
+
+    #[backend_test]
+    async fn lagging_election_id_counter_repaired(db: Database) -> anyhow::Result<()> {
+        let counters = Coll::<Counter>::from_db(&db);
+        let elections = Coll::<Election>::from_db(&db);
+
+        // Nothing to do without any elections.
+        ensure_election_id_counter_exists(&counters).await?;
+        ensure_election_id_counter_consistent(&counters, &elections).await?;
+        assert_eq!(election_id_counter_next(&counters).await?, FIRST_VALUE);
+
+        // The counter is advanced past the latest election.
+        for id in [3, 7] {
+            let mut election = Election::published_example();
+            election.id = id;
+            elections.insert_one(election, None).await?;
+        }
+        ensure_election_id_counter_consistent(&counters, &elections).await?;
+        assert_eq!(election_id_counter_next(&counters).await?, 8);
+
+        // A counter that is already ahead is left alone.
+        counters
+            .update_one(
+                doc! {"_id": ELECTION_ID_COUNTER_ID},
+                doc! {"$set": {"next": 20}},
+                None,
+            )
+            .await?;
+        ensure_election_id_counter_consistent(&counters, &elections).await?;
+        assert_eq!(election_id_counter_next(&counters).await?, 20);
+
+        Ok(())
     }
 }
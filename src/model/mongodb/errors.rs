@@ -6,10 +6,14 @@ use mongodb::error::{Error as DbError, ErrorKind, WriteFailure};
 pub const DUPLICATE_KEY: i32 = 11000;
 
 /// Return true if the given result is a duplicate key write error.
+/// Commands that write, such as `findAndModify` upserts, report these as
+/// command errors rather than write errors.
 pub fn is_duplicate_key_error<T>(result: Result<T, &DbError>) -> bool {
     if let Err(err) = result {
-        if let ErrorKind::Write(WriteFailure::WriteError(ref e)) = *err.kind {
-            return e.code == DUPLICATE_KEY;
+        match *err.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref e)) => return e.code == DUPLICATE_KEY,
+            ErrorKind::Command(ref e) => return e.code == DUPLICATE_KEY,
+            _ => {}
         }
     }
     false
@@ -1,11 +1,22 @@
 mod bson;
+mod clock;
 mod collection;
+mod comment;
 mod counter;
 mod errors;
+mod monitor;
+mod transaction;
 
-pub use bson::{serde_string_map, u32_id_filter, Id};
-pub use collection::{ensure_indexes_exist, Coll, MongoCollection};
+pub use bson::{u32_id_filter, Id};
+pub use clock::{
+    estimate_skew, measure_skew, ClockSkewFairing, ClockSkewMonitor, SkewLevel, SkewThresholds,
+};
+pub use collection::{ensure_indexes_exist, Coll, MongoCollection, SecondaryColl};
+pub use comment::{request_comment, RequestComment};
 pub use counter::{
-    ballot_counter_id, ensure_election_id_counter_exists, Counter, ELECTION_ID_COUNTER_ID,
+    ballot_counter_id, ensure_election_id_counter_consistent, ensure_election_id_counter_exists,
+    Counter, ELECTION_ID_COUNTER_ID,
 };
 pub use errors::is_duplicate_key_error;
+pub use monitor::CommandMonitor;
+pub use transaction::{run_transaction, Deadline};
@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use mongodb::{
+    bson::Document,
+    event::command::{
+        CommandEventHandler, CommandFailedEvent, CommandStartedEvent, CommandSucceededEvent,
+    },
+};
+
+use crate::model::api::db_stats::{CommandStats, DbStats, DurationBucket, SlowCommand};
+
+/// Upper bounds of the duration histogram buckets, in milliseconds.
+const BUCKET_BOUNDS_MS: [u64; 10] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 5000];
+
+/// How many of the most recent slow commands are kept.
+const RECENT_SLOW_COMMANDS: usize = 20;
+
+/// Collection reported for commands that do not run against one.
+const NO_COLLECTION: &str = "-";
+
+/// Times every command the driver sends, keeping statistics per collection and
+/// command, and logging any slower than a threshold.
+///
+/// This is registered as the client's command event handler when connecting,
+/// and placed into managed state so the statistics can be served.
+#[derive(Clone)]
+pub struct CommandMonitor {
+    slow_threshold: Duration,
+    state: Arc<Mutex<MonitorState>>,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    /// Commands that have started but not yet completed, by driver request ID.
+    pending: HashMap<i32, PendingCommand>,
+    /// Timings by collection and command.
+    timings: BTreeMap<(String, String), Timings>,
+    /// The most recent slow commands, oldest first.
+    recent_slow: VecDeque<SlowCommand>,
+}
+
+/// What we need to remember about a command between it starting and completing.
+struct PendingCommand {
+    collection: String,
+    comment: Option<String>,
+}
+
+#[derive(Default)]
+struct Timings {
+    count: u64,
+    failures: u64,
+    slow: u64,
+    total: Duration,
+    max: Duration,
+    /// Number of commands falling into each bucket, not cumulative. The final
+    /// entry counts those slower than every bound.
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+}
+
+impl CommandMonitor {
+    /// Create a monitor that logs commands taking longer than the given time.
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self {
+            slow_threshold,
+            state: Arc::default(),
+        }
+    }
+
+    /// Get the statistics for every command completed so far.
+    pub fn stats(&self) -> DbStats {
+        let state = self.state();
+        let commands = state
+            .timings
+            .iter()
+            .map(|((collection, command), timings)| {
+                timings.to_stats(collection.clone(), command.clone())
+            })
+            .collect();
+        DbStats {
+            slow_threshold_ms: self.slow_threshold.as_millis() as u64,
+            commands,
+            recent_slow: state.recent_slow.iter().rev().cloned().collect(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MonitorState> {
+        // The state is only ever updated whole, so is still usable if another
+        // thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record the completion of a command.
+    fn complete(&self, request_id: i32, command: String, duration: Duration, failed: bool) {
+        let slow = duration > self.slow_threshold;
+        let slow_command = {
+            let mut state = self.state();
+            let PendingCommand {
+                collection,
+                comment,
+            } = state
+                .pending
+                .remove(&request_id)
+                .unwrap_or_else(|| PendingCommand {
+                    collection: NO_COLLECTION.to_string(),
+                    comment: None,
+                });
+            state
+                .timings
+                .entry((collection.clone(), command.clone()))
+                .or_default()
+                .record(duration, failed, slow);
+            if !slow {
+                return;
+            }
+            let slow_command = SlowCommand {
+                collection,
+                command,
+                duration_ms: duration.as_millis() as u64,
+                failed,
+                comment,
+            };
+            if state.recent_slow.len() == RECENT_SLOW_COMMANDS {
+                state.recent_slow.pop_front();
+            }
+            state.recent_slow.push_back(slow_command.clone());
+            slow_command
+        };
+        warn!("{slow_command}");
+    }
+}
+
+impl CommandEventHandler for CommandMonitor {
+    fn handle_command_started_event(&self, event: CommandStartedEvent) {
+        let pending = PendingCommand {
+            collection: command_collection(&event.command, &event.command_name),
+            comment: event
+                .command
+                .get_str("comment")
+                .ok()
+                .map(ToString::to_string),
+        };
+        self.state().pending.insert(event.request_id, pending);
+    }
+
+    fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+        self.complete(event.request_id, event.command_name, event.duration, false);
+    }
+
+    fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+        self.complete(event.request_id, event.command_name, event.duration, true);
+    }
+}
+
+impl Timings {
+    fn record(&mut self, duration: Duration, failed: bool, slow: bool) {
+        self.count += 1;
+        self.failures += failed as u64;
+        self.slow += slow as u64;
+        self.total += duration;
+        self.max = self.max.max(duration);
+        let millis = duration.as_millis() as u64;
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    fn to_stats(&self, collection: String, command: String) -> CommandStats {
+        let bounds = BUCKET_BOUNDS_MS.iter().copied().map(Some).chain([None]);
+        let buckets = bounds
+            .zip(self.buckets.iter().scan(0, |total, count| {
+                *total += count;
+                Some(*total)
+            }))
+            .map(|(le_ms, count)| DurationBucket { le_ms, count })
+            .collect();
+        CommandStats {
+            collection,
+            command,
+            count: self.count,
+            failures: self.failures,
+            slow: self.slow,
+            total_ms: self.total.as_millis() as u64,
+            max_ms: self.max.as_millis() as u64,
+            buckets,
+        }
+    }
+}
+
+/// Get the collection a command runs against. Most commands name it as the
+/// value of the command itself, e.g. `{"find": "voters"}`, but `getMore` names
+/// it separately.
+fn command_collection(command: &Document, command_name: &str) -> String {
+    command
+        .get_str(command_name)
+        .or_else(|_| command.get_str("collection"))
+        .unwrap_or(NO_COLLECTION)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::{
+        bson::doc,
+        options::{ClientOptions, FindOneOptions},
+        Client, Database,
+    };
+
+    use super::*;
+    use crate::{
+        logging::RequestId,
+        model::{
+            db::voter::Voter,
+            mongodb::{Coll, RequestComment},
+        },
+    };
+
+    #[test]
+    fn histogram() {
+        let mut timings = Timings::default();
+        for millis in [0, 3, 3, 40, 9000] {
+            timings.record(Duration::from_millis(millis), false, millis > 250);
+        }
+        timings.record(Duration::from_millis(300), true, true);
+        let stats = timings.to_stats("voters".to_string(), "find".to_string());
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.slow, 2);
+        assert_eq!(stats.total_ms, 9346);
+        assert_eq!(stats.max_ms, 9000);
+        let count_at = |le_ms| {
+            stats
+                .buckets
+                .iter()
+                .find(|bucket| bucket.le_ms == le_ms)
+                .unwrap()
+                .count
+        };
+        assert_eq!(count_at(Some(1)), 1);
+        assert_eq!(count_at(Some(5)), 3);
+        assert_eq!(count_at(Some(50)), 4);
+        assert_eq!(count_at(Some(500)), 5);
+        assert_eq!(count_at(Some(5000)), 5);
+        assert_eq!(count_at(None), 6);
+    }
+
+    #[test]
+    fn collection_names() {
+        let find = doc! {"find": "voters", "filter": {}};
+        assert_eq!(command_collection(&find, "find"), "voters");
+        let get_more = doc! {"getMore": 1234_i64, "collection": "ballots"};
+        assert_eq!(command_collection(&get_more, "getMore"), "ballots");
+        let commit = doc! {"commitTransaction": 1};
+        assert_eq!(command_collection(&commit, "commitTransaction"), "-");
+    }
+
+    #[backend_test]
+    async fn slow_commands_recorded(db: Database) {
+        // Connect a second client to the same database, treating every command
+        // as slow.
+        let db_uri: String = rocket::Config::figment().extract_inner("db_uri").unwrap();
+        let mut options = ClientOptions::parse(db_uri).await.unwrap();
+        let monitor = CommandMonitor::new(Duration::ZERO);
+        options.command_event_handler = Some(Arc::new(monitor.clone()));
+        let client = Client::with_options(options).unwrap();
+        let voters = Coll::<Voter>::from_db(&client.database(db.name()));
+
+        let request_id = RequestId {
+            id: 42,
+            client_op_id: None,
+        };
+        voters
+            .find_one(doc! {}, FindOneOptions::for_request(request_id))
+            .await
+            .unwrap();
+
+        let stats = monitor.stats();
+        assert_eq!(stats.slow_threshold_ms, 0);
+        let find = stats
+            .commands
+            .iter()
+            .find(|stats| stats.collection == "voters" && stats.command == "find")
+            .unwrap();
+        assert_eq!(find.count, 1);
+        assert_eq!(find.slow, 1);
+        assert_eq!(find.failures, 0);
+
+        // The slow command was logged with its collection and request.
+        let slow = stats
+            .recent_slow
+            .iter()
+            .find(|slow| slow.collection == "voters")
+            .unwrap();
+        assert_eq!(slow.command, "find");
+        assert_eq!(slow.comment.as_deref(), Some("req42"));
+        let line = slow.to_string();
+        assert!(line.contains("collection=voters"));
+        assert!(line.contains("command=find"));
+        assert!(line.contains("comment=req42"));
+    }
+}
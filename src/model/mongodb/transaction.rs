@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use mongodb::{
+    error::{Result as DbResult, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    ClientSession,
+};
+use rocket::{
+    futures::future::BoxFuture,
+    tokio::{
+        self,
+        time::{sleep_until, Instant},
+    },
+};
+
+use crate::{
+    error::{Error, Result},
+    logging::RequestId,
+};
+
+/// The time by which a request must have finished its work.
+///
+/// Rocket gives no signal when a client disconnects, so this stands in for
+/// one: a client that has waited this long will have given up, and probably
+/// retried, so the work is no longer wanted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// The deadline for a request starting now that may take `timeout`.
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    /// Has the deadline passed?
+    pub fn has_passed(&self) -> bool {
+        Instant::now() >= self.0
+    }
+
+    /// Wait until the deadline.
+    pub async fn wait(&self) {
+        sleep_until(self.0).await
+    }
+}
+
+/// Run `callback` in a transaction on `session` and commit it, retrying as
+/// [`ClientSession::with_transaction`] does, but giving up at `deadline`.
+///
+/// If the deadline passes before committing has begun, `callback` is dropped
+/// and the transaction aborted, so none of its writes take effect, and
+/// [`Error::DeadlineExceeded`] is returned. Once committing has begun, the
+/// transaction may already have been committed, so it is never aborted;
+/// an unknown commit result is just no longer retried after the deadline.
+pub async fn run_transaction<R, C, F>(
+    session: &mut ClientSession,
+    mut context: C,
+    mut callback: F,
+    deadline: Deadline,
+    request_id: RequestId,
+) -> Result<R>
+where
+    F: for<'a> FnMut(&'a mut ClientSession, &'a mut C) -> BoxFuture<'a, DbResult<R>>,
+{
+    'transaction: loop {
+        if deadline.has_passed() {
+            warn!("  req{request_id} Not starting transaction, as the request took too long");
+            return Err(Error::DeadlineExceeded);
+        }
+        session.start_transaction(None).await?;
+        let outcome = tokio::select! {
+            outcome = callback(session, &mut context) => Some(outcome),
+            _ = deadline.wait() => None,
+        };
+        let ret = match outcome {
+            Some(Ok(ret)) => ret,
+            Some(Err(e)) => {
+                // Errors aborting are ignored, as with `with_transaction`.
+                let _ = session.abort_transaction().await;
+                if e.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+                    continue 'transaction;
+                }
+                return Err(e.into());
+            }
+            None => {
+                // The callback has been dropped, so nothing more will be written.
+                warn!("  req{request_id} Aborting uncommitted transaction, as the request took too long");
+                let _ = session.abort_transaction().await;
+                return Err(Error::DeadlineExceeded);
+            }
+        };
+        'commit: loop {
+            match session.commit_transaction().await {
+                Ok(()) => return Ok(ret),
+                Err(e) if deadline.has_passed() => return Err(e.into()),
+                Err(e) if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue 'commit,
+                Err(e) if e.contains_label(TRANSIENT_TRANSACTION_ERROR) => continue 'transaction,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::{
+        bson::{doc, Document},
+        Database,
+    };
+    use rocket::{futures::FutureExt, local::asynchronous::Client};
+
+    use super::*;
+
+    #[backend_test]
+    async fn abort_at_deadline(client: Client, db: Database) {
+        let db_client = client.rocket().state::<mongodb::Client>().unwrap();
+        let docs = db.collection::<Document>("transaction_test");
+        // Collections cannot be created inside a transaction on older servers.
+        docs.insert_one(doc! { "committed": true }, None)
+            .await
+            .unwrap();
+
+        // A quick transaction is committed.
+        let mut session = db_client.start_session(None).await.unwrap();
+        let deadline = Deadline::after(Duration::from_secs(10));
+        let result = run_transaction(
+            &mut session,
+            &docs,
+            |session, docs| {
+                async move {
+                    docs.insert_one_with_session(doc! { "quick": true }, None, session)
+                        .await?;
+                    Ok(())
+                }
+                .boxed()
+            },
+            deadline,
+            RequestId::next(),
+        )
+        .await;
+        assert!(result.is_ok());
+        let quick = docs.find_one(doc! { "quick": true }, None).await.unwrap();
+        assert!(quick.is_some());
+
+        // A transaction that is artificially slow to finish is aborted at the
+        // deadline, discarding what it had already written.
+        let mut session = db_client.start_session(None).await.unwrap();
+        let deadline = Deadline::after(Duration::from_millis(200));
+        let result = run_transaction(
+            &mut session,
+            &docs,
+            |session, docs| {
+                async move {
+                    docs.insert_one_with_session(doc! { "slow": true }, None, session)
+                        .await?;
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(())
+                }
+                .boxed()
+            },
+            deadline,
+            RequestId::next(),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        assert_eq!(result.unwrap_err().status().code, 503);
+        let slow = docs.find_one(doc! { "slow": true }, None).await.unwrap();
+        assert!(slow.is_none());
+        // The session was left with its transaction aborted.
+        assert!(session.abort_transaction().await.is_err());
+
+        // Nothing is even started once the deadline has passed.
+        let started: Result<()> = run_transaction(
+            &mut session,
+            &docs,
+            |_, _| async { panic!("Transaction started after its deadline") }.boxed(),
+            deadline,
+            RequestId::next(),
+        )
+        .await;
+        assert!(matches!(started, Err(Error::DeadlineExceeded)));
+    }
+}
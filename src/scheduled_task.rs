@@ -1,22 +1,55 @@
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
 use chrono::{DateTime, Utc};
-use rocket::tokio::{
-    self,
-    sync::Notify,
-    task::{JoinError, JoinHandle},
-    time::Duration,
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::Status,
+    tokio::{
+        self,
+        sync::Notify,
+        task::{JoinError, JoinHandle},
+        time::Duration,
+    },
+    Build, Rocket,
 };
 
+use crate::{
+    error::Error,
+    model::api::task::{TaskDesc, TaskState},
+};
+
+/// What is known about a scheduled task, shared between the task and the registry.
+struct TaskInfo {
+    name: String,
+    run_at: DateTime<Utc>,
+    state: Mutex<TaskState>,
+    signal: Notify,
+}
+
+impl TaskInfo {
+    fn state(&self) -> TaskState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Move to state `to` if currently in state `from`.
+    fn transition(&self, from: TaskState, to: TaskState) {
+        let mut state = self.state.lock().unwrap();
+        if *state == from {
+            *state = to;
+        }
+    }
+}
+
 /// A task scheduled for a specific point in the future.
 /// It will automatically execute at that point, or can be cancelled or triggered early.
 pub struct ScheduledTask<T> {
     task_handle: JoinHandle<T>,
     wait_handle: JoinHandle<()>,
-    signal: Arc<Notify>,
+    info: Arc<TaskInfo>,
 }
 
 impl<T> ScheduledTask<T>
@@ -25,45 +58,58 @@ where
 {
     /// Schedule the given task to execute at time `run_at`.
     /// If `run_at` is in the past, the task will execute immediately.
-    pub fn new<Fut>(task: Fut, run_at: DateTime<Utc>) -> Self
+    pub fn new<Fut>(name: impl Into<String>, task: Fut, run_at: DateTime<Utc>) -> Self
     where
         Fut: Future<Output = T> + Send + 'static,
     {
         // Create the synchronisation signal.
-        let signal = Arc::new(Notify::new());
+        let info = Arc::new(TaskInfo {
+            name: name.into(),
+            run_at,
+            state: Mutex::new(TaskState::Pending),
+            signal: Notify::new(),
+        });
 
         // Schedule the task to wait on the signal.
-        let task_signal = signal.clone();
+        let task_info = info.clone();
         let task_handle = tokio::spawn(async move {
-            task_signal.notified().await;
-            trace!("Triggering task that was scheduled for {}", run_at);
-            task.await
+            task_info.signal.notified().await;
+            task_info.transition(TaskState::Pending, TaskState::Running);
+            trace!(
+                "Triggering task {} that was scheduled for {}",
+                task_info.name,
+                run_at
+            );
+            let output = task.await;
+            task_info.transition(TaskState::Running, TaskState::Completed);
+            output
         });
 
         // Spawn another task to give the signal at the appropriate time.
         let sleep_duration = datetime_to_duration(run_at);
-        let wait_signal = signal.clone();
+        let wait_info = info.clone();
         let wait_handle = tokio::spawn(async move {
             tokio::time::sleep(sleep_duration).await;
-            wait_signal.notify_one();
+            wait_info.signal.notify_one();
         });
 
         Self {
             task_handle,
             wait_handle,
-            signal,
+            info,
         }
     }
 
     /// Cancel the task. Returns true iff it had already completed before we could cancel it.
     pub async fn cancel(self) -> bool {
-        trace!("Cancelling scheduled task...");
+        trace!("Cancelling scheduled task {}...", self.info.name);
         self.task_handle.abort();
         self.wait_handle.abort();
         let result = self.task_handle.await.is_ok();
         if result {
             trace!("...but it had already completed");
         } else {
+            *self.info.state.lock().unwrap() = TaskState::Cancelled;
             trace!("...scheduled task cancelled");
         }
         result
@@ -71,9 +117,26 @@ where
 
     /// Trigger the task now instead of waiting till the original time.
     pub fn trigger_now(&self) {
-        trace!("Manually triggering scheduled task");
+        trace!("Manually triggering scheduled task {}", self.info.name);
         self.wait_handle.abort();
-        self.signal.notify_one();
+        self.info.signal.notify_one();
+    }
+}
+
+impl<T> ScheduledTask<T> {
+    /// The task's name.
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    /// When the task runs, unless triggered early.
+    pub fn scheduled_for(&self) -> DateTime<Utc> {
+        self.info.run_at
+    }
+
+    /// The task's current state.
+    pub fn state(&self) -> TaskState {
+        self.info.state()
     }
 }
 
@@ -86,6 +149,86 @@ impl<T> Future for ScheduledTask<T> {
     }
 }
 
+/// A task known to the registry.
+struct RegisteredTask {
+    info: Arc<TaskInfo>,
+    allow_early: bool,
+}
+
+/// Every scheduled task registered by name, so that admins can see what is
+/// scheduled and trigger tasks early.
+/// A task stays listed in its final state until another task with the same
+/// name is registered.
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: Arc<Mutex<BTreeMap<String, RegisteredTask>>>,
+}
+
+impl TaskRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the given task, replacing any task with the same name.
+    /// If `allow_early` is set, admins may trigger it before it is due.
+    pub fn register<T>(&self, task: &ScheduledTask<T>, allow_early: bool) {
+        let registered = RegisteredTask {
+            info: task.info.clone(),
+            allow_early,
+        };
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(task.info.name.clone(), registered);
+    }
+
+    /// Describe every registered task, soonest first.
+    pub fn list(&self) -> Vec<TaskDesc> {
+        let mut tasks = self
+            .tasks
+            .lock()
+            .unwrap()
+            .values()
+            .map(|task| TaskDesc {
+                name: task.info.name.clone(),
+                scheduled_for: task.info.run_at,
+                state: task.info.state(),
+                allow_early: task.allow_early,
+            })
+            .collect::<Vec<_>>();
+        // Sorting is stable, so tasks due at the same time stay in name order.
+        tasks.sort_by_key(|task| task.scheduled_for);
+        tasks
+    }
+
+    /// Trigger the named task now, if it is still pending and may be triggered early.
+    pub fn trigger(&self, name: &str) -> Result<(), Error> {
+        let tasks = self.tasks.lock().unwrap();
+        let task = tasks
+            .get(name)
+            .ok_or_else(|| Error::not_found(format!("Task '{}'", name)))?;
+        if !task.allow_early {
+            return Err(Error::Status(
+                Status::UnprocessableEntity,
+                format!("Task '{}' may not be triggered early", name),
+            ));
+        }
+        let state = task.info.state();
+        if state != TaskState::Pending {
+            return Err(Error::Status(
+                Status::Conflict,
+                format!("Task '{}' is not pending, but {:?}", name, state),
+            ));
+        }
+        trace!("Manually triggering registered task {}", name);
+        // If the task's time comes while it is running, the extra signal is
+        // never waited on, so is harmless.
+        task.info.signal.notify_one();
+        Ok(())
+    }
+}
+
 /// Convert a `DateTime` into a duration from the current instant.
 /// A `DateTime` in the past will produce a duration of zero.
 fn datetime_to_duration(datetime: DateTime<Utc>) -> Duration {
@@ -94,3 +237,20 @@ fn datetime_to_duration(datetime: DateTime<Utc>) -> Duration {
     let time_diff = u64::try_from(target_timestamp - now_timestamp).unwrap_or(0);
     Duration::from_millis(time_diff)
 }
+
+/// Fairing to manage the task registry.
+pub struct TaskRegistryFairing;
+
+#[rocket::async_trait]
+impl Fairing for TaskRegistryFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Task Registry",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        Ok(rocket.manage(TaskRegistry::new()))
+    }
+}
@@ -0,0 +1,108 @@
+//! A development tool for filling a local database with realistic example data.
+//! This connects using the same configuration as the server.
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use dreip_backend::{
+    config::{Config, DatabaseFairing},
+    testdata::{seed, SeedOptions},
+};
+
+const PROGRAM_NAME: &str = "seed";
+
+const ABOUT_TEXT: &str = "Seed the configured database with example data for local development.
+
+The default admin is created if there are no admins. Elections cycle through
+published, draft, and archived examples; every election except drafts gets
+ballots in each state. Voters cycle through never having joined, having joined,
+and having confirmed some ballots.";
+
+const ELECTIONS: &str = "elections";
+
+const ELECTIONS_HELP: &str = "The number of elections to create";
+
+const VOTERS: &str = "voters";
+
+const VOTERS_HELP: &str = "The number of voters to create";
+
+const BALLOTS: &str = "ballots";
+
+const BALLOTS_HELP: &str = "The number of unconfirmed, audited, and confirmed ballots\n\
+to create for each question of each non-draft election";
+
+const WIPE: &str = "wipe";
+
+const WIPE_HELP: &str = "Delete every existing document before seeding";
+
+/// Construct the CLI configuration.
+fn cli() -> Command {
+    // Make the build dirty when the toml changes.
+    include_str!("../Cargo.toml");
+
+    let count = |name: &'static str, help: &'static str, default: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .value_name("N")
+            .help(help)
+            .action(ArgAction::Set)
+            .value_parser(value_parser!(usize))
+            .default_value(default)
+    };
+
+    clap::command!(PROGRAM_NAME)
+        .about(ABOUT_TEXT)
+        .arg(count(ELECTIONS, ELECTIONS_HELP, "3"))
+        .arg(count(VOTERS, VOTERS_HELP, "10"))
+        .arg(count(BALLOTS, BALLOTS_HELP, "5"))
+        .arg(
+            Arg::new(WIPE)
+                .long(WIPE)
+                .help(WIPE_HELP)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Seed the database, returning the exit code.
+async fn run(args: &ArgMatches) -> i32 {
+    // Unwraps safe: these all have defaults.
+    let options = SeedOptions {
+        elections: *args.get_one(ELECTIONS).unwrap(),
+        voters: *args.get_one(VOTERS).unwrap(),
+        ballots: *args.get_one(BALLOTS).unwrap(),
+        wipe: args.get_flag(WIPE),
+    };
+
+    let figment = rocket::Config::figment();
+    let config = match Config::from_figment(&figment) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to load application config: {err}");
+            return 1;
+        }
+    };
+    let db = match DatabaseFairing::probe(&figment).await {
+        Ok((_, db, _, _)) => db,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+
+    match seed(&db, &config, options).await {
+        Ok(report) => {
+            println!("{report}");
+            0
+        }
+        Err(err) => {
+            eprintln!("Failed to seed database `{}`: {err}", db.name());
+            1
+        }
+    }
+}
+
+#[rocket::main]
+async fn main() {
+    let args = cli().get_matches();
+    let exit_code = run(&args).await;
+    std::process::exit(exit_code)
+}
@@ -0,0 +1,609 @@
+//! Fixture builders shared by the tests and the `seed` development tool.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use mongodb::{
+    bson::{doc, Document},
+    error::Error as DbError,
+    options::ReplaceOptions,
+    Database,
+};
+use rand::{seq::SliceRandom, CryptoRng, RngCore};
+use serde::Deserialize;
+
+use crate::{
+    config::Config,
+    model::{
+        api::{
+            election::{ElectionSpec, QuestionSpec},
+            sms::Sms,
+        },
+        common::{
+            allowed_questions::AllowedQuestions,
+            ballot::{Audited, BallotId, Confirmed, Unconfirmed},
+            election::{CandidateId, ElectionState},
+        },
+        db::{
+            admin::{ensure_admin_exists, NewAdmin},
+            ballot::BallotCore,
+            candidate_totals::NewCandidateTotals,
+            election::{Election, Question},
+            voter::{NewVoter, Voter},
+            voter_election::VoterElection,
+        },
+        mongodb::{ballot_counter_id, Coll, Counter, Id},
+    },
+};
+
+/// Insert the published, draft, and archived example elections, with zero
+/// candidate totals for the published one as publishing it would create.
+pub async fn insert_elections(db: &Database) -> Result<(), DbError> {
+    let published = Election::published_example();
+    Coll::<Election>::from_db(db)
+        .insert_many(
+            [
+                &published,
+                &Election::draft_example(),
+                &Election::archived_example(),
+            ],
+            None,
+        )
+        .await?;
+    insert_zero_totals(db, &published).await
+}
+
+/// Insert zero candidate totals for every candidate of the given election, as
+/// publishing it would.
+pub async fn insert_zero_totals(db: &Database, election: &Election) -> Result<(), DbError> {
+    let totals = election
+        .questions
+        .values()
+        .flat_map(|question| {
+            question.candidates.iter().map(|candidate| {
+                NewCandidateTotals::new(election.id, question.id, candidate.clone())
+            })
+        })
+        .collect::<Vec<_>>();
+    if !totals.is_empty() {
+        Coll::<NewCandidateTotals>::from_db(db)
+            .insert_many(totals, None)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Store the given candidate totals, replacing any existing totals for the
+/// same candidates, e.g. the zero totals of a published election.
+pub async fn store_totals(
+    db: &Database,
+    totals: impl IntoIterator<Item = NewCandidateTotals>,
+) -> Result<(), DbError> {
+    let coll = Coll::<NewCandidateTotals>::from_db(db);
+    let options = ReplaceOptions::builder().upsert(true).build();
+    for total in totals {
+        let filter = doc! {
+            "election_id": total.election_id,
+            "question_id": total.question_id,
+            "candidate_name": &total.candidate_name,
+        };
+        coll.replace_one(filter, &total, options.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Insert a fixed set of ballots and candidate totals for the first two
+/// example questions of the published example election, which must already
+/// have been inserted.
+pub async fn insert_ballots(db: &Database) -> Result<(), DbError> {
+    let election = Coll::<Election>::from_db(db)
+        .find_one(doc! { "name": ElectionSpec::current_example().name }, None)
+        .await?
+        .expect("the published example election must be inserted first");
+    let q1 = election
+        .questions
+        .values()
+        .find(|q| q.description == QuestionSpec::example1().description)
+        .unwrap();
+    let q1c1 = q1.candidates.first().unwrap();
+    let q1c2 = q1.candidates.get(1).unwrap();
+    let q2 = election
+        .questions
+        .values()
+        .find(|q| q.description == QuestionSpec::example2().description)
+        .unwrap();
+    let q2c1 = q2.candidates.first().unwrap();
+    let q2c2 = q2.candidates.get(1).unwrap();
+    let mut rng = rand::thread_rng();
+
+    let mut candidate_totals = Vec::new();
+    for candidate in q1.candidates.iter() {
+        candidate_totals.push(NewCandidateTotals::new(
+            election.id,
+            q1.id,
+            candidate.clone(),
+        ));
+    }
+    for candidate in q2.candidates.iter() {
+        candidate_totals.push(NewCandidateTotals::new(
+            election.id,
+            q2.id,
+            candidate.clone(),
+        ));
+    }
+    // This relies on no duplicate candidate names between questions, which is true for the examples.
+    let mut totals_map = candidate_totals
+        .iter_mut()
+        .map(|t| (t.candidate_name.clone(), &mut t.crypto))
+        .collect::<HashMap<_, _>>();
+
+    let mut ballot =
+        |question: &Question, yes: CandidateId, no: CandidateId, ballot_id: &mut BallotId| {
+            *ballot_id += 1;
+            BallotCore::new(*ballot_id, question.id, yes, vec![no], &election, &mut rng).unwrap()
+        };
+
+    macro_rules! ballot {
+        ($q:ident, $yes:ident, $no:ident, $id:ident) => {
+            ballot(&$q, $yes.clone(), $no.clone(), &mut $id)
+        };
+    }
+
+    let mut q1_id = 0;
+    let mut q2_id = 0;
+    // Create confirmed ballots.
+    let confirmed = vec![
+        // q1: 3 votes for candidate 1, 2 votes for candidate 2
+        ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
+        ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
+        ballot!(q1, q1c1, q1c2, q1_id).confirm(&mut totals_map),
+        ballot!(q1, q1c2, q1c1, q1_id).confirm(&mut totals_map),
+        ballot!(q1, q1c2, q1c1, q1_id).confirm(&mut totals_map),
+        // q2: 3 votes for candidate 2
+        ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
+        ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
+        ballot!(q2, q2c2, q2c1, q2_id).confirm(&mut totals_map),
+    ];
+    for totals in &mut candidate_totals {
+        let count = confirmed
+            .iter()
+            .filter(|ballot| ballot.question_id == totals.question_id)
+            .count();
+        totals.confirmed_ballot_count = Some(count as u64);
+    }
+
+    // Create audited ballots.
+    let audited = vec![
+        // q1: 1 vote for each
+        ballot!(q1, q1c1, q1c2, q1_id).audit(),
+        ballot!(q1, q1c2, q1c1, q1_id).audit(),
+        // q2: 3 votes for candidate 2, 1 vote for candidate 1
+        ballot!(q2, q2c2, q2c1, q2_id).audit(),
+        ballot!(q2, q2c2, q2c1, q2_id).audit(),
+        ballot!(q2, q2c2, q2c1, q2_id).audit(),
+        ballot!(q2, q2c1, q2c2, q2_id).audit(),
+    ];
+
+    // Create unconfirmed ballots.
+    let unconfirmed = vec![
+        // q1: 1 vote for each
+        ballot!(q1, q1c1, q1c2, q1_id),
+        ballot!(q1, q1c2, q1c1, q1_id),
+        // q2: 1 vote for candidate 1
+        ballot!(q2, q2c1, q2c2, q2_id),
+    ];
+
+    // Insert ballots.
+    Coll::<BallotCore<Confirmed>>::from_db(db)
+        .insert_many(confirmed, None)
+        .await?;
+    Coll::<BallotCore<Audited>>::from_db(db)
+        .insert_many(audited, None)
+        .await?;
+    Coll::<BallotCore<Unconfirmed>>::from_db(db)
+        .insert_many(unconfirmed, None)
+        .await?;
+    // Store candidate totals.
+    store_totals(db, candidate_totals).await?;
+
+    Ok(())
+}
+
+/// How much data [`seed`] should insert.
+#[derive(Debug, Clone, Copy)]
+pub struct SeedOptions {
+    /// Number of elections, cycling through published, draft, and archived.
+    pub elections: usize,
+    /// Number of voters.
+    pub voters: usize,
+    /// Number of ballots in each state, for each question of each election
+    /// that has been published.
+    pub ballots: usize,
+    /// Delete every existing document first.
+    pub wipe: bool,
+}
+
+/// The number of documents of each kind that [`seed`] inserted.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    pub elections: usize,
+    pub voters: usize,
+    pub unconfirmed: usize,
+    pub audited: usize,
+    pub confirmed: usize,
+}
+
+impl Display for SeedReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Inserted {} elections, {} voters, and {} unconfirmed, {} audited, and {} confirmed ballots",
+            self.elections, self.voters, self.unconfirmed, self.audited, self.confirmed
+        )
+    }
+}
+
+/// Fill the database with example data for local development: the default
+/// admin if there are no admins, the example elections, voters in a variety
+/// of states, and ballots.
+pub async fn seed(
+    db: &Database,
+    config: &Config,
+    options: SeedOptions,
+) -> Result<SeedReport, DbError> {
+    if options.wipe {
+        wipe(db).await?;
+    }
+    ensure_admin_exists(&Coll::<NewAdmin>::from_db(db)).await?;
+
+    let mut report = SeedReport::default();
+
+    // Create the elections.
+    let elections = (0..options.elections)
+        .map(example_election)
+        .collect::<Vec<_>>();
+    if !elections.is_empty() {
+        Coll::<Election>::from_db(db)
+            .insert_many(&elections, None)
+            .await?;
+    }
+    report.elections = elections.len();
+
+    // Create counters, totals, and ballots for every election that has been published.
+    let published = elections
+        .iter()
+        .filter(|election| election.metadata.state != ElectionState::Draft)
+        .collect::<Vec<_>>();
+    for election in published.iter() {
+        let counts = BallotCounts::each(options.ballots);
+        let data = SeedBallots::new(election, counts, rand::thread_rng());
+        report.unconfirmed += data.unconfirmed.len();
+        report.audited += data.audited.len();
+        report.confirmed += data.confirmed.len();
+        data.insert(db).await?;
+    }
+
+    // Create the voters, carrying on numbering from any that already exist.
+    let voters = Coll::<NewVoter>::from_db(db);
+    let existing_voters = Coll::<Voter>::from_db(db)
+        .count_documents(None, None)
+        .await?;
+    let first_voter = existing_voters as usize;
+    let new_voters = (0..options.voters)
+        .map(|i| example_voter(first_voter + i, config))
+        .collect::<Vec<_>>();
+    if !new_voters.is_empty() {
+        let inserted = voters.insert_many(&new_voters, None).await?.inserted_ids;
+        let voter_elections = inserted
+            .into_iter()
+            .flat_map(|(i, voter_id)| {
+                // Unwrap safe: the ID comes directly from the database.
+                let voter_id = voter_id.as_object_id().unwrap().into();
+                example_voter_elections(first_voter + i, voter_id, &published)
+            })
+            .collect::<Vec<_>>();
+        if !voter_elections.is_empty() {
+            Coll::<VoterElection>::from_db(db)
+                .insert_many(&voter_elections, None)
+                .await?;
+        }
+    }
+    report.voters = new_voters.len();
+
+    info!("Seeded database `{}`: {}", db.name(), report);
+    Ok(report)
+}
+
+/// Delete every document from every collection, keeping the indexes.
+async fn wipe(db: &Database) -> Result<(), DbError> {
+    for name in db.list_collection_names(None).await? {
+        db.collection::<Document>(&name)
+            .delete_many(doc! {}, None)
+            .await?;
+    }
+    warn!("Wiped database `{}`", db.name());
+    Ok(())
+}
+
+/// The `index`th seeded election, cycling through the published, draft, and
+/// archived examples. Names are made unique after the first cycle.
+fn example_election(index: usize) -> Election {
+    let mut election = match index % 3 {
+        0 => Election::published_example(),
+        1 => Election::draft_example(),
+        _ => Election::archived_example(),
+    };
+    if index >= 3 {
+        election.metadata.name = format!("{} ({})", election.metadata.name, index / 3 + 1);
+    }
+    election
+}
+
+/// The `index`th seeded voter, with a distinct UK mobile number.
+fn example_voter(index: usize, config: &Config) -> NewVoter {
+    // Unwrap safe: this is always a well-formed UK mobile number.
+    let sms: Sms = format!("+4474{:08}", index).parse().unwrap();
+    NewVoter::new(sms, config)
+}
+
+/// The elections the `index`th seeded voter has joined.
+///
+/// Voters cycle through never having joined an election, having joined every
+/// given election without confirming anything, and having joined every given
+/// election and confirmed ballots on some of its questions.
+fn example_voter_elections(
+    index: usize,
+    voter_id: Id,
+    elections: &[&Election],
+) -> Vec<VoterElection> {
+    if index % 3 == 0 {
+        return Vec::new();
+    }
+    elections
+        .iter()
+        .map(|election| {
+            let confirmed = election
+                .questions
+                .keys()
+                .enumerate()
+                .map(|(i, question_id)| (*question_id, index % 3 == 2 && i % 2 == 0))
+                .collect();
+            VoterElection {
+                voter_id,
+                election_id: election.id,
+                allowed_questions: AllowedQuestions { confirmed },
+                joins: None,
+                consent: None,
+            }
+        })
+        .collect()
+}
+
+/// Number of ballots in each state to create for each question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BallotCounts {
+    pub unconfirmed: usize,
+    pub audited: usize,
+    pub confirmed: usize,
+}
+
+impl BallotCounts {
+    /// The same number of ballots in every state.
+    pub fn each(count: usize) -> Self {
+        Self {
+            unconfirmed: count,
+            audited: count,
+            confirmed: count,
+        }
+    }
+}
+
+/// Ballot counters, candidate totals, and ballots for a single seeded election.
+pub struct SeedBallots {
+    counters: Vec<Counter>,
+    totals: Vec<NewCandidateTotals>,
+    unconfirmed: Vec<BallotCore<Unconfirmed>>,
+    audited: Vec<BallotCore<Audited>>,
+    confirmed: Vec<BallotCore<Confirmed>>,
+}
+
+impl SeedBallots {
+    /// Create the given number of ballots of each state for every question,
+    /// each voting for a random candidate. Ballots of different states are
+    /// interleaved, as though cast by voters at the same time.
+    pub fn new(
+        election: &Election,
+        counts: BallotCounts,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Self {
+        let mut seeded = Self {
+            counters: Vec::new(),
+            totals: Vec::new(),
+            unconfirmed: Vec::new(),
+            audited: Vec::new(),
+            confirmed: Vec::new(),
+        };
+
+        for question in election.questions.values() {
+            let mut totals = question
+                .candidates
+                .iter()
+                .map(|candidate| {
+                    NewCandidateTotals::new(election.id, question.id, candidate.clone())
+                })
+                .collect::<Vec<_>>();
+            let mut totals_map = totals
+                .iter_mut()
+                .map(|t| (t.candidate_name.clone(), &mut t.crypto))
+                .collect::<HashMap<_, _>>();
+
+            let mut next_ballot_id = 1;
+            let rounds = counts.unconfirmed.max(counts.audited).max(counts.confirmed);
+            let states = (0..rounds).flat_map(|round| {
+                [
+                    (round < counts.unconfirmed).then_some(0),
+                    (round < counts.audited).then_some(1),
+                    (round < counts.confirmed).then_some(2),
+                ]
+                .into_iter()
+                .flatten()
+            });
+            for state in states {
+                let yes = question.candidates.choose(&mut rng).unwrap().clone();
+                let no = question
+                    .candidates
+                    .iter()
+                    .filter(|candidate| **candidate != yes)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let ballot =
+                    BallotCore::new(next_ballot_id, question.id, yes, no, election, &mut rng)
+                        .expect("example candidates are distinct");
+                next_ballot_id += 1;
+                match state {
+                    0 => seeded.unconfirmed.push(ballot),
+                    1 => seeded.audited.push(ballot.audit()),
+                    _ => seeded.confirmed.push(ballot.confirm(&mut totals_map)),
+                }
+            }
+
+            for total in &mut totals {
+                total.confirmed_ballot_count = Some(counts.confirmed as u64);
+            }
+            seeded.counters.push(Counter {
+                id: ballot_counter_id(election.id, question.id),
+                next: next_ballot_id,
+            });
+            seeded.totals.append(&mut totals);
+        }
+
+        seeded
+    }
+
+    /// Insert everything into the database. The election itself must be
+    /// inserted separately, without ballot counters.
+    pub async fn insert(self, db: &Database) -> Result<(), DbError> {
+        Coll::<Counter>::from_db(db)
+            .insert_many(self.counters, None)
+            .await?;
+        Coll::<NewCandidateTotals>::from_db(db)
+            .insert_many(self.totals, None)
+            .await?;
+        if !self.unconfirmed.is_empty() {
+            Coll::<BallotCore<Unconfirmed>>::from_db(db)
+                .insert_many(self.unconfirmed, None)
+                .await?;
+        }
+        if !self.audited.is_empty() {
+            Coll::<BallotCore<Audited>>::from_db(db)
+                .insert_many(self.audited, None)
+                .await?;
+        }
+        if !self.confirmed.is_empty() {
+            Coll::<BallotCore<Confirmed>>::from_db(db)
+                .insert_many(self.confirmed, None)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rocket::{futures::TryStreamExt, local::asynchronous::Client};
+
+    use crate::model::{db::admin::Admin, mongodb::MongoCollection};
+
+    use super::*;
+
+    /// Count the documents in the collection for `T` matching the filter.
+    async fn count<T: MongoCollection>(
+        db: &Database,
+        filter: impl Into<Option<Document>>,
+    ) -> usize {
+        Coll::<T>::from_db(db)
+            .count_documents(filter, None)
+            .await
+            .unwrap() as usize
+    }
+
+    #[backend_test]
+    async fn seed_counts(client: Client, db: Database) {
+        let config = client.rocket().state::<Config>().unwrap();
+        let options = SeedOptions {
+            elections: 4,
+            voters: 6,
+            ballots: 2,
+            wipe: false,
+        };
+        let report = seed(&db, config, options).await.unwrap();
+
+        // Elections cycle published, draft, archived, published; only the
+        // three that have been published get ballots.
+        let questions = 4 + 2 + 4;
+        assert_eq!(
+            report,
+            SeedReport {
+                elections: 4,
+                voters: 6,
+                unconfirmed: questions * 2,
+                audited: questions * 2,
+                confirmed: questions * 2,
+            }
+        );
+        assert_eq!(count::<Admin>(&db, None).await, 1);
+        assert_eq!(count::<Election>(&db, None).await, 4);
+        assert_eq!(count::<Voter>(&db, None).await, 6);
+        assert_eq!(count::<Counter>(&db, None).await, questions);
+        assert_eq!(count::<NewCandidateTotals>(&db, None).await, questions * 2);
+        for state in ["Unconfirmed", "Audited", "Confirmed"] {
+            let ballots = count::<BallotCore<Unconfirmed>>(&db, doc! { "state": state }).await;
+            assert_eq!(ballots, questions * 2);
+        }
+
+        // Voters are in a variety of states.
+        let voter_elections = Coll::<VoterElection>::from_db(&db)
+            .find(None, None)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let joined = voter_elections
+            .iter()
+            .map(|voter_election| voter_election.voter_id)
+            .collect::<HashSet<_>>();
+        let never_joined = 6 - joined.len();
+        let some_confirmed = voter_elections
+            .iter()
+            .filter(|voter_election| {
+                voter_election
+                    .allowed_questions
+                    .confirmed
+                    .values()
+                    .any(|confirmed| *confirmed)
+            })
+            .map(|voter_election| voter_election.voter_id)
+            .collect::<HashSet<_>>()
+            .len();
+        assert_eq!(never_joined, 2);
+        assert_eq!(some_confirmed, 2);
+
+        // Seeding again adds to the existing data.
+        seed(&db, config, options).await.unwrap();
+        assert_eq!(count::<Election>(&db, None).await, 8);
+        assert_eq!(count::<Voter>(&db, None).await, 12);
+
+        // Wiping replaces it.
+        let options = SeedOptions {
+            wipe: true,
+            ..options
+        };
+        seed(&db, config, options).await.unwrap();
+        assert_eq!(count::<Admin>(&db, None).await, 1);
+        assert_eq!(count::<Election>(&db, None).await, 4);
+        assert_eq!(count::<Voter>(&db, None).await, 6);
+    }
+}
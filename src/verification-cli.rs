@@ -2,19 +2,27 @@
 //! This uses the internal server verification implementation, and is by definition
 //! compatible with the output of our API endpoints.
 
-use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::BufReader;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use dre_ip::{DreipGroup as DreipGroupTrait, Serializable};
 use rocket::serde::json::serde_json;
+use serde::{de::DeserializeOwned, Deserialize};
 
 use dreip_backend::model::{
-    api::election::{BallotError, ElectionResults, ReceiptError, VerificationError, VoteError},
-    common::election::DreipGroup,
+    api::{
+        bundle::ReceiptBundle,
+        candidate_totals::tally_to_u64,
+        election::{
+            check_group, BallotError, BoardDiffError, ElectionResults, ReceiptError,
+            VerificationError, VerificationPhase, VerificationProgress, VerificationWarning,
+            VoteError,
+        },
+        receipt::RECEIPT_SCHEMA_VERSION,
+    },
+    common::{board::BoardError, election::DREIP_GROUP_NAME},
 };
 
 const PROGRAM_NAME: &str = "verify-dreip";
@@ -24,24 +32,140 @@ const ABOUT_TEXT: &str = "Verify the integrity of a DRE-ip election.
 EXIT CODES:
      0: Verification succeeded.
    255: Ran successfully, but verification failed.
- Other: Error.";
+ Other: Error, including an unsupported receipt schema version or group.";
 
 const RESULTS_PATH: &str = "RESULTS_PATH";
 
-const RESULTS_PATH_HELP: &str = "The path to a JSON dump of a specific question,\n\
+const RESULTS_PATH_HELP: &str = "The path to a dump of a specific question,\n\
 as returned by `GET /elections/<election_id>/<question_id>/dump`";
 
+const BUNDLE: &str = "bundle";
+
+const BUNDLE_HELP: &str = "Treat the file as a receipt bundle,\n\
+as returned by `POST /elections/<election_id>/votes/mine/bundle`";
+
+const FORMAT: &str = "format";
+
+const FORMAT_HELP: &str = "The encoding of the input files: JSON as returned by the API,\n\
+or CBOR as returned when requested with `Accept: application/cbor`";
+
+const PREVIOUS: &str = "previous";
+
+const PREVIOUS_HELP: &str = "The path to an earlier dump of the same question;\n\
+verification fails unless the bulletin board has only been appended to since";
+
+const DIFF: &str = "diff";
+
+const DIFF_HELP: &str = "The path to an earlier dump of the same question;\n\
+verify both, then report any ballots removed, changed, or moved between\n\
+audited and confirmed since the earlier dump";
+
+const TIMING: &str = "timing";
+
+const TIMING_HELP: &str = "Print the time taken by each phase of verification";
+
+const ALL_ERRORS: &str = "all-errors";
+
+const ALL_ERRORS_HELP: &str = "Report every problem found, rather than stopping at the first";
+
+const REQUIRE_SIGNATURE: &str = "require-signature";
+
+const REQUIRE_SIGNATURE_HELP: &str = "Fail unless the dump is signed by the election key,\n\
+as returned by `GET /elections/<election_id>/<question_id>/dump?signed=true`";
+
+/// Show a progress bar when verifying at least this many receipts.
+const PROGRESS_THRESHOLD: usize = 1000;
+
 /// Construct the CLI configuration.
 fn cli() -> Command {
     // Make the build dirty when the toml changes.
     include_str!("../Cargo.toml");
 
-    clap::command!(PROGRAM_NAME).about(ABOUT_TEXT).arg(
-        Arg::new(RESULTS_PATH)
-            .help(RESULTS_PATH_HELP)
-            .action(ArgAction::Set)
-            .required(true),
-    )
+    clap::command!(PROGRAM_NAME)
+        .about(ABOUT_TEXT)
+        .arg(
+            Arg::new(RESULTS_PATH)
+                .help(RESULTS_PATH_HELP)
+                .action(ArgAction::Set)
+                .required(true),
+        )
+        .arg(
+            Arg::new(BUNDLE)
+                .long(BUNDLE)
+                .help(BUNDLE_HELP)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(FORMAT)
+                .long(FORMAT)
+                .help(FORMAT_HELP)
+                .action(ArgAction::Set)
+                .value_parser(["json", "cbor"])
+                .default_value("json"),
+        )
+        .arg(
+            Arg::new(PREVIOUS)
+                .long(PREVIOUS)
+                .value_name("PREVIOUS_PATH")
+                .help(PREVIOUS_HELP)
+                .action(ArgAction::Set)
+                .conflicts_with(BUNDLE),
+        )
+        .arg(
+            Arg::new(DIFF)
+                .long(DIFF)
+                .value_name("OLD_PATH")
+                .help(DIFF_HELP)
+                .action(ArgAction::Set)
+                .conflicts_with_all([BUNDLE, PREVIOUS]),
+        )
+        .arg(
+            Arg::new(TIMING)
+                .long(TIMING)
+                .help(TIMING_HELP)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(BUNDLE),
+        )
+        .arg(
+            Arg::new(ALL_ERRORS)
+                .long(ALL_ERRORS)
+                .help(ALL_ERRORS_HELP)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(BUNDLE),
+        )
+        .arg(
+            Arg::new(REQUIRE_SIGNATURE)
+                .long(REQUIRE_SIGNATURE)
+                .help(REQUIRE_SIGNATURE_HELP)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(BUNDLE),
+        )
+}
+
+/// The encoding of an input file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Json,
+    Cbor,
+}
+
+impl Format {
+    /// Decode a value from raw file contents.
+    fn decode<T: DeserializeOwned>(self, raw: &[u8]) -> Result<T, String> {
+        match self {
+            Format::Json => serde_json::from_slice(raw).map_err(|e| e.to_string()),
+            Format::Cbor => ciborium::from_reader(raw).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Json => write!(f, "JSON"),
+            Format::Cbor => write!(f, "CBOR"),
+        }
+    }
 }
 
 /// Errors that this program may produce.
@@ -49,10 +173,15 @@ fn cli() -> Command {
 enum Error {
     /// IO error described by the inner message.
     IO(String),
-    /// Failed to decode the JSON dump.
+    /// Failed to decode the dump.
     Format(String),
     /// Verification failed due to the contained reason.
     Verification(VerificationError),
+    /// Verification failed due to all of the contained reasons.
+    Verifications(Vec<VerificationError>),
+    /// The later dump is inconsistent with the earlier one for all of the
+    /// contained reasons.
+    Diff(Vec<BoardDiffError>),
 }
 
 /// A friendly, u64-based representation of the results for a particular candidate.
@@ -86,9 +215,11 @@ impl Display for FriendlyResults {
                 if self.audited_votes != 1 { "s" } else { "" }
             )
         } else {
+            // The election may still be in progress, or the question's
+            // tallies may be withheld from the public.
             write!(
                 f,
-                "{}: tally not available yet ({} audited ballot{} so far)",
+                "{}: tally not published ({} audited ballot{})",
                 self.candidate_name,
                 self.audited_votes,
                 if self.audited_votes != 1 { "s" } else { "" }
@@ -97,54 +228,150 @@ impl Display for FriendlyResults {
     }
 }
 
-/// Convert a tally `Scalar` to a u64. We assume that it fits.
-fn tally_to_u64(tally_scalar: <DreipGroup as DreipGroupTrait>::Scalar) -> u64 {
-    // Convert to bytes.
-    let bytes = Serializable::to_bytes(&tally_scalar);
-
-    // Check it fits into a u64.
-    const BYTES: isize = 8;
-    let extra_bytes = isize::try_from(bytes.len()).expect("unreasonably big scalar") - BYTES;
-
-    let u64_bytes: [u8; 8] = match extra_bytes.cmp(&0) {
-        Ordering::Less => {
-            // We can pad.
-            let mut u64_bytes = [0; 8];
-            let padding = (-extra_bytes) as usize;
-            for (i, byte) in bytes.iter().enumerate() {
-                u64_bytes[i + padding] = *byte;
-            }
-            u64_bytes
-        }
-        Ordering::Equal => {
-            // We have exactly the right amount.
-            bytes[..].try_into().unwrap()
-        }
-        Ordering::Greater => {
-            for byte in &bytes[0..extra_bytes as usize] {
-                if *byte != 0 {
-                    // Too big for u64!
-                    panic!("Tally was so large that it didn't fit into 64 bits!")
-                }
-            }
-            // Excess bytes are all zero; we can just trim.
-            let start_index = bytes.len() - 8;
-            bytes[start_index..].try_into().unwrap()
+/// Just enough of a dump or bundle to tell which DRE-ip group it claims to use.
+#[derive(Deserialize)]
+struct GroupProbe {
+    election: ProbedCrypto,
+}
+
+#[derive(Deserialize)]
+struct ProbedCrypto {
+    group: Option<String>,
+}
+
+/// Load a dump or bundle in the given format from the given path.
+/// The claimed group is checked first, since data for another group would
+/// otherwise only fail to parse, with a confusing error about its points.
+fn load<T: DeserializeOwned>(path: &str, format: Format) -> Result<T, Error> {
+    let raw = std::fs::read(path).map_err(|e| Error::IO(e.to_string()))?;
+    if let Ok(GroupProbe {
+        election: ProbedCrypto { group: Some(group) },
+    }) = format.decode(&raw)
+    {
+        check_group(&group).map_err(Error::Verification)?;
+    }
+    format.decode(&raw).map_err(Error::Format)
+}
+
+/// Show a progress bar on stderr while verifying the receipts of large dumps.
+fn show_progress(verified: usize, total: usize) {
+    if total < PROGRESS_THRESHOLD {
+        return;
+    }
+    // Only redraw when the percentage changes.
+    let percent = verified * 100 / total;
+    if percent != (verified - 1) * 100 / total {
+        let filled = percent / 2;
+        eprint!(
+            "\rVerifying receipts [{}{}] {:>3}%",
+            "#".repeat(filled),
+            " ".repeat(50 - filled),
+            percent
+        );
+        if verified == total {
+            eprintln!();
         }
-    };
+    }
+}
 
-    u64::from_be_bytes(u64_bytes)
+/// Print how long each phase took.
+fn print_timings(timings: &[(&str, Duration)]) {
+    for (phase, elapsed) in timings {
+        println!("Timing: {} took {:.3}s.", phase, elapsed.as_secs_f64());
+    }
 }
 
-/// Run verification.
-fn verify(path: &str) -> Result<Vec<FriendlyResults>, Error> {
+/// Run verification, checking against an earlier dump if given, and
+/// optionally printing the time taken by each phase.
+/// Both dumps must be in the given format.
+/// If `all_errors` is set, every problem is reported rather than just the first.
+/// If `require_signature` is set, the dump must be signed by the election key.
+fn verify(
+    path: &str,
+    format: Format,
+    previous: Option<&str>,
+    timing: bool,
+    all_errors: bool,
+    require_signature: bool,
+) -> Result<Vec<FriendlyResults>, Error> {
     // Load the file.
-    let file = BufReader::new(File::open(path).map_err(|e| Error::IO(e.to_string()))?);
-    let results: ElectionResults =
-        serde_json::from_reader(file).map_err(|e| Error::Format(e.to_string()))?;
+    let load_start = Instant::now();
+    let results: ElectionResults = load(path, format)?;
+    let mut timings = vec![("loading", load_start.elapsed())];
+    println!(
+        "Verifying receipt schema version {}.",
+        results.schema_version
+    );
 
-    // Run verification.
-    results.verify().map_err(Error::Verification)?;
+    // Run verification, noting when each phase starts.
+    let phase_starts = Mutex::new(Vec::new());
+    let mut errors = results.verify_all_with_progress(|progress| match progress {
+        VerificationProgress::Started(phase) => {
+            phase_starts.lock().unwrap().push((phase, Instant::now()))
+        }
+        VerificationProgress::Receipt { verified, total } => show_progress(verified, total),
+    });
+    let verify_end = Instant::now();
+    if timing {
+        let phase_starts = phase_starts.into_inner().unwrap();
+        let phase_ends = phase_starts
+            .iter()
+            .skip(1)
+            .map(|(_, start)| *start)
+            .chain(std::iter::once(verify_end));
+        for ((phase, start), end) in phase_starts.iter().zip(phase_ends) {
+            let name = match phase {
+                VerificationPhase::Totals => "verifying totals",
+                VerificationPhase::Receipts => "verifying receipts",
+                VerificationPhase::Board => "verifying bulletin board",
+            };
+            timings.push((name, end - *start));
+        }
+        print_timings(&timings);
+    }
+    for warning in results.warnings() {
+        eprintln!("Warning: {}", describe_warning(&warning));
+    }
+    if !errors.is_empty() {
+        let unsupported = matches!(
+            errors[0],
+            VerificationError::Receipt(ReceiptError::UnsupportedVersion { .. })
+                | VerificationError::UnsupportedGroup { .. }
+        );
+        return Err(if all_errors && !unsupported {
+            Error::Verifications(errors)
+        } else {
+            Error::Verification(errors.swap_remove(0))
+        });
+    }
+
+    // Check that the dump is authentic.
+    if require_signature {
+        results.verify_signature().map_err(Error::Verification)?;
+        println!("Dump is signed by the election key.");
+    }
+
+    // Check that the bulletin board has only grown since the earlier dump.
+    if let Some(previous) = previous {
+        let previous_start = Instant::now();
+        let previous: ElectionResults = load(previous, format)?;
+        previous.verify().map_err(Error::Verification)?;
+        if let Some(earlier) = &previous.board {
+            let board =
+                results
+                    .board
+                    .as_ref()
+                    .ok_or(Error::Verification(VerificationError::Board(
+                        BoardError::Diverged { position: 0 },
+                    )))?;
+            board
+                .extends(earlier)
+                .map_err(|e| Error::Verification(e.into()))?;
+        }
+        if timing {
+            print_timings(&[("checking previous dump", previous_start.elapsed())]);
+        }
+    }
 
     // Assemble the friendly results.
     // First, find all the candidates.
@@ -160,6 +387,13 @@ fn verify(path: &str) -> Result<Vec<FriendlyResults>, Error> {
                 .next()
                 .map(|receipt| receipt.crypto.votes.keys().cloned().collect())
         })
+        .or_else(|| {
+            results
+                .redacted // ...or in a redacted one...
+                .values()
+                .next()
+                .map(|receipt| receipt.crypto.votes.keys().cloned().collect())
+        })
         .or_else(|| {
             results
                 .totals // ...or in the tallies.
@@ -181,6 +415,21 @@ fn verify(path: &str) -> Result<Vec<FriendlyResults>, Error> {
             .unwrap()
             .audited_votes += 1;
     }
+    // Redacted ballots' candidates are only vouched for by the signature, so
+    // may not be real candidates.
+    for receipt in results.redacted.values() {
+        if let Some(res) = friendly_results.get_mut(&receipt.state_data.candidate) {
+            res.audited_votes += 1;
+        }
+    }
+    if !results.redacted.is_empty() {
+        let count = results.redacted.len();
+        println!(
+            "{} audited ballot{} redacted, so the candidates revealed cannot all be checked.",
+            count,
+            if count != 1 { "s were" } else { " was" }
+        );
+    }
     // Plug in the tallies if present.
     if let Some(tallies) = &results.totals {
         for totals_desc in tallies.values() {
@@ -188,9 +437,13 @@ fn verify(path: &str) -> Result<Vec<FriendlyResults>, Error> {
             friendly_results
                 .get_mut(&totals_desc.candidate_name)
                 .unwrap()
-                .tally = Some(tally_to_u64(totals_desc.tally));
+                .tally = Some(
+                tally_to_u64(totals_desc.tally)
+                    .expect("Tally was so large that it didn't fit into 64 bits!"),
+            );
         }
-        // If there were no confirmed votes, the tallies will be missing.
+        // Totals are created for every candidate when an election is published,
+        // but dumps of older elections only have them for candidates with votes.
         for res in friendly_results.values_mut() {
             if res.tally.is_none() {
                 res.tally = Some(0);
@@ -209,14 +462,108 @@ fn verify(path: &str) -> Result<Vec<FriendlyResults>, Error> {
     Ok(results_list)
 }
 
+/// Run verification of a receipt bundle, returning the number of receipts it contains.
+fn verify_bundle(path: &str, format: Format) -> Result<usize, Error> {
+    // Load the file.
+    let bundle: ReceiptBundle = load(path, format)?;
+    if let Some(receipt) = bundle.receipts.first() {
+        println!(
+            "Verifying receipt schema version {}.",
+            receipt.schema_version()
+        );
+    }
+
+    // Run verification.
+    bundle.verify().map_err(Error::Verification)?;
+
+    Ok(bundle.receipts.len())
+}
+
+/// Verify two dumps of the same question, then check that the later one only
+/// adds to the earlier one, returning a summary of what was added.
+/// If `all_errors` is set, every verification problem is reported rather than
+/// just the first; inconsistencies between the dumps are always all reported.
+fn verify_diff(
+    old_path: &str,
+    new_path: &str,
+    format: Format,
+    all_errors: bool,
+) -> Result<Vec<String>, Error> {
+    let mut dumps = Vec::with_capacity(2);
+    for path in [old_path, new_path] {
+        let results: ElectionResults = load(path, format)?;
+        let mut errors = results.verify_all();
+        if !errors.is_empty() {
+            return Err(if all_errors {
+                Error::Verifications(errors)
+            } else {
+                Error::Verification(errors.swap_remove(0))
+            });
+        }
+        dumps.push(results);
+    }
+    let (old, new) = (&dumps[0], &dumps[1]);
+
+    let errors = ElectionResults::diff(old, new);
+    if !errors.is_empty() {
+        return Err(Error::Diff(errors));
+    }
+
+    let added = |count: usize, state: &str| {
+        format!(
+            "{} {} ballot{} added since the earlier dump.",
+            count,
+            state,
+            if count != 1 { "s" } else { "" }
+        )
+    };
+    Ok(vec![
+        added(
+            new.audited.len() + new.redacted.len() - old.audited.len() - old.redacted.len(),
+            "audited",
+        ),
+        added(new.confirmed.len() - old.confirmed.len(), "confirmed"),
+    ])
+}
+
 /// Run verification, report the result, and return the exit code.
 fn run(args: &ArgMatches) -> u8 {
     let path: &String = args.get_one(RESULTS_PATH).unwrap(); // Required argument is guaranteed to be present.
-    match verify(path) {
-        Ok(friendly_results) => {
+    let format = match args.get_one::<String>(FORMAT).map(String::as_str) {
+        Some("cbor") => Format::Cbor,
+        _ => Format::Json,
+    };
+    let previous = args.get_one::<String>(PREVIOUS).map(String::as_str);
+    let timing = args.get_flag(TIMING);
+    let all_errors = args.get_flag(ALL_ERRORS);
+    let require_signature = args.get_flag(REQUIRE_SIGNATURE);
+    let diff = args.get_one::<String>(DIFF).map(String::as_str);
+    let result = if let Some(old_path) = diff {
+        verify_diff(old_path, path, format, all_errors)
+    } else if args.get_flag(BUNDLE) {
+        verify_bundle(path, format).map(|count| {
+            vec![format!(
+                "{} receipt{} signed as included in election.",
+                count,
+                if count != 1 { "s" } else { "" }
+            )]
+        })
+    } else {
+        verify(
+            path,
+            format,
+            previous,
+            timing,
+            all_errors,
+            require_signature,
+        )
+        .map(|results| results.iter().map(ToString::to_string).collect())
+    };
+    match result {
+        Ok(lines) => {
             println!("Verification succeeded.");
-            for result in friendly_results {
-                println!("{}", result);
+            for line in lines {
+                println!("{}", line);
             }
             0
         }
@@ -225,68 +572,239 @@ fn run(args: &ArgMatches) -> u8 {
             1
         }
         Err(Error::Format(msg)) => {
-            println!("Invalid JSON: {}", msg);
+            println!("Invalid {}: {}", format, msg);
+            1
+        }
+        Err(Error::Verification(
+            err @ (VerificationError::Receipt(ReceiptError::UnsupportedVersion { .. })
+            | VerificationError::UnsupportedGroup { .. }),
+        )) => {
+            println!("{}", describe(&err));
             1
         }
         Err(Error::Verification(err)) => {
-            let msg = match err {
-                VerificationError::Ballot(err) => match err {
-                    BallotError::Vote(VoteError {
-                        ballot_id,
-                        candidate_id,
-                    }) => {
-                        format!(
-                            "Ballot {} has an invalid vote for candidate {}.",
-                            ballot_id, candidate_id
-                        )
-                    }
-                    BallotError::BallotProof { ballot_id } => {
-                        format!(
-                            "Ballot {} has an invalid proof of well-formedness.",
-                            ballot_id
-                        )
-                    }
-                },
-                VerificationError::Tally { candidate_id } => {
-                    format!("The tally for candidate {} is incorrect.", candidate_id)
-                }
-                VerificationError::WrongCandidates => String::from(
-                    "The candidates listed in the tallies do \
-                    not match those found in the ballots.",
-                ),
-                VerificationError::Receipt(err) => match err {
-                    ReceiptError::Signature { ballot_id } => {
-                        format!(
-                            "The receipt for ballot {} has an invalid signature.",
-                            ballot_id
-                        )
-                    }
-                    ReceiptError::ConfirmationCode { ballot_id } => {
-                        format!(
-                            "The receipt for ballot {} has an invalid confirmation code.",
-                            ballot_id
-                        )
-                    }
-                    ReceiptError::RevealedCandidate {
-                        ballot_id,
-                        claimed_candidate,
-                        true_candidate,
-                    } => {
-                        format!(
-                            "The receipt for ballot {} claims candidate {} but is actually for candidate {}.",
-                            ballot_id,
-                            claimed_candidate,
-                            true_candidate
-                        )
-                    }
-                },
-            };
-            println!("Verification failed: {}", msg);
+            println!("Verification failed: {}", describe(&err));
+            255
+        }
+        Err(Error::Verifications(errs)) => {
+            println!("Verification failed:");
+            for err in &errs {
+                println!("  {}", describe(err));
+            }
+            println!(
+                "{} problem{} found.",
+                errs.len(),
+                if errs.len() != 1 { "s" } else { "" }
+            );
+            255
+        }
+        Err(Error::Diff(errs)) => {
+            println!("The dump is inconsistent with the earlier dump:");
+            for err in &errs {
+                println!("  {}", describe_diff(err));
+            }
+            println!(
+                "{} problem{} found.",
+                errs.len(),
+                if errs.len() != 1 { "s" } else { "" }
+            );
             255
         }
     }
 }
 
+/// Describe an inconsistency between two dumps for the user.
+fn describe_diff(err: &BoardDiffError) -> String {
+    match err {
+        BoardDiffError::DifferentElection => String::from("The dumps are for different elections."),
+        BoardDiffError::RemovedBallot { ballot_id } => {
+            format!("Ballot {} has been removed.", ballot_id)
+        }
+        BoardDiffError::MutatedCrypto { ballot_id } => {
+            format!("The crypto of ballot {} has been changed.", ballot_id)
+        }
+        BoardDiffError::IllegalTransition {
+            ballot_id,
+            from,
+            to,
+        } => {
+            format!("Ballot {} has changed from {} to {}.", ballot_id, from, to)
+        }
+        BoardDiffError::TotalsInconsistent {
+            added_confirmed,
+            tally_increase: Some(tally_increase),
+        } => {
+            format!(
+                "The tallies have increased by {} but {} confirmed ballot{} been added.",
+                tally_increase,
+                added_confirmed,
+                if *added_confirmed != 1 {
+                    "s have"
+                } else {
+                    " has"
+                }
+            )
+        }
+        BoardDiffError::TotalsInconsistent {
+            tally_increase: None,
+            ..
+        } => String::from("The tallies have been removed or reduced."),
+        BoardDiffError::Board(err) => describe(&VerificationError::Board(err.clone())),
+    }
+}
+
+/// Describe something amiss that does not fail verification for the user.
+fn describe_warning(warning: &VerificationWarning) -> String {
+    match warning {
+        VerificationWarning::ConfirmedBallotCount {
+            candidate_id,
+            claimed,
+            actual,
+        } => {
+            format!(
+                "The totals for candidate {} count {} confirmed ballots, but the dump has {}.",
+                candidate_id, claimed, actual
+            )
+        }
+        VerificationWarning::TallySum {
+            tally_sum: Some(tally_sum),
+            confirmed,
+        } => {
+            format!(
+                "The tallies add up to {} votes, but the dump has {} confirmed ballots.",
+                tally_sum, confirmed
+            )
+        }
+        VerificationWarning::TallySum {
+            tally_sum: None,
+            confirmed,
+        } => {
+            format!(
+                "The tallies are too large to add up, but the dump has {} confirmed ballots.",
+                confirmed
+            )
+        }
+    }
+}
+
+/// Describe a verification failure for the user.
+fn describe(err: &VerificationError) -> String {
+    match err {
+        VerificationError::Ballot(err) => match err {
+            BallotError::Vote(VoteError {
+                ballot_id,
+                candidate_id,
+            }) => {
+                format!(
+                    "Ballot {} has an invalid vote for candidate {}.",
+                    ballot_id, candidate_id
+                )
+            }
+            BallotError::BallotProof { ballot_id } => {
+                format!(
+                    "Ballot {} has an invalid proof of well-formedness.",
+                    ballot_id
+                )
+            }
+        },
+        VerificationError::Tally { candidate_id } => {
+            format!("The tally for candidate {} is incorrect.", candidate_id)
+        }
+        VerificationError::WrongCandidates => String::from(
+            "The candidates listed in the tallies do \
+        not match those found in the ballots.",
+        ),
+        VerificationError::BundleSignature => {
+            String::from("The receipt bundle has an invalid signature.")
+        }
+        VerificationError::DumpSignature => String::from("The dump has an invalid signature."),
+        VerificationError::MissingDumpSignature => String::from("The dump is not signed."),
+        VerificationError::UnsupportedGroup { group } => {
+            format!(
+                "Unsupported DRE-ip group {}: this verifier only understands {}.",
+                group, DREIP_GROUP_NAME
+            )
+        }
+        VerificationError::Board(err) => match err {
+            BoardError::Position { position } => {
+                format!(
+                    "The bulletin board has an event missing or inserted at position {}.",
+                    position
+                )
+            }
+            BoardError::Hash { position } => {
+                format!(
+                    "The bulletin board event at position {} has an invalid hash.",
+                    position
+                )
+            }
+            BoardError::Head => {
+                String::from("The bulletin board head does not match the end of the chain.")
+            }
+            BoardError::MissingEvent { ballot_id } => {
+                format!("Ballot {} is missing from the bulletin board.", ballot_id)
+            }
+            BoardError::MissingReceipt { ballot_id } => {
+                format!(
+                    "The bulletin board records ballot {} but its receipt is missing.",
+                    ballot_id
+                )
+            }
+            BoardError::Diverged { position } => {
+                format!(
+                    "The bulletin board has been rewritten since the previous dump, \
+                starting at position {}.",
+                    position
+                )
+            }
+        },
+        VerificationError::Receipt(err) => match err {
+            ReceiptError::Signature { ballot_id } => {
+                format!(
+                    "The receipt for ballot {} has an invalid signature.",
+                    ballot_id
+                )
+            }
+            ReceiptError::ConfirmationCode { ballot_id } => {
+                format!(
+                    "The receipt for ballot {} has an invalid confirmation code.",
+                    ballot_id
+                )
+            }
+            ReceiptError::ConfirmationPhrase { ballot_id } => {
+                format!(
+                    "The receipt for ballot {} has the wrong confirmation phrase.",
+                    ballot_id
+                )
+            }
+            ReceiptError::RevealedCandidate {
+                ballot_id,
+                claimed_candidate,
+                true_candidate,
+            } => {
+                format!(
+                    "The receipt for ballot {} claims candidate {} but is actually for \
+                    candidate {}.",
+                    ballot_id, claimed_candidate, true_candidate
+                )
+            }
+            ReceiptError::UnsupportedVersion { version } => {
+                format!(
+                    "Unsupported receipt schema version {}: this verifier understands versions \
+                    up to {}. Please use a newer verifier.",
+                    version, RECEIPT_SCHEMA_VERSION
+                )
+            }
+            ReceiptError::WrongQuestion { ballot_id } => {
+                format!(
+                    "The receipt for ballot {} is for a different election or question.",
+                    ballot_id
+                )
+            }
+        },
+    }
+}
+
 fn main() {
     let args = cli().get_matches();
     let exit_code = run(&args);
@@ -295,6 +813,20 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
+    use chrono::Utc;
+
+    use dreip_backend::model::{
+        api::{
+            election::ElectionDescription,
+            receipt::{PublicReceipt, Receipt},
+        },
+        common::election::ResultVisibility,
+        db::{
+            ballot::BallotCore,
+            election::{Election, Question},
+        },
+    };
+
     use super::*;
 
     #[test]
@@ -318,7 +850,17 @@ mod tests {
                 audited_votes: 1,
             },
         ];
-        assert_eq!(verify("example_dumps/election.json"), Ok(expected_results));
+        assert_eq!(
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Ok(expected_results)
+        );
 
         let expected_results = vec![
             FriendlyResults {
@@ -333,12 +875,26 @@ mod tests {
             },
         ];
         assert_eq!(
-            verify("example_dumps/election_inprogress.json"),
+            verify(
+                "example_dumps/election_inprogress.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
             Ok(expected_results)
         );
 
         assert_eq!(
-            verify("example_dumps/election_invalid_candidate.json"),
+            verify(
+                "example_dumps/election_invalid_candidate.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
             Err(Error::Verification(VerificationError::Receipt(
                 ReceiptError::RevealedCandidate {
                     ballot_id: 11,
@@ -348,23 +904,589 @@ mod tests {
             )))
         );
         assert_eq!(
-            verify("example_dumps/election_invalid_conf_code.json"),
+            verify(
+                "example_dumps/election_invalid_conf_code.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
             Err(Error::Verification(VerificationError::Receipt(
                 ReceiptError::ConfirmationCode { ballot_id: 11 }
             )))
         );
         assert_eq!(
-            verify("example_dumps/election_invalid_signature.json"),
+            verify(
+                "example_dumps/election_invalid_signature.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
             Err(Error::Verification(VerificationError::Receipt(
                 ReceiptError::Signature { ballot_id: 5 }
             )))
         );
         assert_eq!(
-            verify("example_dumps/election_invalid_totals.json"),
+            verify(
+                "example_dumps/election_invalid_totals.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Tally {
+                candidate_id: "Parry Hotter".into()
+            }))
+        );
+        assert_eq!(
+            verify(
+                "example_dumps/election_future_version.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion { version: 2 }
+            )))
+        );
+        // Points for another group cannot even be parsed, so the group is checked first.
+        assert_eq!(
+            verify(
+                "example_dumps/election_unknown_group.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::UnsupportedGroup {
+                group: "Curve25519".into()
+            }))
+        );
+    }
+
+    #[test]
+    fn all_errors() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        // By default, only the first problem is reported.
+        assert_eq!(
+            verify(
+                "example_dumps/election_invalid_multiple.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
             Err(Error::Verification(VerificationError::Tally {
                 candidate_id: "Parry Hotter".into()
             }))
         );
+        assert_eq!(
+            verify(
+                "example_dumps/election_invalid_multiple.json",
+                Format::Json,
+                None,
+                false,
+                true,
+                false
+            ),
+            Err(Error::Verifications(vec![
+                VerificationError::Tally {
+                    candidate_id: "Parry Hotter".into()
+                },
+                VerificationError::Receipt(ReceiptError::ConfirmationCode { ballot_id: 11 }),
+            ]))
+        );
+
+        // A valid dump is still valid.
+        assert_eq!(
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                None,
+                false,
+                true,
+                false
+            ),
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            )
+        );
+        // An unsupported version is still reported alone.
+        assert_eq!(
+            verify(
+                "example_dumps/election_future_version.json",
+                Format::Json,
+                None,
+                false,
+                true,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion { version: 2 }
+            )))
+        );
+    }
+
+    #[test]
+    fn describe_unsupported() {
+        // These can be listed after other errors, so must be described too.
+        assert_eq!(
+            describe(&VerificationError::Receipt(
+                ReceiptError::UnsupportedVersion { version: 2 }
+            )),
+            format!(
+                "Unsupported receipt schema version 2: this verifier understands versions up to \
+                {RECEIPT_SCHEMA_VERSION}. Please use a newer verifier."
+            )
+        );
+        assert_eq!(
+            describe(&VerificationError::UnsupportedGroup {
+                group: "Curve25519".into()
+            }),
+            format!(
+                "Unsupported DRE-ip group Curve25519: this verifier only understands \
+                {DREIP_GROUP_NAME}."
+            )
+        );
+    }
+
+    #[test]
+    fn board_verification() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        let expected_results = verify(
+            "example_dumps/election.json",
+            Format::Json,
+            None,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            verify(
+                "example_dumps/election_board.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Ok(expected_results)
+        );
+        assert_eq!(
+            verify(
+                "example_dumps/election_board.json",
+                Format::Json,
+                Some("example_dumps/election_board_earlier.json"),
+                false,
+                false,
+                false
+            )
+            .map(|results| results.len()),
+            Ok(2)
+        );
+
+        assert_eq!(
+            verify(
+                "example_dumps/election_board_tampered.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Board(
+                BoardError::Hash { position: 5 }
+            )))
+        );
+
+        // A consistently rewritten chain is only caught against an earlier dump.
+        assert!(verify(
+            "example_dumps/election_board_rewritten.json",
+            Format::Json,
+            None,
+            false,
+            false,
+            false
+        )
+        .is_ok());
+        assert_eq!(
+            verify(
+                "example_dumps/election_board_rewritten.json",
+                Format::Json,
+                Some("example_dumps/election_board_earlier.json"),
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Board(
+                BoardError::Diverged { position: 0 }
+            )))
+        );
+        // As is dropping the chain entirely.
+        assert_eq!(
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                Some("example_dumps/election_board_earlier.json"),
+                false,
+                false,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Board(
+                BoardError::Diverged { position: 0 }
+            )))
+        );
+    }
+
+    /// Write a dump of a new election with no ballots, signed by its key, and
+    /// return its path. If `tamper` is set, the dump is changed after signing.
+    fn write_signed_dump(tamper: bool) -> String {
+        let now = Utc::now();
+        let election = Election::new(
+            1,
+            "Signed".to_string(),
+            now,
+            now,
+            HashMap::new(),
+            HashMap::new(),
+            rand::thread_rng(),
+        );
+        let mut results = ElectionResults {
+            schema_version: RECEIPT_SCHEMA_VERSION,
+            election: ElectionDescription::from(election.clone()).crypto,
+            audited: HashMap::new(),
+            redacted: HashMap::new(),
+            confirmed: HashMap::new(),
+            totals: None,
+            skipped: 0,
+            board: None,
+            last_event_seq: 0,
+            dump_signature: None,
+        };
+        results.sign(&election);
+        if tamper {
+            results.last_event_seq += 1;
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "{}-signed-{}-{}.json",
+            PROGRAM_NAME,
+            if tamper { "tampered" } else { "valid" },
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_vec(&results).unwrap()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn signature_verification() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        let valid = write_signed_dump(false);
+        assert_eq!(
+            verify(&valid, Format::Json, None, false, false, true),
+            Ok(vec![])
+        );
+
+        // Unsigned dumps only verify if no signature is required.
+        assert!(verify(
+            "example_dumps/election.json",
+            Format::Json,
+            None,
+            false,
+            false,
+            false
+        )
+        .is_ok());
+        assert_eq!(
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                true
+            ),
+            Err(Error::Verification(VerificationError::MissingDumpSignature))
+        );
+
+        // Changes made after signing are detected, but only if the signature is checked.
+        let tampered = write_signed_dump(true);
+        assert_eq!(
+            verify(&tampered, Format::Json, None, false, false, false),
+            Ok(vec![])
+        );
+        assert_eq!(
+            verify(&tampered, Format::Json, None, false, false, true),
+            Err(Error::Verification(VerificationError::DumpSignature))
+        );
+
+        let command_line = [PROGRAM_NAME, "--require-signature", valid.as_str()];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
+        let command_line = [PROGRAM_NAME, "--require-signature", tampered.as_str()];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--require-signature",
+            "example_dumps/election.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
+
+        std::fs::remove_file(valid).unwrap();
+        std::fs::remove_file(tampered).unwrap();
+    }
+
+    /// A new election with a single question, whose candidates are "Yes" and "No".
+    fn yes_no_election(name: &str) -> Election {
+        let now = Utc::now();
+        let question = Question {
+            id: 1,
+            slug: String::new(),
+            description: format!("{name}?"),
+            description_translations: HashMap::new(),
+            constraints: HashMap::new(),
+            candidates: vec!["Yes".to_string(), "No".to_string()],
+            candidate_translations: HashMap::new(),
+            end_time: None,
+            allow_write_in: false,
+            result_visibility: ResultVisibility::Full,
+            quorum: None,
+            last_dump_at: None,
+            last_dump_signature: None,
+        };
+        Election::new(
+            1,
+            name.to_string(),
+            now,
+            now,
+            HashMap::new(),
+            HashMap::from([(1, question)]),
+            rand::thread_rng(),
+        )
+    }
+
+    /// Write a dump of a new election with an audited and a redacted ballot,
+    /// both for the same candidate, and return its path. If `tamper` is set,
+    /// the redacted ballot's candidate is changed after signing.
+    fn write_redacted_dump(tamper: bool) -> String {
+        let election = yes_no_election("Redacted");
+        let candidates = &election.questions[&1].candidates;
+        let audit = |ballot_id| {
+            BallotCore::new(
+                ballot_id,
+                1,
+                candidates[0].clone(),
+                [candidates[1].clone()],
+                &election,
+                rand::thread_rng(),
+            )
+            .unwrap()
+            .audit()
+        };
+        let mut redacted = Receipt::from_ballot(audit(2).redact(), &election);
+        if tamper {
+            redacted.state_data.candidate = candidates[1].clone();
+        }
+        let results = ElectionResults {
+            schema_version: RECEIPT_SCHEMA_VERSION,
+            election: ElectionDescription::from(election.clone()).crypto,
+            audited: HashMap::from([(1, Receipt::from_ballot(audit(1), &election))]),
+            redacted: HashMap::from([(2, redacted)]),
+            confirmed: HashMap::new(),
+            totals: None,
+            skipped: 0,
+            board: None,
+            last_event_seq: 0,
+            dump_signature: None,
+        };
+
+        let path = std::env::temp_dir().join(format!(
+            "{}-redacted-{}-{}.json",
+            PROGRAM_NAME,
+            if tamper { "tampered" } else { "valid" },
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_vec(&results).unwrap()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Write a bundle of an audited and a confirmed ballot of a new election,
+    /// and return its path. If `tamper` is set, the confirmed ballot's
+    /// confirmation code is changed after signing.
+    fn write_bundle(tamper: bool) -> String {
+        let election = yes_no_election("Bundled");
+        let candidates = &election.questions[&1].candidates;
+        let ballot = |ballot_id| {
+            BallotCore::new(
+                ballot_id,
+                1,
+                candidates[0].clone(),
+                [candidates[1].clone()],
+                &election,
+                rand::thread_rng(),
+            )
+            .unwrap()
+        };
+        let audited = Receipt::from_ballot(ballot(1).audit(), &election);
+        let mut confirmed = Receipt::from_ballot(ballot(2).confirm(None), &election);
+        if tamper {
+            confirmed.confirmation_code = audited.confirmation_code.clone();
+        }
+        let bundle = ReceiptBundle::new(
+            &election,
+            vec![
+                PublicReceipt::Audited(audited),
+                PublicReceipt::Confirmed(confirmed),
+            ],
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "{}-bundle-{}-{}.json",
+            PROGRAM_NAME,
+            if tamper { "tampered" } else { "valid" },
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_vec(&bundle).unwrap()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn bundle_verification() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        // A bundle straight from the server verifies.
+        let valid = write_bundle(false);
+        assert_eq!(verify_bundle(&valid, Format::Json), Ok(2));
+        let command_line = [PROGRAM_NAME, "--bundle", &valid];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
+        // But not once a receipt in it has been changed.
+        let tampered = write_bundle(true);
+        assert_eq!(
+            verify_bundle(&tampered, Format::Json),
+            Err(Error::Verification(VerificationError::Receipt(
+                ReceiptError::ConfirmationCode { ballot_id: 2 }
+            )))
+        );
+        let command_line = [PROGRAM_NAME, "--bundle", &tampered];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
+
+        std::fs::remove_file(valid).unwrap();
+        std::fs::remove_file(tampered).unwrap();
+    }
+
+    #[test]
+    fn redacted_ballots() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        // Redacted ballots still verify, and still count as audited.
+        let valid = write_redacted_dump(false);
+        let results = verify(&valid, Format::Json, None, false, false, false).unwrap();
+        let yes = results
+            .iter()
+            .find(|result| result.candidate_name == "Yes")
+            .unwrap();
+        assert_eq!(yes.audited_votes, 2);
+
+        // But their candidate is still covered by the signature.
+        let tampered = write_redacted_dump(true);
+        assert_eq!(
+            verify(&tampered, Format::Json, None, false, false, false).map(|_| ()),
+            Err(Error::Verification(VerificationError::Receipt(
+                ReceiptError::Signature { ballot_id: 2 }
+            )))
+        );
+
+        std::fs::remove_file(valid).unwrap();
+        std::fs::remove_file(tampered).unwrap();
+    }
+
+    #[test]
+    fn withheld_totals() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        // An ended question whose totals are withheld, showing only its winner,
+        // verifies just like an election in progress.
+        let mut results: ElectionResults =
+            load("example_dumps/election.json", Format::Json).unwrap();
+        assert!(results.totals.take().is_some());
+        let path = std::env::temp_dir().join(format!(
+            "{}-withheld-{}.json",
+            PROGRAM_NAME,
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_vec(&results).unwrap()).unwrap();
+        let path = path.to_str().unwrap();
+
+        let expected_results = vec![
+            FriendlyResults {
+                candidate_name: "Chris Riches".to_string(),
+                tally: None,
+                audited_votes: 1,
+            },
+            FriendlyResults {
+                candidate_name: "Parry Hotter".to_string(),
+                tally: None,
+                audited_votes: 1,
+            },
+        ];
+        assert_eq!(
+            expected_results[1].to_string(),
+            "Parry Hotter: tally not published (1 audited ballot)"
+        );
+        assert_eq!(
+            verify(path, Format::Json, None, false, false, false),
+            Ok(expected_results)
+        );
+
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
@@ -373,10 +1495,52 @@ mod tests {
         let args = cli().try_get_matches_from(command_line).unwrap();
         assert_eq!(run(&args), 0);
 
+        let command_line = [
+            PROGRAM_NAME,
+            "--timing",
+            "example_dumps/election_board.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
         let command_line = [PROGRAM_NAME, "example_dumps/election_invalid_totals.json"];
         let args = cli().try_get_matches_from(command_line).unwrap();
         assert_eq!(run(&args), 255);
 
+        let command_line = [
+            PROGRAM_NAME,
+            "--all-errors",
+            "example_dumps/election_invalid_multiple.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--all-errors",
+            "example_dumps/election_future_version.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
+        // Unknown schema versions are refused rather than failing verification.
+        let command_line = [PROGRAM_NAME, "example_dumps/election_future_version.json"];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
+        // As are unknown groups.
+        let command_line = [PROGRAM_NAME, "example_dumps/election_unknown_group.json"];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "example_dumps/election_unknown_group.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
         let command_line = [PROGRAM_NAME, "example_dumps/election_malformed.json"];
         let args = cli().try_get_matches_from(command_line).unwrap();
         assert_eq!(run(&args), 1);
@@ -384,6 +1548,108 @@ mod tests {
         let command_line = [PROGRAM_NAME, "not a real file"];
         let args = cli().try_get_matches_from(command_line).unwrap();
         assert_eq!(run(&args), 1);
+
+        // A dump is not a valid bundle.
+        let command_line = [PROGRAM_NAME, "--bundle", "example_dumps/election.json"];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
+        let command_line = [PROGRAM_NAME, "--bundle", "not a real file"];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--previous",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--previous",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board_rewritten.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
+
+        let command_line = [
+            PROGRAM_NAME,
+            "--previous",
+            "not a real file",
+            "example_dumps/election_board.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 1);
+    }
+
+    #[test]
+    fn diff() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        assert_eq!(
+            verify_diff(
+                "example_dumps/election_board_earlier.json",
+                "example_dumps/election_board.json",
+                Format::Json,
+                false
+            ),
+            Ok(vec![
+                "2 audited ballots added since the earlier dump.".to_string(),
+                "3 confirmed ballots added since the earlier dump.".to_string(),
+            ])
+        );
+        let command_line = [
+            PROGRAM_NAME,
+            "--diff",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
+        // Either dump failing verification fails the diff.
+        assert_eq!(
+            verify_diff(
+                "example_dumps/election_board_earlier.json",
+                "example_dumps/election_board_tampered.json",
+                Format::Json,
+                false
+            ),
+            Err(Error::Verification(VerificationError::Board(
+                BoardError::Hash { position: 5 }
+            )))
+        );
+
+        // Going back to an earlier dump removes ballots, even though it
+        // verifies on its own.
+        match verify_diff(
+            "example_dumps/election_board.json",
+            "example_dumps/election_board_earlier.json",
+            Format::Json,
+            false,
+        ) {
+            Err(Error::Diff(errs)) => {
+                assert!(errs.contains(&BoardDiffError::RemovedBallot { ballot_id: 4 }));
+                assert!(errs.contains(&BoardDiffError::RemovedBallot { ballot_id: 10 }));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let command_line = [
+            PROGRAM_NAME,
+            "--diff",
+            "example_dumps/election_board.json",
+            "example_dumps/election_board_earlier.json",
+        ];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 255);
     }
 
     #[test]
@@ -395,5 +1661,118 @@ mod tests {
         // No options at all.
         let command_line = [PROGRAM_NAME];
         cli().try_get_matches_from(command_line).unwrap_err();
+
+        // A bundle has no bulletin board to compare.
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "--previous",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+
+        // A bundle cannot be compared with an earlier dump either.
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "--diff",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+
+        // Diffing already checks the bulletin board against the earlier dump.
+        let command_line = [
+            PROGRAM_NAME,
+            "--diff",
+            "example_dumps/election_board_earlier.json",
+            "--previous",
+            "example_dumps/election_board_earlier.json",
+            "example_dumps/election_board.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+
+        // Bundle verification is not timed.
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "--timing",
+            "example_dumps/election.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+
+        // Bundle verification stops at the first problem.
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "--all-errors",
+            "example_dumps/election.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+
+        // Bundles are always signed.
+        let command_line = [
+            PROGRAM_NAME,
+            "--bundle",
+            "--require-signature",
+            "example_dumps/election.json",
+        ];
+        cli().try_get_matches_from(command_line).unwrap_err();
+    }
+
+    #[test]
+    fn cbor_input() {
+        log4rs_test_utils::test_logging::init_logging_once_for(
+            ["dre_ip", "dreip_backend"],
+            None,
+            None,
+        );
+
+        // Re-encode the example dump as CBOR.
+        let json = std::fs::read("example_dumps/election.json").unwrap();
+        let results: ElectionResults = serde_json::from_slice(&json).unwrap();
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&results, &mut cbor).unwrap();
+        assert!(cbor.len() < json.len());
+        let path =
+            std::env::temp_dir().join(format!("{}-cbor-{}.cbor", PROGRAM_NAME, std::process::id()));
+        std::fs::write(&path, cbor).unwrap();
+        let path = path.to_str().unwrap();
+
+        // It verifies exactly as the JSON does.
+        assert_eq!(
+            verify(path, Format::Cbor, None, false, false, false),
+            verify(
+                "example_dumps/election.json",
+                Format::Json,
+                None,
+                false,
+                false,
+                false
+            )
+        );
+        let command_line = [PROGRAM_NAME, "--format", "cbor", path];
+        let args = cli().try_get_matches_from(command_line).unwrap();
+        assert_eq!(run(&args), 0);
+
+        // The format must match.
+        assert!(matches!(
+            verify(path, Format::Json, None, false, false, false),
+            Err(Error::Format(_))
+        ));
+        assert!(matches!(
+            verify(
+                "example_dumps/election.json",
+                Format::Cbor,
+                None,
+                false,
+                false,
+                false
+            ),
+            Err(Error::Format(_))
+        ));
+        let command_line = [PROGRAM_NAME, "--format", "xml", path];
+        cli().try_get_matches_from(command_line).unwrap_err();
     }
 }
@@ -0,0 +1,38 @@
+//! Runs the server binary in `--check` mode, which must exit without serving.
+
+use std::process::Command;
+
+fn server() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_dreip-backend"))
+}
+
+#[test]
+fn check_fails_with_exit_code_1() {
+    let output = server()
+        .args(["--check", "--aws-dry-run"])
+        .env("ROCKET_DB_URI", "not a database")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(
+        lines[0].starts_with("CHECK       RESULT  DETAIL"),
+        "{stdout}"
+    );
+    assert!(lines[1].starts_with("config      PASS    "), "{stdout}");
+    assert!(lines[2].starts_with("database    FAIL    "), "{stdout}");
+}
+
+#[test]
+fn unrecognised_argument_exits_with_code_2() {
+    let output = server().arg("--chek").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("Unrecognised argument: --chek"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,105 @@
+//! An in-browser verifier for DRE-ip election results.
+//!
+//! This uses the backend's own verification code, built with only the
+//! `verification-core` feature, so voters can check a dump without trusting
+//! the server or installing the verification tool.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use dreip_backend::model::api::election::ElectionResults;
+
+/// The outcome of verifying a dump.
+#[derive(Debug, Serialize)]
+pub struct VerificationReport {
+    /// Did everything verify?
+    pub valid: bool,
+    /// What failed to verify, if anything.
+    pub errors: Vec<String>,
+    /// Anything amiss that does not make the dump invalid.
+    pub warnings: Vec<String>,
+}
+
+impl VerificationReport {
+    /// Verify the given dump, in the JSON format served by the backend.
+    pub fn for_json(json: &str) -> Self {
+        let results: ElectionResults = match serde_json::from_str(json) {
+            Ok(results) => results,
+            Err(e) => {
+                return Self {
+                    valid: false,
+                    errors: vec![format!("Invalid dump: {}", e)],
+                    warnings: Vec::new(),
+                }
+            }
+        };
+        let errors = results
+            .verify_all()
+            .iter()
+            .map(|err| format!("{:?}", err))
+            .collect::<Vec<_>>();
+        let warnings = results
+            .warnings()
+            .iter()
+            .map(|warning| format!("{:?}", warning))
+            .collect();
+        Self {
+            valid: errors.is_empty(),
+            errors,
+            warnings,
+        }
+    }
+}
+
+/// Verify a dump given as JSON, returning a `{ valid, errors, warnings }` object.
+#[wasm_bindgen]
+pub fn verify_results(json: &str) -> JsValue {
+    // Serialising a plain struct of strings and a bool cannot fail.
+    serde_wasm_bindgen::to_value(&VerificationReport::for_json(json)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(name: &str) -> VerificationReport {
+        let json = std::fs::read_to_string(format!("../example_dumps/{name}")).unwrap();
+        VerificationReport::for_json(&json)
+    }
+
+    #[test]
+    fn valid_dump() {
+        let report = report("election.json");
+        assert!(report.valid, "{:?}", report.errors);
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn dump_with_warnings() {
+        // This dump predates confirmed ballot counts, so its totals count
+        // none, which is valid but suspicious.
+        let report = report("election.json");
+        assert!(report.valid, "{:?}", report.errors);
+        assert!(report
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with("ConfirmedBallotCount")));
+    }
+
+    #[test]
+    fn invalid_dump() {
+        let report = report("election_invalid_conf_code.json");
+        assert!(!report.valid);
+        assert!(report
+            .errors
+            .iter()
+            .any(|err| err.contains("ConfirmationCode { ballot_id: 11 }")));
+    }
+
+    #[test]
+    fn malformed_dump() {
+        let report = VerificationReport::for_json("{\"not\": \"a dump\"}");
+        assert!(!report.valid);
+        assert!(report.errors[0].starts_with("Invalid dump"));
+    }
+}